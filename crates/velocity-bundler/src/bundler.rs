@@ -1,12 +1,31 @@
 use anyhow::Result;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::fs;
+use velocity_compiler::{analyzer, imports, parser};
+use crate::source_map::MappedBundle;
+use crate::resolver::{self, Resolver};
+use crate::side_effects;
+
+/// Entry used when [`BundlerConfig::entries`] is empty, and its bundle
+const DEFAULT_ENTRY: &str = "src/index.tsx";
+const DEFAULT_BUNDLE: &str = "bundle.js";
+
+/// Chunk holding modules used by more than one entry
+const SHARED_CHUNK: &str = "shared.js";
 
 #[derive(Debug, Clone)]
 pub struct BundlerConfig {
     pub root_dir: PathBuf,
     pub out_dir: PathBuf,
     pub minify: bool,
+    /// Entry points relative to `root_dir`, each bundled to `<name>.js`
+    /// (`src/admin.tsx` → `admin.js`). Empty bundles `src/index.tsx` to
+    /// `bundle.js`.
+    pub entries: Vec<PathBuf>,
+    /// Write `<bundle>.map` next to each bundle, combining the source maps
+    /// of the modules in it
+    pub source_maps: bool,
 }
 
 pub struct Bundler {
@@ -22,21 +41,60 @@ impl Bundler {
         // Create output directory
         fs::create_dir_all(&self.config.out_dir)?;
 
-        // Find entry point
-        let entry = self.config.root_dir.join("src/index.tsx");
-        if !entry.exists() {
-            anyhow::bail!("Entry point not found: src/index.tsx");
+        let entries: Vec<(PathBuf, String)> = if self.config.entries.is_empty() {
+            vec![(PathBuf::from(DEFAULT_ENTRY), DEFAULT_BUNDLE.to_string())]
+        } else {
+            self.config.entries.iter().map(|entry| (entry.clone(), bundle_name(entry))).collect()
+        };
+
+        // Collect each entry's modules, checking that bundle names don't clash
+        let resolver = Resolver::for_project(&self.config.root_dir)?;
+        let mut bundles = Vec::new();
+        let mut names = HashSet::new();
+        for (entry, name) in entries {
+            let path = self.config.root_dir.join(&entry);
+            if !path.exists() {
+                anyhow::bail!("Entry point not found: {}", entry.display());
+            }
+            check_entry_shape(&path, &entry)?;
+
+            if !names.insert(name.clone()) {
+                anyhow::bail!("Entry points bundle to the same file {}: rename one of them", name);
+            }
+            bundles.push((name, self.collect_modules(&resolver, &path)?));
         }
 
-        // Process modules
-        let modules = self.collect_modules(&entry)?;
+        // Modules used by several entries go into one shared chunk instead
+        // of being copied into each bundle
+        let mut users: HashMap<PathBuf, usize> = HashMap::new();
+        for (_, modules) in &bundles {
+            for module in modules {
+                *users.entry(module.path.clone()).or_default() += 1;
+            }
+        }
+        let mut shared = Vec::new();
+        for (_, modules) in &mut bundles {
+            for module in std::mem::take(modules) {
+                if users[&module.path] < 2 {
+                    modules.push(module);
+                } else if !shared.iter().any(|m: &crate::Module| m.path == module.path) {
+                    shared.push(module);
+                }
+            }
+        }
 
-        // Bundle
-        let bundle = self.bundle_modules(&modules)?;
+        if !shared.is_empty() {
+            self.write_bundle(SHARED_CHUNK, MappedBundle::new(), &shared)?;
+        }
 
-        // Write output
-        let output_path = self.config.out_dir.join("bundle.js");
-        fs::write(output_path, bundle)?;
+        // Write one bundle per entry
+        for (name, modules) in &bundles {
+            let mut bundle = MappedBundle::new();
+            if !shared.is_empty() {
+                bundle.push_code(&format!("import './{}';\n", SHARED_CHUNK));
+            }
+            self.write_bundle(name, bundle, modules)?;
+        }
 
         // Copy index.html if exists
         let html_path = self.config.root_dir.join("index.html");
@@ -47,7 +105,7 @@ impl Bundler {
         Ok(())
     }
 
-    fn collect_modules(&self, entry: &PathBuf) -> Result<Vec<crate::Module>> {
+    fn collect_modules(&self, resolver: &Resolver, entry: &PathBuf) -> Result<Vec<crate::Module>> {
         let mut modules = Vec::new();
         let content = fs::read_to_string(entry)?;
 
@@ -55,28 +113,87 @@ impl Bundler {
         // JSX transformation will be handled by the runtime bundler (Vite, etc.)
         let transformed = content.clone();
 
+        // Resolve file imports up front so a missing file fails the build
+        // with the paths that were tried
+        let mut dependencies = Vec::new();
+        for import in imports::find_imports(&content, &entry.to_string_lossy())? {
+            if let Some(path) = resolver.resolve(entry, &import.specifier)? {
+                dependencies.push(path.to_string_lossy().into_owned());
+            }
+        }
+
         modules.push(crate::Module {
             path: entry.clone(),
+            has_side_effects: side_effects::has_side_effects(entry, &content),
             content: content.clone(),
             transformed,
-            dependencies: Vec::new(),
+            source_map: None,
+            dependencies,
         });
 
         Ok(modules)
     }
 
-    fn bundle_modules(&self, modules: &[crate::Module]) -> Result<String> {
-        let mut bundle = String::new();
-
+    /// Append `modules` to `bundle` and write it to `name` in the output
+    /// directory, with its source map if enabled
+    fn write_bundle(&self, name: &str, mut bundle: MappedBundle, modules: &[crate::Module]) -> Result<()> {
         // Add runtime imports
-        bundle.push_str("import { createSignal, createEffect, render } from 'velocity-runtime';\n\n");
+        bundle.push_code("import { createSignal, createEffect, render } from 'velocity-runtime';\n\n");
 
         // Add all modules
         for module in modules {
-            bundle.push_str(&module.transformed);
-            bundle.push_str("\n\n");
+            let source = relative_url(&self.config.out_dir, &module.path);
+            bundle.push_module(&module.transformed, &source, &module.content, module.source_map.as_deref())?;
+            bundle.push_code("\n\n");
         }
 
-        Ok(bundle)
+        let path = self.config.out_dir.join(name);
+        if !self.config.source_maps {
+            fs::write(path, bundle.code())?;
+            return Ok(());
+        }
+
+        let map_name = format!("{}.map", name);
+        let (mut code, map) = bundle.finish(name);
+        code.push_str(&format!("//# sourceMappingURL={}\n", map_name));
+        fs::write(path, code)?;
+        fs::write(self.config.out_dir.join(map_name), map)?;
+        Ok(())
+    }
+}
+
+/// Output file name of an entry's bundle: its file stem with `.js`
+fn bundle_name(entry: &Path) -> String {
+    let stem = entry.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    format!("{}.js", stem)
+}
+
+/// `path` relative to the directory `from`, with `/` separators, as a
+/// source map in `from` refers to it
+fn relative_url(from: &Path, path: &Path) -> String {
+    let (from, path) = (resolver::normalize(from), resolver::normalize(path));
+    let common = from.components().zip(path.components()).take_while(|(a, b)| a == b).count();
+    let mut segments: Vec<String> = from.components().skip(common).map(|_| "..".to_string()).collect();
+    segments.extend(path.components().skip(common).map(|c| c.as_os_str().to_string_lossy().into_owned()));
+    segments.join("/")
+}
+
+/// Fail when an entry neither mounts anything nor exports a component, which
+/// would otherwise build into a bundle that leaves the page blank
+fn check_entry_shape(path: &Path, entry: &Path) -> Result<()> {
+    let source = fs::read_to_string(path)?;
+    let module = parser::parse(&source, &path.to_string_lossy())
+        .map_err(|e| anyhow::anyhow!("Failed to parse entry point {}: {}", entry.display(), e))?;
+
+    if !analyzer::inspect_entry(&module).starts_app() {
+        anyhow::bail!(
+            "Entry point {} doesn't start the app: it neither calls render(...) nor has a default export.\n\
+             Mount the root component at the top level:\n\n    \
+             render(App, document.getElementById('root'));\n\n\
+             or export it for the dev server to mount:\n\n    \
+             export default function App() {{ ... }}",
+            entry.display()
+        );
     }
+    Ok(())
 }
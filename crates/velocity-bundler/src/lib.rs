@@ -1,6 +1,10 @@
 mod dev_server;
 mod bundler;
 mod module_graph;
+pub mod resolver;
+pub mod side_effects;
+pub mod source_map;
+pub mod tsconfig;
 
 pub use dev_server::DevServer;
 pub use bundler::{Bundler, BundlerConfig};
@@ -12,5 +16,10 @@ pub struct Module {
     pub path: PathBuf,
     pub content: String,
     pub transformed: String,
+    /// Source map of `transformed`, when it isn't `content` as written
+    pub source_map: Option<String>,
     pub dependencies: Vec<String>,
+    /// Whether loading the module does more than define its exports, so it
+    /// must stay in the bundle even when no export is used
+    pub has_side_effects: bool,
 }
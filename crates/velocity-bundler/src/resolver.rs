@@ -0,0 +1,247 @@
+//! Import resolution
+//!
+//! Maps a relative import specifier to the source file it names, the way
+//! TypeScript's `bundler` resolution and Node do for extensionless imports:
+//!
+//! 1. the path as written, if it's a file (`./Foo.tsx`)
+//! 2. the path with each source extension appended (`./Foo` → `./Foo.tsx`)
+//! 3. the directory's `index` file with each extension (`./Foo/index.tsx`)
+//!
+//! A non-relative specifier goes through the project's `tsconfig.json`
+//! first: the `paths` pattern it matches, then `baseUrl` (see
+//! [`crate::tsconfig`]). Each location is tried the same way. Anything
+//! left (`velocity-runtime`) is a package and isn't resolved here.
+
+use crate::tsconfig::{TsConfig, TsConfigError, TSCONFIG_FILE};
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+/// Extensions tried for extensionless imports, in order
+pub const SOURCE_EXTENSIONS: &[&str] = &["tsx", "ts", "jsx", "js"];
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Cannot resolve '{specifier}' imported from {}; tried:\n{}", importer.display(), format_tried(tried))]
+pub struct ResolveError {
+    pub specifier: String,
+    pub importer: PathBuf,
+    /// Every candidate path checked, in order
+    pub tried: Vec<PathBuf>,
+}
+
+fn format_tried(tried: &[PathBuf]) -> String {
+    tried
+        .iter()
+        .map(|path| format!("  {}", path.display()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Whether `specifier` points at a file rather than a package
+pub fn is_relative(specifier: &str) -> bool {
+    specifier.starts_with("./") || specifier.starts_with("../")
+}
+
+/// Resolves imports for one project, reading its `tsconfig.json` once
+#[derive(Debug, Clone, Default)]
+pub struct Resolver {
+    tsconfig: Option<TsConfig>,
+}
+
+impl Resolver {
+    pub fn new(tsconfig: Option<TsConfig>) -> Self {
+        Self { tsconfig }
+    }
+
+    /// Resolver for the project in `root`, using its `tsconfig.json` if
+    /// there is one
+    pub fn for_project(root: &Path) -> Result<Self, TsConfigError> {
+        let path = root.join(TSCONFIG_FILE);
+        let tsconfig = if path.is_file() { Some(TsConfig::load(&path)?) } else { None };
+        Ok(Self::new(tsconfig))
+    }
+
+    /// Resolve `specifier`, imported from the file `importer`. Returns
+    /// `None` for package imports.
+    pub fn resolve(&self, importer: &Path, specifier: &str) -> Result<Option<PathBuf>, ResolveError> {
+        resolve_with(importer, specifier, self.tsconfig.as_ref(), |path| path.is_file())
+    }
+}
+
+/// Resolve `specifier`, imported from the file `importer`, without
+/// `tsconfig.json` aliases. Returns `None` for package imports.
+pub fn resolve(importer: &Path, specifier: &str) -> Result<Option<PathBuf>, ResolveError> {
+    resolve_with(importer, specifier, None, |path| path.is_file())
+}
+
+/// [`resolve`] with the config and file-existence check supplied by the
+/// caller
+fn resolve_with(
+    importer: &Path,
+    specifier: &str,
+    tsconfig: Option<&TsConfig>,
+    is_file: impl Fn(&Path) -> bool,
+) -> Result<Option<PathBuf>, ResolveError> {
+    let mut tried = Vec::new();
+
+    if is_relative(specifier) {
+        let base = normalize(&importer.parent().unwrap_or(Path::new("")).join(specifier));
+        if let Some(path) = first_file(&base, &is_file, &mut tried) {
+            return Ok(Some(path));
+        }
+        return Err(ResolveError {
+            specifier: specifier.to_string(),
+            importer: importer.to_path_buf(),
+            tried,
+        });
+    }
+
+    // Like `tsc`, fall back to a package when no alias finds a file, so a
+    // catch-all `*` pattern doesn't shadow `node_modules`
+    let Some(tsconfig) = tsconfig else {
+        return Ok(None);
+    };
+    let mut bases = tsconfig.map_path(specifier).unwrap_or_default();
+    bases.extend(tsconfig.base_url.as_ref().map(|base_url| normalize(&base_url.join(specifier))));
+    Ok(bases.iter().find_map(|base| first_file(base, &is_file, &mut tried)))
+}
+
+/// The first file among `base`, `base` with each source extension and its
+/// `index` files, recording the paths that aren't files in `tried`
+fn first_file(base: &Path, is_file: impl Fn(&Path) -> bool, tried: &mut Vec<PathBuf>) -> Option<PathBuf> {
+    let mut candidates = vec![base.to_path_buf()];
+    candidates.extend(SOURCE_EXTENSIONS.iter().map(|ext| with_appended_extension(base, ext)));
+    candidates.extend(SOURCE_EXTENSIONS.iter().map(|ext| base.join(format!("index.{}", ext))));
+
+    for candidate in candidates {
+        if is_file(&candidate) {
+            return Some(candidate);
+        }
+        tried.push(candidate);
+    }
+    None
+}
+
+/// Remove `.` and resolve `..` components without touching the file system,
+/// so one file reached through different relative paths gets one path
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// `path` with `.ext` added, keeping any dots already in the file name
+/// (`Foo.styles` → `Foo.styles.ts`, not `Foo.ts`)
+fn with_appended_extension(path: &Path, ext: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(ext);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn resolve_in(files: &[&str], importer: &str, specifier: &str) -> Result<Option<PathBuf>, ResolveError> {
+        let files: HashSet<PathBuf> = files.iter().map(PathBuf::from).collect();
+        resolve_with(Path::new(importer), specifier, None, |path| files.contains(path))
+    }
+
+    fn resolve_aliased(files: &[&str], tsconfig: &str, specifier: &str) -> Option<PathBuf> {
+        let files: HashSet<PathBuf> = files.iter().map(PathBuf::from).collect();
+        let tsconfig = TsConfig::parse(tsconfig, Path::new("app")).unwrap();
+        resolve_with(Path::new("app/src/pages/Home.tsx"), specifier, Some(&tsconfig), |path| files.contains(path))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_exact_file() {
+        let resolved = resolve_in(&["src/Foo.tsx", "src/Foo.tsx.ts"], "src/index.tsx", "./Foo.tsx");
+        assert_eq!(resolved, Ok(Some(PathBuf::from("src/Foo.tsx"))));
+    }
+
+    #[test]
+    fn test_resolve_extension_order() {
+        let files = ["src/Foo.ts", "src/Foo.tsx", "src/Foo.js"];
+        assert_eq!(resolve_in(&files, "src/index.tsx", "./Foo"), Ok(Some(PathBuf::from("src/Foo.tsx"))));
+        assert_eq!(resolve_in(&files[..1], "src/index.tsx", "./Foo"), Ok(Some(PathBuf::from("src/Foo.ts"))));
+        assert_eq!(
+            resolve_in(&["src/legacy/Widget.jsx"], "src/index.tsx", "./legacy/Widget"),
+            Ok(Some(PathBuf::from("src/legacy/Widget.jsx")))
+        );
+        assert_eq!(
+            resolve_in(&["src/Foo.styles.ts"], "src/index.tsx", "./Foo.styles"),
+            Ok(Some(PathBuf::from("src/Foo.styles.ts")))
+        );
+    }
+
+    #[test]
+    fn test_resolve_directory_index() {
+        let files = ["src/components/Button/index.tsx", "src/utils/index.js"];
+        assert_eq!(
+            resolve_in(&files, "src/App.tsx", "./components/Button"),
+            Ok(Some(PathBuf::from("src/components/Button/index.tsx")))
+        );
+        assert_eq!(
+            resolve_in(&files, "src/components/Button/index.tsx", "../../utils"),
+            Ok(Some(PathBuf::from("src/utils/index.js")))
+        );
+        // A file beats a directory of the same name
+        assert_eq!(
+            resolve_in(&["src/Button.tsx", "src/Button/index.tsx"], "src/App.tsx", "./Button"),
+            Ok(Some(PathBuf::from("src/Button.tsx")))
+        );
+    }
+
+    #[test]
+    fn test_resolve_package_and_missing() {
+        assert_eq!(resolve_in(&[], "src/index.tsx", "velocity-runtime"), Ok(None));
+
+        let error = resolve_in(&[], "src/index.tsx", "./Missing").unwrap_err();
+        assert_eq!(error.tried.len(), 9);
+        assert_eq!(error.tried[0], PathBuf::from("src/Missing"));
+        assert_eq!(error.tried[1], PathBuf::from("src/Missing.tsx"));
+        assert_eq!(error.tried[8], PathBuf::from("src/Missing/index.js"));
+        assert!(error
+            .to_string()
+            .starts_with("Cannot resolve './Missing' imported from src/index.tsx; tried:\n  src/Missing\n"));
+    }
+
+    #[test]
+    fn test_resolve_tsconfig_alias() {
+        let tsconfig = r#"{ "compilerOptions": { "baseUrl": ".", "paths": { "@/*": ["src/*"] } } }"#;
+        let files = ["app/src/components/Button.tsx", "app/src/utils/index.ts", "app/lib/format.ts"];
+
+        assert_eq!(
+            resolve_aliased(&files, tsconfig, "@/components/Button"),
+            Some(PathBuf::from("app/src/components/Button.tsx"))
+        );
+        assert_eq!(resolve_aliased(&files, tsconfig, "@/utils"), Some(PathBuf::from("app/src/utils/index.ts")));
+        // Not an alias, but under `baseUrl`
+        assert_eq!(resolve_aliased(&files, tsconfig, "lib/format"), Some(PathBuf::from("app/lib/format.ts")));
+        // Neither falls back to a package
+        assert_eq!(resolve_aliased(&files, tsconfig, "velocity-runtime"), None);
+        // Relative imports ignore the config
+        assert_eq!(resolve_aliased(&files, tsconfig, "../components/Button"), Some(PathBuf::from("app/src/components/Button.tsx")));
+    }
+
+    #[test]
+    fn test_resolve_tsconfig_paths_without_base_url() {
+        // Locations are relative to the config itself
+        let tsconfig = r#"{ "compilerOptions": { "paths": { "@/*": ["./src/*"], "~icons": ["./assets/icons.ts"] } } }"#;
+        let files = ["app/src/App.tsx", "app/assets/icons.ts"];
+
+        assert_eq!(resolve_aliased(&files, tsconfig, "@/App"), Some(PathBuf::from("app/src/App.tsx")));
+        assert_eq!(resolve_aliased(&files, tsconfig, "~icons"), Some(PathBuf::from("app/assets/icons.ts")));
+        assert_eq!(resolve_aliased(&files, tsconfig, "src/App"), None);
+    }
+}
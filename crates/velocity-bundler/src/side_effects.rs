@@ -0,0 +1,123 @@
+//! Side-effect detection for tree-shaking
+//!
+//! A module may be left out of a bundle when none of its exports are used
+//! only if loading it has no side effects. The nearest `package.json` can
+//! declare that with its `sideEffects` field, as in webpack and Rollup:
+//! - `false`: no module of the package has side effects
+//! - an array of globs (`["*.css", "./src/polyfills/**"]`): only matching
+//!   modules have side effects. Globs without a `/` match the file name
+//!   anywhere in the package; others match paths from the package root.
+//!
+//! Modules the package doesn't declare side-effect-free are analyzed by the
+//! compiler, which treats anything it can't prove harmless as a side effect.
+
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+use velocity_compiler::{analyzer, parser, transformer};
+
+/// Whether loading the module at `path` with `content` may have side effects
+pub fn has_side_effects(path: &Path, content: &str) -> bool {
+    if package_side_effects(path) == Some(false) {
+        return false;
+    }
+    let Ok(module) = parser::parse(content, &path.to_string_lossy()) else {
+        return true;
+    };
+    match transformer::strip_typescript(module) {
+        Ok(module) => analyzer::has_side_effects(&module, content),
+        Err(_) => true,
+    }
+}
+
+/// What the nearest `package.json` above `path` says about its side
+/// effects, or `None` when there's no package or no `sideEffects` field
+pub fn package_side_effects(path: &Path) -> Option<bool> {
+    for dir in path.ancestors().skip(1) {
+        let manifest = dir.join("package.json");
+        let Ok(content) = fs::read_to_string(&manifest) else {
+            continue;
+        };
+        let json: Value = serde_json::from_str(&content).ok()?;
+        let relative = path.strip_prefix(dir).ok()?;
+        return side_effects_field(json.get("sideEffects")?, relative);
+    }
+    None
+}
+
+/// Interpret a `sideEffects` value for the file at `relative` to the package root
+fn side_effects_field(value: &Value, relative: &Path) -> Option<bool> {
+    match value {
+        Value::Bool(side_effects) => Some(*side_effects),
+        Value::Array(globs) => {
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            let file_name = relative.rsplit('/').next().unwrap_or(&relative);
+            Some(globs.iter().filter_map(Value::as_str).any(|glob| {
+                let glob = glob.trim_start_matches("./");
+                if glob.contains('/') {
+                    glob_match(glob, &relative)
+                } else {
+                    glob_match(glob, file_name)
+                }
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Match `path` against a glob where `**` spans directories, `*` matches
+/// within one name and `?` matches one character other than `/`
+fn glob_match(glob: &str, path: &str) -> bool {
+    if let Some(rest) = glob.strip_prefix("**") {
+        let rest = rest.strip_prefix('/').unwrap_or(rest);
+        return rest.is_empty()
+            || glob_match(rest, path)
+            || path.match_indices('/').any(|(i, _)| glob_match(rest, &path[i + 1..]));
+    }
+    let mut glob_chars = glob.chars();
+    match glob_chars.next() {
+        None => path.is_empty(),
+        Some('*') => {
+            let name_end = path.find('/').unwrap_or(path.len());
+            (0..=name_end)
+                .filter(|&i| path.is_char_boundary(i))
+                .any(|i| glob_match(glob_chars.as_str(), &path[i..]))
+        }
+        Some(g) => {
+            let mut path_chars = path.chars();
+            match path_chars.next() {
+                Some(p) if (g == '?' && p != '/') || g == p => glob_match(glob_chars.as_str(), path_chars.as_str()),
+                _ => false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_side_effects_field() {
+        let globs = json!(["*.css", "./src/polyfills/**", "src/setup.?s"]);
+        let check = |path: &str| side_effects_field(&globs, Path::new(path));
+
+        assert_eq!(check("src/theme/reset.css"), Some(true));
+        assert_eq!(check("src/polyfills/fetch/index.js"), Some(true));
+        assert_eq!(check("src/setup.ts"), Some(true));
+        assert_eq!(check("src/Button.tsx"), Some(false));
+        assert_eq!(check("lib/src/setup.ts"), Some(false));
+
+        assert_eq!(side_effects_field(&json!(false), Path::new("index.js")), Some(false));
+        assert_eq!(side_effects_field(&json!("yes"), Path::new("index.js")), None);
+    }
+
+    #[test]
+    fn test_has_side_effects() {
+        let path = Path::new("/nonexistent/src/util.ts");
+        assert!(!has_side_effects(path, "export const double = (n: number): number => n * 2;"));
+        assert!(has_side_effects(path, "document.title = 'App';"));
+        assert!(has_side_effects(path, "export const = ;"));
+    }
+}
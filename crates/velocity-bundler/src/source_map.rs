@@ -0,0 +1,396 @@
+//! Bundle-level source maps
+//!
+//! Concatenating modules moves each one down the bundle by the lines before
+//! it, so a module's own source map no longer lines up with the bundle.
+//! [`MappedBundle`] builds the bundle and its map together: each module's
+//! mappings are decoded, shifted to where the module starts in the bundle,
+//! and re-encoded against the bundle's combined `sources` and `names`. A
+//! module without a map of its own is mapped line by line to its source
+//! file, which is exact for modules copied into the bundle as written.
+//!
+//! Only plain (not indexed, `sections`-based) v3 maps are read.
+
+use serde::Deserialize;
+use serde_json::json;
+use thiserror::Error;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Error, Debug)]
+pub enum SourceMapError {
+    #[error("Invalid source map for {module}: {message}")]
+    Invalid { module: String, message: String },
+}
+
+/// A module's source map, as much of it as merging needs
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    #[serde(default)]
+    sources: Vec<String>,
+    #[serde(default)]
+    sources_content: Vec<Option<String>>,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    source_root: Option<String>,
+    mappings: String,
+}
+
+/// Where a generated position came from; indexes are into the bundle's
+/// `sources` and `names`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Original {
+    pub source: u32,
+    pub line: u32,
+    pub column: u32,
+    pub name: Option<u32>,
+}
+
+/// A mapping starting at `column` of a generated line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    column: u32,
+    original: Option<Original>,
+}
+
+/// A bundle and its source map, built by appending code
+#[derive(Debug, Default)]
+pub struct MappedBundle {
+    code: String,
+    sources: Vec<String>,
+    sources_content: Vec<Option<String>>,
+    names: Vec<String>,
+    /// Segments of each generated line, in column order
+    lines: Vec<Vec<Segment>>,
+}
+
+impl MappedBundle {
+    pub fn new() -> Self {
+        Self {
+            lines: vec![Vec::new()],
+            ..Default::default()
+        }
+    }
+
+    /// The bundle so far
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Append code the bundler generated itself, which maps to nothing
+    pub fn push_code(&mut self, code: &str) {
+        self.append(code);
+    }
+
+    /// Append a module's `code`, compiled from the file `source` with
+    /// contents `content`. `map` is the source map of `code`; without one,
+    /// `code` is taken to be `content` as written.
+    pub fn push_module(
+        &mut self,
+        code: &str,
+        source: &str,
+        content: &str,
+        map: Option<&str>,
+    ) -> Result<(), SourceMapError> {
+        let (start_line, start_column) = self.end();
+        let invalid = |message: String| SourceMapError::Invalid {
+            module: source.to_string(),
+            message,
+        };
+
+        let module_lines = match map {
+            Some(map) => {
+                let map: RawSourceMap = serde_json::from_str(map).map_err(|e| invalid(e.to_string()))?;
+                let root = map.source_root.as_deref().unwrap_or("");
+                let sources: Vec<u32> = map
+                    .sources
+                    .iter()
+                    .enumerate()
+                    .map(|(i, path)| {
+                        let content = map.sources_content.get(i).cloned().flatten();
+                        self.add_source(&format!("{}{}", root, path), content)
+                    })
+                    .collect();
+                let names: Vec<u32> = map.names.iter().map(|name| self.add_name(name)).collect();
+
+                let mut lines = decode_mappings(&map.mappings).map_err(invalid)?;
+                for segment in lines.iter_mut().flatten() {
+                    if let Some(original) = &mut segment.original {
+                        original.source = *sources
+                            .get(original.source as usize)
+                            .ok_or_else(|| invalid(format!("source index {} out of range", original.source)))?;
+                        original.name = match original.name {
+                            Some(name) => Some(
+                                *names
+                                    .get(name as usize)
+                                    .ok_or_else(|| invalid(format!("name index {} out of range", name)))?,
+                            ),
+                            None => None,
+                        };
+                    }
+                }
+                lines
+            }
+            None => {
+                let source = self.add_source(source, Some(content.to_string()));
+                (0..code.lines().count() as u32)
+                    .map(|line| {
+                        vec![Segment {
+                            column: 0,
+                            original: Some(Original {
+                                source,
+                                line,
+                                column: 0,
+                                name: None,
+                            }),
+                        }]
+                    })
+                    .collect()
+            }
+        };
+
+        self.append(code);
+
+        // The module's first line continues the bundle's last line, so its
+        // columns shift too
+        for (i, segments) in module_lines.into_iter().enumerate() {
+            let Some(line) = self.lines.get_mut(start_line + i) else {
+                break;
+            };
+            let shift = if i == 0 { start_column } else { 0 };
+            line.extend(segments.into_iter().map(|segment| Segment {
+                column: segment.column + shift,
+                ..segment
+            }));
+        }
+        Ok(())
+    }
+
+    /// Where a position in the bundle came from, if it's mapped; lines and
+    /// columns are zero-based
+    pub fn lookup(&self, line: usize, column: u32) -> Option<(&str, Original)> {
+        let segment = self.lines.get(line)?.iter().rev().find(|segment| segment.column <= column)?;
+        let original = segment.original?;
+        Some((&self.sources[original.source as usize], original))
+    }
+
+    /// The bundle's code and its source map JSON, which names the bundle
+    /// `file`
+    pub fn finish(self, file: &str) -> (String, String) {
+        let map = json!({
+            "version": 3,
+            "file": file,
+            "sources": self.sources,
+            "sourcesContent": self.sources_content,
+            "names": self.names,
+            "mappings": encode_mappings(&self.lines),
+        });
+        (self.code, map.to_string())
+    }
+
+    /// Line and column (in UTF-16 units, as source maps count) the next
+    /// appended code starts at
+    fn end(&self) -> (usize, u32) {
+        let last_line = self.code.rsplit('\n').next().unwrap_or("");
+        (self.lines.len() - 1, last_line.encode_utf16().count() as u32)
+    }
+
+    fn append(&mut self, code: &str) {
+        self.code.push_str(code);
+        for _ in code.matches('\n') {
+            self.lines.push(Vec::new());
+        }
+    }
+
+    fn add_source(&mut self, path: &str, content: Option<String>) -> u32 {
+        if let Some(index) = self.sources.iter().position(|source| source == path) {
+            return index as u32;
+        }
+        self.sources.push(path.to_string());
+        self.sources_content.push(content);
+        self.sources.len() as u32 - 1
+    }
+
+    fn add_name(&mut self, name: &str) -> u32 {
+        if let Some(index) = self.names.iter().position(|n| n == name) {
+            return index as u32;
+        }
+        self.names.push(name.to_string());
+        self.names.len() as u32 - 1
+    }
+}
+
+/// Decode a `mappings` string into the segments of each generated line
+fn decode_mappings(mappings: &str) -> Result<Vec<Vec<Segment>>, String> {
+    let mut lines = Vec::new();
+    // Every field but the generated column is relative to the previous
+    // segment across lines
+    let (mut source, mut line, mut column, mut name) = (0i64, 0i64, 0i64, 0i64);
+    for mapped_line in mappings.split(';') {
+        let mut segments = Vec::new();
+        let mut generated_column = 0i64;
+        for segment in mapped_line.split(',').filter(|segment| !segment.is_empty()) {
+            let fields = decode_vlq(segment)?;
+            generated_column += fields[0];
+            let original = match fields.len() {
+                1 => None,
+                4 | 5 => {
+                    source += fields[1];
+                    line += fields[2];
+                    column += fields[3];
+                    let named = fields.len() == 5;
+                    if named {
+                        name += fields[4];
+                    }
+                    Some(Original {
+                        source: non_negative(source)?,
+                        line: non_negative(line)?,
+                        column: non_negative(column)?,
+                        name: if named { Some(non_negative(name)?) } else { None },
+                    })
+                }
+                n => return Err(format!("segment '{}' has {} fields", segment, n)),
+            };
+            segments.push(Segment {
+                column: non_negative(generated_column)?,
+                original,
+            });
+        }
+        lines.push(segments);
+    }
+    Ok(lines)
+}
+
+fn non_negative(value: i64) -> Result<u32, String> {
+    u32::try_from(value).map_err(|_| format!("negative or oversized position {}", value))
+}
+
+/// Encode the segments of each generated line as a `mappings` string
+fn encode_mappings(lines: &[Vec<Segment>]) -> String {
+    let mut mappings = String::new();
+    let (mut source, mut line, mut column, mut name) = (0i64, 0i64, 0i64, 0i64);
+    for (i, segments) in lines.iter().enumerate() {
+        if i > 0 {
+            mappings.push(';');
+        }
+        let mut generated_column = 0i64;
+        for (j, segment) in segments.iter().enumerate() {
+            if j > 0 {
+                mappings.push(',');
+            }
+            encode_vlq(&mut mappings, segment.column as i64 - generated_column);
+            generated_column = segment.column as i64;
+            if let Some(original) = segment.original {
+                encode_vlq(&mut mappings, original.source as i64 - source);
+                encode_vlq(&mut mappings, original.line as i64 - line);
+                encode_vlq(&mut mappings, original.column as i64 - column);
+                (source, line, column) = (original.source as i64, original.line as i64, original.column as i64);
+                if let Some(original_name) = original.name {
+                    encode_vlq(&mut mappings, original_name as i64 - name);
+                    name = original_name as i64;
+                }
+            }
+        }
+    }
+    mappings
+}
+
+/// Decode the base64 VLQ fields of one segment
+fn decode_vlq(segment: &str) -> Result<Vec<i64>, String> {
+    let mut fields = Vec::new();
+    let (mut value, mut shift) = (0i64, 0u32);
+    for c in segment.bytes() {
+        let digit = BASE64
+            .iter()
+            .position(|&b| b == c)
+            .ok_or_else(|| format!("invalid character '{}' in mappings", c as char))? as i64;
+        if shift > 60 {
+            return Err(format!("segment '{}' overflows", segment));
+        }
+        value += (digit & 31) << shift;
+        if digit & 32 != 0 {
+            shift += 5;
+        } else {
+            fields.push(if value & 1 == 1 { -(value >> 1) } else { value >> 1 });
+            (value, shift) = (0, 0);
+        }
+    }
+    if shift != 0 {
+        return Err(format!("segment '{}' ends mid-value", segment));
+    }
+    Ok(fields)
+}
+
+fn encode_vlq(out: &mut String, value: i64) {
+    let mut vlq = if value < 0 { ((-value) << 1) | 1 } else { value << 1 };
+    loop {
+        let mut digit = vlq & 31;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 32;
+        }
+        out.push(BASE64[digit as usize] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vlq_round_trip() {
+        let mut encoded = String::new();
+        for value in [0, 1, -1, 15, 16, -16, 1000, -123456] {
+            encode_vlq(&mut encoded, value);
+        }
+        assert_eq!(decode_vlq(&encoded).unwrap(), [0, 1, -1, 15, 16, -16, 1000, -123456]);
+        // Values from the source map spec's examples
+        assert_eq!(decode_vlq("AAgBC").unwrap(), [0, 0, 16, 1]);
+        assert!(decode_vlq("g").is_err());
+    }
+
+    #[test]
+    fn test_concatenated_maps() {
+        let mut bundle = MappedBundle::new();
+        bundle.push_code("import 'velocity-runtime';\n\n");
+        bundle.push_module("const a = 1;\nconst b = 2;\n", "src/a.js", "const a = 1;\nconst b = 2;\n", None).unwrap();
+        bundle.push_code("\n");
+        // `App.js`, compiled from two lines of `App.tsx`: line 0 col 6
+        // (`App`, from line 1 col 9) and line 1 col 0 (from line 2 col 2)
+        let map = r#"{"version":3,"sources":["App.tsx"],"sourcesContent":["x"],"names":["App"],"sourceRoot":"src/","mappings":"MACSA;AACP"}"#;
+        bundle.push_code("/* App */ ");
+        bundle.push_module("const App = 1;\nrender(App);\n", "src/App.tsx", "", Some(map)).unwrap();
+
+        assert_eq!(bundle.lookup(0, 0), None);
+        let (source, original) = bundle.lookup(3, 4).unwrap();
+        assert_eq!((source, original.line, original.column), ("src/a.js", 1, 0));
+        // `/* App */ ` shifts the module's first line by 10 columns
+        let (source, original) = bundle.lookup(5, 16).unwrap();
+        assert_eq!((source, original.line, original.column), ("src/App.tsx", 1, 9));
+        assert_eq!(original.name, Some(0));
+        assert_eq!(bundle.lookup(5, 12), None);
+        let (_, original) = bundle.lookup(6, 3).unwrap();
+        assert_eq!((original.line, original.column), (2, 2));
+
+        let (code, map) = bundle.finish("bundle.js");
+        assert!(code.ends_with("render(App);\n"));
+        let map: serde_json::Value = serde_json::from_str(&map).unwrap();
+        assert_eq!(map["sources"], json!(["src/a.js", "src/App.tsx"]));
+        assert_eq!(map["sourcesContent"][1], json!("x"));
+        let lines = decode_mappings(map["mappings"].as_str().unwrap()).unwrap();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[5][0].column, 16);
+        assert_eq!(lines[5][0].original.unwrap().name, Some(0));
+    }
+
+    #[test]
+    fn test_invalid_module_map() {
+        let mut bundle = MappedBundle::new();
+        let error = bundle.push_module("x;\n", "src/x.js", "x;\n", Some(r#"{"mappings":"AACA","sources":[]}"#));
+        assert!(error.unwrap_err().to_string().contains("source index 0 out of range"));
+    }
+}
@@ -0,0 +1,246 @@
+//! `tsconfig.json` path aliases
+//!
+//! TypeScript projects import through aliases set in `compilerOptions`:
+//! - `paths` maps patterns to locations (`"@/*": ["src/*"]`). A pattern has
+//!   at most one `*`, which the matched text replaces in each location.
+//!   When several patterns match, the one with the longest text before its
+//!   `*` wins, and a pattern without `*` beats any wildcard.
+//! - `baseUrl` is the directory `paths` locations are relative to, and a
+//!   root for any other non-relative import (`components/Button`).
+//!   Without it, locations are relative to the `tsconfig.json` itself.
+//!
+//! A config `extends` another by relative path, inheriting `baseUrl` and
+//! `paths` unless it sets them itself; each stays relative to the file that
+//! set it. Configs extended by package name aren't followed. Comments and
+//! trailing commas are allowed, as `tsc` allows them.
+
+use crate::resolver::normalize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// File name of the config looked up in the project root
+pub const TSCONFIG_FILE: &str = "tsconfig.json";
+
+#[derive(Error, Debug)]
+pub enum TsConfigError {
+    #[error("Failed to read {}: {source}", path.display())]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("Invalid {}: {source}", path.display())]
+    Parse { path: PathBuf, source: serde_json::Error },
+    #[error("{} extends itself through {}", path.display(), extends.display())]
+    Cycle { path: PathBuf, extends: PathBuf },
+}
+
+/// The module resolution settings of a `tsconfig.json`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TsConfig {
+    /// `compilerOptions.baseUrl`, resolved against the config's directory
+    pub base_url: Option<PathBuf>,
+    /// `compilerOptions.paths` as (pattern, locations), in file order
+    pub paths: Vec<(String, Vec<String>)>,
+    /// Directory `paths` locations are relative to
+    pub paths_dir: PathBuf,
+}
+
+impl TsConfig {
+    /// Read the config at `path`, following relative `extends`
+    pub fn load(path: &Path) -> Result<Self, TsConfigError> {
+        Self::load_chain(path, &mut Vec::new())
+    }
+
+    fn load_chain(path: &Path, seen: &mut Vec<PathBuf>) -> Result<Self, TsConfigError> {
+        let path = normalize(path);
+        let text = fs::read_to_string(&path).map_err(|source| TsConfigError::Read {
+            path: path.clone(),
+            source,
+        })?;
+        let json: Value = serde_json::from_str(&strip_jsonc(&text)).map_err(|source| TsConfigError::Parse {
+            path: path.clone(),
+            source,
+        })?;
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        seen.push(path.clone());
+
+        let parent = match json.get("extends").and_then(Value::as_str) {
+            Some(extends) if extends.starts_with("./") || extends.starts_with("../") => {
+                let mut extends = normalize(&dir.join(extends));
+                if extends.extension().is_none() {
+                    extends.set_extension("json");
+                }
+                if seen.contains(&extends) {
+                    return Err(TsConfigError::Cycle { path, extends });
+                }
+                Some(Self::load_chain(&extends, seen)?)
+            }
+            _ => None,
+        };
+
+        Ok(Self::from_json(&json, &dir, parent.unwrap_or_default()))
+    }
+
+    /// Parse the text of a config in `dir` that extends nothing
+    pub fn parse(text: &str, dir: &Path) -> Result<Self, serde_json::Error> {
+        let json: Value = serde_json::from_str(&strip_jsonc(text))?;
+        Ok(Self::from_json(&json, dir, Self::default()))
+    }
+
+    /// Settings from `json`, a config in `dir`, over those of `parent`
+    fn from_json(json: &Value, dir: &Path, parent: Self) -> Self {
+        let options = json.get("compilerOptions");
+        let base_url = options
+            .and_then(|options| options.get("baseUrl"))
+            .and_then(Value::as_str)
+            .map(|base_url| normalize(&dir.join(base_url)));
+        let paths = options.and_then(|options| options.get("paths")).and_then(Value::as_object).map(|paths| {
+            paths
+                .iter()
+                .map(|(pattern, locations)| {
+                    let locations = locations
+                        .as_array()
+                        .map(|locations| locations.iter().filter_map(Value::as_str).map(String::from).collect())
+                        .unwrap_or_default();
+                    (pattern.clone(), locations)
+                })
+                .collect()
+        });
+
+        let base_url = base_url.or(parent.base_url);
+        match paths {
+            // Locations are relative to the `baseUrl` in effect, or to the
+            // file that set `paths`
+            Some(paths) => Self {
+                paths_dir: base_url.clone().unwrap_or_else(|| dir.to_path_buf()),
+                base_url,
+                paths,
+            },
+            None => Self {
+                paths_dir: parent.paths_dir,
+                base_url,
+                paths: parent.paths,
+            },
+        }
+    }
+
+    /// Where the best `paths` pattern matching `specifier` maps it, in the
+    /// order to try them, or `None` when no pattern matches
+    pub fn map_path(&self, specifier: &str) -> Option<Vec<PathBuf>> {
+        let (pattern, locations) = self
+            .paths
+            .iter()
+            .filter(|(pattern, _)| match_pattern(pattern, specifier).is_some())
+            .max_by_key(|(pattern, _)| match pattern.find('*') {
+                Some(star) => star,
+                None => usize::MAX,
+            })?;
+        let matched = match_pattern(pattern, specifier)?;
+
+        Some(
+            locations
+                .iter()
+                .map(|location| normalize(&self.paths_dir.join(location.replacen('*', matched, 1))))
+                .collect(),
+        )
+    }
+}
+
+/// The text `*` stands for when `specifier` matches `pattern` (empty for a
+/// pattern without `*`)
+fn match_pattern<'a>(pattern: &str, specifier: &'a str) -> Option<&'a str> {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) if specifier.len() >= prefix.len() + suffix.len() => {
+            specifier.strip_prefix(prefix)?.strip_suffix(suffix)
+        }
+        Some(_) => None,
+        None => (pattern == specifier).then_some(""),
+    }
+}
+
+/// `text` with `//` and `/* */` comments and trailing commas removed, so
+/// that a `tsconfig.json` parses as JSON
+fn strip_jsonc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                out.push(' ');
+            }
+            ']' | '}' => {
+                let kept = out.trim_end().len();
+                if out[..kept].ends_with(',') {
+                    out.truncate(kept - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_path_prefers_longest_prefix() {
+        let config = TsConfig::parse(
+            r#"{
+                // Comments and trailing commas are fine
+                "compilerOptions": {
+                    "baseUrl": "./src",
+                    "paths": {
+                        "@/*": ["*", "generated/*"],
+                        "@/ui/*": ["components/ui/*"],
+                        "config": ["../config/index.ts"], /* exact */
+                    },
+                },
+            }"#,
+            Path::new("app"),
+        )
+        .unwrap();
+
+        assert_eq!(config.base_url, Some(PathBuf::from("app/src")));
+        assert_eq!(
+            config.map_path("@/components/Button"),
+            Some(vec![PathBuf::from("app/src/components/Button"), PathBuf::from("app/src/generated/components/Button")])
+        );
+        assert_eq!(config.map_path("@/ui/Card"), Some(vec![PathBuf::from("app/src/components/ui/Card")]));
+        assert_eq!(config.map_path("config"), Some(vec![PathBuf::from("app/config/index.ts")]));
+        assert_eq!(config.map_path("velocity-runtime"), None);
+    }
+
+    #[test]
+    fn test_strip_jsonc_keeps_strings() {
+        let text = r#"{ "a": "http://x/*y*/", "b": [1, 2,], /* c */ "d": "\"//" }"#;
+        let json: Value = serde_json::from_str(&strip_jsonc(text)).unwrap();
+
+        assert_eq!(json["a"], "http://x/*y*/");
+        assert_eq!(json["b"], serde_json::json!([1, 2]));
+        assert_eq!(json["d"], "\"//");
+    }
+}
@@ -0,0 +1,36 @@
+//! Bakes the git commit the CLI was built from into `VELOCITY_GIT_HASH`,
+//! shown by `velocity info`.
+
+use std::path::Path;
+use std::process::Command;
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    Some(text.trim().to_string())
+}
+
+fn main() {
+    let hash = git(&["rev-parse", "--short", "HEAD"]);
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"]).is_some_and(|s| !s.is_empty());
+
+    let version = match hash {
+        Some(hash) if dirty => format!("{}-dirty", hash),
+        Some(hash) => hash,
+        None => "unknown".to_string(),
+    };
+    println!("cargo:rustc-env=VELOCITY_GIT_HASH={}", version);
+
+    // Rebuild when HEAD moves (checkout, commit)
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        let git_dir = Path::new(&git_dir);
+        println!("cargo:rerun-if-changed={}", git_dir.join("HEAD").display());
+        println!("cargo:rerun-if-changed={}", git_dir.join("index").display());
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            println!("cargo:rerun-if-changed={}", git_dir.join(head_ref).display());
+        }
+    }
+}
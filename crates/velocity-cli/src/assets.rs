@@ -0,0 +1,182 @@
+//! Static assets for `velocity build`
+//!
+//! Stylesheets, images and data files in `src/` and `public/` are copied into
+//! the output directory with their directory structure preserved, so the
+//! paths compiled modules reference still resolve. `public/foo.png` lands at
+//! `out/foo.png`, `src/img/logo.svg` at `out/img/logo.svg`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use velocity_compiler::css;
+use walkdir::WalkDir;
+use crate::ignore::IgnoreList;
+
+/// Extensions (lowercase) of files copied as-is into the output directory
+const ASSET_EXTENSIONS: &[&str] = &[
+    "css", "png", "jpg", "jpeg", "gif", "webp", "svg", "ico", "json", "woff", "woff2",
+];
+
+/// One asset written to the output directory
+pub struct CopiedAsset {
+    /// Path relative to the project root
+    pub source: PathBuf,
+    /// Path relative to the output directory
+    pub output: PathBuf,
+}
+
+/// Copy the assets of `src/` and `public/` into `out_dir`, minifying plain
+/// CSS when `minify` is set. CSS modules are compiled, not copied.
+pub fn copy_assets(root: &Path, out_dir: &Path, minify: bool) -> anyhow::Result<Vec<CopiedAsset>> {
+    let ignore = IgnoreList::load(root)?;
+    let mut copied = Vec::new();
+
+    for dir in ["public", "src"] {
+        let base = root.join(dir);
+        if !base.is_dir() {
+            continue;
+        }
+
+        let entries = WalkDir::new(&base).follow_links(true).into_iter();
+        for entry in entries.filter_entry(|e| !ignore.is_ignored(e.path())).filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if !path.is_file() || !is_asset(path) {
+                continue;
+            }
+
+            let relative = path.strip_prefix(&base)?;
+            let output = out_dir.join(relative);
+            if let Some(parent) = output.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| anyhow::anyhow!("Failed to create directory {}: {}", parent.display(), e))?;
+            }
+
+            if minify && has_extension(path, "css") {
+                let source = fs::read_to_string(path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+                fs::write(&output, minify_css(&source))
+            } else {
+                fs::copy(path, &output).map(|_| ())
+            }
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", output.display(), e))?;
+
+            copied.push(CopiedAsset {
+                source: path.strip_prefix(root)?.to_path_buf(),
+                output: relative.to_path_buf(),
+            });
+        }
+    }
+
+    Ok(copied)
+}
+
+/// Whether `path` is copied into the output rather than compiled
+fn is_asset(path: &Path) -> bool {
+    if path.to_string_lossy().ends_with(css::CSS_MODULE_SUFFIX) {
+        return false;
+    }
+    ASSET_EXTENSIONS.iter().any(|ext| has_extension(path, ext))
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().eq_ignore_ascii_case(extension))
+        .unwrap_or(false)
+}
+
+/// Strip comments and insignificant whitespace from a stylesheet.
+///
+/// Strings are copied untouched; whitespace inside selectors and values is
+/// collapsed to a single space rather than removed, so `a b` and `1px solid`
+/// keep their meaning.
+pub fn minify_css(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut pending_space = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = '\0';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                pending_space = true;
+            }
+            '"' | '\'' => {
+                push_pending_space(&mut out, &mut pending_space);
+                out.push(c);
+                while let Some(inner) = chars.next() {
+                    out.push(inner);
+                    if inner == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            out.push(escaped);
+                        }
+                    } else if inner == c {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => pending_space = true,
+            '{' | '}' | ';' | ':' | ',' | '>' => {
+                // `a :hover` and `a:hover` are different selectors; a colon
+                // in a selector is followed by `{` before any `;` or `}`
+                if c == ':' && chars.clone().find(|c| matches!(c, '{' | ';' | '}')) == Some('{') {
+                    push_pending_space(&mut out, &mut pending_space);
+                }
+                pending_space = false;
+                if c == '}' && out.ends_with(';') {
+                    out.pop();
+                }
+                out.push(c);
+                // Drop the whitespace that follows, too
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            _ => {
+                push_pending_space(&mut out, &mut pending_space);
+                out.push(c);
+            }
+        }
+    }
+
+    out
+}
+
+/// Emit a collapsed run of whitespace, unless it's leading or follows punctuation
+fn push_pending_space(out: &mut String, pending_space: &mut bool) {
+    if *pending_space && !out.is_empty() && !out.ends_with(['{', '}', ';', ':', ',', '>']) {
+        out.push(' ');
+    }
+    *pending_space = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minify_css() {
+        let source = r#"
+            /* Layout */
+            .card  > .title ,
+            .card :hover {
+                margin : 0 auto;
+                font-family: "Open  Sans", sans-serif;
+            }
+
+            @media (max-width: 600px) {
+                .card { padding: 1px  2px; }
+            }
+        "#;
+
+        assert_eq!(
+            minify_css(source),
+            r#".card>.title,.card :hover{margin:0 auto;font-family:"Open  Sans",sans-serif}@media (max-width:600px){.card{padding:1px 2px}}"#
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use colored::*;
+use velocity_compiler::{parser, CompilerError};
+use walkdir::WalkDir;
+
+/// File names (without extension) treated as the app entry point
+const ENTRY_NAMES: &[&str] = &["index", "main", "app"];
+
+/// Source extensions the compiler understands
+const SOURCE_EXTENSIONS: &[&str] = &["tsx", "ts", "jsx", "js"];
+
+/// Outcome of a single check
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+struct CheckResult {
+    status: Status,
+    title: String,
+    /// Extra lines explaining a failure or how to fix it
+    details: Vec<String>,
+}
+
+impl CheckResult {
+    fn pass(title: impl Into<String>) -> Self {
+        Self { status: Status::Pass, title: title.into(), details: Vec::new() }
+    }
+
+    fn warn(title: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self { status: Status::Warn, title: title.into(), details: vec![detail.into()] }
+    }
+
+    fn fail(title: impl Into<String>, details: Vec<String>) -> Self {
+        Self { status: Status::Fail, title: title.into(), details }
+    }
+}
+
+/// Validate a project's setup, printing a checklist.
+///
+/// Returns an error if any check failed so the process exits non-zero.
+pub fn check_project(root: &str) -> anyhow::Result<()> {
+    let root_path = PathBuf::from(root);
+    let src_dir = root_path.join("src");
+
+    println!("\n{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    println!("{} {}", "🩺".bright_yellow(), "Checking Velocity Project".bright_cyan().bold());
+    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    println!();
+
+    let mut results = Vec::new();
+
+    // Source directory and entry point
+    let entry = if src_dir.is_dir() {
+        results.push(CheckResult::pass("src/ directory exists"));
+        let entry = find_entry(&src_dir);
+        results.push(match &entry {
+            Some(entry) => CheckResult::pass(format!("Entry file found: {}", display_relative(&root_path, entry))),
+            None => CheckResult::fail(
+                "No entry file in src/",
+                vec![format!("Create src/index.tsx (or one of: {})", ENTRY_NAMES.join(", "))],
+            ),
+        });
+        entry
+    } else {
+        results.push(CheckResult::fail(
+            "src/ directory missing",
+            vec![format!("Expected source files in {}", src_dir.display())],
+        ));
+        None
+    };
+
+    results.push(check_index_html(&root_path, entry.as_deref()));
+    results.push(check_runtime(&root_path));
+    results.push(check_tsconfig(&root_path));
+
+    if src_dir.is_dir() {
+        results.push(check_sources_parse(&root_path, &src_dir));
+    }
+
+    // Print the checklist
+    let mut failures = 0;
+    for result in &results {
+        let icon = match result.status {
+            Status::Pass => "✅",
+            Status::Warn => "⚠️ ",
+            Status::Fail => {
+                failures += 1;
+                "❌"
+            }
+        };
+        println!("  {} {}", icon, result.title);
+        for detail in &result.details {
+            println!("     {} {}", "→".bright_black(), detail.bright_black());
+        }
+    }
+    println!();
+
+    if failures > 0 {
+        return Err(anyhow::anyhow!("{} check(s) failed", failures));
+    }
+
+    println!("{}", "All checks passed!".bright_green().bold());
+    println!();
+    Ok(())
+}
+
+/// Find the app entry file in `src/`, e.g. `src/index.tsx`
+fn find_entry(src_dir: &Path) -> Option<PathBuf> {
+    ENTRY_NAMES.iter().find_map(|name| {
+        SOURCE_EXTENSIONS
+            .iter()
+            .map(|ext| src_dir.join(format!("{}.{}", name, ext)))
+            .find(|path| path.is_file())
+    })
+}
+
+/// index.html must load the compiled entry, e.g. `/dist/index.js`
+fn check_index_html(root: &Path, entry: Option<&Path>) -> CheckResult {
+    let Ok(html) = fs::read_to_string(root.join("index.html")) else {
+        return CheckResult::fail(
+            "index.html missing",
+            vec!["Create index.html with a <script type=\"module\"> tag for your entry".to_string()],
+        );
+    };
+
+    let scripts = script_sources(&html);
+    let Some(entry) = entry else {
+        return CheckResult::warn("index.html found", "Can't verify its script tag without an entry file");
+    };
+
+    let stem = entry.file_stem().and_then(|s| s.to_str()).unwrap_or("index");
+    let expected = format!("dist/{}.js", stem);
+    let references_entry = scripts
+        .iter()
+        .any(|src| src.trim_start_matches("./").trim_start_matches('/') == expected);
+
+    if references_entry {
+        CheckResult::pass(format!("index.html loads /{}", expected))
+    } else {
+        let found = if scripts.is_empty() {
+            "no <script src> tags found".to_string()
+        } else {
+            format!("found: {}", scripts.join(", "))
+        };
+        CheckResult::fail(
+            format!("index.html doesn't load /{}", expected),
+            vec![found, format!("Add <script type=\"module\" src=\"/{}\"></script>", expected)],
+        )
+    }
+}
+
+/// Collect the `src` attributes of every `<script>` tag
+fn script_sources(html: &str) -> Vec<String> {
+    html.split("<script")
+        .skip(1)
+        .filter_map(|tag| {
+            let tag = &tag[..tag.find('>')?];
+            let start = tag.find("src=")? + 4;
+            let quote = tag[start..].chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let value = &tag[start + 1..];
+            Some(value[..value.find(quote)?].to_string())
+        })
+        .collect()
+}
+
+/// At least one runtime build must be present
+fn check_runtime(root: &Path) -> CheckResult {
+    match crate::RUNTIME_ARTIFACTS.iter().find(|artifact| root.join(artifact).is_file()) {
+        Some(artifact) => CheckResult::pass(format!("Runtime found: {}", artifact)),
+        None => CheckResult::fail(
+            "Velocity runtime missing",
+            vec![format!("Expected one of: {}", crate::RUNTIME_ARTIFACTS.join(", "))],
+        ),
+    }
+}
+
+/// JSX must be left for the Velocity compiler, not transformed by tsc
+fn check_tsconfig(root: &Path) -> CheckResult {
+    let Ok(text) = fs::read_to_string(root.join("tsconfig.json")) else {
+        return CheckResult::pass("No tsconfig.json (TypeScript config optional)");
+    };
+
+    let config: serde_json::Value = match serde_json::from_str(&text) {
+        Ok(config) => config,
+        Err(e) => {
+            return CheckResult::warn(
+                "tsconfig.json couldn't be checked",
+                format!("Not plain JSON ({}); comments aren't supported by this check", e),
+            )
+        }
+    };
+
+    match config.pointer("/compilerOptions/jsx").and_then(|v| v.as_str()) {
+        Some("preserve") => CheckResult::pass("tsconfig.json preserves JSX"),
+        other => CheckResult::fail(
+            "tsconfig.json transforms JSX",
+            vec![
+                format!("compilerOptions.jsx is {}", other.map(|v| format!("\"{}\"", v)).unwrap_or("unset".to_string())),
+                "Set \"jsx\": \"preserve\" so Velocity compiles JSX".to_string(),
+            ],
+        ),
+    }
+}
+
+/// Every source file must parse
+fn check_sources_parse(root: &Path, src_dir: &Path) -> CheckResult {
+    let mut count = 0;
+    let mut errors = Vec::new();
+
+    for entry in WalkDir::new(src_dir)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        let is_source = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext));
+        if !path.is_file() || !is_source {
+            continue;
+        }
+
+        count += 1;
+        let name = display_relative(root, path);
+        match fs::read_to_string(path) {
+            Ok(source) => {
+                match parser::parse(&source, &name) {
+                    Ok(_) => {}
+                    Err(CompilerError::ParseError(message)) => errors.push(message),
+                    Err(e) => errors.push(e.to_string()),
+                }
+            }
+            Err(e) => errors.push(format!("Failed to read {}: {}", name, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        CheckResult::pass(format!("{} source file(s) parse without errors", count))
+    } else {
+        CheckResult::fail(format!("{} of {} source file(s) failed to parse", errors.len(), count), errors)
+    }
+}
+
+fn display_relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).display().to_string()
+}
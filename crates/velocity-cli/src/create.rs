@@ -1,6 +1,7 @@
 use std::fs;
 use std::path::PathBuf;
 use colored::*;
+use crate::log::info;
 
 /// Create a new Velocity project from a template
 pub fn create_project(name: &str, template: &str) -> anyhow::Result<()> {
@@ -11,10 +12,10 @@ pub fn create_project(name: &str, template: &str) -> anyhow::Result<()> {
         return Err(anyhow::anyhow!("Directory '{}' already exists", name));
     }
 
-    println!("\n{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!("{} {}", "⚡".bright_yellow(), format!("Creating Velocity Project").bright_cyan().bold());
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!();
+    info!("\n{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    info!("{} {}", "⚡".bright_yellow(), format!("Creating Velocity Project").bright_cyan().bold());
+    info!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    info!();
 
     // Create project structure
     fs::create_dir_all(&project_path)?;
@@ -22,9 +23,9 @@ pub fn create_project(name: &str, template: &str) -> anyhow::Result<()> {
     fs::create_dir_all(project_path.join("dist"))?;
     fs::create_dir_all(project_path.join("public"))?;
 
-    println!("{}  Creating project: {}", "✓".bright_green(), name.bright_white());
-    println!("{}  Template: {}", "✓".bright_green(), template.bright_white());
-    println!();
+    info!("{}  Creating project: {}", "✓".bright_green(), name.bright_white());
+    info!("{}  Template: {}", "✓".bright_green(), template.bright_white());
+    info!();
 
     // Write index.html
     let index_html = format!(r#"<!DOCTYPE html>
@@ -42,7 +43,7 @@ pub fn create_project(name: &str, template: &str) -> anyhow::Result<()> {
 </html>"#, name);
 
     fs::write(project_path.join("index.html"), index_html)?;
-    println!("{}  index.html", "✓".bright_green());
+    info!("{}  index.html", "✓".bright_green());
 
     // Write package.json
     let package_json = format!(r#"{{
@@ -56,7 +57,7 @@ pub fn create_project(name: &str, template: &str) -> anyhow::Result<()> {
 }}"#, name);
 
     fs::write(project_path.join("package.json"), package_json)?;
-    println!("{}  package.json", "✓".bright_green());
+    info!("{}  package.json", "✓".bright_green());
 
     // Write example based on template
     let (app_code, styles) = match template {
@@ -75,15 +76,15 @@ pub fn create_project(name: &str, template: &str) -> anyhow::Result<()> {
     };
 
     fs::write(project_path.join("src/index.tsx"), app_code)?;
-    println!("{}  src/index.tsx", "✓".bright_green());
+    info!("{}  src/index.tsx", "✓".bright_green());
 
     fs::write(project_path.join("public/style.css"), styles)?;
-    println!("{}  public/style.css", "✓".bright_green());
+    info!("{}  public/style.css", "✓".bright_green());
 
     // Copy runtime
     let runtime = include_str!("../../velocity-wasm/pkg/velocity_wasm.js");
     fs::write(project_path.join("dist/velocity-runtime.js"), runtime)?;
-    println!("{}  dist/velocity-runtime.js", "✓".bright_green());
+    info!("{}  dist/velocity-runtime.js", "✓".bright_green());
 
     // Write README
     let readme = format!(r#"# {}
@@ -109,17 +110,17 @@ Built with ⚡ by Velocity
 "#, name);
 
     fs::write(project_path.join("README.md"), readme)?;
-    println!("{}  README.md", "✓".bright_green());
-
-    println!();
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!("{} {}", "🎉".bright_yellow(), "Project created successfully!".bright_green().bold());
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!();
-    println!("Next steps:");
-    println!("  {}  cd {}", "→".bright_cyan(), name.bright_white());
-    println!("  {}  velocity dev", "→".bright_cyan());
-    println!();
+    info!("{}  README.md", "✓".bright_green());
+
+    info!();
+    info!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    info!("{} {}", "🎉".bright_yellow(), "Project created successfully!".bright_green().bold());
+    info!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    info!();
+    info!("Next steps:");
+    info!("  {}  cd {}", "→".bright_cyan(), name.bright_white());
+    info!("  {}  velocity dev", "→".bright_cyan());
+    info!();
 
     Ok(())
 }
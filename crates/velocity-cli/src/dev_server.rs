@@ -1,32 +1,72 @@
 //! Development server with HMR support
+//!
+//! Serves one project, or several [workspaces](crate::workspace) side by
+//! side, each under its own path prefix with its own state. The scoped CSS
+//! of `css` templates and CSS modules is served as one stylesheet, linked
+//! into the page, and compiled CSS modules are generated on request.
 
 use anyhow::Result;
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        OriginalUri, Query, Request, State,
     },
-    response::{Html, IntoResponse},
+    handler::Handler,
+    http::{HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Redirect, Response},
     routing::get,
     Router,
 };
 use futures::{sink::SinkExt, stream::StreamExt};
 use notify::{EventKind, RecursiveMode, Watcher};
 use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
     net::SocketAddr,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 use tokio::sync::broadcast;
+use crate::ignore::IgnoreList;
+use crate::log::{self, info, verbose, warning};
+use crate::workspace;
 use tower_http::services::ServeDir;
-use velocity_compiler::{Compiler, CompilerOptions};
+use velocity_compiler::{css, A11yRules, CodeStyle, Compiler, CompilerOptions, Environment, JsxMode, MinifyLevel, ModuleFormat, ReactiveFunctions, RuntimeImportStyle};
+
+/// Version of the HMR protocol spoken by this server and the client it
+/// serves. Bump it whenever a message changes shape or a new one is added,
+/// so a stale client (a tab left open across an upgrade) reloads instead of
+/// silently ignoring updates.
+pub const HMR_PROTOCOL_VERSION: u32 = 1;
+
+/// Placeholder in `hmr_client.js` replaced with [`HMR_PROTOCOL_VERSION`]
+const PROTOCOL_VERSION_PLACEHOLDER: &str = "__VELOCITY_HMR_PROTOCOL__";
+
+/// Placeholder in `hmr_client.js` replaced with the public base path
+const BASE_PLACEHOLDER: &str = "__VELOCITY_BASE__";
+
+/// Placeholder in `hmr_client.js` replaced with the HMR WebSocket path
+const HMR_PATH_PLACEHOLDER: &str = "__VELOCITY_HMR_PATH__";
+
+/// Path under the base path of the stylesheet holding the scoped CSS of
+/// `css` templates and CSS modules, which pages get a `<link>` to
+const STYLES_PATH: &str = "__velocity/styles.css";
+
+/// Default path of the HMR WebSocket, relative to the base path
+pub const DEFAULT_HMR_PATH: &str = "__hmr";
+
+/// First path segments already routed under each base, which the HMR
+/// WebSocket can't be moved under
+const RESERVED_SEGMENTS: &[&str] = &["__velocity", "dist", "src", "public", "examples"];
 
 /// HMR message types
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type")]
 pub enum HMRMessage {
     #[serde(rename = "connected")]
-    Connected,
+    Connected { protocol_version: u32 },
     #[serde(rename = "update")]
     Update {
         module: String,
@@ -36,68 +76,172 @@ pub enum HMRMessage {
         #[serde(skip_serializing_if = "Vec::is_empty", default)]
         dependents: Vec<String>,
     },
+    /// A stylesheet changed; the client swaps its `<link>` without reloading
+    #[serde(rename = "css-update")]
+    CssUpdate {
+        /// URL path of the stylesheet, e.g. `/public/style.css`
+        path: String,
+    },
     #[serde(rename = "full-reload")]
     FullReload { reason: String },
     #[serde(rename = "error")]
     Error { error: String },
 }
 
-/// Dev server state
-#[derive(Clone)]
+/// Compiler options the dev server compiles changed files with
+pub fn dev_compiler_options() -> CompilerOptions {
+    CompilerOptions {
+        optimize: true,
+        source_maps: true,
+        target: "es2020".to_string(),
+        minify: MinifyLevel::None,
+        effect_labels: true,
+        profile_components: false,
+        strict: false,
+        a11y: A11yRules::default(),
+        reactive_functions: ReactiveFunctions::default(),
+        passes: Vec::new(),
+        module_format: ModuleFormat::Esm,
+        code_style: CodeStyle::default(),
+        runtime_import_style: RuntimeImportStyle::Barrel,
+        jsx: JsxMode::Transform,
+        i18n: None,
+        prop_checks: true,
+        environment: Environment::Browser,
+        hmr_signal_keys: true,
+    }
+}
+
+/// State of one served project: the only one, or one workspace
 pub struct DevServerState {
-    /// Broadcast channel for HMR updates
+    /// Broadcast channel for HMR updates, reaching only this project's pages
     hmr_tx: broadcast::Sender<HMRMessage>,
-    /// Root directory
+    /// Root directory, canonicalized
     root: PathBuf,
+    /// Public base path every URL is served under, with leading and trailing
+    /// slashes (`/`, `/app/`, or `/admin/` for workspace `admin`)
+    base: String,
+    /// Path of the HMR WebSocket relative to `base`, without slashes around
+    /// it (`__hmr`)
+    hmr_path: String,
     /// Compiler options
     compiler_options: CompilerOptions,
+    /// Scoped CSS of each source with any, keyed by its path under `src/`,
+    /// served at [`STYLES_PATH`] as `velocity build` collects it into
+    /// styles.css
+    styles: Mutex<BTreeMap<PathBuf, String>>,
 }
 
 impl DevServerState {
-    pub fn new(root: PathBuf) -> Self {
+    pub fn new(root: PathBuf, base: String, hmr_path: String, compiler_options: CompilerOptions) -> Self {
         let (hmr_tx, _) = broadcast::channel(100);
         Self {
             hmr_tx,
-            root,
-            compiler_options: CompilerOptions {
-                optimize: true,
-                source_maps: true,
-                target: "es2020".to_string(),
-                minify: false,
-            },
+            // The watcher reports canonical paths, which have to start with it
+            root: fs::canonicalize(&root).unwrap_or(root),
+            base,
+            hmr_path,
+            compiler_options,
+            styles: Mutex::new(BTreeMap::new()),
         }
     }
 
+    /// Record the scoped CSS of the source at `path`, or that it has none.
+    /// Returns whether the stylesheet changed.
+    fn set_styles(&self, path: &Path, css: Option<String>) -> bool {
+        let source = path.strip_prefix(self.root.join("src")).unwrap_or(path).to_path_buf();
+        let mut styles = self.styles.lock().unwrap();
+        if styles.get(&source) == css.as_ref() {
+            return false;
+        }
+        match css {
+            Some(css) => styles.insert(source, css),
+            None => styles.remove(&source),
+        };
+        true
+    }
+
+    /// URL path of the scoped stylesheet, e.g. `/__velocity/styles.css`
+    fn styles_url(&self) -> String {
+        format!("{}{}", self.base, STYLES_PATH)
+    }
+
     /// Broadcast an HMR update
     pub fn broadcast_update(&self, msg: HMRMessage) {
-        let _ = self.hmr_tx.send(msg);
+        let clients = self.hmr_tx.send(msg).unwrap_or(0);
+        verbose!("Sent to {} HMR client(s) under {}", clients, self.base);
     }
 }
 
-/// Start the development server
-pub async fn start_dev_server(port: u16, root: String) -> Result<()> {
+/// Start the development server, serving everything under `base` (e.g.
+/// `/app/` behind a reverse proxy). With `workspaces` (`--workspace` flags)
+/// or a workspace config in `root`, each workspace is served under
+/// `<base><name>/` and `base` lists them. Pages connect to the HMR
+/// WebSocket at `hmr_path` under their base.
+pub async fn start_dev_server(
+    port: u16,
+    root: String,
+    base: &str,
+    hmr_path: &str,
+    workspaces: &[String],
+) -> Result<()> {
     let root_path = PathBuf::from(&root);
-    let state = Arc::new(DevServerState::new(root_path.clone()));
-
-    // Start file watcher in background
-    let watcher_state = state.clone();
-    let watcher_root = root_path.clone();
-    tokio::spawn(async move {
-        if let Err(e) = start_file_watcher(watcher_state, watcher_root).await {
-            eprintln!("File watcher error: {}", e);
-        }
-    });
+    let base = normalize_base(base)?;
+    let hmr_path = normalize_hmr_path(hmr_path)?;
+    let workspaces = workspace::load_workspaces(&root_path, workspaces)?;
+    let states: Vec<Arc<DevServerState>> = if workspaces.is_empty() {
+        vec![Arc::new(DevServerState::new(root_path.clone(), base.clone(), hmr_path, dev_compiler_options()))]
+    } else {
+        workspaces
+            .into_iter()
+            .map(|w| {
+                let base = format!("{}{}/", base, w.name);
+                Arc::new(DevServerState::new(w.root, base, hmr_path.clone(), w.compiler_options))
+            })
+            .collect()
+    };
+
+    // Start a file watcher per project in background
+    for state in &states {
+        let start = Instant::now();
+        collect_styles(state)?;
+        verbose!("Collected scoped styles under {} in {}", state.base, log::ms(start.elapsed()));
+
+        verbose!(
+            "{} → {} (target {})",
+            state.base,
+            fs::canonicalize(&state.root).unwrap_or(state.root.clone()).display(),
+            state.compiler_options.target
+        );
+        let watcher_state = state.clone();
+        let watcher_root = state.root.clone();
+        tokio::spawn(async move {
+            if let Err(e) = start_file_watcher(watcher_state, watcher_root).await {
+                eprintln!("File watcher error: {}", e);
+            }
+        });
+    }
+
+    let mut app = Router::new();
+    for state in &states {
+        app = app.merge(project_router(state.clone()));
+    }
 
-    // Create router
-    let app = Router::new()
-        .route("/", get(serve_index))
-        .route("/__hmr", get(ws_handler))
-        .route("/__velocity/hmr-client.js", get(serve_hmr_client))
-        .nest_service("/dist", ServeDir::new(root_path.join("dist")))
-        .nest_service("/src", ServeDir::new(root_path.join("src")))
-        .nest_service("/public", ServeDir::new(root_path.join("public")))
-        .nest_service("/examples", ServeDir::new(root_path.join("examples")))
-        .with_state(state);
+    let app = if let [state] = states.as_slice() {
+        if base != "/" {
+            // Send `/` to the app rather than a 404
+            app = app.route("/", get(move || async move { Redirect::temporary(&base) }));
+        }
+        app.fallback_service(serve_not_found.with_state(state.clone()))
+    } else {
+        let listing = workspace_listing(&states, None);
+        app = app.route(&base, get(move || async move { Html(listing) }));
+        if base != "/" {
+            let to = base.clone();
+            app = app.route("/", get(move || async move { Redirect::temporary(&to) }));
+        }
+        app.fallback_service(serve_workspace_not_found.with_state(Arc::new(states.clone())))
+    };
 
     // Try to bind to the requested port, fallback if busy
     let mut current_port = port;
@@ -106,7 +250,7 @@ pub async fn start_dev_server(port: u16, root: String) -> Result<()> {
         match tokio::net::TcpListener::bind(addr).await {
             Ok(listener) => break listener,
             Err(_) if current_port < port + 10 => {
-                println!("⚠️  Port {} is in use, trying {}...", current_port, current_port + 1);
+                warning!("⚠️  Port {} is in use, trying {}...", current_port, current_port + 1);
                 current_port += 1;
             }
             Err(e) => {
@@ -121,15 +265,53 @@ pub async fn start_dev_server(port: u16, root: String) -> Result<()> {
         }
     };
 
-    println!("🚀 Dev server starting on http://localhost:{}", current_port);
-    println!("📁 Serving from: {}", root);
-    println!("🔥 HMR enabled - changes will update instantly!\n");
+    if let [state] = states.as_slice() {
+        info!("🚀 Dev server starting on http://localhost:{}{}", current_port, state.base);
+        info!("📁 Serving from: {}", root);
+    } else {
+        info!("🚀 Dev server starting on http://localhost:{}", current_port);
+        for state in &states {
+            info!("📦 http://localhost:{}{} → {}", current_port, state.base, state.root.display());
+        }
+    }
+    info!("🔥 HMR enabled - changes will update instantly!\n");
 
     axum::serve(listener, app).await?;
 
     Ok(())
 }
 
+/// Routes of one project, all under its base path
+fn project_router(state: Arc<DevServerState>) -> Router {
+    let base = state.base.clone();
+
+    // Missing files under the static directories get the same 404 page as
+    // unmatched routes
+    let not_found = serve_not_found.with_state(state.clone());
+    let serve_dir = |dir: &str| ServeDir::new(state.root.join(dir)).not_found_service(not_found.clone());
+
+    let at = |path: &str| format!("{}{}", base, path);
+    let mut router = Router::new()
+        .route(&base, get(serve_index))
+        .route(&at(&state.hmr_path), get(ws_handler))
+        .route(&at("__velocity/hmr-client.js"), get(serve_hmr_client))
+        .route(&at(STYLES_PATH), get(serve_styles))
+        .nest_service(&at("dist"), serve_dir("dist"))
+        .nest_service(&at("src"), serve_dir("src"))
+        .nest_service(&at("public"), serve_dir("public"))
+        .nest_service(&at("examples"), serve_dir("examples"));
+
+    if base != "/" {
+        // `/app` is the app too
+        let to = base.clone();
+        router = router.route(base.trim_end_matches('/'), get(move || async move { Redirect::temporary(&to) }));
+    }
+
+    router
+        .layer(middleware::from_fn_with_state(state.clone(), serve_css_module))
+        .with_state(state)
+}
+
 /// Serve the index.html with HMR client injected
 async fn serve_index(State(state): State<Arc<DevServerState>>) -> impl IntoResponse {
     let index_path = state.root.join("index.html");
@@ -137,7 +319,22 @@ async fn serve_index(State(state): State<Arc<DevServerState>>) -> impl IntoRespo
     if index_path.exists() {
         let html = tokio::fs::read_to_string(&index_path)
             .await
-            .unwrap_or_else(|_| default_index());
+            .unwrap_or_else(|_| default_index(&state.base));
+
+        // Link the scoped styles if not already linked
+        let styles_url = state.styles_url();
+        let html = if html.contains(&styles_url) {
+            html
+        } else {
+            html.replace(
+                "</head>",
+                &format!(
+                    r#"<link rel="stylesheet" href="{}">
+</head>"#,
+                    styles_url
+                ),
+            )
+        };
 
         // Inject HMR client if not already present
         if html.contains("__velocity/hmr-client.js") {
@@ -145,60 +342,362 @@ async fn serve_index(State(state): State<Arc<DevServerState>>) -> impl IntoRespo
         } else {
             let injected = html.replace(
                 "</body>",
-                r#"<script type="module" src="/__velocity/hmr-client.js"></script>
+                &format!(
+                    r#"<script type="module" src="{}__velocity/hmr-client.js"></script>
 </body>"#,
+                    state.base
+                ),
             );
             Html(injected)
         }
     } else {
-        Html(default_index())
+        Html(default_index(&state.base))
     }
 }
 
 /// Default index.html if none exists
-fn default_index() -> String {
-    r#"<!DOCTYPE html>
+fn default_index(base: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
 <html lang="en">
 <head>
   <meta charset="UTF-8">
   <meta name="viewport" content="width=device-width, initial-scale=1.0">
   <title>Velocity App</title>
+  <link rel="stylesheet" href="{base}__velocity/styles.css">
 </head>
 <body>
   <div id="root"></div>
-  <script type="module" src="/dist/app.js"></script>
-  <script type="module" src="/__velocity/hmr-client.js"></script>
+  <script type="module" src="{base}dist/app.js"></script>
+  <script type="module" src="{base}__velocity/hmr-client.js"></script>
+</body>
+</html>"#,
+        base = base
+    )
+}
+
+/// Serve a 404 page listing the available routes
+async fn serve_not_found(
+    State(state): State<Arc<DevServerState>>,
+    OriginalUri(uri): OriginalUri,
+) -> impl IntoResponse {
+    not_found_page(&state, uri.path())
+}
+
+/// 404 for a path no workspace route matched: the page of the workspace
+/// whose prefix it has, or the list of workspaces
+async fn serve_workspace_not_found(
+    State(states): State<Arc<Vec<Arc<DevServerState>>>>,
+    OriginalUri(uri): OriginalUri,
+) -> impl IntoResponse {
+    let path = uri.path();
+    match states.iter().find(|state| path.starts_with(&state.base)) {
+        Some(state) => not_found_page(state, path),
+        None => (StatusCode::NOT_FOUND, Html(workspace_listing(&states, Some(path)))),
+    }
+}
+
+/// Page linking to each workspace; for a 404 when `missing` is set
+fn workspace_listing(states: &[Arc<DevServerState>], missing: Option<&str>) -> String {
+    let items: String = states
+        .iter()
+        .map(|state| {
+            format!(
+                "\n      <li><a href=\"{base}\"><code>{base}</code></a> - {root}</li>",
+                base = escape_html(&state.base),
+                root = escape_html(&state.root.display().to_string())
+            )
+        })
+        .collect();
+    let (title, heading, message) = match missing {
+        Some(path) => (
+            "404 - Velocity Dev Server",
+            "404 Not Found",
+            format!("<p>Nothing is served at <code>{}</code>.</p>", escape_html(path)),
+        ),
+        None => ("Velocity Workspaces", "Workspaces", String::new()),
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="UTF-8">
+  <title>{title}</title>
+  <style>
+    body {{ font-family: system-ui, -apple-system, sans-serif; background: #f7fafc; color: #1a202c; padding: 40px; }}
+    main {{ max-width: 640px; margin: 0 auto; background: white; padding: 32px; border-radius: 8px; box-shadow: 0 1px 3px rgba(0,0,0,0.1); }}
+    code {{ background: #edf2f7; padding: 2px 6px; border-radius: 4px; }}
+    li {{ margin: 6px 0; }}
+  </style>
+</head>
+<body>
+  <main>
+    <h1>{heading}</h1>
+    {message}
+    <p>Workspaces served here:</p>
+    <ul>{items}
+    </ul>
+  </main>
 </body>
 </html>"#
-        .to_string()
+    )
+}
+
+/// The 404 page for `path` in a project
+fn not_found_page(state: &DevServerState, path: &str) -> (StatusCode, Html<String>) {
+    let base = &state.base;
+
+    // A /dist/*.js request whose source exists in src/ just hasn't been built yet
+    let hint = path
+        .strip_prefix(base.trim_end_matches('/'))
+        .and_then(|path| uncompiled_source(&state.root, path))
+        .map(|source| {
+            format!(
+                r#"<p class="hint">💡 <code>{}</code> exists but hasn't been compiled. Run <code>velocity build</code> to generate it.</p>"#,
+                escape_html(&source)
+            )
+        })
+        .unwrap_or_default();
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="UTF-8">
+  <title>404 - Velocity Dev Server</title>
+  <style>
+    body {{ font-family: system-ui, -apple-system, sans-serif; background: #f7fafc; color: #1a202c; padding: 40px; }}
+    main {{ max-width: 640px; margin: 0 auto; background: white; padding: 32px; border-radius: 8px; box-shadow: 0 1px 3px rgba(0,0,0,0.1); }}
+    h1 {{ color: #ef4444; margin-top: 0; }}
+    code {{ background: #edf2f7; padding: 2px 6px; border-radius: 4px; }}
+    .hint {{ background: #fefcbf; padding: 12px; border-radius: 6px; }}
+    li {{ margin: 6px 0; }}
+  </style>
+</head>
+<body>
+  <main>
+    <h1>404 Not Found</h1>
+    <p>Nothing is served at <code>{}</code>.</p>
+    {}
+    <p>Available routes:</p>
+    <ul>
+      <li><a href="{base}"><code>{base}</code></a> - index.html</li>
+      <li><code>{base}src</code> - source files</li>
+      <li><code>{base}dist</code> - compiled output</li>
+      <li><code>{base}public</code> - static assets</li>
+    </ul>
+  </main>
+</body>
+</html>"#,
+        escape_html(path),
+        hint,
+        base = escape_html(base)
+    );
+
+    (StatusCode::NOT_FOUND, Html(html))
+}
+
+/// Find the source file in `src/` that would compile to a missing `/dist/*.js` path
+fn uncompiled_source(root: &Path, request_path: &str) -> Option<String> {
+    let relative = request_path.strip_prefix("/dist/")?.strip_suffix(".js")?;
+    if relative.split('/').any(|part| part == "..") {
+        return None;
+    }
+
+    ["tsx", "ts", "jsx", "js"].iter().find_map(|ext| {
+        let source = format!("src/{}.{}", relative, ext);
+        root.join(&source).is_file().then_some(source)
+    })
+}
+
+/// Escape text for inclusion in an HTML page
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 /// Serve the HMR client JavaScript
-async fn serve_hmr_client() -> impl IntoResponse {
-    let client_code = include_str!("hmr_client.js");
+async fn serve_hmr_client(State(state): State<Arc<DevServerState>>) -> impl IntoResponse {
+    let client_code = include_str!("hmr_client.js")
+        .replace(PROTOCOL_VERSION_PLACEHOLDER, &HMR_PROTOCOL_VERSION.to_string())
+        .replace(BASE_PLACEHOLDER, &serde_json::to_string(&state.base).unwrap())
+        .replace(HMR_PATH_PLACEHOLDER, &serde_json::to_string(&state.hmr_path).unwrap());
     (
-        [("content-type", "application/javascript")],
+        // Never cached, so a reload always picks up this server's protocol
+        [("content-type", "application/javascript"), ("cache-control", "no-cache")],
         client_code,
     )
 }
 
+/// Serve the scoped CSS of every source as one stylesheet
+async fn serve_styles(State(state): State<Arc<DevServerState>>) -> impl IntoResponse {
+    let stylesheet = crate::stylesheet(&state.styles.lock().unwrap());
+    ([("content-type", "text/css"), ("cache-control", "no-cache")], stylesheet)
+}
+
+/// Serve `X.module.css.js` under `dist/` or `src/` from `src/X.module.css`
+/// as it is now, the module `velocity build` would write to dist/, rather
+/// than a copy left there by an earlier build
+async fn serve_css_module(State(state): State<Arc<DevServerState>>, request: Request, next: Next) -> Response {
+    let source = request
+        .uri()
+        .path()
+        .strip_prefix(&state.base)
+        .and_then(|path| path.strip_prefix("dist/").or_else(|| path.strip_prefix("src/")))
+        .and_then(|path| path.strip_suffix(".js"))
+        .filter(|path| path.ends_with(css::CSS_MODULE_SUFFIX) && !path.split('/').any(|part| part == ".."))
+        .map(|path| state.root.join("src").join(path));
+
+    match source.map(|path| scope_css_module(&state, &path)) {
+        Some(Ok(scoped)) => (
+            [("content-type", "application/javascript"), ("cache-control", "no-cache")],
+            scoped.to_module(),
+        )
+            .into_response(),
+        _ => next.run(request).await,
+    }
+}
+
+/// Scope the CSS module at `path` under the name `velocity build` gives it
+fn scope_css_module(state: &DevServerState, path: &Path) -> std::io::Result<css::ScopedCss> {
+    let source = fs::read_to_string(path)?;
+    Ok(css::scope_css(&source, &crate::project_path(&state.root, path)))
+}
+
+/// Compile every source under `src/` once for its scoped CSS, so the first
+/// page load gets the styles `velocity build` would collect. Errors are
+/// left for the watcher to report when the file is next saved.
+fn collect_styles(state: &DevServerState) -> Result<()> {
+    let compiler = Compiler::new(state.compiler_options.clone());
+    let ignore = IgnoreList::load(&state.root)?;
+    for entry in walkdir::WalkDir::new(state.root.join("src"))
+        .follow_links(true)
+        .into_iter()
+        .filter_entry(|e| !ignore.is_ignored(e.path()))
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        let css = if crate::is_css_module(path) {
+            scope_css_module(state, path).ok().map(|scoped| scoped.css)
+        } else if is_source(path) {
+            fs::read_to_string(path)
+                .ok()
+                .and_then(|source| compiler.compile_full(&source, &crate::project_path(&state.root, path)).ok())
+                .and_then(|output| output.css)
+        } else {
+            continue;
+        };
+        state.set_styles(path, css);
+    }
+    Ok(())
+}
+
+/// Whether `path` is a module the dev server compiles
+fn is_source(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|ext| ext == "tsx" || ext == "ts" || ext == "jsx" || ext == "js")
+}
+
+/// Canonical form of a `--base` path: leading and trailing slash, so
+/// `app`, `/app` and `/app/` all become `/app/`
+fn normalize_base(base: &str) -> Result<String> {
+    let trimmed = base.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        return Ok("/".to_string());
+    }
+    if trimmed.contains(['?', '#', '*', ':']) || trimmed.split('/').any(|s| s.is_empty() || s == "." || s == "..") {
+        anyhow::bail!("Invalid base path '{}': expected a URL path like /app/", base);
+    }
+    Ok(format!("/{}/", trimmed))
+}
+
+/// Canonical form of a `--hmr-path`: relative to the base path, without
+/// leading or trailing slashes, and clear of the other routes
+fn normalize_hmr_path(path: &str) -> Result<String> {
+    let trimmed = path.trim().trim_matches('/');
+    if trimmed.is_empty()
+        || trimmed.contains(['?', '#', '*', ':'])
+        || trimmed.split('/').any(|s| s.is_empty() || s == "." || s == "..")
+    {
+        anyhow::bail!("Invalid HMR path '{}': expected a URL path like __hmr", path);
+    }
+    let first = trimmed.split('/').next().unwrap_or_default();
+    if RESERVED_SEGMENTS.contains(&first) {
+        anyhow::bail!("Invalid HMR path '{}': {}/ is already served by the dev server", path, first);
+    }
+    Ok(trimmed.to_string())
+}
+
+/// The URL a client reached the HMR WebSocket at, as seen through any
+/// proxy: `wss` when `X-Forwarded-Proto` says the page was served over
+/// HTTPS, and the `X-Forwarded-Host` or `Host` it asked for
+fn public_hmr_url(headers: &HeaderMap, base: &str, hmr_path: &str) -> String {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            // Proxies in a chain append theirs; the first is the client's
+            .and_then(|value| value.split(',').next())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let scheme = match header("x-forwarded-proto") {
+        Some(proto) if proto.eq_ignore_ascii_case("https") || proto.eq_ignore_ascii_case("wss") => "wss",
+        _ => "ws",
+    };
+    let host = header("x-forwarded-host").or_else(|| header("host")).unwrap_or("localhost");
+    format!("{}://{}{}{}", scheme, host, base, hmr_path)
+}
+
+/// Query string of the HMR WebSocket URL (`/__hmr?protocol=1`)
+#[derive(Debug, serde::Deserialize)]
+struct HmrQuery {
+    /// Protocol version of the connecting client; clients from before
+    /// versioning don't send one
+    protocol: Option<u32>,
+}
+
 /// WebSocket handler for HMR
 async fn ws_handler(
     ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(query): Query<HmrQuery>,
     State(state): State<Arc<DevServerState>>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    verbose!("HMR client connected at {}", public_hmr_url(&headers, &state.base, &state.hmr_path));
+    ws.on_upgrade(move |socket| handle_socket(socket, state, query.protocol))
 }
 
 /// Handle WebSocket connection
-async fn handle_socket(socket: WebSocket, state: Arc<DevServerState>) {
+async fn handle_socket(socket: WebSocket, state: Arc<DevServerState>, client_protocol: Option<u32>) {
     let (mut sender, mut receiver) = socket.split();
 
+    // A client speaking another protocol can't apply our updates; reloading
+    // the page fetches the client this server serves. Every client version
+    // understands `full-reload`.
+    if client_protocol != Some(HMR_PROTOCOL_VERSION) {
+        let client = client_protocol.map_or("unversioned".to_string(), |v| format!("v{}", v));
+        warning!("⚠️  HMR client protocol {} doesn't match v{}, reloading it", client, HMR_PROTOCOL_VERSION);
+        let reload = HMRMessage::FullReload {
+            reason: format!(
+                "HMR protocol mismatch (client {}, server v{})",
+                client, HMR_PROTOCOL_VERSION
+            ),
+        };
+        let _ = sender.send(Message::Text(serde_json::to_string(&reload).unwrap())).await;
+        return;
+    }
+
     // Subscribe to HMR updates
     let mut rx = state.hmr_tx.subscribe();
 
     // Send connected message
-    let connected_msg = serde_json::to_string(&HMRMessage::Connected).unwrap();
+    let connected_msg = serde_json::to_string(&HMRMessage::Connected {
+        protocol_version: HMR_PROTOCOL_VERSION,
+    })
+    .unwrap();
     let _ = sender.send(Message::Text(connected_msg)).await;
 
     // Spawn task to forward HMR updates to this client
@@ -216,7 +715,7 @@ async fn handle_socket(socket: WebSocket, state: Arc<DevServerState>) {
         while let Some(Ok(msg)) = receiver.next().await {
             if let Message::Text(text) = msg {
                 // Handle client messages if needed
-                println!("Client message: {}", text);
+                verbose!("Client message: {}", text);
             }
         }
     });
@@ -228,61 +727,74 @@ async fn handle_socket(socket: WebSocket, state: Arc<DevServerState>) {
     }
 }
 
+/// How long to wait for a burst of watcher events to settle before recompiling
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(50);
+
 /// Start file watcher
 async fn start_file_watcher(state: Arc<DevServerState>, root: PathBuf) -> Result<()> {
-    use std::sync::mpsc::channel;
-
-    let (tx, rx) = channel();
-    let mut watcher = notify::recommended_watcher(tx)?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    })?;
 
     // Watch src directory
     let src_dir = root.join("src");
     if src_dir.exists() {
         watcher.watch(&src_dir, RecursiveMode::Recursive)?;
-        println!("👀 Watching {}", src_dir.display());
+        info!("👀 Watching {}", src_dir.display());
     }
 
     // Watch index.html in root
     let index_html = root.join("index.html");
     if index_html.exists() {
         watcher.watch(&index_html, RecursiveMode::NonRecursive)?;
-        println!("👀 Watching index.html");
+        info!("👀 Watching index.html");
     }
 
+    // Watch public directory for stylesheets
+    let public_dir = root.join("public");
+    if public_dir.exists() {
+        watcher.watch(&public_dir, RecursiveMode::Recursive)?;
+        info!("👀 Watching {}", public_dir.display());
+    }
+
+    let ignore = IgnoreList::load(&root)?;
+
+    // Paths changed since the last flush; a single save often emits several
+    // Modify events, so they are coalesced until the watcher goes quiet
+    let mut pending: HashSet<PathBuf> = HashSet::new();
+
     // Watch for file changes
     loop {
-        match rx.recv() {
-            Ok(Ok(event)) => {
-                match event.kind {
-                    EventKind::Modify(_) | EventKind::Create(_) => {
-                        for path in event.paths {
-                            // Check if it's index.html
-                            if path.file_name().and_then(|n| n.to_str()) == Some("index.html") {
-                                use std::time::Instant;
-                                let start = Instant::now();
-
-                                state.broadcast_update(HMRMessage::FullReload {
-                                    reason: "index.html updated".to_string(),
-                                });
-
-                                let elapsed = start.elapsed();
-                                println!(
-                                    "🔄 index.html → full reload in {:.2}ms",
-                                    elapsed.as_secs_f64() * 1000.0
-                                );
-                            } else if let Some(ext) = path.extension() {
-                                if ext == "tsx" || ext == "ts" || ext == "jsx" || ext == "js" {
-                                    handle_file_change(&state, &path).await;
-                                }
-                            }
+        let next = if pending.is_empty() {
+            rx.recv().await
+        } else {
+            tokio::select! {
+                event = rx.recv() => event,
+                _ = tokio::time::sleep(WATCH_DEBOUNCE) => {
+                    for path in pending.drain() {
+                        handle_path_change(&state, &path).await;
+                    }
+                    continue;
+                }
+            }
+        };
+
+        match next {
+            Some(Ok(event)) => {
+                if let EventKind::Modify(_) | EventKind::Create(_) = event.kind {
+                    for path in event.paths {
+                        if ignore.is_ignored(&path) {
+                            verbose!("Ignored change to {}", path.display());
+                        } else {
+                            pending.insert(path);
                         }
                     }
-                    _ => {}
                 }
             }
-            Ok(Err(e)) => eprintln!("Watch error: {:?}", e),
-            Err(e) => {
-                eprintln!("Channel error: {:?}", e);
+            Some(Err(e)) => eprintln!("Watch error: {:?}", e),
+            None => {
+                eprintln!("Watch channel closed");
                 break;
             }
         }
@@ -291,12 +803,72 @@ async fn start_file_watcher(state: Arc<DevServerState>, root: PathBuf) -> Result
     Ok(())
 }
 
+/// Dispatch a single changed path: reload for index.html, swap stylesheets,
+/// recompile for sources
+async fn handle_path_change(state: &DevServerState, path: &Path) {
+    // Check if it's index.html
+    if path.file_name().and_then(|n| n.to_str()) == Some("index.html") {
+        let start = Instant::now();
+
+        state.broadcast_update(HMRMessage::FullReload {
+            reason: "index.html updated".to_string(),
+        });
+
+        info!("🔄 index.html → full reload in {}", log::ms(start.elapsed()));
+    } else if is_source(path) {
+        handle_file_change(state, path).await;
+    } else if crate::is_css_module(path) && path.starts_with(state.root.join("src")) {
+        handle_css_module_change(state, path);
+    } else if path.extension().is_some_and(|ext| ext == "css") {
+        handle_css_change(state, path);
+    }
+}
+
+/// Handle stylesheet change - tell clients to re-fetch it in place
+fn handle_css_change(state: &DevServerState, path: &Path) {
+    let url_path = css_url_path(&state.root, &state.base, path);
+
+    state.broadcast_update(HMRMessage::CssUpdate {
+        path: url_path.clone(),
+    });
+    info!("🎨 {} → CSS hot update", url_path);
+}
+
+/// Handle CSS module change - rescope it and swap the scoped stylesheet.
+/// Its `.module.css.js` is generated per request, so a class it adds reaches
+/// the page's code on the next load.
+fn handle_css_module_change(state: &DevServerState, path: &Path) {
+    let module_path = path.strip_prefix(&state.root).unwrap_or(path).display().to_string();
+    let scoped = match scope_css_module(state, path) {
+        Ok(scoped) => scoped,
+        Err(e) => {
+            eprintln!("❌ Failed to read file: {}", e);
+            return;
+        }
+    };
+
+    if state.set_styles(path, Some(scoped.css)) {
+        state.broadcast_update(HMRMessage::CssUpdate {
+            path: state.styles_url(),
+        });
+        info!("🎨 {} → CSS hot update", module_path);
+    }
+}
+
+/// URL the dev server serves `path` from, e.g. `/public/style.css`
+fn css_url_path(root: &Path, base: &str, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let segments: Vec<_> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    format!("{}{}", base, segments.join("/"))
+}
+
 /// Handle file change - compile and broadcast update
 async fn handle_file_change(state: &DevServerState, path: &Path) {
-    use std::time::Instant;
-
     let start = Instant::now();
-    println!("🔄 File changed: {}", path.display());
+    info!("🔄 File changed: {}", path.display());
 
     // Read file
     let source = match tokio::fs::read_to_string(path).await {
@@ -306,12 +878,14 @@ async fn handle_file_change(state: &DevServerState, path: &Path) {
             return;
         }
     };
+    verbose!("Read {} bytes in {}", source.len(), log::ms(start.elapsed()));
 
     // Compile
     let compile_start = Instant::now();
     let compiler = Compiler::new(state.compiler_options.clone());
-    match compiler.compile(&source, path.to_str().unwrap()) {
-        Ok(code) => {
+    match compiler.compile_full(&source, &crate::project_path(&state.root, path)) {
+        Ok(output) => {
+            let code = output.code;
             let compile_time = compile_start.elapsed();
 
             // Get module path relative to root
@@ -321,6 +895,16 @@ async fn handle_file_change(state: &DevServerState, path: &Path) {
                 .to_string_lossy()
                 .to_string();
 
+            // A file saved before anything was written to it would replace
+            // the running module with nothing
+            if code.trim().is_empty() {
+                warning!("⚠️  {} has no statements yet, keeping the current module", module_path);
+                return;
+            }
+
+            // The page reloads for the update, and gets the new styles then
+            state.set_styles(path, output.css);
+
             // Broadcast update
             // TODO: Implement dependency tracking to populate dependents
             state.broadcast_update(HMRMessage::Update {
@@ -334,12 +918,12 @@ async fn handle_file_change(state: &DevServerState, path: &Path) {
             });
 
             let total_time = start.elapsed();
-            println!(
-                "✅ {} → compiled in {:.2}ms, HMR in {:.2}ms (total: {:.2}ms)",
+            info!(
+                "✅ {} → compiled in {}, HMR in {} (total: {})",
                 module_path,
-                compile_time.as_secs_f64() * 1000.0,
-                (total_time - compile_time).as_secs_f64() * 1000.0,
-                total_time.as_secs_f64() * 1000.0
+                log::ms(compile_time),
+                log::ms(total_time - compile_time),
+                log::ms(total_time)
             );
         }
         Err(e) => {
@@ -350,3 +934,74 @@ async fn handle_file_change(state: &DevServerState, path: &Path) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_base() {
+        assert_eq!(normalize_base("/").unwrap(), "/");
+        assert_eq!(normalize_base("").unwrap(), "/");
+        assert_eq!(normalize_base("app").unwrap(), "/app/");
+        assert_eq!(normalize_base("/app").unwrap(), "/app/");
+        assert_eq!(normalize_base("/tools/app/").unwrap(), "/tools/app/");
+        assert!(normalize_base("/app//admin").is_err());
+        assert!(normalize_base("/app/../admin").is_err());
+        assert!(normalize_base("/app?x=1").is_err());
+    }
+
+    #[test]
+    fn test_normalize_hmr_path() {
+        assert_eq!(normalize_hmr_path("__hmr").unwrap(), "__hmr");
+        assert_eq!(normalize_hmr_path("/_ws/hmr/").unwrap(), "_ws/hmr");
+        assert!(normalize_hmr_path("/").is_err());
+        assert!(normalize_hmr_path("ws/../hmr").is_err());
+        assert!(normalize_hmr_path("src/hmr").is_err());
+        assert!(normalize_hmr_path("__velocity").is_err());
+    }
+
+    #[test]
+    fn test_public_hmr_url_honors_forwarded_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("host", "localhost:3000".parse().unwrap());
+        assert_eq!(public_hmr_url(&headers, "/", "__hmr"), "ws://localhost:3000/__hmr");
+
+        headers.insert("x-forwarded-proto", "https, http".parse().unwrap());
+        headers.insert("x-forwarded-host", "abc.ngrok.app".parse().unwrap());
+        assert_eq!(public_hmr_url(&headers, "/app/", "_ws/hmr"), "wss://abc.ngrok.app/app/_ws/hmr");
+    }
+
+    #[test]
+    fn test_collect_styles() {
+        let root = std::env::temp_dir().join(format!("velocity-dev-styles-{}", std::process::id()));
+        fs::create_dir_all(root.join("src/ui")).unwrap();
+        fs::write(root.join("src/ui/Card.module.css"), ".card { padding: 4px; }").unwrap();
+        fs::write(root.join("src/App.tsx"), "const s = css`.app { margin: 0; }`;\nexport const App = () => <main class=\"app\" />;").unwrap();
+        fs::write(root.join("src/util.ts"), "export const x = 1;").unwrap();
+
+        let state = DevServerState::new(root.clone(), "/".to_string(), DEFAULT_HMR_PATH.to_string(), dev_compiler_options());
+        collect_styles(&state).unwrap();
+
+        // Scoped under the same names as in `velocity build`
+        let app = css::scoped_class_name("app", "src/App.tsx");
+        let card = css::scoped_class_name("card", "src/ui/Card.module.css");
+        {
+            let styles = state.styles.lock().unwrap();
+            let sources: Vec<_> = styles.keys().map(|source| source.to_string_lossy().replace('\\', "/")).collect();
+            assert_eq!(sources, ["App.tsx", "ui/Card.module.css"]);
+            assert!(styles[Path::new("App.tsx")].contains(&format!(".{} {{", app)));
+            assert!(styles[Path::new("ui/Card.module.css")].contains(&format!(".{} {{", card)));
+        }
+
+        // Only an actual change updates the stylesheet
+        let module = state.root.join("src/ui/Card.module.css");
+        let scoped = scope_css_module(&state, &module).unwrap();
+        assert!(scoped.to_module().contains(&card));
+        assert!(!state.set_styles(&module, Some(scoped.css)));
+        assert!(state.set_styles(&module, None));
+        assert_eq!(state.styles.lock().unwrap().len(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
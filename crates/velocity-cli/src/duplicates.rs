@@ -0,0 +1,189 @@
+//! Duplicate module detection for `velocity analyze`
+//!
+//! Two copies of one dependency, or a copy-pasted module, ship the same
+//! bytes twice. Outputs with identical content are grouped by hash; other
+//! pairs are compared by the overlap of their token sequences, which survives
+//! renamed strings, reordered functions and reformatting.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Fraction of shared token shingles above which two modules count as near-duplicates
+const SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/// Consecutive tokens hashed together; long enough that unrelated code
+/// rarely shares a shingle
+const SHINGLE_SIZE: usize = 5;
+
+/// Modules smaller than this aren't compared for similarity: tiny
+/// re-export files all look alike and dedupe would save nothing
+const MIN_SIMILAR_SIZE: usize = 256;
+
+/// Outputs with byte-identical content
+#[derive(Debug, Serialize)]
+pub struct DuplicateGroup {
+    pub files: Vec<String>,
+    /// Size of one copy in bytes
+    pub size: u64,
+    /// Bytes saved by keeping a single copy
+    pub wasted_bytes: u64,
+}
+
+/// Two outputs with mostly the same content
+#[derive(Debug, Serialize)]
+pub struct SimilarPair {
+    pub files: [String; 2],
+    /// Shared fraction of their token shingles, 0–1
+    pub similarity: f64,
+    /// Estimated bytes saved by sharing the common code
+    pub wasted_bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DuplicateReport {
+    pub identical: Vec<DuplicateGroup>,
+    pub similar: Vec<SimilarPair>,
+    /// Sum of `wasted_bytes` over every group and pair
+    pub wasted_bytes: u64,
+}
+
+/// Find identical and near-identical modules among `(path, content)` outputs
+pub fn find_duplicates(modules: &[(String, String)]) -> DuplicateReport {
+    let mut by_hash: BTreeMap<Vec<u8>, Vec<&(String, String)>> = BTreeMap::new();
+    for module in modules {
+        by_hash.entry(Sha256::digest(module.1.as_bytes()).to_vec()).or_default().push(module);
+    }
+
+    let mut report = DuplicateReport::default();
+    // One representative per distinct content for the similarity pass
+    let mut distinct = Vec::new();
+    for group in by_hash.into_values() {
+        distinct.push(group[0]);
+        if group.len() < 2 {
+            continue;
+        }
+        let size = group[0].1.len() as u64;
+        let mut files: Vec<String> = group.iter().map(|(path, _)| path.clone()).collect();
+        files.sort();
+        report.identical.push(DuplicateGroup {
+            files,
+            size,
+            wasted_bytes: size * (group.len() as u64 - 1),
+        });
+    }
+
+    let shingled: Vec<(&String, usize, HashSet<u64>)> = distinct
+        .into_iter()
+        .filter(|(_, content)| content.len() >= MIN_SIMILAR_SIZE)
+        .map(|(path, content)| (path, content.len(), shingles(content)))
+        .collect();
+
+    for (i, (a_path, a_size, a)) in shingled.iter().enumerate() {
+        for (b_path, b_size, b) in &shingled[i + 1..] {
+            let similarity = jaccard(a, b);
+            if similarity < SIMILARITY_THRESHOLD {
+                continue;
+            }
+            let mut files = [(*a_path).clone(), (*b_path).clone()];
+            files.sort();
+            report.similar.push(SimilarPair {
+                files,
+                similarity,
+                wasted_bytes: (similarity * (*a_size).min(*b_size) as f64) as u64,
+            });
+        }
+    }
+
+    report.identical.sort_by_key(|group| Reverse(group.wasted_bytes));
+    report.similar.sort_by_key(|pair| Reverse(pair.wasted_bytes));
+    report.wasted_bytes = report.identical.iter().map(|g| g.wasted_bytes).sum::<u64>()
+        + report.similar.iter().map(|p| p.wasted_bytes).sum::<u64>();
+    report
+}
+
+/// Split code into identifier/number runs and single punctuation characters
+fn tokens(code: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in code.char_indices() {
+        let word = c.is_alphanumeric() || c == '_' || c == '$';
+        match (word, start) {
+            (true, None) => start = Some(index),
+            (true, Some(_)) => {}
+            (false, Some(begin)) => {
+                tokens.push(&code[begin..index]);
+                start = None;
+            }
+            (false, None) => {}
+        }
+        if !word && !c.is_whitespace() {
+            tokens.push(&code[index..index + c.len_utf8()]);
+        }
+    }
+    if let Some(begin) = start {
+        tokens.push(&code[begin..]);
+    }
+    tokens
+}
+
+/// Hashes of every run of [`SHINGLE_SIZE`] consecutive tokens
+fn shingles(code: &str) -> HashSet<u64> {
+    tokens(code)
+        .windows(SHINGLE_SIZE)
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_duplicates() {
+        let utils: String = (0..20)
+            .map(|i| format!("export function helper{i}(value) {{ return value * {i} + offset; }}\n"))
+            .collect();
+        let patched = utils.replace("return value * 7 + offset", "return value * 7 - offset");
+        let unrelated: String = (0..20)
+            .map(|i| format!("const [item{i}, setItem{i}] = createSignal(\"label {i}\");\n"))
+            .collect();
+
+        let modules = vec![
+            ("vendor/a/utils.js".to_string(), utils.clone()),
+            ("vendor/b/utils.js".to_string(), utils.clone()),
+            ("lib/utils-copy.js".to_string(), patched.clone()),
+            ("app.js".to_string(), unrelated),
+            ("tiny-1.js".to_string(), "export * from './x';".to_string()),
+            ("tiny-2.js".to_string(), "export * from './y';".to_string()),
+        ];
+
+        let report = find_duplicates(&modules);
+
+        assert_eq!(report.identical.len(), 1);
+        assert_eq!(report.identical[0].files, ["vendor/a/utils.js", "vendor/b/utils.js"]);
+        assert_eq!(report.identical[0].wasted_bytes, utils.len() as u64);
+
+        assert_eq!(report.similar.len(), 1);
+        let pair = &report.similar[0];
+        assert!(pair.files.contains(&"lib/utils-copy.js".to_string()));
+        assert!(pair.similarity > SIMILARITY_THRESHOLD && pair.similarity < 1.0);
+
+        assert_eq!(report.wasted_bytes, utils.len() as u64 + pair.wasted_bytes);
+    }
+}
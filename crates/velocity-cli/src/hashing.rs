@@ -0,0 +1,284 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use velocity_compiler::imports;
+use crate::manifest::{content_hash, BuildManifest};
+
+/// Hex digits of the content hash kept in file names
+pub(crate) const HASH_LENGTH: usize = 8;
+
+/// Rename every output in `manifest` (and the extracted stylesheet) to
+/// `name.<hash>.js` and point imports, source maps and `index.html` at the
+/// new names.
+///
+/// A module's hash covers its own compiled code and the hashes of the modules
+/// it imports, so changing a dependency also renames every importer. Imports
+/// inside a cycle can't be part of each other's hash and are left out.
+pub fn hash_outputs(root: &Path, out_dir: &Path, manifest: &mut BuildManifest) -> anyhow::Result<()> {
+    // Logical output path → compiled code
+    let mut code: HashMap<String, String> = HashMap::new();
+    for entry in &manifest.outputs {
+        let path = out_dir.join(&entry.output);
+        let source = fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        code.insert(entry.output.clone(), source);
+    }
+
+    // Logical output path → logical paths it imports
+    let mut dependencies: HashMap<String, Vec<String>> = HashMap::new();
+    for (logical, source) in &code {
+        let deps = imports::find_imports(source, logical)?
+            .into_iter()
+            .filter_map(|import| resolve_import(logical, &import.specifier, &code))
+            .collect();
+        dependencies.insert(logical.clone(), deps);
+    }
+
+    let mut hashes = HashMap::new();
+    let mut logicals: Vec<&String> = code.keys().collect();
+    logicals.sort();
+    for logical in logicals {
+        compute_hash(logical, &code, &dependencies, &mut hashes, &mut HashSet::new());
+    }
+
+    let mut hashed: BTreeMap<String, String> = hashes
+        .iter()
+        .map(|(logical, hash)| (logical.clone(), hashed_name(logical, hash)))
+        .collect();
+
+    // Write the hashed files, with imports and source map URLs updated
+    for entry in &mut manifest.outputs {
+        let logical = entry.output.clone();
+        let target = &hashed[&logical];
+
+        let mut source = imports::rewrite_imports(&code[&logical], &logical, |specifier| {
+            let dependency = resolve_import(&logical, specifier, &code)?;
+            Some(relative_specifier(&logical, &hashed[&dependency]))
+        })?;
+
+        if let Some(map) = entry.source_map.take() {
+            let hashed_map = format!("{}.map", target);
+            fs::rename(out_dir.join(&map), out_dir.join(&hashed_map))?;
+            source = source.replace(
+                &format!("//# sourceMappingURL={}", file_name(&map)),
+                &format!("//# sourceMappingURL={}", file_name(&hashed_map)),
+            );
+            entry.source_map = Some(hashed_map);
+        }
+
+        fs::write(out_dir.join(target), &source)?;
+        fs::remove_file(out_dir.join(&logical))?;
+
+        entry.output = target.clone();
+        entry.size = source.len() as u64;
+        entry.hash = content_hash(source.as_bytes());
+    }
+
+    // The extracted stylesheet has no imports; its name only covers its content
+    if let Some(styles) = manifest.styles.take() {
+        let css = fs::read(out_dir.join(&styles))?;
+        let target = hashed_name(&styles, &content_hash(&css)[..HASH_LENGTH]);
+        fs::rename(out_dir.join(&styles), out_dir.join(&target))?;
+        hashed.insert(styles, target.clone());
+        manifest.styles = Some(target);
+    }
+
+    rewrite_index_html(root, out_dir, &hashed)?;
+    manifest.hashed = hashed;
+
+    Ok(())
+}
+
+/// Hash a module's code together with the hashes of its (non-cyclic) imports
+fn compute_hash(
+    logical: &str,
+    code: &HashMap<String, String>,
+    dependencies: &HashMap<String, Vec<String>>,
+    hashes: &mut HashMap<String, String>,
+    visiting: &mut HashSet<String>,
+) -> Option<String> {
+    if let Some(hash) = hashes.get(logical) {
+        return Some(hash.clone());
+    }
+    if !visiting.insert(logical.to_string()) {
+        return None; // Import cycle
+    }
+
+    let mut input = code[logical].clone();
+    for dependency in &dependencies[logical] {
+        if let Some(hash) = compute_hash(dependency, code, dependencies, hashes, visiting) {
+            input.push_str(&hash);
+        }
+    }
+
+    visiting.remove(logical);
+    let hash = content_hash(input.as_bytes())[..HASH_LENGTH].to_string();
+    hashes.insert(logical.to_string(), hash.clone());
+    Some(hash)
+}
+
+/// Resolve a relative import to a logical output path, e.g. `./Button` from
+/// `pages/home.js` → `pages/Button.js`
+pub(crate) fn resolve_import(importer: &str, specifier: &str, outputs: &HashMap<String, String>) -> Option<String> {
+    if !specifier.starts_with("./") && !specifier.starts_with("../") {
+        return None;
+    }
+
+    let dir = importer.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+    let joined = normalize(&format!("{}/{}", dir, specifier))?;
+
+    // Sources may be imported by their TypeScript/JSX name or without an extension
+    let stem = [".tsx", ".ts", ".jsx"]
+        .iter()
+        .find_map(|ext| joined.strip_suffix(ext))
+        .unwrap_or(&joined);
+    [
+        joined.clone(),
+        format!("{}.js", stem),
+        format!("{}/index.js", joined),
+    ]
+    .into_iter()
+    .find(|candidate| outputs.contains_key(candidate))
+}
+
+/// Collapse `.` and `..` segments; `None` if the path escapes the output dir
+fn normalize(path: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// `pages/home.js` + hash → `pages/home.1a2b3c4d.js`
+pub(crate) fn hashed_name(logical: &str, hash: &str) -> String {
+    let name_start = logical.rfind('/').map(|i| i + 1).unwrap_or(0);
+    match logical[name_start..].rfind('.') {
+        Some(dot) => {
+            let (stem, ext) = logical.split_at(name_start + dot);
+            format!("{}.{}{}", stem, hash, ext)
+        }
+        None => format!("{}.{}", logical, hash),
+    }
+}
+
+/// Specifier that imports `target` from `importer` (both logical paths)
+fn relative_specifier(importer: &str, target: &str) -> String {
+    let from: Vec<&str> = importer.split('/').collect();
+    let from = &from[..from.len() - 1];
+    let to: Vec<&str> = target.split('/').collect();
+
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<&str> = vec![".."; from.len() - common];
+    parts.extend(&to[common..]);
+
+    let path = parts.join("/");
+    if path.starts_with("..") {
+        path
+    } else {
+        format!("./{}", path)
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Write `index.html` into the output directory with script/link URLs that
+/// point into it swapped for their hashed names. The source file is untouched.
+fn rewrite_index_html(root: &Path, out_dir: &Path, hashed: &BTreeMap<String, String>) -> anyhow::Result<()> {
+    let Ok(html) = fs::read_to_string(root.join("index.html")) else {
+        return Ok(());
+    };
+
+    let out_name = out_dir_url(root, out_dir);
+
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html.as_str();
+
+    while let Some(index) = find_url_attribute(rest) {
+        let (before, after) = rest.split_at(index);
+        output.push_str(before);
+
+        let quote = after.chars().next().unwrap_or('"');
+        let Some(end) = after[1..].find(quote) else {
+            output.push_str(after);
+            rest = "";
+            break;
+        };
+        let url = &after[1..1 + end];
+
+        // `/dist/index.js`, `./dist/index.js` and `dist/index.js` all name the output
+        let path = url.trim_start_matches("./").trim_start_matches('/');
+        let prefix = &url[..url.len() - path.len()];
+        let replaced = path
+            .strip_prefix(&format!("{}/", out_name))
+            .and_then(|logical| hashed.get(logical))
+            .map(|target| format!("{}{}/{}", prefix, out_name, target));
+
+        output.push(quote);
+        output.push_str(replaced.as_deref().unwrap_or(url));
+        output.push(quote);
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+
+    fs::write(out_dir.join("index.html"), output)?;
+    Ok(())
+}
+
+/// The output directory as a URL path segment relative to the root (`dist`)
+pub(crate) fn out_dir_url(root: &Path, out_dir: &Path) -> String {
+    out_dir
+        .strip_prefix(root)
+        .unwrap_or(out_dir)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Offset of the opening quote of the next `src=`/`href=` attribute value
+pub(crate) fn find_url_attribute(html: &str) -> Option<usize> {
+    ["src=", "href="]
+        .iter()
+        .filter_map(|attr| {
+            html.match_indices(attr).find_map(|(index, _)| {
+                let value = index + attr.len();
+                matches!(html[value..].chars().next(), Some('"' | '\'')).then_some(value)
+            })
+        })
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_import() {
+        let outputs: HashMap<String, String> = ["index.js", "components/Button.js", "lib/index.js"]
+            .iter()
+            .map(|path| (path.to_string(), String::new()))
+            .collect();
+
+        assert_eq!(resolve_import("index.js", "./components/Button", &outputs).as_deref(), Some("components/Button.js"));
+        assert_eq!(resolve_import("components/Button.js", "../lib", &outputs).as_deref(), Some("lib/index.js"));
+        assert_eq!(resolve_import("index.js", "./components/Button.tsx", &outputs).as_deref(), Some("components/Button.js"));
+        assert_eq!(resolve_import("index.js", "velocity-runtime", &outputs), None);
+        assert_eq!(resolve_import("index.js", "../outside", &outputs), None);
+    }
+
+    #[test]
+    fn test_relative_specifier() {
+        assert_eq!(relative_specifier("index.js", "components/Button.1234.js"), "./components/Button.1234.js");
+        assert_eq!(relative_specifier("components/Button.js", "lib/index.1234.js"), "../lib/index.1234.js");
+        assert_eq!(relative_specifier("a/b.js", "a/c.1234.js"), "./c.1234.js");
+    }
+}
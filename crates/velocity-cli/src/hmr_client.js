@@ -3,6 +3,35 @@
 
 console.log('🔥 Velocity HMR Client loaded');
 
+// Filled in by the dev server; must match the server's protocol version
+const PROTOCOL_VERSION = __VELOCITY_HMR_PROTOCOL__;
+
+// Filled in by the dev server; the public base path, e.g. '/' or '/app/'
+const BASE = __VELOCITY_BASE__;
+
+// Filled in by the dev server; the WebSocket path under BASE, e.g. '__hmr'
+const HMR_PATH = __VELOCITY_HMR_PATH__;
+
+// sessionStorage item signal values are saved in across a hot update; the
+// runtime's createSignal reads it back
+const SIGNAL_STATE_KEY = 'velocity-hmr-state';
+
+// Whether a value survives a JSON round trip unchanged: primitives, and
+// arrays and plain objects of them
+function isPlainData(value) {
+  if (value === null || ['string', 'number', 'boolean'].includes(typeof value)) {
+    return typeof value !== 'number' || Number.isFinite(value);
+  }
+  if (Array.isArray(value)) {
+    return value.every(isPlainData);
+  }
+  if (typeof value === 'object') {
+    const proto = Object.getPrototypeOf(value);
+    return (proto === Object.prototype || proto === null) && Object.values(value).every(isPlainData);
+  }
+  return false;
+}
+
 class VelocityHMR {
   constructor() {
     this.ws = null;
@@ -13,7 +42,7 @@ class VelocityHMR {
 
   connect() {
     const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
-    const wsUrl = `${protocol}//${window.location.host}/__hmr`;
+    const wsUrl = `${protocol}//${window.location.host}${BASE}${HMR_PATH}?protocol=${PROTOCOL_VERSION}`;
 
     console.log(`[HMR] Connecting to ${wsUrl}...`);
 
@@ -50,6 +79,11 @@ class VelocityHMR {
   handleMessage(message) {
     switch (message.type) {
       case 'connected':
+        if (message.protocol_version !== PROTOCOL_VERSION) {
+          this.handleProtocolMismatch(message.protocol_version);
+          break;
+        }
+        sessionStorage.removeItem('velocity-hmr-reloaded');
         console.log('[HMR] 🎉 Hot Module Replacement enabled');
         break;
 
@@ -57,6 +91,10 @@ class VelocityHMR {
         this.applyUpdate(message);
         break;
 
+      case 'css-update':
+        this.applyCssUpdate(message.path);
+        break;
+
       case 'full-reload':
         console.log(`[HMR] 🔄 Full reload: ${message.reason}`);
         window.location.reload();
@@ -71,13 +109,29 @@ class VelocityHMR {
     }
   }
 
+  handleProtocolMismatch(serverVersion) {
+    console.warn(
+      `[HMR] ⚠️  Protocol mismatch (client v${PROTOCOL_VERSION}, server v${serverVersion}); ` +
+        'hot updates are disabled until the page is reloaded'
+    );
+    // Reload once to pick up the server's client; a second mismatch means the
+    // page is caching an old client, and reloading again would loop
+    if (!sessionStorage.getItem('velocity-hmr-reloaded')) {
+      sessionStorage.setItem('velocity-hmr-reloaded', '1');
+      window.location.reload();
+    } else {
+      this.ws.onclose = null;
+      this.ws.close();
+    }
+  }
+
   applyUpdate(message) {
     const { module, code, timestamp } = message;
     console.log(`[HMR] 📦 Updating module: ${module}`);
 
-    // For now, do a full reload for all updates
-    // This ensures reliability while we work on proper HMR
+    // Reload to apply it, keeping signal values across the reload
     console.log('[HMR] 🔄 Reloading page to apply changes...');
+    this.saveSignalState();
     this.showNotification(`Updated: ${module}`, 'success');
 
     // Small delay to show the notification
@@ -86,6 +140,29 @@ class VelocityHMR {
     }, 100);
   }
 
+  applyCssUpdate(path) {
+    const links = Array.from(document.querySelectorAll('link[rel="stylesheet"]'))
+      .filter((link) => new URL(link.href, window.location.href).pathname === path);
+
+    if (links.length === 0) {
+      // Not loaded through a <link> (e.g. @import), so only a reload picks it up
+      console.log(`[HMR] 🔄 No <link> for ${path}, reloading page...`);
+      window.location.reload();
+      return;
+    }
+
+    console.log(`[HMR] 🎨 Updating stylesheet: ${path}`);
+    links.forEach((link) => {
+      // Load the new sheet next to the old one and drop the old one once it
+      // has applied, so the page never renders unstyled
+      const next = link.cloneNode();
+      next.href = `${path}?t=${Date.now()}`;
+      next.onload = () => link.remove();
+      next.onerror = () => link.remove();
+      link.after(next);
+    });
+  }
+
   applyCascadeUpdates(dependents, savedState) {
     console.log(`[HMR] 🔗 Applying cascade updates to ${dependents.length} module(s)...`);
 
@@ -102,88 +179,38 @@ class VelocityHMR {
     // This would require deeper integration with the Velocity runtime
   }
 
-  captureState() {
-    const state = {
-      signals: new Map(),
-      dom: null,
-      timestamp: Date.now()
-    };
-
-    // Capture Velocity signal values if the runtime is loaded
-    if (window.__velocity_runtime__) {
-      const runtime = window.__velocity_runtime__;
-
-      // Capture all active signals
-      if (runtime.signals) {
-        runtime.signals.forEach((signal, id) => {
-          try {
-            // Store current value of each signal
-            if (typeof signal === 'function') {
-              state.signals.set(id, signal());
-            }
-          } catch (e) {
-            console.warn(`[HMR] Could not capture signal ${id}:`, e);
-          }
-        });
-      }
-    }
-
-    // Capture current DOM state
-    const root = document.getElementById('root');
-    if (root) {
-      state.dom = root.cloneNode(true);
+  // Save the values of the page's keyed signals (see the runtime's
+  // createSignal) for the reloaded page to start from. Values that aren't
+  // plain data are saved as null, which keeps their instance's place.
+  saveSignalState() {
+    const signals = window.__velocity_hmr_signals__;
+    if (!signals) {
+      return;
     }
 
-    console.log(`[HMR] 💾 Captured state: ${state.signals.size} signals`);
-    return state;
-  }
+    const state = {};
+    let saved = 0;
+    signals.forEach((reads, key) => {
+      state[key] = Array.from(reads, (read) => {
+        try {
+          const value = read();
+          if (!isPlainData(value)) {
+            return null;
+          }
+          saved++;
+          return { value };
+        } catch (e) {
+          console.warn(`[HMR] Could not read signal ${key}:`, e);
+          return null;
+        }
+      });
+    });
 
-  tryHotReplace(modulePath, oldModule, newModule, savedState) {
     try {
-      // Check if this is a component module
-      const isComponentModule = modulePath.includes('.tsx') || modulePath.includes('.jsx');
-
-      if (!isComponentModule) {
-        // Non-component modules need full reload
-        return false;
-      }
-
-      // If Velocity runtime is not available, can't hot replace
-      if (!window.__velocity_runtime__) {
-        console.log('[HMR] Runtime not available for hot replace');
-        return false;
-      }
-
-      const runtime = window.__velocity_runtime__;
-
-      // Re-render with the new module while restoring signal values
-      if (savedState.signals.size > 0 && runtime.signals) {
-        console.log(`[HMR] 🔄 Restoring ${savedState.signals.size} signal values...`);
-
-        // Restore signal values after a brief delay to allow new signals to be created
-        setTimeout(() => {
-          let restored = 0;
-          savedState.signals.forEach((value, id) => {
-            const signal = runtime.signals.get(id);
-            if (signal && signal.set) {
-              try {
-                signal.set(value);
-                restored++;
-              } catch (e) {
-                console.warn(`[HMR] Could not restore signal ${id}:`, e);
-              }
-            }
-          });
-          console.log(`[HMR] ✅ Restored ${restored}/${savedState.signals.size} signals`);
-        }, 50);
-      }
-
-      // Module successfully replaced with state preservation
-      return true;
-
+      sessionStorage.setItem(SIGNAL_STATE_KEY, JSON.stringify(state));
+      console.log(`[HMR] 💾 Saved ${saved} signal value(s)`);
     } catch (e) {
-      console.error('[HMR] Hot replace failed:', e);
-      return false;
+      console.warn('[HMR] Could not save signal state:', e);
     }
   }
 
@@ -0,0 +1,157 @@
+//! Paths the dev server watcher and `velocity build` skip
+//!
+//! Always ignored: `node_modules`, `dist`, and dotfiles and dot-directories
+//! (`.git`, `.DS_Store`, editor swap files). A `.velocityignore` file in the
+//! project root adds patterns, one per line:
+//! - `#` starts a comment; blank lines are skipped
+//! - a pattern without `/` matches a file or directory name anywhere
+//!   (`coverage`, `*.log`)
+//! - a pattern with `/` matches a path from the project root and everything
+//!   under it (`src/generated/`, `public/vendor/*.min.js`)
+//! - `*` matches any run of characters within one name, `?` any one character
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the optional ignore file in the project root
+pub const IGNORE_FILE: &str = ".velocityignore";
+
+/// Directory names ignored in every project
+const DEFAULT_IGNORED: &[&str] = &["node_modules", "dist"];
+
+pub struct IgnoreList {
+    /// The root as given and canonicalized: walks report paths under the
+    /// former, the file watcher under the latter
+    roots: Vec<PathBuf>,
+    /// Patterns matched against single names
+    names: Vec<String>,
+    /// Patterns matched against paths from the root, split into names
+    paths: Vec<Vec<String>>,
+}
+
+impl IgnoreList {
+    /// The default list plus the patterns in `root/.velocityignore`, if any
+    pub fn load(root: &Path) -> anyhow::Result<Self> {
+        let path = root.join(IGNORE_FILE);
+        let patterns = if path.is_file() {
+            fs::read_to_string(&path).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?
+        } else {
+            String::new()
+        };
+        Ok(Self::new(root, &patterns))
+    }
+
+    fn new(root: &Path, patterns: &str) -> Self {
+        let mut list = Self {
+            roots: [Some(root.to_path_buf()), root.canonicalize().ok()].into_iter().flatten().collect(),
+            names: DEFAULT_IGNORED.iter().map(|name| name.to_string()).collect(),
+            paths: Vec::new(),
+        };
+        for pattern in patterns.lines().map(str::trim) {
+            if pattern.is_empty() || pattern.starts_with('#') {
+                continue;
+            }
+            let pattern = pattern.trim_end_matches('/');
+            if pattern.contains('/') {
+                let segments = pattern.trim_start_matches('/').split('/').map(str::to_string).collect();
+                list.paths.push(segments);
+            } else {
+                list.names.push(pattern.to_string());
+            }
+        }
+        list
+    }
+
+    /// Whether `path` is ignored itself or lies inside an ignored directory.
+    /// Paths outside the project root are never ignored.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let Some(relative) = self.roots.iter().find_map(|root| path.strip_prefix(root).ok()) else {
+            return false;
+        };
+        let names: Vec<&str> = relative
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect();
+
+        let ignored_name = names
+            .iter()
+            .any(|name| name.starts_with('.') || self.names.iter().any(|pattern| glob_match(pattern, name)));
+        ignored_name
+            || self.paths.iter().any(|pattern| {
+                pattern.len() <= names.len()
+                    && pattern.iter().zip(&names).all(|(pattern, name)| glob_match(pattern, name))
+            })
+    }
+}
+
+/// Match `name` against a pattern where `*` is any run of characters and
+/// `?` any single character
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was and how much of `name` it has taken
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_list() {
+        let patterns = "
+            # generated code
+            src/generated/
+            *.log
+            public/vendor/*.min.js
+        ";
+        let ignore = IgnoreList::new(Path::new("/app"), patterns);
+        let ignored = |path: &str| ignore.is_ignored(Path::new(path));
+
+        assert!(ignored("/app/node_modules/react/index.js"));
+        assert!(ignored("/app/src/node_modules/x.ts"));
+        assert!(ignored("/app/dist/index.js"));
+        assert!(ignored("/app/.git/HEAD"));
+        assert!(ignored("/app/src/.App.tsx.swp"));
+        assert!(ignored("/app/src/generated/api.ts"));
+        assert!(ignored("/app/debug.log"));
+        assert!(ignored("/app/public/vendor/chart.min.js"));
+
+        assert!(!ignored("/app/src/App.tsx"));
+        assert!(!ignored("/app/src/distance.ts"));
+        assert!(!ignored("/app/src/components/generated/Button.tsx"));
+        assert!(!ignored("/app/public/vendor/chart.js"));
+        assert!(!ignored("/app/index.html"));
+        assert!(!ignored("/home/.config/dist/app.ts"));
+
+        assert!(glob_match("*.test.*", "App.test.tsx"));
+        assert!(glob_match("?oo", "foo"));
+        assert!(!glob_match("*.ts", "App.tsx"));
+    }
+}
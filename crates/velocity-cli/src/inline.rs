@@ -0,0 +1,302 @@
+//! Inlining small modules for `velocity build --inline-threshold <BYTES>`
+//!
+//! Every compiled module is its own file, so the browser fetches, records
+//! and links one module per file however little code it holds. With a
+//! threshold, modules whose compiled code is at most that many bytes are
+//! moved into the modules importing them and their files removed.
+//!
+//! [`velocity_compiler::inlining`] decides whether a module's code can be
+//! moved: no side effects, no imports (so no import cycles), no live
+//! bindings. The build adds what only the module graph shows:
+//! - The module isn't an entry, and `index.html` doesn't load it.
+//! - Nothing imports it dynamically, as a namespace or to re-export it,
+//!   since those need the file.
+//! - Each import of it is on one line, so the importer's source map stays
+//!   valid with the import replaced.
+//! - It has a single importer, unless all its exports are primitive
+//!   constants, which copies in several importers can't tell apart.
+//! - No importer is part of an import cycle. The inlined code runs when the
+//!   importer's body does, and a module earlier in the cycle can call the
+//!   importer's exports before that.
+//! - No importer declares a top-level name the module uses as a global.
+//!
+//! Modules are considered in path order, and only once: a module whose
+//! imports were all inlined isn't inlined in turn.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use velocity_compiler::{imports, inlining};
+use velocity_compiler::inlining::InlineModule;
+use crate::hashing::{out_dir_url, resolve_import};
+use crate::manifest::{content_hash, BuildManifest};
+
+/// Inline the small outputs in `manifest` into their importers, rewriting
+/// the importers and removing the inlined files. `entries` are the build's
+/// entry sources. Records what was inlined in the manifest and returns how
+/// many modules were.
+pub fn inline_small_modules(
+    root: &Path,
+    out_dir: &Path,
+    manifest: &mut BuildManifest,
+    entries: &[PathBuf],
+    threshold: usize,
+) -> anyhow::Result<usize> {
+    // Logical output path → compiled code
+    let mut code: HashMap<String, String> = HashMap::new();
+    for entry in &manifest.outputs {
+        let path = out_dir.join(&entry.output);
+        let source = fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        code.insert(entry.output.clone(), source);
+    }
+
+    // Outputs the page or the build loads by name
+    let html = fs::read_to_string(root.join("index.html")).unwrap_or_default();
+    let out_name = out_dir_url(root, out_dir);
+    let pinned: HashSet<String> = manifest
+        .outputs
+        .iter()
+        .filter(|entry| {
+            entries.contains(&root.join(&entry.source)) || html.contains(&format!("{}/{}", out_name, entry.output))
+        })
+        .map(|entry| entry.output.clone())
+        .collect();
+
+    let inlined = inline_outputs(&mut code, &pinned, threshold)?;
+    if inlined.is_empty() {
+        return Ok(0);
+    }
+
+    let importers: HashSet<&String> = inlined.values().flatten().collect();
+    let mut removed = Vec::new();
+    for entry in &mut manifest.outputs {
+        if let Some(into) = inlined.get(&entry.output) {
+            fs::remove_file(out_dir.join(&entry.output))?;
+            if let Some(map) = &entry.source_map {
+                fs::remove_file(out_dir.join(map))?;
+            }
+            manifest.inlined.insert(entry.source.clone(), into.clone());
+            removed.push(entry.output.clone());
+        } else if importers.contains(&entry.output) {
+            let source = &code[&entry.output];
+            fs::write(out_dir.join(&entry.output), source)?;
+            entry.size = source.len() as u64;
+            entry.hash = content_hash(source.as_bytes());
+        }
+    }
+    manifest.outputs.retain(|entry| !removed.contains(&entry.output));
+
+    Ok(inlined.len())
+}
+
+/// Inline what can be inlined among `code` (logical output path → compiled
+/// code), leaving `pinned` outputs alone. Importers are rewritten in
+/// `code`, and inlined outputs removed from it. Returns each inlined output
+/// with the outputs it was inlined into.
+fn inline_outputs(
+    code: &mut HashMap<String, String>,
+    pinned: &HashSet<String>,
+    threshold: usize,
+) -> anyhow::Result<BTreeMap<String, Vec<String>>> {
+    // Output → the outputs importing it statically, and outputs some import
+    // needs as a file
+    let mut importers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut needed: HashSet<String> = pinned.clone();
+    let mut outputs: Vec<&String> = code.keys().collect();
+    outputs.sort();
+    for output in outputs {
+        let source = &code[output];
+        for import in imports::find_imports(source, output)? {
+            if let (true, Some(target)) = (import.dynamic, resolve_import(output, &import.specifier, code)) {
+                needed.insert(target);
+            }
+        }
+        for import in inlining::static_imports(source, output)? {
+            let Some(target) = resolve_import(output, &import.specifier, code) else {
+                continue;
+            };
+            if import.bindings.is_none() {
+                needed.insert(target.clone());
+            }
+            let users = importers.entry(target).or_default();
+            if !users.contains(output) {
+                users.push(output.clone());
+            }
+        }
+    }
+
+    let cyclic = cyclic_outputs(&importers);
+    let mut inlined = BTreeMap::new();
+    for (target, users) in importers {
+        if needed.contains(&target) || code[&target].len() > threshold {
+            continue;
+        }
+        if users.iter().any(|user| cyclic.contains(user)) {
+            continue;
+        }
+        let Some(module) = inlining::inline_module(&code[&target], &target)? else {
+            continue;
+        };
+        if users.len() > 1 && !module.constant {
+            continue;
+        }
+
+        // Rewrite every importer or none
+        let mut rewritten = Vec::new();
+        for user in &users {
+            match inline_into(&code[user], user, &target, &module, code)? {
+                Some(source) => rewritten.push((user.clone(), source)),
+                None => break,
+            }
+        }
+        if rewritten.len() < users.len() {
+            continue;
+        }
+
+        code.extend(rewritten);
+        code.remove(&target);
+        inlined.insert(target, users);
+    }
+    Ok(inlined)
+}
+
+/// `source` (the output `importer`) with its imports of the output `target`
+/// replaced by `module`'s code, or `None` if they can't all be replaced
+fn inline_into(
+    source: &str,
+    importer: &str,
+    target: &str,
+    module: &InlineModule,
+    code: &HashMap<String, String>,
+) -> anyhow::Result<Option<String>> {
+    let declarations: Vec<_> = inlining::static_imports(source, importer)?
+        .into_iter()
+        .filter(|import| resolve_import(importer, &import.specifier, code).as_deref() == Some(target))
+        .collect();
+
+    let mut bindings = Vec::new();
+    for declaration in &declarations {
+        match &declaration.bindings {
+            Some(more) if !source[declaration.range.clone()].contains('\n') => bindings.extend(more.iter().cloned()),
+            _ => return Ok(None),
+        }
+    }
+    let top_level = inlining::top_level_bindings(source, importer)?;
+    let Some(replacement) = inlining::replacement(module, &bindings, &top_level) else {
+        return Ok(None);
+    };
+
+    // The first import gets the module's code; later ones go
+    let mut output = String::with_capacity(source.len() + replacement.len());
+    let mut last = 0;
+    for (i, declaration) in declarations.iter().enumerate() {
+        output.push_str(&source[last..declaration.range.start]);
+        if i == 0 {
+            output.push_str(&replacement);
+        }
+        last = declaration.range.end;
+    }
+    output.push_str(&source[last..]);
+    Ok(Some(output))
+}
+
+/// Outputs that import themselves through a chain of static imports, given
+/// each output's importers
+fn cyclic_outputs(importers: &BTreeMap<String, Vec<String>>) -> HashSet<String> {
+    importers
+        .keys()
+        .filter(|&output| {
+            // Walk up the importers of `output` looking for `output` itself
+            let mut seen = HashSet::new();
+            let mut stack: Vec<&String> = importers[output].iter().collect();
+            while let Some(user) = stack.pop() {
+                if user == output {
+                    return true;
+                }
+                if seen.insert(user) {
+                    stack.extend(importers.get(user).into_iter().flatten());
+                }
+            }
+            false
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outputs(files: &[(&str, &str)]) -> HashMap<String, String> {
+        files.iter().map(|(path, code)| (path.to_string(), code.to_string())).collect()
+    }
+
+    #[test]
+    fn test_inline_outputs() {
+        let mut code = outputs(&[
+            ("index.js", "import { clamp } from './utils/math.js';\nimport { LIMIT } from './limits.js';\nimport { Big } from './big.js';\nconsole.log(clamp(LIMIT));\n"),
+            ("page.js", "import { LIMIT as max } from './limits.js';\nimport { shared } from './shared.js';\nconsole.log(max, shared());\n"),
+            ("other.js", "import { shared } from './shared.js';\nconst lazy = () => import('./lazy.js');\nexport { lazy, shared };\n"),
+            ("utils/math.js", "export function clamp(n) { return Math.min(n, 10); }\n"),
+            ("limits.js", "export const LIMIT = 10;\n"),
+            ("shared.js", "export function shared() { return 1; }\n"),
+            ("big.js", "export class Big { size() { return 1000000000000000000000000000000000000; } }\n"),
+            ("lazy.js", "export const value = 1;\n"),
+        ]);
+        let pinned = HashSet::from(["index.js".to_string()]);
+
+        let inlined = inline_outputs(&mut code, &pinned, 64).unwrap();
+
+        assert_eq!(
+            inlined,
+            BTreeMap::from([
+                ("limits.js".to_string(), vec!["index.js".to_string(), "page.js".to_string()]),
+                ("utils/math.js".to_string(), vec!["index.js".to_string()]),
+            ])
+        );
+        assert!(!code.contains_key("utils/math.js"));
+        assert!(code.contains_key("shared.js"), "shared by two importers and not constant");
+        assert!(code.contains_key("big.js"), "over the threshold");
+        assert!(code.contains_key("lazy.js"), "imported dynamically");
+
+        let index = &code["index.js"];
+        assert!(index.starts_with(r#"const { "clamp": clamp } = (() => { function clamp(n){"#), "{}", index);
+        assert!(index.contains("\nconst { \"LIMIT\": LIMIT } = (() => { const LIMIT=10; return { \"LIMIT\": LIMIT }; })();\n"), "{}", index);
+        assert!(index.contains("import { Big } from './big.js';"), "{}", index);
+        assert_eq!(index.lines().count(), 4, "{}", index);
+        assert!(code["page.js"].starts_with(r#"const { "LIMIT": max } = "#), "{}", code["page.js"]);
+    }
+
+    #[test]
+    fn test_inline_outputs_skips_cycles_and_shadowed_globals() {
+        let mut code = outputs(&[
+            ("a.js", "import { b } from './b.js';
+import { ONE } from './one.js';
+export const a = () => ONE + b();
+"),
+            ("b.js", "import { a } from './a.js';
+export const b = () => a();
+"),
+            ("one.js", "export const ONE = 1;
+"),
+            ("page.js", "import { now } from './clock.js';
+const Date = null;
+console.log(now());
+"),
+            ("clock.js", "export function now() { return Date.now(); }
+"),
+            ("app.js", "import { now } from './clock2.js';
+console.log(now());
+"),
+            ("clock2.js", "export function now() { return Date.now(); }
+"),
+        ]);
+
+        let inlined = inline_outputs(&mut code, &HashSet::new(), 64).unwrap();
+
+        assert_eq!(inlined.keys().collect::<Vec<_>>(), vec!["clock2.js"]);
+        assert!(code.contains_key("one.js"), "imported by a.js, which is in a cycle");
+        assert!(code.contains_key("clock.js"), "page.js shadows the Date global");
+    }
+}
@@ -0,0 +1,270 @@
+//! Legacy `nomodule` bundles for `velocity build --legacy`
+//!
+//! Browsers that can't run `<script type="module">` skip it and run
+//! `<script nomodule>` instead, which module-capable browsers ignore. For
+//! every module script `index.html` loads from the build, `--legacy` writes
+//! one classic script holding everything that entry imports, compiled for
+//! [`LEGACY_TARGET`] as CommonJS and wrapped in a small loader, and adds it
+//! to the page next to the module script:
+//! ```html
+//! <script type="module" src="/dist/index.js"></script>
+//! <script nomodule defer src="/dist/index.legacy.js"></script>
+//! ```
+//!
+//! Package imports such as `velocity-runtime` aren't bundled. The loader
+//! looks them up in `window.velocityExternals`, which the page fills from
+//! a classic script that runs first.
+
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use velocity_compiler::{css, imports, Compiler, CompilerOptions, ModuleFormat};
+use crate::hashing::{hashed_name, out_dir_url, resolve_import, HASH_LENGTH};
+use crate::manifest::{content_hash, BuildManifest};
+
+/// Syntax level of legacy bundles. It suits the browsers that run ES2015
+/// but predate module scripts: Chrome 51–60, Firefox 54–59, Safari 10.0 and
+/// Edge 15. Downleveling lowers optional chaining, nullish coalescing and
+/// logical assignment, but nothing older: ES2015 syntax itself (arrow
+/// functions, classes) is left as written, so Internet Explorer isn't
+/// supported, and so are async functions (ES2017) and object spread and rest
+/// (ES2018). Sources using async functions narrow the range to Chrome 55–60,
+/// Firefox 54–59 and Edge 15; using object spread or rest, to Chrome 60 and
+/// Firefox 55–59.
+pub const LEGACY_TARGET: &str = "es2015";
+
+/// Global the loader reads package imports from
+const EXTERNALS_GLOBAL: &str = "velocityExternals";
+
+/// A module compiled for a legacy bundle
+struct LegacyModule {
+    code: String,
+    /// Import specifier → id of the module it resolves to
+    dependencies: BTreeMap<String, String>,
+}
+
+/// Write a legacy bundle for each module script in `html` that loads a
+/// build output, and `index.html` into `out_dir` with a `nomodule` script
+/// added for each. `sources` are the build's source files and `options`
+/// its compiler options. Returns the bundles written, relative to `out_dir`.
+pub fn write_legacy_bundles(
+    root: &Path,
+    out_dir: &Path,
+    html: &Path,
+    sources: &[(PathBuf, String)],
+    options: &CompilerOptions,
+    manifest: &mut BuildManifest,
+) -> anyhow::Result<Vec<String>> {
+    let page = fs::read_to_string(html).map_err(|_| {
+        anyhow::anyhow!("--legacy adds its scripts to index.html, but {} can't be read", html.display())
+    })?;
+
+    let compiler = Compiler::new(CompilerOptions {
+        target: LEGACY_TARGET.to_string(),
+        module_format: ModuleFormat::Cjs,
+        source_maps: false,
+        ..options.clone()
+    });
+    // Module id (its output path) → source file
+    let ids: HashMap<String, PathBuf> = sources.iter().map(|(path, id)| (id.clone(), path.clone())).collect();
+    let unhashed: HashMap<&String, &String> = manifest.hashed.iter().map(|(logical, hashed)| (hashed, logical)).collect();
+    let out_name = out_dir_url(root, out_dir);
+
+    let mut compiled: HashMap<String, LegacyModule> = HashMap::new();
+    let mut written = Vec::new();
+    let mut scripts = Vec::new();
+    for (insert_at, url) in module_scripts(&page) {
+        let path = url.trim_start_matches("./").trim_start_matches('/');
+        let Some(output) = path.strip_prefix(&format!("{}/", out_name)) else {
+            continue;
+        };
+        let entry = unhashed.get(&output.to_string()).map(|logical| logical.to_string()).unwrap_or(output.to_string());
+        if !ids.contains_key(&entry) {
+            continue;
+        }
+
+        // Every module the entry reaches, in the order first seen
+        let mut order = vec![entry.clone()];
+        let mut index = 0;
+        while let Some(id) = order.get(index).cloned() {
+            if !compiled.contains_key(&id) {
+                compiled.insert(id.clone(), compile_module(&compiler, root, &ids[&id], &id, &ids)?);
+            }
+            for dependency in compiled[&id].dependencies.values() {
+                if !order.contains(dependency) {
+                    order.push(dependency.clone());
+                }
+            }
+            index += 1;
+        }
+
+        let bundle = bundle_source(&entry, order.iter().map(|id| (id.as_str(), &compiled[id])));
+        let stem = entry.strip_suffix(".js").unwrap_or(&entry);
+        let mut name = format!("{}.legacy.js", stem);
+        // Only `--hashed` builds record hashed names
+        if !manifest.hashed.is_empty() {
+            name = hashed_name(&name, &content_hash(bundle.as_bytes())[..HASH_LENGTH]);
+        }
+        let bundle_path = out_dir.join(&name);
+        fs::write(&bundle_path, bundle)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", bundle_path.display(), e))?;
+
+        let file_name = name.rsplit('/').next().unwrap_or(&name);
+        let bundle_url = match url.rsplit_once('/') {
+            Some((dir, _)) => format!("{}/{}", dir, file_name),
+            None => file_name.to_string(),
+        };
+        scripts.push((insert_at, format!(r#"<script nomodule defer src="{}"></script>"#, bundle_url)));
+        manifest.legacy.insert(entry, name.clone());
+        written.push(name);
+    }
+
+    if written.is_empty() {
+        anyhow::bail!(
+            "--legacy found no <script type=\"module\"> in {} that loads a build output from {}/",
+            html.display(),
+            out_name
+        );
+    }
+
+    // Insert from the end so earlier offsets stay valid
+    let mut page = page;
+    for (insert_at, script) in scripts.into_iter().rev() {
+        page.insert_str(insert_at, &format!("\n  {}", script));
+    }
+    fs::write(out_dir.join("index.html"), page)?;
+
+    Ok(written)
+}
+
+/// Compile the source file `path` (module `id`) to CommonJS for the legacy
+/// target, resolving its relative imports to module ids
+fn compile_module(
+    compiler: &Compiler,
+    root: &Path,
+    path: &Path,
+    id: &str,
+    ids: &HashMap<String, PathBuf>,
+) -> anyhow::Result<LegacyModule> {
+    // Compiled under the same name as the ES module build, so the scoped
+    // class names match styles.css
+    let filename = crate::project_path(root, path);
+    let mut source = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    if filename.ends_with(css::CSS_MODULE_SUFFIX) {
+        source = css::scope_css(&source, &filename).to_module();
+    }
+
+    let code = compiler.compile(&source, &filename)?;
+    let known: HashMap<String, String> = ids.keys().map(|id| (id.clone(), String::new())).collect();
+    let dependencies = imports::find_imports(&source, &filename)?
+        .into_iter()
+        .filter_map(|import| {
+            let target = resolve_import(id, &import.specifier, &known)?;
+            Some((import.specifier, target))
+        })
+        .collect();
+
+    Ok(LegacyModule { code, dependencies })
+}
+
+/// Wrap `modules` in a loader that runs `entry`. The loader is ES5, so it
+/// runs wherever the modules do.
+fn bundle_source<'a>(entry: &str, modules: impl Iterator<Item = (&'a str, &'a LegacyModule)>) -> String {
+    let mut bundle = String::from("(function (modules) {\n");
+    bundle.push_str(&format!(
+        r#"  var cache = {{}};
+  function load(id) {{
+    if (cache[id]) return cache[id].exports;
+    var module = cache[id] = {{ exports: {{}} }};
+    var definition = modules[id];
+    definition[0].call(module.exports, module, module.exports, function (specifier) {{
+      var dependency = definition[1][specifier];
+      if (dependency) return load(dependency);
+      var externals = window.{externals} || {{}};
+      if (specifier in externals) return externals[specifier];
+      throw new Error("Cannot find module '" + specifier + "' from " + id + ": add it to window.{externals}");
+    }});
+    return module.exports;
+  }}
+  load({entry});
+}})({{
+"#,
+        externals = EXTERNALS_GLOBAL,
+        entry = json!(entry),
+    ));
+    for (id, module) in modules {
+        bundle.push_str(&format!(
+            "{}: [function (module, exports, require) {{\n{}\n}}, {}],\n",
+            json!(id),
+            module.code.trim_end(),
+            json!(module.dependencies),
+        ));
+    }
+    bundle.push_str("});\n");
+    bundle
+}
+
+/// `<script type="module" src="...">` tags in `html`: where the text after
+/// each one's `</script>` starts, and its `src`
+fn module_scripts(html: &str) -> Vec<(usize, String)> {
+    let mut scripts = Vec::new();
+    for (start, _) in html.match_indices("<script") {
+        let Some(tag_end) = html[start..].find('>').map(|i| start + i) else {
+            continue;
+        };
+        let tag = &html[start..tag_end];
+        let module = ["type=\"module\"", "type='module'", "type=module"].iter().any(|attr| tag.contains(attr));
+        let Some(close) = html[tag_end..].find("</script>").map(|i| tag_end + i + "</script>".len()) else {
+            continue;
+        };
+        if let (true, Some(src)) = (module, attribute(tag, "src")) {
+            scripts.push((close, src.to_string()));
+        }
+    }
+    scripts
+}
+
+/// Value of the quoted attribute `name` in an HTML start tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(" {}=", name);
+    let value = &tag[tag.find(&pattern)? + pattern.len()..];
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &value[1..];
+    Some(&value[..value.find(quote)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_scripts() {
+        let html = r#"<head>
+  <script src="/analytics.js"></script>
+  <script type="module" src="/dist/index.js"></script>
+  <script type='module' src='./dist/admin.1a2b3c4d.js' async></script>
+  <script type="module">import "/dist/inline.js";</script>
+</head>"#;
+
+        let scripts = module_scripts(html);
+        let srcs: Vec<_> = scripts.iter().map(|(_, src)| src.as_str()).collect();
+        assert_eq!(srcs, ["/dist/index.js", "./dist/admin.1a2b3c4d.js"]);
+        assert!(html[..scripts[0].0].ends_with(r#"src="/dist/index.js"></script>"#));
+    }
+
+    #[test]
+    fn test_bundle_source() {
+        let module = LegacyModule {
+            code: "\"use strict\";\nconst _App = require(\"./App\");".to_string(),
+            dependencies: BTreeMap::from([("./App".to_string(), "App.js".to_string())]),
+        };
+        let bundle = bundle_source("index.js", [("index.js", &module)].into_iter());
+
+        assert!(bundle.contains(r#"load("index.js");"#));
+        assert!(bundle.contains(r#""index.js": [function (module, exports, require) {"#));
+        assert!(bundle.contains(r#"}, {"./App":"App.js"}],"#));
+        assert!(bundle.contains("window.velocityExternals"));
+    }
+}
@@ -0,0 +1,95 @@
+//! Console output at the level set by the global `--quiet` and `--verbose`
+//! flags
+//!
+//! Progress and summaries go through [`info!`], details that only help when
+//! debugging (resolved paths, per-phase timings) through [`verbose!`], and
+//! warnings through [`warning!`]. Details go to stderr, so `--verbose` never
+//! mixes them into output piped from stdout. Errors are always printed, so
+//! `--quiet` runs in CI show nothing but failures. What a command exists to
+//! print, such as compiled code on stdout, `analyze` and `check` reports and
+//! `info`, isn't logging and is printed at every level.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+/// How much the CLI prints, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Errors only (`--quiet`)
+    Quiet,
+    /// Progress and summaries
+    Normal,
+    /// Also resolved paths and timings (`--verbose`)
+    Verbose,
+}
+
+impl Level {
+    /// Level for the `--quiet` and `--verbose` flags, which clap keeps from
+    /// being combined
+    pub fn from_flags(quiet: bool, verbose: bool) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Level::Quiet,
+            (_, true) => Level::Verbose,
+            _ => Level::Normal,
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Normal as u8);
+
+/// Set the level for the rest of the run
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Whether output at `level` is printed
+pub fn enabled(level: Level) -> bool {
+    LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+/// `duration` in milliseconds, as every timing is printed
+pub fn ms(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Print a line of progress, unless `--quiet`
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Normal) {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Print a line of detail to stderr with `--verbose`
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Verbose) {
+            eprintln!("   {}", format_args!($($arg)*));
+        }
+    };
+}
+
+/// Print a warning to stderr, unless `--quiet`
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Normal) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use {info, verbose, warning};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_from_flags() {
+        assert_eq!(Level::from_flags(false, false), Level::Normal);
+        assert_eq!(Level::from_flags(true, false), Level::Quiet);
+        assert_eq!(Level::from_flags(false, true), Level::Verbose);
+        assert!(Level::Verbose > Level::Normal && Level::Normal > Level::Quiet);
+    }
+}
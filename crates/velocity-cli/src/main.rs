@@ -1,20 +1,57 @@
 use clap::{Parser, Subcommand};
-use velocity_compiler::{Compiler, CompilerOptions};
+use velocity_compiler::{css, i18n, A11yRules, CodeStyle, Compiler, CompilerError, CompilerOptions, Environment, I18nOptions, JsxMode, MinifyLevel, ModuleFormat, ReactiveFunctions, RuntimeImportStyle};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use notify::{Watcher, RecursiveMode, recommended_watcher};
 use std::sync::mpsc::channel;
 use colored::*;
+use log::{info, verbose, warning};
 
 mod dev_server;
 mod create;
+mod check;
+mod manifest;
+mod hashing;
+mod inline;
+mod assets;
+mod preload;
+mod duplicates;
+mod ignore;
+mod legacy;
+mod log;
+mod workspace;
+
+/// Stylesheet `velocity build` collects extracted CSS into, in the output dir
+const STYLES_FILE: &str = "styles.css";
+
+/// Catalog `velocity build --extract-messages` writes, in the output dir
+const MESSAGES_FILE: &str = "messages.json";
+
+/// How long the watcher must be quiet before a change triggers a recompile
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Runtime files `velocity info` measures, relative to the project root
+const RUNTIME_ARTIFACTS: &[&str] = &[
+    "dist/velocity-runtime.js",
+    "pkg/velocity_wasm.js",
+    "pkg/velocity_wasm_bg.wasm",
+];
 
 #[derive(Parser)]
 #[command(name = "velocity")]
 #[command(about = "Velocity Framework - Lightning fast JavaScript framework", long_about = None)]
 #[command(version)]
 struct Cli {
+    /// Print errors only
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Also print resolved paths and per-phase timings
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -23,21 +60,38 @@ struct Cli {
 enum Commands {
     /// Compile a single file
     Compile {
-        /// Input file path
-        #[arg(value_name = "FILE")]
-        input: PathBuf,
+        /// Input file path (use `-` to read from stdin)
+        #[arg(value_name = "FILE", required_unless_present = "stdin")]
+        input: Option<PathBuf>,
+
+        /// Read source from stdin instead of a file (same as `-` for FILE)
+        #[arg(long)]
+        stdin: bool,
+
+        /// Virtual file name for stdin input, used in error messages
+        #[arg(long, default_value = "stdin.tsx")]
+        filename: String,
 
         /// Output file path (optional, defaults to stdout)
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// Enable minification
+        /// Enable full minification (same as `--minify-level full`)
         #[arg(short, long)]
         minify: bool,
 
+        /// Minification level: none, whitespace (fast, keeps names) or full
+        /// (compress and mangle)
+        #[arg(long, value_name = "LEVEL", conflicts_with = "minify")]
+        minify_level: Option<String>,
+
         /// Disable optimization passes
         #[arg(long)]
         no_optimize: bool,
+
+        /// Error output format: text, json (one JSON object on stderr)
+        #[arg(long, default_value = "text")]
+        error_format: String,
     },
 
     /// Build a project
@@ -48,9 +102,70 @@ enum Commands {
         #[arg(short, long, default_value = "dist")]
         out_dir: String,
 
-        /// Enable minification
+        /// Enable full minification (same as `--minify-level full`)
         #[arg(short, long)]
         minify: bool,
+
+        /// Minification level: none, whitespace (fast, keeps names) or full
+        /// (compress and mangle)
+        #[arg(long, value_name = "LEVEL", conflicts_with = "minify")]
+        minify_level: Option<String>,
+
+        /// Name outputs by content hash (`app.<hash>.js`) for long-term caching
+        #[arg(long)]
+        hashed: bool,
+
+        /// Entry point relative to the root, e.g. `src/sw.ts` (repeatable).
+        /// Entries are listed in the manifest with their output file.
+        #[arg(long = "entry")]
+        entries: Vec<String>,
+
+        /// Worker entry point relative to the root, e.g. `src/search.worker.ts`
+        /// (repeatable). Compiled for a Web Worker: DOM globals are reported,
+        /// JSX is an error and the runtime comes from `velocity-runtime/worker`.
+        /// Listed in the manifest like `--entry`.
+        #[arg(long = "worker")]
+        workers: Vec<String>,
+
+        /// Add `<link rel="modulepreload">` hints to index.html: none,
+        /// static (the entries' imports) or dynamic (also their lazy imports)
+        #[arg(long, default_value = "none")]
+        preload: String,
+
+        /// Also build a `nomodule` bundle per module script in index.html
+        /// for browsers without ES module support, and add it to the page
+        #[arg(long)]
+        legacy: bool,
+
+        /// Build for profiling: optimized, with whitespace stripped but
+        /// names kept, and every component's render timed with
+        /// `performance` marks and a measure named `<Component>`
+        #[arg(long, conflicts_with_all = ["minify", "minify_level"])]
+        profile: bool,
+
+        /// Move modules whose compiled code is at most this many bytes into
+        /// the modules importing them, when that's safe (no side effects,
+        /// no imports, one importer unless it only exports constants)
+        #[arg(long, value_name = "BYTES")]
+        inline_threshold: Option<usize>,
+
+        /// Collect messages marked for translation (`t("key", "Default")`,
+        /// `<Trans id="key">Default</Trans>`) into messages.json
+        #[arg(long)]
+        extract_messages: bool,
+
+        /// Function marking translatable messages
+        #[arg(long, value_name = "NAME", default_value = "t", requires = "extract_messages")]
+        i18n_function: String,
+
+        /// Component marking translatable messages
+        #[arg(long, value_name = "NAME", default_value = "Trans", requires = "extract_messages")]
+        i18n_component: String,
+
+        /// Remove extracted default messages from the output, leaving
+        /// lookups by key
+        #[arg(long, requires = "extract_messages")]
+        strip_message_defaults: bool,
     },
 
     /// Start development server (coming soon)
@@ -60,6 +175,20 @@ enum Commands {
 
         #[arg(short, long, default_value = ".")]
         root: String,
+
+        /// Public base path to serve under (e.g. /app/ behind a reverse proxy)
+        #[arg(long, default_value = "/")]
+        base: String,
+
+        /// Path of the HMR WebSocket under the base path, for proxies that
+        /// only forward WebSockets on certain paths
+        #[arg(long, default_value = dev_server::DEFAULT_HMR_PATH)]
+        hmr_path: String,
+
+        /// Serve a workspace under /NAME/, with its own watcher and HMR
+        /// channel; PATH is relative to --root (repeatable)
+        #[arg(long = "workspace", value_name = "[NAME=]PATH")]
+        workspaces: Vec<String>,
     },
 
     /// Watch and recompile on changes
@@ -72,10 +201,15 @@ enum Commands {
         #[arg(short, long)]
         output: PathBuf,
 
-        /// Enable minification
+        /// Enable full minification (same as `--minify-level full`)
         #[arg(short, long)]
         minify: bool,
 
+        /// Minification level: none, whitespace (fast, keeps names) or full
+        /// (compress and mangle)
+        #[arg(long, value_name = "LEVEL", conflicts_with = "minify")]
+        minify_level: Option<String>,
+
         /// Disable optimization passes
         #[arg(long)]
         no_optimize: bool,
@@ -89,11 +223,30 @@ enum Commands {
         #[arg(short, long, default_value = "dist")]
         out_dir: String,
 
-        /// Output format: text, json
+        /// Output format: text, json, treemap
         #[arg(short, long, default_value = "text")]
         format: String,
     },
 
+    /// Print the reactive dependency graph of a file: its signals, memos,
+    /// effects and reactive elements, and which read which
+    Graph {
+        /// Input file path
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+
+        /// Output format: dot (Graphviz), json
+        #[arg(short, long, default_value = "dot")]
+        format: String,
+    },
+
+    /// Validate project setup (entry file, index.html, runtime, sources)
+    #[command(alias = "doctor")]
+    Check {
+        #[arg(short, long, default_value = ".")]
+        root: String,
+    },
+
     /// Show version and build information
     Info,
 
@@ -109,8 +262,68 @@ enum Commands {
     },
 }
 
+/// `--minify-level` if given, otherwise full minification for `--minify`
+fn minify_level(minify: bool, level: Option<&str>) -> anyhow::Result<MinifyLevel> {
+    match level {
+        Some(level) => Ok(MinifyLevel::parse(level)?),
+        None if minify => Ok(MinifyLevel::Full),
+        None => Ok(MinifyLevel::None),
+    }
+}
+
+/// Compiler options used for files written to disk
+fn compiler_options(minify: MinifyLevel, no_optimize: bool) -> CompilerOptions {
+    CompilerOptions {
+        optimize: !no_optimize,
+        source_maps: true,
+        target: "es2020".to_string(),
+        minify,
+        effect_labels: minify == MinifyLevel::None,
+        profile_components: false,
+        strict: false,
+        a11y: A11yRules::default(),
+        reactive_functions: ReactiveFunctions::default(),
+        passes: Vec::new(),
+        module_format: ModuleFormat::Esm,
+        code_style: CodeStyle::default(),
+        runtime_import_style: RuntimeImportStyle::Barrel,
+        jsx: JsxMode::Transform,
+        i18n: None,
+        prop_checks: false,
+        environment: Environment::Browser,
+        hmr_signal_keys: false,
+    }
+}
+
+/// Compiler options for `velocity build --profile`: optimized like a
+/// production build, but readable in a profiler
+fn profile_options() -> CompilerOptions {
+    CompilerOptions {
+        effect_labels: true,
+        profile_components: true,
+        ..compiler_options(MinifyLevel::Whitespace, false)
+    }
+}
+
+/// What a build does with the compiled outputs before writing the manifest
+struct OutputSteps {
+    /// Inline modules of at most this many bytes into their importers
+    inline_threshold: Option<usize>,
+    /// Name outputs by content hash
+    hashed: bool,
+    preload: preload::PreloadStrategy,
+    /// Also build `nomodule` bundles
+    legacy: bool,
+}
+
 /// Build an entire project by walking the source directory
-fn build_project(root: &str, out_dir: &str, minify: bool) -> anyhow::Result<()> {
+fn build_project(
+    root: &str,
+    out_dir: &str,
+    options: &CompilerOptions,
+    entries: &[(String, Environment)],
+    steps: &OutputSteps,
+) -> anyhow::Result<()> {
     use std::time::Instant;
     use walkdir::WalkDir;
 
@@ -123,24 +336,48 @@ fn build_project(root: &str, out_dir: &str, minify: bool) -> anyhow::Result<()>
         return Err(anyhow::anyhow!("Source directory not found: {}", src_dir.display()));
     }
 
+    // Entries must be compiled sources, so each maps to one output
+    let entries = entries
+        .iter()
+        .map(|(entry, environment)| {
+            let path = root_path.join(entry);
+            let compiled = path.extension().is_some_and(|ext| ["tsx", "ts", "jsx", "js"].iter().any(|e| ext == *e));
+            if !path.is_file() || !path.starts_with(&src_dir) || !compiled {
+                return Err(anyhow::anyhow!("Entry point must be a .tsx/.ts/.jsx/.js file in src/: {}", entry));
+            }
+            Ok((path, *environment))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    // Worker entries are compiled for a worker; the modules they import are
+    // compiled once, for the browser, like any other
+    let worker_options = CompilerOptions {
+        environment: Environment::Worker,
+        ..options.clone()
+    };
+
     // Create output directory
     fs::create_dir_all(&out_path)?;
 
-    println!("📂 Source: {}", src_dir.display());
-    println!("📂 Output: {}", out_path.display());
-    println!();
+    info!("📂 Source: {}", src_dir.display());
+    info!("📂 Output: {}", out_path.display());
+    verbose!("Root: {}", fs::canonicalize(&root_path)?.display());
+    verbose!("Target: {}, minify: {:?}", options.target, options.minify);
+    info!();
 
     // Walk directory and find all source files
+    let phase_start = Instant::now();
+    let ignore = ignore::IgnoreList::load(&root_path)?;
     let mut files_to_compile = Vec::new();
     for entry in WalkDir::new(&src_dir)
         .follow_links(true)
         .into_iter()
+        .filter_entry(|e| !ignore.is_ignored(e.path()))
         .filter_map(|e| e.ok())
     {
         let path = entry.path();
         if path.is_file() {
             if let Some(ext) = path.extension() {
-                if ext == "tsx" || ext == "ts" || ext == "jsx" || ext == "js" {
+                if ext == "tsx" || ext == "ts" || ext == "jsx" || ext == "js" || is_css_module(path) {
                     files_to_compile.push(path.to_path_buf());
                 }
             }
@@ -148,95 +385,237 @@ fn build_project(root: &str, out_dir: &str, minify: bool) -> anyhow::Result<()>
     }
 
     if files_to_compile.is_empty() {
-        println!("⚠️  No source files found in {}", src_dir.display());
+        warning!("⚠️  No source files found in {}", src_dir.display());
         return Ok(());
     }
 
-    println!("🔍 Found {} file(s) to compile", files_to_compile.len());
-    println!();
+    info!("🔍 Found {} file(s) to compile", files_to_compile.len());
+    verbose!("Scanned src/ in {}", log::ms(phase_start.elapsed()));
+    info!();
 
     let build_start = Instant::now();
     let mut compiled_count = 0;
     let mut error_count = 0;
+    let mut manifest = manifest::BuildManifest::new(options);
+    // Scoped CSS extracted from each module, keyed by source path
+    let mut styles: BTreeMap<PathBuf, String> = BTreeMap::new();
+    // Translatable messages of each module, keyed by source path
+    let mut messages: BTreeMap<PathBuf, Vec<i18n::Message>> = BTreeMap::new();
+    // Compiled sources with their unhashed output paths, for `--legacy`
+    let mut sources: Vec<(PathBuf, String)> = Vec::new();
 
     // Compile each file
     for input_path in &files_to_compile {
-        // Calculate output path (maintain directory structure)
+        // Calculate output path (maintain directory structure);
+        // `Button.module.css` compiles to `Button.module.css.js`
         let relative_path = input_path.strip_prefix(&src_dir)?;
-        let output_path = out_path.join(relative_path).with_extension("js");
+        let output_path = if is_css_module(input_path) {
+            out_path.join(format!("{}.js", relative_path.display()))
+        } else {
+            out_path.join(relative_path).with_extension("js")
+        };
 
         // Create parent directories if needed
         if let Some(parent) = output_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        print!("  📄 {} → ", relative_path.display());
-
-        match compile_file(input_path, Some(&output_path), minify, false, false) {
-            Ok(_) => {
-                println!("✅");
+        let file_start = Instant::now();
+        let compiled = if is_css_module(input_path) {
+            compile_css_module(input_path, &root_path, &output_path)
+        } else {
+            let worker = entries.contains(&(input_path.clone(), Environment::Worker));
+            compile_file(input_path, &root_path, Some(&output_path), if worker { &worker_options } else { options }, false)
+        };
+
+        match compiled {
+            Ok(compiled) => {
+                info!("  📄 {} → ✅", relative_path.display());
+                verbose!("{} in {}", output_path.display(), log::ms(file_start.elapsed()));
                 compiled_count += 1;
+                manifest.add_output(&root_path, &out_path, input_path, &output_path)?;
+                let output = output_path.strip_prefix(&out_path)?.to_string_lossy().replace('\\', "/");
+                sources.push((input_path.clone(), output));
+                if let Some(css) = compiled.css {
+                    styles.insert(relative_path.to_path_buf(), css);
+                }
+                if !compiled.messages.is_empty() {
+                    messages.insert(relative_path.to_path_buf(), compiled.messages);
+                }
             }
             Err(e) => {
-                println!("❌");
-                eprintln!("     Error: {}", e);
+                info!("  📄 {} → ❌", relative_path.display());
+                eprintln!("     Error in {}: {}", relative_path.display(), e);
                 error_count += 1;
             }
         }
     }
 
+    // Stylesheets, images and data files referenced by the compiled modules
+    let phase_start = Instant::now();
+    let assets = assets::copy_assets(&root_path, &out_path, options.minify != MinifyLevel::None)?;
+    for asset in &assets {
+        info!("  📁 {} → {}", asset.source.display(), asset.output.display());
+    }
+    verbose!("Copied assets in {}", log::ms(phase_start.elapsed()));
+
     let build_duration = build_start.elapsed();
 
-    println!();
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    println!("📊 Build Summary:");
-    println!("   ✅ Compiled: {} file(s)", compiled_count);
+    info!();
+    info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    info!("📊 Build Summary:");
+    info!("   ✅ Compiled: {} file(s)", compiled_count);
+    if !assets.is_empty() {
+        info!("   📁 Assets:   {} file(s)", assets.len());
+    }
     if error_count > 0 {
-        println!("   ❌ Errors:   {} file(s)", error_count);
+        info!("   ❌ Errors:   {} file(s)", error_count);
     }
-    println!("   ⏱️  Time:     {:.2}ms", build_duration.as_secs_f64() * 1000.0);
-    println!("   📦 Output:   {}", out_path.display());
+    info!("   ⏱️  Time:     {:.2}ms", build_duration.as_secs_f64() * 1000.0);
+    info!("   📦 Output:   {}", out_path.display());
 
     if error_count > 0 {
         return Err(anyhow::anyhow!("Build completed with {} error(s)", error_count));
     }
 
+    // All extracted CSS goes into one stylesheet, in source path order
+    if !styles.is_empty() {
+        fs::write(out_path.join(STYLES_FILE), stylesheet(&styles))?;
+        manifest.styles = Some(STYLES_FILE.to_string());
+    }
+
+    // Messages merge in source path order, so the first default a key gets
+    // doesn't depend on the order files were walked in
+    if options.i18n.is_some() {
+        let mut catalog = i18n::Catalog::default();
+        for (source, messages) in &messages {
+            let file = source.display().to_string();
+            for conflict in catalog.add(&file, messages) {
+                warning!(
+                    "⚠️  {}: message `{}` is already \"{}\"; ignoring \"{}\"",
+                    src_dir.join(source).display(),
+                    conflict.key,
+                    conflict.kept,
+                    conflict.ignored
+                );
+            }
+        }
+        fs::write(out_path.join(MESSAGES_FILE), catalog.to_json())?;
+        manifest.messages = Some(MESSAGES_FILE.to_string());
+        info!("   🌐 Messages: {} key(s) in {}", catalog.len(), out_path.join(MESSAGES_FILE).display());
+    }
+
+    if let Some(threshold) = steps.inline_threshold {
+        let phase_start = Instant::now();
+        let entry_paths: Vec<PathBuf> = entries.iter().map(|(path, _)| path.clone()).collect();
+        let inlined = inline::inline_small_modules(&root_path, &out_path, &mut manifest, &entry_paths, threshold)?;
+        verbose!("Inlined small modules in {}", log::ms(phase_start.elapsed()));
+        if inlined > 0 {
+            info!("   🧩 Inlined:  {} module(s) of at most {} bytes", inlined, threshold);
+        }
+    }
+
+    if steps.hashed {
+        let phase_start = Instant::now();
+        hashing::hash_outputs(&root_path, &out_path, &mut manifest)?;
+        verbose!("Hashed {} output(s) in {}", manifest.hashed.len(), log::ms(phase_start.elapsed()));
+        if root_path.join("index.html").is_file() {
+            info!("   🔗 HTML:     {}", out_path.join("index.html").display());
+        }
+    }
+
+    let mut html = if steps.hashed { out_path.join("index.html") } else { root_path.join("index.html") };
+    let preloaded = preload::write_preload_hints(&root_path, &out_path, &html, &manifest, steps.preload)?;
+    if preloaded > 0 {
+        info!("   ⚡ Preload:  {} module(s) in {}", preloaded, out_path.join("index.html").display());
+        html = out_path.join("index.html");
+    }
+
+    if steps.legacy {
+        let phase_start = Instant::now();
+        for bundle in legacy::write_legacy_bundles(&root_path, &out_path, &html, &sources, options, &mut manifest)? {
+            info!("   🧓 Legacy:   {}", out_path.join(bundle).display());
+        }
+        verbose!("Built legacy bundles in {}", log::ms(phase_start.elapsed()));
+    }
+
+    if let Some(styles) = &manifest.styles {
+        info!("   🎨 Styles:   {}", out_path.join(styles).display());
+    }
+
+    // Recorded after hashing so entries point at the final file names.
+    // Modules shared by several entries are compiled once and imported by each.
+    for (entry, _) in &entries {
+        manifest.add_entry(&root_path, entry);
+    }
+    for (source, output) in &manifest.entries {
+        info!("   🚪 Entry:    {} → {}", source, out_path.join(output).display());
+    }
+
+    // Only successful builds get a manifest, so deploy scripts never see a partial one
+    manifest.write(&out_path)?;
+    info!("   🧾 Manifest: {}", out_path.join(manifest::MANIFEST_FILE).display());
+
     Ok(())
 }
 
-/// Compile a file with given options
+/// What compiling one file extracted besides its code
+#[derive(Default)]
+struct CompiledFile {
+    /// Scoped CSS
+    css: Option<String>,
+    /// Messages marked for translation, with `--extract-messages`
+    messages: Vec<i18n::Message>,
+}
+
+/// Compile a file with given options, returning the CSS and messages it
+/// extracted
 fn compile_file(
     input: &Path,
+    root: &Path,
     output: Option<&Path>,
-    minify: bool,
-    no_optimize: bool,
+    options: &CompilerOptions,
     show_time: bool,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<CompiledFile> {
     // Create compiler with options
-    let options = CompilerOptions {
-        optimize: !no_optimize,
-        source_maps: true,
-        target: "es2020".to_string(),
-        minify,
-    };
-
-    let compiler = Compiler::new(options);
+    let compiler = Compiler::new(options.clone());
 
     // Read input file
     let source = fs::read_to_string(input)
         .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", input.display(), e))?;
+    verbose!("Input: {} ({} bytes)", fs::canonicalize(input)?.display(), source.len());
 
     // Compile with source map
     let start = Instant::now();
-    let result = compiler.compile_with_source_map(&source, input.to_str().unwrap())?;
+    let result = compiler.compile_full(&source, &project_path(root, input))?;
     let duration = start.elapsed();
 
     if show_time {
-        println!("✅ Compiled in {:.2}ms", duration.as_secs_f64() * 1000.0);
+        info!("✅ Compiled in {}", log::ms(duration));
+    }
+
+    for diagnostic in &result.diagnostics {
+        let (line, column) = diagnostic.location(&source);
+        warning!("⚠️  {}:{}:{}: {}", input.display(), line, column, diagnostic);
+    }
+
+    // Referenced files are copied as they are, so a typo only shows up as a 404
+    let dir = input.parent().unwrap_or(Path::new(""));
+    for asset in &result.assets {
+        if !dir.join(asset.path()).is_file() {
+            let (line, column) = asset.location(&source);
+            warning!("⚠️  {}:{}:{}: `{}` does not exist", input.display(), line, column, asset.specifier);
+        }
     }
 
     // Write output
     if let Some(output_path) = output {
+        // Create parent directories if needed (the .js.map shares the same parent)
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| anyhow::anyhow!("Failed to create directory {}: {}", parent.display(), e))?;
+        }
+
         // Write source map if generated
         let mut final_code = result.code.clone();
         if let Some(source_map) = &result.source_map {
@@ -248,21 +627,171 @@ fn compile_file(
             final_code.push_str(&format!("\n//# sourceMappingURL={}\n", map_path.file_name().unwrap().to_str().unwrap()));
 
             if show_time {
-                println!("🗺️  Source map written to {}", map_path.display());
+                info!("🗺️  Source map written to {}", map_path.display());
             }
         }
 
         // Write JavaScript file with source mapping URL
+        let write_start = Instant::now();
         fs::write(output_path, final_code)
             .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", output_path.display(), e))?;
 
         if show_time {
-            println!("📝 Output written to {}", output_path.display());
+            info!("📝 Output written to {}", output_path.display());
+            verbose!("Wrote output in {}", log::ms(write_start.elapsed()));
         }
     } else {
         println!("\n{}", result.code);
     }
 
+    Ok(CompiledFile {
+        css: result.css,
+        messages: result.messages,
+    })
+}
+
+/// Compile a CSS module into a JS module exporting its scoped class names,
+/// returning the scoped CSS
+fn compile_css_module(input: &Path, root: &Path, output: &Path) -> anyhow::Result<CompiledFile> {
+    let source = fs::read_to_string(input)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", input.display(), e))?;
+
+    let scoped = css::scope_css(&source, &project_path(root, input));
+    fs::write(output, scoped.to_module())
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", output.display(), e))?;
+
+    Ok(CompiledFile {
+        css: Some(scoped.css),
+        ..Default::default()
+    })
+}
+
+/// One stylesheet of the CSS extracted from each source, in source path
+/// order, each under a comment naming its source
+fn stylesheet(styles: &BTreeMap<PathBuf, String>) -> String {
+    let mut stylesheet = String::new();
+    for (source, css) in styles {
+        stylesheet.push_str(&format!("/* {} */\n{}\n\n", source.display(), css.trim()));
+    }
+    stylesheet
+}
+
+/// Write CSS extracted from a single compiled file next to its output
+/// (`app.js` → `app.css`)
+fn write_css_sidecar(output: &Path, css: &str) -> anyhow::Result<()> {
+    let css_path = output.with_extension("css");
+    fs::write(&css_path, css)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", css_path.display(), e))?;
+    info!("🎨 Styles written to {}", css_path.display());
+    Ok(())
+}
+
+/// Name `path` is compiled under: its path relative to the project `root`
+/// with `/` separators (`src/Button.tsx`). Scoped class names and HMR keys
+/// are derived from it, so they don't change with the working directory or
+/// with how the path was written. A path outside `root` is kept as given.
+fn project_path(root: &Path, path: &Path) -> String {
+    let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    match canonical(path).strip_prefix(canonical(root)) {
+        Ok(relative) => relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => path.to_string_lossy().replace('\\', "/"),
+    }
+}
+
+/// Project root of a file compiled on its own: the nearest directory above
+/// it with a `package.json`, or its own directory
+fn project_root(path: &Path) -> PathBuf {
+    let dir = fs::canonicalize(path)
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.parent().unwrap_or(Path::new("")).to_path_buf());
+    dir.ancestors()
+        .find(|dir| dir.join("package.json").is_file())
+        .unwrap_or(&dir)
+        .to_path_buf()
+}
+
+/// Whether `path` is a CSS module (`*.module.css`)
+fn is_css_module(path: &Path) -> bool {
+    path.to_string_lossy().ends_with(css::CSS_MODULE_SUFFIX)
+}
+
+/// Compile source read from stdin and write the JavaScript to `output`, or
+/// to stdout without one.
+///
+/// Nothing but the compiled code is printed so the command can be used as a
+/// subprocess by editors and formatters.
+fn compile_stdin(filename: &str, output: Option<&Path>, minify: MinifyLevel, no_optimize: bool) -> anyhow::Result<()> {
+    use std::io::{Read, Write};
+
+    let options = CompilerOptions {
+        source_maps: false,
+        ..compiler_options(minify, no_optimize)
+    };
+
+    let mut source = String::new();
+    std::io::stdin()
+        .read_to_string(&mut source)
+        .map_err(|e| anyhow::anyhow!("Failed to read stdin: {}", e))?;
+
+    let start = Instant::now();
+    let code = Compiler::new(options).compile(&source, filename)?;
+    verbose!("Compiled {} in {}", filename, log::ms(start.elapsed()));
+
+    if let Some(output) = output {
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| anyhow::anyhow!("Failed to create directory {}: {}", parent.display(), e))?;
+        }
+        return fs::write(output, code).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", output.display(), e));
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(code.as_bytes())?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// Machine-readable form of a failed compile for `--error-format json`.
+/// `code` and `category` are null for errors outside the compiler (e.g. a
+/// missing input file).
+fn error_json(error: &anyhow::Error) -> serde_json::Value {
+    let compiler_error = error.downcast_ref::<CompilerError>();
+    serde_json::json!({
+        "code": compiler_error.map(|e| e.code()),
+        "category": compiler_error.map(|e| e.category().as_str()),
+        "message": error.to_string(),
+    })
+}
+
+/// Size of `bytes` after gzip compression, as served by most CDNs
+fn gzipped_size(bytes: &[u8]) -> anyhow::Result<usize> {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(bytes)?;
+    Ok(encoder.finish()?.len())
+}
+
+/// Print the reactive dependency graph of `input` as DOT or JSON
+fn print_dependency_graph(input: &Path, format: &str) -> anyhow::Result<()> {
+    let source = fs::read_to_string(input)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", input.display(), e))?;
+    let compiler = Compiler::new(compiler_options(MinifyLevel::None, false));
+    let graph = compiler.dependency_graph(&source, &input.to_string_lossy())?;
+    verbose!("{} nodes, {} edges", graph.nodes.len(), graph.edges.len());
+
+    match format {
+        "dot" => print!("{}", graph.to_dot()),
+        "json" => println!("{}", serde_json::to_string_pretty(&graph)?),
+        other => anyhow::bail!("Unknown graph format '{}' (expected dot or json)", other),
+    }
     Ok(())
 }
 
@@ -297,41 +826,95 @@ fn analyze_bundle(root: &str, out_dir: &str, format: &str) -> anyhow::Result<()>
         file_count: usize,
         files: Vec<FileInfo>,
         largest_files: Vec<FileInfo>,
+        duplicates: duplicates::DuplicateReport,
+        /// Modules `--inline-threshold` moved into their importers
+        inlined_modules: usize,
     }
 
-    // Collect all JS files with their sizes
-    let mut files = Vec::new();
-    let mut total_size: u64 = 0;
+    /// Nested `{name, value, children}` node understood by common treemap viewers
+    #[derive(Serialize)]
+    struct TreemapNode {
+        name: String,
+        value: u64,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        children: Vec<TreemapNode>,
+    }
 
-    for entry in WalkDir::new(&dist_path)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-        if path.is_file() {
-            if let Some(ext) = path.extension() {
-                if ext == "js" {
-                    let metadata = fs::metadata(path)?;
-                    let size = metadata.len();
-                    total_size += size;
+    impl TreemapNode {
+        fn new(name: &str) -> Self {
+            Self {
+                name: name.to_string(),
+                value: 0,
+                children: Vec::new(),
+            }
+        }
 
+        /// Insert a file by its path components, adding its size to every ancestor
+        fn insert(&mut self, components: &[&str], size: u64) {
+            self.value += size;
+            if let Some((first, rest)) = components.split_first() {
+                let index = match self.children.iter().position(|c| c.name == *first) {
+                    Some(index) => index,
+                    None => {
+                        self.children.push(TreemapNode::new(first));
+                        self.children.len() - 1
+                    }
+                };
+                self.children[index].insert(rest, size);
+            }
+        }
+    }
+
+    // Collect all JS files with their sizes, preferring the build manifest
+    let manifest = manifest::BuildManifest::read(&dist_path);
+    let inlined_modules = manifest.as_ref().map_or(0, |manifest| manifest.inlined.len());
+    let outputs: Vec<(String, u64)> = match manifest {
+        Some(manifest) => manifest
+            .outputs
+            .into_iter()
+            .map(|entry| (entry.output, entry.size))
+            .collect(),
+        None => {
+            let mut outputs = Vec::new();
+            for entry in WalkDir::new(&dist_path)
+                .follow_links(true)
+                .into_iter()
+                .filter_map(|e| e.ok())
+            {
+                let path = entry.path();
+                if path.is_file() && path.extension().is_some_and(|ext| ext == "js") {
                     let relative_path = path
                         .strip_prefix(&dist_path)
                         .unwrap_or(path)
                         .to_string_lossy()
                         .to_string();
-
-                    files.push(FileInfo {
-                        path: relative_path,
-                        size,
-                        size_kb: size as f64 / 1024.0,
-                        percentage: 0.0, // Will calculate after total is known
-                    });
+                    outputs.push((relative_path, fs::metadata(path)?.len()));
                 }
             }
+            outputs
         }
-    }
+    };
+
+    verbose!("Read {} output(s) from {}", outputs.len(), fs::canonicalize(&dist_path)?.display());
+
+    let phase_start = Instant::now();
+    let contents: Vec<(String, String)> = outputs
+        .iter()
+        .filter_map(|(path, _)| Some((path.clone(), fs::read_to_string(dist_path.join(path)).ok()?)))
+        .collect();
+    let duplicates = duplicates::find_duplicates(&contents);
+    verbose!("Looked for duplicate modules in {}", log::ms(phase_start.elapsed()));
+
+    let total_size: u64 = outputs.iter().map(|(_, size)| size).sum();
+    let mut files: Vec<FileInfo> = outputs
+        .into_iter()
+        .map(|(path, size)| FileInfo {
+            path,
+            size,
+            size_kb: size as f64 / 1024.0,
+            percentage: 0.0, // Will calculate after total is known
+        })
+        .collect();
 
     // Calculate percentages
     for file in &mut files {
@@ -350,6 +933,8 @@ fn analyze_bundle(root: &str, out_dir: &str, format: &str) -> anyhow::Result<()>
         file_count: files.len(),
         files: files.clone(),
         largest_files,
+        duplicates,
+        inlined_modules,
     };
 
     // Output based on format
@@ -358,6 +943,15 @@ fn analyze_bundle(root: &str, out_dir: &str, format: &str) -> anyhow::Result<()>
             let json = serde_json::to_string_pretty(&analysis)?;
             println!("{}", json);
         }
+        "treemap" => {
+            let mut root_node = TreemapNode::new(out_dir);
+            for file in &analysis.files {
+                let components: Vec<&str> = file.path.split(['/', '\\']).collect();
+                root_node.insert(&components, file.size);
+            }
+            let json = serde_json::to_string_pretty(&root_node)?;
+            println!("{}", json);
+        }
         _ => {
             // Text format (default)
             println!();
@@ -373,6 +967,9 @@ fn analyze_bundle(root: &str, out_dir: &str, format: &str) -> anyhow::Result<()>
                 analysis.total_size_kb,
                 analysis.total_size
             );
+            if analysis.inlined_modules > 0 {
+                println!("{} {} modules", "🧩 Inlined:".bright_white(), analysis.inlined_modules);
+            }
             println!();
 
             if !analysis.largest_files.is_empty() {
@@ -393,6 +990,33 @@ fn analyze_bundle(root: &str, out_dir: &str, format: &str) -> anyhow::Result<()>
                 println!();
             }
 
+            let duplicates = &analysis.duplicates;
+            if duplicates.wasted_bytes > 0 {
+                println!(
+                    "{} {:.2} KB could be saved",
+                    "♻️  Duplicate Modules:".bright_white().bold(),
+                    duplicates.wasted_bytes as f64 / 1024.0
+                );
+                for group in &duplicates.identical {
+                    println!(
+                        "  {} identical ({:.2} KB wasted): {}",
+                        "•".bright_yellow(),
+                        group.wasted_bytes as f64 / 1024.0,
+                        group.files.join(", ").bright_cyan()
+                    );
+                }
+                for pair in &duplicates.similar {
+                    println!(
+                        "  {} {:.0}% similar (~{:.2} KB wasted): {}",
+                        "•".bright_yellow(),
+                        pair.similarity * 100.0,
+                        pair.wasted_bytes as f64 / 1024.0,
+                        pair.files.join(", ").bright_cyan()
+                    );
+                }
+                println!();
+            }
+
             // Optimization suggestions
             println!("{}", "💡 Optimization Suggestions:".bright_white().bold());
 
@@ -419,19 +1043,47 @@ fn analyze_bundle(root: &str, out_dir: &str, format: &str) -> anyhow::Result<()>
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    log::set_level(log::Level::from_flags(cli.quiet, cli.verbose));
 
     match cli.command {
-        Commands::Compile { input, output, minify, no_optimize } => {
-            println!("🔨 Compiling {}...", input.display());
-            compile_file(&input, output.as_deref(), minify, no_optimize, true)?;
+        Commands::Compile { input, stdin, filename, output, minify, minify_level: level, no_optimize, error_format } => {
+            let minify = minify_level(minify, level.as_deref())?;
+            let result = match input {
+                Some(input) if stdin && input != Path::new("-") => Err(anyhow::anyhow!(
+                    "--stdin reads the source from stdin, so {} would be ignored; pass one or the other",
+                    input.display()
+                )),
+                Some(input) if input != Path::new("-") => {
+                    info!("🔨 Compiling {}...", input.display());
+                    compile_file(&input, &project_root(&input), output.as_deref(), &compiler_options(minify, no_optimize), true).and_then(|compiled| {
+                        match (output, compiled.css) {
+                            (Some(output), Some(css)) => write_css_sidecar(&output, &css),
+                            _ => Ok(()),
+                        }
+                    })
+                }
+                _ => compile_stdin(&filename, output.as_deref(), minify, no_optimize),
+            };
+
+            if let Err(error) = result {
+                if error_format == "json" {
+                    eprintln!("{}", error_json(&error));
+                    std::process::exit(1);
+                }
+                return Err(error);
+            }
         }
 
-        Commands::Watch { input, output, minify, no_optimize } => {
-            println!("👀 Watching {}...", input.display());
-            println!("Press Ctrl+C to stop\n");
+        Commands::Watch { input, output, minify, minify_level: level, no_optimize } => {
+            let options = compiler_options(minify_level(minify, level.as_deref())?, no_optimize);
+            let root = project_root(&input);
+            info!("👀 Watching {}...", input.display());
+            info!("Press Ctrl+C to stop\n");
 
             // Initial compilation
-            compile_file(&input, Some(&output), minify, no_optimize, true)?;
+            if let Some(css) = compile_file(&input, &root, Some(&output), &options, true)?.css {
+                write_css_sidecar(&output, &css)?;
+            }
 
             // Set up file watcher
             let (tx, rx) = channel();
@@ -445,11 +1097,21 @@ async fn main() -> anyhow::Result<()> {
                 match rx.recv() {
                     Ok(Ok(event)) => {
                         use notify::EventKind;
+                        verbose!("{:?} {:?}", event.kind, event.paths);
                         match event.kind {
                             EventKind::Modify(_) | EventKind::Create(_) => {
-                                println!("\n🔄 File changed, recompiling...");
-                                match compile_file(&input, Some(&output), minify, no_optimize, true) {
-                                    Ok(_) => {},
+                                // A single save can emit several events; swallow the
+                                // rest of the burst so we only recompile once
+                                while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+
+                                info!("\n🔄 File changed, recompiling...");
+                                match compile_file(&input, &root, Some(&output), &options, true).map(|compiled| compiled.css) {
+                                    Ok(Some(css)) => {
+                                        if let Err(e) = write_css_sidecar(&output, &css) {
+                                            eprintln!("❌ {}", e);
+                                        }
+                                    }
+                                    Ok(None) => {},
                                     Err(e) => eprintln!("❌ Compilation error: {}", e),
                                 }
                             }
@@ -465,40 +1127,91 @@ async fn main() -> anyhow::Result<()> {
             }
         }
 
-        Commands::Build { root, out_dir, minify } => {
-            println!("📦 Building project from {}...", root);
-            build_project(&root, &out_dir, minify)?;
+        Commands::Build {
+            root,
+            out_dir,
+            minify,
+            minify_level: level,
+            hashed,
+            entries,
+            workers,
+            preload,
+            legacy,
+            profile,
+            inline_threshold,
+            extract_messages,
+            i18n_function,
+            i18n_component,
+            strip_message_defaults,
+        } => {
+            let mut options = if profile {
+                profile_options()
+            } else {
+                compiler_options(minify_level(minify, level.as_deref())?, false)
+            };
+            if extract_messages {
+                options.i18n = Some(I18nOptions {
+                    function: i18n_function,
+                    component: i18n_component,
+                    strip_defaults: strip_message_defaults,
+                });
+            }
+            let steps = OutputSteps {
+                inline_threshold,
+                hashed,
+                preload: preload::PreloadStrategy::parse(&preload)?,
+                legacy,
+            };
+            info!("📦 Building project from {}{}...", root, if profile { " for profiling" } else { "" });
+            let entries: Vec<_> = entries
+                .into_iter()
+                .map(|entry| (entry, Environment::Browser))
+                .chain(workers.into_iter().map(|worker| (worker, Environment::Worker)))
+                .collect();
+            build_project(&root, &out_dir, &options, &entries, &steps)?;
         }
 
-        Commands::Dev { port, root } => {
-            dev_server::start_dev_server(port, root).await?;
+        Commands::Dev { port, root, base, hmr_path, workspaces } => {
+            dev_server::start_dev_server(port, root, &base, &hmr_path, &workspaces).await?;
         }
 
         Commands::Analyze { root, out_dir, format } => {
-            println!("📊 Analyzing bundle from {}...", root);
+            // Keep machine-readable formats clean for piping
+            if format == "text" {
+                info!("📊 Analyzing bundle from {}...", root);
+            }
             analyze_bundle(&root, &out_dir, &format)?;
         }
 
+        Commands::Graph { input, format } => {
+            print_dependency_graph(&input, &format)?;
+        }
+
         Commands::Create { name, template } => {
             create::create_project(&name, &template)?;
         }
 
+        Commands::Check { root } => {
+            check::check_project(&root)?;
+        }
+
         Commands::Info => {
             println!("\n{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
             println!("{} {}", "⚡".bright_yellow(), format!("Velocity Framework v{}", env!("CARGO_PKG_VERSION")).bright_cyan().bold());
             println!("{}", "Lightning-fast JavaScript framework".bright_black());
             println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
 
+            println!("\n{}", "BUILD".bright_white().bold());
+            println!("  {} Version: {}", "•".bright_green(), env!("CARGO_PKG_VERSION"));
+            println!("  {} Commit: {}", "•".bright_green(), env!("VELOCITY_GIT_HASH"));
+
             println!("\n{}", "CORE COMPONENTS".bright_white().bold());
-            println!("  {} Rust/WASM Runtime (33KB)", "•".bright_green());
+            println!("  {} Rust/WASM Runtime", "•".bright_green());
             println!("    {} Fine-grained reactivity with Signals", "→".bright_black());
             println!("    {} Zero Virtual DOM overhead", "→".bright_black());
             println!("  {} Rust Compiler (SWC-based)", "•".bright_green());
-            println!("    {} {} faster than Webpack/Babel", "→".bright_black(), "10-40x".bright_yellow().bold());
-            println!("    {} <1ms compilation per file", "→".bright_black());
             println!("  {} Development Server", "•".bright_green());
             println!("    {} WebSocket-based HMR", "→".bright_black());
-            println!("    {} <50ms update cycle", "→".bright_black());
 
             println!("\n{}", "AVAILABLE COMMANDS".bright_white().bold());
             println!("  {} {} - Compile a single file", "velocity compile".bright_cyan(), "<file>".bright_black());
@@ -506,24 +1219,24 @@ async fn main() -> anyhow::Result<()> {
             println!("  {} - Build entire project", "velocity build".bright_cyan());
             println!("  {} - Development server with HMR", "velocity dev".bright_cyan());
 
-            println!("\n{}", "DEVELOPMENT STATUS".bright_white().bold());
-            println!("  {} Phase 1: WASM Runtime", "✅".green());
-            println!("  {} Phase 2: Rust Compiler", "✅".green());
-            println!("  {} Phase 3: CLI & Dev Tools", "✅".green());
-            println!("    {} Single file compilation", "✓".bright_green());
-            println!("    {} Watch mode", "✓".bright_green());
-            println!("    {} Multi-file builds", "✓".bright_green());
-            println!("    {} Development server", "✓".bright_green());
-            println!("    {} Hot Module Replacement", "✓".bright_green());
-            println!("  {} Phase 4: Partial Hydration", "⏳".yellow());
-            println!("  {} Phase 5: Unified Data Layer", "⏳".yellow());
-            println!("  {} Phase 6: SSR Streaming", "⏳".yellow());
-
-            println!("\n{}", "PERFORMANCE".bright_white().bold());
-            println!("  {} Compile: ~1ms per file", "⚡".bright_yellow());
-            println!("  {} Build: ~5ms for 3 files", "⚡".bright_yellow());
-            println!("  {} HMR: <50ms total update", "⚡".bright_yellow());
-            println!("  {} Runtime: 33KB (gzipped)", "⚡".bright_yellow());
+            println!("\n{}", "RUNTIME SIZE".bright_white().bold());
+            let mut found = false;
+            for artifact in RUNTIME_ARTIFACTS {
+                let Ok(bytes) = fs::read(artifact) else {
+                    continue;
+                };
+                found = true;
+                println!(
+                    "  {} {}: {:.2} KB ({:.2} KB gzipped)",
+                    "⚡".bright_yellow(),
+                    artifact,
+                    bytes.len() as f64 / 1024.0,
+                    gzipped_size(&bytes)? as f64 / 1024.0
+                );
+            }
+            if !found {
+                println!("  {} No runtime build found in this project", "•".bright_black());
+            }
 
             println!("\n{} {}", "Repository:".bright_black(), "https://github.com/yourname/velocity-framework".bright_blue());
             println!("{} {}\n", "License:".bright_black(), "MIT".bright_green());
@@ -532,3 +1245,27 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_path() {
+        let root = std::env::temp_dir().join(format!("velocity-project-path-{}", std::process::id()));
+        fs::create_dir_all(root.join("src/components")).unwrap();
+        fs::write(root.join("package.json"), "{}").unwrap();
+        let button = root.join("src/components/Button.tsx");
+        fs::write(&button, "").unwrap();
+
+        // However the file is named, it compiles under the same name
+        assert_eq!(project_path(&root, &button), "src/components/Button.tsx");
+        assert_eq!(project_path(&root.join("."), &root.join("src/../src/components/./Button.tsx")), "src/components/Button.tsx");
+        assert_eq!(project_root(&button), fs::canonicalize(&root).unwrap());
+        assert_eq!(project_path(&project_root(&button), &button), "src/components/Button.tsx");
+
+        assert_eq!(project_path(&root.join("src"), Path::new("missing/App.tsx")), "missing/App.tsx");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use velocity_compiler::{CompilerOptions, MinifyLevel};
+
+/// File name of the manifest written into the output directory
+pub const MANIFEST_FILE: &str = "velocity-manifest.json";
+
+/// Machine-readable record of what `velocity build` produced
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuildManifest {
+    /// Velocity version that produced the build
+    pub version: String,
+    /// Build time in seconds since the Unix epoch
+    pub built_at: u64,
+    pub options: ManifestOptions,
+    pub outputs: Vec<ManifestEntry>,
+    /// Stylesheet of extracted component CSS, relative to the output directory
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub styles: Option<String>,
+    /// Catalog of translatable messages, relative to the output directory,
+    /// for builds with `--extract-messages`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub messages: Option<String>,
+    /// Logical output path → content-hashed file name, for `--hashed` builds
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub hashed: BTreeMap<String, String>,
+    /// Entry source path (relative to the project root) → its output, for
+    /// builds with `--entry`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub entries: BTreeMap<String, String>,
+    /// Module script output → its `nomodule` bundle, for builds with `--legacy`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub legacy: BTreeMap<String, String>,
+    /// Source path of each module moved into its importers → the outputs it
+    /// was inlined into, for builds with `--inline-threshold`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub inlined: BTreeMap<String, Vec<String>>,
+}
+
+/// The `CompilerOptions` a build used (custom passes are not recorded)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestOptions {
+    pub optimize: bool,
+    pub source_maps: bool,
+    pub target: String,
+    pub minify: bool,
+    /// Components are timed with `performance` marks (`velocity build --profile`)
+    #[serde(default)]
+    pub profile: bool,
+}
+
+/// One compiled output file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Output path relative to the output directory, `/`-separated
+    pub output: String,
+    /// Source path relative to the project root, `/`-separated
+    pub source: String,
+    /// Size of the output in bytes
+    pub size: u64,
+    /// SHA-256 of the output, hex encoded
+    pub hash: String,
+    /// Source map path relative to the output directory, if one was written
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub source_map: Option<String>,
+}
+
+impl BuildManifest {
+    pub fn new(options: &CompilerOptions) -> Self {
+        let built_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            built_at,
+            options: ManifestOptions {
+                optimize: options.optimize,
+                source_maps: options.source_maps,
+                target: options.target.clone(),
+                minify: options.minify != MinifyLevel::None,
+                profile: options.profile_components,
+            },
+            outputs: Vec::new(),
+            styles: None,
+            messages: None,
+            hashed: BTreeMap::new(),
+            entries: BTreeMap::new(),
+            legacy: BTreeMap::new(),
+            inlined: BTreeMap::new(),
+        }
+    }
+
+    /// Record a compiled file, reading its final size and hash from disk
+    pub fn add_output(&mut self, root: &Path, out_dir: &Path, source: &Path, output: &Path) -> anyhow::Result<()> {
+        let bytes = fs::read(output)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", output.display(), e))?;
+
+        let map_path = output.with_extension("js.map");
+        let source_map = map_path.is_file().then(|| relative_path(out_dir, &map_path));
+
+        self.outputs.push(ManifestEntry {
+            output: relative_path(out_dir, output),
+            source: relative_path(root, source),
+            size: bytes.len() as u64,
+            hash: content_hash(&bytes),
+            source_map,
+        });
+        Ok(())
+    }
+
+    /// Record an entry point, mapping it to the output compiled from it
+    pub fn add_entry(&mut self, root: &Path, source: &Path) {
+        let source = relative_path(root, source);
+        if let Some(output) = self.outputs.iter().find(|entry| entry.source == source) {
+            self.entries.insert(source, output.output.clone());
+        }
+    }
+
+    /// Write the manifest into `out_dir`
+    pub fn write(&self, out_dir: &Path) -> anyhow::Result<()> {
+        let path = out_dir.join(MANIFEST_FILE);
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(&path, json)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Read the manifest from `out_dir`, if a build wrote one
+    pub fn read(out_dir: &Path) -> Option<Self> {
+        let json = fs::read_to_string(out_dir.join(MANIFEST_FILE)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+}
+
+/// Hex-encoded SHA-256 of `bytes`
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `path` relative to `base` with `/` separators on every platform
+fn relative_path(base: &Path, path: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,187 @@
+//! Module preload hints for `velocity build --preload`
+//!
+//! The browser only discovers a module's imports after fetching and parsing
+//! it, so a deep import graph loads one level per round trip. Listing the
+//! modules a page will need as `<link rel="modulepreload">` in `index.html`
+//! lets them download in parallel with the entry script.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use velocity_compiler::imports;
+use crate::hashing::{find_url_attribute, out_dir_url, resolve_import};
+use crate::manifest::BuildManifest;
+
+/// Which modules get a preload hint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreloadStrategy {
+    /// No hints
+    None,
+    /// Everything the page's entry scripts import statically
+    Static,
+    /// `static`, plus the entries' own dynamic imports (typically the lazy
+    /// default route) and everything those import statically
+    Dynamic,
+}
+
+impl PreloadStrategy {
+    pub fn parse(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "none" => Ok(Self::None),
+            "static" => Ok(Self::Static),
+            "dynamic" => Ok(Self::Dynamic),
+            other => Err(anyhow::anyhow!(
+                "Unknown preload strategy '{}' (expected none, static or dynamic)",
+                other
+            )),
+        }
+    }
+}
+
+/// Output path → the outputs it imports, with whether each import is dynamic
+type ModuleGraph = HashMap<String, Vec<(String, bool)>>;
+
+/// Write `index.html` into `out_dir` with preload hints for the modules
+/// `strategy` picks, returning how many were added.
+///
+/// `html` is the page to add them to: the project's `index.html`, or the
+/// copy `--hashed` rewrote, whose URLs match the hashed names in `manifest`.
+/// Entries are the compiled outputs the page references (`<script src>`).
+pub fn write_preload_hints(
+    root: &Path,
+    out_dir: &Path,
+    html: &Path,
+    manifest: &BuildManifest,
+    strategy: PreloadStrategy,
+) -> anyhow::Result<usize> {
+    if strategy == PreloadStrategy::None {
+        return Ok(0);
+    }
+    let Ok(html) = fs::read_to_string(html) else {
+        return Ok(0);
+    };
+
+    let mut code = HashMap::new();
+    for entry in &manifest.outputs {
+        let path = out_dir.join(&entry.output);
+        let source = fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        code.insert(entry.output.clone(), source);
+    }
+
+    let mut graph = ModuleGraph::new();
+    for (output, source) in &code {
+        let imports = imports::find_imports(source, output)?
+            .into_iter()
+            .filter_map(|import| Some((resolve_import(output, &import.specifier, &code)?, import.dynamic)))
+            .collect();
+        graph.insert(output.clone(), imports);
+    }
+
+    // Outputs the page already references, and the URL prefix it uses for them
+    let out_name = out_dir_url(root, out_dir);
+    let mut referenced = Vec::new();
+    let mut prefix = "/".to_string();
+    let mut rest = html.as_str();
+    while let Some(index) = find_url_attribute(rest) {
+        let after = &rest[index..];
+        let quote = after.chars().next().unwrap_or('"');
+        let Some(end) = after[1..].find(quote) else { break };
+        let url = &after[1..1 + end];
+
+        let path = url.trim_start_matches("./").trim_start_matches('/');
+        if let Some(output) = path.strip_prefix(&format!("{}/", out_name)).filter(|o| graph.contains_key(*o)) {
+            if referenced.is_empty() {
+                prefix = url[..url.len() - path.len()].to_string();
+            }
+            referenced.push(output.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+
+    let hints: Vec<String> = preload_targets(&referenced, &graph, strategy)
+        .into_iter()
+        .map(|output| format!(r#"<link rel="modulepreload" href="{}{}/{}">"#, prefix, out_name, output))
+        .collect();
+    if hints.is_empty() {
+        return Ok(0);
+    }
+
+    fs::write(out_dir.join("index.html"), insert_into_head(&html, &hints))?;
+    Ok(hints.len())
+}
+
+/// Modules to preload for a page loading `entries`, excluding the entries
+fn preload_targets(entries: &[String], graph: &ModuleGraph, strategy: PreloadStrategy) -> BTreeSet<String> {
+    let mut roots: Vec<&String> = entries.iter().collect();
+    if strategy == PreloadStrategy::Dynamic {
+        roots.extend(
+            entries
+                .iter()
+                .filter_map(|entry| graph.get(entry))
+                .flatten()
+                .filter(|(_, dynamic)| *dynamic)
+                .map(|(output, _)| output),
+        );
+    }
+
+    // Static imports of every root, transitively
+    let mut targets = BTreeSet::new();
+    let mut seen: HashSet<&String> = HashSet::new();
+    let mut stack = roots.clone();
+    while let Some(module) = stack.pop() {
+        if !seen.insert(module) {
+            continue;
+        }
+        targets.insert(module.clone());
+        if let Some(imports) = graph.get(module) {
+            stack.extend(imports.iter().filter(|(_, dynamic)| !dynamic).map(|(output, _)| output));
+        }
+    }
+
+    for entry in entries {
+        targets.remove(entry);
+    }
+    targets
+}
+
+/// Insert `lines` at the end of `<head>`, or at the top without one
+fn insert_into_head(html: &str, lines: &[String]) -> String {
+    let position = html.find("</head>").unwrap_or(0);
+    let mut block: String = lines.iter().map(|line| format!("  {}\n", line)).collect();
+    if position == 0 {
+        block = block.trim_start().to_string();
+    }
+    format!("{}{}{}", &html[..position], block, &html[position..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preload_targets() {
+        let edges = |imports: &[(&str, bool)]| imports.iter().map(|(o, d)| (o.to_string(), *d)).collect();
+        let graph: ModuleGraph = [
+            ("index.js", edges(&[("app.js", false), ("routes/home.js", true)])),
+            ("app.js", edges(&[("lib/signals.js", false), ("routes/admin.js", true)])),
+            ("routes/home.js", edges(&[("lib/signals.js", false), ("ui/card.js", false)])),
+            ("routes/admin.js", edges(&[])),
+            ("lib/signals.js", edges(&[])),
+            ("ui/card.js", edges(&[])),
+        ]
+        .into_iter()
+        .map(|(output, imports)| (output.to_string(), imports))
+        .collect();
+        let entries = vec!["index.js".to_string()];
+
+        let targets = |strategy| preload_targets(&entries, &graph, strategy).into_iter().collect::<Vec<_>>();
+
+        assert_eq!(targets(PreloadStrategy::Static), ["app.js", "lib/signals.js"]);
+        // Only the entry's own dynamic imports: `routes/admin.js` is loaded by `app.js`
+        assert_eq!(
+            targets(PreloadStrategy::Dynamic),
+            ["app.js", "lib/signals.js", "routes/home.js", "ui/card.js"]
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Workspaces served together by `velocity dev`
+//!
+//! A monorepo's apps can share one dev server, each under its own path
+//! prefix with its own compiler options, file watcher and HMR channel.
+//! Workspaces come from `--workspace [NAME=]PATH` flags (the name defaults
+//! to the directory name) and from `velocity.workspaces.json` in the root:
+//! ```json
+//! {
+//!   "workspaces": {
+//!     "admin": "apps/admin",
+//!     "site": { "root": "apps/site", "target": "es2017", "strict": true }
+//!   }
+//! }
+//! ```
+//! Paths are relative to the dev server root. Workspace `admin` is served
+//! at `<base>admin/`, exactly as `velocity dev --root apps/admin --base
+//! /admin/` would serve it alone: its page, `dist/`, `src/`, `public/`, HMR
+//! client and HMR WebSocket all live under the prefix, so a change in one
+//! workspace only reaches pages of that workspace.
+
+use crate::dev_server;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use velocity_compiler::CompilerOptions;
+
+/// Name of the optional workspace config in the dev server root
+pub const WORKSPACE_FILE: &str = "velocity.workspaces.json";
+
+/// A project served under its own path prefix
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    /// Path segment the workspace is served under
+    pub name: String,
+    pub root: PathBuf,
+    pub compiler_options: CompilerOptions,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkspaceFile {
+    workspaces: BTreeMap<String, WorkspaceEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WorkspaceEntry {
+    Root(String),
+    Config(WorkspaceConfig),
+}
+
+/// Compiler options a workspace overrides; the rest keep the dev server's
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct WorkspaceConfig {
+    root: String,
+    target: Option<String>,
+    strict: Option<bool>,
+    optimize: Option<bool>,
+    effect_labels: Option<bool>,
+}
+
+/// The workspaces configured for the dev server at `root`: those in
+/// `root/velocity.workspaces.json`, if any, then one per `--workspace` flag.
+/// Empty when neither is used, in which case `root` is served on its own.
+pub fn load_workspaces(root: &Path, flags: &[String]) -> anyhow::Result<Vec<Workspace>> {
+    let mut workspaces = Vec::new();
+
+    let config = root.join(WORKSPACE_FILE);
+    if config.is_file() {
+        let content = fs::read_to_string(&config)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", config.display(), e))?;
+        let file: WorkspaceFile = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid {}: {}", config.display(), e))?;
+        for (name, entry) in file.workspaces {
+            workspaces.push(from_entry(root, name, entry));
+        }
+    }
+
+    for flag in flags {
+        let (name, path) = match flag.split_once('=') {
+            Some((name, path)) => (name.to_string(), path),
+            None => {
+                let name = Path::new(flag).file_name().map(|name| name.to_string_lossy().to_string());
+                (name.unwrap_or_default(), flag.as_str())
+            }
+        };
+        workspaces.push(from_entry(root, name, WorkspaceEntry::Root(path.to_string())));
+    }
+
+    for (i, workspace) in workspaces.iter().enumerate() {
+        let name = &workspace.name;
+        let valid = !name.is_empty()
+            && !name.starts_with("__")
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && name != "."
+            && name != "..";
+        if !valid {
+            anyhow::bail!("Invalid workspace name '{}': use letters, digits, '-', '_' and '.'", name);
+        }
+        if workspaces[..i].iter().any(|other| &other.name == name) {
+            anyhow::bail!("Workspace '{}' is defined more than once", name);
+        }
+        if !workspace.root.is_dir() {
+            anyhow::bail!("Workspace '{}' root not found: {}", name, workspace.root.display());
+        }
+    }
+
+    Ok(workspaces)
+}
+
+fn from_entry(root: &Path, name: String, entry: WorkspaceEntry) -> Workspace {
+    let mut compiler_options = dev_server::dev_compiler_options();
+    let path = match entry {
+        WorkspaceEntry::Root(path) => path,
+        WorkspaceEntry::Config(config) => {
+            if let Some(target) = config.target {
+                compiler_options.target = target;
+            }
+            if let Some(strict) = config.strict {
+                compiler_options.strict = strict;
+            }
+            if let Some(optimize) = config.optimize {
+                compiler_options.optimize = optimize;
+            }
+            if let Some(effect_labels) = config.effect_labels {
+                compiler_options.effect_labels = effect_labels;
+            }
+            config.root
+        }
+    };
+    Workspace {
+        name,
+        root: root.join(path),
+        compiler_options,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_workspaces() {
+        let root = std::env::temp_dir().join(format!("velocity-workspaces-{}", std::process::id()));
+        for app in ["apps/admin", "apps/site", "packages/docs"] {
+            fs::create_dir_all(root.join(app)).unwrap();
+        }
+        fs::write(
+            root.join(WORKSPACE_FILE),
+            r#"{ "workspaces": { "site": { "root": "apps/site", "target": "es2017", "strict": true }, "admin": "apps/admin" } }"#,
+        )
+        .unwrap();
+
+        let workspaces = load_workspaces(&root, &["packages/docs".to_string(), "help=apps/site".to_string()]).unwrap();
+        let names: Vec<_> = workspaces.iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(names, ["admin", "site", "docs", "help"]);
+        assert_eq!(workspaces[1].root, root.join("apps/site"));
+        assert_eq!(workspaces[1].compiler_options.target, "es2017");
+        assert!(workspaces[1].compiler_options.strict);
+        assert_eq!(workspaces[3].compiler_options.target, "es2020");
+
+        assert!(load_workspaces(&root, &["admin=apps/admin".to_string()]).is_err());
+        assert!(load_workspaces(&root, &["a/b=apps/admin".to_string()]).is_err());
+        assert!(load_workspaces(&root, &["missing=apps/missing".to_string()]).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
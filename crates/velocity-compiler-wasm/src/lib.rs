@@ -0,0 +1,341 @@
+//! Velocity Compiler for the browser
+//!
+//! A thin wasm-bindgen wrapper around [`velocity_compiler`] so playgrounds
+//! and docs can compile TSX client-side. Build it with:
+//!
+//! ```text
+//! wasm-pack build crates/velocity-compiler-wasm --target web
+//! ```
+//!
+//! ```js
+//! import init, { compile } from './velocity_compiler_wasm.js';
+//!
+//! await init();
+//! const code = compile(source, 'App.tsx', { minify: false, target: 'es2020' });
+//! ```
+//!
+//! Failures are thrown as `Error`s carrying the compiler's stable `code` and
+//! `category` (see [`velocity_compiler::error`]).
+
+use velocity_compiler::{
+    A11yRules, Compiler, CompilerError, CompilerOptions, Environment, JsxMode, MinifyLevel, ModuleFormat, ReactiveFunctions,
+    RuntimeImportMap, RuntimeImportStyle,
+};
+use wasm_bindgen::prelude::*;
+
+/// Compile a JSX/TSX module to JavaScript.
+///
+/// `options` is an optional object with any of `optimize`, `strict`,
+/// `effectLabels`, `profileComponents` (booleans), `minify` (`"none"`, `"whitespace"`, `"full"`,
+/// or a boolean for none/full), `target` (e.g. `"es2015"`), `moduleFormat`
+/// (`"esm"` or `"cjs"`), `jsx` (`"transform"`, or `"preserve"` to leave JSX
+/// for another tool), `environment` (`"browser"`, `"worker"` or `"node"`),
+/// `runtimeImports` (`"barrel"`, `"deep"`, or an
+/// object mapping helper names to modules, imported deep with the default
+/// map overridden by it), `a11y` (an object turning strict mode's
+/// accessibility rules on or off by code, e.g. `{ "a11y-click-events": false }`)
+/// and `reactiveFunctions` (an object of `signals`, `memos` and `effects`
+/// arrays naming custom reactive primitives, e.g.
+/// `{ signals: ["createLocalStorageSignal"] }`);
+/// missing fields keep their defaults.
+/// Source maps are not generated.
+#[wasm_bindgen]
+pub fn compile(source: &str, filename: &str, options: JsValue) -> Result<String, JsValue> {
+    let options = parse_options(&options).map_err(|e| error_to_js(&e))?;
+    Compiler::new(options)
+        .compile(source, filename)
+        .map_err(|e| error_to_js(&e))
+}
+
+/// Read compiler options from a JS object
+fn parse_options(value: &JsValue) -> Result<CompilerOptions, CompilerError> {
+    let mut options = CompilerOptions {
+        source_maps: false,
+        ..Default::default()
+    };
+    if value.is_undefined() || value.is_null() {
+        return Ok(options);
+    }
+    if !value.is_object() {
+        return Err(CompilerError::InvalidConfig("options must be an object".to_string()));
+    }
+
+    if let Some(optimize) = bool_option(value, "optimize")? {
+        options.optimize = optimize;
+    }
+    let minify = get(value, "minify")?;
+    if let Some(enabled) = minify.as_bool() {
+        options.minify = if enabled { MinifyLevel::Full } else { MinifyLevel::None };
+    } else if let Some(level) = minify.as_string() {
+        options.minify = MinifyLevel::parse(&level)?;
+    } else if !minify.is_undefined() {
+        return Err(CompilerError::InvalidConfig(
+            "`minify` must be a boolean or \"none\", \"whitespace\" or \"full\"".to_string(),
+        ));
+    }
+    if let Some(strict) = bool_option(value, "strict")? {
+        options.strict = strict;
+    }
+    if let Some(effect_labels) = bool_option(value, "effectLabels")? {
+        options.effect_labels = effect_labels;
+    }
+    if let Some(profile_components) = bool_option(value, "profileComponents")? {
+        options.profile_components = profile_components;
+    }
+
+    let target = get(value, "target")?;
+    if !target.is_undefined() {
+        options.target = target
+            .as_string()
+            .ok_or_else(|| CompilerError::InvalidConfig("`target` must be a string".to_string()))?;
+    }
+
+    let module_format = get(value, "moduleFormat")?;
+    if !module_format.is_undefined() {
+        options.module_format = match module_format.as_string().as_deref() {
+            Some("esm") => ModuleFormat::Esm,
+            Some("cjs") => ModuleFormat::Cjs,
+            _ => {
+                return Err(CompilerError::InvalidConfig(
+                    "`moduleFormat` must be \"esm\" or \"cjs\"".to_string(),
+                ))
+            }
+        };
+    }
+
+    let jsx = get(value, "jsx")?;
+    if !jsx.is_undefined() {
+        options.jsx = match jsx.as_string().as_deref() {
+            Some("transform") => JsxMode::Transform,
+            Some("preserve") => JsxMode::Preserve,
+            _ => {
+                return Err(CompilerError::InvalidConfig(
+                    "`jsx` must be \"transform\" or \"preserve\"".to_string(),
+                ))
+            }
+        };
+    }
+
+    let environment = get(value, "environment")?;
+    if !environment.is_undefined() {
+        let name = environment
+            .as_string()
+            .ok_or_else(|| CompilerError::InvalidConfig("`environment` must be a string".to_string()))?;
+        options.environment = Environment::parse(&name)?;
+    }
+
+    let runtime_imports = get(value, "runtimeImports")?;
+    if !runtime_imports.is_undefined() {
+        options.runtime_import_style = runtime_import_style(&runtime_imports)?;
+    }
+
+    let a11y = get(value, "a11y")?;
+    if !a11y.is_undefined() {
+        options.a11y = a11y_rules(&a11y)?;
+    }
+
+    let reactive_functions = get(value, "reactiveFunctions")?;
+    if !reactive_functions.is_undefined() {
+        options.reactive_functions = custom_reactive_functions(&reactive_functions)?;
+    }
+
+    Ok(options)
+}
+
+/// Read the `runtimeImports` option
+fn runtime_import_style(value: &JsValue) -> Result<RuntimeImportStyle, CompilerError> {
+    match value.as_string().as_deref() {
+        Some("barrel") => return Ok(RuntimeImportStyle::Barrel),
+        Some("deep") => return Ok(RuntimeImportStyle::Deep(RuntimeImportMap::default())),
+        _ => {}
+    }
+    let invalid = || {
+        CompilerError::InvalidConfig(
+            "`runtimeImports` must be \"barrel\", \"deep\" or an object of helper → module".to_string(),
+        )
+    };
+    if !value.is_object() {
+        return Err(invalid());
+    }
+
+    let mut map = RuntimeImportMap::default();
+    let entries = js_sys::Object::entries(value.unchecked_ref());
+    for entry in entries.iter() {
+        let entry: js_sys::Array = entry.unchecked_into();
+        let (Some(name), Some(path)) = (entry.get(0).as_string(), entry.get(1).as_string()) else {
+            return Err(invalid());
+        };
+        map.paths.insert(name, path);
+    }
+    Ok(RuntimeImportStyle::Deep(map))
+}
+
+/// Read the `a11y` option
+fn a11y_rules(value: &JsValue) -> Result<A11yRules, CompilerError> {
+    let invalid = || CompilerError::InvalidConfig("`a11y` must be an object of rule → boolean".to_string());
+    if !value.is_object() {
+        return Err(invalid());
+    }
+
+    let mut rules = A11yRules::default();
+    let entries = js_sys::Object::entries(value.unchecked_ref());
+    for entry in entries.iter() {
+        let entry: js_sys::Array = entry.unchecked_into();
+        let (Some(code), Some(enabled)) = (entry.get(0).as_string(), entry.get(1).as_bool()) else {
+            return Err(invalid());
+        };
+        rules.set(&code, enabled)?;
+    }
+    Ok(rules)
+}
+
+/// Read the `reactiveFunctions` option
+fn custom_reactive_functions(value: &JsValue) -> Result<ReactiveFunctions, CompilerError> {
+    if !value.is_object() {
+        return Err(CompilerError::InvalidConfig(
+            "`reactiveFunctions` must be an object of `signals`, `memos` and `effects`".to_string(),
+        ));
+    }
+
+    Ok(ReactiveFunctions {
+        signals: string_list(value, "signals")?,
+        memos: string_list(value, "memos")?,
+        effects: string_list(value, "effects")?,
+    })
+}
+
+/// Read an optional array of strings from `reactiveFunctions`
+fn string_list(options: &JsValue, name: &str) -> Result<Vec<String>, CompilerError> {
+    let invalid = || CompilerError::InvalidConfig(format!("`reactiveFunctions.{}` must be an array of strings", name));
+    let value = get(options, name)?;
+    if value.is_undefined() {
+        return Ok(Vec::new());
+    }
+    if !js_sys::Array::is_array(&value) {
+        return Err(invalid());
+    }
+
+    let array: js_sys::Array = value.unchecked_into();
+    array.iter().map(|item| item.as_string().ok_or_else(invalid)).collect()
+}
+
+fn bool_option(options: &JsValue, name: &str) -> Result<Option<bool>, CompilerError> {
+    let value = get(options, name)?;
+    if value.is_undefined() {
+        return Ok(None);
+    }
+    value
+        .as_bool()
+        .map(Some)
+        .ok_or_else(|| CompilerError::InvalidConfig(format!("`{}` must be a boolean", name)))
+}
+
+fn get(options: &JsValue, name: &str) -> Result<JsValue, CompilerError> {
+    js_sys::Reflect::get(options, &JsValue::from_str(name))
+        .map_err(|_| CompilerError::InvalidConfig(format!("Could not read option `{}`", name)))
+}
+
+/// A JS `Error` with the compiler error's message, `code` and `category`
+fn error_to_js(error: &CompilerError) -> JsValue {
+    let js_error = js_sys::Error::new(&error.to_string());
+    js_sys::Reflect::set(&js_error, &JsValue::from_str("code"), &JsValue::from_str(error.code())).ok();
+    js_sys::Reflect::set(
+        &js_error,
+        &JsValue::from_str("category"),
+        &JsValue::from_str(error.category().as_str()),
+    )
+    .ok();
+    js_error.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn options(json: &str) -> Result<CompilerOptions, CompilerError> {
+        parse_options(&js_sys::JSON::parse(json).unwrap())
+    }
+
+    #[wasm_bindgen_test]
+    fn test_parse_options_defaults() {
+        let defaults = CompilerOptions::default();
+        for parsed in [parse_options(&JsValue::UNDEFINED), parse_options(&JsValue::NULL), options("{}")] {
+            let parsed = parsed.unwrap();
+            assert!(!parsed.source_maps, "the wrapper never generates source maps");
+            assert_eq!(parsed.optimize, defaults.optimize);
+            assert_eq!(parsed.minify, defaults.minify);
+            assert_eq!(parsed.target, defaults.target);
+            assert_eq!(parsed.module_format, defaults.module_format);
+        }
+        assert!(parse_options(&JsValue::from_str("es2020")).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_parse_options_reads_fields() {
+        let parsed = options(
+            r#"{
+                "optimize": false,
+                "minify": "whitespace",
+                "target": "es2015",
+                "moduleFormat": "cjs",
+                "jsx": "preserve",
+                "environment": "worker",
+                "runtimeImports": { "forEach": "velocity-runtime/list" },
+                "reactiveFunctions": { "signals": ["createLocalStorageSignal"] }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(!parsed.optimize);
+        assert_eq!(parsed.minify, MinifyLevel::Whitespace);
+        assert_eq!(parsed.target, "es2015");
+        assert_eq!(parsed.module_format, ModuleFormat::Cjs);
+        assert_eq!(parsed.jsx, JsxMode::Preserve);
+        assert_eq!(parsed.environment, Environment::Worker);
+        let RuntimeImportStyle::Deep(map) = &parsed.runtime_import_style else {
+            panic!("an object of paths imports deep");
+        };
+        assert_eq!(map.paths.get("forEach").map(String::as_str), Some("velocity-runtime/list"));
+        assert_eq!(parsed.reactive_functions.signals, vec!["createLocalStorageSignal".to_string()]);
+        assert_eq!(options(r#"{ "minify": true }"#).unwrap().minify, MinifyLevel::Full);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_parse_options_rejects_bad_values() {
+        for json in [
+            r#"{ "optimize": "yes" }"#,
+            r#"{ "minify": 1 }"#,
+            r#"{ "minify": "tiny" }"#,
+            r#"{ "target": 2015 }"#,
+            r#"{ "moduleFormat": "amd" }"#,
+            r#"{ "jsx": "react" }"#,
+            r#"{ "environment": "deno" }"#,
+            r#"{ "runtimeImports": "flat" }"#,
+            r#"{ "a11y": { "a11y-click-events": "off" } }"#,
+            r#"{ "reactiveFunctions": { "signals": "createStore" } }"#,
+        ] {
+            assert!(matches!(options(json), Err(CompilerError::InvalidConfig(_))), "{}", json);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_error_to_js_carries_code_and_category() {
+        let error = CompilerError::InvalidConfig("Unknown target 'es1999'".to_string());
+        let js_error = error_to_js(&error);
+
+        let js_error: &js_sys::Error = js_error.dyn_ref().expect("an Error");
+        assert_eq!(String::from(js_error.message()), error.to_string());
+        let field = |name: &str| js_sys::Reflect::get(js_error, &JsValue::from_str(name)).unwrap().as_string();
+        assert_eq!(field("code").as_deref(), Some(error.code()));
+        assert_eq!(field("category").as_deref(), Some(error.category().as_str()));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_compile_throws_compiler_errors() {
+        let error = compile("const = ;", "App.tsx", JsValue::UNDEFINED).unwrap_err();
+        let code = js_sys::Reflect::get(&error, &JsValue::from_str("code")).unwrap();
+        assert!(code.as_string().is_some_and(|code| !code.is_empty()));
+        assert!(compile("const a = 1;", "App.tsx", JsValue::UNDEFINED).unwrap().contains("const a = 1;"));
+    }
+}
@@ -6,28 +6,69 @@
 //! - Dependency graphs for effects
 //! - Optimization opportunities
 
+use crate::diagnostics::{self, Diagnostic};
 use crate::error::Result;
+use swc_core::common::{Span, Spanned};
 use swc_core::ecma::ast::*;
 use swc_core::ecma::visit::{Visit, VisitWith};
 use std::collections::{HashMap, HashSet};
 
+#[cfg(feature = "incremental")]
+pub mod incremental;
+mod a11y;
+mod bindings;
+mod conditional_primitives;
+mod graph;
+mod side_effects;
+
+pub use a11y::{check_accessibility, A11yRules};
+pub use bindings::resolve_bindings;
+pub(crate) use bindings::clear_bindings;
+pub use graph::{dependency_graph, GraphEdge, GraphNode, NodeKind, ReactiveGraph};
+pub use side_effects::has_side_effects;
+
 /// Analysis results
+///
+/// Signals, memos and the like are recorded per binding, as the name and
+/// syntax context of the identifier declaring them (see [`resolve_bindings`]).
+/// On a module whose bindings aren't resolved every context is empty, and a
+/// name stands for every binding with that name.
 #[derive(Debug, Clone)]
 pub struct Analysis {
-    /// Set of identifiers that are reactive signals
-    pub signals: HashSet<String>,
+    /// Set of bindings that are reactive signals
+    pub signals: HashSet<Id>,
+
+    /// Set of bindings that are signal setters (second element of `createSignal`)
+    pub setters: HashSet<Id>,
+
+    /// Map of setter binding to the signal getter it writes
+    pub setter_signals: HashMap<Id, Id>,
 
-    /// Set of identifiers that are computed/memos
-    pub memos: HashSet<String>,
+    /// Set of bindings holding a whole `[getter, setter]` tuple
+    /// (`const count = createSignal(0)`), read as `count[0]()`
+    pub signal_tuples: HashSet<Id>,
 
-    /// Set of identifiers that are effects
-    pub effects: HashSet<String>,
+    /// Set of bindings that are computed/memos
+    pub memos: HashSet<Id>,
 
-    /// Map of JSX elements to their reactive dependencies
+    /// Set of bindings that are effects
+    pub effects: HashSet<Id>,
+
+    /// Signals and memos each JSX element reads in its attributes and
+    /// expression children, by the element's position in the module
+    /// (1-based, in source order)
     pub jsx_dependencies: HashMap<usize, Vec<String>>,
 
     /// Set of function names that create reactivity
     pub reactive_functions: HashSet<String>,
+
+    /// Warnings found during analysis
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// Whether loading the module may do more than define its exports, so
+    /// it must be kept even when none of them are used. Conservatively
+    /// `true` until set from [`has_side_effects`], which needs the source.
+    pub has_side_effects: bool,
 }
 
 impl Default for Analysis {
@@ -37,6 +78,8 @@ impl Default for Analysis {
         reactive_functions.insert("createSignal".to_string());
         reactive_functions.insert("createMemo".to_string());
         reactive_functions.insert("createEffect".to_string());
+        reactive_functions.insert("createRenderEffect".to_string());
+        reactive_functions.insert("createComputed".to_string());
         reactive_functions.insert("createResource".to_string());
 
         // React API (drop-in replacement)
@@ -47,66 +90,112 @@ impl Default for Analysis {
 
         Self {
             signals: HashSet::new(),
+            setters: HashSet::new(),
+            setter_signals: HashMap::new(),
+            signal_tuples: HashSet::new(),
             memos: HashSet::new(),
             effects: HashSet::new(),
             jsx_dependencies: HashMap::new(),
             reactive_functions,
+            diagnostics: Vec::new(),
+            has_side_effects: true,
         }
     }
 }
 
+/// Reactive primitives beyond the built-in ones, such as a library's
+/// `createLocalStorageSignal`, each analyzed like the built-in primitive it
+/// resembles
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReactiveFunctions {
+    /// Functions returning a `[getter, setter]` pair, like `createSignal`
+    pub signals: Vec<String>,
+    /// Functions returning a getter for a derived value, like `createMemo`
+    pub memos: Vec<String>,
+    /// Functions re-running a callback when what it reads changes, like
+    /// `createEffect`
+    pub effects: Vec<String>,
+}
+
+impl ReactiveFunctions {
+    fn is_memo(&self, name: &str) -> bool {
+        self.memos.iter().any(|memo| memo == name)
+    }
+
+    fn is_effect(&self, name: &str) -> bool {
+        self.effects.iter().any(|effect| effect == name)
+    }
+}
+
 /// Visitor that analyzes reactivity in the AST
-struct ReactivityAnalyzer {
+struct ReactivityAnalyzer<'a> {
     analysis: Analysis,
     current_jsx_key: usize,
+    /// Same-file functions that return a signal tuple (custom hooks), and
+    /// the custom signal functions
+    signal_hooks: HashSet<String>,
+    /// Custom memo and effect functions
+    functions: &'a ReactiveFunctions,
+    /// Set while visiting a `.map` rendered as a JSX child, whose elements
+    /// the child check already covers
+    in_list_child: bool,
 }
 
-impl ReactivityAnalyzer {
-    fn new() -> Self {
+impl<'a> ReactivityAnalyzer<'a> {
+    /// `signal_hooks` must include `functions.signals`, as
+    /// [`collect_signal_hooks`] returns them
+    fn new(signal_hooks: HashSet<String>, functions: &'a ReactiveFunctions) -> Self {
+        let mut analysis = Analysis::default();
+        analysis.reactive_functions.extend(signal_hooks.iter().cloned());
+        analysis.reactive_functions.extend(functions.memos.iter().cloned());
+        analysis.reactive_functions.extend(functions.effects.iter().cloned());
+
         Self {
-            analysis: Analysis::default(),
+            analysis,
             current_jsx_key: 0,
+            signal_hooks,
+            functions,
+            in_list_child: false,
         }
     }
 
-    /// Check if a call expression creates a signal (Velocity or React API)
+    /// Check if a call expression creates a signal (Velocity or React API,
+    /// or a custom hook that returns one)
     fn is_create_signal(&self, callee: &Callee) -> bool {
-        if let Callee::Expr(expr) = callee {
-            if let Expr::Ident(ident) = &**expr {
-                let name = ident.sym.as_ref();
-                return name == "createSignal" || name == "useState";
-            }
-        }
-        false
+        is_signal_call(callee, &self.signal_hooks)
     }
 
-    /// Check if a call expression creates a memo (Velocity or React API)
+    /// Check if a call expression creates a memo (Velocity or React API, or
+    /// a custom memo function)
     fn is_create_memo(&self, callee: &Callee) -> bool {
         if let Callee::Expr(expr) = callee {
             if let Expr::Ident(ident) = &**expr {
                 let name = ident.sym.as_ref();
-                return name == "createMemo" || name == "useMemo" || name == "useCallback";
+                return name == "createMemo" || name == "useMemo" || name == "useCallback" || self.functions.is_memo(name);
             }
         }
         false
     }
 
-    /// Check if a call expression creates an effect (Velocity or React API)
+    /// Check if a call expression creates an effect (Velocity or React API,
+    /// or a custom effect function). `createComputed` counts: it re-runs on
+    /// changes like an effect, and unlike a memo returns no value to read.
     fn is_create_effect(&self, callee: &Callee) -> bool {
         if let Callee::Expr(expr) = callee {
             if let Expr::Ident(ident) = &**expr {
                 let name = ident.sym.as_ref();
-                return name == "createEffect" || name == "useEffect";
+                return matches!(name, "createEffect" | "createRenderEffect" | "createComputed" | "useEffect")
+                    || self.functions.is_effect(name);
             }
         }
         false
     }
 
-    /// Extract identifier from a pattern (e.g., destructuring)
-    fn extract_identifiers(&self, pat: &Pat, identifiers: &mut Vec<String>) {
+    /// Extract the bindings a pattern declares (e.g., destructuring)
+    fn extract_identifiers(&self, pat: &Pat, identifiers: &mut Vec<Id>) {
         match pat {
             Pat::Ident(ident) => {
-                identifiers.push(ident.id.sym.to_string());
+                identifiers.push(ident.id.to_id());
             }
             Pat::Array(array) => {
                 for elem in &array.elems {
@@ -122,7 +211,7 @@ impl ReactivityAnalyzer {
                             self.extract_identifiers(&kv.value, identifiers);
                         }
                         ObjectPatProp::Assign(assign) => {
-                            identifiers.push(assign.key.sym.to_string());
+                            identifiers.push(assign.key.to_id());
                         }
                         ObjectPatProp::Rest(rest) => {
                             self.extract_identifiers(&rest.arg, identifiers);
@@ -141,7 +230,7 @@ impl ReactivityAnalyzer {
     }
 }
 
-impl Visit for ReactivityAnalyzer {
+impl Visit for ReactivityAnalyzer<'_> {
     /// Visit variable declarations to find signals, memos, and effects
     fn visit_var_declarator(&mut self, decl: &VarDeclarator) {
         if let Some(init) = &decl.init {
@@ -152,8 +241,35 @@ impl Visit for ReactivityAnalyzer {
                 if self.is_create_signal(&call.callee) {
                     // createSignal returns [getter, setter]
                     // Usually destructured as: const [count, setCount] = createSignal(0)
-                    if identifiers.len() >= 1 {
-                        self.analysis.signals.insert(identifiers[0].clone());
+                    if let Pat::Array(array) = &decl.name {
+                        let getter = match array.elems.first() {
+                            Some(Some(Pat::Ident(getter))) => Some(getter.id.to_id()),
+                            _ => None,
+                        };
+                        if let Some(getter) = &getter {
+                            self.analysis.signals.insert(getter.clone());
+                        }
+                        if let Some(Some(Pat::Ident(setter))) = array.elems.get(1) {
+                            let setter = setter.id.to_id();
+                            if let Some(getter) = getter {
+                                self.analysis.setter_signals.insert(setter.clone(), getter);
+                            }
+                            self.analysis.setters.insert(setter);
+                        }
+                    } else if let Pat::Ident(tuple) = &decl.name {
+                        let name = &tuple.id.sym;
+                        self.analysis.diagnostics.push(Diagnostic::warning(
+                            "undestructured-signal",
+                            format!(
+                                "`{name}` holds the signal's `[getter, setter]` pair; destructure it \
+                                 (`const [{name}, set{}] = ...`) and call the getter to read it",
+                                capitalize(name)
+                            ),
+                            tuple.id.span,
+                        ));
+                        self.analysis.signal_tuples.insert(tuple.id.to_id());
+                    } else if let Some(first) = identifiers.first() {
+                        self.analysis.signals.insert(first.clone());
                     }
                 } else if self.is_create_memo(&call.callee) {
                     for ident in identifiers {
@@ -175,35 +291,996 @@ impl Visit for ReactivityAnalyzer {
         self.current_jsx_key += 1;
         let key = self.current_jsx_key;
 
-        // Track dependencies for this JSX element
-        let deps = Vec::new();
-
-        // Visit children and attributes to find reactive dependencies
-        // This is a simplified version - full implementation would track
-        // all identifiers used in the JSX that are reactive
-
+        let deps: Vec<String> = jsx_reads(elem)
+            .into_iter()
+            .filter(|id| self.analysis.signals.contains(id) || self.analysis.memos.contains(id))
+            .map(|(name, _)| name.to_string())
+            .collect();
         self.analysis.jsx_dependencies.insert(key, deps);
 
+        self.check_inner_html_children(elem);
         elem.visit_children_with(self);
     }
 
     /// Visit call expressions
     fn visit_call_expr(&mut self, call: &CallExpr) {
+        if self.is_create_effect(&call.callee) {
+            self.check_effect_feedback(call);
+        }
+        if !std::mem::take(&mut self.in_list_child) {
+            self.check_list_keys(call);
+        }
+        call.visit_children_with(self);
+    }
+
+    /// Warn about `{items.map(item => <li />)}` children without a `key`
+    fn visit_jsx_element_child(&mut self, child: &JSXElementChild) {
+        if let JSXElementChild::JSXExprContainer(JSXExprContainer {
+            expr: JSXExpr::Expr(expr),
+            ..
+        }) = child
+        {
+            if let Some(elem) = mapped_element(expr) {
+                if !has_key(elem) {
+                    self.analysis.diagnostics.push(Diagnostic::warning(
+                        "missing-key",
+                        "elements rendered from `.map` should have a `key` so the list can reuse them when it changes",
+                        elem.opening.span,
+                    ));
+                }
+                self.in_list_child = true;
+            }
+        }
+        child.visit_children_with(self);
+        self.in_list_child = false;
+    }
+}
+
+/// Whether an element has children other than whitespace and `{/* comments */}`
+fn has_children(elem: &JSXElement) -> bool {
+    elem.children.iter().any(|child| match child {
+        JSXElementChild::JSXText(text) => !text.value.trim().is_empty(),
+        JSXElementChild::JSXExprContainer(container) => !matches!(container.expr, JSXExpr::JSXEmptyExpr(_)),
+        _ => true,
+    })
+}
+
+fn has_key(elem: &JSXElement) -> bool {
+    elem.opening.attrs.iter().any(|attr| {
+        matches!(attr, JSXAttrOrSpread::JSXAttr(JSXAttr { name: JSXAttrName::Ident(name), .. }) if name.sym == "key")
+    })
+}
+
+/// The JSX element returned by the callback of an `items.map(callback)` call
+fn mapped_element(expr: &Expr) -> Option<&JSXElement> {
+    let Expr::Call(call) = expr else {
+        return None;
+    };
+    match map_callback(call)?.1.unwrap_parens() {
+        Expr::JSXElement(elem) => Some(elem),
+        _ => None,
+    }
+}
+
+/// The callback of an `items.map(callback)` call and the expression it returns
+fn map_callback(call: &CallExpr) -> Option<(&Expr, &Expr)> {
+    let CallExpr {
+        callee: Callee::Expr(callee),
+        args,
+        ..
+    } = call
+    else {
+        return None;
+    };
+    let Expr::Member(MemberExpr {
+        prop: MemberProp::Ident(prop),
+        ..
+    }) = &**callee
+    else {
+        return None;
+    };
+    if prop.sym != "map" || args.len() != 1 {
+        return None;
+    }
+
+    let callback = &*args[0].expr;
+    let body = match callback {
+        Expr::Arrow(arrow) => match &*arrow.body {
+            BlockStmtOrExpr::Expr(expr) => Some(&**expr),
+            BlockStmtOrExpr::BlockStmt(block) => block_return(block),
+        },
+        Expr::Fn(func) => func.function.body.as_ref().and_then(block_return),
+        _ => None,
+    }?;
+    Some((callback, body))
+}
+
+/// Elements an expression can evaluate to: itself, or either branch of a
+/// `cond ? <A /> : <B />` or `cond && <A />`
+fn returned_elements<'a>(expr: &'a Expr, elements: &mut Vec<&'a JSXElement>) {
+    match expr.unwrap_parens() {
+        Expr::JSXElement(elem) => elements.push(elem),
+        Expr::Cond(cond) => {
+            returned_elements(&cond.cons, elements);
+            returned_elements(&cond.alt, elements);
+        }
+        Expr::Bin(BinExpr {
+            op: BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing,
+            right,
+            ..
+        }) => returned_elements(right, elements),
+        _ => {}
+    }
+}
+
+/// The expression returned by the last statement of a block
+fn block_return(block: &BlockStmt) -> Option<&Expr> {
+    match block.stmts.last()? {
+        Stmt::Return(ReturnStmt { arg: Some(arg), .. }) => Some(&**arg),
+        _ => None,
+    }
+}
+
+impl ReactivityAnalyzer<'_> {
+    /// Warn about a `.map` anywhere outside JSX children (assigned, returned,
+    /// passed as a prop) whose callback returns elements or components
+    /// without a `key`. Reported at the callback.
+    fn check_list_keys(&mut self, call: &CallExpr) {
+        let Some((callback, body)) = map_callback(call) else {
+            return;
+        };
+        let mut elements = Vec::new();
+        returned_elements(body, &mut elements);
+
+        if let Some(elem) = elements.iter().find(|elem| !has_key(elem)) {
+            let name = match &elem.opening.name {
+                JSXElementName::Ident(ident) => ident.sym.to_string(),
+                _ => "element".to_string(),
+            };
+            self.analysis.diagnostics.push(Diagnostic::warning(
+                "missing-key",
+                format!(
+                    "`.map` callback returns <{}> without a `key`, so the list can't reuse or reorder \
+                     its items when it changes",
+                    name
+                ),
+                callback.span(),
+            ));
+        }
+    }
+
+    /// Warn about children of a DOM element whose content is set as raw HTML,
+    /// since the HTML replaces them
+    fn check_inner_html_children(&mut self, elem: &JSXElement) {
+        let JSXElementName::Ident(tag) = &elem.opening.name else {
+            return;
+        };
+        if !tag.sym.starts_with(|c: char| c.is_ascii_lowercase()) || !has_children(elem) {
+            return;
+        }
+        let attr = elem.opening.attrs.iter().find_map(|attr| match attr {
+            JSXAttrOrSpread::JSXAttr(JSXAttr {
+                name: JSXAttrName::Ident(name),
+                ..
+            }) if INNER_HTML_ATTRS.contains(&&*name.sym) => Some(name.sym.to_string()),
+            _ => None,
+        });
+        if let Some(attr) = attr {
+            self.analysis.diagnostics.push(Diagnostic::warning(
+                "inner-html-children",
+                format!(
+                    "<{}> sets `{}`, which replaces its children; put the content in the HTML or remove the children",
+                    tag.sym, attr
+                ),
+                elem.span,
+            ));
+        }
+    }
+
+    /// Warn when an effect reads a signal and also calls its setter, since the
+    /// write re-triggers the effect and loops forever
+    fn check_effect_feedback(&mut self, call: &CallExpr) {
+        let body = match call.args.first().map(|arg| &*arg.expr) {
+            Some(Expr::Arrow(arrow)) => match &*arrow.body {
+                BlockStmtOrExpr::BlockStmt(block) => EffectBody::Block(block),
+                BlockStmtOrExpr::Expr(expr) => EffectBody::Expr(expr),
+            },
+            Some(Expr::Fn(fn_expr)) => match &fn_expr.function.body {
+                Some(block) => EffectBody::Block(block),
+                None => return,
+            },
+            _ => return,
+        };
+
+        let mut collector = EffectAccessCollector {
+            analysis: &self.analysis,
+            reads: HashSet::new(),
+            writes: Vec::new(),
+        };
+        match body {
+            EffectBody::Block(block) => block.visit_with(&mut collector),
+            EffectBody::Expr(expr) => expr.visit_with(&mut collector),
+        }
+
+        let EffectAccessCollector { reads, writes, .. } = collector;
+
+        let mut reported = HashSet::new();
+        for (setter, signal) in writes {
+            if !reads.contains(&signal) || !reported.insert(signal.clone()) {
+                continue;
+            }
+            let (setter, signal) = (&setter.0, &signal.0);
+            self.analysis.diagnostics.push(Diagnostic::warning(
+                "effect-loop",
+                format!(
+                    "effect reads `{signal}()` and writes it with `{setter}(...)`, \
+                     which re-runs the effect in an infinite loop"
+                ),
+                call.span,
+            ));
+        }
+    }
+}
+
+/// Body of an effect callback
+enum EffectBody<'a> {
+    Block(&'a BlockStmt),
+    Expr(&'a Expr),
+}
+
+/// Visitor that records which signals an effect body reads and writes.
+/// Nested functions and `untrack` calls don't create dependencies, so they
+/// are skipped. Elements of an undestructured signal are recorded as the
+/// tuple's binding renamed to `count[0]` and `count[1]`.
+struct EffectAccessCollector<'a> {
+    analysis: &'a Analysis,
+    reads: HashSet<Id>,
+    /// (setter, signal) pairs
+    writes: Vec<(Id, Id)>,
+}
+
+impl Visit for EffectAccessCollector<'_> {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Callee::Expr(callee) = &call.callee {
+            // `count[0]()` reads and `count[1](...)` writes an undestructured signal
+            if let Some((tuple, index)) = signal_tuple_index(callee, &self.analysis.signal_tuples) {
+                let element = |index: u8| (format!("{}[{}]", tuple.sym, index).into(), tuple.ctxt);
+                match index {
+                    0 if call.args.is_empty() => {
+                        self.reads.insert(element(0));
+                    }
+                    1 => self.writes.push((element(1), element(0))),
+                    _ => {}
+                }
+            }
+            if let Expr::Ident(ident) = &**callee {
+                if ident.sym == "untrack" {
+                    return;
+                }
+                let id = ident.to_id();
+                if self.analysis.signals.contains(&id) && call.args.is_empty() {
+                    self.reads.insert(id.clone());
+                }
+                if let Some(signal) = self.analysis.setter_signals.get(&id) {
+                    self.writes.push((id, signal.clone()));
+                }
+            }
+        }
         call.visit_children_with(self);
     }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+}
+
+/// Bindings a function body calls as getters (`count()`) where the runtime
+/// tracks the read, in order of first read. Nested functions, `untrack`
+/// calls and JSX elements (which track their own reads) are skipped.
+#[derive(Default)]
+struct GetterCalls {
+    ids: Vec<Id>,
+}
+
+impl GetterCalls {
+    fn add(&mut self, ident: &Ident) {
+        if !self.ids.iter().any(|seen| ident.sym == seen.0 && ident.ctxt == seen.1) {
+            self.ids.push(ident.to_id());
+        }
+    }
 }
 
-/// Analyze a module for reactivity
+impl Visit for GetterCalls {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Callee::Expr(callee) = &call.callee {
+            if let Expr::Ident(ident) = &**callee {
+                if ident.sym == "untrack" {
+                    return;
+                }
+                if call.args.is_empty() {
+                    self.add(ident);
+                }
+            }
+        }
+        call.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_jsx_element(&mut self, _: &JSXElement) {}
+}
+
+/// Bindings read by the body of the callback passed to a memo or effect
+fn callback_reads(call: &CallExpr) -> Vec<Id> {
+    let mut reads = GetterCalls::default();
+    match call.args.first().map(|arg| &*arg.expr) {
+        Some(Expr::Arrow(arrow)) => arrow.body.visit_with(&mut reads),
+        Some(Expr::Fn(fn_expr)) => fn_expr.function.body.visit_with(&mut reads),
+        _ => {}
+    }
+    reads.ids
+}
+
+/// Bindings a JSX element reads in its attributes and expression children:
+/// getter calls, and getters passed bare (`{count}`), which the transform
+/// also binds reactively
+fn jsx_reads(elem: &JSXElement) -> Vec<Id> {
+    let mut reads = GetterCalls::default();
+    let attr_values = elem.opening.attrs.iter().filter_map(|attr| match attr {
+        JSXAttrOrSpread::JSXAttr(JSXAttr {
+            value: Some(JSXAttrValue::JSXExprContainer(container)),
+            ..
+        }) => Some(container),
+        _ => None,
+    });
+    let children = elem.children.iter().filter_map(|child| match child {
+        JSXElementChild::JSXExprContainer(container) => Some(container),
+        _ => None,
+    });
+    for container in attr_values.chain(children) {
+        let JSXExpr::Expr(expr) = &container.expr else {
+            continue;
+        };
+        match &**expr {
+            Expr::Ident(ident) => reads.add(ident),
+            expr => expr.visit_with(&mut reads),
+        }
+    }
+    reads.ids
+}
+
+/// `tuple[index]` for a known signal tuple, as `(tuple, index)`
+fn signal_tuple_index<'a>(expr: &'a Expr, tuples: &HashSet<Id>) -> Option<(&'a Ident, u8)> {
+    let Expr::Member(MemberExpr {
+        obj,
+        prop: MemberProp::Computed(ComputedPropName { expr: index, .. }),
+        ..
+    }) = expr
+    else {
+        return None;
+    };
+    let (Expr::Ident(tuple), Expr::Lit(Lit::Num(index))) = (&**obj, &**index) else {
+        return None;
+    };
+    if !tuples.contains(&tuple.to_id()) || (index.value != 0.0 && index.value != 1.0) {
+        return None;
+    }
+    Some((tuple, index.value as u8))
+}
+
+/// `count` → `Count`
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Check if a callee creates a signal: `createSignal`/`useState` or a known hook
+fn is_signal_call(callee: &Callee, signal_hooks: &HashSet<String>) -> bool {
+    if let Callee::Expr(expr) = callee {
+        if let Expr::Ident(ident) = &**expr {
+            let name = ident.sym.as_ref();
+            return name == "createSignal" || name == "useState" || signal_hooks.contains(name);
+        }
+    }
+    false
+}
+
+/// Visitor that collects every named function in the module with its body
+#[derive(Default)]
+struct FunctionCollector<'a> {
+    functions: Vec<(String, FunctionBody<'a>)>,
+}
+
+/// The part of a function that can return a value
+#[derive(Clone, Copy)]
+enum FunctionBody<'a> {
+    Block(&'a BlockStmt),
+    Expr(&'a Expr),
+}
+
+impl<'a> FunctionCollector<'a> {
+    fn collect(module: &'a Module) -> Vec<(String, FunctionBody<'a>)> {
+        let mut collector = Self::default();
+        for item in &module.body {
+            collector.collect_item(item);
+        }
+        collector.functions
+    }
+
+    fn collect_item(&mut self, item: &'a ModuleItem) {
+        let decl = match item {
+            ModuleItem::Stmt(Stmt::Decl(decl)) => decl,
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export)) => &export.decl,
+            // export default function useCounter() { ... }
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+                decl: DefaultDecl::Fn(FnExpr { ident: Some(ident), function }),
+                ..
+            })) => {
+                if let Some(body) = &function.body {
+                    self.functions.push((ident.sym.to_string(), FunctionBody::Block(body)));
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        match decl {
+            Decl::Fn(fn_decl) => {
+                if let Some(body) = &fn_decl.function.body {
+                    self.functions
+                        .push((fn_decl.ident.sym.to_string(), FunctionBody::Block(body)));
+                }
+            }
+            Decl::Var(var) => {
+                for declarator in &var.decls {
+                    let (Pat::Ident(name), Some(init)) = (&declarator.name, &declarator.init) else {
+                        continue;
+                    };
+                    let body = match &**init {
+                        Expr::Arrow(arrow) => match &*arrow.body {
+                            BlockStmtOrExpr::BlockStmt(block) => FunctionBody::Block(block),
+                            BlockStmtOrExpr::Expr(expr) => FunctionBody::Expr(expr),
+                        },
+                        Expr::Fn(fn_expr) => match &fn_expr.function.body {
+                            Some(block) => FunctionBody::Block(block),
+                            None => continue,
+                        },
+                        _ => continue,
+                    };
+                    self.functions.push((name.id.sym.to_string(), body));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Visitor that decides whether a function body returns a signal tuple.
+/// Nested functions are skipped since their returns don't belong to the body.
+struct SignalReturnFinder<'a> {
+    signal_hooks: &'a HashSet<String>,
+    /// Variables holding a whole tuple: `const s = createSignal(0)`
+    tuples: HashSet<String>,
+    /// Getters destructured from a tuple: `const [a, setA] = createSignal(0)`
+    getters: HashSet<String>,
+    returns_signal: bool,
+}
+
+impl<'a> SignalReturnFinder<'a> {
+    fn new(signal_hooks: &'a HashSet<String>) -> Self {
+        Self {
+            signal_hooks,
+            tuples: HashSet::new(),
+            getters: HashSet::new(),
+            returns_signal: false,
+        }
+    }
+
+    fn check_returned(&mut self, expr: &Expr) {
+        let returns_signal = match expr {
+            Expr::Call(call) => is_signal_call(&call.callee, self.signal_hooks),
+            Expr::Ident(ident) => self.tuples.contains(ident.sym.as_ref()),
+            Expr::Array(array) => matches!(
+                array.elems.first(),
+                Some(Some(ExprOrSpread { spread: None, expr }))
+                    if matches!(&**expr, Expr::Ident(ident) if self.getters.contains(ident.sym.as_ref()))
+            ),
+            Expr::Paren(paren) => return self.check_returned(&paren.expr),
+            _ => false,
+        };
+        self.returns_signal |= returns_signal;
+    }
+}
+
+impl Visit for SignalReturnFinder<'_> {
+    fn visit_var_declarator(&mut self, decl: &VarDeclarator) {
+        if let Some(Expr::Call(call)) = decl.init.as_deref() {
+            if is_signal_call(&call.callee, self.signal_hooks) {
+                match &decl.name {
+                    Pat::Ident(ident) => {
+                        self.tuples.insert(ident.id.sym.to_string());
+                    }
+                    Pat::Array(array) => {
+                        if let Some(Some(Pat::Ident(getter))) = array.elems.first() {
+                            self.getters.insert(getter.id.sym.to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        decl.visit_children_with(self);
+    }
+
+    fn visit_return_stmt(&mut self, ret: &ReturnStmt) {
+        if let Some(arg) = &ret.arg {
+            self.check_returned(arg);
+        }
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+}
+
+/// Find same-file functions that return a signal tuple, so that
+/// `const [a, setA] = useCounter()` is treated like `createSignal`.
+/// Hooks built on other hooks are found by iterating to a fixed point,
+/// starting from the custom signal functions `signals`, which are included.
+fn collect_signal_hooks(module: &Module, signals: &[String]) -> HashSet<String> {
+    let functions = FunctionCollector::collect(module);
+    let mut hooks: HashSet<String> = signals.iter().cloned().collect();
+
+    loop {
+        let mut changed = false;
+
+        for (name, body) in &functions {
+            if hooks.contains(name) {
+                continue;
+            }
+
+            let mut finder = SignalReturnFinder::new(&hooks);
+            match body {
+                FunctionBody::Block(block) => block.visit_with(&mut finder),
+                FunctionBody::Expr(expr) => finder.check_returned(expr),
+            }
+
+            if finder.returns_signal {
+                hooks.insert(name.clone());
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return hooks;
+        }
+    }
+}
+
+/// Analyze a module for reactivity.
+///
+/// Expects TypeScript already stripped. The result describes this exact
+/// module and is what [`transform`](crate::transformer::transform) and
+/// [`optimize`](crate::optimizer::optimize) take alongside it.
 pub fn analyze(module: &Module) -> Result<Analysis> {
-    let mut analyzer = ReactivityAnalyzer::new();
+    analyze_with(module, &ReactiveFunctions::default())
+}
+
+/// [`analyze`], treating `functions` as reactive primitives too
+pub fn analyze_with(module: &Module, functions: &ReactiveFunctions) -> Result<Analysis> {
+    let mut analyzer = ReactivityAnalyzer::new(collect_signal_hooks(module, &functions.signals), functions);
     module.visit_with(&mut analyzer);
-    Ok(analyzer.analysis)
+    let mut analysis = analyzer.analysis;
+    let misplaced =
+        conditional_primitives::check_conditional_primitives(module, &analysis.reactive_functions, functions);
+    analysis.diagnostics.extend(misplaced);
+    Ok(analysis)
+}
+
+/// Every identifier referenced in `module` outside its import declarations
+pub(crate) fn referenced_identifiers(module: &Module) -> HashSet<String> {
+    let mut collector = ReferenceCollector::default();
+    module.visit_with(&mut collector);
+    collector.names
+}
+
+/// Import specifiers whose local binding is never referenced, as
+/// `(local name, span)`. Side-effect imports (`import "./a.css"`) have no
+/// specifiers and never appear here.
+pub(crate) fn unused_imports(module: &Module, used: &HashSet<String>) -> Vec<(String, Span)> {
+    module
+        .body
+        .iter()
+        .filter_map(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => Some(import),
+            _ => None,
+        })
+        .flat_map(|import| &import.specifiers)
+        .map(import_local)
+        .filter(|local| !used.contains(&*local.sym))
+        .map(|local| (local.sym.to_string(), local.span))
+        .collect()
+}
+
+/// Warn about imports that are never used (reported in strict mode)
+pub fn check_unused_imports(module: &Module) -> Vec<Diagnostic> {
+    unused_imports(module, &referenced_identifiers(module))
+        .into_iter()
+        .map(|(name, span)| {
+            Diagnostic::warning("unused-import", format!("`{}` is imported but never used", name), span)
+        })
+        .collect()
+}
+
+/// Warn about `createResource` calls inside functions whose key reads a
+/// signal (reported in strict mode). Resources are cached by key, so a key
+/// like `` `user-${id()}` `` adds a cache entry and refetches each time the
+/// component renders with a new value.
+pub fn check_resource_keys(module: &Module, analysis: &Analysis, source: &str) -> Vec<Diagnostic> {
+    let mut checker = ResourceKeyChecker {
+        analysis,
+        source,
+        function_depth: 0,
+        diagnostics: Vec::new(),
+    };
+    module.visit_with(&mut checker);
+    checker.diagnostics
+}
+
+/// Visitor behind [`check_resource_keys`]
+struct ResourceKeyChecker<'a> {
+    analysis: &'a Analysis,
+    source: &'a str,
+    function_depth: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Visit for ResourceKeyChecker<'_> {
+    fn visit_function(&mut self, function: &Function) {
+        self.function_depth += 1;
+        function.visit_children_with(self);
+        self.function_depth -= 1;
+    }
+
+    fn visit_arrow_expr(&mut self, arrow: &ArrowExpr) {
+        self.function_depth += 1;
+        arrow.visit_children_with(self);
+        self.function_depth -= 1;
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        call.visit_children_with(self);
+
+        // Module-level resources are only created once
+        let is_resource = matches!(&call.callee, Callee::Expr(callee) if matches!(&**callee, Expr::Ident(ident) if ident.sym == "createResource"));
+        let Some(key) = call.args.first().filter(|_| is_resource && self.function_depth > 0) else {
+            return;
+        };
+
+        let mut collector = EffectAccessCollector {
+            analysis: self.analysis,
+            reads: HashSet::new(),
+            writes: Vec::new(),
+        };
+        key.expr.visit_with(&mut collector);
+        let Some((signal, _)) = collector.reads.into_iter().min_by(|a, b| a.0.cmp(&b.0)) else {
+            return;
+        };
+
+        self.diagnostics.push(Diagnostic::warning(
+            "unstable-resource-key",
+            format!(
+                "resource key {} reads `{}()`, so every render with a new value adds a cache entry \
+                 and refetches; memoize the key with `createMemo` or use a stable key",
+                diagnostics::span_text(self.source, key.expr.span()),
+                signal
+            ),
+            key.expr.span(),
+        ));
+    }
+}
+
+/// Elements that can't have children
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// Elements that are only valid directly inside one of the listed parents
+const REQUIRED_PARENTS: &[(&str, &[&str])] = &[
+    ("td", &["tr"]),
+    ("th", &["tr"]),
+    ("tr", &["table", "thead", "tbody", "tfoot"]),
+    ("thead", &["table"]),
+    ("tbody", &["table"]),
+    ("tfoot", &["table"]),
+    ("option", &["select", "datalist", "optgroup"]),
+    ("optgroup", &["select"]),
+    ("li", &["ul", "ol", "menu"]),
+];
+
+/// Attributes that set a DOM element's content as raw HTML:
+/// `innerHTML={html}` and React's `dangerouslySetInnerHTML={{ __html: html }}`
+pub(crate) const INNER_HTML_ATTRS: &[&str] = &["innerHTML", "dangerouslySetInnerHTML"];
+
+/// Warn about HTML the browser would silently repair: duplicate attributes,
+/// children of void elements and table/list/select children in the wrong
+/// parent (reported in strict mode).
+///
+/// Nesting is only checked against a parent in the same JSX tree. An element
+/// at the root of a component (`<tr>` returned by a `Row` component) could be
+/// placed anywhere and isn't reported.
+pub fn check_jsx_structure(module: &Module) -> Vec<Diagnostic> {
+    let mut validator = JsxValidator::default();
+    module.visit_with(&mut validator);
+    validator.diagnostics
+}
+
+/// Visitor behind [`check_jsx_structure`]
+#[derive(Default)]
+struct JsxValidator {
+    /// Enclosing JSX elements, innermost last. `None` is a parent whose
+    /// rendered element is unknown: a component, or an attribute value.
+    parents: Vec<Option<String>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl JsxValidator {
+    fn check_duplicate_attributes(&mut self, attrs: &[JSXAttrOrSpread]) {
+        let mut seen = HashSet::new();
+        for attr in attrs {
+            let JSXAttrOrSpread::JSXAttr(attr) = attr else {
+                continue;
+            };
+            let name = match &attr.name {
+                JSXAttrName::Ident(ident) => ident.sym.to_string(),
+                JSXAttrName::JSXNamespacedName(name) => format!("{}:{}", name.ns.sym, name.name.sym),
+            };
+            if !seen.insert(name.clone()) {
+                self.diagnostics.push(Diagnostic::warning(
+                    "duplicate-attribute",
+                    format!("attribute `{}` is set more than once; only the last value is used", name),
+                    attr.span,
+                ));
+            }
+        }
+    }
+
+    fn check_parent(&mut self, tag: &str, span: Span) {
+        let Some((_, allowed)) = REQUIRED_PARENTS.iter().find(|(child, _)| *child == tag) else {
+            return;
+        };
+        let Some(Some(parent)) = self.parents.last() else {
+            return;
+        };
+        if !allowed.contains(&parent.as_str()) {
+            self.diagnostics.push(Diagnostic::warning(
+                "invalid-nesting",
+                format!(
+                    "<{}> inside <{}> is invalid HTML; it must be a child of {}",
+                    tag,
+                    parent,
+                    allowed.iter().map(|p| format!("<{}>", p)).collect::<Vec<_>>().join(", ")
+                ),
+                span,
+            ));
+        }
+    }
+
+    fn check_void_children(&mut self, tag: &str, elem: &JSXElement) {
+        if !VOID_ELEMENTS.contains(&tag) {
+            return;
+        }
+        if has_children(elem) {
+            self.diagnostics.push(Diagnostic::warning(
+                "void-element-children",
+                format!("<{}> is a void element and can't have children", tag),
+                elem.span,
+            ));
+        }
+    }
+}
+
+impl Visit for JsxValidator {
+    fn visit_jsx_element(&mut self, elem: &JSXElement) {
+        // Lowercase plain names are DOM elements; anything else is a component
+        let tag = match &elem.opening.name {
+            JSXElementName::Ident(ident) if ident.sym.starts_with(|c: char| c.is_ascii_lowercase()) => {
+                Some(ident.sym.to_string())
+            }
+            _ => None,
+        };
+
+        self.check_duplicate_attributes(&elem.opening.attrs);
+        if let Some(tag) = &tag {
+            self.check_parent(tag, elem.span);
+            self.check_void_children(tag, elem);
+        }
+
+        // Elements in attribute values aren't children of this element
+        self.parents.push(None);
+        elem.opening.visit_with(self);
+        self.parents.pop();
+
+        self.parents.push(tag);
+        elem.children.visit_with(self);
+        self.parents.pop();
+    }
+
+    // Fragments are transparent: their children belong to the enclosing element
+    fn visit_jsx_fragment(&mut self, frag: &JSXFragment) {
+        frag.children.visit_with(self);
+    }
+}
+
+/// The local binding an import specifier introduces
+pub(crate) fn import_local(specifier: &ImportSpecifier) -> &Ident {
+    match specifier {
+        ImportSpecifier::Named(named) => &named.local,
+        ImportSpecifier::Default(default) => &default.local,
+        ImportSpecifier::Namespace(namespace) => &namespace.local,
+    }
+}
+
+/// Whether `expr` always evaluates to an equal value: literals, and object
+/// and array literals built only from literals. Such an expression has no
+/// free variables, so it can't read a signal or any other changing state.
+pub(crate) fn is_constant_expr(expr: &Expr) -> bool {
+    match expr {
+        // A regex literal is a stateful object (`lastIndex`)
+        Expr::Lit(lit) => !matches!(lit, Lit::Regex(_) | Lit::JSXText(_)),
+        Expr::Tpl(tpl) => tpl.exprs.is_empty(),
+        Expr::Unary(unary) => {
+            matches!(unary.op, UnaryOp::Minus | UnaryOp::Plus | UnaryOp::Bang) && is_constant_expr(&unary.arg)
+        }
+        Expr::Paren(paren) => is_constant_expr(&paren.expr),
+        Expr::Array(array) => array.elems.iter().all(|elem| match elem {
+            Some(ExprOrSpread { spread: None, expr }) => is_constant_expr(expr),
+            Some(_) => false,
+            None => true,
+        }),
+        Expr::Object(object) => object.props.iter().all(|prop| match prop {
+            PropOrSpread::Prop(prop) => match &**prop {
+                Prop::KeyValue(KeyValueProp { key, value }) => {
+                    !matches!(key, PropName::Computed(_)) && is_constant_expr(value)
+                }
+                _ => false,
+            },
+            PropOrSpread::Spread(_) => false,
+        }),
+        _ => false,
+    }
+}
+
+/// Whether `module` has any JSX. Modules without it (plain `.ts` logic)
+/// skip the JSX transform.
+pub fn contains_jsx(module: &Module) -> bool {
+    let mut detector = JsxDetector::default();
+    module.visit_with(&mut detector);
+    detector.found
+}
+
+/// Visitor behind [`contains_jsx`]; stops descending at the first element
+#[derive(Default)]
+struct JsxDetector {
+    found: bool,
+}
+
+impl Visit for JsxDetector {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if self.found {
+            return;
+        }
+        if matches!(expr, Expr::JSXElement(_) | Expr::JSXFragment(_)) {
+            self.found = true;
+            return;
+        }
+        expr.visit_children_with(self);
+    }
+}
+
+/// How an entry module starts the app
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EntryShape {
+    /// A top-level statement calls `render(...)` or `hydrateRoot(...)`
+    pub mounts: bool,
+    /// The module has a default export for a harness to mount
+    pub default_export: bool,
+}
+
+impl EntryShape {
+    /// Whether loading the module can put anything on the page
+    pub fn starts_app(&self) -> bool {
+        self.mounts || self.default_export
+    }
+}
+
+/// Inspect the top-level statements of an entry module. Calls inside
+/// function declarations don't count, since nothing guarantees they run;
+/// callbacks passed at the top level (`addEventListener(..., () => render(...))`) do.
+pub fn inspect_entry(module: &Module) -> EntryShape {
+    let mut shape = EntryShape::default();
+    for item in &module.body {
+        match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(_) | ModuleDecl::ExportDefaultExpr(_)) => {
+                shape.default_export = true;
+            }
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) => {
+                shape.default_export |= export.specifiers.iter().any(|specifier| match specifier {
+                    ExportSpecifier::Named(named) => {
+                        matches!(&named.exported, Some(ModuleExportName::Ident(ident)) if &*ident.sym == "default")
+                    }
+                    ExportSpecifier::Default(_) => true,
+                    ExportSpecifier::Namespace(_) => false,
+                });
+            }
+            ModuleItem::Stmt(Stmt::Decl(Decl::Fn(_) | Decl::Class(_))) => {}
+            ModuleItem::Stmt(stmt) => {
+                let mut finder = MountCallFinder::default();
+                stmt.visit_with(&mut finder);
+                shape.mounts |= finder.found;
+            }
+            ModuleItem::ModuleDecl(_) => {}
+        }
+    }
+    shape
+}
+
+/// Visitor behind [`inspect_entry`]: finds a call to `render` or `hydrateRoot`
+#[derive(Default)]
+struct MountCallFinder {
+    found: bool,
+}
+
+impl Visit for MountCallFinder {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Callee::Expr(callee) = &call.callee {
+            if let Expr::Ident(ident) = &**callee {
+                if matches!(&*ident.sym, "render" | "hydrateRoot") {
+                    self.found = true;
+                    return;
+                }
+            }
+        }
+        call.visit_children_with(self);
+    }
+}
+
+/// Visitor that records identifier references, skipping import bindings
+#[derive(Default)]
+struct ReferenceCollector {
+    names: HashSet<String>,
+}
+
+impl Visit for ReferenceCollector {
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.names.insert(ident.sym.to_string());
+    }
+
+    // `use:tooltip` refers to the `tooltip` directive function
+    fn visit_jsx_namespaced_name(&mut self, name: &JSXNamespacedName) {
+        if name.ns.sym == "use" {
+            self.names.insert(name.name.sym.to_string());
+        }
+    }
+
+    fn visit_import_decl(&mut self, _: &ImportDecl) {}
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser;
+    use swc_core::common::SyntaxContext;
+
+    /// The binding of `name` in a module whose bindings aren't resolved
+    fn id(name: &str) -> Id {
+        (name.into(), SyntaxContext::empty())
+    }
 
     #[test]
     fn test_analyze_signals() {
@@ -218,8 +1295,34 @@ mod tests {
         let module = parser::parse(source, "test.tsx").unwrap();
         let analysis = analyze(&module).unwrap();
 
-        assert!(analysis.signals.contains("count"));
-        assert!(analysis.signals.contains("name"));
+        assert!(analysis.signals.contains(&id("count")));
+        assert!(analysis.signals.contains(&id("name")));
+    }
+
+    #[test]
+    fn test_analyze_signal_tuples() {
+        let source = r#"
+            function Counter() {
+                const [count, setCount] = createSignal(0);
+                const total = createSignal(0);
+                createEffect(() => total[1](total[0]() + 1));
+                createEffect(() => setCount(total[0]()));
+                return <div>{count()} {total[0]()}</div>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+
+        assert!(analysis.signals.contains(&id("count")));
+        assert!(analysis.setters.contains(&id("setCount")));
+        assert!(analysis.signal_tuples.contains(&id("total")));
+        assert!(!analysis.signals.contains(&id("total")));
+
+        let found: Vec<_> = analysis.diagnostics.iter().map(|d| (d.code, d.location(source))).collect();
+        assert_eq!(found, [("undestructured-signal", (4, 23)), ("effect-loop", (5, 17))]);
+        assert!(analysis.diagnostics[0].message.contains("`const [total, setTotal] = ...`"));
+        assert!(analysis.diagnostics[1].message.contains("reads `total[0]()` and writes it with `total[1](...)`"));
     }
 
     #[test]
@@ -235,7 +1338,480 @@ mod tests {
         let module = parser::parse(source, "test.tsx").unwrap();
         let analysis = analyze(&module).unwrap();
 
-        assert!(analysis.signals.contains("count"));
-        assert!(analysis.memos.contains("doubled"));
+        assert!(analysis.signals.contains(&id("count")));
+        assert!(analysis.memos.contains(&id("doubled")));
+    }
+
+    #[test]
+    fn test_analyze_jsx_dependencies() {
+        let source = r#"
+            function App() {
+                const [count, setCount] = createSignal(0);
+                const doubled = createMemo(() => count() * 2);
+                return (
+                    <div title={count()} onClick={() => setCount(count() + 1)}>
+                        {doubled} <b>{format(doubled(), count())}</b>
+                    </div>
+                );
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+
+        assert_eq!(analysis.jsx_dependencies[&1], ["count", "doubled"]);
+        assert_eq!(analysis.jsx_dependencies[&2], ["doubled", "count"]);
+    }
+
+    #[test]
+    fn test_analyze_computed() {
+        let source = r#"
+            function Form() {
+                const [first, setFirst] = createSignal("Ada");
+                const [full, setFull] = createSignal("");
+                const sync = createComputed(() => setFull(first() + "!"));
+                createComputed(() => setFirst(first().trim()));
+                return <p>{full()}</p>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+
+        // Reactive like an effect, with no value to read like a memo
+        assert!(analysis.effects.contains(&id("sync")));
+        assert!(analysis.memos.is_empty());
+        let found: Vec<_> = analysis.diagnostics.iter().map(|d| (d.code, d.location(source))).collect();
+        assert_eq!(found, [("effect-loop", (6, 17))]);
+    }
+
+    #[test]
+    fn test_analyze_setters() {
+        let source = r#"
+            function Counter() {
+                const [count, setCount] = createSignal(0);
+                const [, setHidden] = createSignal(false);
+                return <div onClick={() => setCount(count() + 1)}>{count}</div>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+
+        assert!(analysis.signals.contains(&id("count")));
+        assert!(analysis.setters.contains(&id("setCount")));
+        assert!(analysis.setters.contains(&id("setHidden")));
+        assert!(!analysis.signals.contains(&id("setCount")));
+        assert!(!analysis.signals.contains(&id("setHidden")));
+    }
+
+    #[test]
+    fn test_analyze_custom_signal_hooks() {
+        let source = r#"
+            function useCounter() {
+                const [count, setCount] = createSignal(0);
+                return [count, setCount];
+            }
+
+            const useToggle = () => createSignal(false);
+
+            function useDoubleCounter() {
+                const counter = useCounter();
+                return counter;
+            }
+
+            function App() {
+                const [a, setA] = useCounter();
+                const [b, setB] = useToggle();
+                const [c, setC] = useDoubleCounter();
+                return <div>{a()} {b()} {c()}</div>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+
+        assert!(analysis.signals.contains(&id("a")));
+        assert!(analysis.signals.contains(&id("b")));
+        assert!(analysis.signals.contains(&id("c")));
+        assert!(analysis.setters.contains(&id("setA")));
+        assert!(analysis.reactive_functions.contains("useCounter"));
+    }
+
+    #[test]
+    fn test_analyze_custom_reactive_functions() {
+        let source = r#"
+            function useTheme() {
+                return createLocalStorageSignal("theme", "light");
+            }
+
+            function App() {
+                const [count, setCount] = createLocalStorageSignal("count", 0);
+                const [theme, setTheme] = useTheme();
+                const doubled = createCachedMemo(() => count() * 2);
+                createLoggedEffect(() => setCount(count() + 1));
+                return <div>{doubled()} {theme()}</div>;
+            }
+        "#;
+        let functions = ReactiveFunctions {
+            signals: vec!["createLocalStorageSignal".to_string()],
+            memos: vec!["createCachedMemo".to_string()],
+            effects: vec!["createLoggedEffect".to_string()],
+        };
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze_with(&module, &functions).unwrap();
+
+        assert!(analysis.signals.contains(&id("count")) && analysis.signals.contains(&id("theme")));
+        assert!(analysis.setters.contains(&id("setCount")));
+        assert!(analysis.memos.contains(&id("doubled")));
+        assert!(analysis.reactive_functions.contains("useTheme"));
+        assert!(analysis.reactive_functions.contains("createCachedMemo"));
+        let codes: Vec<_> = analysis.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["effect-loop"]);
+
+        let analysis = analyze(&module).unwrap();
+        assert!(analysis.signals.is_empty() && analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_resolved_bindings() {
+        let source = r#"
+            function Counter() {
+                const [count, setCount] = createSignal(0);
+                return <div>{count()}</div>;
+            }
+
+            function Stepper({ count, setCount }) {
+                createEffect(() => setCount(count() + 1));
+                return <div>{count()}</div>;
+            }
+        "#;
+        let mut module = parser::parse(source, "test.tsx").unwrap();
+
+        // By name, the props look like Counter's signal
+        let analysis = analyze(&module).unwrap();
+        let codes: Vec<_> = analysis.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["effect-loop"]);
+
+        resolve_bindings(&mut module);
+        let analysis = analyze(&module).unwrap();
+        assert_eq!(analysis.signals.len(), 1);
+        assert!(analysis.diagnostics.is_empty());
+        assert_eq!(analysis.jsx_dependencies[&1], ["count"]);
+        assert!(analysis.jsx_dependencies[&2].is_empty());
+    }
+
+    #[test]
+    fn test_analyze_ignores_non_signal_functions() {
+        let source = r#"
+            function usePair() {
+                return [1, 2];
+            }
+
+            function App() {
+                const [a, b] = usePair();
+                return <div>{a}</div>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+
+        assert!(!analysis.signals.contains(&id("a")));
+        assert!(!analysis.reactive_functions.contains("usePair"));
+    }
+
+    #[test]
+    fn test_analyze_effect_feedback_loop() {
+        let source = r#"
+            function Counter() {
+                const [count, setCount] = createSignal(0);
+                createEffect(() => {
+                    setCount(count() + 1);
+                });
+                return <div>{count}</div>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+
+        assert_eq!(analysis.diagnostics.len(), 1);
+        let diagnostic = &analysis.diagnostics[0];
+        assert_eq!(diagnostic.code, "effect-loop");
+        assert!(diagnostic.message.contains("count"));
+        assert_eq!(diagnostic.location(source), (4, 17));
+    }
+
+    #[test]
+    fn test_check_unused_imports() {
+        let source = r#"
+            import "./styles.css";
+            import { a, b } from "./ab";
+            import C, * as d from "./cd";
+            export { C };
+            function App() {
+                return <d.Panel>{a()}</d.Panel>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let diagnostics = check_unused_imports(&module);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "unused-import");
+        assert!(diagnostics[0].message.contains("`b`"));
+        assert_eq!(diagnostics[0].location(source), (3, 25));
+    }
+
+    #[test]
+    fn test_check_jsx_structure() {
+        let source = r#"
+            function Table(props) {
+                return (
+                    <table>
+                        <tr><td class="a" class="b">1</td></tr>
+                        <div><td>2</td></div>
+                        {props.rows.map((row) => <tr><td>{row}</td></tr>)}
+                        <br>text</br>
+                        <select><option>ok</option></select>
+                        <ul>{props.items.map((item) => <option>{item}</option>)}</ul>
+                        <Cell><td>3</td></Cell>
+                        <>
+                            <tr><td>4</td></tr>
+                        </>
+                    </table>
+                );
+            }
+            function Row() {
+                return <tr><td>5</td></tr>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let diagnostics = check_jsx_structure(&module);
+        let codes: Vec<&str> = diagnostics.iter().map(|d| d.code).collect();
+
+        assert_eq!(codes, ["duplicate-attribute", "invalid-nesting", "void-element-children", "invalid-nesting"]);
+        assert!(diagnostics[1].message.contains("<td> inside <div>"));
+        assert!(diagnostics[3].message.contains("<option> inside <ul>"));
+    }
+
+    #[test]
+    fn test_analyze_inner_html_children() {
+        let source = r#"
+            function Post(props) {
+                return (
+                    <section>
+                        <div innerHTML={props.html}>
+                            {/* filled by the HTML */}
+                        </div>
+                        <div dangerouslySetInnerHTML={{ __html: props.html }}><p>lost</p></div>
+                        <Card innerHTML={props.html}>kept</Card>
+                    </section>
+                );
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+        let found: Vec<_> = analysis.diagnostics.iter().map(|d| (d.code, d.location(source))).collect();
+
+        assert_eq!(found, [("inner-html-children", (8, 25))]);
+        assert!(analysis.diagnostics[0].message.starts_with("<div> sets `dangerouslySetInnerHTML`"));
+    }
+
+    #[test]
+    fn test_analyze_missing_list_keys() {
+        let source = r#"
+            function List(props) {
+                return (
+                    <ul>
+                        {props.items.map(item => <li>{item}</li>)}
+                        {props.items.map(item => <li key={item.id}>{item}</li>)}
+                        {props.items.map(function (item) { return (<li>{item}</li>); })}
+                        {props.items.map(item => <>{item}</>)}
+                    </ul>
+                );
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+        let codes: Vec<&str> = analysis.diagnostics.iter().map(|d| d.code).collect();
+
+        assert_eq!(codes, ["missing-key", "missing-key"]);
+    }
+
+    #[test]
+    fn test_analyze_missing_keys_in_mapped_lists() {
+        let source = r#"
+            function Table(props) {
+                const rows = props.items.map(item => <Row {...item} />);
+                const keyed = props.items.map(item => <Row key={item.id} {...item} />);
+                const labels = props.items.map(item => item.label);
+                return (
+                    <Layout sidebar={props.links.map(link => link.external ? <a href={link.url} /> : <Link key={link.url} />)}>
+                        {rows}
+                        {props.items.map(item => <Row {...item} />)}
+                    </Layout>
+                );
+            }
+            function Cells(props) {
+                return props.cells.map(function (cell) {
+                    return <Cell value={cell} />;
+                });
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+        let found: Vec<_> = analysis
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.location(source), d.message.clone()))
+            .collect();
+
+        assert_eq!(found.len(), 4, "{:#?}", found);
+        assert!(found.iter().all(|(code, _, _)| *code == "missing-key"));
+        // Reported at the callback, naming what it returns
+        assert_eq!(found[0].1, (3, 46));
+        assert!(found[0].2.starts_with("`.map` callback returns <Row> without a `key`"));
+        assert!(found[1].2.contains("<a>"));
+        // The JSX child is reported once, by the element check
+        assert_eq!(found[2].1, (9, 50));
+        assert!(found[2].2.starts_with("elements rendered from `.map`"));
+        assert_eq!(found[3].1, (14, 40));
+    }
+
+    #[test]
+    fn test_analyze_effect_without_feedback() {
+        let source = r#"
+            function Counter() {
+                const [count, setCount] = createSignal(0);
+                const [log, setLog] = createSignal("");
+                createEffect(() => setLog(`count is ${count()}`));
+                createEffect(() => setCount(untrack(() => count()) + 1));
+                createEffect(() => {
+                    document.onclick = () => setCount(count() + 1);
+                });
+                return <div>{count}</div>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+
+        assert!(analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_contains_jsx() {
+        let logic = parser::parse("export const double = (n: number) => n * 2;", "math.ts").unwrap();
+        let nested = parser::parse("export const view = () => [1].map(n => <>{n}</>);", "view.tsx").unwrap();
+
+        assert!(!contains_jsx(&logic));
+        assert!(contains_jsx(&nested));
+    }
+
+    #[test]
+    fn test_analyze_component_forms() {
+        let components = [
+            "function App() { BODY }",
+            "const App = () => { BODY };",
+            "const App = function () { BODY };",
+            "export default function () { BODY }",
+            "export const App = () => { BODY };",
+            "const views = { app() { BODY } };",
+            "class Views { app() { BODY } }",
+            "const App = memo(() => { BODY });",
+        ];
+        let body = r#"
+            const [count, setCount] = useCounter();
+            const doubled = createMemo(() => count() * 2);
+            createEffect(() => setCount(count() + 1));
+            return <div>{doubled()}</div>;
+        "#;
+
+        for component in components {
+            let source = format!(
+                "{}\n{}",
+                "const useCounter = () => createSignal(0);",
+                component.replace("BODY", body)
+            );
+            let module = parser::parse(&source, "test.tsx").unwrap();
+            let analysis = analyze(&module).unwrap();
+
+            assert!(analysis.signals.contains(&id("count")), "{}", component);
+            assert_eq!(analysis.setter_signals.get(&id("setCount")), Some(&id("count")), "{}", component);
+            assert!(analysis.memos.contains(&id("doubled")), "{}", component);
+            let codes: Vec<&str> = analysis.diagnostics.iter().map(|d| d.code).collect();
+            assert_eq!(codes, ["effect-loop"], "{}", component);
+        }
+    }
+
+    #[test]
+    fn test_collect_signal_hooks_forms() {
+        let source = r#"
+            function useA() { return createSignal(0); }
+            const useB = () => createSignal(0);
+            const useC = function () { const s = createSignal(0); return s; };
+            export function useD() { const [d, setD] = useA(); return [d, setD]; }
+            export const useE = () => { return useB(); };
+            export default function useF() { return createSignal(0); }
+            const notAHook = () => 1;
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let mut hooks: Vec<String> = collect_signal_hooks(&module, &[]).into_iter().collect();
+        hooks.sort();
+
+        assert_eq!(hooks, ["useA", "useB", "useC", "useD", "useE", "useF"]);
+    }
+
+    #[test]
+    fn test_check_resource_keys() {
+        let source = r#"
+            const [page, setPage] = createSignal(1);
+            const [settings] = createResource(`settings-${page()}`, loadSettings);
+
+            function Profile(props) {
+                const [id, setId] = createSignal(props.id);
+                const [user] = createResource(`user-${id()}`, () => fetchUser(id()));
+                const [posts] = createResource("posts", () => fetchPosts(id()));
+                const [feed] = createResource(props.feed, loadFeed);
+                return <div>{user()}</div>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyze(&module).unwrap();
+        let diagnostics = check_resource_keys(&module, &analysis, source);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "unstable-resource-key");
+        assert!(
+            diagnostics[0].message.starts_with("resource key `user-${id()}` reads `id()`"),
+            "{}",
+            diagnostics[0].message
+        );
+        assert_eq!(diagnostics[0].location(source), (7, 47));
+    }
+
+    #[test]
+    fn test_inspect_entry() {
+        let shape = |source: &str| inspect_entry(&parser::parse(source, "index.tsx").unwrap());
+
+        assert_eq!(
+            shape("import { render } from 'velocity-runtime';\nrender(<App />, document.getElementById('root'));"),
+            EntryShape { mounts: true, default_export: false }
+        );
+        assert!(shape("document.addEventListener('DOMContentLoaded', () => render(<App />, root));").mounts);
+        assert!(shape("export default function App() { return <div />; }").default_export);
+        assert!(shape("function App() { return <div />; }\nexport { App as default };").default_export);
+
+        let idle = shape("export function App() { return <div />; }\nfunction start() { render(<App />, root); }");
+        assert!(!idle.starts_app());
     }
 }
@@ -0,0 +1,256 @@
+//! Accessibility Checks
+//!
+//! Common JSX mistakes that leave an element unusable with a screen reader
+//! or keyboard, reported in strict mode with a suggested fix:
+//! - `a11y-img-alt`: `<img>` without `alt`
+//! - `a11y-anchor-href`: `<a>` without `href`, which isn't focusable
+//! - `a11y-button-label`: `<button>` with nothing to announce, such as only
+//!   an icon (`<svg>`, an `<img>` without alt text, or a component named
+//!   `Icon` or `...Icon`) and no `aria-label`
+//! - `a11y-click-events`: a click handler on an element that isn't
+//!   interactive and has no `role`, so keyboards can't trigger it
+//!
+//! Elements with a spread (`{...props}`) may get the missing attribute from
+//! it and aren't reported. Each rule can be turned off in [`A11yRules`].
+
+use crate::diagnostics::Diagnostic;
+use crate::error::{CompilerError, Result};
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::{Visit, VisitWith};
+
+/// Elements a keyboard can reach and activate without extra attributes
+const INTERACTIVE_ELEMENTS: &[&str] = &[
+    "a", "button", "input", "select", "textarea", "summary", "details", "label", "option", "audio", "video",
+];
+
+/// Attributes that give an element an accessible name
+const LABEL_ATTRS: &[&str] = &["aria-label", "aria-labelledby", "title"];
+
+/// Accessibility checks run in strict mode, each named by the code of the
+/// diagnostic it reports. All are on by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct A11yRules {
+    /// `a11y-img-alt`
+    pub img_alt: bool,
+    /// `a11y-anchor-href`
+    pub anchor_href: bool,
+    /// `a11y-button-label`
+    pub button_label: bool,
+    /// `a11y-click-events`
+    pub click_events: bool,
+}
+
+impl Default for A11yRules {
+    fn default() -> Self {
+        Self {
+            img_alt: true,
+            anchor_href: true,
+            button_label: true,
+            click_events: true,
+        }
+    }
+}
+
+impl A11yRules {
+    /// Diagnostic codes of every rule
+    pub const CODES: &'static [&'static str] =
+        &["a11y-img-alt", "a11y-anchor-href", "a11y-button-label", "a11y-click-events"];
+
+    /// Turn the rule reporting `code` on or off
+    pub fn set(&mut self, code: &str, enabled: bool) -> Result<()> {
+        let rule = match code {
+            "a11y-img-alt" => &mut self.img_alt,
+            "a11y-anchor-href" => &mut self.anchor_href,
+            "a11y-button-label" => &mut self.button_label,
+            "a11y-click-events" => &mut self.click_events,
+            other => {
+                return Err(CompilerError::InvalidConfig(format!(
+                    "Unknown accessibility rule '{}' (expected one of {})",
+                    other,
+                    Self::CODES.join(", ")
+                )))
+            }
+        };
+        *rule = enabled;
+        Ok(())
+    }
+}
+
+/// Report the accessibility mistakes `rules` enables in `module`'s JSX
+pub fn check_accessibility(module: &Module, rules: &A11yRules) -> Vec<Diagnostic> {
+    let mut checker = A11yChecker {
+        rules,
+        diagnostics: Vec::new(),
+    };
+    module.visit_with(&mut checker);
+    checker.diagnostics
+}
+
+struct A11yChecker<'a> {
+    rules: &'a A11yRules,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl A11yChecker<'_> {
+    fn report(&mut self, code: &'static str, message: String, elem: &JSXElement) {
+        self.diagnostics.push(Diagnostic::warning(code, message, elem.opening.span));
+    }
+}
+
+impl Visit for A11yChecker<'_> {
+    fn visit_jsx_element(&mut self, elem: &JSXElement) {
+        elem.visit_children_with(self);
+
+        let Some(tag) = dom_tag(elem) else {
+            return;
+        };
+        let attrs = &elem.opening.attrs;
+        if attrs.iter().any(|attr| matches!(attr, JSXAttrOrSpread::SpreadElement(_))) {
+            return;
+        }
+
+        match tag {
+            "img" if self.rules.img_alt && attr(attrs, "alt").is_none() => self.report(
+                "a11y-img-alt",
+                "<img> has no `alt`; describe the image in `alt`, or use `alt=\"\"` if it's decorative".to_string(),
+                elem,
+            ),
+            "a" if self.rules.anchor_href && attr(attrs, "href").is_none() => self.report(
+                "a11y-anchor-href",
+                "<a> has no `href`, so keyboards can't reach it; add the link's `href`, or use a <button> \
+                 for an action"
+                    .to_string(),
+                elem,
+            ),
+            "button" if self.rules.button_label && !has_label(attrs) && !has_text(&elem.children) => self.report(
+                "a11y-button-label",
+                "<button> has no text for screen readers to announce; add an `aria-label` describing what \
+                 it does, or visible text"
+                    .to_string(),
+                elem,
+            ),
+            _ => {}
+        }
+
+        let handler = ["onClick", "onclick", "on:click"].into_iter().find(|name| attr(attrs, name).is_some());
+        if let Some(handler) = handler {
+            if self.rules.click_events && !INTERACTIVE_ELEMENTS.contains(&tag) && attr(attrs, "role").is_none() {
+                self.report(
+                    "a11y-click-events",
+                    format!(
+                        "`{}` on <{}>, which keyboards can't focus or activate; use a <button>, or add \
+                         `role=\"button\"`, `tabIndex={{0}}` and an `onKeyDown` handler",
+                        handler, tag
+                    ),
+                    elem,
+                );
+            }
+        }
+    }
+}
+
+/// The tag of a DOM element (a lowercase plain name); `None` for components
+fn dom_tag(elem: &JSXElement) -> Option<&str> {
+    match &elem.opening.name {
+        JSXElementName::Ident(ident) if ident.sym.starts_with(|c: char| c.is_ascii_lowercase()) => Some(&ident.sym),
+        _ => None,
+    }
+}
+
+/// The attribute `name`, if set
+fn attr<'a>(attrs: &'a [JSXAttrOrSpread], name: &str) -> Option<&'a JSXAttr> {
+    attrs.iter().find_map(|attr| match attr {
+        JSXAttrOrSpread::JSXAttr(attr) => {
+            let attr_name = match &attr.name {
+                JSXAttrName::Ident(ident) => ident.sym.to_string(),
+                JSXAttrName::JSXNamespacedName(name) => format!("{}:{}", name.ns.sym, name.name.sym),
+            };
+            (attr_name == name).then_some(attr)
+        }
+        JSXAttrOrSpread::SpreadElement(_) => None,
+    })
+}
+
+/// Whether an attribute gives the element an accessible name
+fn has_label(attrs: &[JSXAttrOrSpread]) -> bool {
+    LABEL_ATTRS.iter().any(|name| attr(attrs, name).is_some())
+}
+
+/// Whether `children` may render text: text, an expression, a labelled or
+/// texted element, or a component other than an icon
+fn has_text(children: &[JSXElementChild]) -> bool {
+    children.iter().any(|child| match child {
+        JSXElementChild::JSXText(text) => !text.value.trim().is_empty(),
+        JSXElementChild::JSXExprContainer(container) => !matches!(container.expr, JSXExpr::JSXEmptyExpr(_)),
+        JSXElementChild::JSXSpreadChild(_) => true,
+        JSXElementChild::JSXFragment(fragment) => has_text(&fragment.children),
+        JSXElementChild::JSXElement(elem) => match dom_tag(elem) {
+            Some("img") => attr(&elem.opening.attrs, "alt").is_some_and(|alt| !is_empty_string(alt)),
+            Some(_) => has_label(&elem.opening.attrs) || has_text(&elem.children),
+            None => !is_icon_component(elem),
+        },
+    })
+}
+
+fn is_empty_string(attr: &JSXAttr) -> bool {
+    matches!(&attr.value, Some(JSXAttrValue::Lit(Lit::Str(value))) if value.value.trim().is_empty())
+}
+
+fn is_icon_component(elem: &JSXElement) -> bool {
+    let name = match &elem.opening.name {
+        JSXElementName::Ident(ident) => ident.sym.as_ref(),
+        JSXElementName::JSXMemberExpr(member) => member.prop.sym.as_ref(),
+        JSXElementName::JSXNamespacedName(_) => return false,
+    };
+    name.ends_with("Icon")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn check(source: &str, rules: &A11yRules) -> Vec<&'static str> {
+        let module = parser::parse(source, "test.tsx").unwrap();
+        check_accessibility(&module, rules).into_iter().map(|diagnostic| diagnostic.code).collect()
+    }
+
+    #[test]
+    fn test_accessibility_rules() {
+        let rules = A11yRules::default();
+        let flagged = [
+            ("<img src=\"/logo.png\" />", "a11y-img-alt"),
+            ("<a onClick={go}>Next</a>", "a11y-anchor-href"),
+            ("<button onClick={close}><svg><path d=\"M0 0\" /></svg></button>", "a11y-button-label"),
+            ("<button><CloseIcon /></button>", "a11y-button-label"),
+            ("<button><img src=\"/x.svg\" alt=\"\" /></button>", "a11y-button-label"),
+            ("<div onClick={open}>Open</div>", "a11y-click-events"),
+        ];
+        for (jsx, code) in flagged {
+            assert_eq!(check(&format!("const view = {};", jsx), &rules), [code], "{}", jsx);
+        }
+
+        let allowed = [
+            "<img src=\"/divider.png\" alt=\"\" />",
+            "<a href=\"/docs\">Docs</a>",
+            "<button aria-label=\"Close\"><CloseIcon /></button>",
+            "<button>{label()}</button>",
+            "<button><Trans id=\"save\" /></button>",
+            "<button><img src=\"/x.svg\" alt=\"Close\" /></button>",
+            "<div role=\"button\" tabIndex={0} onClick={open} onKeyDown={open}>Open</div>",
+            "<img {...props} />",
+            "<Image src=\"/logo.png\" />",
+        ];
+        for jsx in allowed {
+            assert!(check(&format!("const view = {};", jsx), &rules).is_empty(), "{}", jsx);
+        }
+    }
+
+    #[test]
+    fn test_disabled_accessibility_rules() {
+        let mut rules = A11yRules::default();
+        rules.set("a11y-click-events", false).unwrap();
+        assert_eq!(check("const view = <span onClick={f}><img /></span>;", &rules), ["a11y-img-alt"]);
+        assert!(rules.set("a11y-color-contrast", false).is_err());
+    }
+}
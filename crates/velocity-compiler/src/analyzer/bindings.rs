@@ -0,0 +1,85 @@
+//! Binding Resolution
+//!
+//! The analysis records signals and memos by identifier, and a name alone
+//! doesn't say which declaration an identifier refers to: a `count` signal in
+//! one component and a `count` parameter in another function are unrelated.
+//! [`resolve_bindings`] runs SWC's resolver, which walks the module with a
+//! stack of scopes and gives every identifier the syntax context of the scope
+//! declaring it. Two identifiers then refer to the same binding exactly when
+//! their [`Id`]s (name and context) are equal.
+//!
+//! The contexts belong to a hygiene table that only lives for the call, so
+//! they're only good for telling bindings apart. Code generation clears them
+//! with [`clear_bindings`] before resolving the output again for its own
+//! rewrites.
+
+use swc_core::common::{Globals, Mark, SyntaxContext, GLOBALS};
+use swc_core::ecma::ast::*;
+use swc_core::ecma::transforms::base::resolver;
+use swc_core::ecma::visit::{VisitMut, VisitMutWith};
+
+/// Give every identifier in `module` the syntax context of the binding it
+/// refers to. Expects TypeScript already stripped.
+pub fn resolve_bindings(module: &mut Module) {
+    GLOBALS.set(&Globals::new(), || {
+        module.visit_mut_with(&mut resolver(Mark::new(), Mark::new(), false));
+    });
+}
+
+/// Reset every syntax context in `module`, undoing [`resolve_bindings`]
+pub(crate) fn clear_bindings(module: &mut Module) {
+    module.visit_mut_with(&mut ContextClearer);
+}
+
+struct ContextClearer;
+
+impl VisitMut for ContextClearer {
+    fn visit_mut_syntax_context(&mut self, ctxt: &mut SyntaxContext) {
+        *ctxt = SyntaxContext::empty();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+    use swc_core::ecma::visit::{Visit, VisitWith};
+
+    /// Every `count` identifier in the module, in source order
+    fn counts(module: &Module) -> Vec<Id> {
+        struct Finder(Vec<Id>);
+        impl Visit for Finder {
+            fn visit_ident(&mut self, ident: &Ident) {
+                if ident.sym == "count" {
+                    self.0.push(ident.to_id());
+                }
+            }
+        }
+
+        let mut finder = Finder(Vec::new());
+        module.visit_with(&mut finder);
+        finder.0
+    }
+
+    #[test]
+    fn test_resolve_bindings_separates_shadowed_names() {
+        let source = r#"
+            const [count, setCount] = createSignal(0);
+            function show(count) {
+                return count;
+            }
+            export const read = () => count();
+        "#;
+        let mut module = parser::parse(source, "test.tsx").unwrap();
+
+        resolve_bindings(&mut module);
+        let ids = counts(&module);
+        assert_eq!(ids.len(), 4);
+        assert_ne!(ids[0], ids[1]);
+        assert_eq!(ids[1], ids[2]);
+        assert_eq!(ids[0], ids[3]);
+
+        clear_bindings(&mut module);
+        assert!(counts(&module).iter().all(|id| id.1 == SyntaxContext::empty()));
+    }
+}
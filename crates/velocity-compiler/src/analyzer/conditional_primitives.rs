@@ -0,0 +1,258 @@
+//! Reactive Primitives Created Conditionally
+//!
+//! Signals, effects and memos belong at the top level of a component or
+//! hook, like React's rules of hooks. One created inside an `if`, a loop or
+//! a `.map()` callback is created a varying number of times from render to
+//! render, and the effects among them are never disposed, so they pile up
+//! and keep running. The same goes for one created inside an effect or memo
+//! callback, which runs again on every change.
+//!
+//! Callbacks that don't run while rendering, such as event handlers and
+//! `onMount` callbacks, aren't checked.
+
+use super::ReactiveFunctions;
+use crate::diagnostics::Diagnostic;
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::{Visit, VisitWith};
+use std::collections::HashSet;
+
+/// Array methods that call their callback once per item
+const ITERATION_METHODS: &[&str] = &[
+    "map", "flatMap", "forEach", "filter", "reduce", "reduceRight", "some", "every", "find", "findIndex",
+];
+
+/// Primitives whose callback runs again whenever a signal it read changes
+const RERUNNING_PRIMITIVES: &[&str] = &[
+    "createEffect", "createRenderEffect", "createComputed", "createMemo", "useEffect", "useMemo",
+];
+
+/// Warn about calls to `reactive_functions` (the analysis' primitives and
+/// signal hooks) that aren't at the top level of the function rendering
+/// them. The callbacks of `custom` memos and effects re-run like the
+/// built-in ones'.
+pub(super) fn check_conditional_primitives(
+    module: &Module,
+    reactive_functions: &HashSet<String>,
+    custom: &ReactiveFunctions,
+) -> Vec<Diagnostic> {
+    let mut checker = PlacementChecker {
+        reactive_functions,
+        custom,
+        rendering: false,
+        nested_in: None,
+        diagnostics: Vec::new(),
+    };
+    module.visit_with(&mut checker);
+    checker.diagnostics
+}
+
+struct PlacementChecker<'a> {
+    reactive_functions: &'a HashSet<String>,
+    custom: &'a ReactiveFunctions,
+    /// Whether the code being visited runs while rendering: in a function
+    /// body, but not in a callback that runs later
+    rendering: bool,
+    /// What the code being visited is nested in within its function, if it
+    /// isn't at the top level
+    nested_in: Option<&'static str>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl PlacementChecker<'_> {
+    /// Visit `node` with `nested_in` as what it's nested in, if it's checked
+    fn visit_nested<N: VisitWith<Self>>(&mut self, node: &N, nested_in: &'static str) {
+        let outer = self.nested_in;
+        if self.rendering {
+            self.nested_in = Some(nested_in);
+        }
+        node.visit_with(self);
+        self.nested_in = outer;
+    }
+
+    /// Visit the function `expr` with the given placement, or `expr` as
+    /// usual if it isn't a function
+    fn visit_callback(&mut self, expr: &Expr, rendering: bool, nested_in: Option<&'static str>) {
+        let outer = (self.rendering, self.nested_in);
+        (self.rendering, self.nested_in) = (rendering, nested_in);
+        match expr {
+            Expr::Arrow(arrow) => arrow.visit_children_with(self),
+            Expr::Fn(function) => function.function.visit_children_with(self),
+            _ => {
+                (self.rendering, self.nested_in) = outer;
+                expr.visit_with(self);
+            }
+        }
+        (self.rendering, self.nested_in) = outer;
+    }
+
+    /// Visit a function body, which starts at its own top level
+    fn visit_function_body<N: VisitWith<Self>>(&mut self, node: &N) {
+        let outer = (self.rendering, self.nested_in);
+        (self.rendering, self.nested_in) = (true, None);
+        node.visit_children_with(self);
+        (self.rendering, self.nested_in) = outer;
+    }
+}
+
+impl Visit for PlacementChecker<'_> {
+    fn visit_function(&mut self, function: &Function) {
+        self.visit_function_body(function);
+    }
+
+    fn visit_arrow_expr(&mut self, arrow: &ArrowExpr) {
+        self.visit_function_body(arrow);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        let Callee::Expr(callee) = &call.callee else {
+            call.visit_children_with(self);
+            return;
+        };
+        callee.visit_with(self);
+
+        let name = match &**callee {
+            Expr::Ident(ident) => Some(ident.sym.as_ref()),
+            _ => None,
+        };
+        if let (Some(name), Some(nested_in)) = (name, self.nested_in) {
+            if self.rendering && self.reactive_functions.contains(name) {
+                self.diagnostics.push(Diagnostic::warning(
+                    "conditional-primitive",
+                    format!(
+                        "`{name}` is called inside {nested_in}, so how many times it runs changes from \
+                         render to render and effects it creates are never disposed; call it at the \
+                         top level of the component and move the condition or loop inside it"
+                    ),
+                    call.span,
+                ));
+            }
+        }
+
+        let method = match &**callee {
+            Expr::Member(MemberExpr { prop: MemberProp::Ident(prop), .. }) => Some(prop.sym.as_ref()),
+            _ => None,
+        };
+        let (rendering, nested_in) = match (name, method) {
+            (_, Some(method)) if ITERATION_METHODS.contains(&method) => {
+                (self.rendering, Some("a callback that runs for every item"))
+            }
+            (Some(name), _)
+                if RERUNNING_PRIMITIVES.contains(&name) || self.custom.is_memo(name) || self.custom.is_effect(name) =>
+            {
+                (self.rendering, Some("an effect or memo callback, which runs again on every change"))
+            }
+            // Event handlers, `onMount` and the like run after rendering
+            _ => (false, None),
+        };
+        for arg in &call.args {
+            self.visit_callback(&arg.expr, rendering, nested_in);
+        }
+    }
+
+    fn visit_jsx_attr_value(&mut self, value: &JSXAttrValue) {
+        match value {
+            JSXAttrValue::JSXExprContainer(JSXExprContainer { expr: JSXExpr::Expr(expr), .. }) => {
+                self.visit_callback(expr, false, None);
+            }
+            _ => value.visit_children_with(self),
+        }
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &IfStmt) {
+        stmt.test.visit_with(self);
+        self.visit_nested(&stmt.cons, "an `if`");
+        self.visit_nested(&stmt.alt, "an `if`");
+    }
+
+    fn visit_switch_stmt(&mut self, stmt: &SwitchStmt) {
+        stmt.discriminant.visit_with(self);
+        self.visit_nested(&stmt.cases, "a `switch`");
+    }
+
+    fn visit_for_stmt(&mut self, stmt: &ForStmt) {
+        stmt.init.visit_with(self);
+        self.visit_nested(&stmt.test, "a loop");
+        self.visit_nested(&stmt.update, "a loop");
+        self.visit_nested(&stmt.body, "a loop");
+    }
+
+    fn visit_for_of_stmt(&mut self, stmt: &ForOfStmt) {
+        stmt.right.visit_with(self);
+        self.visit_nested(&stmt.body, "a loop");
+    }
+
+    fn visit_for_in_stmt(&mut self, stmt: &ForInStmt) {
+        stmt.right.visit_with(self);
+        self.visit_nested(&stmt.body, "a loop");
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &WhileStmt) {
+        self.visit_nested(&stmt.test, "a loop");
+        self.visit_nested(&stmt.body, "a loop");
+    }
+
+    fn visit_do_while_stmt(&mut self, stmt: &DoWhileStmt) {
+        self.visit_nested(&stmt.body, "a loop");
+        self.visit_nested(&stmt.test, "a loop");
+    }
+
+    fn visit_cond_expr(&mut self, cond: &CondExpr) {
+        cond.test.visit_with(self);
+        self.visit_nested(&cond.cons, "a conditional expression");
+        self.visit_nested(&cond.alt, "a conditional expression");
+    }
+
+    fn visit_bin_expr(&mut self, bin: &BinExpr) {
+        bin.left.visit_with(self);
+        match bin.op {
+            BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing => {
+                self.visit_nested(&bin.right, "a conditional expression");
+            }
+            _ => bin.right.visit_with(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::Analysis;
+    use crate::parser;
+
+    fn check(source: &str) -> Vec<String> {
+        let module = parser::parse(source, "test.tsx").unwrap();
+        check_conditional_primitives(&module, &Analysis::default().reactive_functions, &ReactiveFunctions::default())
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_conditional_primitives() {
+        let flagged = [
+            "function App(props) { if (props.live) { createEffect(() => poll()); } }",
+            "function App(props) { for (const id of props.ids) { const [a] = createSignal(id); } }",
+            "const App = (props) => { const doubled = props.on ? createMemo(() => 2) : null; };",
+            "function App() { return <ul>{items.map(item => { const [open] = createSignal(false); return <li />; })}</ul>; }",
+            "function App() { createEffect(() => { createEffect(() => log(count())); }); }",
+            "function useToggle(on) { while (on()) { useEffect(() => {}); } }",
+        ];
+        for source in flagged {
+            let messages = check(source);
+            assert_eq!(messages.len(), 1, "{}", source);
+        }
+        assert!(check(flagged[0])[0].starts_with("`createEffect` is called inside an `if`"));
+        assert!(check(flagged[3])[0].contains("a callback that runs for every item"));
+
+        let allowed = [
+            "function App() { const [count, setCount] = createSignal(0); createEffect(() => { if (count()) log(); }); }",
+            "function App() { return <button onClick={() => { if (a) { createEffect(() => {}); } }} />; }",
+            "function App() { onMount(() => { for (const el of els) createEffect(() => el); }); }",
+            "if (import.meta.hot) { createEffect(() => reload()); }",
+            "function App() { if (ready) { const value = compute(); } }",
+        ];
+        for source in allowed {
+            assert!(check(source).is_empty(), "{}", source);
+        }
+    }
+}
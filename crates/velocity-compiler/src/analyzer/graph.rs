@@ -0,0 +1,287 @@
+//! Reactive Dependency Graph
+//!
+//! A static picture of a module's reactivity, before anything runs: its
+//! signals, memos and effects, and the JSX elements that update when they
+//! change, with an edge from each signal or memo to everything that reads
+//! it. Reads are found the way the runtime tracks them: getter calls
+//! (`count()`) in a memo or effect callback, or in a JSX attribute or
+//! expression child, outside nested functions and `untrack`.
+//!
+//! Names resolve to the nearest enclosing declaration, so two components
+//! each with a `count` signal get a node each. Signals kept as a whole
+//! `[getter, setter]` tuple aren't included.
+
+use super::{callback_reads, collect_signal_hooks, is_signal_call, jsx_reads};
+use crate::diagnostics;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use swc_core::common::Span;
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::{Visit, VisitWith};
+
+/// Signals, memos, effects and reactive JSX elements of a module, and which
+/// read which
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReactiveGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphNode {
+    /// The variable it's bound to, `memo`/`effect` for an unbound one, or
+    /// the element's tag (`<span>`)
+    pub name: String,
+    pub kind: NodeKind,
+    /// 1-based line of its declaration
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    Signal,
+    Memo,
+    Effect,
+    /// A JSX element with reactive attributes or children
+    Element,
+}
+
+/// `to` reads `from`, and re-runs or updates when it changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct GraphEdge {
+    /// Index of the signal or memo in [`ReactiveGraph::nodes`]
+    pub from: usize,
+    /// Index of the reader in [`ReactiveGraph::nodes`]
+    pub to: usize,
+}
+
+impl ReactiveGraph {
+    /// The graph in Graphviz DOT, e.g. for `dot -Tsvg`
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph reactivity {\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let shape = match node.kind {
+                NodeKind::Signal => "ellipse",
+                NodeKind::Memo => "diamond",
+                NodeKind::Effect => "box",
+                NodeKind::Element => "note",
+            };
+            let label = format!("{}:{}", node.name, node.line).replace('\\', "\\\\").replace('"', "\\\"");
+            dot.push_str(&format!("  n{} [label=\"{}\", shape={}];\n", i, label, shape));
+        }
+        for edge in &self.edges {
+            dot.push_str(&format!("  n{} -> n{};\n", edge.from, edge.to));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Build the reactive graph of `module`, parsed from `source`. Expects
+/// TypeScript already stripped, as [`analyze`](super::analyze) does.
+pub fn dependency_graph(module: &Module, source: &str) -> ReactiveGraph {
+    let mut builder = GraphBuilder {
+        source,
+        signal_hooks: collect_signal_hooks(module, &[]),
+        graph: ReactiveGraph::default(),
+        scopes: vec![HashMap::new()],
+        binding: None,
+    };
+    module.visit_with(&mut builder);
+    builder.graph
+}
+
+struct GraphBuilder<'a> {
+    source: &'a str,
+    signal_hooks: HashSet<String>,
+    graph: ReactiveGraph,
+    /// Signals and memos declared in the module and each enclosing
+    /// function, innermost last, by name
+    scopes: Vec<HashMap<String, usize>>,
+    /// Variable the call being visited is assigned to
+    binding: Option<String>,
+}
+
+impl GraphBuilder<'_> {
+    fn add_node(&mut self, name: String, kind: NodeKind, span: Span) -> usize {
+        let line = diagnostics::line_column(self.source, span).0;
+        self.graph.nodes.push(GraphNode { name, kind, line });
+        self.graph.nodes.len() - 1
+    }
+
+    fn declare(&mut self, name: String, node: usize) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, node);
+        }
+    }
+
+    /// Add an edge into `to` from each signal or memo in `reads`
+    fn add_reads(&mut self, to: usize, reads: &[Id]) {
+        for (name, _) in reads {
+            let Some(&from) = self.scopes.iter().rev().find_map(|scope| scope.get(&**name)) else {
+                continue;
+            };
+            let edge = GraphEdge { from, to };
+            if !self.graph.edges.contains(&edge) {
+                self.graph.edges.push(edge);
+            }
+        }
+    }
+
+    fn in_scope(&mut self, visit: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashMap::new());
+        visit(self);
+        self.scopes.pop();
+    }
+}
+
+impl Visit for GraphBuilder<'_> {
+    fn visit_function(&mut self, function: &Function) {
+        self.in_scope(|builder| function.visit_children_with(builder));
+    }
+
+    fn visit_arrow_expr(&mut self, arrow: &ArrowExpr) {
+        self.in_scope(|builder| arrow.visit_children_with(builder));
+    }
+
+    fn visit_var_declarator(&mut self, decl: &VarDeclarator) {
+        if let Some(Expr::Call(call)) = decl.init.as_deref() {
+            match &decl.name {
+                Pat::Array(array) if is_signal_call(&call.callee, &self.signal_hooks) => {
+                    if let Some(Some(Pat::Ident(getter))) = array.elems.first() {
+                        let node = self.add_node(getter.id.sym.to_string(), NodeKind::Signal, getter.id.span);
+                        self.declare(getter.id.sym.to_string(), node);
+                    }
+                }
+                Pat::Ident(ident) => self.binding = Some(ident.id.sym.to_string()),
+                _ => {}
+            }
+        }
+        decl.visit_children_with(self);
+        self.binding = None;
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        let binding = self.binding.take();
+        let kind = match &call.callee {
+            Callee::Expr(callee) => match &**callee {
+                Expr::Ident(ident) => match ident.sym.as_ref() {
+                    "createMemo" | "useMemo" => Some(NodeKind::Memo),
+                    "createEffect" | "createRenderEffect" | "createComputed" | "useEffect" => Some(NodeKind::Effect),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some(kind) = kind {
+            let name = binding.clone().unwrap_or_else(|| match kind {
+                NodeKind::Memo => "memo".to_string(),
+                _ => "effect".to_string(),
+            });
+            let node = self.add_node(name, kind, call.span);
+            self.add_reads(node, &callback_reads(call));
+            if let (NodeKind::Memo, Some(binding)) = (kind, binding) {
+                self.declare(binding, node);
+            }
+        }
+        call.visit_children_with(self);
+    }
+
+    fn visit_jsx_element(&mut self, elem: &JSXElement) {
+        let reads = jsx_reads(elem);
+        let reactive = reads
+            .iter()
+            .any(|(name, _)| self.scopes.iter().any(|scope| scope.contains_key(&**name)));
+        if reactive {
+            let tag = match &elem.opening.name {
+                JSXElementName::Ident(ident) => ident.sym.to_string(),
+                JSXElementName::JSXMemberExpr(member) => member.prop.sym.to_string(),
+                JSXElementName::JSXNamespacedName(name) => format!("{}:{}", name.ns.sym, name.name.sym),
+            };
+            let node = self.add_node(format!("<{}>", tag), NodeKind::Element, elem.opening.span);
+            self.add_reads(node, &reads);
+        }
+        elem.visit_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn graph(source: &str) -> ReactiveGraph {
+        let module = parser::parse(source, "test.tsx").unwrap();
+        dependency_graph(&module, source)
+    }
+
+    /// Edges as `(from name, to name)`
+    fn edges(graph: &ReactiveGraph) -> Vec<(&str, &str)> {
+        graph
+            .edges
+            .iter()
+            .map(|edge| (graph.nodes[edge.from].name.as_str(), graph.nodes[edge.to].name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let graph = graph(
+            r#"function Counter() {
+    const [count, setCount] = createSignal(0);
+    const [step] = createSignal(1);
+    const doubled = createMemo(() => count() * 2);
+    createEffect(() => console.log(doubled(), untrack(() => step())));
+    const onClick = () => setCount(count() + step());
+    return <button onClick={onClick}>{doubled()} <b>{step}</b></button>;
+}
+"#,
+        );
+
+        let nodes: Vec<_> = graph.nodes.iter().map(|n| (n.name.as_str(), n.kind, n.line)).collect();
+        assert_eq!(
+            nodes,
+            [
+                ("count", NodeKind::Signal, 2),
+                ("step", NodeKind::Signal, 3),
+                ("doubled", NodeKind::Memo, 4),
+                ("effect", NodeKind::Effect, 5),
+                ("<button>", NodeKind::Element, 7),
+                ("<b>", NodeKind::Element, 7),
+            ]
+        );
+        assert_eq!(
+            edges(&graph),
+            [("count", "doubled"), ("doubled", "effect"), ("doubled", "<button>"), ("step", "<b>")]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph reactivity {\n"));
+        assert!(dot.contains("  n2 [label=\"doubled:4\", shape=diamond];\n"), "{}", dot);
+        assert!(dot.contains("  n0 -> n2;\n"), "{}", dot);
+    }
+
+    #[test]
+    fn test_dependency_graph_scopes() {
+        let graph = graph(
+            r#"
+const [theme] = createSignal("light");
+function A() {
+    const [count] = createSignal(0);
+    return <p class={theme()}>{count()}</p>;
+}
+function B() {
+    const [count] = createSignal(0);
+    return <span>{count()}</span>;
+}
+"#,
+        );
+
+        let edges: Vec<_> = graph.edges.iter().map(|edge| (edge.from, edge.to)).collect();
+        // theme → <p>, A's count → <p>, B's count → <span>
+        assert_eq!(edges, [(0, 2), (1, 2), (3, 4)]);
+    }
+}
@@ -0,0 +1,277 @@
+//! Incremental Analysis (prototype, `incremental` feature)
+//!
+//! Re-analyzing a large file on every keystroke repeats the same work for
+//! every component the edit didn't touch. [`IncrementalAnalyzer`] analyzes a
+//! module one top-level item at a time and caches each item's result, so a
+//! recompile only re-analyzes the items whose source changed.
+//!
+//! An item's result depends on more than its own text: custom signal hooks
+//! defined anywhere in the file decide what counts as a signal, and effect
+//! diagnostics look up signals declared by the items before it. The cache
+//! key therefore hashes three things:
+//!
+//! 1. the item's source text
+//! 2. the module's signal hooks
+//! 3. the names declared by earlier items (signals, setters, signal tuples,
+//!    memos, effects)
+//!
+//! Editing a component body without renaming anything re-analyzes just that
+//! component; renaming or adding a signal also re-analyzes the items after
+//! it. The merged [`Analysis`] is identical to what [`analyze`] returns:
+//! - declared names are the union of every item's names
+//! - diagnostics are concatenated in item order, with cached spans shifted
+//!   by however far their item moved
+//! - `jsx_dependencies` keys are renumbered in item order
+//!
+//! Entries not used by a call are dropped at its end, so the cache only ever
+//! holds the current version of the file.
+//!
+//! Cached bindings keep the syntax contexts of the compile that produced
+//! them, so modules should come straight from the parser, without
+//! [`resolve_bindings`]; names then stand for every binding with that name.
+//!
+//! [`analyze`]: super::analyze
+//! [`resolve_bindings`]: super::resolve_bindings
+
+use super::{collect_signal_hooks, Analysis, ReactiveFunctions, ReactivityAnalyzer};
+use crate::diagnostics::{self, Diagnostic};
+use crate::error::Result;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use swc_core::common::{BytePos, Span, Spanned};
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::VisitWith;
+
+/// What one top-level item added to the analysis
+#[derive(Debug, Clone)]
+struct ItemAnalysis {
+    signals: HashSet<Id>,
+    setters: HashSet<Id>,
+    setter_signals: HashMap<Id, Id>,
+    signal_tuples: HashSet<Id>,
+    memos: HashSet<Id>,
+    effects: HashSet<Id>,
+    /// Dependencies of the item's JSX elements, in visit order
+    jsx_dependencies: Vec<Vec<String>>,
+    diagnostics: Vec<Diagnostic>,
+    /// Where the item started when it was analyzed
+    start: BytePos,
+}
+
+/// Analyzer that caches per-item results between compiles of one file
+#[derive(Debug, Default)]
+pub struct IncrementalAnalyzer {
+    cache: HashMap<u64, ItemAnalysis>,
+    reused: usize,
+}
+
+impl IncrementalAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Analyze `module`, parsed from `source`, reusing cached results for
+    /// unchanged items. Equivalent to [`super::analyze`].
+    pub fn analyze(&mut self, module: &Module, source: &str) -> Result<Analysis> {
+        let functions = ReactiveFunctions::default();
+        let hooks = collect_signal_hooks(module, &functions.signals);
+        let mut hook_names: Vec<&String> = hooks.iter().collect();
+        hook_names.sort();
+
+        let mut merged = ReactivityAnalyzer::new(hooks.clone(), &functions).analysis;
+        let mut next_cache = HashMap::new();
+        let mut declarations = declarations_hash(&merged);
+        self.reused = 0;
+
+        for item in &module.body {
+            let mut hasher = DefaultHasher::new();
+            diagnostics::span_text(source, item.span()).hash(&mut hasher);
+            hook_names.hash(&mut hasher);
+            declarations.hash(&mut hasher);
+            let key = hasher.finish();
+
+            let result = match self.cache.remove(&key) {
+                Some(cached) => {
+                    self.reused += 1;
+                    cached
+                }
+                None => analyze_item(item, &merged, hooks.clone(), &functions),
+            };
+
+            let added_names = merge(&mut merged, &result, item.span().lo);
+            if added_names {
+                declarations = declarations_hash(&merged);
+            }
+            next_cache.insert(key, result);
+        }
+
+        self.cache = next_cache;
+        Ok(merged)
+    }
+
+    /// How many items the last [`IncrementalAnalyzer::analyze`] took from the cache
+    pub fn reused(&self) -> usize {
+        self.reused
+    }
+}
+
+/// Analyze one item in the context of the items before it
+fn analyze_item(
+    item: &ModuleItem,
+    context: &Analysis,
+    hooks: HashSet<String>,
+    functions: &ReactiveFunctions,
+) -> ItemAnalysis {
+    let mut analyzer = ReactivityAnalyzer::new(hooks, functions);
+    analyzer.analysis.signals = context.signals.clone();
+    analyzer.analysis.setters = context.setters.clone();
+    analyzer.analysis.setter_signals = context.setter_signals.clone();
+    analyzer.analysis.signal_tuples = context.signal_tuples.clone();
+    analyzer.analysis.memos = context.memos.clone();
+    analyzer.analysis.effects = context.effects.clone();
+    item.visit_with(&mut analyzer);
+
+    let analysis = analyzer.analysis;
+    let mut jsx_keys: Vec<_> = analysis.jsx_dependencies.into_iter().collect();
+    jsx_keys.sort_by_key(|(key, _)| *key);
+
+    ItemAnalysis {
+        signals: analysis.signals.difference(&context.signals).cloned().collect(),
+        setters: analysis.setters.difference(&context.setters).cloned().collect(),
+        setter_signals: analysis
+            .setter_signals
+            .into_iter()
+            .filter(|(setter, signal)| context.setter_signals.get(setter) != Some(signal))
+            .collect(),
+        signal_tuples: analysis.signal_tuples.difference(&context.signal_tuples).cloned().collect(),
+        memos: analysis.memos.difference(&context.memos).cloned().collect(),
+        effects: analysis.effects.difference(&context.effects).cloned().collect(),
+        jsx_dependencies: jsx_keys.into_iter().map(|(_, deps)| deps).collect(),
+        diagnostics: analysis.diagnostics,
+        start: item.span().lo,
+    }
+}
+
+/// Add an item's result to `merged`, with its spans moved to `start`.
+/// Returns whether it declared any new names.
+fn merge(merged: &mut Analysis, item: &ItemAnalysis, start: BytePos) -> bool {
+    let mut added = false;
+    for name in &item.signals {
+        added |= merged.signals.insert(name.clone());
+    }
+    for name in &item.setters {
+        added |= merged.setters.insert(name.clone());
+    }
+    for (setter, signal) in &item.setter_signals {
+        added |= merged.setter_signals.insert(setter.clone(), signal.clone()).as_ref() != Some(signal);
+    }
+    for name in &item.signal_tuples {
+        added |= merged.signal_tuples.insert(name.clone());
+    }
+    for name in &item.memos {
+        added |= merged.memos.insert(name.clone());
+    }
+    for name in &item.effects {
+        added |= merged.effects.insert(name.clone());
+    }
+
+    for deps in &item.jsx_dependencies {
+        let key = merged.jsx_dependencies.len() + 1;
+        merged.jsx_dependencies.insert(key, deps.clone());
+    }
+
+    let offset = start.0 as i64 - item.start.0 as i64;
+    let shift = |pos: BytePos| BytePos((pos.0 as i64 + offset) as u32);
+    merged.diagnostics.extend(item.diagnostics.iter().map(|diagnostic| Diagnostic {
+        span: Span::new(shift(diagnostic.span.lo), shift(diagnostic.span.hi)),
+        ..diagnostic.clone()
+    }));
+
+    added
+}
+
+/// Order-independent fingerprint of the names declared so far
+fn declarations_hash(analysis: &Analysis) -> u64 {
+    fn sorted<'a>(ids: impl Iterator<Item = &'a Id>) -> Vec<&'a Id> {
+        let mut ids: Vec<_> = ids.collect();
+        ids.sort_by(|a, b| (&*a.0, a.1).cmp(&(&*b.0, b.1)));
+        ids
+    }
+
+    let mut setter_signals: Vec<_> = analysis.setter_signals.iter().collect();
+    setter_signals.sort_by(|a, b| (&*a.0 .0, a.0 .1).cmp(&(&*b.0 .0, b.0 .1)));
+
+    let mut hasher = DefaultHasher::new();
+    sorted(analysis.signals.iter()).hash(&mut hasher);
+    sorted(analysis.setters.iter()).hash(&mut hasher);
+    setter_signals.hash(&mut hasher);
+    sorted(analysis.signal_tuples.iter()).hash(&mut hasher);
+    sorted(analysis.memos.iter()).hash(&mut hasher);
+    sorted(analysis.effects.iter()).hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analyzer, parser};
+
+    const SOURCE: &str = r#"
+        const [theme, setTheme] = createSignal("light");
+
+        function Counter() {
+            const [count, setCount] = createSignal(0);
+            createEffect(() => setCount(count() + 1));
+            return <button>{count()}</button>;
+        }
+
+        function Header() {
+            createEffect(() => setTheme(theme() + "!"));
+            return <header><h1>{theme()}</h1></header>;
+        }
+    "#;
+
+    fn assert_same_analysis(incremental: &Analysis, full: &Analysis, source: &str) {
+        assert_eq!(incremental.signals, full.signals);
+        assert_eq!(incremental.setters, full.setters);
+        assert_eq!(incremental.setter_signals, full.setter_signals);
+        assert_eq!(incremental.signal_tuples, full.signal_tuples);
+        assert_eq!(incremental.memos, full.memos);
+        assert_eq!(incremental.effects, full.effects);
+        assert_eq!(incremental.jsx_dependencies, full.jsx_dependencies);
+        let summary = |analysis: &Analysis| -> Vec<_> {
+            analysis
+                .diagnostics
+                .iter()
+                .map(|d| (d.code, d.message.clone(), d.location(source)))
+                .collect()
+        };
+        assert_eq!(summary(incremental), summary(full));
+    }
+
+    #[test]
+    fn test_incremental_reuses_unchanged_items() {
+        let mut analyzer = IncrementalAnalyzer::new();
+
+        let module = parser::parse(SOURCE, "test.tsx").unwrap();
+        let first = analyzer.analyze(&module, SOURCE).unwrap();
+        assert_eq!(analyzer.reused(), 0);
+        assert_same_analysis(&first, &analyzer::analyze(&module).unwrap(), SOURCE);
+
+        // Growing Counter's body moves Header, whose cached diagnostic must follow
+        let edited = SOURCE.replace("<button>{count()}</button>", "<button class=\"big\">Count: {count()}</button>");
+        let module = parser::parse(&edited, "test.tsx").unwrap();
+        let second = analyzer.analyze(&module, &edited).unwrap();
+        assert_eq!(analyzer.reused(), 2);
+        assert_same_analysis(&second, &analyzer::analyze(&module).unwrap(), &edited);
+        assert_eq!(second.diagnostics.len(), 2);
+
+        // Renaming a signal changes what later items see, so they're redone
+        let renamed = edited.replace("[theme, setTheme]", "[mode, setTheme]");
+        let module = parser::parse(&renamed, "test.tsx").unwrap();
+        let third = analyzer.analyze(&module, &renamed).unwrap();
+        assert_eq!(analyzer.reused(), 0);
+        assert_same_analysis(&third, &analyzer::analyze(&module).unwrap(), &renamed);
+    }
+}
@@ -0,0 +1,184 @@
+//! Top-Level Side Effects
+//!
+//! A bundler may drop a module whose exports nothing uses only if loading it
+//! does nothing else: no calls, assignments or DOM work at the top level.
+//! [`has_side_effects`] answers that conservatively. Anything it can't prove
+//! harmless counts as a side effect:
+//! - declarations of functions, and classes without static initializers,
+//!   are pure
+//! - variable initializers and exported expressions are pure when built from
+//!   literals, identifiers, functions and operators on pure operands
+//! - calls and `new` are side effects unless annotated `/*#__PURE__*/` (or
+//!   `/*@__PURE__*/`), and even then their arguments must be pure
+//! - property reads can run getters, so they count as side effects
+//! - imports and re-exports are not; whether the imported module has side
+//!   effects is decided for that module
+//!
+//! Modules are checked after TypeScript is stripped, so enums (compiled to
+//! an assigning IIFE) count as side effects.
+
+use crate::diagnostics;
+use swc_core::common::{BytePos, Span};
+use swc_core::ecma::ast::*;
+
+/// Annotations that mark a call or `new` as free of side effects
+const PURE_ANNOTATIONS: &[&str] = &["/*#__PURE__*/", "/*@__PURE__*/"];
+
+/// Whether evaluating the top level of `module`, parsed from `source`, may
+/// have effects beyond defining its bindings
+pub fn has_side_effects(module: &Module, source: &str) -> bool {
+    let checker = SideEffectChecker { source };
+    module.body.iter().any(|item| checker.item_has_side_effects(item))
+}
+
+struct SideEffectChecker<'a> {
+    /// Read for `/*#__PURE__*/` annotations, which the parser drops
+    source: &'a str,
+}
+
+impl SideEffectChecker<'_> {
+    fn item_has_side_effects(&self, item: &ModuleItem) -> bool {
+        match item {
+            ModuleItem::ModuleDecl(decl) => match decl {
+                ModuleDecl::ExportDecl(export) => self.decl_has_side_effects(&export.decl),
+                ModuleDecl::ExportDefaultDecl(export) => match &export.decl {
+                    DefaultDecl::Class(class) => self.class_has_side_effects(&class.class),
+                    DefaultDecl::Fn(_) | DefaultDecl::TsInterfaceDecl(_) => false,
+                },
+                ModuleDecl::ExportDefaultExpr(export) => !self.is_pure(&export.expr),
+                ModuleDecl::Import(_) | ModuleDecl::ExportNamed(_) | ModuleDecl::ExportAll(_) => false,
+                _ => true,
+            },
+            ModuleItem::Stmt(stmt) => match stmt {
+                Stmt::Decl(decl) => self.decl_has_side_effects(decl),
+                Stmt::Empty(_) => false,
+                // Includes directives such as "use strict"
+                Stmt::Expr(expr) => !self.is_pure(&expr.expr),
+                _ => true,
+            },
+        }
+    }
+
+    fn decl_has_side_effects(&self, decl: &Decl) -> bool {
+        match decl {
+            Decl::Fn(_) | Decl::TsInterface(_) | Decl::TsTypeAlias(_) => false,
+            Decl::Class(class) => self.class_has_side_effects(&class.class),
+            Decl::Var(var) => var.decls.iter().any(|declarator| {
+                // Destructuring runs iterators and getters
+                !matches!(declarator.name, Pat::Ident(_))
+                    || declarator.init.as_ref().is_some_and(|init| !self.is_pure(init))
+            }),
+            _ => true,
+        }
+    }
+
+    /// Defining a class evaluates its superclass, decorators, computed keys,
+    /// static fields and static blocks
+    fn class_has_side_effects(&self, class: &Class) -> bool {
+        if !class.decorators.is_empty() || class.super_class.as_ref().is_some_and(|sup| !self.is_pure(sup)) {
+            return true;
+        }
+        class.body.iter().any(|member| {
+            let computed = |key: &PropName| matches!(key, PropName::Computed(key) if !self.is_pure(&key.expr));
+            match member {
+                ClassMember::Method(method) => computed(&method.key) || !method.function.decorators.is_empty(),
+                ClassMember::ClassProp(prop) => {
+                    computed(&prop.key)
+                        || !prop.decorators.is_empty()
+                        || (prop.is_static && prop.value.as_ref().is_some_and(|value| !self.is_pure(value)))
+                }
+                ClassMember::PrivateProp(prop) => {
+                    !prop.decorators.is_empty()
+                        || (prop.is_static && prop.value.as_ref().is_some_and(|value| !self.is_pure(value)))
+                }
+                ClassMember::StaticBlock(_) => true,
+                _ => false,
+            }
+        })
+    }
+
+    /// Whether evaluating `expr` can have no effect beyond producing a value
+    fn is_pure(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Lit(_) | Expr::Ident(_) | Expr::This(_) | Expr::Arrow(_) | Expr::Fn(_) => true,
+            Expr::Class(class) => !self.class_has_side_effects(&class.class),
+            Expr::Tpl(tpl) => tpl.exprs.iter().all(|expr| self.is_pure(expr)),
+            Expr::Paren(paren) => self.is_pure(&paren.expr),
+            Expr::Seq(seq) => seq.exprs.iter().all(|expr| self.is_pure(expr)),
+            Expr::Unary(unary) => unary.op != UnaryOp::Delete && self.is_pure(&unary.arg),
+            Expr::Bin(bin) => self.is_pure(&bin.left) && self.is_pure(&bin.right),
+            Expr::Cond(cond) => self.is_pure(&cond.test) && self.is_pure(&cond.cons) && self.is_pure(&cond.alt),
+            Expr::Array(array) => array.elems.iter().flatten().all(|elem| elem.spread.is_none() && self.is_pure(&elem.expr)),
+            Expr::Object(object) => object.props.iter().all(|prop| match prop {
+                PropOrSpread::Prop(prop) => match &**prop {
+                    Prop::Shorthand(_) | Prop::Method(_) | Prop::Getter(_) | Prop::Setter(_) => true,
+                    Prop::KeyValue(kv) => {
+                        !matches!(&kv.key, PropName::Computed(key) if !self.is_pure(&key.expr)) && self.is_pure(&kv.value)
+                    }
+                    _ => false,
+                },
+                PropOrSpread::Spread(_) => false,
+            }),
+            Expr::Call(call) => {
+                matches!(call.callee, Callee::Expr(_))
+                    && self.is_pure_annotated(call.span)
+                    && call.args.iter().all(|arg| arg.spread.is_none() && self.is_pure(&arg.expr))
+            }
+            Expr::New(new) => {
+                self.is_pure_annotated(new.span)
+                    && new.args.iter().flatten().all(|arg| arg.spread.is_none() && self.is_pure(&arg.expr))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a `/*#__PURE__*/` comment directly precedes `span`
+    fn is_pure_annotated(&self, span: Span) -> bool {
+        let start = Span::new(BytePos(1), span.lo);
+        let before = diagnostics::span_text(self.source, start).trim_end();
+        PURE_ANNOTATIONS.iter().any(|annotation| before.ends_with(annotation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn side_effects(source: &str) -> bool {
+        has_side_effects(&parser::parse(source, "module.js").unwrap(), source)
+    }
+
+    #[test]
+    fn test_has_side_effects() {
+        let pure = [
+            "import { a } from './a'; export { b } from './b'; export * from './c';",
+            "export function Button(props) { return <button>{props.label}</button>; }",
+            "export const SIZES = ['sm', 'md', `${prefix}-lg`]; const limit = 10 * 2;",
+            "export default class Store { static version = 1; load() { fetch('/x'); } }",
+            "'use strict'; export const handlers = { click() { track(); }, id: -1 };",
+            "export const theme = /*#__PURE__*/ createTheme({ dark: true });",
+            "const cache = /*@__PURE__*/ new Map();",
+            "",
+        ];
+        for source in pure {
+            assert!(!side_effects(source), "{}", source);
+        }
+
+        let impure = [
+            "import { App } from './App'; render(App, root);",
+            "window.app = {};",
+            "export const [count, setCount] = createSignal(0);",
+            "export const theme = createTheme();",
+            "export const theme = /*#__PURE__*/ createTheme(register());",
+            "export const width = document.body.clientWidth;",
+            "export default class Widget { static { define(this); } }",
+            "class Panel extends mixin(Base) {}",
+            "if (import.meta.hot) { accept(); }",
+            "export default run();",
+        ];
+        for source in impure {
+            assert!(side_effects(source), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Asset References
+//!
+//! Files a module pulls in that aren't JavaScript modules, so a build can
+//! copy, hash or check them:
+//! - `import logo from "./logo.png"` / `import "./reset.css"`
+//! - `new URL("./worker-data.json", import.meta.url)`
+//!
+//! Only relative specifiers with a non-script extension count. CSS modules
+//! (`./Button.module.css`) compile to JavaScript modules, so they're imports
+//! like any other rather than assets.
+
+use crate::css::CSS_MODULE_SUFFIX;
+use crate::diagnostics;
+use swc_core::common::Span;
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::{Visit, VisitWith};
+
+/// Extensions of files that are loaded as modules, not assets
+const SCRIPT_EXTENSIONS: &[&str] = &["js", "jsx", "ts", "tsx", "mjs", "cjs", "mts", "cts"];
+
+/// A file referenced by a module that isn't a JavaScript module
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetRef {
+    /// The specifier as written, e.g. `./logo.png`
+    pub specifier: String,
+    /// The specifier's string literal in the source
+    pub span: Span,
+}
+
+impl AssetRef {
+    /// The referenced file's path, without any `?query` or `#hash`
+    pub fn path(&self) -> &str {
+        strip_query(&self.specifier)
+    }
+
+    /// Resolve the 1-based line and column of the reference in `source`
+    pub fn location(&self, source: &str) -> (usize, usize) {
+        diagnostics::line_column(source, self.span)
+    }
+}
+
+/// Collect the asset references in `module`, in source order
+pub fn find_assets(module: &Module) -> Vec<AssetRef> {
+    let mut finder = AssetFinder::default();
+    module.visit_with(&mut finder);
+    finder.assets
+}
+
+/// Whether `specifier` names a file relative to the importer that isn't a
+/// script, ignoring any `?query` or `#hash`
+pub fn is_asset_specifier(specifier: &str) -> bool {
+    if !(specifier.starts_with("./") || specifier.starts_with("../")) {
+        return false;
+    }
+    let path = strip_query(specifier);
+    if path.ends_with(CSS_MODULE_SUFFIX) {
+        return false;
+    }
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    match file_name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => !SCRIPT_EXTENSIONS.contains(&ext),
+        _ => false,
+    }
+}
+
+fn strip_query(specifier: &str) -> &str {
+    specifier.split(['?', '#']).next().unwrap_or(specifier)
+}
+
+/// Visitor behind [`find_assets`]
+#[derive(Default)]
+struct AssetFinder {
+    assets: Vec<AssetRef>,
+}
+
+impl AssetFinder {
+    fn record(&mut self, src: &Str) {
+        if is_asset_specifier(&src.value) {
+            self.assets.push(AssetRef {
+                specifier: src.value.to_string(),
+                span: src.span,
+            });
+        }
+    }
+}
+
+impl Visit for AssetFinder {
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        self.record(&import.src);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let (Callee::Import(_), Some(arg)) = (&call.callee, call.args.first()) {
+            if let Expr::Lit(Lit::Str(src)) = &*arg.expr {
+                self.record(src);
+            }
+        }
+        call.visit_children_with(self);
+    }
+
+    /// `new URL("./file", import.meta.url)`
+    fn visit_new_expr(&mut self, new: &NewExpr) {
+        let is_url = matches!(&*new.callee, Expr::Ident(ident) if ident.sym == "URL");
+        if let (true, Some([path, base])) = (is_url, new.args.as_deref()) {
+            let base_is_module = matches!(
+                &*base.expr,
+                Expr::Member(MemberExpr { obj, prop: MemberProp::Ident(prop), .. })
+                    if prop.sym == "url" && matches!(&**obj, Expr::MetaProp(_))
+            );
+            if let (true, Expr::Lit(Lit::Str(src))) = (base_is_module, &*path.expr) {
+                self.record(src);
+            }
+        }
+        new.visit_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_find_assets() {
+        let source = r#"
+            import logo from "./logo.svg";
+            import "../styles/reset.css";
+            import styles from "./Button.module.css";
+            import { helper } from "./helper.ts";
+            import Icon from "./Icon";
+            import "velocity-runtime/style.css";
+            const data = new URL("./data/levels.json?raw", import.meta.url);
+            const remote = new URL("./not-an-asset.png", location.href);
+            const font = () => import("./fonts/inter.woff2");
+        "#;
+
+        let module = parser::parse(source, "src/App.tsx").unwrap();
+        let specifiers: Vec<_> = find_assets(&module).into_iter().map(|asset| asset.specifier).collect();
+
+        assert_eq!(
+            specifiers,
+            ["./logo.svg", "../styles/reset.css", "./data/levels.json?raw", "./fonts/inter.woff2"]
+        );
+        assert_eq!(find_assets(&module)[2].path(), "./data/levels.json");
+        assert_eq!(find_assets(&module)[0].location(source), (2, 30));
+        assert!(!is_asset_specifier("./.env"));
+        assert!(!is_asset_specifier("./v1.2/index"));
+    }
+}
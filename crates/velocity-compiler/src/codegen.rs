@@ -1,20 +1,94 @@
 //! Code Generation
 //!
 //! Generates JavaScript code from the optimized AST with optional source maps.
+//! With [`ModuleFormat::Cjs`] the module's imports and exports are rewritten
+//! to `require()` and `exports` first, and with [`MinifyLevel::Full`] SWC's
+//! compressor and mangler run before emitting.
+//!
+//! Readable output follows [`CodeStyle`]: indentation, ASCII-only string
+//! escaping, and whether statements end in semicolons. Without semicolons, a
+//! statement is still terminated where automatic semicolon insertion would
+//! join it with the next line, which then starts with `;` (`;[a, b] = [b, a]`).
 
+use crate::analyzer;
+use crate::diagnostics::Diagnostic;
+use crate::downlevel;
 use crate::error::{CompilerError, Result};
-use crate::CompilerOptions;
-use swc_core::common::{sync::Lrc, SourceMap, FileName};
-use swc_core::ecma::ast::Module;
-use swc_core::ecma::codegen::{text_writer::JsWriter, Emitter, Config};
+use crate::{CompilerOptions, MinifyLevel, ModuleFormat};
+use swc_core::common::comments::NoopComments;
+use swc_core::common::util::take::Take;
+use swc_core::common::{sync::Lrc, FileName, Globals, Mark, SourceMap, GLOBALS};
+use swc_core::ecma::ast::{EsVersion, Module, Program};
+use swc_core::common::{BytePos, Span};
+use swc_core::ecma::codegen::text_writer::{JsWriter, WriteJs};
+use swc_core::ecma::codegen::{Emitter, Config};
+use swc_core::ecma::minifier::option::terser::{TerserCompressorOptions, TerserEcmaVersion};
+use swc_core::ecma::minifier::option::{ExtraOptions, MangleOptions, MinifyOptions};
+use swc_core::ecma::transforms::base::feature::enable_available_feature_from_es_version;
+use swc_core::ecma::transforms::base::fixer::fixer;
+use swc_core::ecma::transforms::base::helpers::{inject_helpers, Helpers, HELPERS};
+use swc_core::ecma::transforms::base::hygiene::hygiene;
+use swc_core::ecma::transforms::base::resolver;
+use swc_core::ecma::transforms::module::{common_js, util::Config as ModuleConfig};
+use swc_core::ecma::visit::VisitMutWith;
 
 /// Result of code generation including optional source map
 pub struct GenerateResult {
     pub code: String,
     pub source_map: Option<String>,
+    /// Warnings collected while compiling (filled in by the compiler)
+    pub diagnostics: Vec<Diagnostic>,
+    /// Scoped CSS extracted from `css` templates (filled in by the compiler)
+    pub css: Option<String>,
+}
+
+/// How non-minified output is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeStyle {
+    /// Indentation of nested blocks
+    pub indent: Indent,
+    /// Escape non-ASCII characters in strings and identifiers (`\u00e9`),
+    /// for servers that don't declare a charset
+    pub ascii_only: bool,
+    /// End every statement with `;`. When off, only the semicolons automatic
+    /// semicolon insertion needs are kept.
+    pub semicolons: bool,
+}
+
+impl Default for CodeStyle {
+    fn default() -> Self {
+        Self {
+            indent: Indent::Spaces(4),
+            ascii_only: false,
+            semicolons: true,
+        }
+    }
+}
+
+/// One level of indentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    /// 1 to 8 spaces
+    Spaces(u8),
+    Tab,
 }
 
-/// Generate JavaScript code from an AST module
+impl Indent {
+    fn as_str(self) -> Result<&'static str> {
+        const SPACES: &str = "        ";
+        match self {
+            Indent::Tab => Ok("\t"),
+            Indent::Spaces(width @ 1..=8) => Ok(&SPACES[..width as usize]),
+            Indent::Spaces(width) => Err(CompilerError::InvalidConfig(format!(
+                "Indentation of {} spaces is not supported (expected 1 to 8)",
+                width
+            ))),
+        }
+    }
+}
+
+/// Generate JavaScript code from an AST module. Any module can be emitted,
+/// but JSX and TypeScript syntax are printed as-is.
 pub fn generate(module: &Module, options: &CompilerOptions) -> Result<String> {
     let result = generate_with_source_map(module, options, None)?;
     Ok(result.code)
@@ -26,6 +100,14 @@ pub fn generate_with_source_map(
     options: &CompilerOptions,
     source_file_name: Option<&str>,
 ) -> Result<GenerateResult> {
+    let prepared;
+    let module = if options.module_format == ModuleFormat::Esm && options.minify != MinifyLevel::Full {
+        module
+    } else {
+        prepared = prepare_output(module.clone(), options)?;
+        &prepared
+    };
+
     let cm: Lrc<SourceMap> = Default::default();
 
     // Add source file if provided (for source map generation)
@@ -41,10 +123,23 @@ pub fn generate_with_source_map(
 
     // For source maps, we need to use a different approach
     // JsWriter with source map writer creates line/column mappings, not the actual map
-    let writer = JsWriter::new(cm.clone(), "\n", &mut buf, None);
+    let mut writer = JsWriter::new(cm.clone(), "\n", &mut buf, None);
+    let style = &options.code_style;
+    writer.set_indent_str(style.indent.as_str()?);
+    let minify = options.minify != MinifyLevel::None;
+    // The minifier's writer already drops every semicolon it can
+    let omit_semicolons = !style.semicolons && !minify;
+    let writer: Box<dyn WriteJs + '_> = if omit_semicolons {
+        Box::new(AsiWriter::new(writer))
+    } else {
+        Box::new(writer)
+    };
 
     let mut emitter = Emitter {
-        cfg: Config::default().with_minify(options.minify),
+        cfg: Config::default()
+            .with_minify(minify)
+            .with_ascii_only(style.ascii_only)
+            .with_omit_last_semi(omit_semicolons),
         cm: cm.clone(),
         comments: None,
         wr: writer,
@@ -53,6 +148,7 @@ pub fn generate_with_source_map(
     emitter
         .emit_module(module)
         .map_err(|e| CompilerError::CodegenError(format!("Failed to emit code: {:?}", e)))?;
+    drop(emitter);
 
     let code = String::from_utf8(buf)
         .map_err(|e| CompilerError::CodegenError(format!("Invalid UTF-8: {}", e)))?;
@@ -72,7 +168,245 @@ pub fn generate_with_source_map(
         None
     };
 
-    Ok(GenerateResult { code, source_map })
+    Ok(GenerateResult {
+        code,
+        source_map,
+        diagnostics: Vec::new(),
+        css: None,
+    })
+}
+
+/// Punctuation that continues the previous line's expression when a line
+/// starts with it, so the statement before needs its semicolon
+const ASI_HAZARDS: &[&str] = &["(", "[", "`", "+", "-", "/", "*"];
+
+/// Property names that would change meaning if the class member after them
+/// continued on their line (`get` + newline + `foo() {}` is a getter)
+const CONTEXTUAL_KEYWORDS: &[&str] = &["get", "set", "static", "async", "accessor"];
+
+/// Writer that holds back statement semicolons and writes one only where
+/// automatic semicolon insertion wouldn't end the statement: before a
+/// hazardous token on the next line, or before anything on the same line
+/// except `}`
+struct AsiWriter<W: WriteJs> {
+    inner: W,
+    /// A statement ended and its semicolon hasn't been written
+    pending: bool,
+    /// A line break was written since the pending semicolon
+    line_break: bool,
+    /// The last token was a name listed in [`CONTEXTUAL_KEYWORDS`]
+    contextual: bool,
+}
+
+impl<W: WriteJs> AsiWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            pending: false,
+            line_break: false,
+            contextual: false,
+        }
+    }
+
+    /// Decide on a pending semicolon before writing a token
+    fn settle(&mut self, hazard: bool) -> swc_core::ecma::codegen::Result {
+        if std::mem::take(&mut self.pending) && (hazard || !self.line_break) {
+            self.inner.write_semi(None)?;
+        }
+        Ok(())
+    }
+
+    /// Settle before a word token, remembering whether it's contextual
+    fn word(&mut self, s: &str) -> swc_core::ecma::codegen::Result {
+        self.settle(false)?;
+        self.contextual = CONTEXTUAL_KEYWORDS.contains(&s);
+        Ok(())
+    }
+}
+
+impl<W: WriteJs> WriteJs for AsiWriter<W> {
+    fn increase_indent(&mut self) -> swc_core::ecma::codegen::Result {
+        self.inner.increase_indent()
+    }
+
+    fn decrease_indent(&mut self) -> swc_core::ecma::codegen::Result {
+        self.inner.decrease_indent()
+    }
+
+    fn write_semi(&mut self, span: Option<Span>) -> swc_core::ecma::codegen::Result {
+        if self.contextual {
+            return self.inner.write_semi(span);
+        }
+        self.pending = true;
+        self.line_break = false;
+        Ok(())
+    }
+
+    fn write_space(&mut self) -> swc_core::ecma::codegen::Result {
+        self.inner.write_space()
+    }
+
+    fn write_keyword(&mut self, span: Option<Span>, s: &'static str) -> swc_core::ecma::codegen::Result {
+        self.word(s)?;
+        self.inner.write_keyword(span, s)
+    }
+
+    fn write_operator(&mut self, span: Option<Span>, s: &str) -> swc_core::ecma::codegen::Result {
+        self.settle(s.starts_with(['+', '-', '/', '*']))?;
+        self.contextual = false;
+        self.inner.write_operator(span, s)
+    }
+
+    fn write_param(&mut self, s: &str) -> swc_core::ecma::codegen::Result {
+        self.word(s)?;
+        self.inner.write_param(s)
+    }
+
+    fn write_property(&mut self, s: &str) -> swc_core::ecma::codegen::Result {
+        self.word(s)?;
+        self.inner.write_property(s)
+    }
+
+    fn write_line(&mut self) -> swc_core::ecma::codegen::Result {
+        self.line_break |= self.pending;
+        self.inner.write_line()
+    }
+
+    fn write_lit(&mut self, span: Span, s: &str) -> swc_core::ecma::codegen::Result {
+        self.word(s)?;
+        self.inner.write_lit(span, s)
+    }
+
+    fn write_comment(&mut self, s: &str) -> swc_core::ecma::codegen::Result {
+        self.inner.write_comment(s)
+    }
+
+    fn write_str_lit(&mut self, span: Span, s: &str) -> swc_core::ecma::codegen::Result {
+        self.word(s)?;
+        self.inner.write_str_lit(span, s)
+    }
+
+    fn write_str(&mut self, s: &str) -> swc_core::ecma::codegen::Result {
+        self.word(s)?;
+        self.inner.write_str(s)
+    }
+
+    fn write_symbol(&mut self, span: Span, s: &str) -> swc_core::ecma::codegen::Result {
+        self.word(s)?;
+        self.inner.write_symbol(span, s)
+    }
+
+    fn write_punct(&mut self, span: Option<Span>, s: &'static str) -> swc_core::ecma::codegen::Result {
+        if s == "}" {
+            self.pending = false;
+        } else {
+            self.settle(ASI_HAZARDS.contains(&s))?;
+        }
+        self.contextual = false;
+        self.inner.write_punct(span, s)
+    }
+
+    fn care_about_srcmap(&self) -> bool {
+        self.inner.care_about_srcmap()
+    }
+
+    fn add_srcmap(&mut self, pos: BytePos) -> swc_core::ecma::codegen::Result {
+        self.inner.add_srcmap(pos)
+    }
+
+    // The emitter asks for pending semicolons before many tokens; whether
+    // one is needed is decided by the token itself
+    fn commit_pending_semi(&mut self) -> swc_core::ecma::codegen::Result {
+        Ok(())
+    }
+}
+
+/// Apply the whole-module rewrites `options` ask for: CommonJS conversion,
+/// then compression and mangling
+fn prepare_output(mut module: Module, options: &CompilerOptions) -> Result<Module> {
+    let year = downlevel::target_year(&options.target)?;
+    let commonjs = options.module_format == ModuleFormat::Cjs;
+    let full = options.minify == MinifyLevel::Full;
+
+    GLOBALS.set(&Globals::new(), || {
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+
+        // Both rewrites tell bindings apart by the syntax context the
+        // resolver assigns, e.g. an import from a local that shadows it.
+        // Contexts left by the analysis belong to another table and would
+        // stop the resolver from assigning its own.
+        analyzer::clear_bindings(&mut module);
+        module.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+        if commonjs {
+            to_commonjs(&mut module, unresolved_mark, year);
+        }
+        if full {
+            compress_and_mangle(&mut module, unresolved_mark, top_level_mark, year, !commonjs);
+        } else {
+            module.visit_mut_with(&mut hygiene());
+        }
+        module.visit_mut_with(&mut fixer(None));
+    });
+
+    Ok(module)
+}
+
+/// Rewrite `import` / `export` into `require()` / `exports`, with SWC's
+/// interop helpers inlined so the output needs no runtime package
+fn to_commonjs(module: &mut Module, unresolved_mark: Mark, year: u32) {
+    // Arrow functions and `const` in the generated code, unless the target predates them
+    let version = if year < 2015 { EsVersion::Es5 } else { EsVersion::Es2015 };
+
+    HELPERS.set(&Helpers::new(false), || {
+        module.visit_mut_with(&mut common_js(
+            unresolved_mark,
+            ModuleConfig::default(),
+            enable_available_feature_from_es_version(version),
+            None::<NoopComments>,
+        ));
+        module.visit_mut_with(&mut inject_helpers(unresolved_mark));
+    });
+}
+
+/// Run SWC's compressor and mangler with terser's defaults. `es_module`
+/// lets them drop and rename top-level bindings that aren't exported.
+fn compress_and_mangle(module: &mut Module, unresolved_mark: Mark, top_level_mark: Mark, year: u32, es_module: bool) {
+    let cm: Lrc<SourceMap> = Default::default();
+    let compress = TerserCompressorOptions {
+        // The compressor mustn't introduce syntax the target lacks
+        ecma: TerserEcmaVersion::Num(match year {
+            ..=2014 => 5,
+            2015..=2022 => year as usize,
+            _ => 2022,
+        }),
+        module: es_module,
+        ..Default::default()
+    };
+    let options = MinifyOptions {
+        compress: Some(compress.into_config(cm.clone())),
+        mangle: Some(MangleOptions {
+            top_level: Some(es_module),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let extra = ExtraOptions {
+        unresolved_mark,
+        top_level_mark,
+        mangle_name_cache: None,
+    };
+
+    let program = swc_core::ecma::minifier::optimize(
+        Program::Module(module.take()),
+        cm,
+        None,
+        None,
+        &options,
+        &extra,
+    );
+    *module = program.expect_module();
 }
 
 #[cfg(test)]
@@ -127,11 +461,142 @@ mod tests {
 
         let module = parser::parse(source, "test.ts").unwrap();
         let options = CompilerOptions {
-            minify: true,
+            minify: MinifyLevel::Whitespace,
             ..Default::default()
         };
         let result = generate(&module, &options);
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_generate_minify_levels() {
+        let source = r#"
+            import { createSignal } from "velocity-runtime";
+            // Only used here, so it can be inlined and renamed
+            function formatCount(currentValue) {
+                if (false) {
+                    console.log("never");
+                }
+                return "Count: " + currentValue;
+            }
+            export function Counter() {
+                const [count, setCount] = createSignal(0);
+                return formatCount(count());
+            }
+        "#;
+        let module = parser::parse(source, "test.ts").unwrap();
+        let generate_at = |minify| generate(&module, &CompilerOptions { minify, ..Default::default() }).unwrap();
+
+        let readable = generate_at(MinifyLevel::None);
+        let whitespace = generate_at(MinifyLevel::Whitespace);
+        let full = generate_at(MinifyLevel::Full);
+
+        assert!(whitespace.len() < readable.len());
+        assert!(whitespace.contains("function formatCount(currentValue)"));
+        assert!(whitespace.contains("console.log"));
+
+        assert!(full.len() < whitespace.len(), "{}", full);
+        assert!(!full.contains("formatCount") && !full.contains("currentValue"), "{}", full);
+        assert!(!full.contains("never"), "{}", full);
+        // Imports and exports keep their names
+        assert!(full.contains("export function Counter"), "{}", full);
+        assert!(full.contains("createSignal"), "{}", full);
+        parser::parse(&full, "out.js").unwrap();
+    }
+
+    #[test]
+    fn test_generate_code_style() {
+        let source = r#"
+            const greeting = "héllo";
+            let a = 1, b = 2;
+            [a, b] = [b, a];
+            class Panel {
+                get;
+                open = false;
+                *items() { yield greeting; }
+                toggle() { if (this.open) { this.open = false; } else { this.open = true; } }
+            }
+            (() => console.log(a))();
+            export { Panel };
+        "#;
+        let module = parser::parse(source, "test.js").unwrap();
+        let generate_with = |code_style: CodeStyle| {
+            let options = CompilerOptions {
+                code_style,
+                ..Default::default()
+            };
+            generate(&module, &options).unwrap()
+        };
+
+        let default = generate_with(CodeStyle::default());
+        assert!(default.contains("\n    get;\n"));
+        assert!(default.contains("const greeting = \"héllo\";"));
+
+        let styled = generate_with(CodeStyle {
+            indent: Indent::Tab,
+            ascii_only: true,
+            semicolons: false,
+        });
+        assert!(styled.contains("const greeting = \"h\\xe9llo\"\n"), "{}", styled);
+        assert!(styled.contains("let a = 1, b = 2\n;[a, b] = [\n"), "{}", styled);
+        assert!(styled.contains("\tget;\n\topen = false\n\t;*items() {\n\t\tyield greeting\n\t}"), "{}", styled);
+        assert!(styled.contains("\t\t\tthis.open = false\n\t\t} else {"), "{}", styled);
+        // A class declaration can't continue into a call
+        assert!(styled.contains("}\n(()=>console.log(a))()\n"), "{}", styled);
+        assert!(styled.ends_with("export { Panel }\n"), "{}", styled);
+        // Still the same program
+        let reparsed = parser::parse(&styled, "styled.js").unwrap();
+        let escaped = generate_with(CodeStyle {
+            ascii_only: true,
+            ..Default::default()
+        });
+        assert_eq!(generate(&reparsed, &CompilerOptions::default()).unwrap(), escaped);
+
+        let two_spaces = generate_with(CodeStyle {
+            indent: Indent::Spaces(2),
+            ..Default::default()
+        });
+        assert!(two_spaces.contains("\n  open = false;\n"));
+        let options = CompilerOptions {
+            code_style: CodeStyle {
+                indent: Indent::Spaces(12),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(generate(&module, &options).is_err());
+    }
+
+    #[test]
+    fn test_generate_commonjs() {
+        let source = r#"
+            import { createSignal } from "velocity-runtime";
+            import App from "./App";
+            export const [count, setCount] = createSignal(0);
+            export function render() {
+                const createSignal = () => "shadowed";
+                return App(count(), createSignal());
+            }
+            export default App;
+        "#;
+
+        let module = parser::parse(source, "test.ts").unwrap();
+        let options = CompilerOptions {
+            module_format: ModuleFormat::Cjs,
+            ..Default::default()
+        };
+        let code = generate(&module, &options).unwrap();
+
+        assert!(code.contains(r#"require("velocity-runtime")"#));
+        assert!(code.contains(r#"require("./App")"#));
+        assert!(code.contains("exports"));
+        // Imported bindings are read from the required module; locals are left alone
+        assert!(code.contains("_velocityruntime.createSignal)(0)"), "{}", code);
+        assert!(code.contains("createSignal = ()=>\"shadowed\""), "{}", code);
+
+        // Still parses, and nothing module-only is left
+        let output = parser::parse(&code, "out.js").unwrap();
+        assert!(output.body.iter().all(|item| item.is_stmt()));
+    }
 }
@@ -0,0 +1,645 @@
+//! Scoped Styles
+//!
+//! Component-scoped CSS from two sources:
+//! - `css` tagged templates in a component module:
+//!   ```jsx
+//!   const styles = css`.button { color: red; }`;
+//!   <button class="button">  // or class={styles.button}
+//!   ```
+//! - CSS module files (`Button.module.css`), imported as
+//!   `import styles from "./Button.module.css"`
+//!
+//! ## Scoping scheme
+//! Every class selector `.name` becomes `.name_<hash>`, where `<hash>` is six
+//! hex digits of an FNV-1a hash of the scope (the file name passed to the
+//! compiler, which the CLI makes the path relative to the project root, so
+//! names don't depend on the working directory) and the class name. Selectors inside `:global(...)` are left
+//! alone. Only class selectors are scoped; element, id and attribute
+//! selectors pass through unchanged.
+//!
+//! At-rules and nesting:
+//! - `@media`, `@supports`, `@container` and `@layer` blocks are scoped like
+//!   the top level, including when nested inside a rule
+//! - Rules nested in a rule (`.card { .title { } &:hover { } }`) are scoped
+//!   too; `&` passes through
+//! - `@keyframes name` is scoped with the same scheme as classes, and
+//!   `animation` / `animation-name` declarations in the same stylesheet are
+//!   rewritten to match, wherever the keyframes appear. Names declared as
+//!   `@keyframes :global(name)`, or not declared in the stylesheet, stay global.
+//! - Other at-rules (`@font-face`, `@page`, `@import`, ...) are copied verbatim
+//!
+//! ## Extraction
+//! A `css` template is replaced by an object mapping each class name to its
+//! scoped name, and static `class`/`className` attributes in the same module
+//! are rewritten to the scoped names. The scoped CSS itself is returned from
+//! compilation ([`GenerateResult::css`](crate::GenerateResult::css)) for the
+//! build to collect. A CSS module compiles to a JS module exporting the same
+//! kind of object ([`ScopedCss::to_module`]), and imports of it are pointed at
+//! that module (`./Button.module.css.js`).
+
+use crate::error::{CompilerError, Result};
+use crate::transformer::module_bindings;
+use std::collections::BTreeMap;
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::{VisitMut, VisitMutWith};
+
+/// Name of the tagged template that declares scoped styles
+const CSS_TAG: &str = "css";
+
+/// Suffix of CSS module files
+pub const CSS_MODULE_SUFFIX: &str = ".module.css";
+
+/// CSS with its class selectors scoped
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScopedCss {
+    /// The rewritten stylesheet
+    pub css: String,
+    /// Original class name → scoped class name
+    pub classes: BTreeMap<String, String>,
+}
+
+impl ScopedCss {
+    /// JS module exporting the class name mapping, for a compiled CSS module
+    pub fn to_module(&self) -> String {
+        let entries: Vec<String> = self
+            .classes
+            .iter()
+            .map(|(class, scoped)| format!("  {:?}: {:?}", class, scoped))
+            .collect();
+        format!("export default {{\n{}\n}};\n", entries.join(",\n"))
+    }
+
+    fn extend(&mut self, other: ScopedCss) {
+        if !self.css.is_empty() && !other.css.is_empty() {
+            self.css.push('\n');
+        }
+        self.css.push_str(&other.css);
+        self.classes.extend(other.classes);
+    }
+}
+
+/// Scoped name for `class` in `scope`, e.g. `button` → `button_3fa2c1`
+pub fn scoped_class_name(class: &str, scope: &str) -> String {
+    // FNV-1a, stable across platforms and releases
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in scope.bytes().chain([0]).chain(class.bytes()) {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    format!("{}_{:06x}", class, hash & 0xff_ffff)
+}
+
+/// Scope every class selector in `css`
+pub fn scope_css(css: &str, scope: &str) -> ScopedCss {
+    // Keyframes can be used before they're declared, so the first pass only
+    // collects their names
+    let mut collector = Scoper::new(css, scope, BTreeMap::new());
+    collector.rules(false);
+
+    let mut scoper = Scoper::new(css, scope, collector.keyframes);
+    scoper.rules(false);
+
+    ScopedCss {
+        css: scoper.output,
+        classes: scoper.classes,
+    }
+}
+
+/// Scope the `css` templates of a module and rewrite class names and CSS
+/// module imports to match. Returns the extracted CSS, if the module had any.
+pub fn scope_styles(module: &mut Module, filename: &str) -> Result<Option<ScopedCss>> {
+    let mut rewriter = StyleRewriter {
+        // A local `css` binding (e.g. another styling library) isn't ours
+        enabled: !module_bindings(module).contains(CSS_TAG),
+        scope: filename,
+        styles: ScopedCss::default(),
+        found: false,
+        error: None,
+    };
+    module.visit_mut_with(&mut rewriter);
+
+    if let Some(error) = rewriter.error {
+        return Err(error);
+    }
+
+    for item in &mut module.body {
+        if let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item {
+            if import.src.value.ends_with(CSS_MODULE_SUFFIX) {
+                let src = format!("{}.js", import.src.value);
+                *import.src = Str::from(src);
+            }
+        }
+    }
+
+    if !rewriter.found {
+        return Ok(None);
+    }
+
+    // Static class names can only be rewritten once every template is known
+    let classes = rewriter.styles.classes.clone();
+    module.visit_mut_with(&mut ClassAttrRewriter { classes: &classes });
+
+    Ok(Some(rewriter.styles))
+}
+
+/// Replaces `css` templates with their class name mapping
+struct StyleRewriter<'a> {
+    enabled: bool,
+    scope: &'a str,
+    styles: ScopedCss,
+    found: bool,
+    error: Option<CompilerError>,
+}
+
+impl VisitMut for StyleRewriter<'_> {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        expr.visit_mut_children_with(self);
+
+        let Expr::TaggedTpl(tagged) = expr else {
+            return;
+        };
+        if !self.enabled || !matches!(&*tagged.tag, Expr::Ident(tag) if tag.sym == CSS_TAG) {
+            return;
+        }
+        if !tagged.tpl.exprs.is_empty() {
+            self.error = Some(CompilerError::TransformError(
+                "css`` templates can't contain ${} expressions; use inline styles for dynamic values"
+                    .to_string(),
+            ));
+            return;
+        }
+
+        let quasi = &tagged.tpl.quasis[0];
+        let source = quasi.cooked.as_ref().unwrap_or(&quasi.raw);
+        let scoped = scope_css(source, self.scope);
+
+        *expr = class_map_expr(&scoped.classes);
+        self.styles.extend(scoped);
+        self.found = true;
+    }
+}
+
+/// Rewrites static `class`/`className` strings to scoped names
+struct ClassAttrRewriter<'a> {
+    classes: &'a BTreeMap<String, String>,
+}
+
+impl VisitMut for ClassAttrRewriter<'_> {
+    fn visit_mut_jsx_attr(&mut self, attr: &mut JSXAttr) {
+        let JSXAttrName::Ident(name) = &attr.name else {
+            return;
+        };
+        if name.sym != "class" && name.sym != "className" {
+            return;
+        }
+        if let Some(JSXAttrValue::Lit(Lit::Str(value))) = &mut attr.value {
+            let rewritten: Vec<&str> = value
+                .value
+                .split_whitespace()
+                .map(|class| self.classes.get(class).map(String::as_str).unwrap_or(class))
+                .collect();
+            *value = Str::from(rewritten.join(" "));
+        }
+    }
+}
+
+/// `{ "button": "button_3fa2c1", ... }`
+fn class_map_expr(classes: &BTreeMap<String, String>) -> Expr {
+    let props = classes
+        .iter()
+        .map(|(class, scoped)| {
+            PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                key: PropName::Str(Str::from(class.as_str())),
+                value: Box::new(Expr::Lit(Lit::Str(Str::from(scoped.as_str())))),
+            })))
+        })
+        .collect();
+
+    Expr::Object(ObjectLit {
+        props,
+        ..Default::default()
+    })
+}
+
+/// Single-pass CSS rewriter; understands just enough syntax to tell
+/// selectors from declarations, comments and strings
+struct Scoper<'a> {
+    source: &'a str,
+    pos: usize,
+    scope: &'a str,
+    output: String,
+    classes: BTreeMap<String, String>,
+    /// Local keyframes name → scoped name
+    keyframes: BTreeMap<String, String>,
+}
+
+impl<'a> Scoper<'a> {
+    fn new(source: &'a str, scope: &'a str, keyframes: BTreeMap<String, String>) -> Self {
+        Self {
+            source,
+            pos: 0,
+            scope,
+            output: String::with_capacity(source.len()),
+            classes: BTreeMap::new(),
+            keyframes,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn rest(&self) -> &str {
+        &self.source[self.pos..]
+    }
+
+    /// Copy one char to the output
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.output.push(c);
+            self.pos += c.len_utf8();
+        }
+    }
+
+    /// Copy a comment or string starting at the current position, if any
+    fn skip_opaque(&mut self) -> bool {
+        if self.rest().starts_with("/*") {
+            let end = self.rest()[2..].find("*/").map(|i| i + 4).unwrap_or(self.rest().len());
+            self.copy(end);
+            return true;
+        }
+        if let Some(quote @ ('"' | '\'')) = self.peek() {
+            let mut end = 1;
+            let mut escaped = false;
+            for c in self.rest()[1..].chars() {
+                end += c.len_utf8();
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    _ if c == quote => break,
+                    _ => {}
+                }
+            }
+            self.copy(end);
+            return true;
+        }
+        false
+    }
+
+    fn copy(&mut self, len: usize) {
+        self.output.push_str(&self.source[self.pos..self.pos + len]);
+        self.pos += len;
+    }
+
+    /// A list of rules, until the closing `}` of the enclosing block
+    fn rules(&mut self, nested: bool) {
+        while let Some(c) = self.peek() {
+            if self.skip_opaque() {
+                continue;
+            }
+            match c {
+                '}' if nested => {
+                    self.bump();
+                    return;
+                }
+                '@' => self.at_rule(false),
+                c if c.is_whitespace() || c == '}' => self.bump(),
+                _ => self.style_rule(),
+            }
+        }
+    }
+
+    /// `@media (...) { rules }`, `@keyframes x { ... }`, `@import ...;`.
+    /// Inside a style rule, conditional blocks hold declarations too.
+    fn at_rule(&mut self, in_style_rule: bool) {
+        let name_len = self.rest()[1..]
+            .find(|c: char| !is_ident_char(c))
+            .map(|i| i + 1)
+            .unwrap_or(self.rest().len());
+        let name = self.rest()[1..name_len].to_ascii_lowercase();
+        let nests_rules = matches!(name.as_str(), "media" | "supports" | "container" | "layer" | "document");
+
+        self.copy(name_len);
+        // `@keyframes` and vendor-prefixed `@-webkit-keyframes`
+        if name.ends_with("keyframes") {
+            self.keyframes_name();
+        }
+
+        while let Some(c) = self.peek() {
+            if self.skip_opaque() {
+                continue;
+            }
+            match c {
+                ';' => {
+                    self.bump();
+                    return;
+                }
+                '{' => {
+                    self.bump();
+                    match (nests_rules, in_style_rule) {
+                        (true, true) => self.declarations(),
+                        (true, false) => self.rules(true),
+                        _ => self.block(),
+                    }
+                    return;
+                }
+                _ => self.bump(),
+            }
+        }
+    }
+
+    /// The name after `@keyframes`: scoped, or unwrapped from `:global(...)`
+    fn keyframes_name(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+        let global = self.rest().starts_with(":global(");
+        if global {
+            self.pos += ":global(".len();
+        }
+
+        let len = self.rest().find(|c: char| !is_ident_char(c)).unwrap_or(self.rest().len());
+        let name = &self.source[self.pos..self.pos + len];
+        if global || name.is_empty() {
+            self.copy(len);
+        } else {
+            let scoped = scoped_class_name(name, self.scope);
+            self.output.push_str(&scoped);
+            self.keyframes.insert(name.to_string(), scoped);
+            self.pos += len;
+        }
+
+        if global && self.peek() == Some(')') {
+            self.pos += 1;
+        }
+    }
+
+    /// `selector { declarations }`
+    fn style_rule(&mut self) {
+        self.selector();
+        if self.peek() == Some('{') {
+            self.bump();
+            self.declarations();
+        }
+    }
+
+    /// A rule body through its closing brace: declarations, plus nested
+    /// rules and at-rules
+    fn declarations(&mut self) {
+        while let Some(c) = self.peek() {
+            if self.skip_opaque() {
+                continue;
+            }
+            match c {
+                '}' => {
+                    self.bump();
+                    return;
+                }
+                '@' => self.at_rule(true),
+                c if c.is_whitespace() || c == ';' => self.bump(),
+                _ if self.at_nested_rule() => self.style_rule(),
+                _ => self.declaration(),
+            }
+        }
+    }
+
+    /// Whether the text ahead is a selector, which runs to a `{`, rather
+    /// than a declaration, which runs to a `;` or the end of the block
+    fn at_nested_rule(&self) -> bool {
+        let mut quote = None;
+        let mut depth = 0;
+        for c in self.rest().chars() {
+            match (quote, c) {
+                (Some(q), _) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '(') => depth += 1,
+                (None, ')') => depth -= 1,
+                (None, '{') if depth == 0 => return true,
+                (None, ';' | '}') if depth == 0 => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// `property: value`, up to its `;` or the end of the block. References
+    /// to local keyframes in animation properties are rewritten.
+    fn declaration(&mut self) {
+        let len = self.rest().find(|c: char| !is_ident_char(c)).unwrap_or(self.rest().len());
+        let property = self.rest()[..len].to_ascii_lowercase();
+        let animation = property.ends_with("animation") || property.ends_with("animation-name");
+        self.copy(len.max(1));
+
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            if self.skip_opaque() {
+                continue;
+            }
+            match c {
+                ';' | '}' if depth == 0 => return,
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                c if animation && is_ident_char(c) => {
+                    let len = self.rest().find(|c: char| !is_ident_char(c)).unwrap_or(self.rest().len());
+                    match self.keyframes.get(&self.rest()[..len]) {
+                        Some(scoped) => {
+                            self.output.push_str(scoped);
+                            self.pos += len;
+                        }
+                        None => self.copy(len),
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            self.bump();
+        }
+    }
+
+    /// Copy a selector up to its `{`, scoping class names
+    fn selector(&mut self) {
+        while let Some(c) = self.peek() {
+            if self.skip_opaque() {
+                continue;
+            }
+            match c {
+                '{' | '}' => return,
+                '.' => {
+                    self.bump();
+                    let len = self
+                        .rest()
+                        .find(|c: char| !is_ident_char(c))
+                        .unwrap_or(self.rest().len());
+                    let class = &self.source[self.pos..self.pos + len];
+                    if class.starts_with(|c: char| c.is_ascii_digit()) || class.is_empty() {
+                        continue;
+                    }
+                    let scoped = scoped_class_name(class, self.scope);
+                    self.output.push_str(&scoped);
+                    self.classes.insert(class.to_string(), scoped);
+                    self.pos += len;
+                }
+                ':' if self.rest().starts_with(":global(") => {
+                    // Unwrap `:global(.x)` to a plain, unscoped `.x`
+                    self.pos += ":global(".len();
+                    let mut depth = 1;
+                    while let Some(c) = self.peek() {
+                        match c {
+                            '(' => depth += 1,
+                            ')' => depth -= 1,
+                            _ => {}
+                        }
+                        if depth == 0 {
+                            self.pos += 1;
+                            break;
+                        }
+                        self.bump();
+                    }
+                }
+                _ => self.bump(),
+            }
+        }
+    }
+
+    /// Copy a `{ ... }` block body verbatim, through its closing brace
+    /// (keyframes, `@font-face`)
+    fn block(&mut self) {
+        let mut depth = 1;
+        while let Some(c) = self.peek() {
+            if self.skip_opaque() {
+                continue;
+            }
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            self.bump();
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Compiler, CompilerOptions};
+
+    #[test]
+    fn test_scope_css() {
+        let css = r#"
+            /* .comment { } */
+            .button, .button:hover > .icon { content: ".not-a-class"; width: 1.5em; }
+            @media (max-width: 600px) { .button { color: red; } }
+            @keyframes spin { from { opacity: 0.5; } }
+            :global(.app) .button { margin: 0; }
+        "#;
+
+        let scoped = scope_css(css, "Button.module.css");
+        let button = scoped_class_name("button", "Button.module.css");
+        let icon = scoped_class_name("icon", "Button.module.css");
+
+        assert_eq!(scoped.classes.keys().collect::<Vec<_>>(), vec!["button", "icon"]);
+        assert!(scoped.css.contains(&format!(".{}, .{}:hover > .{} {{", button, button, icon)));
+        assert!(scoped.css.contains(&format!("@media (max-width: 600px) {{ .{} {{", button)));
+        assert!(scoped.css.contains(&format!(".app .{} {{", button)));
+        assert!(scoped.css.contains("/* .comment { } */"));
+        assert!(scoped.css.contains(r#"content: ".not-a-class"; width: 1.5em;"#));
+        assert!(scoped.css.contains("from { opacity: 0.5; }"));
+    }
+
+    #[test]
+    fn test_scope_css_at_rules_and_nesting() {
+        let css = r#"
+            .spinner { animation: spin 1s linear infinite, fade 2s; }
+            .card {
+                color: red;
+                background: url("a;b.png");
+                .title { font-weight: bold; }
+                &:hover .icon { opacity: 1 }
+                @media (min-width: 800px) { padding: 2rem; .title { font-size: 2rem; } }
+            }
+            @supports (display: grid) { .grid { display: grid; } }
+            @keyframes spin { from { transform: rotate(0deg); } to { transform: rotate(360deg); } }
+            @-webkit-keyframes :global(fade) { from { opacity: 0; } }
+            @font-face { font-family: "Title"; src: url(title.woff2); }
+            .pulse { animation-name: spin; }
+        "#;
+
+        let scope = "Card.tsx";
+        let scoped = scope_css(css, scope);
+        let class = |name| scoped_class_name(name, scope);
+        let spin = scoped_class_name("spin", scope);
+
+        assert_eq!(
+            scoped.classes.keys().collect::<Vec<_>>(),
+            vec!["card", "grid", "icon", "pulse", "spinner", "title"]
+        );
+        // Keyframes are scoped, and referenced by their scoped name even before being declared
+        assert!(scoped.css.contains(&format!("@keyframes {} {{ from {{", spin)), "{}", scoped.css);
+        assert!(scoped.css.contains(&format!("animation: {} 1s linear infinite, fade 2s;", spin)));
+        assert!(scoped.css.contains(&format!("animation-name: {};", spin)));
+        assert!(scoped.css.contains("@-webkit-keyframes fade { from { opacity: 0; } }"));
+        // Nested rules and conditional blocks inside a rule
+        assert!(scoped.css.contains(r#"color: red;
+                background: url("a;b.png");"#));
+        assert!(scoped.css.contains(&format!(".{} {{ font-weight: bold; }}", class("title"))));
+        assert!(scoped.css.contains(&format!("&:hover .{} {{ opacity: 1 }}", class("icon"))));
+        assert!(scoped.css.contains(&format!(
+            "@media (min-width: 800px) {{ padding: 2rem; .{} {{ font-size: 2rem; }} }}",
+            class("title")
+        )));
+        assert!(scoped.css.contains(&format!("@supports (display: grid) {{ .{} {{", class("grid"))));
+        // Other at-rules are copied as they are
+        assert!(scoped.css.contains(r#"@font-face { font-family: "Title"; src: url(title.woff2); }"#));
+        assert!(!scoped.css.contains(":global"));
+    }
+
+    #[test]
+    fn test_scoped_names_depend_on_scope() {
+        assert_ne!(scoped_class_name("a", "A.tsx"), scoped_class_name("a", "B.tsx"));
+        assert_eq!(scoped_class_name("a", "A.tsx"), scoped_class_name("a", "A.tsx"));
+    }
+
+    #[test]
+    fn test_compile_css_template() {
+        let source = r#"
+            import theme from "./theme.module.css";
+            const styles = css`.title { font-weight: bold; }`;
+            function Title() {
+                return <h1 class="title global">{styles.title}{theme.accent}</h1>;
+            }
+        "#;
+
+        let compiler = Compiler::new(CompilerOptions::default());
+        let result = compiler.compile_with_source_map(source, "Title.tsx").unwrap();
+        let title = scoped_class_name("title", "Title.tsx");
+
+        assert_eq!(result.css.as_deref(), Some(format!(".{} {{ font-weight: bold; }}", title).as_str()));
+        assert!(result.code.contains(&format!("\"title\": \"{}\"", title)));
+        assert!(result.code.contains(&format!("\"{} global\"", title)));
+        assert!(result.code.contains("\"./theme.module.css.js\""));
+        assert!(!result.code.contains("css`"));
+    }
+
+    #[test]
+    fn test_local_css_binding_is_left_alone() {
+        let source = r#"
+            import { css } from "other-lib";
+            const styles = css`.title { color: red; }`;
+        "#;
+
+        let compiler = Compiler::new(CompilerOptions::default());
+        let result = compiler.compile_with_source_map(source, "test.ts").unwrap();
+
+        assert!(result.css.is_none());
+        assert!(result.code.contains("css`"));
+    }
+}
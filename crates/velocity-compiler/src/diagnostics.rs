@@ -0,0 +1,161 @@
+//! Compiler Diagnostics
+//!
+//! Non-fatal warnings found while compiling (e.g. effects that write to their
+//! own dependencies). Diagnostics carry the span of the offending code; use
+//! [`Diagnostic::location`] with the original source to get a line/column.
+//!
+//! A diagnostic can be silenced with a comment on the same or preceding line:
+//! ```js
+//! // velocity-ignore effect-loop
+//! createEffect(() => setCount(count() + 1));
+//! ```
+//! A bare `// velocity-ignore` silences every diagnostic for that line.
+
+use std::fmt;
+use swc_core::common::Span;
+
+/// Comment directive used to suppress diagnostics
+const IGNORE_DIRECTIVE: &str = "velocity-ignore";
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A single compiler diagnostic
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Short kebab-case identifier, also used by `velocity-ignore` comments
+    pub code: &'static str,
+    pub message: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    /// Create a warning diagnostic
+    pub fn warning(code: &'static str, message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity: Severity::Warning,
+            code,
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Resolve the 1-based line and column of this diagnostic in `source`
+    pub fn location(&self, source: &str) -> (usize, usize) {
+        line_column(source, self.span)
+    }
+}
+
+/// The text of `source` that `span` covers, or `""` if it is out of range
+pub(crate) fn span_text(source: &str, span: Span) -> &str {
+    // The parser's source map starts the file at BytePos(1)
+    let start = (span.lo.0 as usize).saturating_sub(1);
+    let end = (span.hi.0 as usize).saturating_sub(1);
+    source.get(start..end).unwrap_or("")
+}
+
+/// 1-based line and column where `span` starts in `source`
+pub(crate) fn line_column(source: &str, span: Span) -> (usize, usize) {
+    // The parser's source map starts the file at BytePos(1)
+    let offset = (span.lo.0 as usize).saturating_sub(1).min(source.len());
+    let before = source.get(..offset).unwrap_or(source);
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}[{}]: {}", severity, self.code, self.message)
+    }
+}
+
+/// Drop diagnostics silenced by a `velocity-ignore` comment
+pub fn remove_suppressed(diagnostics: Vec<Diagnostic>, source: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = source.lines().collect();
+
+    diagnostics
+        .into_iter()
+        .filter(|diagnostic| {
+            let (line, _) = diagnostic.location(source);
+            let same_line = lines.get(line - 1).is_some_and(|text| is_ignored(text, diagnostic.code));
+
+            // A directive on the line above only counts if it is a comment-only line
+            let previous_line = line
+                .checked_sub(2)
+                .and_then(|i| lines.get(i))
+                .filter(|text| text.trim_start().starts_with("//") || text.trim_start().starts_with("/*"))
+                .is_some_and(|text| is_ignored(text, diagnostic.code));
+
+            !same_line && !previous_line
+        })
+        .collect()
+}
+
+/// Check whether a line carries an ignore directive covering `code`
+fn is_ignored(line: &str, code: &str) -> bool {
+    let Some(index) = line.find(IGNORE_DIRECTIVE) else {
+        return false;
+    };
+    if !line[..index].contains("//") && !line[..index].contains("/*") {
+        return false;
+    }
+
+    let codes = line[index + IGNORE_DIRECTIVE.len()..]
+        .trim_start()
+        .trim_end_matches("*/")
+        .trim();
+    codes.is_empty() || codes.split([',', ' ']).any(|c| c.trim() == code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swc_core::common::BytePos;
+
+    fn diagnostic_at(offset: u32) -> Diagnostic {
+        let span = Span::new(BytePos(offset + 1), BytePos(offset + 2));
+        Diagnostic::warning("effect-loop", "test", span)
+    }
+
+    #[test]
+    fn test_location() {
+        let source = "let a;\nlet b;\n  createEffect();";
+        let diagnostic = diagnostic_at(source.find("createEffect").unwrap() as u32);
+
+        assert_eq!(diagnostic.location(source), (3, 3));
+    }
+
+    #[test]
+    fn test_remove_suppressed() {
+        let source = "// velocity-ignore effect-loop\nfoo();\nbar(); // velocity-ignore\nbaz();";
+        let diagnostics = vec![
+            diagnostic_at(source.find("foo").unwrap() as u32),
+            diagnostic_at(source.find("bar").unwrap() as u32),
+            diagnostic_at(source.find("baz").unwrap() as u32),
+        ];
+
+        let remaining = remove_suppressed(diagnostics, source);
+
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].location(source), (4, 1));
+    }
+
+    #[test]
+    fn test_suppression_requires_matching_code() {
+        let source = "// velocity-ignore unused-import\nfoo();";
+        let diagnostics = vec![diagnostic_at(source.find("foo").unwrap() as u32)];
+
+        assert_eq!(remove_suppressed(diagnostics, source).len(), 1);
+    }
+}
@@ -0,0 +1,594 @@
+//! Target Downleveling
+//!
+//! Rewrites syntax newer than the configured `target` into equivalent code
+//! older engines understand:
+//! - Optional chaining `a?.b` / `a?.()` (ES2020)
+//! - Nullish coalescing `a ?? b` (ES2020)
+//! - Logical assignment `a ||= b`, `a &&= b`, `a ??= b` (ES2021)
+//!
+//! Non-trivial operands are evaluated once by caching them in `_ref` temps,
+//! which are declared with `var` at the top of the enclosing function. Temps
+//! skip names the module already uses.
+
+use crate::error::{CompilerError, Result};
+use std::collections::HashSet;
+use swc_core::ecma::ast::*;
+use swc_core::ecma::transforms::base::fixer::fixer;
+use swc_core::ecma::visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+
+/// ECMAScript version a target name refers to, as a year (ES5 = 2009)
+pub(crate) fn target_year(target: &str) -> Result<u32> {
+    let target = target.to_ascii_lowercase();
+    match target.as_str() {
+        "es5" => Ok(2009),
+        "es6" => Ok(2015),
+        "esnext" => Ok(u32::MAX),
+        _ => target
+            .strip_prefix("es")
+            .and_then(|year| year.parse().ok())
+            .filter(|year| (2015..=2100).contains(year))
+            .ok_or_else(|| CompilerError::InvalidConfig(format!("Unknown target '{}'", target))),
+    }
+}
+
+/// One link of an optional chain, e.g. `?.b` or `(x)`
+struct ChainSegment {
+    optional: bool,
+    kind: SegmentKind,
+}
+
+enum SegmentKind {
+    Member(MemberProp),
+    Call(Vec<ExprOrSpread>),
+}
+
+/// Visitor that lowers syntax unsupported by the target
+struct Downleveler {
+    lower_optional: bool,
+    lower_logical_assign: bool,
+    /// Temps to declare, one list per enclosing function
+    scopes: Vec<Vec<String>>,
+    /// Identifiers already used in the module, which temps must avoid
+    taken: HashSet<String>,
+    temp_count: usize,
+    changed: bool,
+}
+
+impl Downleveler {
+    fn new(year: u32, taken: HashSet<String>) -> Self {
+        Self {
+            lower_optional: year < 2020,
+            lower_logical_assign: year < 2021,
+            scopes: vec![Vec::new()],
+            taken,
+            temp_count: 0,
+            changed: false,
+        }
+    }
+
+    /// Allocate a temp variable in the current function scope
+    fn temp(&mut self) -> String {
+        let name = loop {
+            self.temp_count += 1;
+            let name = if self.temp_count == 1 {
+                "_ref".to_string()
+            } else {
+                format!("_ref{}", self.temp_count)
+            };
+            if !self.taken.contains(&name) {
+                break name;
+            }
+        };
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(name.clone());
+        }
+        name
+    }
+
+    /// Split an expression into a first use and a side-effect free reuse
+    fn memoize(&mut self, expr: Expr) -> (Expr, Expr) {
+        match expr {
+            Expr::Ident(_) | Expr::This(_) => (expr.clone(), expr),
+            _ => {
+                let name = self.temp();
+                (assign(ident_target(&name), expr), ident(&name))
+            }
+        }
+    }
+
+    /// `a ?? b` → `(_ref = a) != null ? _ref : b`
+    fn lower_nullish(&mut self, left: Expr, right: Expr) -> Expr {
+        let (first, reuse) = self.memoize(left);
+        Expr::Cond(CondExpr {
+            span: Default::default(),
+            test: Box::new(not_nullish(first)),
+            cons: Box::new(reuse),
+            alt: Box::new(right),
+        })
+    }
+
+    /// `a?.b.c` → `(_ref = a) == null ? void 0 : _ref.b.c`
+    fn lower_opt_chain(&mut self, chain: Expr) -> Expr {
+        let (mut current, segments) = flatten_chain(chain);
+        current.visit_mut_with(self);
+
+        let mut tests = Vec::new();
+        let mut this_value = None;
+        let next_is_optional_call: Vec<bool> = segments
+            .iter()
+            .skip(1)
+            .map(|s| s.optional && matches!(s.kind, SegmentKind::Call(_)))
+            .chain(std::iter::once(false))
+            .collect();
+
+        for (segment, next_optional_call) in segments.into_iter().zip(next_is_optional_call) {
+            if segment.optional {
+                let (first, reuse) = self.memoize(current);
+                tests.push(is_nullish(first));
+                current = reuse;
+            }
+
+            current = match segment.kind {
+                SegmentKind::Member(mut prop) => {
+                    prop.visit_mut_with(self);
+                    // Keep the receiver around so `a.b?.()` is still called on `a`
+                    if next_optional_call {
+                        let (first, reuse) = self.memoize(current);
+                        this_value = Some(reuse);
+                        current = first;
+                    } else {
+                        this_value = None;
+                    }
+                    Expr::Member(MemberExpr {
+                        span: Default::default(),
+                        obj: Box::new(current),
+                        prop,
+                    })
+                }
+                SegmentKind::Call(mut args) => {
+                    args.visit_mut_with(self);
+                    let callee = match this_value.take() {
+                        Some(this) if segment.optional => {
+                            args.insert(0, ExprOrSpread { spread: None, expr: Box::new(this) });
+                            Expr::Member(MemberExpr {
+                                span: Default::default(),
+                                obj: Box::new(current),
+                                prop: MemberProp::Ident(IdentName::new("call".into(), Default::default())),
+                            })
+                        }
+                        _ => current,
+                    };
+                    Expr::Call(CallExpr {
+                        callee: Callee::Expr(Box::new(callee)),
+                        args,
+                        ..Default::default()
+                    })
+                }
+            };
+        }
+
+        let Some(test) = tests.into_iter().reduce(|left, right| {
+            Expr::Bin(BinExpr {
+                span: Default::default(),
+                op: BinaryOp::LogicalOr,
+                left: Box::new(left),
+                right: Box::new(right),
+            })
+        }) else {
+            return current;
+        };
+
+        Expr::Cond(CondExpr {
+            span: Default::default(),
+            test: Box::new(test),
+            cons: Box::new(void_zero()),
+            alt: Box::new(current),
+        })
+    }
+
+    /// `a ||= b` → `a || (a = b)`, evaluating member objects only once
+    fn lower_logical_assign(&mut self, assign_expr: AssignExpr) -> Expr {
+        let op = match assign_expr.op {
+            AssignOp::OrAssign => BinaryOp::LogicalOr,
+            AssignOp::AndAssign => BinaryOp::LogicalAnd,
+            _ => BinaryOp::NullishCoalescing,
+        };
+
+        let (read, write) = match assign_expr.left {
+            AssignTarget::Simple(SimpleAssignTarget::Ident(binding)) => (
+                Expr::Ident(binding.id.clone()),
+                AssignTarget::Simple(SimpleAssignTarget::Ident(binding)),
+            ),
+            AssignTarget::Simple(SimpleAssignTarget::Member(member)) => {
+                let (obj_first, obj_reuse) = self.memoize(*member.obj);
+                let (prop_first, prop_reuse) = match member.prop {
+                    MemberProp::Computed(computed) if !matches!(*computed.expr, Expr::Lit(_)) => {
+                        let (first, reuse) = self.memoize(*computed.expr);
+                        (computed_prop(first), computed_prop(reuse))
+                    }
+                    prop => (prop.clone(), prop),
+                };
+                (
+                    Expr::Member(MemberExpr {
+                        span: Default::default(),
+                        obj: Box::new(obj_first),
+                        prop: prop_first,
+                    }),
+                    AssignTarget::Simple(SimpleAssignTarget::Member(MemberExpr {
+                        span: Default::default(),
+                        obj: Box::new(obj_reuse),
+                        prop: prop_reuse,
+                    })),
+                )
+            }
+            // Other targets (e.g. `super.x`) are left for the engine to handle
+            left => return Expr::Assign(AssignExpr { left, ..assign_expr }),
+        };
+
+        let write = assign(write, *assign_expr.right);
+        if op == BinaryOp::NullishCoalescing && self.lower_optional {
+            return self.lower_nullish(read, write);
+        }
+
+        Expr::Bin(BinExpr {
+            span: assign_expr.span,
+            op,
+            left: Box::new(read),
+            right: Box::new(write),
+        })
+    }
+
+    /// Visit a function body in its own temp scope, returning its temps
+    fn with_scope(&mut self, visit: impl FnOnce(&mut Self)) -> Vec<String> {
+        self.scopes.push(Vec::new());
+        visit(self);
+        self.scopes.pop().unwrap_or_default()
+    }
+}
+
+impl VisitMut for Downleveler {
+    fn visit_mut_module(&mut self, module: &mut Module) {
+        module.visit_mut_children_with(self);
+
+        let temps = self.scopes.pop().unwrap_or_default();
+        if !temps.is_empty() {
+            // Declare after imports so the header stays readable
+            let index = module
+                .body
+                .iter()
+                .position(|item| !matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_))))
+                .unwrap_or(module.body.len());
+            module.body.insert(index, ModuleItem::Stmt(temp_decl(temps)));
+        }
+    }
+
+    fn visit_mut_function(&mut self, function: &mut Function) {
+        let temps = self.with_scope(|this| function.visit_mut_children_with(this));
+        if let (false, Some(body)) = (temps.is_empty(), function.body.as_mut()) {
+            body.stmts.insert(0, temp_decl(temps));
+        }
+    }
+
+    fn visit_mut_arrow_expr(&mut self, arrow: &mut ArrowExpr) {
+        let temps = self.with_scope(|this| arrow.visit_mut_children_with(this));
+        if temps.is_empty() {
+            return;
+        }
+
+        // Expression bodies need a block to hold the declaration
+        let body = match &mut *arrow.body {
+            BlockStmtOrExpr::BlockStmt(block) => block,
+            BlockStmtOrExpr::Expr(expr) => {
+                let stmt = Stmt::Return(ReturnStmt {
+                    span: Default::default(),
+                    arg: Some(expr.clone()),
+                });
+                *arrow.body = BlockStmtOrExpr::BlockStmt(BlockStmt {
+                    stmts: vec![stmt],
+                    ..Default::default()
+                });
+                match &mut *arrow.body {
+                    BlockStmtOrExpr::BlockStmt(block) => block,
+                    BlockStmtOrExpr::Expr(_) => unreachable!(),
+                }
+            }
+        };
+        body.stmts.insert(0, temp_decl(temps));
+    }
+
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        if self.lower_optional {
+            // Chains are lowered as a whole so the entire chain short-circuits
+            if let Expr::OptChain(_) = expr {
+                let chain = take_expr(expr);
+                *expr = self.lower_opt_chain(chain);
+                self.changed = true;
+                return;
+            }
+
+            // `delete a?.b` evaluates to true when `a` is nullish
+            if let Expr::Unary(UnaryExpr { op: UnaryOp::Delete, arg, .. }) = expr {
+                if let Expr::OptChain(_) = &**arg {
+                    let chain = take_expr(arg);
+                    let lowered = self.lower_opt_chain(chain);
+                    *expr = match lowered {
+                        Expr::Cond(cond) => Expr::Cond(CondExpr {
+                            cons: Box::new(Expr::Lit(Lit::Bool(Bool { span: Default::default(), value: true }))),
+                            alt: Box::new(delete(*cond.alt)),
+                            ..cond
+                        }),
+                        other => delete(other),
+                    };
+                    self.changed = true;
+                    return;
+                }
+            }
+        }
+
+        expr.visit_mut_children_with(self);
+
+        let lower_nullish = self.lower_optional
+            && matches!(expr, Expr::Bin(bin) if bin.op == BinaryOp::NullishCoalescing);
+        let lower_logical_assign = self.lower_logical_assign
+            && matches!(
+                expr,
+                Expr::Assign(AssignExpr {
+                    op: AssignOp::OrAssign | AssignOp::AndAssign | AssignOp::NullishAssign,
+                    ..
+                })
+            );
+        if !lower_nullish && !lower_logical_assign {
+            return;
+        }
+
+        *expr = match take_expr(expr) {
+            Expr::Bin(bin) => self.lower_nullish(*bin.left, *bin.right),
+            Expr::Assign(assign_expr) => self.lower_logical_assign(assign_expr),
+            other => other,
+        };
+        self.changed = true;
+    }
+}
+
+/// Move an expression out of the tree, leaving a placeholder
+fn take_expr(expr: &mut Expr) -> Expr {
+    std::mem::replace(expr, Expr::Invalid(Invalid { span: Default::default() }))
+}
+
+/// Flatten `a?.b.c()` into its root (`a`) and the links applied to it
+fn flatten_chain(mut expr: Expr) -> (Expr, Vec<ChainSegment>) {
+    let mut segments = Vec::new();
+
+    loop {
+        match expr {
+            Expr::OptChain(chain) => match *chain.base {
+                OptChainBase::Member(member) => {
+                    segments.push(ChainSegment {
+                        optional: chain.optional,
+                        kind: SegmentKind::Member(member.prop),
+                    });
+                    expr = *member.obj;
+                }
+                OptChainBase::Call(call) => {
+                    segments.push(ChainSegment {
+                        optional: chain.optional,
+                        kind: SegmentKind::Call(call.args),
+                    });
+                    expr = *call.callee;
+                }
+            },
+            root => {
+                segments.reverse();
+
+                // `a.b?.()` roots at a plain member; split it so `a` is kept as `this`
+                let optional_call = matches!(
+                    segments.first(),
+                    Some(ChainSegment { optional: true, kind: SegmentKind::Call(_) })
+                );
+                if let (true, Expr::Member(member)) = (optional_call, &root) {
+                    segments.insert(
+                        0,
+                        ChainSegment {
+                            optional: false,
+                            kind: SegmentKind::Member(member.prop.clone()),
+                        },
+                    );
+                    return (*member.obj.clone(), segments);
+                }
+
+                return (root, segments);
+            }
+        }
+    }
+}
+
+fn ident(name: &str) -> Expr {
+    Expr::Ident(Ident::new_no_ctxt(name.into(), Default::default()))
+}
+
+fn ident_target(name: &str) -> AssignTarget {
+    AssignTarget::Simple(SimpleAssignTarget::Ident(BindingIdent {
+        id: Ident::new_no_ctxt(name.into(), Default::default()),
+        type_ann: None,
+    }))
+}
+
+fn computed_prop(expr: Expr) -> MemberProp {
+    MemberProp::Computed(ComputedPropName {
+        span: Default::default(),
+        expr: Box::new(expr),
+    })
+}
+
+fn assign(left: AssignTarget, right: Expr) -> Expr {
+    Expr::Assign(AssignExpr {
+        span: Default::default(),
+        op: AssignOp::Assign,
+        left,
+        right: Box::new(right),
+    })
+}
+
+fn delete(arg: Expr) -> Expr {
+    Expr::Unary(UnaryExpr {
+        span: Default::default(),
+        op: UnaryOp::Delete,
+        arg: Box::new(arg),
+    })
+}
+
+fn void_zero() -> Expr {
+    Expr::Unary(UnaryExpr {
+        span: Default::default(),
+        op: UnaryOp::Void,
+        arg: Box::new(Expr::Lit(Lit::Num(Number {
+            span: Default::default(),
+            value: 0.0,
+            raw: None,
+        }))),
+    })
+}
+
+fn null_lit() -> Expr {
+    Expr::Lit(Lit::Null(Null { span: Default::default() }))
+}
+
+/// `expr == null` (true for both null and undefined)
+fn is_nullish(expr: Expr) -> Expr {
+    Expr::Bin(BinExpr {
+        span: Default::default(),
+        op: BinaryOp::EqEq,
+        left: Box::new(expr),
+        right: Box::new(null_lit()),
+    })
+}
+
+/// `expr != null`
+fn not_nullish(expr: Expr) -> Expr {
+    Expr::Bin(BinExpr {
+        span: Default::default(),
+        op: BinaryOp::NotEq,
+        left: Box::new(expr),
+        right: Box::new(null_lit()),
+    })
+}
+
+/// `var _ref, _ref2;`
+fn temp_decl(names: Vec<String>) -> Stmt {
+    Stmt::Decl(Decl::Var(Box::new(VarDecl {
+        kind: VarDeclKind::Var,
+        decls: names
+            .iter()
+            .map(|name| VarDeclarator {
+                span: Default::default(),
+                name: Pat::Ident(BindingIdent {
+                    id: Ident::new_no_ctxt(name.as_str().into(), Default::default()),
+                    type_ann: None,
+                }),
+                init: None,
+                definite: false,
+            })
+            .collect(),
+        ..Default::default()
+    })))
+}
+
+/// Lower syntax that `target` (e.g. "es2015", "es2020", "esnext") doesn't support
+pub fn downlevel(mut module: Module, target: &str) -> Result<Module> {
+    let mut names = NameCollector::default();
+    module.visit_with(&mut names);
+    let mut downleveler = Downleveler::new(target_year(target)?, names.names);
+    module.visit_mut_with(&mut downleveler);
+
+    // Generated nodes carry no parentheses; add the ones precedence requires
+    if downleveler.changed {
+        module.visit_mut_with(&mut fixer(None));
+    }
+
+    Ok(module)
+}
+
+/// Every identifier in a module, bindings and imports included
+#[derive(Default)]
+struct NameCollector {
+    names: HashSet<String>,
+}
+
+impl Visit for NameCollector {
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.names.insert(ident.sym.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{codegen, parser, CompilerOptions};
+
+    const SNIPPET: &str = r#"
+        function load(config) {
+            const name = config.user?.profile.name;
+            const retries = config.retries ?? 3;
+            config.cache ??= {};
+            config.enabled ||= false;
+            config.handler?.();
+            return name;
+        }
+    "#;
+
+    fn downlevel_to_js(source: &str, target: &str) -> String {
+        let module = parser::parse(source, "test.js").unwrap();
+        let lowered = downlevel(module, target).unwrap();
+        codegen::generate(&lowered, &CompilerOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_downlevel_es2020_keeps_optional_syntax() {
+        let code = downlevel_to_js(SNIPPET, "es2020");
+
+        assert!(code.contains("config.user?.profile.name"));
+        assert!(code.contains("config.retries ?? 3"));
+        assert!(code.contains("config.handler?.()"));
+        // Logical assignment is ES2021
+        assert!(code.contains("config.cache ?? (config.cache = {})"));
+        assert!(code.contains("config.enabled || (config.enabled = false)"));
+    }
+
+    #[test]
+    fn test_downlevel_es2015_lowers_all() {
+        let code = downlevel_to_js(SNIPPET, "es2015");
+
+        assert!(!code.contains("?."));
+        assert!(!code.contains("??"));
+        assert!(!code.contains("||="));
+        assert!(code.contains("var _ref"));
+        assert!(code.contains("(_ref = config.user) == null ? void 0 : _ref.profile.name"));
+        assert!(code.contains("(_ref2 = config.retries) != null ? _ref2 : 3"));
+        assert!(code.contains("(_ref3 = config.cache) != null ? _ref3 : config.cache = {}"));
+        assert!(code.contains("config.enabled || (config.enabled = false)"));
+        assert!(code.contains("(_ref4 = config.handler) == null ? void 0 : _ref4.call(config)"));
+    }
+
+    #[test]
+    fn test_downlevel_temps_skip_names_in_use() {
+        let code = downlevel_to_js("import { _ref } from './refs'; const _ref2 = 1; f(a.b ?? _ref, c.d ?? _ref2);", "es2015");
+
+        assert!(code.contains("var _ref3, _ref4;"), "{}", code);
+        assert!(code.contains("(_ref3 = a.b) != null ? _ref3 : _ref"), "{}", code);
+        assert!(code.contains("(_ref4 = c.d) != null ? _ref4 : _ref2"), "{}", code);
+    }
+
+    #[test]
+    fn test_downlevel_esnext_is_untouched() {
+        let code = downlevel_to_js("a.b ||= c?.d ?? e;", "esnext");
+
+        assert!(code.contains("a.b ||= c?.d ?? e"));
+    }
+
+    #[test]
+    fn test_downlevel_rejects_unknown_target() {
+        let module = parser::parse("a;", "test.js").unwrap();
+
+        assert!(downlevel(module, "es1999").is_err());
+    }
+}
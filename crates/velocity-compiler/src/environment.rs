@@ -0,0 +1,290 @@
+//! Target Environments
+//!
+//! A module runs in a browser window, a Web Worker or Node, and each has its
+//! own globals. [`CompilerOptions::environment`](crate::CompilerOptions)
+//! says which one a module is compiled for:
+//!
+//! | | Browser | Worker | Node |
+//! |---|---|---|---|
+//! | DOM globals (`window`, `document`, `HTMLElement`, `localStorage`) | ✓ | | |
+//! | Worker globals (`importScripts`) | | ✓ | |
+//! | Browser globals (`self`, `location`, `requestAnimationFrame`) | ✓ | ✓ | |
+//! | JSX | DOM helpers | error | DOM helpers, for SSR |
+//! | Runtime imports | `velocity-runtime` | `velocity-runtime/worker` | `velocity-runtime` |
+//!
+//! Globals no environment lacks (`fetch`, `setTimeout`, `console`) are
+//! assumed everywhere. [`check_environment`] warns about reads of a global
+//! the environment doesn't have (`unavailable-global`), except under
+//! `typeof`, which is how code checks for one, and names the module declares
+//! itself. In a worker it also warns about DOM helpers imported from the
+//! runtime (`worker-dom-helper`): `velocity-runtime/worker` only has the
+//! reactivity and component helpers, which don't touch the DOM.
+
+use crate::diagnostics::Diagnostic;
+use crate::error::{CompilerError, Result};
+use crate::runtime_imports::is_dom_helper;
+use crate::transformer::RUNTIME_MODULE;
+use std::collections::HashSet;
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::{Visit, VisitWith};
+
+/// Runtime entry point worker modules import from instead of the barrel
+pub(crate) const WORKER_RUNTIME_MODULE: &str = "velocity-runtime/worker";
+
+/// Globals only a browser window has
+const WINDOW_GLOBALS: &[&str] = &[
+    "window",
+    "document",
+    "localStorage",
+    "sessionStorage",
+    "alert",
+    "confirm",
+    "prompt",
+    "getComputedStyle",
+    "matchMedia",
+    "requestIdleCallback",
+    "customElements",
+    "history",
+    "Node",
+    "Element",
+    "HTMLElement",
+    "SVGElement",
+    "Text",
+    "DocumentFragment",
+    "DOMParser",
+    "Image",
+    "Audio",
+    "MutationObserver",
+    "IntersectionObserver",
+    "ResizeObserver",
+];
+
+/// Globals only a worker has
+const WORKER_GLOBALS: &[&str] = &["importScripts"];
+
+/// Globals windows and workers have but Node doesn't
+const BROWSER_GLOBALS: &[&str] = &["self", "location", "requestAnimationFrame", "cancelAnimationFrame"];
+
+/// Where compiled code runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Environment {
+    /// A browser window's main thread
+    #[default]
+    Browser,
+    /// A dedicated, shared or service worker
+    Worker,
+    /// Node, for server rendering and tooling
+    Node,
+}
+
+impl Environment {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "browser" => Ok(Self::Browser),
+            "worker" => Ok(Self::Worker),
+            "node" => Ok(Self::Node),
+            other => Err(CompilerError::InvalidConfig(format!(
+                "Unknown environment '{}' (expected browser, worker or node)",
+                other
+            ))),
+        }
+    }
+
+    /// Whether code running here can read the global `name`
+    pub fn has_global(self, name: &str) -> bool {
+        let missing: &[&[&str]] = match self {
+            Self::Browser => &[WORKER_GLOBALS],
+            Self::Worker => &[WINDOW_GLOBALS],
+            Self::Node => &[WINDOW_GLOBALS, WORKER_GLOBALS, BROWSER_GLOBALS],
+        };
+        !missing.iter().any(|globals| globals.contains(&name))
+    }
+
+    /// The environment as it reads in a diagnostic
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Browser => "a browser window",
+            Self::Worker => "a Web Worker",
+            Self::Node => "Node",
+        }
+    }
+}
+
+/// Warnings for globals and runtime helpers `module` uses that
+/// `environment` doesn't have. Expects TypeScript already stripped.
+pub fn check_environment(module: &Module, environment: Environment) -> Vec<Diagnostic> {
+    let mut declared = DeclaredNames::default();
+    module.visit_with(&mut declared);
+
+    let mut checker = EnvironmentChecker {
+        environment,
+        declared: declared.0,
+        diagnostics: Vec::new(),
+    };
+    module.visit_with(&mut checker);
+    checker.diagnostics
+}
+
+/// Every name declared anywhere in a module. A global is only reported when
+/// no scope shadows it, which keeps the check free of false positives at
+/// the cost of missing some reads.
+#[derive(Default)]
+struct DeclaredNames(HashSet<String>);
+
+impl Visit for DeclaredNames {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.0.insert(ident.id.sym.to_string());
+    }
+
+    fn visit_fn_decl(&mut self, decl: &FnDecl) {
+        self.0.insert(decl.ident.sym.to_string());
+        decl.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, decl: &ClassDecl) {
+        self.0.insert(decl.ident.sym.to_string());
+        decl.visit_children_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, expr: &FnExpr) {
+        self.0.extend(expr.ident.as_ref().map(|ident| ident.sym.to_string()));
+        expr.visit_children_with(self);
+    }
+
+    fn visit_class_expr(&mut self, expr: &ClassExpr) {
+        self.0.extend(expr.ident.as_ref().map(|ident| ident.sym.to_string()));
+        expr.visit_children_with(self);
+    }
+
+    fn visit_import_specifier(&mut self, specifier: &ImportSpecifier) {
+        let local = match specifier {
+            ImportSpecifier::Named(named) => &named.local,
+            ImportSpecifier::Default(default) => &default.local,
+            ImportSpecifier::Namespace(namespace) => &namespace.local,
+        };
+        self.0.insert(local.sym.to_string());
+    }
+}
+
+/// Visitor behind [`check_environment`]
+struct EnvironmentChecker {
+    environment: Environment,
+    declared: HashSet<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl EnvironmentChecker {
+    fn check_global(&mut self, ident: &Ident) {
+        if self.environment.has_global(&ident.sym) || self.declared.contains(&*ident.sym) {
+            return;
+        }
+        self.diagnostics.push(Diagnostic::warning(
+            "unavailable-global",
+            format!("`{}` isn't available in {}", ident.sym, self.environment.describe()),
+            ident.span,
+        ));
+    }
+}
+
+impl Visit for EnvironmentChecker {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Ident(ident) => self.check_global(ident),
+            // `typeof document !== "undefined"` is how code checks first
+            Expr::Unary(UnaryExpr {
+                op: UnaryOp::TypeOf,
+                arg,
+                ..
+            }) if arg.is_ident() => {}
+            _ => expr.visit_children_with(self),
+        }
+    }
+
+    // `{ document }`
+    fn visit_prop(&mut self, prop: &Prop) {
+        match prop {
+            Prop::Shorthand(ident) => self.check_global(ident),
+            _ => prop.visit_children_with(self),
+        }
+    }
+
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        if self.environment != Environment::Worker || &*import.src.value != RUNTIME_MODULE || import.type_only {
+            return;
+        }
+        for specifier in &import.specifiers {
+            let ImportSpecifier::Named(named) = specifier else {
+                continue;
+            };
+            let name = match &named.imported {
+                Some(ModuleExportName::Ident(ident)) => ident.sym.as_str(),
+                Some(ModuleExportName::Str(name)) => name.value.as_str(),
+                None => named.local.sym.as_str(),
+            };
+            if is_dom_helper(name) && !named.is_type_only {
+                self.diagnostics.push(Diagnostic::warning(
+                    "worker-dom-helper",
+                    format!("`{}` works on the DOM, which a Web Worker doesn't have", name),
+                    named.span,
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser, Compiler, CompilerOptions};
+
+    fn codes(source: &str, environment: Environment) -> Vec<(&'static str, String)> {
+        let module = parser::parse(source, "test.ts").unwrap();
+        check_environment(&module, environment)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_environment_reports_missing_globals() {
+        let source = r#"
+            import { createSignal, render } from "velocity-runtime";
+            const [count, setCount] = createSignal(0);
+            if (typeof document !== "undefined") document.title = "ok";
+            self.onmessage = (event) => postMessage(localStorage.getItem(event.data));
+            function handle(window) { return window.location; }
+            importScripts("polyfill.js");
+        "#;
+
+        assert_eq!(
+            codes(source, Environment::Worker),
+            vec![
+                ("worker-dom-helper", "`render` works on the DOM, which a Web Worker doesn't have".to_string()),
+                ("unavailable-global", "`document` isn't available in a Web Worker".to_string()),
+                ("unavailable-global", "`localStorage` isn't available in a Web Worker".to_string()),
+            ]
+        );
+        assert_eq!(
+            codes(source, Environment::Browser),
+            vec![("unavailable-global", "`importScripts` isn't available in a browser window".to_string())]
+        );
+        assert_eq!(codes(source, Environment::Node).len(), 4);
+    }
+
+    #[test]
+    fn test_worker_imports_worker_runtime_and_rejects_jsx() {
+        let compiler = Compiler::new(CompilerOptions {
+            source_maps: false,
+            environment: Environment::Worker,
+            ..Default::default()
+        });
+
+        let code = compiler
+            .compile(r#"import { createSignal } from "velocity-runtime"; export const [a, b] = createSignal(0);"#, "w.ts")
+            .unwrap();
+        assert!(code.contains(r#"from "velocity-runtime/worker""#), "{}", code);
+
+        let error = compiler.compile("export const view = <div />;", "w.tsx").unwrap_err();
+        assert!(error.to_string().contains("w.tsx renders JSX"), "{}", error);
+    }
+}
@@ -1,5 +1,22 @@
 //! Error types for the Velocity compiler
+//!
+//! Every error has a stable code (`VEL<category><number>`) and a category so
+//! tooling can react to specific failures without matching on messages:
+//!
+//! | Code      | Category       | Variant             |
+//! |-----------|----------------|---------------------|
+//! | `VEL1001` | `parse`        | `ParseError`        |
+//! | `VEL2001` | `analysis`     | `AnalysisError`     |
+//! | `VEL3001` | `transform`    | `TransformError`    |
+//! | `VEL3002` | `transform`    | `InvalidJsx`        |
+//! | `VEL4001` | `optimization` | `OptimizationError` |
+//! | `VEL5001` | `codegen`      | `CodegenError`      |
+//! | `VEL6001` | `io`           | `IoError`           |
+//! | `VEL7001` | `config`       | `InvalidConfig`     |
+//!
+//! Codes are never reused or renumbered once released.
 
+use std::fmt;
 use thiserror::Error;
 
 /// Result type alias for compiler operations
@@ -32,3 +49,82 @@ pub enum CompilerError {
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
 }
+
+/// Broad class of a [`CompilerError`], for filtering whole groups of failures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    Parse,
+    Analysis,
+    Transform,
+    Optimization,
+    Codegen,
+    Io,
+    Config,
+}
+
+impl ErrorCategory {
+    /// Lowercase name used in machine-readable output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCategory::Parse => "parse",
+            ErrorCategory::Analysis => "analysis",
+            ErrorCategory::Transform => "transform",
+            ErrorCategory::Optimization => "optimization",
+            ErrorCategory::Codegen => "codegen",
+            ErrorCategory::Io => "io",
+            ErrorCategory::Config => "config",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl CompilerError {
+    /// Stable error code, e.g. `VEL1001` for a parse error
+    pub fn code(&self) -> &'static str {
+        match self {
+            CompilerError::ParseError(_) => "VEL1001",
+            CompilerError::AnalysisError(_) => "VEL2001",
+            CompilerError::TransformError(_) => "VEL3001",
+            CompilerError::InvalidJsx(_) => "VEL3002",
+            CompilerError::OptimizationError(_) => "VEL4001",
+            CompilerError::CodegenError(_) => "VEL5001",
+            CompilerError::IoError(_) => "VEL6001",
+            CompilerError::InvalidConfig(_) => "VEL7001",
+        }
+    }
+
+    /// Category the error belongs to
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            CompilerError::ParseError(_) => ErrorCategory::Parse,
+            CompilerError::AnalysisError(_) => ErrorCategory::Analysis,
+            CompilerError::TransformError(_) | CompilerError::InvalidJsx(_) => ErrorCategory::Transform,
+            CompilerError::OptimizationError(_) => ErrorCategory::Optimization,
+            CompilerError::CodegenError(_) => ErrorCategory::Codegen,
+            CompilerError::IoError(_) => ErrorCategory::Io,
+            CompilerError::InvalidConfig(_) => ErrorCategory::Config,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        let error = CompilerError::ParseError("unexpected token".to_string());
+        assert_eq!(error.code(), "VEL1001");
+        assert_eq!(error.category(), ErrorCategory::Parse);
+        assert_eq!(error.category().to_string(), "parse");
+
+        let error = CompilerError::InvalidJsx("unclosed tag".to_string());
+        assert_eq!(error.code(), "VEL3002");
+        assert_eq!(error.category(), ErrorCategory::Transform);
+    }
+}
@@ -0,0 +1,203 @@
+//! HMR Signal Keys
+//!
+//! A hot update re-evaluates the page's modules, so every `createSignal`
+//! starts over from its initial value. [`key_signals`] gives each call a
+//! key that stays the same across edits, so the HMR client can save signal
+//! values before the update and the runtime hand them back to the signals
+//! with the same key afterwards:
+//! ```js
+//! function Counter() {
+//!     const [count, setCount] = createSignal(0);
+//!     // becomes
+//!     const [count, setCount] = createSignal(0, "src/Counter.tsx#Counter.count:0");
+//! }
+//! ```
+//!
+//! ## Matching
+//! A key is the file, the enclosing named function (the component or hook;
+//! none at the top level), the name the signal is bound to (its getter when
+//! destructured, `signal` when it isn't bound to a name) and how many
+//! signals of that name came before it in that function. Editing a
+//! component's JSX, effects or handlers, or adding a signal under another
+//! name, keeps every key. Renaming a signal, its component or its file, or
+//! adding a signal of the same name before it, gives it a new key and so its
+//! initial value.
+//!
+//! A component rendered several times creates several signals with one key;
+//! the runtime restores them in the order they're created, so instances
+//! that render in another order after the update swap state. A value is
+//! restored only when it was plain data (primitives, arrays, plain objects)
+//! and has the same kind as the new initial value, or that is `null` or
+//! `undefined`; changing `createSignal(0)` to `createSignal("")` resets it.
+//!
+//! Only calls to `createSignal` by that name with a single argument are
+//! keyed. [`CompilerOptions::hmr_signal_keys`](crate::CompilerOptions) turns
+//! this on for the dev server.
+
+use std::collections::HashMap;
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::{noop_visit_mut_type, VisitMut, VisitMutWith};
+
+/// Name in keys for signals that aren't bound to a name
+const UNNAMED: &str = "signal";
+
+/// Give every `createSignal(initial)` call in `module` its HMR key as a
+/// second argument
+pub fn key_signals(module: &mut Module, filename: &str) {
+    module.visit_mut_with(&mut SignalKeys {
+        file: filename.replace('\\', "/"),
+        functions: Vec::new(),
+        counts: HashMap::new(),
+    });
+}
+
+/// Visitor behind [`key_signals`]
+struct SignalKeys {
+    file: String,
+    /// Names of the enclosing named functions, innermost last
+    functions: Vec<String>,
+    /// `function.name` → signals keyed under it so far
+    counts: HashMap<String, usize>,
+}
+
+impl SignalKeys {
+    fn in_function(&mut self, name: String, visit: impl FnOnce(&mut Self)) {
+        self.functions.push(name);
+        visit(self);
+        self.functions.pop();
+    }
+
+    /// Add the key to `call` if it's a call to be keyed
+    fn key(&mut self, call: &mut CallExpr, name: &str) {
+        if !is_create_signal(call) || call.args.len() != 1 || call.args[0].spread.is_some() {
+            return;
+        }
+
+        let scope = match self.functions.last() {
+            Some(function) => format!("{}.{}", function, name),
+            None => name.to_string(),
+        };
+        let count = self.counts.entry(scope.clone()).or_default();
+        let key = format!("{}#{}:{}", self.file, scope, count);
+        *count += 1;
+
+        call.args.push(ExprOrSpread {
+            spread: None,
+            expr: Box::new(Expr::Lit(Lit::Str(Str {
+                span: Default::default(),
+                value: key.into(),
+                raw: None,
+            }))),
+        });
+    }
+}
+
+impl VisitMut for SignalKeys {
+    noop_visit_mut_type!();
+
+    fn visit_mut_fn_decl(&mut self, decl: &mut FnDecl) {
+        self.in_function(decl.ident.sym.to_string(), |keys| decl.visit_mut_children_with(keys));
+    }
+
+    // export default function Counter() { ... }
+    fn visit_mut_fn_expr(&mut self, expr: &mut FnExpr) {
+        match &expr.ident {
+            Some(ident) => self.in_function(ident.sym.to_string(), |keys| expr.visit_mut_children_with(keys)),
+            None => expr.visit_mut_children_with(self),
+        }
+    }
+
+    fn visit_mut_var_declarator(&mut self, decl: &mut VarDeclarator) {
+        // const Counter = () => { ... }
+        if let (Pat::Ident(name), Some(Expr::Arrow(_) | Expr::Fn(_))) = (&decl.name, decl.init.as_deref()) {
+            let name = name.id.sym.to_string();
+            return self.in_function(name, |keys| decl.visit_mut_children_with(keys));
+        }
+
+        // const [count, setCount] = createSignal(0), keyed by the getter
+        if let Some(Expr::Call(call)) = decl.init.as_deref_mut() {
+            let name = match &decl.name {
+                Pat::Ident(name) => Some(&name.id.sym),
+                Pat::Array(array) => match array.elems.first() {
+                    Some(Some(Pat::Ident(getter))) => Some(&getter.id.sym),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some(name) = name {
+                self.key(call, name);
+            }
+        }
+        decl.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        call.visit_mut_children_with(self);
+        self.key(call, UNNAMED);
+    }
+}
+
+/// Whether `call` calls `createSignal`
+fn is_create_signal(call: &CallExpr) -> bool {
+    matches!(&call.callee, Callee::Expr(callee) if matches!(&**callee, Expr::Ident(ident) if &*ident.sym == "createSignal"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Compiler, CompilerOptions};
+
+    fn compile(source: &str) -> String {
+        let compiler = Compiler::new(CompilerOptions {
+            source_maps: false,
+            hmr_signal_keys: true,
+            ..Default::default()
+        });
+        compiler.compile(source, "src/Counter.tsx").unwrap()
+    }
+
+    #[test]
+    fn test_key_signals() {
+        let code = compile(
+            r#"
+            import { createSignal } from "velocity-runtime";
+            const [theme] = createSignal("dark");
+            function Counter() {
+                const [count, setCount] = createSignal(0);
+                const step = createSignal(1);
+                const [other] = createSignal(0, "custom");
+                const pair = [createSignal(0), createSignal(1)];
+                function useInner() { const [count] = createSignal(5); }
+                return count();
+            }
+            export const Toggle = () => { const [count] = createSignal(false); return count(); };
+            "#,
+        );
+
+        assert!(code.contains(r#"createSignal("dark", "src/Counter.tsx#theme:0")"#), "{}", code);
+        assert!(code.contains(r#"createSignal(0, "src/Counter.tsx#Counter.count:0")"#), "{}", code);
+        assert!(code.contains(r#"createSignal(1, "src/Counter.tsx#Counter.step:0")"#), "{}", code);
+        assert!(code.contains(r#"createSignal(0, "custom")"#), "{}", code);
+        assert!(code.contains(r#"createSignal(0, "src/Counter.tsx#Counter.signal:0")"#), "{}", code);
+        assert!(code.contains(r#"createSignal(1, "src/Counter.tsx#Counter.signal:1")"#), "{}", code);
+        assert!(code.contains(r#"createSignal(5, "src/Counter.tsx#useInner.count:0")"#), "{}", code);
+        assert!(code.contains(r#"createSignal(false, "src/Counter.tsx#Toggle.count:0")"#), "{}", code);
+    }
+
+    #[test]
+    fn test_keys_survive_edits_elsewhere() {
+        let before = compile(
+            r#"function Counter() { const [count] = createSignal(0); return <p>{count()}</p>; }"#,
+        );
+        let after = compile(
+            r#"function Counter() {
+                const [label] = createSignal("Clicks");
+                const [count] = createSignal(0);
+                return <button>{label()}: {count()}</button>;
+            }"#,
+        );
+
+        let key = r#""src/Counter.tsx#Counter.count:0""#;
+        assert!(before.contains(key), "{}", before);
+        assert!(after.contains(key), "{}", after);
+    }
+}
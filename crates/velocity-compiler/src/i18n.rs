@@ -0,0 +1,401 @@
+//! Translatable Messages
+//!
+//! Strings marked for translation, collected at build time so translators
+//! get every message without anyone listing them by hand:
+//! ```jsx
+//! t("cart.empty", "Your cart is empty")
+//! <Trans id="cart.title">Shopping cart</Trans>
+//! ```
+//! The key is required and must be a string literal. The default message (the
+//! second argument, or the element's text) is optional and must be literal
+//! too; a key with a computed default is still extracted, without one. The
+//! marker names are configurable ([`I18nOptions`]).
+//!
+//! With [`I18nOptions::strip_defaults`] the defaults are removed from the
+//! code once extracted, leaving keyed lookups: `t("cart.empty")` and
+//! `<Trans id="cart.title" />`. The catalog then has to be loaded at runtime.
+//!
+//! ## Catalog format
+//! [`Catalog`] serializes to one flat JSON object from key to default
+//! message, sorted by key; a key never given a default maps to `""`. Files
+//! are merged in the order they're added: a key used in several places gets
+//! the first default given for it, and a different default given later is
+//! reported as a [`Conflict`] rather than silently replacing it.
+
+use crate::diagnostics::Diagnostic;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use swc_core::common::{Span, Spanned};
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::{VisitMut, VisitMutWith};
+
+/// Which calls and elements mark translatable messages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct I18nOptions {
+    /// Function taking a key and an optional default, e.g. `t`
+    pub function: String,
+    /// Component taking the key as `id` and the default as its text
+    pub component: String,
+    /// Whether to remove extracted defaults from the output
+    pub strip_defaults: bool,
+}
+
+impl Default for I18nOptions {
+    fn default() -> Self {
+        Self {
+            function: "t".to_string(),
+            component: "Trans".to_string(),
+            strip_defaults: false,
+        }
+    }
+}
+
+/// A message marked for translation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub key: String,
+    /// The default message, when given as a literal
+    pub default: Option<String>,
+    /// The marking call or element
+    pub span: Span,
+}
+
+/// What [`extract_messages`] found in a module
+#[derive(Debug, Clone, Default)]
+pub struct Extraction {
+    /// Messages in source order
+    pub messages: Vec<Message>,
+    /// Markers whose key or default isn't a literal
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Collect the messages marked in `module`, removing their defaults if
+/// [`I18nOptions::strip_defaults`] is set. Expects TypeScript already
+/// stripped and JSX not yet transformed.
+pub fn extract_messages(module: &mut Module, options: &I18nOptions) -> Extraction {
+    let mut extractor = MessageExtractor {
+        options,
+        extraction: Extraction::default(),
+    };
+    module.visit_mut_with(&mut extractor);
+    extractor.extraction
+}
+
+/// Visitor behind [`extract_messages`]
+struct MessageExtractor<'a> {
+    options: &'a I18nOptions,
+    extraction: Extraction,
+}
+
+impl MessageExtractor<'_> {
+    fn record(&mut self, key: String, default: Option<String>, span: Span) {
+        self.extraction.messages.push(Message { key, default, span });
+    }
+
+    fn warn(&mut self, code: &'static str, message: String, span: Span) {
+        self.extraction.diagnostics.push(Diagnostic::warning(code, message, span));
+    }
+
+    /// `t("key", "default")`
+    fn extract_call(&mut self, call: &mut CallExpr) {
+        let name = &self.options.function;
+        let Some(key) = call.args.first().and_then(|arg| string_literal(&arg.expr)) else {
+            self.warn(
+                "i18n-dynamic-key",
+                format!("`{}()` needs a string literal key to be extracted", name),
+                call.span,
+            );
+            return;
+        };
+
+        let default = match call.args.get(1) {
+            None => None,
+            Some(arg) => match string_literal(&arg.expr) {
+                Some(default) => Some(default),
+                None => {
+                    let message = format!("The default of `{}(\"{}\")` isn't a string literal, so it isn't extracted", name, key);
+                    self.warn("i18n-dynamic-message", message, arg.expr.span());
+                    None
+                }
+            },
+        };
+
+        // Later arguments (interpolation values) keep their positions
+        if default.is_some() && self.options.strip_defaults {
+            if call.args.len() == 2 {
+                call.args.truncate(1);
+            } else {
+                *call.args[1].expr = Expr::Ident(Ident::new_no_ctxt("undefined".into(), Default::default()));
+            }
+        }
+        self.record(key, default, call.span);
+    }
+
+    /// `<Trans id="key">default</Trans>`
+    fn extract_element(&mut self, element: &mut JSXElement) {
+        let component = &self.options.component;
+        let id = element.opening.attrs.iter().find_map(|attr| match attr {
+            JSXAttrOrSpread::JSXAttr(JSXAttr {
+                name: JSXAttrName::Ident(name),
+                value,
+                ..
+            }) if name.sym == "id" => Some(value.as_ref().and_then(attr_literal)),
+            _ => None,
+        });
+        let Some(Some(key)) = id else {
+            self.warn(
+                "i18n-dynamic-key",
+                format!("`<{}>` needs a string literal `id` to be extracted", component),
+                element.span,
+            );
+            return;
+        };
+
+        let default = if element.children.is_empty() {
+            None
+        } else {
+            match children_text(&element.children) {
+                Some(text) => Some(text),
+                None => {
+                    let message = format!(
+                        "`<{} id=\"{}\">` has markup or expressions inside, so its text isn't extracted",
+                        component, key
+                    );
+                    self.warn("i18n-dynamic-message", message, element.span);
+                    None
+                }
+            }
+        };
+
+        if default.is_some() && self.options.strip_defaults {
+            element.children.clear();
+            element.closing = None;
+            element.opening.self_closing = true;
+        }
+        self.record(key, default, element.span);
+    }
+}
+
+impl VisitMut for MessageExtractor<'_> {
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        call.visit_mut_children_with(self);
+        if matches!(&call.callee, Callee::Expr(callee) if matches!(&**callee, Expr::Ident(ident) if ident.sym == *self.options.function)) {
+            self.extract_call(call);
+        }
+    }
+
+    fn visit_mut_jsx_element(&mut self, element: &mut JSXElement) {
+        element.visit_mut_children_with(self);
+        if matches!(&element.opening.name, JSXElementName::Ident(ident) if ident.sym == *self.options.component) {
+            self.extract_element(element);
+        }
+    }
+}
+
+/// The value of a string literal or a template literal without substitutions
+fn string_literal(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+        Expr::Tpl(tpl) if tpl.exprs.is_empty() => tpl.quasis.first()?.cooked.as_ref().map(|s| s.to_string()),
+        Expr::Paren(paren) => string_literal(&paren.expr),
+        _ => None,
+    }
+}
+
+/// The string of `id="key"` or `id={"key"}`
+fn attr_literal(value: &JSXAttrValue) -> Option<String> {
+    match value {
+        JSXAttrValue::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+        JSXAttrValue::JSXExprContainer(JSXExprContainer {
+            expr: JSXExpr::Expr(expr),
+            ..
+        }) => string_literal(expr),
+        _ => None,
+    }
+}
+
+/// The text of children made only of text and `{"literal"}` containers,
+/// with whitespace runs collapsed to one space as the page would show them
+fn children_text(children: &[JSXElementChild]) -> Option<String> {
+    let mut text = String::new();
+    for child in children {
+        match child {
+            JSXElementChild::JSXText(t) => text.push_str(&t.value),
+            JSXElementChild::JSXExprContainer(JSXExprContainer {
+                expr: JSXExpr::Expr(expr),
+                ..
+            }) => text.push_str(&string_literal(expr)?),
+            JSXElementChild::JSXExprContainer(JSXExprContainer {
+                expr: JSXExpr::JSXEmptyExpr(_),
+                ..
+            }) => {}
+            _ => return None,
+        }
+    }
+    Some(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// A key given two different defaults
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub key: String,
+    /// The default the catalog keeps, from the file added first
+    pub kept: String,
+    /// The default that was dropped
+    pub ignored: String,
+    /// File the dropped default came from
+    pub file: String,
+}
+
+/// The messages of a whole build, in the format described in the
+/// [module docs](self)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Catalog {
+    messages: BTreeMap<String, String>,
+}
+
+impl Catalog {
+    /// Merge the messages extracted from `file`, returning the keys whose
+    /// default differs from the one already in the catalog
+    pub fn add(&mut self, file: &str, messages: &[Message]) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+        for message in messages {
+            let entry = self.messages.entry(message.key.clone()).or_default();
+            match &message.default {
+                Some(default) if entry.is_empty() => *entry = default.clone(),
+                Some(default) if entry != default => conflicts.push(Conflict {
+                    key: message.key.clone(),
+                    kept: entry.clone(),
+                    ignored: default.clone(),
+                    file: file.to_string(),
+                }),
+                _ => {}
+            }
+        }
+        conflicts
+    }
+
+    /// The default message of `key`; `""` for a key without one
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// The catalog as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{codegen, parser, CompilerOptions};
+
+    fn extract(source: &str, options: &I18nOptions) -> (Extraction, String) {
+        let mut module = parser::parse(source, "test.tsx").unwrap();
+        let extraction = extract_messages(&mut module, options);
+        let code = codegen::generate(
+            &module,
+            &CompilerOptions {
+                jsx: crate::JsxMode::Preserve,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        (extraction, code)
+    }
+
+    const SOURCE: &str = r#"
+        const title = t("cart.title", "Your cart");
+        const count = t(`cart.count`, "{n} items", { n });
+        const label = t("cart.label");
+        const computed = t(key, "ignored");
+        const view = <p>
+            <Trans id="cart.empty">
+                Nothing  here
+                {" yet"}
+            </Trans>
+            <Trans id="cart.rich">Go <a href="/">home</a></Trans>
+        </p>;
+    "#;
+
+    #[test]
+    fn test_extract_messages() {
+        let (extraction, code) = extract(SOURCE, &I18nOptions::default());
+
+        let messages: Vec<_> = extraction
+            .messages
+            .iter()
+            .map(|m| (m.key.as_str(), m.default.as_deref()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                ("cart.title", Some("Your cart")),
+                ("cart.count", Some("{n} items")),
+                ("cart.label", None),
+                ("cart.empty", Some("Nothing here yet")),
+                ("cart.rich", None),
+            ]
+        );
+        let codes: Vec<_> = extraction.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["i18n-dynamic-key", "i18n-dynamic-message"]);
+        assert!(code.contains(r#"t("cart.title", "Your cart")"#), "{}", code);
+    }
+
+    #[test]
+    fn test_strip_defaults() {
+        let options = I18nOptions {
+            function: "translate".to_string(),
+            component: "Message".to_string(),
+            strip_defaults: true,
+        };
+        let source = SOURCE.replace("t(", "translate(").replace("Trans", "Message");
+        let (extraction, code) = extract(&source, &options);
+
+        assert_eq!(extraction.messages.len(), 5);
+        assert!(code.contains(r#"translate("cart.title")"#), "{}", code);
+        assert!(code.contains(r#"translate(`cart.count`, undefined, {"#), "{}", code);
+        assert!(code.contains(r#"translate(key, "ignored")"#), "{}", code);
+        assert!(code.contains(r#"<Message id="cart.empty"/>"#), "{}", code);
+        assert!(code.contains(r#"<a href="/">home</a></Message>"#), "{}", code);
+    }
+
+    #[test]
+    fn test_catalog_merges_files() {
+        let message = |key: &str, default: Option<&str>| Message {
+            key: key.to_string(),
+            default: default.map(String::from),
+            span: Default::default(),
+        };
+        let mut catalog = Catalog::default();
+
+        let conflicts = catalog.add("src/A.tsx", &[message("save", Some("Save")), message("title", None)]);
+        assert!(conflicts.is_empty());
+        let conflicts = catalog.add(
+            "src/B.tsx",
+            &[message("save", Some("Save")), message("title", Some("Title")), message("save", Some("Store"))],
+        );
+
+        assert_eq!(
+            conflicts,
+            [Conflict {
+                key: "save".to_string(),
+                kept: "Save".to_string(),
+                ignored: "Store".to_string(),
+                file: "src/B.tsx".to_string(),
+            }]
+        );
+        assert_eq!(catalog.get("title"), Some("Title"));
+        assert_eq!(catalog.to_json(), "{\n  \"save\": \"Save\",\n  \"title\": \"Title\"\n}");
+    }
+}
@@ -0,0 +1,153 @@
+//! Import Specifiers
+//!
+//! Finds and rewrites the module specifiers a file imports, e.g. to point
+//! imports at content-hashed output files:
+//! - `import x from "./a"` / `import "./a"`
+//! - `export * from "./a"` / `export { x } from "./a"`
+//! - dynamic `import("./a")` with a string literal argument
+
+use crate::error::Result;
+use crate::parser;
+use std::ops::Range;
+use swc_core::common::Span;
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::{Visit, VisitWith};
+
+/// A module specifier found in source code
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportSpecifierRef {
+    /// The specifier as written, without quotes
+    pub specifier: String,
+    /// Byte range of the string literal in the source, including quotes
+    pub range: Range<usize>,
+    /// Whether this is a dynamic `import()`, loaded only when it runs
+    pub dynamic: bool,
+}
+
+/// Collect every static and dynamic import specifier in `source`
+pub fn find_imports(source: &str, filename: &str) -> Result<Vec<ImportSpecifierRef>> {
+    let module = parser::parse(source, filename)?;
+    let mut finder = ImportFinder::default();
+    module.visit_with(&mut finder);
+
+    finder.imports.sort_by_key(|import| import.range.start);
+    Ok(finder.imports)
+}
+
+/// Rewrite import specifiers in `source`.
+///
+/// `rewrite` receives each specifier and returns a replacement, or `None` to
+/// leave it unchanged. The original quote style is kept.
+pub fn rewrite_imports(
+    source: &str,
+    filename: &str,
+    mut rewrite: impl FnMut(&str) -> Option<String>,
+) -> Result<String> {
+    let mut output = String::with_capacity(source.len());
+    let mut last = 0;
+
+    for import in find_imports(source, filename)? {
+        let Some(replacement) = rewrite(&import.specifier) else {
+            continue;
+        };
+        let quote = source[import.range.clone()].chars().next().unwrap_or('"');
+
+        output.push_str(&source[last..import.range.start]);
+        output.push(quote);
+        output.push_str(&replacement);
+        output.push(quote);
+        last = import.range.end;
+    }
+
+    output.push_str(&source[last..]);
+    Ok(output)
+}
+
+/// Visitor that records module specifier string literals
+#[derive(Default)]
+struct ImportFinder {
+    imports: Vec<ImportSpecifierRef>,
+}
+
+impl ImportFinder {
+    fn record(&mut self, src: &Str, dynamic: bool) {
+        self.imports.push(ImportSpecifierRef {
+            specifier: src.value.to_string(),
+            range: byte_range(src.span),
+            dynamic,
+        });
+    }
+}
+
+impl Visit for ImportFinder {
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        self.record(&import.src, false);
+    }
+
+    fn visit_export_all(&mut self, export: &ExportAll) {
+        self.record(&export.src, false);
+    }
+
+    fn visit_named_export(&mut self, export: &NamedExport) {
+        if let Some(src) = &export.src {
+            self.record(src, false);
+        }
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let (Callee::Import(_), Some(arg)) = (&call.callee, call.args.first()) {
+            if let Expr::Lit(Lit::Str(src)) = &*arg.expr {
+                self.record(src, true);
+            }
+        }
+        call.visit_children_with(self);
+    }
+}
+
+/// Convert a span into a byte range of the parsed source
+fn byte_range(span: Span) -> Range<usize> {
+    // The parser's source map starts the file at BytePos(1)
+    (span.lo.0 as usize - 1)..(span.hi.0 as usize - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_imports() {
+        let source = r#"
+            import { a } from "./a.js";
+            export * from '../b';
+            export { c } from "./c";
+            const d = () => import("./d.js");
+            import "velocity-runtime";
+        "#;
+
+        let imports = find_imports(source, "test.js").unwrap();
+        let specifiers: Vec<&str> = imports.iter().map(|import| import.specifier.as_str()).collect();
+        let dynamic: Vec<&str> = imports
+            .iter()
+            .filter(|import| import.dynamic)
+            .map(|import| import.specifier.as_str())
+            .collect();
+
+        assert_eq!(specifiers, vec!["./a.js", "../b", "./c", "./d.js", "velocity-runtime"]);
+        assert_eq!(dynamic, vec!["./d.js"]);
+    }
+
+    #[test]
+    fn test_rewrite_imports_keeps_quotes() {
+        let source = "import a from './a.js';\nimport b from \"b\";\nconst c = import(\"./c.js\");\n";
+
+        let rewritten = rewrite_imports(source, "test.js", |specifier| {
+            specifier.strip_suffix(".js").map(|stem| format!("{}.1234.js", stem))
+        })
+        .unwrap();
+
+        assert_eq!(
+            rewritten,
+            "import a from './a.1234.js';\nimport b from \"b\";\nconst c = import(\"./c.1234.js\");\n"
+        );
+    }
+}
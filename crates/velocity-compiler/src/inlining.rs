@@ -0,0 +1,511 @@
+//! Module Inlining
+//!
+//! A module of a few lines costs more as its own file (a request, a module
+//! record, an entry in the browser's module map) than as code in the module
+//! importing it. The build can move such a module into its importers,
+//! replacing each import of it with the module's code wrapped in a function:
+//! ```js
+//! import { clamp as c } from "./math.js";
+//! // becomes
+//! const { "clamp": c } = (() => { function clamp(n, lo, hi) { ... } return { "clamp": clamp }; })();
+//! ```
+//! The function keeps the module's own names out of the importer's scope.
+//!
+//! This module has the per-file parts: [`inline_module`] checks that a
+//! compiled module can be moved and returns its code, [`static_imports`]
+//! lists the imports that would be replaced and [`replacement`] writes the
+//! code that replaces them. Which modules to inline is up to the build.
+//!
+//! ## Safety
+//! A module can be inlined when:
+//! - Loading it has no side effects (see [`analyzer::has_side_effects`]),
+//!   so running it where it's imported instead of once up front doesn't
+//!   change anything.
+//! - It imports nothing, so it can't be part of an import cycle and has no
+//!   imports of its own to move.
+//! - It exports only `const`, function and class declarations, local names
+//!   and a default: an exported `let` is a live binding, which the copy the
+//!   importer gets would stop following.
+//! - It doesn't use top-level `await` or `import.meta`, which mean something
+//!   else inside the wrapper function.
+//! - The globals it uses aren't names the importer declares at the top
+//!   level, which would shadow them once it's moved there
+//!   ([`replacement`] checks this for each importer).
+//! - Its code fits on one line with whitespace stripped, so that putting it
+//!   in place of a one-line import leaves the importer's source map valid.
+//!
+//! Each importer gets its own copy, so a module imported by several modules
+//! is only safe to inline if the copies can't be told apart:
+//! [`InlineModule::constant`] says whether every export is a primitive
+//! constant.
+
+use crate::analyzer;
+use crate::codegen;
+use crate::error::Result;
+use crate::parser;
+use crate::{CompilerOptions, MinifyLevel};
+use std::collections::{BTreeSet, HashSet};
+use std::ops::Range;
+use swc_core::common::{Globals, Mark, GLOBALS};
+use swc_core::ecma::ast::*;
+use swc_core::ecma::transforms::base::resolver;
+use swc_core::ecma::visit::{Visit, VisitMutWith, VisitWith};
+
+/// Local name given to an anonymous default export
+const DEFAULT_LOCAL: &str = "$default";
+
+/// A compiled module that can be moved into its importers
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineModule {
+    /// The module's statements without `export`, on one line
+    pub body: String,
+    /// Exported name → the local binding holding it
+    pub exports: Vec<(String, String)>,
+    /// Whether every export is a `const` holding a primitive literal, so
+    /// that each importer getting its own copy can't be observed
+    pub constant: bool,
+    /// Names the module uses without declaring them (globals), sorted
+    pub free_names: Vec<String>,
+}
+
+/// An `import` or `export ... from` declaration
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticImport {
+    /// The specifier as written, without quotes
+    pub specifier: String,
+    /// Byte range of the whole declaration in the source
+    pub range: Range<usize>,
+    /// Imported name → local name, or `None` when the declaration can't be
+    /// replaced by local bindings (`import * as ns`, `export ... from`)
+    pub bindings: Option<Vec<(String, String)>>,
+}
+
+/// `code` (a compiled module) ready to be inlined, or `None` when it isn't
+/// safe to inline
+pub fn inline_module(code: &str, filename: &str) -> Result<Option<InlineModule>> {
+    let module = parser::parse(code, filename)?;
+    if analyzer::has_side_effects(&module, code) {
+        return Ok(None);
+    }
+    let free_names = resolved_names(&module, Scope::Unresolved).into_iter().collect();
+
+    let mut checker = ScopeChecker::default();
+    module.visit_with(&mut checker);
+    if checker.uses_module_scope {
+        return Ok(None);
+    }
+
+    // `export { count }` of a `let` is as live as `export let count`
+    let mut mutable = MutableNames::default();
+    for item in &module.body {
+        if let ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) = item {
+            if var.kind != VarDeclKind::Const {
+                var.visit_with(&mut mutable);
+            }
+        }
+    }
+
+    let span = module.span;
+    let mut body = Vec::new();
+    let mut exports = Vec::new();
+    let mut constant = true;
+    for item in module.body {
+        let decl = match item {
+            ModuleItem::Stmt(stmt) => {
+                body.push(stmt);
+                continue;
+            }
+            ModuleItem::ModuleDecl(decl) => decl,
+        };
+        match decl {
+            ModuleDecl::ExportDecl(export) => {
+                match &export.decl {
+                    Decl::Var(var) if var.kind == VarDeclKind::Const => {
+                        for declarator in &var.decls {
+                            let Pat::Ident(name) = &declarator.name else {
+                                return Ok(None);
+                            };
+                            constant &= declarator.init.as_deref().is_some_and(is_primitive);
+                            exports.push((name.id.sym.to_string(), name.id.sym.to_string()));
+                        }
+                    }
+                    Decl::Fn(function) => {
+                        constant = false;
+                        exports.push((function.ident.sym.to_string(), function.ident.sym.to_string()));
+                    }
+                    Decl::Class(class) => {
+                        constant = false;
+                        exports.push((class.ident.sym.to_string(), class.ident.sym.to_string()));
+                    }
+                    _ => return Ok(None),
+                }
+                body.push(Stmt::Decl(export.decl));
+            }
+            ModuleDecl::ExportNamed(NamedExport { src: None, specifiers, .. }) => {
+                for specifier in specifiers {
+                    let ExportSpecifier::Named(named) = specifier else {
+                        return Ok(None);
+                    };
+                    let ModuleExportName::Ident(local) = &named.orig else {
+                        return Ok(None);
+                    };
+                    if mutable.0.iter().any(|name| *name == *local.sym) {
+                        return Ok(None);
+                    }
+                    let exported = named.exported.as_ref().map(export_name).unwrap_or(local.sym.to_string());
+                    // Whether the local is a constant isn't tracked
+                    constant = false;
+                    exports.push((exported, local.sym.to_string()));
+                }
+            }
+            ModuleDecl::ExportDefaultDecl(export) => {
+                constant = false;
+                let decl = match export.decl {
+                    DefaultDecl::Fn(FnExpr { ident, function }) => {
+                        let ident = ident.unwrap_or_else(|| Ident::new_no_ctxt(DEFAULT_LOCAL.into(), Default::default()));
+                        exports.push(("default".to_string(), ident.sym.to_string()));
+                        Decl::Fn(FnDecl {
+                            ident,
+                            declare: false,
+                            function,
+                        })
+                    }
+                    DefaultDecl::Class(ClassExpr { ident, class }) => {
+                        let ident = ident.unwrap_or_else(|| Ident::new_no_ctxt(DEFAULT_LOCAL.into(), Default::default()));
+                        exports.push(("default".to_string(), ident.sym.to_string()));
+                        Decl::Class(ClassDecl {
+                            ident,
+                            declare: false,
+                            class,
+                        })
+                    }
+                    DefaultDecl::TsInterfaceDecl(_) => return Ok(None),
+                };
+                body.push(Stmt::Decl(decl));
+            }
+            ModuleDecl::ExportDefaultExpr(export) => {
+                constant &= is_primitive(&export.expr);
+                exports.push(("default".to_string(), DEFAULT_LOCAL.to_string()));
+                body.push(Stmt::Decl(Decl::Var(Box::new(VarDecl {
+                    kind: VarDeclKind::Const,
+                    decls: vec![VarDeclarator {
+                        span: Default::default(),
+                        name: Pat::Ident(Ident::new_no_ctxt(DEFAULT_LOCAL.into(), Default::default()).into()),
+                        init: Some(export.expr),
+                        definite: false,
+                    }],
+                    ..Default::default()
+                }))));
+            }
+            // Imports, re-exports and TypeScript-only declarations
+            _ => return Ok(None),
+        }
+    }
+
+    let options = CompilerOptions {
+        minify: MinifyLevel::Whitespace,
+        source_maps: false,
+        ..Default::default()
+    };
+    let module = Module {
+        span,
+        body: body.into_iter().map(ModuleItem::Stmt).collect(),
+        shebang: None,
+    };
+    let body = codegen::generate(&module, &options)?.trim_end().to_string();
+    if body.contains('\n') {
+        return Ok(None);
+    }
+
+    Ok(Some(InlineModule {
+        body,
+        exports,
+        constant,
+        free_names,
+    }))
+}
+
+/// Names `code` declares at the top level, imports included. Pass them to
+/// [`replacement`] for each importer.
+pub fn top_level_bindings(code: &str, filename: &str) -> Result<HashSet<String>> {
+    let module = parser::parse(code, filename)?;
+    Ok(resolved_names(&module, Scope::TopLevel).into_iter().collect())
+}
+
+/// Which identifiers [`resolved_names`] returns
+#[derive(Clone, Copy, PartialEq)]
+enum Scope {
+    /// Bound at the top level of the module
+    TopLevel,
+    /// Not bound anywhere in the module
+    Unresolved,
+}
+
+/// Names of the identifiers in `module` that resolve to `scope`
+fn resolved_names(module: &Module, scope: Scope) -> BTreeSet<String> {
+    struct Finder {
+        mark: Mark,
+        names: BTreeSet<String>,
+    }
+    impl Visit for Finder {
+        fn visit_ident(&mut self, ident: &Ident) {
+            if ident.ctxt.outer() == self.mark {
+                self.names.insert(ident.sym.to_string());
+            }
+        }
+    }
+
+    let mut module = module.clone();
+    GLOBALS.set(&Globals::new(), || {
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        module.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+        let mut finder = Finder {
+            mark: match scope {
+                Scope::TopLevel => top_level_mark,
+                Scope::Unresolved => unresolved_mark,
+            },
+            names: BTreeSet::new(),
+        };
+        module.visit_with(&mut finder);
+        finder.names
+    })
+}
+
+/// Every `import` and `export ... from` declaration in `code`, in source
+/// order. Dynamic `import()` isn't included.
+pub fn static_imports(code: &str, filename: &str) -> Result<Vec<StaticImport>> {
+    let module = parser::parse(code, filename)?;
+    let imports = module
+        .body
+        .iter()
+        .filter_map(|item| {
+            let ModuleItem::ModuleDecl(decl) = item else {
+                return None;
+            };
+            let (src, span, bindings) = match decl {
+                ModuleDecl::Import(import) if !import.type_only => {
+                    let bindings = import
+                        .specifiers
+                        .iter()
+                        .map(|specifier| match specifier {
+                            ImportSpecifier::Named(named) => Some((
+                                named.imported.as_ref().map(export_name).unwrap_or(named.local.sym.to_string()),
+                                named.local.sym.to_string(),
+                            )),
+                            ImportSpecifier::Default(default) => {
+                                Some(("default".to_string(), default.local.sym.to_string()))
+                            }
+                            ImportSpecifier::Namespace(_) => None,
+                        })
+                        .collect();
+                    (&import.src, import.span, bindings)
+                }
+                ModuleDecl::ExportAll(export) => (&export.src, export.span, None),
+                ModuleDecl::ExportNamed(NamedExport { src: Some(src), span, .. }) => (src, *span, None),
+                _ => return None,
+            };
+            Some(StaticImport {
+                specifier: src.value.to_string(),
+                // The parser's source map starts the file at BytePos(1)
+                range: (span.lo.0 as usize - 1)..(span.hi.0 as usize - 1),
+                bindings,
+            })
+        })
+        .collect();
+    Ok(imports)
+}
+
+/// The statement replacing an importer's imports of `module`: `bindings`
+/// (imported name → local name) assigned from a copy of the module. Empty
+/// when nothing is imported by name, since the module has no side effects
+/// to run. `None` when a binding names something the module doesn't export,
+/// or when one of the importer's `top_level` bindings (see
+/// [`top_level_bindings`]) would shadow a global the module uses.
+pub fn replacement(
+    module: &InlineModule,
+    bindings: &[(String, String)],
+    top_level: &HashSet<String>,
+) -> Option<String> {
+    if bindings.is_empty() {
+        return Some(String::new());
+    }
+    if module.free_names.iter().any(|name| top_level.contains(name)) {
+        return None;
+    }
+
+    let mut pattern = Vec::new();
+    let mut returned = Vec::new();
+    for (imported, local) in bindings {
+        let (_, binding) = module.exports.iter().find(|(name, _)| name == imported)?;
+        let key = serde_json::to_string(imported).ok()?;
+        pattern.push(format!("{}: {}", key, local));
+        let entry = format!("{}: {}", key, binding);
+        if !returned.contains(&entry) {
+            returned.push(entry);
+        }
+    }
+    Some(format!(
+        "const {{ {} }} = (() => {{ {} return {{ {} }}; }})();",
+        pattern.join(", "),
+        module.body,
+        returned.join(", ")
+    ))
+}
+
+fn export_name(name: &ModuleExportName) -> String {
+    match name {
+        ModuleExportName::Ident(ident) => ident.sym.to_string(),
+        ModuleExportName::Str(name) => name.value.to_string(),
+    }
+}
+
+/// Whether `expr` is a primitive literal, which has no identity
+fn is_primitive(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(lit) => !matches!(lit, Lit::Regex(_)),
+        Expr::Tpl(tpl) => tpl.exprs.is_empty(),
+        Expr::Unary(UnaryExpr { op: UnaryOp::Minus, arg, .. }) => matches!(&**arg, Expr::Lit(Lit::Num(_))),
+        _ => false,
+    }
+}
+
+/// Names bound by the visited declarations
+#[derive(Default)]
+struct MutableNames(Vec<String>);
+
+impl Visit for MutableNames {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.0.push(ident.id.sym.to_string());
+    }
+
+    // Only the names being declared, not those read by initializers
+    fn visit_expr(&mut self, _: &Expr) {}
+}
+
+/// Finds top-level `await` and any `import.meta`, which mean something
+/// else once the code is moved into a function in another module
+#[derive(Default)]
+struct ScopeChecker {
+    uses_module_scope: bool,
+    in_function: bool,
+}
+
+impl ScopeChecker {
+    fn visit_function_body(&mut self, visit: impl FnOnce(&mut Self)) {
+        let in_function = std::mem::replace(&mut self.in_function, true);
+        visit(self);
+        self.in_function = in_function;
+    }
+}
+
+impl Visit for ScopeChecker {
+    fn visit_await_expr(&mut self, expr: &AwaitExpr) {
+        self.uses_module_scope |= !self.in_function;
+        expr.visit_children_with(self);
+    }
+
+    fn visit_for_of_stmt(&mut self, stmt: &ForOfStmt) {
+        self.uses_module_scope |= stmt.is_await && !self.in_function;
+        stmt.visit_children_with(self);
+    }
+
+    fn visit_meta_prop_expr(&mut self, _: &MetaPropExpr) {
+        self.uses_module_scope = true;
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        self.visit_function_body(|checker| function.visit_children_with(checker));
+    }
+
+    fn visit_arrow_expr(&mut self, arrow: &ArrowExpr) {
+        self.visit_function_body(|checker| arrow.visit_children_with(checker));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_module_strips_exports() {
+        let module = inline_module(
+            "const scale = 2;\nexport function double(n) { return n * scale; }\nexport const NAME = \"math\";\nexport { scale as factor };\nexport default 42;\n",
+            "math.js",
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(!module.constant);
+        assert!(!module.body.contains("export"), "{}", module.body);
+        assert_eq!(
+            module.exports,
+            vec![
+                ("double".to_string(), "double".to_string()),
+                ("NAME".to_string(), "NAME".to_string()),
+                ("factor".to_string(), "scale".to_string()),
+                ("default".to_string(), DEFAULT_LOCAL.to_string()),
+            ]
+        );
+
+        let bindings = [("double".to_string(), "dbl".to_string()), ("default".to_string(), "answer".to_string())];
+        let code = replacement(&module, &bindings, &HashSet::new()).unwrap();
+        assert!(code.starts_with(r#"const { "double": dbl, "default": answer } = (() => { "#), "{}", code);
+        assert!(code.ends_with(r#" return { "double": double, "default": $default }; })();"#), "{}", code);
+        assert_eq!(replacement(&module, &[("missing".to_string(), "m".to_string())], &HashSet::new()), None);
+    }
+
+    #[test]
+    fn test_replacement_skips_importers_shadowing_globals() {
+        let module = inline_module("export function clamp(n) { return Math.min(n, max); }
+", "math.js")
+            .unwrap()
+            .unwrap();
+        assert_eq!(module.free_names, vec!["Math".to_string(), "max".to_string()]);
+
+        let importer = "import { clamp } from './math.js';
+const Math = { min: () => 0 };
+function max() {}
+";
+        let top_level = top_level_bindings(importer, "app.js").unwrap();
+        assert!(top_level.contains("clamp") && top_level.contains("Math") && top_level.contains("max"));
+
+        let bindings = [("clamp".to_string(), "clamp".to_string())];
+        assert_eq!(replacement(&module, &bindings, &top_level), None);
+        let unrelated = top_level_bindings("import { clamp } from './math.js';
+function run(Math) {}
+", "app.js").unwrap();
+        assert!(replacement(&module, &bindings, &unrelated).is_some());
+    }
+
+    #[test]
+    fn test_inline_module_rejects_unsafe_modules() {
+        let inline = |code: &str| inline_module(code, "m.js").unwrap();
+
+        assert!(inline(r#"export const A = 1, B = "b", C = -1;"#).unwrap().constant);
+        assert!(inline("export let count = 0;").is_none());
+        assert!(inline("let count = 0; export { count };").is_none());
+        assert!(inline("import { x } from './x.js'; export const y = x;").is_none());
+        assert!(inline("export * from './x.js';").is_none());
+        assert!(inline("console.log('loaded'); export const a = 1;").is_none());
+        assert!(inline("export const data = await fetch('/data');").is_none());
+        assert!(inline("export const url = () => import.meta.url;").is_none());
+        assert!(inline("export const load = async () => await fetch('/data');").is_some());
+    }
+
+    #[test]
+    fn test_static_imports() {
+        let code = "import a, { b as c } from './m.js';\nimport * as ns from './n.js';\nexport { d } from './d.js';\nconst lazy = import('./lazy.js');\n";
+        let imports = static_imports(code, "app.js").unwrap();
+
+        assert_eq!(imports.len(), 3);
+        assert_eq!(&code[imports[0].range.clone()], "import a, { b as c } from './m.js';");
+        assert_eq!(
+            imports[0].bindings,
+            Some(vec![("default".to_string(), "a".to_string()), ("b".to_string(), "c".to_string())])
+        );
+        assert_eq!(imports[1].bindings, None);
+        assert_eq!(imports[2].specifier, "./d.js");
+        assert_eq!(imports[2].bindings, None);
+    }
+}
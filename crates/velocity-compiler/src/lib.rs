@@ -7,17 +7,82 @@
 //! - Static analysis of reactivity graphs
 //! - Transform JSX → Direct DOM operations
 //! - Optimization passes (dead code elimination, effect pruning, template cloning)
+//! - Component-scoped CSS from `css` templates and CSS modules
+//! - Downleveling of modern syntax for older targets
 //! - Generate minimal, optimized JavaScript
+//!
+//! ## Using the stages directly
+//! [`Compiler::compile`] goes from source text to JavaScript. Tools that
+//! already hold an AST, or chain several transforms, can skip re-parsing:
+//! [`Compiler::compile_module`] runs everything between parsing and code
+//! generation on a borrowed [`ast::Module`], or the stages can be called one
+//! by one, in this order:
+//!
+//! 1. [`parser::parse`]: source text → `Module`
+//! 2. [`transformer::strip_typescript`]: must run before anything below,
+//!    and after [`prop_checks::insert_prop_checks`], which reads the types
+//! 3. [`css::scope_styles`]: optional, only needed for `css` templates;
+//!    likewise [`i18n::extract_messages`] for translatable messages and
+//!    [`environment::check_environment`] for globals the target lacks
+//! 4. [`analyzer::analyze`]: reactivity analysis of the stripped module,
+//!    after [`analyzer::resolve_bindings`] so that shadowed names are told
+//!    apart
+//! 5. [`transformer::transform`]: JSX → runtime calls, given the analysis
+//!    of the same module; skipped with [`JsxMode::Preserve`].
+//!    [`transformer::transform_with_helpers`] also returns the runtime
+//!    helpers the generated code calls. Then [`transformer::label_effects`]
+//!    and [`hmr::key_signals`] for development builds
+//! 6. [`optimizer::optimize`]: expects transformed output and the same analysis;
+//!    then [`transformer::instrument_components`] for profiling builds
+//! 7. [`runtime_imports::apply_import_style`]: any module, then
+//!    [`runtime_imports::use_worker_runtime`] for worker code
+//! 8. [`downlevel::downlevel`]: any module, for the configured `target`
+//! 9. [`codegen::generate`]: any module → JavaScript
+//!
+//! [`assets::find_assets`] lists the images, stylesheets and other files a
+//! module references; [`Compiler::compile_full`] returns them with the code,
+//! CSS and diagnostics. [`Compiler::dependency_graph`] maps a module's
+//! signals, memos and effects and what reads them, without compiling it.
+//!
+//! Every stage takes the module by value or `&mut` and hands it on, so none
+//! of them reads source text except the parser.
 
 pub mod parser;
 pub mod analyzer;
 pub mod transformer;
 pub mod optimizer;
 pub mod codegen;
+pub mod downlevel;
+pub mod runtime_imports;
+pub mod pass;
+pub mod imports;
+pub mod inlining;
+pub mod css;
+pub mod i18n;
+pub mod environment;
+pub mod prop_checks;
+pub mod hmr;
+pub mod assets;
+pub mod diagnostics;
 pub mod error;
 
-pub use error::{CompilerError, Result};
-pub use codegen::GenerateResult;
+pub use error::{CompilerError, ErrorCategory, Result};
+pub use codegen::{CodeStyle, GenerateResult, Indent};
+pub use assets::AssetRef;
+pub use i18n::{I18nOptions, Message};
+pub use environment::Environment;
+pub use diagnostics::{Diagnostic, Severity};
+pub use pass::CompilerPass;
+pub use runtime_imports::{RuntimeImportMap, RuntimeImportStyle};
+pub use analyzer::{A11yRules, GraphEdge, GraphNode, NodeKind, ReactiveFunctions, ReactiveGraph};
+/// Re-exported so passes can build AST nodes with the compiler's SWC version
+pub use swc_core::ecma::ast;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use swc_core::common::util::take::Take;
+use swc_core::common::{BytePos, Span};
+use swc_core::ecma::ast::Module;
 
 /// Compiler configuration
 #[derive(Debug, Clone)]
@@ -26,10 +91,99 @@ pub struct CompilerOptions {
     pub optimize: bool,
     /// Whether to generate source maps
     pub source_maps: bool,
-    /// Target environment (e.g., "es2015", "es2020")
+    /// ECMAScript version to emit (e.g., "es2015", "es2020")
     pub target: String,
-    /// Whether to minify output
-    pub minify: bool,
+    /// How much to shrink the output
+    pub minify: MinifyLevel,
+    /// Whether to label effects and memos with their component and line
+    /// (`Counter:12`) for DevTools and runtime error messages
+    pub effect_labels: bool,
+    /// Whether to wrap every component in `performance` marks and a measure
+    /// (see [`transformer::instrument_components`]), so its renders can be
+    /// timed in DevTools
+    pub profile_components: bool,
+    /// Whether to report warnings for code that is valid but likely a
+    /// mistake, such as unused imports or HTML the browser would repair
+    /// (duplicate attributes, `<td>` outside a row)
+    pub strict: bool,
+    /// Which accessibility checks strict mode runs on JSX
+    pub a11y: A11yRules,
+    /// Functions treated as reactive primitives besides the built-in ones,
+    /// for user-land reactive libraries
+    pub reactive_functions: ReactiveFunctions,
+    /// Custom passes run after the optimizer, before downleveling and codegen
+    /// (see [`pass`] for ordering). Shared so options stay cheap to clone.
+    pub passes: Vec<Arc<dyn CompilerPass>>,
+    /// Module system of the emitted code
+    pub module_format: ModuleFormat,
+    /// Indentation, escaping and semicolons of readable output
+    pub code_style: CodeStyle,
+    /// Whether runtime helpers are imported from the `velocity-runtime`
+    /// barrel or from its submodules
+    pub runtime_import_style: RuntimeImportStyle,
+    /// Whether JSX is lowered to runtime calls or left for another tool
+    pub jsx: JsxMode,
+    /// Markers of translatable messages to extract, if any
+    pub i18n: Option<I18nOptions>,
+    /// Whether components warn at runtime about props that don't match their
+    /// TypeScript props type. For development builds; see [`prop_checks`].
+    pub prop_checks: bool,
+    /// Where the code runs, which decides the globals it may use and the
+    /// runtime entry point it imports (see [`environment`])
+    pub environment: Environment,
+    /// Whether `createSignal` calls get a stable key, so signal values
+    /// survive hot updates. For the dev server; see [`hmr`].
+    pub hmr_signal_keys: bool,
+}
+
+/// What the compiler does with JSX, like tsc's `jsx` option
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsxMode {
+    /// Lower JSX to Velocity runtime calls
+    #[default]
+    Transform,
+    /// Emit JSX as written, for a later tool to transform. Types are still
+    /// stripped, the code around the JSX is still optimized and downleveled,
+    /// and the analyzer still reports diagnostics.
+    Preserve,
+}
+
+/// How much the emitted code is shrunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MinifyLevel {
+    /// Readable output
+    #[default]
+    None,
+    /// Strip whitespace and comments only: fast, and names stay as written
+    Whitespace,
+    /// Run SWC's compressor (dead code, constant folding, inlining) and
+    /// mangle local names, then strip whitespace
+    Full,
+}
+
+impl MinifyLevel {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "none" => Ok(Self::None),
+            "whitespace" => Ok(Self::Whitespace),
+            "full" => Ok(Self::Full),
+            other => Err(CompilerError::InvalidConfig(format!(
+                "Unknown minify level '{}' (expected none, whitespace or full)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Module system the compiler emits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModuleFormat {
+    /// `import` / `export`, for browsers and bundlers
+    #[default]
+    Esm,
+    /// `require()` / `module.exports`, for SSR on Node versions or tooling
+    /// that can't load ES modules
+    Cjs,
 }
 
 impl Default for CompilerOptions {
@@ -38,11 +192,68 @@ impl Default for CompilerOptions {
             optimize: true,
             source_maps: true,
             target: "es2020".to_string(),
-            minify: false,
+            minify: MinifyLevel::None,
+            effect_labels: true,
+            profile_components: false,
+            strict: false,
+            a11y: A11yRules::default(),
+            reactive_functions: ReactiveFunctions::default(),
+            passes: Vec::new(),
+            module_format: ModuleFormat::Esm,
+            code_style: CodeStyle::default(),
+            runtime_import_style: RuntimeImportStyle::Barrel,
+            jsx: JsxMode::Transform,
+            i18n: None,
+            prop_checks: false,
+            environment: Environment::Browser,
+            hmr_signal_keys: false,
         }
     }
 }
 
+/// What [`Compiler::compile_module`] learned about a module besides its
+/// lowered AST
+#[derive(Debug, Clone)]
+pub struct ModuleOutput {
+    /// Reactivity analysis of the module before the JSX transform, with
+    /// warnings silenced by `velocity-ignore` comments removed
+    pub analysis: analyzer::Analysis,
+    /// Scoped CSS extracted from `css` templates
+    pub css: Option<String>,
+    /// Non-module files the module references, such as images and stylesheets
+    pub assets: Vec<AssetRef>,
+    /// Messages marked for translation, when [`CompilerOptions::i18n`] is set
+    pub messages: Vec<Message>,
+    /// Runtime functions the compiler's generated code calls
+    /// (`createElement`, `createRenderEffect`, `mark`); calls written in the
+    /// source aren't included
+    pub used_runtime_helpers: HashSet<String>,
+}
+
+/// Everything [`Compiler::compile_full`] produces for one file
+#[derive(Debug, Clone)]
+pub struct CompileOutput {
+    /// The compiled JavaScript
+    pub code: String,
+    /// Source map JSON, when [`CompilerOptions::source_maps`] is on
+    pub source_map: Option<String>,
+    /// Scoped CSS extracted from `css` templates, to ship alongside `code`
+    pub css: Option<String>,
+    /// Images, stylesheets and other files `code` expects next to it
+    pub assets: Vec<AssetRef>,
+    /// Warnings that weren't silenced by a `velocity-ignore` comment
+    pub diagnostics: Vec<Diagnostic>,
+    /// Whether loading `code` does more than define its exports; a bundler
+    /// may drop the module when this is `false` and no export is used
+    pub has_side_effects: bool,
+    /// Messages marked for translation, for the build to collect into a
+    /// [`i18n::Catalog`]
+    pub messages: Vec<Message>,
+    /// Runtime functions the generated code calls, so a bundler can include
+    /// just those; see [`ModuleOutput::used_runtime_helpers`]
+    pub used_runtime_helpers: HashSet<String>,
+}
+
 /// Main compiler struct
 pub struct Compiler {
     options: CompilerOptions,
@@ -63,54 +274,158 @@ impl Compiler {
 
     /// Compile a single file from source code
     pub fn compile(&self, source: &str, filename: &str) -> Result<String> {
-        // 1. Parse JSX/TSX → AST
         let mut module = parser::parse(source, filename)?;
+        self.compile_module(&mut module, source, filename)?;
+        codegen::generate(&module, &self.options)
+    }
 
-        // 2. Strip TypeScript types (must be done before analysis)
-        module = transformer::strip_typescript(module)?;
+    /// Compile with source map generation
+    pub fn compile_with_source_map(&self, source: &str, filename: &str) -> Result<GenerateResult> {
+        let output = self.compile_full(source, filename)?;
+        Ok(GenerateResult {
+            code: output.code,
+            source_map: output.source_map,
+            diagnostics: output.diagnostics,
+            css: output.css,
+        })
+    }
 
-        // 3. Analyze reactivity
-        let analysis = analyzer::analyze(&module)?;
+    /// Compile a file, returning its code together with the CSS, asset
+    /// references and diagnostics a build needs to collect from it
+    pub fn compile_full(&self, source: &str, filename: &str) -> Result<CompileOutput> {
+        let mut module = parser::parse(source, filename)?;
+        let output = self.compile_module(&mut module, source, filename)?;
+        let result = codegen::generate_with_source_map(&module, &self.options, Some(filename))?;
 
-        // 4. Transform JSX → DOM operations
-        let transformed = transformer::transform(module, &analysis)?;
+        Ok(CompileOutput {
+            code: result.code,
+            source_map: result.source_map,
+            css: output.css,
+            assets: output.assets,
+            has_side_effects: output.analysis.has_side_effects,
+            diagnostics: output.analysis.diagnostics,
+            messages: output.messages,
+            used_runtime_helpers: output.used_runtime_helpers,
+        })
+    }
 
-        // 5. Optimize (if enabled)
-        let optimized = if self.options.optimize {
-            optimizer::optimize(transformed, &analysis)?
+    /// Run every stage between parsing and code generation on an already
+    /// parsed module, replacing it with the lowered output.
+    ///
+    /// `module` can come from [`parser::parse`] or another tool sharing the
+    /// AST; `source` must be the text its spans point into, which effect
+    /// labels, diagnostic suppression comments and some strict checks read.
+    /// Emit the result with [`codegen::generate`], or keep transforming it.
+    pub fn compile_module(&self, module: &mut Module, source: &str, filename: &str) -> Result<ModuleOutput> {
+        // Nothing but whitespace, comments, a BOM or a shebang: almost always
+        // a file saved before it was written, not a module meant to be empty
+        let empty = module.body.is_empty().then(|| {
+            format!("{} has no statements, so it compiles to an empty module", filename)
+        });
+        if let Some(message) = &empty {
+            if self.options.strict {
+                return Err(CompilerError::AnalysisError(message.clone()));
+            }
+        }
+
+        // 1. Strip TypeScript types (must be done before analysis), once dev
+        // prop checks have read the props types
+        let check_helpers = if self.options.prop_checks {
+            prop_checks::insert_prop_checks(module)
         } else {
-            transformed
+            &[]
         };
+        *module = transformer::strip_typescript(module.take())?;
+        let assets = assets::find_assets(module);
 
-        // 6. Generate JavaScript code
-        let code = codegen::generate(&optimized, &self.options)?;
-
-        Ok(code)
-    }
-
-    /// Compile with source map generation
-    pub fn compile_with_source_map(&self, source: &str, filename: &str) -> Result<GenerateResult> {
-        // 1. Parse JSX/TSX → AST
-        let mut module = parser::parse(source, filename)?;
-
-        // 2. Strip TypeScript types
-        module = transformer::strip_typescript(module)?;
+        // 2. Scope `css` templates and extract translatable messages
+        let styles = css::scope_styles(module, filename)?;
+        let extraction = match &self.options.i18n {
+            Some(i18n) => i18n::extract_messages(module, i18n),
+            None => i18n::Extraction::default(),
+        };
 
-        // 3. Analyze reactivity
-        let analysis = analyzer::analyze(&module)?;
+        // 3. Analyze reactivity, per binding rather than per name
+        analyzer::resolve_bindings(module);
+        let has_jsx = analyzer::contains_jsx(module);
+        let mut analysis = analyzer::analyze_with(module, &self.options.reactive_functions)?;
+        analysis.has_side_effects = analyzer::has_side_effects(module, source);
+        if let Some(message) = empty {
+            let start = Span::new(BytePos(1), BytePos(1));
+            analysis.diagnostics.push(Diagnostic::warning("empty-module", message, start));
+        }
+        analysis.diagnostics.extend(extraction.diagnostics);
+        analysis.diagnostics.extend(environment::check_environment(module, self.options.environment));
+        if self.options.strict {
+            analysis.diagnostics.extend(analyzer::check_unused_imports(module));
+            analysis.diagnostics.extend(analyzer::check_resource_keys(module, &analysis, source));
+            if has_jsx {
+                analysis.diagnostics.extend(analyzer::check_jsx_structure(module));
+                analysis.diagnostics.extend(analyzer::check_accessibility(module, &self.options.a11y));
+            }
+        }
 
-        // 4. Transform JSX → DOM operations
-        let transformed = transformer::transform(module, &analysis)?;
+        // 4. Transform JSX → DOM operations (modules without JSX, or with
+        //    JSX preserved, pass through)
+        if has_jsx && self.options.jsx == JsxMode::Transform && self.options.environment == Environment::Worker {
+            return Err(CompilerError::InvalidJsx(format!(
+                "{} renders JSX, but it's compiled for a Web Worker, which has no DOM",
+                filename
+            )));
+        }
+        let (mut transformed, mut used_runtime_helpers) = if has_jsx && self.options.jsx == JsxMode::Transform {
+            transformer::transform_with_helpers(module.take(), &analysis)?
+        } else {
+            (module.take(), HashSet::new())
+        };
+        used_runtime_helpers.extend(check_helpers.iter().map(|name| name.to_string()));
+        if self.options.effect_labels {
+            transformer::label_effects(&mut transformed, source, filename);
+        }
+        if self.options.hmr_signal_keys {
+            hmr::key_signals(&mut transformed, filename);
+        }
 
-        // 5. Optimize (if enabled)
-        let optimized = if self.options.optimize {
+        // 5. Optimize (if enabled), then time components if asked to, so
+        //    the marks wrap the optimized bodies
+        let mut optimized = if self.options.optimize {
             optimizer::optimize(transformed, &analysis)?
         } else {
             transformed
         };
+        if self.options.profile_components {
+            let helpers = transformer::instrument_components(&mut optimized);
+            used_runtime_helpers.extend(helpers.iter().map(|name| name.to_string()));
+        }
+
+        // 6. Run custom passes
+        pass::run_passes(&self.options.passes, &mut optimized, &analysis)?;
 
-        // 6. Generate JavaScript code with source map
-        codegen::generate_with_source_map(&optimized, &self.options, Some(filename))
+        // 7. Point runtime imports at the configured modules
+        runtime_imports::apply_import_style(&mut optimized, &self.options.runtime_import_style);
+        if self.options.environment == Environment::Worker {
+            runtime_imports::use_worker_runtime(&mut optimized);
+        }
+
+        // 8. Lower syntax the target doesn't support
+        *module = downlevel::downlevel(optimized, &self.options.target)?;
+
+        analysis.diagnostics = diagnostics::remove_suppressed(analysis.diagnostics, source);
+        Ok(ModuleOutput {
+            analysis,
+            css: styles.map(|styles| styles.css),
+            assets,
+            messages: extraction.messages,
+            used_runtime_helpers,
+        })
+    }
+
+    /// The signals, memos, effects and reactive JSX elements of a file, and
+    /// which read which. Only parses and strips types; nothing is compiled.
+    pub fn dependency_graph(&self, source: &str, filename: &str) -> Result<ReactiveGraph> {
+        let module = parser::parse(source, filename)?;
+        let module = transformer::strip_typescript(module)?;
+        Ok(analyzer::dependency_graph(&module, source))
     }
 
     /// Compile a file from disk
@@ -139,4 +454,287 @@ mod tests {
         let result = compiler.compile(source, "test.tsx");
         assert!(result.is_ok(), "Compilation should succeed");
     }
+
+    #[test]
+    fn test_compile_folds_constant_jsx_conditional() {
+        let compiler = Compiler::default();
+        let source = r#"
+            function App() {
+                return <div>{true ? <b>yes</b> : <i>no</i>}</div>;
+            }
+        "#;
+
+        let code = compiler.compile(source, "test.tsx").unwrap();
+
+        assert!(code.contains(r#"createElement("b", {}, "yes")"#));
+        assert!(!code.contains(r#"createElement("i""#));
+    }
+
+    #[test]
+    fn test_compile_reports_suppressible_diagnostics() {
+        let compiler = Compiler::default();
+        let source = r#"
+            function Counter() {
+                const [count, setCount] = createSignal(0);
+                createEffect(() => setCount(count() + 1));
+                // velocity-ignore effect-loop
+                createEffect(() => setCount(count() + 1));
+                return <div>{count}</div>;
+            }
+        "#;
+
+        let result = compiler.compile_with_source_map(source, "test.tsx").unwrap();
+
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].location(source).0, 4);
+    }
+
+    #[test]
+    fn test_strict_mode_reports_unused_imports() {
+        let source = "import { a, b } from \"./ab\";\nexport const value = a;\n";
+
+        let lenient = Compiler::default().compile_with_source_map(source, "test.ts").unwrap();
+        assert!(lenient.diagnostics.is_empty());
+
+        let strict = Compiler::new(CompilerOptions {
+            strict: true,
+            ..Default::default()
+        })
+        .compile_with_source_map(source, "test.ts")
+        .unwrap();
+        assert_eq!(strict.diagnostics.len(), 1);
+        assert_eq!(strict.diagnostics[0].code, "unused-import");
+        // The optimizer strips it from the output either way
+        assert!(strict.code.contains("import { a } from \"./ab\";"));
+    }
+
+    #[test]
+    fn test_compile_module_on_parsed_ast() {
+        let source = r#"
+            const card = css`.card { padding: 4px; }`;
+            function Counter(props: { start: number }) {
+                const [count, setCount] = createSignal(props.start);
+                return <div class="card" onClick={() => setCount(count() + 1)}>{count()}</div>;
+            }
+        "#;
+        let compiler = Compiler::default();
+
+        let mut module = parser::parse(source, "Counter.tsx").unwrap();
+        let output = compiler.compile_module(&mut module, source, "Counter.tsx").unwrap();
+
+        assert!(output.analysis.signals.iter().any(|(name, _)| name == "count"));
+        assert!(output.css.unwrap().contains("padding: 4px"));
+        let code = codegen::generate(&module, &compiler.options).unwrap();
+        assert_eq!(code, compiler.compile(source, "Counter.tsx").unwrap());
+
+        // The lowered module can keep going through other tools
+        let extra = parser::parse("export const chained = true;", "extra.js").unwrap();
+        module.body.extend(extra.body);
+        let code = codegen::generate(&module, &compiler.options).unwrap();
+        assert!(code.contains("createElement(\"div\""));
+        assert!(code.ends_with("export const chained = true;\n"));
+    }
+
+    #[test]
+    fn test_compile_full() {
+        let source = r#"import logo from "./logo.svg";
+import { unused } from "./helpers";
+const badge = css`.badge { color: red; }`;
+export function Badge() {
+    return <img class="badge" src={logo} alt="New" />;
+}
+"#;
+        let compiler = Compiler::new(CompilerOptions {
+            strict: true,
+            ..Default::default()
+        });
+
+        let output = compiler.compile_full(source, "Badge.tsx").unwrap();
+
+        assert!(output.code.contains("import logo from \"./logo.svg\";"));
+        assert!(output.source_map.is_some());
+        assert!(output.css.unwrap().contains("color: red"));
+        assert_eq!(output.assets.len(), 1);
+        assert_eq!(output.assets[0].specifier, "./logo.svg");
+        assert_eq!(diagnostics::span_text(source, output.assets[0].span), "\"./logo.svg\"");
+        let codes: Vec<_> = output.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["unused-import"]);
+        assert!(!output.has_side_effects);
+        assert_eq!(output.used_runtime_helpers, HashSet::from(["createElement".to_string()]));
+
+        let entry = compiler.compile_full("import { Badge } from './Badge';\nrender(Badge, root);", "main.ts").unwrap();
+        assert!(entry.has_side_effects);
+        assert!(entry.used_runtime_helpers.is_empty());
+
+        // The string API returns the same code
+        assert_eq!(compiler.compile(source, "Badge.tsx").unwrap(), output.code);
+    }
+
+    #[test]
+    fn test_used_runtime_helpers() {
+        let source = r#"
+            import { createEffect } from "velocity-runtime";
+            export function List() {
+                const [items, setItems] = createSignal([]);
+                createEffect(() => console.log(items()));
+                return <ul>{items().map(item => <li key={item.id}>{item.text}</li>)}</ul>;
+            }
+        "#;
+        let helpers = |options: CompilerOptions| {
+            let output = Compiler::new(options).compile_full(source, "List.tsx").unwrap();
+            let mut helpers: Vec<_> = output.used_runtime_helpers.into_iter().collect();
+            helpers.sort();
+            helpers
+        };
+
+        // The source's own `createEffect` call isn't the compiler's
+        assert_eq!(helpers(CompilerOptions::default()), ["createElement", "forEach"]);
+        let profiled = CompilerOptions {
+            profile_components: true,
+            ..Default::default()
+        };
+        assert_eq!(helpers(profiled), ["createElement", "forEach", "mark", "measure"]);
+    }
+
+    #[test]
+    fn test_empty_module_diagnostic() {
+        let empty_sources = ["", "\u{feff}// TODO: write the component\n/* later */\n", "#!/usr/bin/env node\n"];
+        for source in empty_sources {
+            let result = Compiler::default().compile_with_source_map(source, "Empty.tsx").unwrap();
+            assert!(!result.code.contains('\u{feff}'));
+            let codes: Vec<_> = result.diagnostics.iter().map(|d| (d.code, d.location(source))).collect();
+            assert_eq!(codes, [("empty-module", (1, 1))], "{:?}", source);
+        }
+
+        let strict = Compiler::new(CompilerOptions {
+            strict: true,
+            ..Default::default()
+        });
+        let error = strict.compile("// nothing yet\n", "Empty.tsx").unwrap_err();
+        assert!(error.to_string().contains("Empty.tsx has no statements"));
+
+        // Type-only modules aren't empty, even if nothing is left after stripping
+        let types = Compiler::default()
+            .compile_with_source_map("export interface Props { title: string }", "types.ts")
+            .unwrap();
+        assert!(types.diagnostics.is_empty());
+    }
+
+    /// Pass that appends `export const __passes = <statement count>;`
+    struct CountStatements;
+
+    impl CompilerPass for CountStatements {
+        fn run(&self, module: &mut ast::Module, _analysis: &analyzer::Analysis) -> Result<()> {
+            let count = module.body.len();
+            let code = format!("export const __passes = {};", count);
+            let extra = parser::parse(&code, "pass.js")?;
+            module.body.extend(extra.body);
+            Ok(())
+        }
+    }
+
+    /// Pass that always fails
+    struct Reject;
+
+    impl CompilerPass for Reject {
+        fn run(&self, _module: &mut ast::Module, _analysis: &analyzer::Analysis) -> Result<()> {
+            Err(CompilerError::TransformError("rejected".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_preserve_jsx() {
+        let source = r#"
+            import { Card } from "./Card";
+            function Counter(props: { start: number }) {
+                const [count, setCount] = createSignal(props.start);
+                createEffect(() => setCount(count() + 1));
+                return <div class="counter"><Card title={`${1 + 2}`}>{count()}</Card></div>;
+            }
+        "#;
+        let compiler = Compiler::new(CompilerOptions {
+            jsx: JsxMode::Preserve,
+            ..Default::default()
+        });
+
+        let result = compiler.compile_with_source_map(source, "Counter.tsx").unwrap();
+
+        assert!(result.code.contains("<div class=\"counter\">"), "{}", result.code);
+        assert!(result.code.contains("<Card title={`${3}`}>"), "{}", result.code);
+        assert!(result.code.contains("import { Card } from \"./Card\";"), "{}", result.code);
+        assert!(!result.code.contains("createElement") && !result.code.contains(": number"), "{}", result.code);
+        let codes: Vec<_> = result.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["effect-loop"]);
+    }
+
+    #[test]
+    fn test_custom_reactive_functions() {
+        let source = r#"
+            function Counter() {
+                const [count, setCount] = createLocalStorageSignal("count", 0);
+                createEffect(() => setCount(count() + 1));
+                return <div>{count()}</div>;
+            }
+        "#;
+        let compiler = Compiler::new(CompilerOptions {
+            reactive_functions: ReactiveFunctions {
+                signals: vec!["createLocalStorageSignal".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let result = compiler.compile_with_source_map(source, "Counter.tsx").unwrap();
+
+        let codes: Vec<_> = result.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["effect-loop"]);
+    }
+
+    #[test]
+    fn test_shadowed_signal_names_are_not_reactive() {
+        let source = r#"
+            function Counter() {
+                const [count, setCount] = createSignal(0);
+                return <Display value={count} />;
+            }
+
+            function Label(count) {
+                return <Display value={count} />;
+            }
+        "#;
+
+        let code = Compiler::default().compile(source, "test.tsx").unwrap();
+
+        assert_eq!(code.matches("get value").count(), 1, "{}", code);
+        assert!(code.contains("value: count"), "{}", code);
+    }
+
+    #[test]
+    fn test_custom_passes_run_in_order_after_transform() {
+        let compiler = Compiler::new(CompilerOptions {
+            passes: vec![Arc::new(CountStatements), Arc::new(CountStatements)],
+            ..Default::default()
+        });
+        let source = "function App() { return <div>hi</div>; }";
+
+        let code = compiler
+            .compile_with_source_map(source, "test.tsx")
+            .unwrap()
+            .code;
+
+        // Passes see transformed JSX and run in order
+        assert!(code.contains("createElement(\"div\""));
+        assert!(code.contains("export const __passes = 1;"));
+        assert!(code.contains("export const __passes = 2;"));
+    }
+
+    #[test]
+    fn test_custom_pass_error_aborts_compile() {
+        let compiler = Compiler::new(CompilerOptions {
+            passes: vec![Arc::new(Reject)],
+            ..Default::default()
+        });
+
+        assert!(compiler.compile("const a = 1;", "test.ts").is_err());
+    }
 }
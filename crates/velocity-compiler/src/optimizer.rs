@@ -6,12 +6,21 @@
 //! - Template cloning (reuse element creation for static structures)
 //! - Constant folding
 //! - Unused import removal
+//! - Static props hoisting (constant `createElement` props become
+//!   module-level constants, so re-rendering doesn't rebuild them;
+//!   structurally identical props share one constant)
+//! - Trivial memo folding (`createMemo(() => count())` is replaced by
+//!   `count` itself, saving a node in the reactive graph)
 
-use crate::analyzer::Analysis;
+use crate::analyzer::{self, Analysis};
 use crate::error::Result;
+use swc_core::common::EqIgnoreSpan;
 use swc_core::ecma::ast::*;
-use swc_core::ecma::visit::{VisitMut, VisitMutWith};
-use std::collections::HashSet;
+use swc_core::ecma::visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+use std::collections::{HashMap, HashSet};
+
+/// Prefix of the module-level constants that hoisted props objects are bound to
+const HOISTED_PREFIX: &str = "_tmpl";
 
 /// Optimizer that applies optimization passes
 #[allow(dead_code)]
@@ -37,6 +46,15 @@ impl Optimizer {
     /// Optimize constant expressions
     fn optimize_expr(&mut self, expr: &mut Expr) {
         match expr {
+            // `true && a` → a, `false && a` → false, `false || a` → a, `true || a` → true
+            Expr::Bin(bin) if matches!(bin.op, BinaryOp::LogicalAnd | BinaryOp::LogicalOr) => {
+                let Some(truthy) = literal_truthiness(&bin.left) else {
+                    return;
+                };
+                let keep_left = truthy == (bin.op == BinaryOp::LogicalOr);
+                *expr = if keep_left { (*bin.left).clone() } else { (*bin.right).clone() };
+            }
+
             // Constant folding for binary expressions
             Expr::Bin(bin) => {
                 // Example: 1 + 2 → 3
@@ -74,6 +92,24 @@ impl Optimizer {
             _ => {}
         }
     }
+
+    /// Drop import specifiers that aren't in `used_identifiers`, and imports
+    /// left with none. Side-effect imports (`import "./a.css"`) are kept.
+    fn remove_unused_imports(&self, module: &mut Module) {
+        module.body.retain_mut(|item| {
+            let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item else {
+                return true;
+            };
+            if import.specifiers.is_empty() {
+                return true;
+            }
+
+            import
+                .specifiers
+                .retain(|specifier| self.used_identifiers.contains(&*analyzer::import_local(specifier).sym));
+            !import.specifiers.is_empty()
+        });
+    }
 }
 
 impl VisitMut for Optimizer {
@@ -89,24 +125,417 @@ impl VisitMut for Optimizer {
         stmts.visit_mut_children_with(self);
     }
 
-    /// Track identifier usage
+    /// Drop `createElement` children that folded to a value rendering
+    /// nothing (`{false && <A />}`), rather than passing them to the runtime
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        call.visit_mut_children_with(self);
+
+        let Callee::Expr(callee) = &call.callee else {
+            return;
+        };
+        if !matches!(&**callee, Expr::Ident(ident) if ident.sym == "createElement") || call.args.len() <= 2 {
+            return;
+        }
+        let children = call.args.split_off(2);
+        call.args
+            .extend(children.into_iter().filter(|child| child.spread.is_some() || !renders_nothing(&child.expr)));
+    }
+}
+
+/// Truthiness of a literal (`0`, `""`, `null`, `undefined` are falsy), or
+/// `None` when it isn't known at compile time
+fn literal_truthiness(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Lit(Lit::Bool(b)) => Some(b.value),
+        Expr::Lit(Lit::Num(n)) => Some(n.value != 0.0 && !n.value.is_nan()),
+        Expr::Lit(Lit::Str(s)) => Some(!s.value.is_empty()),
+        Expr::Lit(Lit::Null(_)) => Some(false),
+        Expr::Ident(ident) if ident.sym == "undefined" => Some(false),
+        Expr::Paren(paren) => literal_truthiness(&paren.expr),
+        _ => None,
+    }
+}
+
+/// Whether a child renders nothing: `true`, `false`, `null` and `undefined`
+/// (`0` and `""` still render text)
+fn renders_nothing(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(Lit::Bool(_) | Lit::Null(_)) => true,
+        Expr::Ident(ident) => ident.sym == "undefined",
+        Expr::Paren(paren) => renders_nothing(&paren.expr),
+        _ => false,
+    }
+}
+
+/// Replaces memos whose whole body is one read of a signal or memo
+/// (`const current = createMemo(() => count())`) with what they read:
+/// the declaration is dropped and `current` becomes `count` everywhere.
+///
+/// A memo is only folded when its callback takes no parameters and does
+/// nothing but call the getter with no arguments, and when neither name is
+/// bound more than once in the module (so no reference can mean another
+/// binding) or exported (which would change the module's interface).
+struct MemoFolder {
+    /// Folded memo → the getter that replaces it
+    replacements: HashMap<String, String>,
+}
+
+impl MemoFolder {
+    fn new(module: &Module, analysis: &Analysis) -> Self {
+        let mut finder = TrivialMemoFinder {
+            analysis,
+            memos: HashMap::new(),
+            bindings: HashMap::new(),
+            exported: HashSet::new(),
+        };
+        module.visit_with(&mut finder);
+
+        let TrivialMemoFinder { memos, bindings, exported, .. } = finder;
+        let unique = |name: &String| bindings.get(name) == Some(&1) && !exported.contains(name);
+        let folded: HashMap<String, String> = memos
+            .into_iter()
+            .filter(|(memo, read)| unique(memo) && unique(read))
+            .collect();
+
+        // A memo of a folded memo reads what that one read
+        let replacements = folded
+            .keys()
+            .map(|memo| {
+                let mut target = &folded[memo];
+                for _ in 0..folded.len() {
+                    match folded.get(target) {
+                        Some(next) => target = next,
+                        None => break,
+                    }
+                }
+                (memo.clone(), target.clone())
+            })
+            .collect();
+        Self { replacements }
+    }
+}
+
+impl VisitMut for MemoFolder {
+    fn visit_mut_var_decl(&mut self, var: &mut VarDecl) {
+        var.decls
+            .retain(|decl| !matches!(&decl.name, Pat::Ident(name) if self.replacements.contains_key(&*name.id.sym)));
+        var.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.visit_mut_children_with(self);
+        stmts.retain(|stmt| !matches!(stmt, Stmt::Decl(Decl::Var(var)) if var.decls.is_empty()));
+    }
+
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        items.visit_mut_children_with(self);
+        items.retain(|item| !matches!(item, ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) if var.decls.is_empty()));
+    }
+
+    /// `{ current }` keeps its key: `{ current: count }`
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        if let Prop::Shorthand(ident) = prop {
+            if let Some(target) = self.replacements.get(&*ident.sym) {
+                *prop = Prop::KeyValue(KeyValueProp {
+                    key: PropName::Ident(ident.clone().into()),
+                    value: Box::new(Expr::Ident(Ident::new_no_ctxt(target.as_str().into(), ident.span))),
+                });
+                return;
+            }
+        }
+        prop.visit_mut_children_with(self);
+    }
+
     fn visit_mut_ident(&mut self, ident: &mut Ident) {
-        self.used_identifiers.insert(ident.sym.to_string());
-        ident.visit_mut_children_with(self);
+        if let Some(target) = self.replacements.get(&*ident.sym) {
+            ident.sym = target.as_str().into();
+        }
     }
 }
 
-/// Apply optimization passes to a module
+/// Visitor behind [`MemoFolder::new`]: finds trivial memos, and counts
+/// bindings and exported names
+struct TrivialMemoFinder<'a> {
+    analysis: &'a Analysis,
+    /// Memo → the getter its body reads
+    memos: HashMap<String, String>,
+    /// How many times each name is bound
+    bindings: HashMap<String, usize>,
+    exported: HashSet<String>,
+}
+
+impl TrivialMemoFinder<'_> {
+    fn bind(&mut self, name: &str) {
+        *self.bindings.entry(name.to_string()).or_default() += 1;
+    }
+
+    /// The getter `createMemo(() => getter())` reads, if that's all it does
+    fn read_getter<'e>(&self, init: &'e Expr) -> Option<&'e str> {
+        let Expr::Call(CallExpr { callee: Callee::Expr(callee), args, .. }) = init else {
+            return None;
+        };
+        if !matches!(&**callee, Expr::Ident(ident) if ident.sym == "createMemo") {
+            return None;
+        }
+        let body = match args.first().map(|arg| (&arg.spread, &*arg.expr)) {
+            Some((None, Expr::Arrow(arrow))) if arrow.params.is_empty() && !arrow.is_async && !arrow.is_generator => {
+                match &*arrow.body {
+                    BlockStmtOrExpr::Expr(expr) => &**expr,
+                    BlockStmtOrExpr::BlockStmt(block) => single_return(block)?,
+                }
+            }
+            Some((None, Expr::Fn(function)))
+                if function.function.params.is_empty()
+                    && !function.function.is_async
+                    && !function.function.is_generator =>
+            {
+                single_return(function.function.body.as_ref()?)?
+            }
+            _ => return None,
+        };
+
+        let Expr::Call(CallExpr { callee: Callee::Expr(getter), args, .. }) = body else {
+            return None;
+        };
+        let Expr::Ident(getter) = &**getter else {
+            return None;
+        };
+        let id = getter.to_id();
+        let reactive = self.analysis.signals.contains(&id) || self.analysis.memos.contains(&id);
+        (args.is_empty() && reactive).then_some(&*getter.sym)
+    }
+}
+
+/// `expr` of a block that is exactly `{ return expr; }`
+fn single_return(block: &BlockStmt) -> Option<&Expr> {
+    match block.stmts.as_slice() {
+        [Stmt::Return(ReturnStmt { arg: Some(arg), .. })] => Some(arg),
+        _ => None,
+    }
+}
+
+impl Visit for TrivialMemoFinder<'_> {
+    fn visit_var_declarator(&mut self, decl: &VarDeclarator) {
+        if let (Pat::Ident(name), Some(init)) = (&decl.name, &decl.init) {
+            if let Some(getter) = self.read_getter(init) {
+                self.memos.insert(name.id.sym.to_string(), getter.to_string());
+            }
+        }
+        decl.visit_children_with(self);
+    }
+
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.bind(&ident.id.sym);
+    }
+
+    fn visit_fn_decl(&mut self, function: &FnDecl) {
+        self.bind(&function.ident.sym);
+        function.function.visit_with(self);
+    }
+
+    fn visit_class_decl(&mut self, class: &ClassDecl) {
+        self.bind(&class.ident.sym);
+        class.class.visit_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, function: &FnExpr) {
+        if let Some(ident) = &function.ident {
+            self.bind(&ident.sym);
+        }
+        function.function.visit_with(self);
+    }
+
+    fn visit_class_expr(&mut self, class: &ClassExpr) {
+        if let Some(ident) = &class.ident {
+            self.bind(&ident.sym);
+        }
+        class.class.visit_with(self);
+    }
+
+    fn visit_import_specifier(&mut self, specifier: &ImportSpecifier) {
+        self.bind(&analyzer::import_local(specifier).sym);
+    }
+
+    fn visit_export_decl(&mut self, export: &ExportDecl) {
+        let mut names = ExportedNames::default();
+        export.decl.visit_with(&mut names);
+        self.exported.extend(names.0);
+        export.visit_children_with(self);
+    }
+
+    fn visit_export_named_specifier(&mut self, specifier: &ExportNamedSpecifier) {
+        if let ModuleExportName::Ident(ident) = &specifier.orig {
+            self.exported.insert(ident.sym.to_string());
+        }
+    }
+
+    fn visit_export_default_expr(&mut self, export: &ExportDefaultExpr) {
+        if let Expr::Ident(ident) = &*export.expr {
+            self.exported.insert(ident.sym.to_string());
+        }
+        export.visit_children_with(self);
+    }
+}
+
+/// Variables an exported declaration binds
+#[derive(Default)]
+struct ExportedNames(HashSet<String>);
+
+impl Visit for ExportedNames {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.0.insert(ident.id.sym.to_string());
+    }
+
+    fn visit_var_declarator(&mut self, decl: &VarDeclarator) {
+        decl.name.visit_with(self);
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+}
+
+/// Moves constant props objects of `createElement` calls inside functions to
+/// module-level constants. Only props are hoisted: the runtime never mutates
+/// them, so sharing one object between renders is safe.
+struct PropsHoister {
+    /// Identifiers already used in the module, which hoisted names must avoid
+    taken: HashSet<String>,
+    next_id: usize,
+    function_depth: usize,
+    hoisted: Vec<(String, Expr)>,
+}
+
+impl PropsHoister {
+    fn new(taken: HashSet<String>) -> Self {
+        Self {
+            taken,
+            next_id: 1,
+            function_depth: 0,
+            hoisted: Vec::new(),
+        }
+    }
+
+    fn next_name(&mut self) -> String {
+        loop {
+            let name = format!("{}{}", HOISTED_PREFIX, self.next_id);
+            self.next_id += 1;
+            if !self.taken.contains(&name) {
+                return name;
+            }
+        }
+    }
+
+    /// Insert `const _tmplN = {...};` declarations after the imports
+    fn insert_declarations(self, module: &mut Module) {
+        let position = module
+            .body
+            .iter()
+            .take_while(|item| matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_))))
+            .count();
+
+        let declarations = self.hoisted.into_iter().map(|(name, value)| {
+            ModuleItem::Stmt(Stmt::Decl(Decl::Var(Box::new(VarDecl {
+                kind: VarDeclKind::Const,
+                decls: vec![VarDeclarator {
+                    span: Default::default(),
+                    name: Pat::Ident(BindingIdent {
+                        id: Ident::new_no_ctxt(name.into(), Default::default()),
+                        type_ann: None,
+                    }),
+                    init: Some(Box::new(value)),
+                    definite: false,
+                }],
+                ..Default::default()
+            }))))
+        });
+        module.body.splice(position..position, declarations);
+    }
+}
+
+impl VisitMut for PropsHoister {
+    fn visit_mut_function(&mut self, function: &mut Function) {
+        self.function_depth += 1;
+        function.visit_mut_children_with(self);
+        self.function_depth -= 1;
+    }
+
+    fn visit_mut_arrow_expr(&mut self, arrow: &mut ArrowExpr) {
+        self.function_depth += 1;
+        arrow.visit_mut_children_with(self);
+        self.function_depth -= 1;
+    }
+
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        call.visit_mut_children_with(self);
+
+        // Top-level elements are only created once; nothing to save there
+        if self.function_depth == 0 {
+            return;
+        }
+        let Callee::Expr(callee) = &call.callee else {
+            return;
+        };
+        if !matches!(&**callee, Expr::Ident(ident) if ident.sym == "createElement") {
+            return;
+        }
+        let Some(ExprOrSpread { spread: None, expr: props }) = call.args.get_mut(1) else {
+            return;
+        };
+        // `{}` is left alone to keep the output readable
+        if !matches!(&**props, Expr::Object(object) if !object.props.is_empty())
+            || !analyzer::is_constant_expr(props)
+        {
+            return;
+        }
+
+        // Elements with the same static props (`<button class="btn">` in two
+        // components) share one constant
+        let existing = self
+            .hoisted
+            .iter()
+            .find(|(_, value)| value.eq_ignore_span(props))
+            .map(|(name, _)| name.clone());
+        let name = match existing {
+            Some(name) => name,
+            None => {
+                let name = self.next_name();
+                self.hoisted.push((name.clone(), (**props).clone()));
+                name
+            }
+        };
+        **props = Expr::Ident(Ident::new_no_ctxt(name.as_str().into(), Default::default()));
+    }
+}
+
+/// Apply optimization passes to a module.
+///
+/// Expects the output of [`transform`](crate::transformer::transform), or
+/// a module without JSX, with the same analysis it was given.
 pub fn optimize(mut module: Module, analysis: &Analysis) -> Result<Module> {
+    let mut folder = MemoFolder::new(&module, analysis);
+    if !folder.replacements.is_empty() {
+        module.visit_mut_with(&mut folder);
+    }
+
     let mut optimizer = Optimizer::new(analysis.clone());
     module.visit_mut_with(&mut optimizer);
+
+    // Collect references only after folding, which can drop some of them
+    optimizer.used_identifiers = analyzer::referenced_identifiers(&module);
+    optimizer.remove_unused_imports(&mut module);
+
+    let mut hoister = PropsHoister::new(optimizer.used_identifiers);
+    module.visit_mut_with(&mut hoister);
+    hoister.insert_declarations(&mut module);
+
     Ok(module)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{analyzer, parser, transformer};
+    use crate::{analyzer, codegen, parser, transformer, CompilerOptions, MinifyLevel};
 
     #[test]
     fn test_optimize_constant_folding() {
@@ -125,6 +554,149 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_optimize_removes_unused_imports() {
+        let source = r#"
+            import "./styles.css";
+            import { a, b } from "./ab";
+            import c from "./c";
+            import { folded } from "./folded";
+            export const value = a() + (true ? 1 : folded);
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyzer::analyze(&module).unwrap();
+        let optimized = optimize(module, &analysis).unwrap();
+        let code = codegen::generate(&optimized, &CompilerOptions::default()).unwrap();
+
+        assert!(code.contains(r#"import "./styles.css";"#));
+        assert!(code.contains(r#"import { a } from "./ab";"#));
+        assert!(!code.contains("./c"));
+        assert!(!code.contains("./folded"));
+    }
+
+    #[test]
+    fn test_optimize_hoists_static_props() {
+        let source = r#"
+            function Card(props) {
+                const [count, setCount] = createSignal(0);
+                return (
+                    <div class="card" style={{ color: "red", margin: -1 }}>
+                        <span title={count()}>{count()}</span>
+                        <p>{props.text}</p>
+                    </div>
+                );
+            }
+            const header = <h1 id="title">Title</h1>;
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyzer::analyze(&module).unwrap();
+        let transformed = transformer::transform(module, &analysis).unwrap();
+        let optimized = optimize(transformed, &analysis).unwrap();
+        let code = codegen::generate(&optimized, &CompilerOptions { minify: MinifyLevel::Whitespace, ..Default::default() }).unwrap();
+
+        // The static props object is built once, at module level
+        assert!(code.contains(r#"const _tmpl1={"class":"card","style":{color:"red",margin:-1}}"#));
+        assert!(code.contains(r#"createElement("div",_tmpl1,"#));
+        // Props reading a signal, and empty props, stay inline
+        assert!(code.contains(r#"createElement("span",{"title":"#));
+        assert!(code.contains(r#"createElement("p",{},"#));
+        // Elements outside functions are only created once
+        assert!(code.contains(r#"createElement("h1",{"id":"title"},"#));
+        assert!(!code.contains("_tmpl2"));
+    }
+
+    #[test]
+    fn test_optimize_folds_logical_jsx_guards() {
+        let source = r#"
+            function App(props) {
+                return (
+                    <div>
+                        {true && <b>shown</b>}
+                        {false && <i>hidden</i>}
+                        {false || <u>fallback</u>}
+                        {0 && <s>zero</s>}
+                        {props.open && <p>open</p>}
+                    </div>
+                );
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyzer::analyze(&module).unwrap();
+        let transformed = transformer::transform(module, &analysis).unwrap();
+        let optimized = optimize(transformed, &analysis).unwrap();
+        let code = codegen::generate(&optimized, &CompilerOptions { minify: MinifyLevel::Whitespace, ..Default::default() }).unwrap();
+
+        // `true &&` unwraps to the element, `false &&` leaves no child at all
+        assert!(code.contains(r#"createElement("div",{},createElement("b",{},"shown"),createElement("u",{},"fallback"),0,"#));
+        assert!(!code.contains("hidden"));
+        assert!(!code.contains("false"));
+        // Guards that aren't constant stay
+        assert!(code.contains("props.open&&"));
+    }
+
+    #[test]
+    fn test_optimize_shares_identical_hoisted_props() {
+        let source = r#"
+            function Save() {
+                return <button class="btn" type="button">Save</button>;
+            }
+            function Cancel() {
+                return <button type="button" class="btn">Cancel</button>;
+            }
+            function Delete() {
+                return <button class="btn" type="button">Delete</button>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyzer::analyze(&module).unwrap();
+        let transformed = transformer::transform(module, &analysis).unwrap();
+        let optimized = optimize(transformed, &analysis).unwrap();
+        let code = codegen::generate(&optimized, &CompilerOptions { minify: MinifyLevel::Whitespace, ..Default::default() }).unwrap();
+
+        // Same props share a constant; the same keys in another order don't
+        assert!(code.contains(r#"const _tmpl1={"class":"btn","type":"button"}"#));
+        assert!(code.contains(r#"const _tmpl2={"type":"button","class":"btn"}"#));
+        assert_eq!(code.matches(r#"createElement("button",_tmpl1,"#).count(), 2);
+        assert!(!code.contains("_tmpl3"));
+    }
+
+    #[test]
+    fn test_optimize_folds_trivial_memos() {
+        let source = r#"
+            export const shared = createMemo(() => total());
+            const [total, setTotal] = createSignal(0);
+            function Counter(props) {
+                const [count, setCount] = createSignal(0);
+                const current = createMemo(() => count());
+                const latest = createMemo(function () { return current(); });
+                const doubled = createMemo(() => latest() * 2);
+                const label = createMemo(() => props.label());
+                const first = createMemo(() => count(0));
+                return <p title={label()} data={{ latest }}>{latest()} {doubled()} {first()}</p>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyzer::analyze(&module).unwrap();
+        let transformed = transformer::transform(module, &analysis).unwrap();
+        let optimized = optimize(transformed, &analysis).unwrap();
+        let code = codegen::generate(&optimized, &CompilerOptions { minify: MinifyLevel::Whitespace, ..Default::default() }).unwrap();
+
+        // Memos that only read another getter are gone, even chained
+        assert!(!code.contains("current"), "{}", code);
+        assert!(!code.contains("const latest"), "{}", code);
+        assert!(code.contains("const doubled=createMemo(()=>count()*2)"), "{}", code);
+        assert!(code.contains("{latest:count}"), "{}", code);
+        // Anything else stays a memo: other calls, arguments, exports
+        assert!(code.contains("createMemo(()=>props.label())"), "{}", code);
+        assert!(code.contains("createMemo(()=>count(0))"), "{}", code);
+        assert!(code.contains("export const shared=createMemo(()=>total())"), "{}", code);
+    }
+
     #[test]
     fn test_optimize_conditional() {
         let source = r#"
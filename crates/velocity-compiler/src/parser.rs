@@ -5,7 +5,7 @@
 use crate::error::{CompilerError, Result};
 use swc_core::common::{
     sync::Lrc,
-    SourceMap, FileName,
+    SourceMap, FileName, Spanned,
 };
 use swc_core::ecma::ast::Module;
 use swc_core::ecma::parser::{lexer::Lexer, Parser, StringInput, Syntax, TsSyntax};
@@ -43,8 +43,15 @@ pub fn parse(source: &str, filename: &str) -> Result<Module> {
     parser
         .parse_module()
         .map_err(|e| {
-            // Format the error with source context
-            let error_msg = format!("Failed to parse {}: {:?}", filename, e);
+            // Format the error as file:line:column so editors can jump to it
+            let loc = cm.lookup_char_pos(e.span().lo);
+            let error_msg = format!(
+                "Failed to parse {}:{}:{}: {}",
+                filename,
+                loc.line,
+                loc.col_display + 1,
+                e.kind().msg()
+            );
             CompilerError::ParseError(error_msg)
         })
 }
@@ -117,4 +124,13 @@ mod tests {
         let result = parse(source, "test.tsx");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_parse_error_includes_location() {
+        let source = "const a = 1;\nconst b = ;\n";
+
+        let error = parse(source, "test.tsx").unwrap_err().to_string();
+
+        assert!(error.contains("test.tsx:2:11"), "{}", error);
+    }
 }
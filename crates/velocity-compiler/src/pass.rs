@@ -0,0 +1,54 @@
+//! Custom Compiler Passes
+//!
+//! Lets build tooling run its own AST transforms (auto-imports, i18n
+//! extraction, ...) as part of compilation without forking the compiler.
+//!
+//! ## Ordering
+//! Passes listed in [`CompilerOptions::passes`](crate::CompilerOptions::passes)
+//! run in order, once per file:
+//! 1. Parse, strip TypeScript, scope `css` templates, analyze reactivity
+//! 2. Transform JSX → DOM operations
+//! 3. Built-in optimizer (only when `optimize` is enabled), then component
+//!    profiling marks (only when `profile_components` is enabled)
+//! 4. **Custom passes**
+//! 5. Downleveling for `target`, then code generation
+//!
+//! So a pass always sees JSX already lowered to runtime calls and, when
+//! enabled, the optimizer's output. Whatever syntax a pass emits is still
+//! downleveled for the configured target. The [`Analysis`] describes the
+//! module as it was before the JSX transform. Identifiers from the source
+//! keep the syntax contexts the analysis resolved, so `ident.to_id()` can be
+//! looked up in its signal and memo sets.
+
+use crate::analyzer::Analysis;
+use crate::error::Result;
+use std::fmt;
+use std::sync::Arc;
+use swc_core::ecma::ast::Module;
+
+/// A user-supplied transform run between the optimizer and code generation.
+///
+/// Returning an error aborts compilation of the file.
+pub trait CompilerPass: Send + Sync {
+    /// Name shown in debug output
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Transform `module` in place
+    fn run(&self, module: &mut Module, analysis: &Analysis) -> Result<()>;
+}
+
+impl fmt::Debug for dyn CompilerPass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CompilerPass({})", self.name())
+    }
+}
+
+/// Run every pass over `module`, stopping at the first error
+pub fn run_passes(passes: &[Arc<dyn CompilerPass>], module: &mut Module, analysis: &Analysis) -> Result<()> {
+    for pass in passes {
+        pass.run(module, analysis)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,606 @@
+//! Development Prop Checks
+//!
+//! A component's TypeScript props type only guards callers that are
+//! type-checked. [`insert_prop_checks`] turns it into runtime checks at the
+//! top of the component, which warn in the console when a caller passes the
+//! wrong thing:
+//! ```tsx
+//! interface Props { label: string; size?: "sm" | "lg" }
+//! function Button(props: Props) { ... }
+//! // → untrack(() => {
+//! //     if (props.label === undefined) console.warn("<Button>: missing required prop `label`");
+//! //     else if (!(typeof props.label === "string")) console.warn(...);
+//! //     if (props.size !== undefined && !(props.size === "sm" || props.size === "lg")) console.warn(...);
+//! //   });
+//! ```
+//!
+//! ## What is checked
+//! A prop is required unless it's declared `?:` or its type includes
+//! `undefined` or `void`. Its value is then checked against its type:
+//!
+//! | Type | Check |
+//! |------|-------|
+//! | `string`, `number`, `boolean`, `bigint`, `symbol` | `typeof` |
+//! | template literal types | `typeof` is `"string"` |
+//! | function types and method signatures | `typeof` is `"function"` |
+//! | `T[]`, tuples, `Array<T>`, `ReadonlyArray<T>` | `Array.isArray` (elements aren't checked) |
+//! | string, number and boolean literals, `null` | `===` |
+//! | unions of the above | any alternative matches |
+//! | aliases of the above (`type Size = "sm" \| "lg"`) | as the aliased type |
+//!
+//! Any other type (object types, interfaces, generics, `any`) only gets the
+//! presence check, and so does a union with such a type in it.
+//!
+//! Components are capitalized functions, as for
+//! [`instrument_components`](crate::transformer::instrument_components),
+//! whose first parameter is a plain identifier annotated with an inline
+//! type literal or the name of an interface or type literal alias in the same
+//! module. Members inherited through `extends` aren't checked, and neither
+//! are destructured props. The checks read each prop once or twice, and a
+//! component runs inside the effect rendering it, where a getter prop would
+//! subscribe that effect to whatever it reads; so they run in `untrack`,
+//! imported from `velocity-runtime`.
+//!
+//! Runs before [`strip_typescript`](crate::transformer::strip_typescript),
+//! which would throw the types away;
+//! [`CompilerOptions::prop_checks`](crate::CompilerOptions::prop_checks)
+//! turns it on for development builds only.
+
+use std::collections::HashMap;
+use crate::transformer::{import_helpers, is_component_name};
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+
+/// How deep type aliases are followed, so a cycle can't loop forever
+const MAX_ALIAS_DEPTH: usize = 8;
+
+/// Insert runtime prop checks into every typed component in `module`.
+/// Expects the module as parsed, with its types.
+///
+/// Returns the runtime helpers the checks call: `untrack`, or none when no
+/// component got checks.
+pub fn insert_prop_checks(module: &mut Module) -> &'static [&'static str] {
+    let mut types = TypeDecls::default();
+    module.visit_with(&mut types);
+    let mut checker = PropChecker { types, checked: false };
+    module.visit_mut_with(&mut checker);
+    if !checker.checked {
+        return &[];
+    }
+    import_helpers(module, CHECK_HELPERS.iter().copied());
+    CHECK_HELPERS
+}
+
+/// Runtime helpers [`insert_prop_checks`] calls
+const CHECK_HELPERS: &[&str] = &["untrack"];
+
+/// One value a prop may have
+#[derive(Debug, Clone, PartialEq)]
+enum Alternative {
+    /// `typeof value === "string"`
+    TypeOf(&'static str),
+    /// `Array.isArray(value)`
+    Array,
+    /// `value === "sm"`, or `null`
+    Value(Lit),
+}
+
+/// What a prop's type allows, as far as it can be checked at runtime
+#[derive(Debug, Clone, PartialEq)]
+struct Expected {
+    /// The values allowed, or `None` when any value could be valid
+    alternatives: Option<Vec<Alternative>>,
+    /// Whether `undefined` is allowed
+    optional: bool,
+}
+
+/// A prop of a component and what its value should be
+#[derive(Debug, Clone, PartialEq)]
+struct PropCheck {
+    name: String,
+    expected: Expected,
+}
+
+/// Interfaces and type aliases declared in the module, by name
+#[derive(Default)]
+struct TypeDecls {
+    interfaces: HashMap<String, Vec<TsTypeElement>>,
+    aliases: HashMap<String, TsType>,
+}
+
+impl Visit for TypeDecls {
+    fn visit_ts_interface_decl(&mut self, decl: &TsInterfaceDecl) {
+        self.interfaces.insert(decl.id.sym.to_string(), decl.body.body.clone());
+    }
+
+    fn visit_ts_type_alias_decl(&mut self, decl: &TsTypeAliasDecl) {
+        self.aliases.insert(decl.id.sym.to_string(), (*decl.type_ann).clone());
+    }
+}
+
+impl TypeDecls {
+    /// The members of the props type `ty`, if it's an object type this can
+    /// see into
+    fn members<'a>(&'a self, ty: &'a TsType, depth: usize) -> Option<&'a [TsTypeElement]> {
+        match ty {
+            TsType::TsTypeLit(lit) => Some(&lit.members),
+            TsType::TsParenthesizedType(paren) => self.members(&paren.type_ann, depth),
+            TsType::TsTypeRef(TsTypeRef {
+                type_name: TsEntityName::Ident(name),
+                ..
+            }) if depth < MAX_ALIAS_DEPTH => match self.interfaces.get(&*name.sym) {
+                Some(members) => Some(members),
+                None => self.members(self.aliases.get(&*name.sym)?, depth + 1),
+            },
+            _ => None,
+        }
+    }
+
+    /// The checks for the members of a props type
+    fn prop_checks(&self, members: &[TsTypeElement]) -> Vec<PropCheck> {
+        members
+            .iter()
+            .filter_map(|member| {
+                let (key, optional, expected) = match member {
+                    TsTypeElement::TsPropertySignature(prop) => {
+                        let expected = match &prop.type_ann {
+                            Some(ann) => self.expected(&ann.type_ann, 0),
+                            None => Expected {
+                                alternatives: None,
+                                optional: false,
+                            },
+                        };
+                        (&prop.key, prop.optional, expected)
+                    }
+                    TsTypeElement::TsMethodSignature(method) => (
+                        &method.key,
+                        method.optional,
+                        Expected {
+                            alternatives: Some(vec![Alternative::TypeOf("function")]),
+                            optional: false,
+                        },
+                    ),
+                    _ => return None,
+                };
+                let name = match &**key {
+                    Expr::Ident(ident) => ident.sym.to_string(),
+                    Expr::Lit(Lit::Str(s)) => s.value.to_string(),
+                    _ => return None,
+                };
+                Some(PropCheck {
+                    name,
+                    expected: Expected {
+                        optional: optional || expected.optional,
+                        ..expected
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// What values `ty` allows
+    fn expected(&self, ty: &TsType, depth: usize) -> Expected {
+        let one = |alternative| Expected {
+            alternatives: Some(vec![alternative]),
+            optional: false,
+        };
+        let unknown = Expected {
+            alternatives: None,
+            optional: false,
+        };
+
+        match ty {
+            TsType::TsKeywordType(keyword) => match keyword.kind {
+                TsKeywordTypeKind::TsStringKeyword => one(Alternative::TypeOf("string")),
+                TsKeywordTypeKind::TsNumberKeyword => one(Alternative::TypeOf("number")),
+                TsKeywordTypeKind::TsBooleanKeyword => one(Alternative::TypeOf("boolean")),
+                TsKeywordTypeKind::TsBigIntKeyword => one(Alternative::TypeOf("bigint")),
+                TsKeywordTypeKind::TsSymbolKeyword => one(Alternative::TypeOf("symbol")),
+                TsKeywordTypeKind::TsNullKeyword => one(Alternative::Value(Lit::Null(Null { span: keyword.span }))),
+                TsKeywordTypeKind::TsUndefinedKeyword | TsKeywordTypeKind::TsVoidKeyword => Expected {
+                    alternatives: Some(Vec::new()),
+                    optional: true,
+                },
+                _ => unknown,
+            },
+            TsType::TsFnOrConstructorType(_) => one(Alternative::TypeOf("function")),
+            TsType::TsArrayType(_) | TsType::TsTupleType(_) => one(Alternative::Array),
+            TsType::TsLitType(lit) => match &lit.lit {
+                TsLit::Str(s) => one(Alternative::Value(Lit::Str(s.clone()))),
+                TsLit::Number(n) => one(Alternative::Value(Lit::Num(n.clone()))),
+                TsLit::Bool(b) => one(Alternative::Value(Lit::Bool(*b))),
+                TsLit::Tpl(_) => one(Alternative::TypeOf("string")),
+                TsLit::BigInt(_) => one(Alternative::TypeOf("bigint")),
+            },
+            TsType::TsParenthesizedType(paren) => self.expected(&paren.type_ann, depth),
+            TsType::TsUnionOrIntersectionType(TsUnionOrIntersectionType::TsUnionType(union)) => {
+                let mut alternatives = Some(Vec::new());
+                let mut optional = false;
+                for ty in &union.types {
+                    let expected = self.expected(ty, depth);
+                    optional |= expected.optional;
+                    alternatives = alternatives.zip(expected.alternatives).map(|(mut all, more)| {
+                        all.extend(more);
+                        all
+                    });
+                }
+                Expected { alternatives, optional }
+            }
+            TsType::TsTypeRef(TsTypeRef {
+                type_name: TsEntityName::Ident(name),
+                ..
+            }) => match &*name.sym {
+                "Array" | "ReadonlyArray" => one(Alternative::Array),
+                name if depth < MAX_ALIAS_DEPTH => match self.aliases.get(name) {
+                    Some(aliased) => self.expected(aliased, depth + 1),
+                    None => unknown,
+                },
+                _ => unknown,
+            },
+            _ => unknown,
+        }
+    }
+}
+
+/// Visitor behind [`insert_prop_checks`]
+struct PropChecker {
+    types: TypeDecls,
+    /// Whether any component got checks
+    checked: bool,
+}
+
+impl PropChecker {
+    /// The statement checking the props of `component`, which takes
+    /// `param`, if it has checkable props
+    fn check_stmt(&mut self, component: &str, param: Option<&Pat>) -> Option<Stmt> {
+        let Some(Pat::Ident(props)) = param else {
+            return None;
+        };
+        let members = self.types.members(&props.type_ann.as_ref()?.type_ann, 0)?;
+        let stmts = check_stmts(component, &props.id.sym, &self.types.prop_checks(members));
+        if stmts.is_empty() {
+            return None;
+        }
+        self.checked = true;
+        Some(untracked(stmts))
+    }
+}
+
+impl VisitMut for PropChecker {
+    fn visit_mut_fn_decl(&mut self, decl: &mut FnDecl) {
+        decl.visit_mut_children_with(self);
+        check_function(self, &decl.ident.sym, &mut decl.function);
+    }
+
+    // export default function Button() { ... }
+    fn visit_mut_fn_expr(&mut self, expr: &mut FnExpr) {
+        expr.visit_mut_children_with(self);
+        if let Some(ident) = &expr.ident {
+            check_function(self, &ident.sym, &mut expr.function);
+        }
+    }
+
+    // const Button = (props: Props) => ...
+    fn visit_mut_var_declarator(&mut self, decl: &mut VarDeclarator) {
+        decl.visit_mut_children_with(self);
+        let Pat::Ident(name) = &decl.name else {
+            return;
+        };
+        if !is_component_name(&name.id.sym) {
+            return;
+        }
+        match decl.init.as_deref_mut() {
+            Some(Expr::Arrow(arrow)) => {
+                let Some(check) = self.check_stmt(&name.id.sym, arrow.params.first()) else {
+                    return;
+                };
+                let mut stmts = vec![check];
+                match std::mem::replace(&mut *arrow.body, BlockStmtOrExpr::BlockStmt(Default::default())) {
+                    BlockStmtOrExpr::BlockStmt(block) => stmts.extend(block.stmts),
+                    BlockStmtOrExpr::Expr(expr) => stmts.push(Stmt::Return(ReturnStmt {
+                        span: arrow.span,
+                        arg: Some(expr),
+                    })),
+                }
+                *arrow.body = BlockStmtOrExpr::BlockStmt(BlockStmt {
+                    span: arrow.span,
+                    stmts,
+                    ..Default::default()
+                });
+            }
+            Some(Expr::Fn(FnExpr { function, ident: None })) => check_function(self, &name.id.sym, function),
+            _ => {}
+        }
+    }
+}
+
+/// Insert checks at the top of `function` if it's a typed component
+fn check_function(checker: &mut PropChecker, name: &str, function: &mut Function) {
+    if !is_component_name(name) {
+        return;
+    }
+    let Some(body) = &mut function.body else {
+        return;
+    };
+    if let Some(check) = checker.check_stmt(name, function.params.first().map(|param| &param.pat)) {
+        body.stmts.insert(0, check);
+    }
+}
+
+/// `untrack(() => { stmts })`
+fn untracked(stmts: Vec<Stmt>) -> Stmt {
+    let checks = Expr::Arrow(ArrowExpr {
+        body: Box::new(BlockStmtOrExpr::BlockStmt(BlockStmt {
+            stmts,
+            ..Default::default()
+        })),
+        ..Default::default()
+    });
+    Stmt::Expr(ExprStmt {
+        span: Default::default(),
+        expr: Box::new(call(ident("untrack"), vec![checks])),
+    })
+}
+
+/// The statements checking `checks` against the props object `props` of
+/// `component`
+fn check_stmts(component: &str, props: &str, checks: &[PropCheck]) -> Vec<Stmt> {
+    checks
+        .iter()
+        .filter_map(|check| {
+            let value = || prop_access(props, &check.name);
+            let missing = warn(
+                format!("<{}>: missing required prop `{}`", component, check.name),
+                None,
+            );
+            let wrong_type = |alternatives: &[Alternative]| {
+                let expected: Vec<String> = alternatives.iter().map(describe).collect();
+                let message = format!(
+                    "<{}>: prop `{}` should be {}, got",
+                    component,
+                    check.name,
+                    expected.join(" | ")
+                );
+                warn(message, Some(value()))
+            };
+
+            match (&check.expected.alternatives, check.expected.optional) {
+                (None, true) => None,
+                (None, false) => Some(if_stmt(is_undefined(value(), true), missing, None)),
+                (Some(alternatives), false) => {
+                    let mismatch = not(matches_any(&value, alternatives));
+                    let else_check = if_stmt(mismatch, wrong_type(alternatives), None);
+                    Some(if_stmt(is_undefined(value(), true), missing, Some(else_check)))
+                }
+                // `undefined` was the only thing allowed
+                (Some(alternatives), true) if alternatives.is_empty() => None,
+                (Some(alternatives), true) => {
+                    let mismatch = and(is_undefined(value(), false), not(matches_any(&value, alternatives)));
+                    Some(if_stmt(mismatch, wrong_type(alternatives), None))
+                }
+            }
+        })
+        .collect()
+}
+
+/// How a type alternative reads in a warning
+fn describe(alternative: &Alternative) -> String {
+    match alternative {
+        Alternative::TypeOf(name) => name.to_string(),
+        Alternative::Array => "an array".to_string(),
+        Alternative::Value(Lit::Str(s)) => format!("\"{}\"", s.value),
+        Alternative::Value(Lit::Num(n)) => n.value.to_string(),
+        Alternative::Value(Lit::Bool(b)) => b.value.to_string(),
+        Alternative::Value(_) => "null".to_string(),
+    }
+}
+
+/// `value` matching any of `alternatives`
+fn matches_any(value: &impl Fn() -> Expr, alternatives: &[Alternative]) -> Expr {
+    alternatives
+        .iter()
+        .map(|alternative| match alternative {
+            Alternative::TypeOf(name) => strict_eq(
+                Expr::Unary(UnaryExpr {
+                    span: Default::default(),
+                    op: UnaryOp::TypeOf,
+                    arg: Box::new(value()),
+                }),
+                Expr::Lit(Lit::Str((*name).into())),
+            ),
+            Alternative::Array => call(member(ident("Array"), "isArray"), vec![value()]),
+            Alternative::Value(lit) => strict_eq(value(), Expr::Lit(lit.clone())),
+        })
+        .reduce(|left, right| binary(left, BinaryOp::LogicalOr, right))
+        .unwrap_or(Expr::Lit(Lit::Bool(false.into())))
+}
+
+/// `props.name`, or `props["data-id"]` for a name that isn't an identifier
+fn prop_access(props: &str, name: &str) -> Expr {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if valid {
+        member(ident(props), name)
+    } else {
+        Expr::Member(MemberExpr {
+            span: Default::default(),
+            obj: Box::new(ident(props)),
+            prop: MemberProp::Computed(ComputedPropName {
+                span: Default::default(),
+                expr: Box::new(Expr::Lit(Lit::Str(name.into()))),
+            }),
+        })
+    }
+}
+
+fn ident(name: &str) -> Expr {
+    Expr::Ident(Ident::new_no_ctxt(name.into(), Default::default()))
+}
+
+fn member(obj: Expr, prop: &str) -> Expr {
+    Expr::Member(MemberExpr {
+        span: Default::default(),
+        obj: Box::new(obj),
+        prop: MemberProp::Ident(IdentName::new(prop.into(), Default::default())),
+    })
+}
+
+fn call(callee: Expr, args: Vec<Expr>) -> Expr {
+    Expr::Call(CallExpr {
+        span: Default::default(),
+        ctxt: Default::default(),
+        callee: Callee::Expr(Box::new(callee)),
+        args: args.into_iter().map(|expr| ExprOrSpread { spread: None, expr: Box::new(expr) }).collect(),
+        type_args: None,
+    })
+}
+
+fn binary(left: Expr, op: BinaryOp, right: Expr) -> Expr {
+    Expr::Bin(BinExpr {
+        span: Default::default(),
+        op,
+        left: Box::new(left),
+        right: Box::new(right),
+    })
+}
+
+fn strict_eq(left: Expr, right: Expr) -> Expr {
+    binary(left, BinaryOp::EqEqEq, right)
+}
+
+fn and(left: Expr, right: Expr) -> Expr {
+    binary(left, BinaryOp::LogicalAnd, right)
+}
+
+/// `!(expr)`
+fn not(expr: Expr) -> Expr {
+    Expr::Unary(UnaryExpr {
+        span: Default::default(),
+        op: UnaryOp::Bang,
+        arg: Box::new(Expr::Paren(ParenExpr {
+            span: Default::default(),
+            expr: Box::new(expr),
+        })),
+    })
+}
+
+/// `value === undefined`, or `value !== undefined`
+fn is_undefined(value: Expr, undefined: bool) -> Expr {
+    let op = if undefined { BinaryOp::EqEqEq } else { BinaryOp::NotEqEq };
+    binary(value, op, ident("undefined"))
+}
+
+/// `console.warn(message, value)`
+fn warn(message: String, value: Option<Expr>) -> Stmt {
+    let mut args = vec![Expr::Lit(Lit::Str(message.into()))];
+    args.extend(value);
+    Stmt::Expr(ExprStmt {
+        span: Default::default(),
+        expr: Box::new(call(member(ident("console"), "warn"), args)),
+    })
+}
+
+fn if_stmt(test: Expr, cons: Stmt, alt: Option<Stmt>) -> Stmt {
+    Stmt::If(IfStmt {
+        span: Default::default(),
+        test: Box::new(test),
+        cons: Box::new(cons),
+        alt: alt.map(Box::new),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{codegen, parser, transformer, Compiler, CompilerOptions};
+
+    fn checked(source: &str) -> String {
+        let mut module = parser::parse(source, "test.tsx").unwrap();
+        insert_prop_checks(&mut module);
+        let module = transformer::strip_typescript(module).unwrap();
+        let code = codegen::generate(&module, &CompilerOptions::default()).unwrap();
+        code.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn test_prop_checks_from_interface() {
+        let code = checked(
+            r#"
+            type Size = "sm" | "lg";
+            interface ButtonProps {
+                label: string;
+                count?: number | null;
+                size: Size | undefined;
+                items: string[];
+                "data-id": number;
+                onClick(): void;
+                icon: JSX.Element;
+                extra?: Record<string, unknown>;
+            }
+            export function Button(props: ButtonProps) {
+                return props.label;
+            }
+            "#,
+        );
+
+        let expected = [
+            r#"if (props.label === undefined) console.warn("<Button>: missing required prop `label`"); else if (!(typeof props.label === "string")) console.warn("<Button>: prop `label` should be string, got", props.label);"#,
+            r#"if (props.count !== undefined && !(typeof props.count === "number" || props.count === null)) console.warn("<Button>: prop `count` should be number | null, got", props.count);"#,
+            r#"if (props.size !== undefined && !(props.size === "sm" || props.size === "lg")) console.warn('<Button>: prop `size` should be "sm" | "lg", got', props.size);"#,
+            r#"else if (!(Array.isArray(props.items))) console.warn("<Button>: prop `items` should be an array, got", props.items);"#,
+            r#"if (props["data-id"] === undefined) console.warn("<Button>: missing required prop `data-id`");"#,
+            r#"else if (!(typeof props.onClick === "function"))"#,
+            r#"if (props.icon === undefined) console.warn("<Button>: missing required prop `icon`"); }); return props.label;"#,
+        ];
+        for expected in expected {
+            assert!(code.contains(expected), "{}\n\nnot in\n\n{}", expected, code);
+        }
+        assert!(!code.contains("props.extra"), "{}", code);
+    }
+
+    #[test]
+    fn test_prop_checks_skip_untyped_and_non_components() {
+        let code = checked(
+            r#"
+            const Badge = (props: { text: string }) => <span>{props.text}</span>;
+            const helper = (props: { text: string }) => props.text;
+            function List({ items }: { items: string[] }) { return items; }
+            function Plain(props) { return props; }
+            "#,
+        );
+
+        assert!(code.contains(r#"const Badge = (props)=>{ untrack(()=>{ if (props.text === undefined)"#), "{}", code);
+        assert!(code.contains(r#"return <span>{props.text}</span>; };"#), "{}", code);
+        assert_eq!(code.matches("console.warn").count(), 2, "{}", code);
+    }
+
+    #[test]
+    fn test_prop_checks_run_untracked() {
+        // A getter prop read by the checks would otherwise subscribe the
+        // effect rendering the component
+        let source = r#"
+            export function Counter(props: { count: number }) {
+                return <span>{props.count}</span>;
+            }
+        "#;
+        let options = CompilerOptions {
+            prop_checks: true,
+            ..Default::default()
+        };
+        let output = Compiler::new(options).compile_full(source, "Counter.tsx").unwrap();
+        assert!(output.code.contains(r#"import { untrack } from "velocity-runtime";"#), "{}", output.code);
+        assert!(output.used_runtime_helpers.contains("untrack"));
+
+        // An `untrack` the module already imports is used as it is
+        let code = checked(
+            r#"
+            import { untrack } from "velocity-runtime";
+            export function Counter(props: { count: number }) {
+                return untrack(() => props.count);
+            }
+            "#,
+        );
+        assert_eq!(code.matches("import").count(), 1, "{}", code);
+        assert!(code.contains(r#"{ untrack(()=>{ if (props.count === undefined)"#), "{}", code);
+    }
+}
@@ -0,0 +1,219 @@
+//! Runtime Import Style
+//!
+//! Generated code imports its helpers (`createRenderEffect`, `forEach`,
+//! `mergeProps`) from the `velocity-runtime` barrel. Bundlers that don't
+//! tree-shake barrels keep the whole runtime for it, so with
+//! [`RuntimeImportStyle::Deep`] each name is imported from the submodule
+//! that defines it instead:
+//! ```js
+//! import { createRenderEffect, forEach } from "velocity-runtime";
+//! // becomes
+//! import { createRenderEffect } from "velocity-runtime/reactivity";
+//! import { forEach } from "velocity-runtime/dom";
+//! ```
+//! The module's own named imports from `velocity-runtime` are split the same
+//! way. Names the [`RuntimeImportMap`] doesn't list, and default and
+//! namespace imports, stay on the barrel.
+//!
+//! Modules compiled for a worker import from `velocity-runtime/worker`
+//! instead of the barrel (see [`use_worker_runtime`]), so that loading them
+//! doesn't load the DOM helpers.
+
+use crate::environment::WORKER_RUNTIME_MODULE;
+use crate::transformer::RUNTIME_MODULE;
+use std::collections::BTreeMap;
+use swc_core::ecma::ast::*;
+
+/// Submodule of `velocity-runtime` each runtime export is defined in
+const DEFAULT_SUBMODULES: &[(&str, &str)] = &[
+    ("createSignal", "velocity-runtime/reactivity"),
+    ("createEffect", "velocity-runtime/reactivity"),
+    ("createRenderEffect", "velocity-runtime/reactivity"),
+    ("createMemo", "velocity-runtime/reactivity"),
+    ("batch", "velocity-runtime/reactivity"),
+    ("untrack", "velocity-runtime/reactivity"),
+    ("createRoot", "velocity-runtime/reactivity"),
+    ("onCleanup", "velocity-runtime/component"),
+    ("onMount", "velocity-runtime/component"),
+    ("createComponent", "velocity-runtime/component"),
+    ("getContext", "velocity-runtime/component"),
+    ("createContext", "velocity-runtime/component"),
+    ("mergeProps", "velocity-runtime/component"),
+    ("createElement", "velocity-runtime/dom"),
+    ("insert", "velocity-runtime/dom"),
+    ("render", "velocity-runtime/dom"),
+    ("createPortal", "velocity-runtime/dom"),
+    ("forEach", "velocity-runtime/dom"),
+    ("mark", "velocity-runtime/profiling"),
+    ("measure", "velocity-runtime/profiling"),
+];
+
+/// Submodule of the runtime's DOM helpers
+const DOM_SUBMODULE: &str = "velocity-runtime/dom";
+
+/// Whether the runtime export `name` works on the DOM
+pub(crate) fn is_dom_helper(name: &str) -> bool {
+    DEFAULT_SUBMODULES.iter().any(|(export, path)| *export == name && *path == DOM_SUBMODULE)
+}
+
+/// How imports from `velocity-runtime` are written
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum RuntimeImportStyle {
+    /// One import from the `velocity-runtime` barrel
+    #[default]
+    Barrel,
+    /// One import per submodule, as the map says
+    Deep(RuntimeImportMap),
+}
+
+/// Which module each runtime export is imported from under
+/// [`RuntimeImportStyle::Deep`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeImportMap {
+    /// Export name → module specifier. The default lists the submodules of
+    /// the `velocity-runtime` package; insert or remove entries for runtimes
+    /// laid out differently.
+    pub paths: BTreeMap<String, String>,
+}
+
+impl Default for RuntimeImportMap {
+    fn default() -> Self {
+        Self {
+            paths: DEFAULT_SUBMODULES
+                .iter()
+                .map(|(name, path)| (name.to_string(), path.to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// Rewrite `module`'s named imports from `velocity-runtime` for `style`
+pub fn apply_import_style(module: &mut Module, style: &RuntimeImportStyle) {
+    let RuntimeImportStyle::Deep(map) = style else {
+        return;
+    };
+
+    let mut body = Vec::with_capacity(module.body.len());
+    for item in module.body.drain(..) {
+        let ModuleItem::ModuleDecl(ModuleDecl::Import(mut import)) = item else {
+            body.push(item);
+            continue;
+        };
+        if &*import.src.value != RUNTIME_MODULE || import.type_only {
+            body.push(ModuleItem::ModuleDecl(ModuleDecl::Import(import)));
+            continue;
+        }
+
+        // Submodule → its specifiers, in the order first imported
+        let mut deep: Vec<(&str, Vec<ImportSpecifier>)> = Vec::new();
+        let mut barrel = Vec::new();
+        for specifier in import.specifiers.drain(..) {
+            let path = match &specifier {
+                ImportSpecifier::Named(named) if !named.is_type_only => {
+                    let name = match &named.imported {
+                        Some(ModuleExportName::Ident(ident)) => ident.sym.as_str(),
+                        Some(ModuleExportName::Str(name)) => name.value.as_str(),
+                        None => named.local.sym.as_str(),
+                    };
+                    map.paths.get(name)
+                }
+                _ => None,
+            };
+            match path {
+                Some(path) => match deep.iter_mut().find(|(submodule, _)| *submodule == path.as_str()) {
+                    Some((_, specifiers)) => specifiers.push(specifier),
+                    None => deep.push((path.as_str(), vec![specifier])),
+                },
+                None => barrel.push(specifier),
+            }
+        }
+
+        // A bare `import "velocity-runtime"` (no specifiers) is kept as is
+        let keep_barrel = !barrel.is_empty() || deep.is_empty();
+        for (path, specifiers) in deep {
+            let mut submodule = import.clone();
+            submodule.src = Box::new(Str {
+                span: Default::default(),
+                value: path.into(),
+                raw: None,
+            });
+            submodule.specifiers = specifiers;
+            body.push(ModuleItem::ModuleDecl(ModuleDecl::Import(submodule)));
+        }
+        if keep_barrel {
+            import.specifiers = barrel;
+            body.push(ModuleItem::ModuleDecl(ModuleDecl::Import(import)));
+        }
+    }
+    module.body = body;
+}
+
+/// Point `module`'s imports from the `velocity-runtime` barrel at its
+/// worker entry point. Run after [`apply_import_style`]; submodule imports
+/// are left alone.
+pub fn use_worker_runtime(module: &mut Module) {
+    for item in &mut module.body {
+        if let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item {
+            if &*import.src.value == RUNTIME_MODULE {
+                *import.src = Str {
+                    span: import.src.span,
+                    value: WORKER_RUNTIME_MODULE.into(),
+                    raw: None,
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Compiler, CompilerOptions};
+
+    fn compile(source: &str, style: RuntimeImportStyle) -> String {
+        let compiler = Compiler::new(CompilerOptions {
+            source_maps: false,
+            runtime_import_style: style,
+            ..Default::default()
+        });
+        compiler.compile(source, "App.tsx").unwrap()
+    }
+
+    #[test]
+    fn test_deep_runtime_imports() {
+        let source = r#"
+            import { createSignal, onMount as mounted } from "velocity-runtime";
+            export function List(props) {
+                const [items] = createSignal([]);
+                mounted(() => {});
+                return <ul>{items().map(item => <Row {...props} item={item} />)}</ul>;
+            }
+        "#;
+
+        let barrel = compile(source, RuntimeImportStyle::Barrel);
+        assert!(barrel.contains(r#"import { forEach, mergeProps } from "velocity-runtime";"#), "{}", barrel);
+        assert!(barrel.contains(r#"import { createSignal, onMount as mounted } from "velocity-runtime";"#), "{}", barrel);
+
+        let mut map = RuntimeImportMap::default();
+        map.paths.insert("mergeProps".to_string(), "velocity-runtime/props".to_string());
+        let deep = compile(source, RuntimeImportStyle::Deep(map));
+        assert!(deep.contains(r#"import { createSignal } from "velocity-runtime/reactivity";"#), "{}", deep);
+        assert!(deep.contains(r#"import { mergeProps } from "velocity-runtime/props";"#), "{}", deep);
+        assert!(deep.contains(r#"import { onMount as mounted } from "velocity-runtime/component";"#), "{}", deep);
+        assert!(deep.contains(r#"import { forEach } from "velocity-runtime/dom";"#), "{}", deep);
+        assert!(!deep.contains(r#"from "velocity-runtime";"#), "{}", deep);
+    }
+
+    #[test]
+    fn test_unmapped_runtime_imports_stay_on_barrel() {
+        let source = r#"
+            import "velocity-runtime";
+            import Velocity, { createMemo, Fragment } from "velocity-runtime";
+            export const value = [Velocity, createMemo, Fragment];
+        "#;
+        let deep = compile(source, RuntimeImportStyle::Deep(RuntimeImportMap::default()));
+        assert!(deep.contains("import \"velocity-runtime\";"), "{}", deep);
+        assert!(deep.contains(r#"import { createMemo } from "velocity-runtime/reactivity";"#), "{}", deep);
+        assert!(deep.contains(r#"import Velocity, { Fragment } from "velocity-runtime";"#), "{}", deep);
+    }
+}
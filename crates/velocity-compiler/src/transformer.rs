@@ -11,20 +11,46 @@
 //! const _el = document.createElement('div');
 //! _el.className = 'container';
 //! const _text = document.createTextNode('');
-//! createEffect(() => { _text.textContent = count(); });
+//! createRenderEffect(() => { _text.textContent = count(); });
 //! _el.appendChild(_text);
 //! ```
+//!
+//! ## Directives
+//! Namespaced attributes attach behavior to the created element instead of
+//! becoming props:
+//! - `use:name={value}` calls the directive function `name` in scope with the
+//!   element and an accessor: `name(el, () => value)` (a bare `use:name`
+//!   passes `() => true`). Directives run once, after children are appended.
+//! - `on:event={handler}` registers `handler` for `event` exactly as written
+//!   (`el.addEventListener("event", handler)`), so custom events with
+//!   uppercase letters or dashes work. `onClick`-style props are handled by
+//!   the runtime, which lowercases the event name.
+//!
+//! ## Raw HTML
+//! `innerHTML={html}` and `dangerouslySetInnerHTML={{ __html: html }}` on a
+//! DOM element set its content without escaping: `el.innerHTML = html`,
+//! inside `createRenderEffect` when `html` is reactive. The HTML replaces the
+//! element's children, so any JSX children are dropped (the analyzer warns
+//! about them). On components both are ordinary props.
 
-use crate::analyzer::Analysis;
-use crate::error::Result;
+use crate::analyzer::{Analysis, INNER_HTML_ATTRS};
+use crate::error::{CompilerError, Result};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use swc_core::ecma::ast::*;
-use swc_core::ecma::visit::{VisitMut, VisitMutWith, noop_visit_mut_type};
+use swc_core::ecma::visit::{Visit, VisitMut, VisitMutWith, VisitWith, noop_visit_mut_type};
+
+/// Module that generated helper calls (`createRenderEffect`, `forEach`) import from
+pub(crate) const RUNTIME_MODULE: &str = "velocity-runtime";
 
 /// Transformer that converts JSX to DOM operations
-#[allow(dead_code)]
 struct JsxTransformer {
     analysis: Analysis,
     element_counter: usize,
+    /// Runtime helpers referenced by generated code
+    helpers: BTreeSet<&'static str>,
+    /// Whether generated code calls `createElement`, which isn't imported:
+    /// JSX modules already bring it into scope themselves
+    creates_elements: bool,
 }
 
 impl JsxTransformer {
@@ -32,20 +58,137 @@ impl JsxTransformer {
         Self {
             analysis,
             element_counter: 0,
+            helpers: BTreeSet::new(),
+            creates_elements: false,
         }
     }
 
+    /// Reference a runtime helper, making sure it gets imported
+    fn helper(&mut self, name: &'static str) -> Expr {
+        self.helpers.insert(name);
+        ident_expr(name)
+    }
+
     /// Generate a unique element variable name
-    #[allow(dead_code)]
     fn next_element_name(&mut self) -> String {
         self.element_counter += 1;
         format!("_el{}", self.element_counter)
     }
 
-    /// Check if an identifier is reactive (signal or memo)
-    #[allow(dead_code)]
-    fn is_reactive(&self, name: &str) -> bool {
-        self.analysis.signals.contains(name) || self.analysis.memos.contains(name)
+    /// Check if an identifier refers to reactive state (a signal, memo or
+    /// signal tuple binding)
+    fn is_reactive(&self, ident: &Ident) -> bool {
+        let id = ident.to_id();
+        self.analysis.signals.contains(&id)
+            || self.analysis.memos.contains(&id)
+            || self.analysis.signal_tuples.contains(&id)
+    }
+
+    /// Check if an expression may read reactive state when evaluated.
+    ///
+    /// Any reference to a known signal/memo counts, as does any call, since
+    /// accessors passed through props are not visible to the analyzer.
+    fn is_reactive_expr(&self, expr: &Expr) -> bool {
+        let mut finder = ReactiveRefFinder {
+            transformer: self,
+            found: false,
+        };
+        expr.visit_with(&mut finder);
+        finder.found
+    }
+
+    /// Lower a `classList={{ name: cond }}` object into per-key
+    /// `el.classList.toggle(name, cond)` statements. Reactive conditions are
+    /// wrapped in `createRenderEffect` so the class follows the signal.
+    fn transform_class_list(&mut self, el: &str, obj: &ObjectLit) -> Vec<Stmt> {
+        let mut stmts = Vec::new();
+
+        for prop in &obj.props {
+            let (class_name, cond) = match prop {
+                PropOrSpread::Prop(prop) => match &**prop {
+                    Prop::KeyValue(kv) => {
+                        let name = match &kv.key {
+                            PropName::Ident(ident) => ident.sym.to_string(),
+                            PropName::Str(s) => s.value.to_string(),
+                            _ => continue,
+                        };
+                        (name, kv.value.clone())
+                    }
+                    Prop::Shorthand(ident) => {
+                        (ident.sym.to_string(), Box::new(Expr::Ident(ident.clone())))
+                    }
+                    _ => continue,
+                },
+                PropOrSpread::Spread(_) => continue,
+            };
+
+            let reactive = self.is_reactive_expr(&cond);
+
+            // el.classList.toggle("name", cond)
+            let toggle = call_expr(
+                Expr::Member(MemberExpr {
+                    span: Default::default(),
+                    obj: Box::new(Expr::Member(MemberExpr {
+                        span: Default::default(),
+                        obj: Box::new(ident_expr(el)),
+                        prop: MemberProp::Ident("classList".into()),
+                    })),
+                    prop: MemberProp::Ident("toggle".into()),
+                }),
+                vec![str_lit(&class_name), *cond],
+            );
+
+            let expr = if reactive {
+                call_expr(
+                    self.helper("createRenderEffect"),
+                    vec![Expr::Arrow(ArrowExpr {
+                        body: Box::new(BlockStmtOrExpr::Expr(Box::new(toggle))),
+                        ..Default::default()
+                    })],
+                )
+            } else {
+                toggle
+            };
+
+            stmts.push(Stmt::Expr(ExprStmt {
+                span: Default::default(),
+                expr: Box::new(expr),
+            }));
+        }
+
+        stmts
+    }
+
+    /// `el.innerHTML = html`, kept up to date by an effect when `html` is reactive
+    fn inner_html_stmt(&mut self, el: &str, html: Box<Expr>) -> Stmt {
+        let reactive = self.is_reactive_expr(&html);
+        let assign = Expr::Assign(AssignExpr {
+            span: Default::default(),
+            op: AssignOp::Assign,
+            left: AssignTarget::Simple(SimpleAssignTarget::Member(MemberExpr {
+                span: Default::default(),
+                obj: Box::new(ident_expr(el)),
+                prop: MemberProp::Ident("innerHTML".into()),
+            })),
+            right: html,
+        });
+
+        let expr = if reactive {
+            call_expr(
+                self.helper("createRenderEffect"),
+                vec![Expr::Arrow(ArrowExpr {
+                    body: Box::new(BlockStmtOrExpr::Expr(Box::new(assign))),
+                    ..Default::default()
+                })],
+            )
+        } else {
+            assign
+        };
+
+        Stmt::Expr(ExprStmt {
+            span: Default::default(),
+            expr: Box::new(expr),
+        })
     }
 
     /// Transform JSX element to createElement calls
@@ -99,36 +242,51 @@ impl JsxTransformer {
 
         // Props object - extract JSX attributes
         let mut prop_entries = Vec::new();
+        let mut class_list = None;
+        // `use:` directives and `on:` events, applied once the element exists
+        let mut directives = Vec::new();
+        // Raw HTML content, assigned to `innerHTML` in place of children
+        let mut inner_html = None;
 
         for attr in attrs {
             if let JSXAttrOrSpread::JSXAttr(jsx_attr) = attr {
                 // Get attribute name
                 let key_name = match &jsx_attr.name {
+                    // `key` is a reconciliation hint for list lowering, not a DOM attribute
+                    JSXAttrName::Ident(ident) if ident.sym == "key" => continue,
                     JSXAttrName::Ident(ident) => ident.sym.to_string(),
-                    _ => continue,
+                    JSXAttrName::JSXNamespacedName(name) => match &*name.ns.sym {
+                        "use" | "on" => {
+                            directives.push(jsx_attr);
+                            continue;
+                        }
+                        // Other namespaces (`xlink:href`) are plain attributes
+                        ns => format!("{}:{}", ns, name.name.sym),
+                    },
                 };
 
-                // Get attribute value
-                let value_expr = match &jsx_attr.value {
-                    Some(JSXAttrValue::Lit(lit)) => {
-                        // String literal like class="counter"
-                        Box::new(Expr::Lit(lit.clone()))
-                    }
-                    Some(JSXAttrValue::JSXExprContainer(container)) => {
-                        // Expression like onClick={handler}
-                        match &container.expr {
-                            JSXExpr::Expr(expr) => expr.clone(),
-                            _ => continue,
+                if INNER_HTML_ATTRS.contains(&key_name.as_str()) {
+                    inner_html = inner_html_value(&key_name, jsx_attr);
+                    continue;
+                }
+
+                // classList={{ ... }} is lowered to classList.toggle calls below
+                if key_name == "classList" {
+                    if let Some(JSXAttrValue::JSXExprContainer(JSXExprContainer {
+                        expr: JSXExpr::Expr(expr),
+                        ..
+                    })) = &jsx_attr.value
+                    {
+                        if let Expr::Object(obj) = &**expr {
+                            class_list = Some(obj.clone());
+                            continue;
                         }
                     }
-                    None => {
-                        // Boolean attribute like disabled
-                        Box::new(Expr::Lit(Lit::Bool(Bool {
-                            span: Default::default(),
-                            value: true,
-                        })))
-                    }
-                    _ => continue,
+                }
+
+                // Get attribute value
+                let Some(value_expr) = jsx_attr_value(&jsx_attr.value) else {
+                    continue;
                 };
 
                 // Create property
@@ -152,17 +310,13 @@ impl JsxTransformer {
         });
 
         // Children (simplified)
-        for child in children {
-            if let Some(child_expr) = self.transform_jsx_child(child) {
-                args.push(ExprOrSpread {
-                    spread: None,
-                    expr: Box::new(child_expr),
-                });
-            }
+        if inner_html.is_none() {
+            args.extend(children.iter().filter_map(|child| self.transform_jsx_child(child)));
         }
 
-        // Return createElement call
-        Expr::Call(CallExpr {
+        // createElement call
+        self.creates_elements = true;
+        let create = Expr::Call(CallExpr {
             span: Default::default(),
             ctxt: Default::default(),
             callee: Callee::Expr(Box::new(Expr::Ident(Ident {
@@ -173,67 +327,288 @@ impl JsxTransformer {
             }))),
             args,
             type_args: None,
-        })
+        });
+
+        if class_list.is_none() && directives.is_empty() && inner_html.is_none() {
+            return create;
+        }
+
+        // With a classList, directives or raw HTML the element needs a name, so wrap it
+        // in an IIFE:
+        // (() => { const _el = createElement(...); ...setup; return _el; })()
+        let el = self.next_element_name();
+        let mut stmts = vec![Stmt::Decl(Decl::Var(Box::new(VarDecl {
+            kind: VarDeclKind::Const,
+            decls: vec![VarDeclarator {
+                span: Default::default(),
+                name: Pat::Ident(BindingIdent {
+                    id: Ident::new_no_ctxt(el.as_str().into(), Default::default()),
+                    type_ann: None,
+                }),
+                init: Some(Box::new(create)),
+                definite: false,
+            }],
+            ..Default::default()
+        })))];
+        if let Some(class_list) = &class_list {
+            stmts.extend(self.transform_class_list(&el, class_list));
+        }
+        if let Some(html) = inner_html {
+            stmts.push(self.inner_html_stmt(&el, html));
+        }
+        stmts.extend(directives.into_iter().filter_map(|attr| directive_stmt(&el, attr)));
+        stmts.push(Stmt::Return(ReturnStmt {
+            span: Default::default(),
+            arg: Some(Box::new(ident_expr(&el))),
+        }));
+
+        call_expr(
+            Expr::Paren(ParenExpr {
+                span: Default::default(),
+                expr: Box::new(Expr::Arrow(ArrowExpr {
+                    body: Box::new(BlockStmtOrExpr::BlockStmt(BlockStmt {
+                        stmts,
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                })),
+            }),
+            vec![],
+        )
     }
 
     /// Transform a component element like <Counter />
+    ///
+    /// Attributes become props; reactive values are passed as getters so the
+    /// component reads them when (and wherever) it uses them. Children are a
+    /// `children` getter holding the single child or an array of them, with
+    /// reactive expression children lowered to functions the runtime keeps
+    /// live at the spot the component inserts `props.children`:
+    /// ```js
+    /// Wrapper({ title: "Hi", get children() { return [createElement("p", {}, "hi"), () => count()]; } })
+    /// ```
+    ///
+    /// With spread attributes the props are composed in source order with
+    /// `mergeProps`, each spread and each run of explicit props (children
+    /// last) being one source, so later ones win:
+    /// ```js
+    /// // <Button {...base} variant="primary" {...overrides} />
+    /// Button(mergeProps(base, { variant: "primary" }, overrides))
+    /// ```
+    /// `mergeProps` looks keys up through getters instead of copying them, so
+    /// reactive props of a spread object aren't read eagerly. As with
+    /// `mergeProps`, a prop that is `undefined` falls through to earlier
+    /// sources. Spread expressions are evaluated once, when the component is
+    /// created.
     fn transform_component_element(
         &mut self,
         name: &str,
-        _attrs: &[JSXAttrOrSpread],
-        _children: &[JSXElementChild],
+        attrs: &[JSXAttrOrSpread],
+        children: &[JSXElementChild],
     ) -> Expr {
-        // Call the component as a function with props
-        Expr::Call(CallExpr {
-            span: Default::default(),
-            ctxt: Default::default(),
-            callee: Callee::Expr(Box::new(Expr::Ident(Ident {
-                span: Default::default(),
-                ctxt: Default::default(),
-                sym: name.into(),
-                optional: false,
-            }))),
-            args: vec![
-                // Props object (simplified)
-                ExprOrSpread {
-                    spread: None,
-                    expr: Box::new(Expr::Object(ObjectLit {
-                        span: Default::default(),
-                        props: vec![],
-                    })),
+        let mut props = Vec::new();
+        // Merge sources in order when there are spreads; `props` holds the
+        // run of explicit props since the last one
+        let mut sources = Vec::new();
+
+        for attr in attrs {
+            match attr {
+                JSXAttrOrSpread::SpreadElement(spread) => {
+                    if !props.is_empty() {
+                        sources.push(object_expr(std::mem::take(&mut props)));
+                    }
+                    sources.push(*spread.expr.clone());
                 }
-            ],
-            type_args: None,
-        })
+                JSXAttrOrSpread::JSXAttr(jsx_attr) => {
+                    let key = match &jsx_attr.name {
+                        // `key` is a reconciliation hint for list lowering, not a prop
+                        JSXAttrName::Ident(ident) if ident.sym == "key" => continue,
+                        JSXAttrName::Ident(ident) => ident.sym.to_string(),
+                        JSXAttrName::JSXNamespacedName(name) => format!("{}:{}", name.ns.sym, name.name.sym),
+                    };
+                    let Some(value) = jsx_attr_value(&jsx_attr.value) else {
+                        continue;
+                    };
+                    let reactive = matches!(&jsx_attr.value, Some(JSXAttrValue::JSXExprContainer(_)))
+                        && !is_function_expr(&value)
+                        && self.is_reactive_expr(&value);
+                    props.push(if reactive { getter_prop(&key, *value) } else { value_prop(&key, value) });
+                }
+            }
+        }
+
+        // A function child is a render prop (`{(data) => <li>{data}</li>}`):
+        // it's passed as-is for the component to call, never wrapped
+        let mut children: Vec<ExprOrSpread> = children
+            .iter()
+            .filter_map(|child| {
+                let reactive = matches!(
+                    child,
+                    JSXElementChild::JSXExprContainer(JSXExprContainer { expr: JSXExpr::Expr(expr), .. })
+                        if !is_function_expr(expr) && self.is_reactive_expr(expr)
+                );
+                let mut child = self.transform_jsx_child(child)?;
+                if reactive {
+                    child.expr = Box::new(Expr::Arrow(ArrowExpr {
+                        body: Box::new(BlockStmtOrExpr::Expr(child.expr)),
+                        ..Default::default()
+                    }));
+                }
+                Some(child)
+            })
+            .collect();
+
+        let children = match children.len() {
+            0 => None,
+            1 if children[0].spread.is_none() => Some(*children.remove(0).expr),
+            _ => Some(Expr::Array(ArrayLit {
+                span: Default::default(),
+                elems: children.into_iter().map(Some).collect(),
+            })),
+        };
+        if let Some(children) = children {
+            props.push(getter_prop("children", children));
+        }
+
+        let props = if sources.is_empty() {
+            object_expr(props)
+        } else {
+            if !props.is_empty() {
+                sources.push(object_expr(props));
+            }
+            let merge_props = self.helper("mergeProps");
+            call_expr(merge_props, sources)
+        };
+        call_expr(ident_expr(name), vec![props])
     }
 
     /// Transform a JSX child element
-    fn transform_jsx_child(&mut self, child: &JSXElementChild) -> Option<Expr> {
-        match child {
-            JSXElementChild::JSXElement(elem) => {
-                Some(self.transform_jsx_element(elem))
-            }
-            JSXElementChild::JSXExprContainer(container) => {
-                match &container.expr {
-                    JSXExpr::Expr(expr) => Some((**expr).clone()),
-                    JSXExpr::JSXEmptyExpr(_) => None,
-                }
+    fn transform_jsx_child(&mut self, child: &JSXElementChild) -> Option<ExprOrSpread> {
+        let expr = match child {
+            JSXElementChild::JSXElement(elem) => self.transform_jsx_element(elem),
+            JSXElementChild::JSXFragment(frag) => self.transform_fragment(frag),
+            JSXElementChild::JSXExprContainer(container) => match &container.expr {
+                JSXExpr::Expr(expr) => (**expr).clone(),
+                JSXExpr::JSXEmptyExpr(_) => return None,
+            },
+            // {...items} spreads into the parent's children
+            JSXElementChild::JSXSpreadChild(spread) => {
+                return Some(ExprOrSpread {
+                    spread: Some(spread.span),
+                    expr: spread.expr.clone(),
+                });
             }
             JSXElementChild::JSXText(text) => {
                 let value = text.value.to_string().trim().to_string();
                 if value.is_empty() {
-                    None
-                } else {
-                    Some(Expr::Lit(Lit::Str(Str {
-                        span: Default::default(),
-                        value: value.into(),
-                        raw: None,
-                    })))
+                    return None;
                 }
+                str_lit(&value)
             }
-            _ => None,
+        };
+
+        Some(ExprOrSpread {
+            spread: None,
+            expr: Box::new(expr),
+        })
+    }
+
+    /// Transform `<>...</>` into an array of its children
+    fn transform_fragment(&mut self, frag: &JSXFragment) -> Expr {
+        Expr::Array(ArrayLit {
+            span: Default::default(),
+            elems: frag
+                .children
+                .iter()
+                .filter_map(|child| self.transform_jsx_child(child))
+                .map(Some)
+                .collect(),
+        })
+    }
+
+    /// Lower a reactive `{items().map(item => <li key={item.id} />)}` child to
+    /// `forEach(() => items(), item => ..., item => item.id)` so the list
+    /// re-renders with keyed reuse. Static arrays are left as a plain `.map`.
+    ///
+    /// Called before the callback's JSX is transformed so `key` can be taken
+    /// off the returned element.
+    fn transform_list_child(&mut self, container: &mut JSXExprContainer) {
+        let list = match &mut container.expr {
+            JSXExpr::Expr(expr) => map_call_parts(expr).map(|(source, callback)| {
+                let reactive = self.is_reactive_expr(source);
+                (reactive, callback_params(callback), take_returned_key(callback))
+            }),
+            JSXExpr::JSXEmptyExpr(_) => None,
+        };
+
+        container.visit_mut_children_with(self);
+
+        let Some((true, params, key)) = list else {
+            return;
+        };
+        let JSXExpr::Expr(expr) = &mut container.expr else {
+            return;
+        };
+        let Some((source, callback)) = map_call_parts(expr) else {
+            return;
+        };
+
+        let mut args = vec![
+            Expr::Arrow(ArrowExpr {
+                body: Box::new(BlockStmtOrExpr::Expr(Box::new(source.clone()))),
+                ..Default::default()
+            }),
+            callback.clone(),
+        ];
+        if let Some(key) = key {
+            args.push(Expr::Arrow(ArrowExpr {
+                params,
+                body: Box::new(BlockStmtOrExpr::Expr(key)),
+                ..Default::default()
+            }));
         }
+
+        **expr = call_expr(self.helper("forEach"), args);
+    }
+
+}
+
+/// Import runtime helpers generated code uses unless the module already binds them
+pub(crate) fn import_helpers<'a>(module: &mut Module, helpers: impl IntoIterator<Item = &'a str>) {
+    let bound = module_bindings(module);
+
+    let specifiers: Vec<ImportSpecifier> = helpers
+        .into_iter()
+        .filter(|name| !bound.contains(*name))
+        .map(|name| {
+            ImportSpecifier::Named(ImportNamedSpecifier {
+                span: Default::default(),
+                local: Ident::new_no_ctxt(name.into(), Default::default()),
+                imported: None,
+                is_type_only: false,
+            })
+        })
+        .collect();
+
+    if specifiers.is_empty() {
+        return;
     }
+
+    module.body.insert(
+        0,
+        ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+            span: Default::default(),
+            specifiers,
+            src: Box::new(Str {
+                span: Default::default(),
+                value: RUNTIME_MODULE.into(),
+                raw: None,
+            }),
+            type_only: false,
+            with: None,
+            phase: Default::default(),
+        })),
+    );
 }
 
 impl VisitMut for JsxTransformer {
@@ -246,128 +621,1743 @@ impl VisitMut for JsxTransformer {
         if let Expr::JSXElement(elem) = expr {
             let transformed = self.transform_jsx_element(elem);
             *expr = transformed;
-        } else if let Expr::JSXFragment(_frag) = expr {
-            // Handle fragments - for now, create an empty div
-            *expr = Expr::Call(CallExpr {
-                span: Default::default(),
-                ctxt: Default::default(),
-                callee: Callee::Expr(Box::new(Expr::Ident(Ident {
-                    span: Default::default(),
-                    ctxt: Default::default(),
-                    sym: "createElement".into(),
-                    optional: false,
-                }))),
-                args: vec![
-                    ExprOrSpread {
-                        spread: None,
-                        expr: Box::new(Expr::Lit(Lit::Str(Str {
-                            span: Default::default(),
-                            value: "div".into(),
-                            raw: None,
-                        }))),
-                    },
-                    ExprOrSpread {
-                        spread: None,
-                        expr: Box::new(Expr::Object(ObjectLit {
-                            span: Default::default(),
-                            props: vec![],
-                        })),
-                    },
-                ],
-                type_args: None,
-            });
+        } else if let Expr::JSXFragment(frag) = expr {
+            let transformed = self.transform_fragment(frag);
+            *expr = transformed;
+        }
+    }
+
+    /// List children need to see the callback's JSX before it is transformed
+    fn visit_mut_jsx_element_child(&mut self, child: &mut JSXElementChild) {
+        match child {
+            JSXElementChild::JSXExprContainer(container) => self.transform_list_child(container),
+            _ => child.visit_mut_children_with(self),
         }
     }
 }
 
-/// Transform a module by converting JSX to DOM operations
-pub fn transform(mut module: Module, analysis: &Analysis) -> Result<Module> {
-    let mut transformer = JsxTransformer::new(analysis.clone());
-    module.visit_mut_with(&mut transformer);
-    Ok(module)
+/// Split `source.map(callback)` into its parts
+fn map_call_parts(expr: &mut Expr) -> Option<(&mut Expr, &mut Expr)> {
+    let Expr::Call(CallExpr {
+        callee: Callee::Expr(callee),
+        args,
+        ..
+    }) = expr
+    else {
+        return None;
+    };
+    let Expr::Member(MemberExpr {
+        obj,
+        prop: MemberProp::Ident(prop),
+        ..
+    }) = &mut **callee
+    else {
+        return None;
+    };
+    if prop.sym != "map" || args.len() != 1 || args[0].spread.is_some() {
+        return None;
+    }
+
+    match &mut *args[0].expr {
+        callback @ (Expr::Arrow(_) | Expr::Fn(_)) => Some((&mut **obj, callback)),
+        _ => None,
+    }
 }
 
-/// TypeScript stripper - removes all TypeScript syntax
-struct TypeScriptStripper;
+/// Parameters of a `.map` callback, reused for the generated key function
+fn callback_params(callback: &Expr) -> Vec<Pat> {
+    match callback {
+        Expr::Arrow(arrow) => arrow.params.clone(),
+        Expr::Fn(func) => func.function.params.iter().map(|p| p.pat.clone()).collect(),
+        _ => Vec::new(),
+    }
+}
 
-impl VisitMut for TypeScriptStripper {
-    // Remove type annotations from function parameters
-    noop_visit_mut_type!();
+/// Find the JSX element a `.map` callback returns and remove its `key`
+/// attribute, returning the key expression.
+///
+/// The key becomes a function of its own, which can't see the callback's
+/// locals: a key reading one (`const id = item.id; return <li key={id} />`)
+/// is removed but not returned, so the list isn't keyed.
+fn take_returned_key(callback: &mut Expr) -> Option<Box<Expr>> {
+    let mut locals = DeclaredNames::default();
+    let mut returned = match callback {
+        Expr::Arrow(arrow) => match &mut *arrow.body {
+            BlockStmtOrExpr::Expr(expr) => Some(&mut **expr),
+            BlockStmtOrExpr::BlockStmt(block) => {
+                block.visit_with(&mut locals);
+                returned_expr(block)
+            }
+        },
+        Expr::Fn(func) => func.function.body.as_mut().and_then(|body| {
+            body.visit_with(&mut locals);
+            returned_expr(body)
+        }),
+        _ => None,
+    }?;
 
-    // Remove interface/type declarations from module items
-    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
-        items.retain(|item| {
-            !matches!(
-                item,
-                ModuleItem::Stmt(Stmt::Decl(Decl::TsInterface(_)))
-                    | ModuleItem::Stmt(Stmt::Decl(Decl::TsTypeAlias(_)))
-                    | ModuleItem::Stmt(Stmt::Decl(Decl::TsEnum(_)))
-                    | ModuleItem::Stmt(Stmt::Decl(Decl::TsModule(_)))
-            )
-        });
-        // Continue visiting remaining items
-        for item in items.iter_mut() {
-            item.visit_mut_children_with(self);
-        }
+    while let Expr::Paren(paren) = returned {
+        returned = &mut paren.expr;
     }
+    let Expr::JSXElement(elem) = returned else {
+        return None;
+    };
 
-    // Remove TypeScript-only expressions
-    fn visit_mut_expr(&mut self, expr: &mut Expr) {
-        // Remove TypeScript assertion operators (as, !)
-        if let Expr::TsAs(ts_as) = expr {
-            *expr = (*ts_as.expr).clone();
-        } else if let Expr::TsNonNull(ts_non_null) = expr {
-            *expr = (*ts_non_null.expr).clone();
-        } else if let Expr::TsTypeAssertion(ts_assert) = expr {
-            *expr = (*ts_assert.expr).clone();
-        } else if let Expr::TsConstAssertion(ts_const) = expr {
-            *expr = (*ts_const.expr).clone();
+    let attrs = &mut elem.opening.attrs;
+    let index = attrs.iter().position(|attr| {
+        matches!(attr, JSXAttrOrSpread::JSXAttr(JSXAttr { name: JSXAttrName::Ident(name), .. }) if name.sym == "key")
+    })?;
+    match attrs.remove(index) {
+        JSXAttrOrSpread::JSXAttr(JSXAttr {
+            value: Some(JSXAttrValue::JSXExprContainer(JSXExprContainer {
+                expr: JSXExpr::Expr(expr),
+                ..
+            })),
+            ..
+        }) => Some(expr),
+        JSXAttrOrSpread::JSXAttr(JSXAttr {
+            value: Some(JSXAttrValue::Lit(lit)),
+            ..
+        }) => Some(Box::new(Expr::Lit(lit))),
+        _ => None,
+    }
+    .filter(|key| !locals.is_read_by(key))
+}
+
+/// Visitor that records every name declared in a function body
+#[derive(Default)]
+struct DeclaredNames {
+    names: HashSet<String>,
+}
+
+impl DeclaredNames {
+    /// Whether `expr` reads any of the names
+    fn is_read_by(&self, expr: &Expr) -> bool {
+        struct Reads<'a>(&'a HashSet<String>, bool);
+        impl Visit for Reads<'_> {
+            fn visit_ident(&mut self, ident: &Ident) {
+                self.1 |= self.0.contains(ident.sym.as_ref());
+            }
         }
 
-        expr.visit_mut_children_with(self);
+        let mut reads = Reads(&self.names, false);
+        expr.visit_with(&mut reads);
+        reads.1
     }
 }
 
-/// Strip TypeScript syntax from a module
-pub fn strip_typescript(mut module: Module) -> Result<Module> {
-    let mut stripper = TypeScriptStripper;
-    module.visit_mut_with(&mut stripper);
-    Ok(module)
+impl Visit for DeclaredNames {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.names.insert(ident.id.sym.to_string());
+    }
+
+    fn visit_fn_decl(&mut self, decl: &FnDecl) {
+        self.names.insert(decl.ident.sym.to_string());
+        decl.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, decl: &ClassDecl) {
+        self.names.insert(decl.ident.sym.to_string());
+        decl.visit_children_with(self);
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{analyzer, parser};
+/// The expression returned by the last statement of a block
+fn returned_expr(block: &mut BlockStmt) -> Option<&mut Expr> {
+    match block.stmts.last_mut()? {
+        Stmt::Return(ReturnStmt { arg: Some(arg), .. }) => Some(&mut **arg),
+        _ => None,
+    }
+}
 
-    #[test]
-    fn test_transform_simple_jsx() {
-        let source = r#"
-            function Hello() {
-                return <div>Hello World</div>;
+/// Names bound at the top level of a module (imports and declarations)
+pub(crate) fn module_bindings(module: &Module) -> HashSet<String> {
+    let mut collector = BindingCollector::default();
+    for item in &module.body {
+        match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => {
+                for specifier in &import.specifiers {
+                    let local = match specifier {
+                        ImportSpecifier::Named(named) => &named.local,
+                        ImportSpecifier::Default(default) => &default.local,
+                        ImportSpecifier::Namespace(namespace) => &namespace.local,
+                    };
+                    collector.names.insert(local.sym.to_string());
+                }
             }
-        "#;
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. }))
+            | ModuleItem::Stmt(Stmt::Decl(decl)) => match decl {
+                Decl::Fn(func) => {
+                    collector.names.insert(func.ident.sym.to_string());
+                }
+                Decl::Class(class) => {
+                    collector.names.insert(class.ident.sym.to_string());
+                }
+                Decl::Var(var) => var.decls.iter().for_each(|d| d.name.visit_with(&mut collector)),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    collector.names
+}
 
-        let module = parser::parse(source, "test.tsx").unwrap();
-        let analysis = analyzer::analyze(&module).unwrap();
-        let result = transform(module, &analysis);
+/// Visitor that records every name bound by a pattern
+#[derive(Default)]
+struct BindingCollector {
+    names: HashSet<String>,
+}
 
-        assert!(result.is_ok());
+impl Visit for BindingCollector {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.names.insert(ident.id.sym.to_string());
     }
 
-    #[test]
-    fn test_transform_jsx_with_reactive_child() {
-        let source = r#"
-            function Counter() {
-                const [count, setCount] = createSignal(0);
-                return <div>{count}</div>;
-            }
-        "#;
+    // Default values are expressions, not bindings
+    fn visit_expr(&mut self, _: &Expr) {}
+}
 
-        let module = parser::parse(source, "test.tsx").unwrap();
-        let analysis = analyzer::analyze(&module).unwrap();
-        let result = transform(module, &analysis);
+/// Visitor that looks for reads of reactive state inside an expression
+struct ReactiveRefFinder<'a> {
+    transformer: &'a JsxTransformer,
+    found: bool,
+}
 
-        assert!(result.is_ok());
+impl Visit for ReactiveRefFinder<'_> {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if self.transformer.is_reactive(ident) {
+            self.found = true;
+        }
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        self.found = true;
+        call.visit_children_with(self);
+    }
+}
+
+/// Whether `expr` is a function literal, which defers its body already
+fn is_function_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Arrow(_) | Expr::Fn(_) => true,
+        Expr::Paren(paren) => is_function_expr(&paren.expr),
+        _ => false,
+    }
+}
+
+/// Build an identifier expression
+fn ident_expr(name: &str) -> Expr {
+    Expr::Ident(Ident::new_no_ctxt(name.into(), Default::default()))
+}
+
+/// Build a string literal expression
+fn str_lit(value: &str) -> Expr {
+    Expr::Lit(Lit::Str(Str {
+        span: Default::default(),
+        value: value.into(),
+        raw: None,
+    }))
+}
+
+/// Object key for a prop name, quoted unless it's a valid identifier
+fn prop_name(name: &str) -> PropName {
+    if Ident::verify_symbol(name).is_ok() {
+        PropName::Ident(IdentName::new(name.into(), Default::default()))
+    } else {
+        PropName::Str(Str {
+            span: Default::default(),
+            value: name.into(),
+            raw: None,
+        })
+    }
+}
+
+/// Build an object literal expression
+fn object_expr(props: Vec<PropOrSpread>) -> Expr {
+    Expr::Object(ObjectLit {
+        span: Default::default(),
+        props,
+    })
+}
+
+/// `name: value`
+fn value_prop(name: &str, value: Box<Expr>) -> PropOrSpread {
+    PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+        key: prop_name(name),
+        value,
+    })))
+}
+
+/// `get name() { return value; }`, evaluated each time the prop is read
+fn getter_prop(name: &str, value: Expr) -> PropOrSpread {
+    PropOrSpread::Prop(Box::new(Prop::Getter(GetterProp {
+        span: Default::default(),
+        key: prop_name(name),
+        type_ann: None,
+        body: Some(BlockStmt {
+            stmts: vec![Stmt::Return(ReturnStmt {
+                span: Default::default(),
+                arg: Some(Box::new(value)),
+            })],
+            ..Default::default()
+        }),
+    })))
+}
+
+/// Value of a JSX attribute as an expression; a bare attribute (`disabled`)
+/// is `true`
+fn jsx_attr_value(value: &Option<JSXAttrValue>) -> Option<Box<Expr>> {
+    match value {
+        // String literal like class="counter"
+        Some(JSXAttrValue::Lit(lit)) => Some(Box::new(Expr::Lit(lit.clone()))),
+        // Expression like onClick={handler}
+        Some(JSXAttrValue::JSXExprContainer(container)) => match &container.expr {
+            JSXExpr::Expr(expr) => Some(expr.clone()),
+            _ => None,
+        },
+        // Boolean attribute like disabled
+        None => Some(Box::new(Expr::Lit(Lit::Bool(Bool {
+            span: Default::default(),
+            value: true,
+        })))),
+        _ => None,
+    }
+}
+
+/// The HTML string set by an `innerHTML` or `dangerouslySetInnerHTML`
+/// attribute. `dangerouslySetInnerHTML={{ __html: html }}` yields `html`;
+/// any other object expression is read as `value.__html`.
+fn inner_html_value(name: &str, attr: &JSXAttr) -> Option<Box<Expr>> {
+    let value = jsx_attr_value(&attr.value)?;
+    if name != "dangerouslySetInnerHTML" {
+        return Some(value);
+    }
+    if let Expr::Object(obj) = &*value {
+        let html = obj.props.iter().find_map(|prop| match prop {
+            PropOrSpread::Prop(prop) => match &**prop {
+                Prop::KeyValue(kv) if matches!(&kv.key, PropName::Ident(key) if key.sym == "__html") => {
+                    Some(kv.value.clone())
+                }
+                Prop::Shorthand(ident) if ident.sym == "__html" => Some(Box::new(Expr::Ident(ident.clone()))),
+                _ => None,
+            },
+            PropOrSpread::Spread(_) => None,
+        });
+        if html.is_some() {
+            return html;
+        }
+    }
+    Some(Box::new(Expr::Member(MemberExpr {
+        span: Default::default(),
+        obj: value,
+        prop: MemberProp::Ident("__html".into()),
+    })))
+}
+
+/// Lower a `use:` or `on:` attribute on element `el`:
+/// - `use:tooltip={text}` → `tooltip(el, () => text)`
+/// - `on:my-event={handler}` → `el.addEventListener("my-event", handler)`
+fn directive_stmt(el: &str, attr: &JSXAttr) -> Option<Stmt> {
+    let JSXAttrName::JSXNamespacedName(name) = &attr.name else {
+        return None;
+    };
+    let value = jsx_attr_value(&attr.value)?;
+
+    let expr = match &*name.ns.sym {
+        "use" => call_expr(
+            ident_expr(&name.name.sym),
+            vec![
+                ident_expr(el),
+                Expr::Arrow(ArrowExpr {
+                    body: Box::new(BlockStmtOrExpr::Expr(value)),
+                    ..Default::default()
+                }),
+            ],
+        ),
+        // A bare `on:event` has no handler to register
+        "on" if attr.value.is_some() => call_expr(
+            Expr::Member(MemberExpr {
+                span: Default::default(),
+                obj: Box::new(ident_expr(el)),
+                prop: MemberProp::Ident("addEventListener".into()),
+            }),
+            vec![str_lit(&name.name.sym), *value],
+        ),
+        _ => return None,
+    };
+
+    Some(Stmt::Expr(ExprStmt {
+        span: Default::default(),
+        expr: Box::new(expr),
+    }))
+}
+
+/// Build a call expression with plain (non-spread) arguments
+fn call_expr(callee: Expr, args: Vec<Expr>) -> Expr {
+    Expr::Call(CallExpr {
+        span: Default::default(),
+        ctxt: Default::default(),
+        callee: Callee::Expr(Box::new(callee)),
+        args: args
+            .into_iter()
+            .map(|expr| ExprOrSpread {
+                spread: None,
+                expr: Box::new(expr),
+            })
+            .collect(),
+        type_args: None,
+    })
+}
+
+/// Transform a module by converting JSX to DOM operations.
+///
+/// `analysis` must come from [`analyze`](crate::analyzer::analyze) on this
+/// module after [`strip_typescript`].
+pub fn transform(module: Module, analysis: &Analysis) -> Result<Module> {
+    transform_with_helpers(module, analysis).map(|(module, _)| module)
+}
+
+/// [`transform`], also returning the runtime helpers the generated code
+/// calls (`createElement`, `createRenderEffect`, `forEach`), whether imported here
+/// or already bound by the module
+pub fn transform_with_helpers(mut module: Module, analysis: &Analysis) -> Result<(Module, HashSet<String>)> {
+    let mut transformer = JsxTransformer::new(analysis.clone());
+    module.visit_mut_with(&mut transformer);
+    import_helpers(&mut module, transformer.helpers.iter().copied());
+
+    let mut helpers: HashSet<String> = transformer.helpers.into_iter().map(String::from).collect();
+    if transformer.creates_elements {
+        helpers.insert("createElement".to_string());
+    }
+    Ok((module, helpers))
+}
+
+/// Label `createEffect`, `createRenderEffect`, `createComputed` and `createMemo` calls with
+/// the enclosing function and line (`Counter:12`), so DevTools and runtime
+/// errors can name them. Calls that already pass a label are left alone;
+/// outside any named function the file name is used.
+pub fn label_effects(module: &mut Module, source: &str, filename: &str) {
+    let file = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    let mut labeler = EffectLabeler {
+        source,
+        file: file.split('.').next().unwrap_or(file),
+        functions: Vec::new(),
+    };
+    module.visit_mut_with(&mut labeler);
+}
+
+/// Visitor behind [`label_effects`]
+struct EffectLabeler<'a> {
+    source: &'a str,
+    file: &'a str,
+    /// Names of the enclosing named functions, innermost last
+    functions: Vec<String>,
+}
+
+impl EffectLabeler<'_> {
+    /// Run `visit` with a method's name as the enclosing function, when the
+    /// name is static
+    fn with_method_name(&mut self, key: &PropName, visit: impl FnOnce(&mut Self)) {
+        let name = match key {
+            PropName::Ident(ident) => Some(ident.sym.to_string()),
+            PropName::Str(s) => Some(s.value.to_string()),
+            _ => None,
+        };
+        let Some(name) = name else {
+            visit(self);
+            return;
+        };
+        self.functions.push(name);
+        visit(self);
+        self.functions.pop();
+    }
+}
+
+impl VisitMut for EffectLabeler<'_> {
+    noop_visit_mut_type!();
+
+    fn visit_mut_fn_decl(&mut self, decl: &mut FnDecl) {
+        self.functions.push(decl.ident.sym.to_string());
+        decl.visit_mut_children_with(self);
+        self.functions.pop();
+    }
+
+    // const Counter = () => { ... }
+    fn visit_mut_var_declarator(&mut self, decl: &mut VarDeclarator) {
+        let named = match (&decl.name, decl.init.as_deref()) {
+            (Pat::Ident(name), Some(Expr::Arrow(_) | Expr::Fn(_))) => Some(name.id.sym.to_string()),
+            _ => None,
+        };
+        let Some(name) = named else {
+            decl.visit_mut_children_with(self);
+            return;
+        };
+        self.functions.push(name);
+        decl.visit_mut_children_with(self);
+        self.functions.pop();
+    }
+
+    // export default function Counter() { ... }
+    fn visit_mut_fn_expr(&mut self, expr: &mut FnExpr) {
+        let Some(ident) = &expr.ident else {
+            expr.visit_mut_children_with(self);
+            return;
+        };
+        self.functions.push(ident.sym.to_string());
+        expr.visit_mut_children_with(self);
+        self.functions.pop();
+    }
+
+    // const views = { counter() { ... } }
+    fn visit_mut_method_prop(&mut self, method: &mut MethodProp) {
+        self.with_method_name(&method.key, |labeler| method.function.visit_mut_with(labeler));
+    }
+
+    // class Views { counter() { ... } }
+    fn visit_mut_class_method(&mut self, method: &mut ClassMethod) {
+        self.with_method_name(&method.key, |labeler| method.function.visit_mut_with(labeler));
+    }
+
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        call.visit_mut_children_with(self);
+
+        let Callee::Expr(callee) = &call.callee else {
+            return;
+        };
+        let Expr::Ident(callee) = &**callee else {
+            return;
+        };
+        // Position of the label argument; `createEffect` has `timing` first
+        let label_index = match &*callee.sym {
+            "createEffect" => 2,
+            "createRenderEffect" | "createComputed" | "createMemo" => 1,
+            _ => return,
+        };
+        if call.args.is_empty() || call.args.len() > label_index || call.args.iter().any(|arg| arg.spread.is_some()) {
+            return;
+        }
+
+        let (line, _) = crate::diagnostics::line_column(self.source, call.span);
+        let owner = self.functions.last().map(String::as_str).unwrap_or(self.file);
+        while call.args.len() < label_index {
+            call.args.push(ExprOrSpread {
+                spread: None,
+                expr: Box::new(ident_expr("undefined")),
+            });
+        }
+        call.args.push(ExprOrSpread {
+            spread: None,
+            expr: Box::new(str_lit(&format!("{}:{}", owner, line))),
+        });
+    }
+}
+
+/// Wrap the body of every component, a function named with a capital
+/// letter, in `performance` marks and a measure named after it, so its
+/// renders show up by name in a DevTools profile:
+/// ```js
+/// function Counter(props) {
+///     mark("Counter:start");
+///     try {
+///         return ...;
+///     } finally {
+///         mark("Counter:end");
+///         measure("<Counter>", "Counter:start", "Counter:end");
+///     }
+/// }
+/// ```
+/// The start mark is the body's first statement, and the end mark and
+/// measure run however the body exits, by a return or a throw. Components
+/// are function declarations and function or arrow expressions assigned to
+/// a variable; an expression-bodied arrow gets a block returning the
+/// expression. `mark` and `measure` are imported from `velocity-runtime`.
+///
+/// Returns the runtime helpers the instrumentation calls: both, or none when
+/// the module has no components.
+pub fn instrument_components(module: &mut Module) -> &'static [&'static str] {
+    let mut instrumenter = ComponentInstrumenter { instrumented: false };
+    module.visit_mut_with(&mut instrumenter);
+    if !instrumenter.instrumented {
+        return &[];
+    }
+    import_helpers(module, PROFILE_HELPERS.iter().copied());
+    PROFILE_HELPERS
+}
+
+/// Runtime helpers [`instrument_components`] calls
+const PROFILE_HELPERS: &[&str] = &["mark", "measure"];
+
+/// Visitor behind [`instrument_components`]
+struct ComponentInstrumenter {
+    instrumented: bool,
+}
+
+impl ComponentInstrumenter {
+    fn instrument(&mut self, name: &str, body: BlockStmt) -> BlockStmt {
+        self.instrumented = true;
+        let (start, end) = (format!("{}:start", name), format!("{}:end", name));
+        let stmt = |expr: Expr| Stmt::Expr(ExprStmt {
+            span: Default::default(),
+            expr: Box::new(expr),
+        });
+
+        let finalizer = BlockStmt {
+            stmts: vec![
+                stmt(call_expr(ident_expr("mark"), vec![str_lit(&end)])),
+                stmt(call_expr(
+                    ident_expr("measure"),
+                    vec![str_lit(&format!("<{}>", name)), str_lit(&start), str_lit(&end)],
+                )),
+            ],
+            ..Default::default()
+        };
+        BlockStmt {
+            stmts: vec![
+                stmt(call_expr(ident_expr("mark"), vec![str_lit(&start)])),
+                Stmt::Try(Box::new(TryStmt {
+                    span: body.span,
+                    block: body,
+                    handler: None,
+                    finalizer: Some(finalizer),
+                })),
+            ],
+            ..Default::default()
+        }
+    }
+}
+
+/// Whether a function named `name` is a component
+pub(crate) fn is_component_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+impl VisitMut for ComponentInstrumenter {
+    noop_visit_mut_type!();
+
+    fn visit_mut_fn_decl(&mut self, decl: &mut FnDecl) {
+        decl.visit_mut_children_with(self);
+        if is_component_name(&decl.ident.sym) {
+            if let Some(body) = decl.function.body.take() {
+                decl.function.body = Some(self.instrument(&decl.ident.sym, body));
+            }
+        }
+    }
+
+    // export default function Counter() { ... }
+    fn visit_mut_fn_expr(&mut self, expr: &mut FnExpr) {
+        expr.visit_mut_children_with(self);
+        let Some(ident) = &expr.ident else {
+            return;
+        };
+        if is_component_name(&ident.sym) {
+            if let Some(body) = expr.function.body.take() {
+                expr.function.body = Some(self.instrument(&ident.sym, body));
+            }
+        }
+    }
+
+    // const Counter = () => ...
+    fn visit_mut_var_declarator(&mut self, decl: &mut VarDeclarator) {
+        decl.visit_mut_children_with(self);
+        let Pat::Ident(name) = &decl.name else {
+            return;
+        };
+        if !is_component_name(&name.id.sym) {
+            return;
+        }
+        match decl.init.as_deref_mut() {
+            Some(Expr::Arrow(arrow)) => {
+                let body = match std::mem::replace(&mut *arrow.body, BlockStmtOrExpr::BlockStmt(Default::default())) {
+                    BlockStmtOrExpr::BlockStmt(block) => block,
+                    BlockStmtOrExpr::Expr(expr) => BlockStmt {
+                        span: arrow.span,
+                        stmts: vec![Stmt::Return(ReturnStmt {
+                            span: arrow.span,
+                            arg: Some(expr),
+                        })],
+                        ..Default::default()
+                    },
+                };
+                *arrow.body = BlockStmtOrExpr::BlockStmt(self.instrument(&name.id.sym, body));
+            }
+            // A named function expression was instrumented under its own name
+            Some(Expr::Fn(FnExpr { ident: None, function })) => {
+                if let Some(body) = function.body.take() {
+                    function.body = Some(self.instrument(&name.id.sym, body));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// TypeScript stripper - removes all TypeScript syntax
+///
+/// Enums are runtime constructs, so instead of being stripped they are
+/// lowered to the usual object-with-reverse-mapping IIFE. `const enum`
+/// member accesses are inlined as literals and the declarations dropped,
+/// unless they're exported: other modules only see the object.
+#[derive(Default)]
+struct TypeScriptStripper {
+    /// Constant values of every `const enum`, by enum name then member name
+    const_enums: HashMap<String, HashMap<String, EnumValue>>,
+    /// Const enums exported from the module, which are lowered too
+    exported_const_enums: HashSet<String>,
+    /// First error hit while lowering (visitors can't return errors)
+    error: Option<CompilerError>,
+}
+
+impl TypeScriptStripper {
+    /// Check if a declaration only exists at the type level
+    fn is_type_only(decl: &Decl) -> bool {
+        matches!(
+            decl,
+            Decl::TsInterface(_) | Decl::TsTypeAlias(_) | Decl::TsModule(_)
+        )
+    }
+
+    /// Lower an enum declaration, returning `None` if it has no runtime output
+    fn lower_enum_decl(&mut self, decl: &TsEnumDecl) -> Option<(VarDecl, Stmt)> {
+        let name = decl.id.sym.as_ref();
+        if decl.declare || (self.const_enums.contains_key(name) && !self.exported_const_enums.contains(name)) {
+            return None;
+        }
+        match lower_enum(decl) {
+            Ok(lowered) => Some(lowered),
+            Err(e) => {
+                self.error.get_or_insert(e);
+                None
+            }
+        }
+    }
+}
+
+impl VisitMut for TypeScriptStripper {
+    // Types are dropped whole below, so there's no need to walk into them
+    noop_visit_mut_type!();
+
+    // Remove annotations (`x: T`, `(): T`), type parameters (`<T>`) and
+    // type arguments (`f<T>()`, `extends Base<T>`)
+    fn visit_mut_opt_ts_type_ann(&mut self, ann: &mut Option<Box<TsTypeAnn>>) {
+        *ann = None;
+    }
+
+    fn visit_mut_opt_ts_type_param_decl(&mut self, params: &mut Option<Box<TsTypeParamDecl>>) {
+        *params = None;
+    }
+
+    fn visit_mut_opt_ts_type_param_instantiation(&mut self, args: &mut Option<Box<TsTypeParamInstantiation>>) {
+        *args = None;
+    }
+
+    // Optional parameters (`x?: T`)
+    fn visit_mut_binding_ident(&mut self, ident: &mut BindingIdent) {
+        ident.id.optional = false;
+        ident.type_ann = None;
+    }
+
+    fn visit_mut_class(&mut self, class: &mut Class) {
+        class.implements.clear();
+        class.is_abstract = false;
+        class.visit_mut_children_with(self);
+    }
+
+    // Member modifiers: `private`, `readonly`, `override`, `x?`, `x!`
+    fn visit_mut_class_prop(&mut self, prop: &mut ClassProp) {
+        prop.accessibility = None;
+        prop.readonly = false;
+        prop.is_override = false;
+        prop.is_optional = false;
+        prop.definite = false;
+        prop.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_class_method(&mut self, method: &mut ClassMethod) {
+        method.accessibility = None;
+        method.is_override = false;
+        method.is_optional = false;
+        method.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_constructor(&mut self, constructor: &mut Constructor) {
+        constructor.accessibility = None;
+        constructor.visit_mut_children_with(self);
+    }
+
+    // Remove interface/type declarations and lower enums in module items
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        let mut lowered = Vec::with_capacity(items.len());
+
+        for item in items.drain(..) {
+            match item {
+                ModuleItem::Stmt(Stmt::Decl(decl)) if Self::is_type_only(&decl) => {}
+                ModuleItem::Stmt(Stmt::Decl(Decl::TsEnum(decl))) => {
+                    if let Some((var, init)) = self.lower_enum_decl(&decl) {
+                        lowered.push(ModuleItem::Stmt(Stmt::Decl(Decl::Var(Box::new(var)))));
+                        lowered.push(ModuleItem::Stmt(init));
+                    }
+                }
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                    span,
+                    decl: Decl::TsEnum(decl),
+                })) => {
+                    if let Some((var, init)) = self.lower_enum_decl(&decl) {
+                        lowered.push(ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                            span,
+                            decl: Decl::Var(Box::new(var)),
+                        })));
+                        lowered.push(ModuleItem::Stmt(init));
+                    }
+                }
+                item => lowered.push(item),
+            }
+        }
+        *items = lowered;
+
+        // Continue visiting remaining items
+        for item in items.iter_mut() {
+            item.visit_mut_children_with(self);
+        }
+    }
+
+    // Same as above for declarations inside function bodies and blocks
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        let mut lowered = Vec::with_capacity(stmts.len());
+
+        for stmt in stmts.drain(..) {
+            match stmt {
+                Stmt::Decl(decl) if Self::is_type_only(&decl) => {}
+                Stmt::Decl(Decl::TsEnum(decl)) => {
+                    if let Some((var, init)) = self.lower_enum_decl(&decl) {
+                        lowered.push(Stmt::Decl(Decl::Var(Box::new(var))));
+                        lowered.push(init);
+                    }
+                }
+                stmt => lowered.push(stmt),
+            }
+        }
+        *stmts = lowered;
+
+        for stmt in stmts.iter_mut() {
+            stmt.visit_mut_children_with(self);
+        }
+    }
+
+    // Remove TypeScript-only expressions
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        // Remove TypeScript assertion operators (as, !)
+        if let Expr::TsAs(ts_as) = expr {
+            *expr = (*ts_as.expr).clone();
+        } else if let Expr::TsNonNull(ts_non_null) = expr {
+            *expr = (*ts_non_null.expr).clone();
+        } else if let Expr::TsTypeAssertion(ts_assert) = expr {
+            *expr = (*ts_assert.expr).clone();
+        } else if let Expr::TsConstAssertion(ts_const) = expr {
+            *expr = (*ts_const.expr).clone();
+        }
+
+        // Inline const enum member accesses: Direction.Up → 0
+        if let Expr::Member(member) = expr {
+            if let Expr::Ident(obj) = &*member.obj {
+                let value = self
+                    .const_enums
+                    .get(obj.sym.as_ref())
+                    .zip(member_prop_name(&member.prop))
+                    .and_then(|(members, name)| members.get(&name));
+                if let Some(value) = value {
+                    *expr = value.to_expr();
+                    return;
+                }
+            }
+        }
+
+        expr.visit_mut_children_with(self);
+    }
+}
+
+/// Compile-time value of an enum member
+#[derive(Debug, Clone, PartialEq)]
+enum EnumValue {
+    Num(f64),
+    Str(String),
+}
+
+impl EnumValue {
+    fn to_expr(&self) -> Expr {
+        match self {
+            EnumValue::Num(value) if *value < 0.0 => Expr::Unary(UnaryExpr {
+                span: Default::default(),
+                op: UnaryOp::Minus,
+                arg: Box::new(EnumValue::Num(-value).to_expr()),
+            }),
+            EnumValue::Num(value) => Expr::Lit(Lit::Num(Number {
+                span: Default::default(),
+                value: *value,
+                raw: None,
+            })),
+            EnumValue::Str(value) => str_lit(value),
+        }
+    }
+}
+
+/// Name of a member property if it is static (`a.b` or `a["b"]`)
+fn member_prop_name(prop: &MemberProp) -> Option<String> {
+    match prop {
+        MemberProp::Ident(ident) => Some(ident.sym.to_string()),
+        MemberProp::Computed(ComputedPropName { expr, .. }) => match &**expr {
+            Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Member name of an enum member (`Red` or `"Red"`)
+fn enum_member_name(id: &TsEnumMemberId) -> String {
+    match id {
+        TsEnumMemberId::Ident(ident) => ident.sym.to_string(),
+        TsEnumMemberId::Str(s) => s.value.to_string(),
+    }
+}
+
+/// Evaluate an enum member initializer, allowing references to earlier members
+fn eval_enum_init(
+    expr: &Expr,
+    enum_name: &str,
+    members: &HashMap<String, EnumValue>,
+) -> Option<EnumValue> {
+    match expr {
+        Expr::Lit(Lit::Num(num)) => Some(EnumValue::Num(num.value)),
+        Expr::Lit(Lit::Str(s)) => Some(EnumValue::Str(s.value.to_string())),
+        Expr::Tpl(tpl) if tpl.exprs.is_empty() => tpl
+            .quasis
+            .first()
+            .and_then(|q| q.cooked.as_ref())
+            .map(|cooked| EnumValue::Str(cooked.to_string())),
+        Expr::Paren(paren) => eval_enum_init(&paren.expr, enum_name, members),
+        Expr::Ident(ident) => members.get(ident.sym.as_ref()).cloned(),
+        Expr::Member(member) => match &*member.obj {
+            Expr::Ident(obj) if obj.sym == enum_name => {
+                members.get(&member_prop_name(&member.prop)?).cloned()
+            }
+            _ => None,
+        },
+        Expr::Unary(unary) => match (unary.op, eval_enum_init(&unary.arg, enum_name, members)?) {
+            (UnaryOp::Minus, EnumValue::Num(n)) => Some(EnumValue::Num(-n)),
+            (UnaryOp::Plus, EnumValue::Num(n)) => Some(EnumValue::Num(n)),
+            (UnaryOp::Tilde, EnumValue::Num(n)) => Some(EnumValue::Num(!(n as i32) as f64)),
+            _ => None,
+        },
+        Expr::Bin(bin) => {
+            let left = eval_enum_init(&bin.left, enum_name, members)?;
+            let right = eval_enum_init(&bin.right, enum_name, members)?;
+            match (left, right) {
+                (EnumValue::Str(l), EnumValue::Str(r)) if bin.op == BinaryOp::Add => {
+                    Some(EnumValue::Str(l + r.as_str()))
+                }
+                (EnumValue::Num(l), EnumValue::Num(r)) => {
+                    let (li, ri) = (l as i32, r as i32);
+                    let value = match bin.op {
+                        BinaryOp::Add => l + r,
+                        BinaryOp::Sub => l - r,
+                        BinaryOp::Mul => l * r,
+                        BinaryOp::Div => l / r,
+                        BinaryOp::Mod => l % r,
+                        BinaryOp::BitOr => (li | ri) as f64,
+                        BinaryOp::BitAnd => (li & ri) as f64,
+                        BinaryOp::BitXor => (li ^ ri) as f64,
+                        BinaryOp::LShift => li.wrapping_shl(ri as u32) as f64,
+                        BinaryOp::RShift => li.wrapping_shr(ri as u32) as f64,
+                        _ => return None,
+                    };
+                    Some(EnumValue::Num(value))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Compute the value of every enum member, or `None` if any is not constant
+fn eval_enum_members(decl: &TsEnumDecl) -> Option<Vec<(String, EnumValue)>> {
+    let enum_name = decl.id.sym.as_ref();
+    let mut known = HashMap::new();
+    let mut values = Vec::with_capacity(decl.members.len());
+    let mut next = Some(0.0);
+
+    for member in &decl.members {
+        let name = enum_member_name(&member.id);
+        let value = match &member.init {
+            Some(init) => eval_enum_init(init, enum_name, &known)?,
+            None => EnumValue::Num(next?),
+        };
+        next = match value {
+            EnumValue::Num(n) => Some(n + 1.0),
+            EnumValue::Str(_) => None,
+        };
+        known.insert(name.clone(), value.clone());
+        values.push((name, value));
+    }
+
+    Some(values)
+}
+
+/// Lower `enum Color { Red, Green = "g" }` to:
+/// ```js
+/// var Color;
+/// (function(Color) {
+///     Color[Color["Red"] = 0] = "Red";
+///     Color["Green"] = "g";
+/// })(Color || (Color = {}));
+/// ```
+fn lower_enum(decl: &TsEnumDecl) -> Result<(VarDecl, Stmt)> {
+    let enum_name = decl.id.sym.as_ref();
+    let member = |name: &str| {
+        MemberExpr {
+            span: Default::default(),
+            obj: Box::new(ident_expr(enum_name)),
+            prop: MemberProp::Computed(ComputedPropName {
+                span: Default::default(),
+                expr: Box::new(str_lit(name)),
+            }),
+        }
+    };
+    let assign = |target: MemberExpr, value: Expr| {
+        Expr::Assign(AssignExpr {
+            span: Default::default(),
+            op: AssignOp::Assign,
+            left: AssignTarget::Simple(SimpleAssignTarget::Member(target)),
+            right: Box::new(value),
+        })
+    };
+
+    let mut known = HashMap::new();
+    let mut next = Some(0.0);
+    let mut stmts = Vec::with_capacity(decl.members.len());
+    let mut member_refs = EnumMemberRefs {
+        enum_name,
+        members: decl.members.iter().map(|member| enum_member_name(&member.id)).collect(),
+    };
+
+    for enum_member in &decl.members {
+        let name = enum_member_name(&enum_member.id);
+        let value = match &enum_member.init {
+            Some(init) => eval_enum_init(init, enum_name, &known),
+            None => Some(EnumValue::Num(next.ok_or_else(|| {
+                CompilerError::TransformError(format!(
+                    "Enum member '{}.{}' must have an initializer",
+                    enum_name, name
+                ))
+            })?)),
+        };
+
+        next = match &value {
+            Some(EnumValue::Num(n)) => Some(n + 1.0),
+            _ => None,
+        };
+
+        let expr = match &value {
+            // String members have no reverse mapping
+            Some(EnumValue::Str(_)) => assign(member(&name), value.as_ref().unwrap().to_expr()),
+            // Color[Color["Red"] = 0] = "Red"
+            _ => {
+                let value_expr = match &value {
+                    Some(value) => value.to_expr(),
+                    None => {
+                        let mut init = (**enum_member.init.as_ref().unwrap()).clone();
+                        init.visit_mut_with(&mut member_refs);
+                        init
+                    }
+                };
+                let forward = assign(member(&name), value_expr);
+                let reverse = MemberExpr {
+                    span: Default::default(),
+                    obj: Box::new(ident_expr(enum_name)),
+                    prop: MemberProp::Computed(ComputedPropName {
+                        span: Default::default(),
+                        expr: Box::new(forward),
+                    }),
+                };
+                assign(reverse, str_lit(&name))
+            }
+        };
+
+        if let Some(value) = value {
+            known.insert(name, value);
+        }
+        stmts.push(Stmt::Expr(ExprStmt {
+            span: Default::default(),
+            expr: Box::new(expr),
+        }));
+    }
+
+    let binding = || {
+        Pat::Ident(BindingIdent {
+            id: Ident::new_no_ctxt(enum_name.into(), Default::default()),
+            type_ann: None,
+        })
+    };
+
+    let var = VarDecl {
+        kind: VarDeclKind::Var,
+        decls: vec![VarDeclarator {
+            span: Default::default(),
+            name: binding(),
+            init: None,
+            definite: false,
+        }],
+        ..Default::default()
+    };
+
+    // Color || (Color = {})
+    let target = Expr::Bin(BinExpr {
+        span: Default::default(),
+        op: BinaryOp::LogicalOr,
+        left: Box::new(ident_expr(enum_name)),
+        right: Box::new(Expr::Paren(ParenExpr {
+            span: Default::default(),
+            expr: Box::new(Expr::Assign(AssignExpr {
+                span: Default::default(),
+                op: AssignOp::Assign,
+                left: AssignTarget::Simple(SimpleAssignTarget::Ident(BindingIdent {
+                    id: Ident::new_no_ctxt(enum_name.into(), Default::default()),
+                    type_ann: None,
+                })),
+                right: Box::new(Expr::Object(ObjectLit {
+                    span: Default::default(),
+                    props: vec![],
+                })),
+            })),
+        })),
+    });
+
+    let iife = call_expr(
+        Expr::Paren(ParenExpr {
+            span: Default::default(),
+            expr: Box::new(Expr::Fn(FnExpr {
+                ident: None,
+                function: Box::new(Function {
+                    params: vec![Param::from(binding())],
+                    body: Some(BlockStmt {
+                        stmts,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+            })),
+        }),
+        vec![target],
+    );
+
+    Ok((
+        var,
+        Stmt::Expr(ExprStmt {
+            span: Default::default(),
+            expr: Box::new(iife),
+        }),
+    ))
+}
+
+/// Rewrites the bare member names an enum initializer can refer to into
+/// accesses on the enum: `B = A + f()` → `Color["B"] = Color.A + f()`
+struct EnumMemberRefs<'a> {
+    enum_name: &'a str,
+    members: HashSet<String>,
+}
+
+impl EnumMemberRefs<'_> {
+    fn member(&self, name: &str) -> Expr {
+        Expr::Member(MemberExpr {
+            span: Default::default(),
+            obj: Box::new(ident_expr(self.enum_name)),
+            prop: MemberProp::Ident(IdentName::new(name.into(), Default::default())),
+        })
+    }
+}
+
+impl VisitMut for EnumMemberRefs<'_> {
+    noop_visit_mut_type!();
+
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Ident(ident) if self.members.contains(ident.sym.as_ref()) => *expr = self.member(&ident.sym),
+            _ => expr.visit_mut_children_with(self),
+        }
+    }
+
+    // { A } → { A: Color.A }
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        match prop {
+            Prop::Shorthand(ident) if self.members.contains(ident.sym.as_ref()) => {
+                *prop = Prop::KeyValue(KeyValueProp {
+                    key: PropName::Ident(IdentName::new(ident.sym.clone(), Default::default())),
+                    value: Box::new(self.member(&ident.sym)),
+                });
+            }
+            _ => prop.visit_mut_children_with(self),
+        }
+    }
+}
+
+/// Visitor that records the values of every `const enum` in a module, and
+/// which names the module exports
+#[derive(Default)]
+struct ConstEnumCollector {
+    const_enums: HashMap<String, HashMap<String, EnumValue>>,
+    exported: HashSet<String>,
+}
+
+impl Visit for ConstEnumCollector {
+    // export const enum Flags { ... }
+    fn visit_export_decl(&mut self, export: &ExportDecl) {
+        if let Decl::TsEnum(decl) = &export.decl {
+            self.exported.insert(decl.id.sym.to_string());
+        }
+        export.visit_children_with(self);
+    }
+
+    // export { Flags }
+    fn visit_named_export(&mut self, export: &NamedExport) {
+        if export.src.is_some() {
+            return;
+        }
+        for specifier in &export.specifiers {
+            if let ExportSpecifier::Named(ExportNamedSpecifier { orig: ModuleExportName::Ident(orig), .. }) = specifier {
+                self.exported.insert(orig.sym.to_string());
+            }
+        }
+    }
+
+    fn visit_ts_enum_decl(&mut self, decl: &TsEnumDecl) {
+        // Const enums with non-constant members are lowered like regular enums
+        if decl.is_const {
+            if let Some(values) = eval_enum_members(decl) {
+                self.const_enums
+                    .insert(decl.id.sym.to_string(), values.into_iter().collect());
+            }
+        }
+    }
+}
+
+/// Strip TypeScript syntax from a module. The other stages don't
+/// understand type syntax, so this runs first.
+pub fn strip_typescript(mut module: Module) -> Result<Module> {
+    let mut collector = ConstEnumCollector::default();
+    module.visit_with(&mut collector);
+
+    let mut stripper = TypeScriptStripper {
+        const_enums: collector.const_enums,
+        exported_const_enums: collector.exported,
+        error: None,
+    };
+    module.visit_mut_with(&mut stripper);
+
+    match stripper.error {
+        Some(e) => Err(e),
+        None => Ok(module),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analyzer, codegen, parser, CompilerOptions};
+
+    /// Parse, analyze and transform `source`, returning the generated JS
+    fn transform_to_js(source: &str) -> String {
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyzer::analyze(&module).unwrap();
+        let transformed = transform(module, &analysis).unwrap();
+        codegen::generate(&transformed, &CompilerOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_transform_simple_jsx() {
+        let source = r#"
+            function Hello() {
+                return <div>Hello World</div>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyzer::analyze(&module).unwrap();
+        let result = transform(module, &analysis);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_transform_jsx_with_reactive_child() {
+        let source = r#"
+            function Counter() {
+                const [count, setCount] = createSignal(0);
+                return <div>{count}</div>;
+            }
+        "#;
+
+        let module = parser::parse(source, "test.tsx").unwrap();
+        let analysis = analyzer::analyze(&module).unwrap();
+        let result = transform(module, &analysis);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_transform_class_list_reactive() {
+        let source = r#"
+            function Item() {
+                const [isActive, setActive] = createSignal(false);
+                return <li classList={{ active: isActive() }}>Item</li>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(code.contains("createRenderEffect(()=>_el1.classList.toggle(\"active\", isActive()))"));
+        assert!(!code.contains("\"classList\""));
+    }
+
+    #[test]
+    fn test_transform_class_list_static() {
+        let source = r#"
+            function Item() {
+                return <li classList={{ selected: true, "is-open": false }}>Item</li>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(code.contains("_el1.classList.toggle(\"selected\", true);"));
+        assert!(code.contains("_el1.classList.toggle(\"is-open\", false);"));
+        assert!(!code.contains("createRenderEffect"));
+    }
+
+    #[test]
+    fn test_transform_directives_and_namespaced_events() {
+        let source = r#"
+            function Menu() {
+                return <div use:clickOutside={close} use:focusTrap on:menuOpen={log} xlink:href="/icons.svg">Menu</div>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(code.contains("clickOutside(_el1, ()=>close);"));
+        assert!(code.contains("focusTrap(_el1, ()=>true);"));
+        assert!(code.contains("_el1.addEventListener(\"menuOpen\", log);"));
+        assert!(code.contains("\"xlink:href\": \"/icons.svg\""));
+        assert!(!code.contains("\"use:"));
+    }
+
+    #[test]
+    fn test_transform_inner_html() {
+        let source = r#"
+            function Post(props) {
+                const [body, setBody] = createSignal("<p>loading</p>");
+                return (
+                    <article>
+                        <div innerHTML={body()} />
+                        <div dangerouslySetInnerHTML={{ __html: props.summary }}>ignored</div>
+                        <div dangerouslySetInnerHTML={markup} />
+                        <Card innerHTML="<b>prop</b>" />
+                    </article>
+                );
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(code.contains("createRenderEffect(()=>_el1.innerHTML = body());"), "{}", code);
+        assert!(code.contains("_el2.innerHTML = props.summary;"), "{}", code);
+        assert!(code.contains("_el3.innerHTML = markup.__html;"), "{}", code);
+        assert!(!code.contains("ignored"));
+        assert!(!code.contains("\"innerHTML\""));
+        assert!(!code.contains("\"dangerouslySetInnerHTML\""));
+        // On components it's an ordinary prop
+        assert!(code.contains(r#"innerHTML: "<b>prop</b>""#));
+    }
+
+    #[test]
+    fn test_transform_jsx_in_ternary() {
+        let source = r#"
+            function App() {
+                const [a, setA] = createSignal(true);
+                return <div>{a() ? <X /> : <Y />}</div>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(code.contains("a() ? X({}) : Y({})"));
+        assert!(!code.contains("<X"));
+    }
+
+    #[test]
+    fn test_transform_jsx_in_logical_and() {
+        let source = r#"
+            function App(props) {
+                return <div>{props.cond && <Z />}</div>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(code.contains("props.cond && Z({})"));
+    }
+
+    #[test]
+    fn test_transform_jsx_in_length_ternary() {
+        let source = r#"
+            function App(props) {
+                return <div>{props.list.length ? <List /> : <Empty />}</div>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(code.contains("props.list.length ? List({}) : Empty({})"));
+    }
+
+    #[test]
+    fn test_transform_component_props_and_children() {
+        let source = r#"
+            function App() {
+                const [count, setCount] = createSignal(0);
+                return (
+                    <Wrapper title="Hi" count={count()} data-id={7} {...rest} key="w">
+                        <p>hi</p>
+                        {count()}
+                        {label}
+                    </Wrapper>
+                );
+            }
+            function Single() {
+                return <Wrapper><p>only</p></Wrapper>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+        let compact: String = code.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        assert!(compact.contains(r#"Wrapper(mergeProps({ title: "Hi", get count () { return count(); }, "data-id": 7 }, rest, { get children () { return [ createElement("p", {}, "hi"), ()=>count(), label ]; } }))"#), "{}", code);
+        assert!(compact.contains(r#"Wrapper({ get children () { return createElement("p", {}, "only"); } })"#), "{}", code);
+    }
+
+    #[test]
+    fn test_transform_component_spread_props() {
+        let source = r#"
+            function App() {
+                const [size, setSize] = createSignal("md");
+                return <Button {...base} variant="primary" size={size()} {...overrides} />;
+            }
+            function Forward(props) {
+                return <Button {...props}>go</Button>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+        let compact: String = code.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        assert!(compact.contains(r#"Button(mergeProps(base, { variant: "primary", get size () { return size(); } }, overrides))"#), "{}", code);
+        assert!(compact.contains(r#"Button(mergeProps(props, { get children () { return "go"; } }))"#), "{}", code);
+        assert!(compact.contains(r#"import { mergeProps } from "velocity-runtime""#), "{}", code);
+        assert!(!code.contains("..."), "{}", code);
+    }
+
+    #[test]
+    fn test_transform_render_prop_children() {
+        let source = r#"
+            function App() {
+                const [user, setUser] = createSignal(null);
+                return (
+                    <DataProvider source={user()} fallback={() => <p>loading</p>}>
+                        {(data) => <div class="row">{data.name}</div>}
+                    </DataProvider>
+                );
+            }
+            function List() {
+                return <Each items={rows}>{function (row, index) { return <li>{index}</li>; }}</Each>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+        let compact: String = code.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        // Function props and children are passed through, with their JSX transformed
+        assert!(compact.contains(r#"fallback: ()=>createElement("p", {}, "loading")"#), "{}", code);
+        assert!(compact.contains(r#"get children () { return (data)=>createElement("div", { "class": "row" }, data.name); }"#), "{}", code);
+        assert!(compact.contains(r#"get children () { return function(row, index) { return createElement("li", {}, index); }; }"#), "{}", code);
+        assert!(!code.contains("<"), "{}", code);
+    }
+
+    #[test]
+    fn test_transform_jsx_in_ternary_outside_return() {
+        let source = r#"
+            function App(props) {
+                const el = props.ok ? <span>yes</span> : <em>no</em>;
+                return el;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(code.contains(r#"props.ok ? createElement("span", {}, "yes") : createElement("em", {}, "no")"#));
+    }
+
+    fn strip_to_js(source: &str) -> String {
+        let module = parser::parse(source, "test.ts").unwrap();
+        let stripped = strip_typescript(module).unwrap();
+        codegen::generate(&stripped, &CompilerOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_strip_numeric_enum() {
+        let code = strip_to_js("enum Color { Red, Green = 5, Blue }");
+
+        assert!(code.contains("var Color;"));
+        assert!(code.contains(r#"Color[Color["Red"] = 0] = "Red";"#));
+        assert!(code.contains(r#"Color[Color["Green"] = 5] = "Green";"#));
+        assert!(code.contains(r#"Color[Color["Blue"] = 6] = "Blue";"#));
+        assert!(code.contains("(Color || (Color = {}))"));
+    }
+
+    #[test]
+    fn test_strip_string_enum() {
+        let code = strip_to_js(r#"export enum Direction { Up = "UP", Down = "DOWN" }"#);
+
+        assert!(code.contains("export var Direction;"));
+        assert!(code.contains(r#"Direction["Up"] = "UP";"#));
+        assert!(!code.contains(r#"Direction[Direction["Up"]"#));
+    }
+
+    #[test]
+    fn test_strip_const_enum_inlines_members() {
+        let source = r#"
+            const enum Flags { None = 0, A = 1 << 0, B = 1 << 1, AB = A | B }
+            const mask = Flags.AB;
+            const none = Flags["None"];
+        "#;
+
+        let code = strip_to_js(source);
+
+        assert!(!code.contains("Flags"));
+        assert!(code.contains("const mask = 3;"));
+        assert!(code.contains("const none = 0;"));
+    }
+
+    #[test]
+    fn test_strip_enum_computed_member_refers_to_members() {
+        let code = strip_to_js("enum E { A = 1, B = A + f(), C = g({ A }) }");
+
+        assert!(code.contains(r#"E[E["B"] = E.A + f()] = "B";"#), "{}", code);
+        assert!(code.contains(r#"E[E["C"] = g({"#), "{}", code);
+        assert!(code.contains("A: E.A"), "{}", code);
+    }
+
+    #[test]
+    fn test_strip_exported_const_enum_keeps_object() {
+        let source = r#"
+            export const enum Flags { A = 1, B = 2 }
+            const enum Local { C = 3 }
+            export { Local };
+            const mask = Flags.A | Local.C;
+        "#;
+
+        let code = strip_to_js(source);
+
+        assert!(code.contains("export var Flags;"), "{}", code);
+        assert!(code.contains(r#"Flags[Flags["B"] = 2] = "B";"#), "{}", code);
+        assert!(code.contains("var Local;"), "{}", code);
+        assert!(code.contains("const mask = 1 | 3;"), "{}", code);
+    }
+
+    #[test]
+    fn test_strip_type_annotations_and_arguments() {
+        let source = r#"
+            const [count, setCount] = createSignal<number>(0);
+            const cache = new Map<string, number>();
+            function identity<T>(value: T, label?: string): T { return value; }
+            const parse = (text: string): number => Number(text);
+            abstract class Store<T> extends Base<T> implements Disposable {
+                private readonly items: T[] = [];
+                declared!: string;
+                public override get(index: number): T { return this.items[index]; }
+            }
+        "#;
+
+        let code = strip_to_js(source);
+
+        assert!(code.contains("createSignal(0)"), "{}", code);
+        assert!(code.contains("new Map()"), "{}", code);
+        assert!(code.contains("function identity(value, label) {"), "{}", code);
+        assert!(code.contains("const parse = (text)=>Number(text);"), "{}", code);
+        assert!(code.contains("class Store extends Base {"), "{}", code);
+        assert!(code.contains("items = [];"), "{}", code);
+        assert!(code.contains("get(index) {"), "{}", code);
+        for ts in [":", "<", "private", "readonly", "abstract", "override", "implements", "!"] {
+            assert!(!code.contains(ts), "`{}` left in {}", ts, code);
+        }
+    }
+
+    #[test]
+    fn test_strip_enum_missing_initializer() {
+        let module = parser::parse(r#"enum E { A = "a", B }"#, "test.ts").unwrap();
+
+        assert!(strip_typescript(module).is_err());
+    }
+
+    #[test]
+    fn test_transform_reactive_map_to_keyed_for_each() {
+        let source = r#"
+            function List() {
+                const [items, setItems] = createSignal([]);
+                return <ul>{items().map(item => <li key={item.id}>{item.text}</li>)}</ul>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(code.contains(r#"import { forEach } from "velocity-runtime";"#));
+        assert!(code.contains(
+            r#"forEach(()=>items(), (item)=>createElement("li", {}, item.text), (item)=>item.id)"#
+        ));
+    }
+
+    #[test]
+    fn test_transform_keyed_list_skips_key_reading_callback_locals() {
+        let source = r#"
+            function List() {
+                const [items, setItems] = createSignal([]);
+                return <ul>{items().map(item => { const id = item.id; return <li key={id}>{id}</li>; })}</ul>;
+            }
+            function Rows() {
+                const [rows] = createSignal([]);
+                return <ul>{rows().map(row => { const label = row.label; return <li key={row.id}>{label}</li>; })}</ul>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(!code.contains("(item)=>id"), "{}", code);
+        assert!(code.contains(r#"return createElement("li", {}, id);"#), "{}", code);
+        assert!(code.contains("}, (row)=>row.id))"), "{}", code);
+    }
+
+    #[test]
+    fn test_transform_static_map_stays_plain() {
+        let source = r#"
+            function List(props) {
+                return <ul>{props.items.map(item => <li key={item}>{item}</li>)}</ul>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(!code.contains("forEach"));
+        assert!(code.contains(r#"props.items.map((item)=>createElement("li", {}, item))"#));
+    }
+
+    #[test]
+    fn test_label_effects() {
+        let source = r#"import { createEffect, createMemo } from "velocity-runtime";
+function Counter() {
+    createEffect(() => console.log(count()));
+    const double = createMemo(() => count() * 2);
+    createEffect(() => save(), "microtask");
+    createEffect(() => save(), undefined, "custom");
+}
+const useTimer = () => createRenderEffect(() => tick());
+createEffect(() => ready());
+const views = { Panel() { createEffect(() => open()); } };
+class Views { Modal() { createEffect(() => close()); } }
+export default function Dialog() { createEffect(() => focus()); }
+function Form() { createComputed(() => setFull(first())); }
+"#;
+
+        let mut module = parser::parse(source, "src/App.tsx").unwrap();
+        label_effects(&mut module, source, "src/App.tsx");
+        let code = codegen::generate(&module, &CompilerOptions::default()).unwrap();
+
+        assert!(code.contains(r#"createEffect(()=>console.log(count()), undefined, "Counter:3")"#));
+        assert!(code.contains(r#"createMemo(()=>count() * 2, "Counter:4")"#));
+        assert!(code.contains(r#"createEffect(()=>save(), "microtask", "Counter:5")"#));
+        assert!(code.contains(r#"createEffect(()=>save(), undefined, "custom")"#));
+        assert!(code.contains(r#"createRenderEffect(()=>tick(), "useTimer:8")"#));
+        assert!(code.contains(r#"createEffect(()=>ready(), undefined, "App:9")"#));
+        assert!(code.contains(r#"createEffect(()=>open(), undefined, "Panel:10")"#));
+        assert!(code.contains(r#"createEffect(()=>close(), undefined, "Modal:11")"#));
+        assert!(code.contains(r#"createEffect(()=>focus(), undefined, "Dialog:12")"#));
+        assert!(code.contains(r#"createComputed(()=>setFull(first()), "Form:13")"#));
+    }
+
+    #[test]
+    fn test_instrument_components() {
+        let source = r#"import { mark } from "velocity-runtime";
+function Counter() {
+    const handler = () => save();
+    return createElement("button", { onClick: handler });
+}
+const Badge = (props) => props.label;
+export default function Dialog() { return null; }
+function useTimer() { return tick(); }
+"#;
+
+        let mut module = parser::parse(source, "test.tsx").unwrap();
+        assert_eq!(instrument_components(&mut module), ["mark", "measure"]);
+        let code = codegen::generate(&module, &CompilerOptions::default()).unwrap();
+
+        assert!(code.starts_with("import { measure } from \"velocity-runtime\";"), "{}", code);
+        assert!(code.contains(
+            r#"function Counter() {
+    mark("Counter:start");
+    try {
+        const handler = ()=>save();
+        return createElement("button", {
+            onClick: handler
+        });
+    } finally{
+        mark("Counter:end");
+        measure("<Counter>", "Counter:start", "Counter:end");
+    }
+}"#
+        ), "{}", code);
+        assert!(code.contains(
+            r#"try {
+        return props.label;
+    } finally{
+        mark("Badge:end");"#
+        ), "{}", code);
+        assert!(code.contains(r#"measure("<Dialog>", "Dialog:start", "Dialog:end")"#));
+        assert!(!code.contains("useTimer:start") && !code.contains("handler:start"));
+    }
+
+    #[test]
+    fn test_transform_drops_key_attribute() {
+        let source = r#"
+            function Panel(props) {
+                return <section key={props.id} id="panel">{props.children}</section>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        assert!(!code.contains("key"));
+        assert!(code.contains(r#""id": "panel""#));
+    }
+
+    #[test]
+    fn test_transform_fragment_and_spread_children() {
+        let source = r#"
+            function App(props) {
+                return <ul>{props.rows.map(row => <><li>{row}</li><hr /></>)}{...props.extra}</ul>;
+            }
+        "#;
+
+        let code = transform_to_js(source);
+
+        let compact: String = code.split_whitespace().collect();
+        assert!(compact.contains(r#"(row)=>[createElement("li",{},row),createElement("hr",{})]"#));
+        assert!(compact.contains("...props.extra)"));
     }
 }
@@ -11,7 +11,18 @@ import * as wasm from './velocity_wasm.js';
 // Export Velocity primitives (native API)
 export const createSignal = wasm.createSignal;
 export const createEffect = wasm.createEffect;
+export const createRenderEffect = wasm.createRenderEffect;
 export const createMemo = wasm.createMemo;
+export const createComputed = wasm.createComputed;
+export const startTransition = wasm.startTransition;
+export const on = wasm.on;
+
+// Export Stores
+export const createStore = (initial) => {
+  const [store, setStore] = wasm.createStore(initial);
+  return [store, (...args) => setStore(args)];
+};
+export const produce = wasm.produce;
 
 // Export React-compatible hooks
 export const useState = wasm.useState;
@@ -24,10 +35,18 @@ export const createElement = wasm.createElement;
 export const createTextNode = wasm.createTextNode;
 export const setText = wasm.setText;
 export const appendChild = wasm.appendChild;
+export const insertNodes = wasm.insertNodes;
 export const setAttribute = wasm.setAttribute;
 export const addClass = wasm.addClass;
 export const removeClass = wasm.removeClass;
 
+// Export Rendering & Lifecycle
+export const render = wasm.render;
+export const createRoot = wasm.createRoot;
+export const onMount = wasm.onMount;
+export const onCleanup = wasm.onCleanup;
+export const portal = wasm.portal;
+
 // Export SSR/Hydration
 export const renderToString = wasm.renderToString;
 export const renderToStream = wasm.renderToStream;
@@ -35,6 +54,16 @@ export const hydrateRoot = wasm.hydrateRoot;
 export const isSSR = wasm.isSSR;
 export const serializeState = wasm.serializeState;
 export const deserializeState = wasm.deserializeState;
+export const serializeValue = wasm.serializeValue;
+export const deserializeValue = wasm.deserializeValue;
+export const createUniqueId = wasm.createUniqueId;
+export const markIsland = wasm.markIsland;
+
+// Export Context
+export const createContext = wasm.createContext;
+export const useContext = wasm.useContext;
+export const provideContext = wasm.provideContext;
+export const RequestContext = wasm.requestContext();
 
 // Export Resource management
 export const createResource = wasm.createResource;
@@ -43,16 +72,29 @@ export const refetchResource = wasm.refetchResource;
 export const setResourceOptimistic = wasm.setResourceOptimistic;
 export const getResourceState = wasm.getResourceState;
 export const clearResourceCache = wasm.clearResourceCache;
+export const suspense = wasm.suspense;
 
 // Export Error handling
 export const createErrorBoundary = wasm.createErrorBoundary;
 export const onError = wasm.onError;
+export const catchError = wasm.catchError;
+
+// Export Code splitting
+export const lazy = wasm.lazy;
+
+// Export Props utilities
+export const mergeProps = (...sources) => wasm.mergeProps(sources);
+export const splitProps = wasm.splitProps;
 
 // Export DevTools
 export const enableDevTools = wasm.enableDevTools;
 export const getMetrics = wasm.getMetrics;
 export const mark = wasm.mark;
 export const measure = wasm.measure;
+export const setStrictMode = wasm.setStrictMode;
+export const enableProfiling = wasm.enableProfiling;
+export const disableProfiling = wasm.disableProfiling;
+export const getProfile = wasm.getProfile;
 
 // Export Fragment
 export const Fragment = 'fragment';
@@ -65,7 +107,15 @@ export default {
   // Velocity API
   createSignal,
   createEffect,
+  createRenderEffect,
   createMemo,
+  createComputed,
+  startTransition,
+  on,
+
+  // Stores
+  createStore,
+  produce,
 
   // React API
   useState,
@@ -78,11 +128,19 @@ export default {
   createTextNode,
   setText,
   appendChild,
+  insertNodes,
   setAttribute,
   addClass,
   removeClass,
   Fragment,
 
+  // Rendering & Lifecycle
+  render,
+  createRoot,
+  onMount,
+  onCleanup,
+  portal,
+
   // SSR
   renderToString,
   renderToStream,
@@ -90,6 +148,16 @@ export default {
   isSSR,
   serializeState,
   deserializeState,
+  serializeValue,
+  deserializeValue,
+  createUniqueId,
+  markIsland,
+
+  // Context
+  createContext,
+  useContext,
+  provideContext,
+  RequestContext,
 
   // Resources
   createResource,
@@ -98,16 +166,29 @@ export default {
   setResourceOptimistic,
   getResourceState,
   clearResourceCache,
+  suspense,
 
   // Error handling
   createErrorBoundary,
   onError,
+  catchError,
+
+  // Code splitting
+  lazy,
+
+  // Props utilities
+  mergeProps,
+  splitProps,
 
   // DevTools
   enableDevTools,
   getMetrics,
   mark,
   measure,
+  setStrictMode,
+  enableProfiling,
+  disableProfiling,
+  getProfile,
 
   // Signal class
   Signal,
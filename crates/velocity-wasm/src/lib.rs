@@ -1,10 +1,17 @@
 use wasm_bindgen::prelude::*;
 use web_sys::{console, Element, HtmlElement, Node};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::collections::HashMap;
 
-// Use wee_alloc as the global allocator for smaller WASM size
+mod serialize;
+mod ssr_html;
+
+use serialize::{Graph, Node as GraphNode, Ref};
+
+// Use wee_alloc as the global allocator for smaller WASM size. Without the
+// `wee-alloc` feature Rust's default allocator is used instead.
+#[cfg(feature = "wee-alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
@@ -16,9 +23,23 @@ type EffectFn = Rc<dyn Fn()>;
 type SignalId = usize;
 type EffectId = usize;
 
+/// Synchronous re-runs of one effect (triggered by its own writes) allowed
+/// before it is stopped as an infinite loop
+const MAX_EFFECT_DEPTH: usize = 100;
+
+/// How long one chunk of transition effects may run before yielding to the
+/// event loop, in milliseconds
+const TRANSITION_BUDGET_MS: f64 = 5.0;
+
 thread_local! {
     static RUNTIME: RefCell<Runtime> = RefCell::new(Runtime::new());
     static CURRENT_EFFECT: RefCell<Option<EffectId>> = RefCell::new(None);
+    static SCHEDULER: RefCell<Scheduler> = RefCell::new(Scheduler::default());
+    /// Throw on runtime misuse (e.g. reading a dead signal) instead of logging
+    static STRICT_MODE: Cell<bool> = const { Cell::new(false) };
+    /// How many `startTransition` callbacks (or transition effects) are on
+    /// the call stack
+    static TRANSITION_DEPTH: Cell<usize> = const { Cell::new(0) };
 }
 
 struct Runtime {
@@ -36,6 +57,45 @@ struct SignalState {
 struct Effect {
     func: EffectFn,
     dependencies: Vec<SignalId>,
+    timing: EffectTiming,
+    /// Name shown in DevTools and error messages (e.g. `Counter:12`)
+    label: Option<String>,
+    /// How many runs of this effect are on the call stack; more than one
+    /// means it re-triggered itself synchronously
+    depth: usize,
+    /// `catchError` scope the effect was created in; errors from re-runs go there
+    error_scope: Option<Rc<ErrorScope>>,
+    /// Context values provided where the effect was created; re-runs see them too
+    context_scope: Option<Rc<ContextScope>>,
+    /// What the last run created, disposed before the next run and with the effect
+    owner: Rc<Owner>,
+}
+
+/// When an effect re-runs after one of its dependencies changes
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EffectTiming {
+    /// Immediately on signal write (before paint; `createRenderEffect`)
+    Sync,
+    /// Once per tick, on the microtask queue (`createEffect`'s default)
+    Microtask,
+    /// Once per frame, in `requestAnimationFrame`
+    Frame,
+    /// Immediately on signal write, even inside a transition (`createComputed`)
+    Eager,
+}
+
+impl EffectTiming {
+    fn parse(timing: Option<&str>) -> Result<Self, JsValue> {
+        match timing {
+            Some("sync") => Ok(EffectTiming::Sync),
+            None | Some("microtask") => Ok(EffectTiming::Microtask),
+            Some("frame") => Ok(EffectTiming::Frame),
+            Some(other) => Err(JsValue::from_str(&format!(
+                "Unknown effect timing '{}' (expected sync, microtask or frame)",
+                other
+            ))),
+        }
+    }
 }
 
 impl Runtime {
@@ -63,7 +123,9 @@ impl Runtime {
         id
     }
 
-    fn read_signal(&mut self, id: SignalId) -> JsValue {
+    /// Read a signal, tracking it as a dependency of the running effect.
+    /// `None` if the signal doesn't belong to this runtime.
+    fn read_signal(&mut self, id: SignalId) -> Option<JsValue> {
         // Track dependency
         if let Some(effect_id) = CURRENT_EFFECT.with(|e| *e.borrow()) {
             if let Some(signal) = self.signals.get_mut(&id) {
@@ -78,52 +140,72 @@ impl Runtime {
             }
         }
 
-        self.signals
-            .get(&id)
-            .map(|s| s.value.clone())
-            .unwrap_or(JsValue::UNDEFINED)
+        self.signals.get(&id).map(|s| s.value.clone())
     }
 
-    fn write_signal(&mut self, id: SignalId, value: JsValue) -> Vec<EffectId> {
-        // Collect subscribers BEFORE updating the value
-        let subscribers: Vec<EffectId> = self.signals
-            .get(&id)
-            .map(|s| s.subscribers.clone())
-            .unwrap_or_default();
+    /// Effects currently subscribed to a signal
+    fn subscribers(&self, id: SignalId) -> Vec<EffectId> {
+        self.signals.get(&id).map(|s| s.subscribers.clone()).unwrap_or_default()
+    }
 
-        // Update the signal value
-        if let Some(signal) = self.signals.get_mut(&id) {
-            signal.value = value;
-        }
+    /// Write a signal, returning the effects to notify. `None` if the signal
+    /// doesn't belong to this runtime.
+    fn write_signal(&mut self, id: SignalId, value: JsValue) -> Option<Vec<EffectId>> {
+        let signal = self.signals.get_mut(&id)?;
+
+        // Collect subscribers BEFORE updating the value
+        let subscribers = signal.subscribers.clone();
+        signal.value = value;
 
         // Return the subscribers to notify (caller will run effects)
-        subscribers
+        Some(subscribers)
     }
 
-    fn create_effect(&mut self, func: EffectFn) -> EffectId {
+    fn create_effect(&mut self, func: EffectFn, timing: EffectTiming, label: Option<String>) -> EffectId {
         let id = self.next_effect_id;
         self.next_effect_id += 1;
 
         let effect = Effect {
             func,
             dependencies: Vec::new(),
+            timing,
+            label,
+            depth: 0,
+            error_scope: current_error_scope(),
+            context_scope: current_context_scope(),
+            owner: Rc::default(),
         };
 
         self.effects.insert(id, effect);
+        own(Owned::Effect(id));
 
         id
     }
 
+    /// Name of an effect for messages: its label, or its id
+    fn effect_name(&self, id: EffectId) -> String {
+        match self.effects.get(&id).and_then(|e| e.label.as_deref()) {
+            Some(label) => label.to_string(),
+            None => format!("#{}", id),
+        }
+    }
+
     fn run_effect(id: EffectId) {
         // Prepare the effect (clear dependencies, set context)
-        RUNTIME.with(|runtime| {
+        let looping = RUNTIME.with(|runtime| {
             let mut r = runtime.borrow_mut();
 
+            // An effect that keeps re-triggering itself synchronously would
+            // otherwise recurse until the stack overflows
+            let effect = r.effects.get(&id)?;
+            if effect.depth >= MAX_EFFECT_DEPTH {
+                return Some((r.effect_name(id), effect.error_scope.clone()));
+            }
+            let effect = r.effects.get_mut(&id)?;
+            effect.depth += 1;
+
             // Clone dependencies before clearing to avoid borrow issues
-            let old_deps: Vec<SignalId> = r.effects
-                .get(&id)
-                .map(|e| e.dependencies.clone())
-                .unwrap_or_default();
+            let old_deps = std::mem::take(&mut effect.dependencies);
 
             // Clear old subscribers
             for signal_id in old_deps {
@@ -132,27 +214,282 @@ impl Runtime {
                 }
             }
 
-            // Clear dependencies
-            if let Some(effect) = r.effects.get_mut(&id) {
-                effect.dependencies.clear();
-            }
+            None
         });
 
+        if let Some((name, error_scope)) = looping {
+            let error = js_sys::Error::new(&format!(
+                "effect {} is looping: it re-triggered itself {} times in a row, \
+                 probably by writing a signal it reads",
+                name, MAX_EFFECT_DEPTH
+            ));
+            report_error(error_scope, "Effect loop:", &error.into());
+            return;
+        }
+
         // Set current effect context
-        CURRENT_EFFECT.with(|e| *e.borrow_mut() = Some(id));
+        let previous_effect = CURRENT_EFFECT.with(|e| e.replace(Some(id)));
 
         // Clone the effect function so we can call it without holding a borrow
-        let effect_fn = RUNTIME.with(|runtime| {
-            runtime.borrow().effects.get(&id).map(|e| e.func.clone())
+        let effect = RUNTIME.with(|runtime| {
+            runtime
+                .borrow()
+                .effects
+                .get(&id)
+                .map(|e| (e.func.clone(), e.error_scope.clone(), e.context_scope.clone(), e.owner.clone()))
+        });
+
+        // Run the effect WITHOUT holding any borrow on Runtime, inside the
+        // error and context scopes it was created in, once what its last run
+        // created is disposed
+        if let Some((func, error_scope, context_scope, owner)) = effect {
+            owner.dispose();
+            let run = || {
+                with_error_scope(error_scope, || {
+                    with_context_scope(context_scope, || with_owner(Some(owner), || func()))
+                })
+            };
+            if PROFILING.with(Cell::get) {
+                let start = profile_now();
+                run();
+                profile_effect_run(id, profile_now() - start);
+            } else {
+                run();
+            }
+        }
+
+        // Restore the outer effect context
+        CURRENT_EFFECT.with(|e| *e.borrow_mut() = previous_effect);
+
+        RUNTIME.with(|runtime| {
+            if let Some(effect) = runtime.borrow_mut().effects.get_mut(&id) {
+                effect.depth -= 1;
+            }
+        });
+    }
+}
+
+/// Name of the effect that is currently running, for error messages
+fn current_effect_name() -> String {
+    match CURRENT_EFFECT.with(|e| *e.borrow()) {
+        Some(id) => RUNTIME.with(|runtime| runtime.borrow().effect_name(id)),
+        None => "(unknown)".to_string(),
+    }
+}
+
+/// Queues deferred effects so that many signal writes in one tick (or frame)
+/// re-run each effect only once
+#[derive(Default)]
+struct Scheduler {
+    microtask_queue: Vec<EffectId>,
+    frame_queue: Vec<EffectId>,
+    /// Effects triggered inside `startTransition`, run in chunks after
+    /// urgent work
+    transition_queue: Vec<EffectId>,
+    microtask_scheduled: bool,
+    frame_scheduled: bool,
+    transition_scheduled: bool,
+}
+
+impl Scheduler {
+    /// Run or queue an effect according to its timing
+    fn schedule(id: EffectId) {
+        let timing = RUNTIME.with(|runtime| {
+            runtime.borrow().effects.get(&id).map(|e| e.timing)
+        });
+
+        if TRANSITION_DEPTH.with(Cell::get) > 0 && timing != Some(EffectTiming::Eager) {
+            Self::enqueue_transition(id);
+            return;
+        }
+
+        // An urgent write supersedes a pending transition run of the same effect
+        SCHEDULER.with(|scheduler| scheduler.borrow_mut().transition_queue.retain(|&queued| queued != id));
+
+        match timing {
+            Some(EffectTiming::Sync | EffectTiming::Eager) => Runtime::run_effect(id),
+            Some(EffectTiming::Microtask) => Self::enqueue(id, EffectTiming::Microtask),
+            Some(EffectTiming::Frame) => Self::enqueue(id, EffectTiming::Frame),
+            None => {}
+        }
+    }
+
+    fn enqueue(id: EffectId, timing: EffectTiming) {
+        let needs_flush = SCHEDULER.with(|scheduler| {
+            let mut s = scheduler.borrow_mut();
+            let s = &mut *s;
+            let (queue, scheduled) = match timing {
+                EffectTiming::Frame => (&mut s.frame_queue, &mut s.frame_scheduled),
+                _ => (&mut s.microtask_queue, &mut s.microtask_scheduled),
+            };
+            if !queue.contains(&id) {
+                queue.push(id);
+            }
+            !std::mem::replace(scheduled, true)
+        });
+
+        if needs_flush {
+            Self::request_flush(timing);
+        }
+    }
+
+    fn request_flush(timing: EffectTiming) {
+        if timing == EffectTiming::Frame {
+            if let Some(window) = web_sys::window() {
+                let callback = Closure::once_into_js(move || Self::flush(EffectTiming::Frame));
+                if window.request_animation_frame(callback.unchecked_ref()).is_ok() {
+                    return;
+                }
+            }
+        }
+
+        // Microtasks (and frames when there is no window, e.g. SSR)
+        wasm_bindgen_futures::spawn_local(async move {
+            Self::flush(timing);
+        });
+    }
+
+    /// Run every effect queued for the given timing
+    fn flush(timing: EffectTiming) {
+        let queue = SCHEDULER.with(|scheduler| {
+            let mut s = scheduler.borrow_mut();
+            match timing {
+                EffectTiming::Frame => {
+                    s.frame_scheduled = false;
+                    std::mem::take(&mut s.frame_queue)
+                }
+                _ => {
+                    s.microtask_scheduled = false;
+                    std::mem::take(&mut s.microtask_queue)
+                }
+            }
+        });
+
+        for id in queue {
+            Runtime::run_effect(id);
+        }
+    }
+
+    fn enqueue_transition(id: EffectId) {
+        SCHEDULER.with(|scheduler| {
+            let mut s = scheduler.borrow_mut();
+            if !s.transition_queue.contains(&id) {
+                s.transition_queue.push(id);
+            }
+        });
+        Self::request_transition_flush();
+    }
+
+    /// Schedule a transition chunk as a macrotask, so input events and
+    /// urgent effects get to run first
+    fn request_transition_flush() {
+        let needs_flush = SCHEDULER.with(|scheduler| {
+            let mut s = scheduler.borrow_mut();
+            !s.transition_queue.is_empty() && !std::mem::replace(&mut s.transition_scheduled, true)
+        });
+        if !needs_flush {
+            return;
+        }
+
+        if let Some(window) = web_sys::window() {
+            let callback = Closure::once_into_js(Self::flush_transitions);
+            if window
+                .set_timeout_with_callback_and_timeout_and_arguments_0(callback.unchecked_ref(), 0)
+                .is_ok()
+            {
+                return;
+            }
+        }
+        wasm_bindgen_futures::spawn_local(async move {
+            Self::flush_transitions();
         });
+    }
 
-        // Run the effect WITHOUT holding any borrow on Runtime
-        if let Some(func) = effect_fn {
-            func();
+    /// Run queued transition effects until the time budget is spent, then
+    /// yield and continue in a later task. Writes made by these effects are
+    /// transitions too.
+    fn flush_transitions() {
+        SCHEDULER.with(|scheduler| scheduler.borrow_mut().transition_scheduled = false);
+        let deadline = js_sys::Date::now() + TRANSITION_BUDGET_MS;
+
+        loop {
+            let next = SCHEDULER.with(|scheduler| {
+                let mut s = scheduler.borrow_mut();
+                (!s.transition_queue.is_empty()).then(|| s.transition_queue.remove(0))
+            });
+            let Some(id) = next else { break };
+
+            in_transition(|| Runtime::run_effect(id));
+            if js_sys::Date::now() >= deadline {
+                break;
+            }
         }
 
-        // Clear current effect context
-        CURRENT_EFFECT.with(|e| *e.borrow_mut() = None);
+        Self::request_transition_flush();
+    }
+}
+
+/// Run `f` with signal writes marked as non-urgent
+fn in_transition<T>(f: impl FnOnce() -> T) -> T {
+    TRANSITION_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let result = f();
+    TRANSITION_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    result
+}
+
+/// Run `f` without tracking the signals it reads as dependencies of the
+/// running effect
+fn untracked<T>(f: impl FnOnce() -> T) -> T {
+    let previous_effect = CURRENT_EFFECT.with(|e| e.replace(None));
+    let result = f();
+    CURRENT_EFFECT.with(|e| *e.borrow_mut() = previous_effect);
+    result
+}
+
+/// Why a signal couldn't be read or written
+enum SignalAccessError {
+    /// The thread-local runtime has been torn down
+    RuntimeUnavailable,
+    /// The runtime is already borrowed by another update on the call stack
+    RuntimeBusy,
+    /// The signal was created by a different (or reset) runtime
+    UnknownSignal(SignalId),
+}
+
+impl SignalAccessError {
+    fn message(&self, action: &str) -> String {
+        let reason = match self {
+            SignalAccessError::RuntimeUnavailable => "the Velocity runtime is not available".to_string(),
+            SignalAccessError::RuntimeBusy => "the runtime is in the middle of another update".to_string(),
+            SignalAccessError::UnknownSignal(id) => {
+                format!("signal {} was disposed or does not belong to the current runtime", id)
+            }
+        };
+        format!("signal {} outside reactive context: {}", action, reason)
+    }
+}
+
+/// Borrow the runtime, reporting why it isn't available instead of panicking
+fn with_runtime<T>(f: impl FnOnce(&mut Runtime) -> T) -> Result<T, SignalAccessError> {
+    RUNTIME
+        .try_with(|runtime| {
+            runtime
+                .try_borrow_mut()
+                .map(|mut runtime| f(&mut runtime))
+                .map_err(|_| SignalAccessError::RuntimeBusy)
+        })
+        .map_err(|_| SignalAccessError::RuntimeUnavailable)?
+}
+
+/// Log a failed signal access. In strict mode it is also returned as an
+/// error, which throws in JS.
+fn signal_access_failed(action: &str, error: SignalAccessError) -> Result<(), JsValue> {
+    let message = error.message(action);
+    console::error_1(&JsValue::from_str(&message));
+    if STRICT_MODE.with(Cell::get) {
+        Err(js_sys::Error::new(&message).into())
+    } else {
+        Ok(())
     }
 }
 
@@ -160,6 +497,14 @@ impl Runtime {
 // Public API
 // ============================================================================
 
+/// Make runtime misuse throw instead of only logging an error. Meant for
+/// development, so a signal read that can't succeed fails loudly at the
+/// call site rather than rendering `undefined`.
+#[wasm_bindgen(js_name = setStrictMode)]
+pub fn set_strict_mode(enabled: bool) {
+    STRICT_MODE.with(|strict| strict.set(enabled));
+}
+
 #[wasm_bindgen]
 pub struct Signal {
     id: SignalId,
@@ -173,39 +518,179 @@ impl Signal {
         Signal { id }
     }
 
+    /// Read the value. If the runtime can't serve the read, an error is
+    /// logged and `undefined` returned (thrown instead in strict mode).
     #[wasm_bindgen(js_name = get)]
-    pub fn get(&self) -> JsValue {
-        RUNTIME.with(|runtime| runtime.borrow_mut().read_signal(self.id))
+    pub fn get(&self) -> Result<JsValue, JsValue> {
+        let read = with_runtime(|runtime| runtime.read_signal(self.id))
+            .and_then(|value| value.ok_or(SignalAccessError::UnknownSignal(self.id)));
+
+        match read {
+            Ok(value) => Ok(value),
+            Err(error) => signal_access_failed("read", error).map(|_| JsValue::UNDEFINED),
+        }
     }
 
+    /// Write the value and notify subscribers. Failures are reported like
+    /// [`Signal::get`].
     #[wasm_bindgen(js_name = set)]
-    pub fn set(&self, value: JsValue) {
+    pub fn set(&self, value: JsValue) -> Result<(), JsValue> {
         // Get the list of subscribers to notify
-        let subscribers = RUNTIME.with(|runtime| {
-            runtime.borrow_mut().write_signal(self.id, value)
-        });
+        let write = with_runtime(|runtime| runtime.write_signal(self.id, value))
+            .and_then(|subscribers| subscribers.ok_or(SignalAccessError::UnknownSignal(self.id)));
+
+        let subscribers = match write {
+            Ok(subscribers) => subscribers,
+            Err(error) => return signal_access_failed("write", error),
+        };
+        profile_signal_writes(1);
 
-        // Run effects after releasing the borrow
+        // Run (or queue) effects after releasing the borrow
         for effect_id in subscribers {
-            Runtime::run_effect(effect_id);
+            Scheduler::schedule(effect_id);
         }
+        Ok(())
+    }
+}
+
+// Hot update state: the compiler keys signals in dev builds, the HMR client
+// saves their values under `HMR_STATE_KEY` before reloading for an update,
+// and signals created with the same key afterwards start from them
+
+/// sessionStorage item the HMR client saves signal values in
+const HMR_STATE_KEY: &str = "velocity-hmr-state";
+
+thread_local! {
+    static HMR_SIGNALS: RefCell<Option<HmrSignals>> = const { RefCell::new(None) };
+}
+
+struct HmrSignals {
+    /// Key → `Set` of reads of the live signals with it, which the HMR client
+    /// saves; published as `globalThis.__velocity_hmr_signals__`
+    live: js_sys::Map,
+    /// Key → saved value of each instance, in creation order: `{ value }`,
+    /// or `null` if it wasn't plain data
+    saved: JsValue,
+    /// Signals created with each key so far
+    created: HashMap<String, u32>,
+}
+
+impl HmrSignals {
+    fn load() -> Self {
+        // Consumed once, so a manual reload starts fresh. No sessionStorage
+        // (workers, private modes) or a corrupt entry restores nothing.
+        let storage = web_sys::window().and_then(|window| window.session_storage().ok().flatten());
+        let saved = storage
+            .and_then(|storage| {
+                let item = storage.get_item(HMR_STATE_KEY).ok().flatten();
+                let _ = storage.remove_item(HMR_STATE_KEY);
+                item
+            })
+            .and_then(|item| js_sys::JSON::parse(&item).ok())
+            .filter(|saved| saved.is_object())
+            .unwrap_or_else(|| js_sys::Object::new().into());
+        Self::new(saved)
+    }
+
+    fn new(saved: JsValue) -> Self {
+        let live = js_sys::Map::new();
+        let _ = js_sys::Reflect::set(&js_sys::global(), &"__velocity_hmr_signals__".into(), &live);
+        HmrSignals { live, saved, created: HashMap::new() }
+    }
+}
+
+fn with_hmr_signals<T>(f: impl FnOnce(&mut HmrSignals) -> T) -> T {
+    HMR_SIGNALS.with(|state| f(state.borrow_mut().get_or_insert_with(HmrSignals::load)))
+}
+
+/// `null`, `undefined`, or the JSON type of a value
+fn kind_of(value: &JsValue) -> String {
+    if value.is_null() || value.is_undefined() {
+        "empty".to_string()
+    } else if js_sys::Array::is_array(value) {
+        "array".to_string()
+    } else {
+        value.js_typeof().as_string().unwrap_or_default()
+    }
+}
+
+/// The value the key's next signal starts from: the one saved before the hot
+/// update, if it's of the same kind as `initial_value`
+fn restore_signal(key: &str, initial_value: JsValue) -> JsValue {
+    let saved = with_hmr_signals(|state| {
+        let created = state.created.entry(key.to_string()).or_default();
+        let index = *created;
+        *created += 1;
+        js_sys::Reflect::get(&state.saved, &key.into())
+            .ok()
+            .filter(js_sys::Array::is_array)
+            .map(|instances| instances.unchecked_into::<js_sys::Array>().get(index))
+            .filter(|saved| saved.is_object())
+    });
+
+    let Some(value) = saved.and_then(|saved| js_sys::Reflect::get(&saved, &"value".into()).ok()) else {
+        return initial_value;
+    };
+    let initial_kind = kind_of(&initial_value);
+    if initial_kind == "empty" || initial_kind == kind_of(&value) {
+        value
+    } else {
+        initial_value
     }
 }
 
+fn track_signal(key: &str, read: &JsValue) {
+    let reads = with_hmr_signals(|state| {
+        let reads = state.live.get(&key.into());
+        if let Some(reads) = reads.dyn_ref::<js_sys::Set>() {
+            return reads.clone();
+        }
+        let reads = js_sys::Set::new(&JsValue::UNDEFINED);
+        state.live.set(&key.into(), &reads);
+        reads
+    });
+    reads.add(read);
+
+    let read = read.clone();
+    let untrack = Closure::wrap(Box::new(move || {
+        reads.delete(&read);
+    }) as Box<dyn Fn()>);
+    own(Owned::Cleanup(untrack.into_js_value().unchecked_into(), None));
+}
+
+/// Create a signal, returning `[getter, setter]`. A signal created under a
+/// root or an effect is released when it's disposed (see [`create_root`]).
+///
+/// As with React's `useState`, a function `initial_value` is an initializer:
+/// it's called once, right away, and its result is the initial value, so
+/// `createSignal(() => expensive())` doesn't store the function. To store a
+/// function, return it from an initializer: `createSignal(() => onSave)`.
+///
+/// Dev builds pass an `hmr_key` naming the call, so its value survives hot
+/// updates; see the compiler's `hmr` module.
 #[wasm_bindgen(js_name = createSignal)]
-pub fn create_signal(initial_value: JsValue) -> Vec<JsValue> {
+pub fn create_signal(initial_value: JsValue, hmr_key: Option<String>) -> Result<Vec<JsValue>, JsValue> {
+    let initial_value = match initial_value.dyn_ref::<js_sys::Function>() {
+        Some(initializer) => initializer.call0(&JsValue::NULL)?,
+        None => initial_value,
+    };
+    let initial_value = match &hmr_key {
+        Some(key) => restore_signal(key, initial_value),
+        None => initial_value,
+    };
     let signal = Signal::new(initial_value);
+    own(Owned::Signal(signal.id));
     let signal_ref = Rc::new(RefCell::new(signal));
 
     let getter_signal = signal_ref.clone();
     let getter = Closure::wrap(Box::new(move || {
         getter_signal.borrow().get()
-    }) as Box<dyn Fn() -> JsValue>);
+    }) as Box<dyn Fn() -> Result<JsValue, JsValue>>);
 
     let setter_signal = signal_ref.clone();
     let setter = Closure::wrap(Box::new(move |value: JsValue| {
-        setter_signal.borrow().set(value);
-    }) as Box<dyn Fn(JsValue)>);
+        setter_signal.borrow().set(value)
+    }) as Box<dyn Fn(JsValue) -> Result<(), JsValue>>);
 
     let result = vec![
         getter.as_ref().clone(),
@@ -215,74 +700,149 @@ pub fn create_signal(initial_value: JsValue) -> Vec<JsValue> {
     getter.forget();
     setter.forget();
 
-    result
+    if let Some(key) = &hmr_key {
+        track_signal(key, &result[0]);
+    }
+
+    Ok(result)
 }
 
 /// React-compatible useState hook (alias for createSignal)
 #[wasm_bindgen(js_name = useState)]
-pub fn use_state(initial_value: JsValue) -> Vec<JsValue> {
-    create_signal(initial_value)
+pub fn use_state(initial_value: JsValue) -> Result<Vec<JsValue>, JsValue> {
+    create_signal(initial_value, None)
 }
 
+/// Create an effect that re-runs when the signals it reads change.
+///
+/// `timing` controls when re-runs happen: `"microtask"` (default) batches
+/// them to once per tick, after the code that wrote the signals finishes,
+/// `"frame"` batches DOM writes into a single `requestAnimationFrame` and
+/// `"sync"` runs immediately on write, like [`create_render_effect`]. The
+/// first run is always synchronous.
+///
+/// `label` names the effect in DevTools and error messages; the compiler
+/// fills it in with the component and line (`Counter:12`).
 #[wasm_bindgen(js_name = createEffect)]
-pub fn create_effect(func: &js_sys::Function) {
+pub fn create_effect(func: &js_sys::Function, timing: Option<String>, label: Option<String>) -> Result<(), JsValue> {
+    let timing = EffectTiming::parse(timing.as_deref())?;
+    run_new_effect(func, timing, label, "Effect");
+    Ok(())
+}
+
+/// Register `func` as an effect with `timing` and run it once. `kind` names
+/// it in error messages.
+fn run_new_effect(func: &js_sys::Function, timing: EffectTiming, label: Option<String>, kind: &'static str) {
     let func_clone = func.clone();
     let effect_fn = Rc::new(move || {
         match func_clone.call0(&JsValue::NULL) {
             Ok(_) => {},
             Err(e) => {
-                console::error_2(&"Effect error:".into(), &e);
+                let context = format!("{} {} error:", kind, current_effect_name());
+                report_error(current_error_scope(), &context, &e);
             }
         }
     });
 
     let effect_id = RUNTIME.with(|runtime| {
-        runtime.borrow_mut().create_effect(effect_fn)
+        runtime.borrow_mut().create_effect(effect_fn, timing, label)
     });
 
     // Run the effect immediately after creating it
     Runtime::run_effect(effect_id);
 }
 
+/// Create an effect that always re-runs synchronously, as soon as a signal
+/// it read is written, before paint. Use for DOM reads/writes that must not
+/// lag behind the signal; the compiler's DOM bindings use it.
+#[wasm_bindgen(js_name = createRenderEffect)]
+pub fn create_render_effect(func: &js_sys::Function, label: Option<String>) {
+    run_new_effect(func, EffectTiming::Sync, label, "Render effect");
+}
+
+/// Create a computation that runs eagerly: once on creation and again,
+/// synchronously, whenever a signal it read changes, even inside
+/// `startTransition`. Meant for derivations with side effects, such as
+/// keeping a signal in step with others:
+/// `createComputed(() => setFullName(`${first()} ${last()}`))`.
+///
+/// Unlike [`create_memo`] it returns nothing and runs whether or not
+/// anything reads what it writes. `label` names it like [`create_effect`]'s.
+#[wasm_bindgen(js_name = createComputed)]
+pub fn create_computed(func: &js_sys::Function, label: Option<String>) {
+    run_new_effect(func, EffectTiming::Eager, label, "Computed");
+}
+
 /// React-compatible useEffect hook (alias for createEffect)
 #[wasm_bindgen(js_name = useEffect)]
-pub fn use_effect(func: &js_sys::Function) {
-    create_effect(func)
+pub fn use_effect(func: &js_sys::Function) -> Result<(), JsValue> {
+    create_effect(func, None, None)
+}
+
+/// A memo's cached value and whether it needs recomputing
+struct MemoState {
+    value: Signal,
+    /// A signal the last computation read has changed since (or it never ran)
+    stale: Cell<bool>,
+    /// Set while the getter recomputes, to tell the memo's effect to run
+    /// `func` rather than mark the value stale
+    computing: Cell<bool>,
 }
 
-/// Create a memoized computed value (compatible with useMemo)
+/// Create a memoized computed value (compatible with useMemo). `label` names
+/// it like [`create_effect`]'s.
+///
+/// Memos are lazy: `func` first runs when the getter is called. When a signal
+/// it read changes, the memo is only marked stale and the effects reading it
+/// are notified; `func` runs again at the next read, once however many
+/// signals changed, and not at all if nothing reads the memo any more. For a
+/// computation that must run on every change, use [`create_computed`].
 #[wasm_bindgen(js_name = createMemo)]
-pub fn create_memo(func: &js_sys::Function) -> js_sys::Function {
-    // Create a signal to hold the computed value
-    let result_signal = Signal::new(JsValue::UNDEFINED);
-    let result_ref = Rc::new(RefCell::new(result_signal));
+pub fn create_memo(func: &js_sys::Function, label: Option<String>) -> js_sys::Function {
+    let state = Rc::new(MemoState {
+        value: Signal::new(JsValue::UNDEFINED),
+        stale: Cell::new(true),
+        computing: Cell::new(false),
+    });
 
-    // Create an effect that computes and stores the value
     let func_clone = func.clone();
-    let result_clone = result_ref.clone();
-
+    let effect_state = state.clone();
     let effect_fn = Rc::new(move || {
+        let state = &effect_state;
+        if !state.computing.get() {
+            // A dependency changed: recompute at the next read, and make
+            // readers read again
+            state.stale.set(true);
+            let readers = with_runtime(|runtime| runtime.subscribers(state.value.id)).unwrap_or_default();
+            for reader in readers {
+                Scheduler::schedule(reader);
+            }
+            return;
+        }
+
         match func_clone.call0(&JsValue::NULL) {
-            Ok(value) => {
-                result_clone.borrow().set(value);
-            },
+            // Readers are pulling this value, so don't notify them of it
+            Ok(value) => drop(with_runtime(|runtime| runtime.write_signal(state.value.id, value))),
             Err(e) => {
-                console::error_2(&"Memo error:".into(), &e);
+                let context = format!("Memo {} error:", current_effect_name());
+                report_error(current_error_scope(), &context, &e);
             }
         }
     });
 
     let effect_id = RUNTIME.with(|runtime| {
-        runtime.borrow_mut().create_effect(effect_fn)
+        runtime.borrow_mut().create_effect(effect_fn, EffectTiming::Sync, label)
     });
 
-    // Run the effect immediately to compute initial value
-    Runtime::run_effect(effect_id);
-
-    // Return a getter function
+    // Return a getter function that recomputes a stale value before reading it
     let getter = Closure::wrap(Box::new(move || {
-        result_ref.borrow().get()
-    }) as Box<dyn Fn() -> JsValue>);
+        if state.stale.replace(false) {
+            state.computing.set(true);
+            Runtime::run_effect(effect_id);
+            state.computing.set(false);
+        }
+        state.value.get()
+    }) as Box<dyn Fn() -> Result<JsValue, JsValue>>);
 
     let func = getter.as_ref().clone();
     getter.forget();
@@ -292,17 +852,114 @@ pub fn create_memo(func: &js_sys::Function) -> js_sys::Function {
 /// React-compatible useMemo hook (alias for createMemo)
 #[wasm_bindgen(js_name = useMemo)]
 pub fn use_memo(func: &js_sys::Function) -> js_sys::Function {
-    create_memo(func)
+    create_memo(func, None)
+}
+
+/// Run `func` with its signal writes marked as non-urgent.
+///
+/// The writes themselves happen immediately, but the effects they trigger,
+/// whatever their timing, are queued and run after urgent work in chunks of
+/// a few milliseconds, yielding to the event loop in between so input stays
+/// responsive. Effects those effects trigger are non-urgent as well.
+///
+/// Urgent work wins: if an effect is still queued for a transition when an
+/// ordinary write triggers it, it runs right away with the latest values and
+/// leaves the transition queue. Microtask- and frame-batched effects that are
+/// already queued stay in their batch.
+#[wasm_bindgen(js_name = startTransition)]
+pub fn start_transition(func: &js_sys::Function) -> Result<JsValue, JsValue> {
+    in_transition(|| func.call0(&JsValue::NULL))
+}
+
+/// Wrap `func` so the effect or memo it's passed to depends on `deps` and
+/// nothing else: `createEffect(on([a, b], ([a, b], prev) => ...))`.
+///
+/// Each run reads `deps` (getters, or a single getter), which subscribes the
+/// running effect to them, then calls `func(input, prevInput, prevResult)`
+/// untracked, so signals `func` reads don't re-run the effect. `input` is
+/// the getters' values, an array unless `deps` is a single getter, and
+/// `prevInput` and `prevResult` are those of the last call (`undefined` on
+/// the first). With `{ defer: true }` the first run only subscribes, and
+/// `func` first runs when a dependency changes.
+#[wasm_bindgen(js_name = on)]
+pub fn on(deps: JsValue, func: &js_sys::Function, options: JsValue) -> Result<js_sys::Function, JsValue> {
+    let single = deps.is_function();
+    let getters: Vec<js_sys::Function> = if single {
+        vec![deps.unchecked_into()]
+    } else if js_sys::Array::is_array(&deps) {
+        js_sys::Array::from(&deps)
+            .iter()
+            .map(|dep| dep.dyn_into::<js_sys::Function>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| js_sys::Error::new("on() dependencies must be getter functions"))?
+    } else {
+        return Err(js_sys::Error::new("on() expects a getter or an array of getters").into());
+    };
+    let defer = options.is_object()
+        && js_sys::Reflect::get(&options, &"defer".into()).is_ok_and(|defer| defer.is_truthy());
+
+    let func = func.clone();
+    let deferred = Cell::new(defer);
+    let previous = RefCell::new((JsValue::UNDEFINED, JsValue::UNDEFINED));
+    let tracked = Closure::wrap(Box::new(move || -> Result<JsValue, JsValue> {
+        // Read in the caller's tracking context, subscribing it
+        let values = getters
+            .iter()
+            .map(|getter| getter.call0(&JsValue::NULL))
+            .collect::<Result<Vec<_>, _>>()?;
+        let input = if single {
+            values.into_iter().next().unwrap_or(JsValue::UNDEFINED)
+        } else {
+            values.into_iter().collect::<js_sys::Array>().into()
+        };
+
+        if deferred.replace(false) {
+            previous.borrow_mut().0 = input;
+            return Ok(JsValue::UNDEFINED);
+        }
+
+        let (previous_input, previous_result) = previous.borrow().clone();
+        let result = untracked(|| func.call3(&JsValue::NULL, &input, &previous_input, &previous_result))?;
+        *previous.borrow_mut() = (input, result.clone());
+        Ok(result)
+    }) as Box<dyn Fn() -> Result<JsValue, JsValue>>);
+
+    let tracked_fn = tracked.as_ref().clone();
+    tracked.forget();
+    Ok(tracked_fn.into())
 }
 
 // ============================================================================
 // DOM Utilities
 // ============================================================================
 
+/// The page's document. Where there is none, such as during SSR, the error
+/// names `operation` instead of failing on a missing window.
+fn document(operation: &str) -> Result<web_sys::Document, JsValue> {
+    let window = web_sys::window().ok_or_else(|| {
+        JsValue::from_str(&format!(
+            "{} needs a browser DOM, which isn't available during server rendering",
+            operation
+        ))
+    })?;
+    window
+        .document()
+        .ok_or_else(|| JsValue::from_str(&format!("{}: the window has no document", operation)))
+}
+
+/// Create an element with `props` and `children`.
+///
+/// During SSR there's no DOM, so this returns the element's markup for
+/// `renderToString` instead: `className`, `style` and string props become
+/// attributes, event handlers are dropped, and children render as in the
+/// browser except that functions are called once rather than tracked.
 #[wasm_bindgen(js_name = createElement)]
-pub fn create_element(tag: &str, props: JsValue, children: js_sys::Array) -> Result<HtmlElement, JsValue> {
-    let window = web_sys::window().ok_or("No window")?;
-    let document = window.document().ok_or("No document")?;
+pub fn create_element(tag: &str, props: JsValue, children: js_sys::Array) -> Result<JsValue, JsValue> {
+    if is_ssr() {
+        return ssr_element(tag, &props, &children);
+    }
+
+    let document = document("createElement")?;
     let element = document.create_element(tag)?;
     let html_element = element.dyn_into::<HtmlElement>()?;
 
@@ -348,29 +1005,107 @@ pub fn create_element(tag: &str, props: JsValue, children: js_sys::Array) -> Res
         }
     }
 
-    // Append children
-    for i in 0..children.length() {
-        let child = children.get(i);
+    // Append children in one insertion
+    let fragment = build_fragment(&document, &children)?;
+    html_element.append_child(&fragment)?;
 
-        // Handle text nodes
-        if let Some(text) = child.as_string() {
-            let text_node = document.create_text_node(&text);
-            html_element.append_child(&text_node)?;
-        }
-        // Handle DOM elements
-        else if let Ok(child_element) = child.dyn_into::<Node>() {
-            html_element.append_child(&child_element)?;
+    Ok(html_element.into())
+}
+
+/// Insert `nodes` (a node, string, number, function or array of them, nested
+/// arrays included) at the end of `parent` with a single DOM insertion, so a large batch
+/// causes one reflow instead of one per node.
+#[wasm_bindgen(js_name = insertNodes)]
+pub fn insert_nodes(parent: &Node, nodes: &JsValue) -> Result<(), JsValue> {
+    let document = document("insertNodes")?;
+    let fragment = build_fragment(&document, nodes)?;
+    parent.append_child(&fragment)?;
+    Ok(())
+}
+
+/// Collect a rendered value into a detached `DocumentFragment`: strings and
+/// numbers become text nodes, nodes are moved in, arrays are flattened and
+/// functions are reactive (see [`insert_reactive`]). Anything else renders
+/// nothing.
+fn build_fragment(document: &web_sys::Document, value: &JsValue) -> Result<web_sys::DocumentFragment, JsValue> {
+    fn collect(document: &web_sys::Document, fragment: &web_sys::DocumentFragment, value: &JsValue) -> Result<(), JsValue> {
+        if let Some(text) = value.as_string() {
+            fragment.append_child(&document.create_text_node(&text))?;
+        } else if value.as_f64().is_some() {
+            let text = value.unchecked_ref::<js_sys::Number>().to_string_with_radix(10)?;
+            fragment.append_child(&document.create_text_node(&String::from(text)))?;
+        } else if let Some(func) = value.dyn_ref::<js_sys::Function>() {
+            insert_reactive(document, fragment, func.clone())?;
+        } else if let Some(node) = value.dyn_ref::<Node>() {
+            fragment.append_child(node)?;
+        } else if js_sys::Array::is_array(value) {
+            for item in value.unchecked_ref::<js_sys::Array>().iter() {
+                collect(document, fragment, &item)?;
+            }
         }
+        Ok(())
     }
 
-    Ok(html_element)
+    let fragment = document.create_document_fragment();
+    collect(document, &fragment, value)?;
+    Ok(fragment)
+}
+
+/// Render the result of `func` at the end of `fragment`, and render it again
+/// in place whenever a signal `func` reads changes. This is how a component's
+/// reactive children (`() => count()`, lowered by the compiler) stay live
+/// wherever the component inserts `props.children`.
+fn insert_reactive(
+    document: &web_sys::Document,
+    fragment: &web_sys::DocumentFragment,
+    func: js_sys::Function,
+) -> Result<(), JsValue> {
+    // The content always sits right before this empty text node, which moves
+    // with the fragment into the element
+    let anchor: Node = document.create_text_node("").into();
+    fragment.append_child(&anchor)?;
+
+    let document = document.clone();
+    let current: RefCell<Vec<Node>> = RefCell::new(Vec::new());
+    let effect_fn = Rc::new(move || {
+        let render = || -> Result<(), JsValue> {
+            let value = func.call0(&JsValue::NULL)?;
+            let next = build_fragment(&document, &value)?;
+            let children = next.child_nodes();
+            let nodes: Vec<Node> = (0..children.length()).filter_map(|i| children.get(i)).collect();
+
+            for node in current.borrow_mut().drain(..) {
+                if let Some(parent) = node.parent_node() {
+                    parent.remove_child(&node)?;
+                }
+                release_portals(&node);
+            }
+            if let Some(parent) = anchor.parent_node() {
+                parent.insert_before(&next, Some(&anchor))?;
+            }
+            *current.borrow_mut() = nodes;
+            Ok(())
+        };
+        if let Err(e) = render() {
+            let context = format!("Effect {} error:", current_effect_name());
+            report_error(current_error_scope(), &context, &e);
+        }
+    });
+
+    let effect_id = RUNTIME.with(|runtime| {
+        runtime.borrow_mut().create_effect(effect_fn, EffectTiming::Sync, None)
+    });
+    Runtime::run_effect(effect_id);
+    Ok(())
 }
 
+/// Create a text node, or during SSR its escaped markup
 #[wasm_bindgen(js_name = createTextNode)]
-pub fn create_text_node(text: &str) -> Result<Node, JsValue> {
-    let window = web_sys::window().ok_or("No window")?;
-    let document = window.document().ok_or("No document")?;
-    Ok(document.create_text_node(text).into())
+pub fn create_text_node(text: &str) -> Result<JsValue, JsValue> {
+    if is_ssr() {
+        return Ok(ssr_node(ssr_html::escape_text(text)));
+    }
+    Ok(document("createTextNode")?.create_text_node(text).into())
 }
 
 #[wasm_bindgen(js_name = setText)]
@@ -403,7 +1138,62 @@ pub fn remove_class(element: &Element, class: &str) -> Result<(), JsValue> {
 // Hydration Support (Phase 4)
 // ============================================================================
 
-/// Mark a component as an island (interactive zone that needs hydration)
+/// Prefix of the ids `createUniqueId` returns within a render
+const RENDER_ID_PREFIX: &str = "v";
+
+thread_local! {
+    /// Where `createUniqueId` counts from: the current render, or the
+    /// current build of a `suspense` boundary
+    static ID_SCOPE: RefCell<Option<Rc<IdScope>>> = const { RefCell::new(None) };
+    /// Counter for ids created outside any render
+    static NEXT_GLOBAL_ID: Cell<u32> = const { Cell::new(0) };
+}
+
+struct IdScope {
+    prefix: String,
+    next: Cell<u32>,
+}
+
+/// Run `f` with ids counting from 0 under `prefix`
+fn with_id_scope<T>(prefix: &str, f: impl FnOnce() -> T) -> T {
+    let scope = Rc::new(IdScope {
+        prefix: prefix.to_string(),
+        next: Cell::new(0),
+    });
+    let previous = ID_SCOPE.with(|current| current.replace(Some(scope)));
+    let result = f();
+    ID_SCOPE.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// An id unique within the page, for `markIsland` and for `id` attributes
+/// referenced by `aria-labelledby`, `for` and the like.
+///
+/// Ids count up in the order components run, from `v-0` at the start of
+/// every `renderToString`, `renderToStream` and top-level `render` (one not
+/// called from inside another `render`). The same tree rendered for the same
+/// data on the server and on the client therefore gets the same ids. Each
+/// `suspense` boundary takes one id and numbers its children under it
+/// (`v-3-0`, `v-3-1`, ...), so they keep their ids however many times they
+/// are rebuilt, on either side. Outside any render (in event handlers, or
+/// effects re-running later) ids come from a separate counter (`vg-0`,
+/// `vg-1`, ...) that is never reset and won't match across the boundary.
+#[wasm_bindgen(js_name = createUniqueId)]
+pub fn create_unique_id() -> String {
+    let scoped = ID_SCOPE.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .map(|scope| format!("{}-{}", scope.prefix, scope.next.replace(scope.next.get() + 1)))
+    });
+    scoped.unwrap_or_else(|| {
+        let id = NEXT_GLOBAL_ID.with(|next| next.replace(next.get() + 1));
+        format!("vg-{}", id)
+    })
+}
+
+/// Mark a component as an island (interactive zone that needs hydration).
+/// Use an id from `createUniqueId` so the server and the client agree on it.
 #[wasm_bindgen(js_name = markIsland)]
 pub fn mark_island(element: &Element, island_id: &str) -> Result<(), JsValue> {
     element.set_attribute("data-island", island_id)?;
@@ -453,8 +1243,7 @@ pub fn mark_hydrated(element: &Element) -> Result<(), JsValue> {
 /// Get all islands in the DOM that need hydration
 #[wasm_bindgen(js_name = getIslandsToHydrate)]
 pub fn get_islands_to_hydrate() -> Result<js_sys::Array, JsValue> {
-    let window = web_sys::window().ok_or("No window")?;
-    let document = window.document().ok_or("No document")?;
+    let document = document("getIslandsToHydrate")?;
 
     let selector = "[data-hydrate='pending']";
     let node_list = document.query_selector_all(selector)?;
@@ -479,6 +1268,7 @@ type ResourceId = usize;
 thread_local! {
     static RESOURCE_CACHE: RefCell<HashMap<String, ResourceState>> = RefCell::new(HashMap::new());
     static NEXT_RESOURCE_ID: RefCell<ResourceId> = RefCell::new(0);
+    static NEXT_FETCH_GENERATION: Cell<u64> = const { Cell::new(0) };
 }
 
 struct ResourceState {
@@ -487,9 +1277,42 @@ struct ResourceState {
     error: Option<String>,
     timestamp: f64,
     refetch_fn: Option<js_sys::Function>,
+    /// Which fetch this entry is waiting on; older fetches' results are dropped
+    generation: u64,
+    /// Aborts the in-flight fetch when the entry is invalidated
+    controller: Option<web_sys::AbortController>,
+    /// `suspense` boundaries showing their fallback until the fetch settles
+    waiters: Vec<Rc<SuspenseBoundary>>,
+}
+
+impl ResourceState {
+    fn abort(&self) {
+        if let Some(controller) = &self.controller {
+            controller.abort();
+        }
+    }
+
+    /// Hold the surrounding `suspense` boundary, if any, until this entry settles
+    fn suspend_current_boundary(&mut self) {
+        if let Some(boundary) = current_suspense_boundary() {
+            boundary.pending.set(boundary.pending.get() + 1);
+            self.waiters.push(boundary);
+        }
+    }
+
+    /// Let the boundaries waiting on this entry render again; called once it
+    /// has settled or been removed
+    fn release_waiters(&mut self) -> Vec<Rc<SuspenseBoundary>> {
+        std::mem::take(&mut self.waiters)
+    }
 }
 
 /// Create a resource for async data fetching
+///
+/// The fetcher is called with an `AbortSignal` that fires when the resource is
+/// invalidated or refetched before it settles. Reading a resource that is
+/// still loading inside `suspense` shows the boundary's fallback until it
+/// settles.
 #[wasm_bindgen(js_name = createResource)]
 pub fn create_resource(
     key: &str,
@@ -497,7 +1320,10 @@ pub fn create_resource(
 ) -> js_sys::Array {
     // Check cache first
     let cached = RESOURCE_CACHE.with(|cache| {
-        cache.borrow().get(key).map(|state| {
+        cache.borrow_mut().get_mut(key).map(|state| {
+            if state.loading {
+                state.suspend_current_boundary();
+            }
             let result = js_sys::Array::new();
             result.push(&state.data);
             result.push(&JsValue::from_bool(state.loading));
@@ -511,14 +1337,29 @@ pub fn create_resource(
     }
 
     // Initialize loading state
+    let generation = NEXT_FETCH_GENERATION.with(|next| {
+        let generation = next.get();
+        next.set(generation + 1);
+        generation
+    });
+    let controller = web_sys::AbortController::new().ok();
+    let signal = controller
+        .as_ref()
+        .map(|controller| JsValue::from(controller.signal()))
+        .unwrap_or(JsValue::UNDEFINED);
+    let mut state = ResourceState {
+        data: JsValue::NULL,
+        loading: true,
+        error: None,
+        timestamp: js_sys::Date::now(),
+        refetch_fn: Some(fetcher.clone()),
+        generation,
+        controller,
+        waiters: Vec::new(),
+    };
+    state.suspend_current_boundary();
     RESOURCE_CACHE.with(|cache| {
-        cache.borrow_mut().insert(key.to_string(), ResourceState {
-            data: JsValue::NULL,
-            loading: true,
-            error: None,
-            timestamp: js_sys::Date::now(),
-            refetch_fn: Some(fetcher.clone()),
-        });
+        cache.borrow_mut().insert(key.to_string(), state);
     });
 
     // Return initial loading state
@@ -530,46 +1371,60 @@ pub fn create_resource(
     // Trigger async fetch
     let key_clone = key.to_string();
     let fetcher_clone = fetcher.clone();
+    // A rejected fetch goes to the `catchError` handler around this call
+    let error_scope = current_error_scope();
 
     wasm_bindgen_futures::spawn_local(async move {
-        match call_async_fetcher(&fetcher_clone).await {
-            Ok(data) => {
-                RESOURCE_CACHE.with(|cache| {
-                    if let Some(state) = cache.borrow_mut().get_mut(&key_clone) {
-                        state.data = data;
-                        state.loading = false;
-                        state.error = None;
-                        state.timestamp = js_sys::Date::now();
-                    }
-                });
-            }
-            Err(err) => {
-                RESOURCE_CACHE.with(|cache| {
-                    if let Some(state) = cache.borrow_mut().get_mut(&key_clone) {
-                        state.loading = false;
-                        state.error = Some(format!("{:?}", err));
-                        state.timestamp = js_sys::Date::now();
-                    }
-                });
+        let outcome = call_async_fetcher(&fetcher_clone, &signal).await;
+
+        // Only the latest fetch for the key may settle it; a slower earlier
+        // response (or the rejection of an aborted one) is dropped
+        let settled = RESOURCE_CACHE.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            let state = cache.get_mut(&key_clone).filter(|state| state.generation == generation)?;
+            match &outcome {
+                Ok(data) => {
+                    state.data = data.clone();
+                    state.error = None;
+                }
+                Err(err) => state.error = Some(format!("{:?}", err)),
             }
+            state.loading = false;
+            state.timestamp = js_sys::Date::now();
+            state.controller = None;
+            Some(state.release_waiters())
+        });
+
+        let Some(waiters) = settled else {
+            return;
+        };
+        if let Err(err) = &outcome {
+            report_error(error_scope, "Resource error:", err);
+        }
+        for boundary in waiters {
+            boundary.settle();
         }
     });
 
     result
 }
 
-async fn call_async_fetcher(fetcher: &js_sys::Function) -> Result<JsValue, JsValue> {
-    let promise = fetcher.call0(&JsValue::NULL)?;
+async fn call_async_fetcher(fetcher: &js_sys::Function, signal: &JsValue) -> Result<JsValue, JsValue> {
+    let promise = fetcher.call1(&JsValue::NULL, signal)?;
     let promise = js_sys::Promise::from(promise);
     wasm_bindgen_futures::JsFuture::from(promise).await
 }
 
-/// Invalidate a resource cache entry
+/// Invalidate a resource cache entry, aborting its in-flight fetch
 #[wasm_bindgen(js_name = invalidateResource)]
 pub fn invalidate_resource(key: &str) {
-    RESOURCE_CACHE.with(|cache| {
-        cache.borrow_mut().remove(key);
-    });
+    let removed = RESOURCE_CACHE.with(|cache| cache.borrow_mut().remove(key));
+    if let Some(mut state) = removed {
+        state.abort();
+        for boundary in state.release_waiters() {
+            boundary.settle();
+        }
+    }
 }
 
 /// Refetch a resource
@@ -618,53 +1473,815 @@ pub fn get_resource_state(key: &str) -> js_sys::Array {
 /// Clear all resource caches
 #[wasm_bindgen(js_name = clearResourceCache)]
 pub fn clear_resource_cache() {
-    RESOURCE_CACHE.with(|cache| {
-        cache.borrow_mut().clear();
-    });
+    let cleared: Vec<ResourceState> =
+        RESOURCE_CACHE.with(|cache| cache.borrow_mut().drain().map(|(_, state)| state).collect());
+    for mut state in cleared {
+        state.abort();
+        for boundary in state.release_waiters() {
+            boundary.settle();
+        }
+    }
 }
 
 // ============================================================================
-// SSR Support (Phase 6)
+// Ownership
 // ============================================================================
 
-/// Render component to HTML string for SSR
-#[wasm_bindgen(js_name = renderToString)]
-pub fn render_to_string(component: &js_sys::Function) -> Result<String, JsValue> {
-    // Create a virtual DOM context for SSR
-    let result = component.call0(&JsValue::NULL)?;
+thread_local! {
+    /// Root or effect run that owns the effects, signals and cleanups being created
+    static OWNER: RefCell<Option<Rc<Owner>>> = const { RefCell::new(None) };
+}
 
-    // Convert the result to HTML string
-    // In a full implementation, this would traverse the component tree
-    // and generate HTML with hydration markers
+/// What a root or an effect run created, disposed together
+#[derive(Default)]
+struct Owner {
+    owned: RefCell<Vec<Owned>>,
+}
 
-    Ok(format!(
-        "<!DOCTYPE html>\
-         <html>\
-         <head><title>Velocity SSR</title></head>\
-         <body>\
-         <div id=\"root\" data-server-rendered=\"true\">{}</div>\
-         <script type=\"module\" src=\"/velocity-runtime.js\"></script>\
-         </body>\
-         </html>",
-        result.as_string().unwrap_or_default()
+enum Owned {
+    Effect(EffectId),
+    /// A signal from `createSignal`; memo values and store signals belong
+    /// to their memo or store
+    Signal(SignalId),
+    /// An `onCleanup` callback and the `catchError` scope it was registered in
+    Cleanup(js_sys::Function, Option<Rc<ErrorScope>>),
+}
+
+impl Owner {
+    /// Dispose everything owned, last created first: effects stop (and
+    /// dispose what they own), signals are released and cleanups run.
+    /// Disposing again does nothing.
+    fn dispose(&self) {
+        let owned = std::mem::take(&mut *self.owned.borrow_mut());
+        if owned.is_empty() {
+            return;
+        }
+
+        // Cleanups don't subscribe the running effect, and what they create
+        // isn't owned by what they clean up
+        with_owner(None, || {
+            untracked(|| {
+                for item in owned.into_iter().rev() {
+                    match item {
+                        Owned::Effect(id) => dispose_effect(id),
+                        Owned::Signal(id) => drop(with_runtime(|runtime| runtime.signals.remove(&id))),
+                        Owned::Cleanup(func, error_scope) => {
+                            let result = with_error_scope(error_scope.clone(), || func.call0(&JsValue::NULL));
+                            if let Err(e) = result {
+                                report_error(error_scope, "onCleanup error:", &e);
+                            }
+                        }
+                    }
+                }
+            })
+        });
+    }
+}
+
+/// Register `item` with the current owner; outside a root or an effect it
+/// lives as long as the page
+fn own(item: Owned) {
+    OWNER.with(|owner| {
+        if let Some(owner) = &*owner.borrow() {
+            owner.owned.borrow_mut().push(item);
+        }
+    });
+}
+
+/// Run `f` with `owner` owning what it creates
+fn with_owner<T>(owner: Option<Rc<Owner>>, f: impl FnOnce() -> T) -> T {
+    let previous = OWNER.with(|current| current.replace(owner));
+    let result = f();
+    OWNER.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// Stop an effect: unsubscribe it, drop it from the runtime and dispose what
+/// it owns
+fn dispose_effect(id: EffectId) {
+    let effect = RUNTIME.with(|runtime| {
+        let mut r = runtime.borrow_mut();
+        let effect = r.effects.remove(&id)?;
+        for signal_id in &effect.dependencies {
+            if let Some(signal) = r.signals.get_mut(signal_id) {
+                signal.subscribers.retain(|&e| e != id);
+            }
+        }
+        Some(effect)
+    });
+    if let Some(effect) = effect {
+        effect.owner.dispose();
+    }
+}
+
+/// A JS function disposing `owner`
+fn disposer(owner: Rc<Owner>) -> js_sys::Function {
+    Closure::wrap(Box::new(move || owner.dispose()) as Box<dyn Fn()>)
+        .into_js_value()
+        .unchecked_into()
+}
+
+/// Run `func` in a new root scope, passing it a function that disposes the
+/// root, and return what `func` returns.
+///
+/// Effects, `createSignal` signals and `onCleanup` callbacks created while
+/// `func` runs belong to the root: disposing it stops the effects (and what
+/// they created), releases the signals, which can't be read afterwards, and
+/// runs the cleanups. A root inside another root or an effect is disposed
+/// only by its own function. `func` runs untracked, so creating a root in an
+/// effect doesn't subscribe the effect to what `func` reads.
+#[wasm_bindgen(js_name = createRoot)]
+pub fn create_root(func: &js_sys::Function) -> Result<JsValue, JsValue> {
+    let owner = Rc::new(Owner::default());
+    let dispose = disposer(owner.clone());
+    with_owner(Some(owner), || untracked(|| func.call1(&JsValue::NULL, &dispose)))
+}
+
+/// Run `func` when the current root or effect is disposed. An effect's
+/// cleanups also run before it re-runs, so one registered in an effect undoes
+/// that run (`createEffect(() => { const id = setInterval(...);
+/// onCleanup(() => clearInterval(id)); })`). Outside a root or an effect it
+/// never runs.
+#[wasm_bindgen(js_name = onCleanup)]
+pub fn on_cleanup(func: &js_sys::Function) {
+    own(Owned::Cleanup(func.clone(), current_error_scope()));
+}
+
+// ============================================================================
+// Rendering & Lifecycle
+// ============================================================================
+
+thread_local! {
+    /// `onMount` callbacks, one frame per `render` call in progress
+    static MOUNT_QUEUE: RefCell<Vec<Vec<MountCallback>>> = RefCell::new(Vec::new());
+}
+
+/// An `onMount` callback and the `catchError` scope it was registered in
+type MountCallback = (js_sys::Function, Option<Rc<ErrorScope>>);
+
+/// Render a component into `container`, replacing its contents, and return
+/// a function that unmounts it.
+///
+/// The component runs in its own root (see [`create_root`]). Unmounting
+/// disposes the root, stopping the effects, releasing the signals and running
+/// the `onCleanup` callbacks the app created, and removes the nodes `render`
+/// inserted. Nodes added to `container` since, by another app or the page,
+/// are left in place. Unmounting again does nothing.
+///
+/// `onMount` callbacks registered while the component runs are called once
+/// its DOM has been inserted into `container`. They are dropped, and the
+/// root disposed, if the component throws. A top-level call restarts
+/// `createUniqueId`.
+#[wasm_bindgen(js_name = render)]
+pub fn render(component: &js_sys::Function, container: &Element) -> Result<js_sys::Function, JsValue> {
+    let document = document("render")?;
+    let owner = Rc::new(Owner::default());
+
+    let top_level = MOUNT_QUEUE.with(|queue| queue.borrow().is_empty());
+    MOUNT_QUEUE.with(|queue| queue.borrow_mut().push(Vec::new()));
+    let mount = || {
+        with_owner(Some(owner.clone()), || untracked(|| component.call0(&JsValue::NULL)))
+            .and_then(|value| mount_value(container, &value))
+    };
+    let result = if top_level { with_id_scope(RENDER_ID_PREFIX, mount) } else { mount() };
+    let callbacks = MOUNT_QUEUE.with(|queue| queue.borrow_mut().pop().unwrap_or_default());
+    if let Err(e) = result {
+        owner.dispose();
+        return Err(e);
+    }
+
+    // Bracket what was rendered, so unmounting removes just that
+    let start: Node = document.create_text_node("").into();
+    let end: Node = document.create_text_node("").into();
+    container.insert_before(&start, container.first_child().as_ref())?;
+    container.append_child(&end)?;
+
+    for (callback, error_scope) in callbacks {
+        run_mount_callback(&callback, error_scope);
+    }
+
+    let unmount = Closure::wrap(Box::new(move || {
+        owner.dispose();
+        while let Some(node) = start.next_sibling() {
+            if node == end {
+                break;
+            }
+            release_portals(&node);
+            let _ = start.parent_node().map(|parent| parent.remove_child(&node));
+        }
+        for marker in [&start, &end] {
+            let _ = marker.parent_node().map(|parent| parent.remove_child(marker));
+        }
+    }) as Box<dyn Fn()>);
+    Ok(unmount.into_js_value().unchecked_into())
+}
+
+/// Run `func` once, after the DOM of the component being rendered has been
+/// inserted into the document.
+///
+/// Callbacks run in registration order when the enclosing `render` call
+/// finishes, not on every effect run. Components created outside `render`
+/// (e.g. inside an effect) get their callbacks on the next microtask, after
+/// the caller has inserted the returned nodes.
+#[wasm_bindgen(js_name = onMount)]
+pub fn on_mount(func: &js_sys::Function) {
+    queue_mount_callback(func.clone(), current_error_scope());
+}
+
+/// Queue a callback on the innermost `render` frame, or for the next
+/// microtask outside `render`
+fn queue_mount_callback(func: js_sys::Function, error_scope: Option<Rc<ErrorScope>>) {
+    let unqueued = MOUNT_QUEUE.with(|queue| match queue.borrow_mut().last_mut() {
+        Some(frame) => {
+            frame.push((func, error_scope));
+            None
+        }
+        None => Some((func, error_scope)),
+    });
+
+    if let Some((func, error_scope)) = unqueued {
+        wasm_bindgen_futures::spawn_local(async move {
+            run_mount_callback(&func, error_scope);
+        });
+    }
+}
+
+fn run_mount_callback(callback: &js_sys::Function, error_scope: Option<Rc<ErrorScope>>) {
+    let result = with_error_scope(error_scope.clone(), || callback.call0(&JsValue::NULL));
+    if let Err(e) = result {
+        report_error(error_scope, "onMount error:", &e);
+    }
+}
+
+// ============================================================================
+// Context
+// ============================================================================
+
+/// Id of [`request_context`]; contexts from `createContext` count up from it
+const REQUEST_CONTEXT: u32 = 0;
+
+thread_local! {
+    static NEXT_CONTEXT_ID: Cell<u32> = const { Cell::new(REQUEST_CONTEXT + 1) };
+    static CONTEXT_SCOPE: RefCell<Option<Rc<ContextScope>>> = const { RefCell::new(None) };
+}
+
+/// A value provided for a context, and the values provided around it
+struct ContextScope {
+    context: u32,
+    value: JsValue,
+    parent: Option<Rc<ContextScope>>,
+}
+
+fn current_context_scope() -> Option<Rc<ContextScope>> {
+    CONTEXT_SCOPE.with(|scope| scope.borrow().clone())
+}
+
+/// Run `f` with `scope` as the current context scope
+fn with_context_scope<T>(scope: Option<Rc<ContextScope>>, f: impl FnOnce() -> T) -> T {
+    let previous = CONTEXT_SCOPE.with(|current| current.replace(scope));
+    let result = f();
+    CONTEXT_SCOPE.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// A value passed down the component tree without props. Provide it with
+/// `provideContext` and read it with `useContext`.
+#[wasm_bindgen]
+pub struct Context {
+    id: u32,
+    default_value: JsValue,
+}
+
+/// Create a context whose `useContext` returns `default_value` where no
+/// value has been provided
+#[wasm_bindgen(js_name = createContext)]
+pub fn create_context(default_value: JsValue) -> Context {
+    let id = NEXT_CONTEXT_ID.with(|next| next.replace(next.get() + 1));
+    Context { id, default_value }
+}
+
+/// The context `renderToString` and `renderToStream` provide their
+/// `request` argument as. It's `undefined` outside a server render.
+#[wasm_bindgen(js_name = requestContext)]
+pub fn request_context() -> Context {
+    Context { id: REQUEST_CONTEXT, default_value: JsValue::UNDEFINED }
+}
+
+/// Run `func` with `value` provided for `context`, returning its result.
+/// Components `func` renders, and effects and memos created while it runs,
+/// see `value` from `useContext(context)`, unless a nested `provideContext`
+/// provides another.
+#[wasm_bindgen(js_name = provideContext)]
+pub fn provide_context(context: &Context, value: JsValue, func: &js_sys::Function) -> Result<JsValue, JsValue> {
+    let scope = Rc::new(ContextScope {
+        context: context.id,
+        value,
+        parent: current_context_scope(),
+    });
+    with_context_scope(Some(scope), || func.call0(&JsValue::NULL))
+}
+
+/// The value of the nearest `provideContext` for `context` around the
+/// caller, or the context's default value
+#[wasm_bindgen(js_name = useContext)]
+pub fn use_context(context: &Context) -> JsValue {
+    let mut scope = current_context_scope();
+    while let Some(current) = scope {
+        if current.context == context.id {
+            return current.value.clone();
+        }
+        scope = current.parent.clone();
+    }
+    context.default_value.clone()
+}
+
+// ============================================================================
+// Portals
+// ============================================================================
+
+thread_local! {
+    /// Portals currently rendered into their targets
+    static PORTALS: RefCell<Vec<Portal>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Portal {
+    /// Empty text node left where the portal was declared
+    placeholder: Node,
+    /// Wrapper around the portal's content inside the target
+    container: Element,
+}
+
+/// Render `children` into `target` instead of where the portal is declared,
+/// e.g. a modal into `document.body`. A `null` target means `document.body`.
+///
+/// `children` is called right away, in the declaring component's context:
+/// its effects report to the surrounding `catchError`, its `onMount`
+/// callbacks run with the declaring `render`, and signals it reads update
+/// the portal in place. The content sits in a `<div data-velocity-portal>`
+/// appended to `target`.
+///
+/// Returns an empty placeholder to insert at the declaration site; when the
+/// placeholder is removed by a re-render (`{open() && portal(...)}` turning
+/// false) or a new `render` into its container, the portal's content is
+/// removed too. During SSR nothing is rendered and `children` isn't called;
+/// the portal appears once the client renders.
+#[wasm_bindgen(js_name = portal)]
+pub fn portal(target: Option<Element>, children: &js_sys::Function) -> Result<JsValue, JsValue> {
+    if is_ssr() {
+        return Ok(JsValue::UNDEFINED);
+    }
+
+    let document = document("portal")?;
+    let target = match target {
+        Some(target) => target,
+        None => document.body().ok_or("portal: no target and no document.body")?.into(),
+    };
+
+    let container = document.create_element("div")?;
+    container.set_attribute("data-velocity-portal", "")?;
+    let content = build_fragment(&document, children.unchecked_ref())?;
+    container.append_child(&content)?;
+    target.append_child(&container)?;
+
+    let placeholder: Node = document.create_text_node("").into();
+    PORTALS.with(|portals| {
+        portals.borrow_mut().push(Portal {
+            placeholder: placeholder.clone(),
+            container,
+        })
+    });
+    Ok(placeholder.into())
+}
+
+/// Remove the content of every portal declared inside `removed`
+fn release_portals(removed: &Node) {
+    let released: Vec<Portal> = PORTALS.with(|portals| {
+        let mut portals = portals.borrow_mut();
+        let (released, kept) = std::mem::take(&mut *portals)
+            .into_iter()
+            .partition(|portal| removed.contains(Some(&portal.placeholder)));
+        *portals = kept;
+        released
+    });
+    for portal in released {
+        portal.container.remove();
+    }
+}
+
+// ============================================================================
+// Suspense
+// ============================================================================
+
+thread_local! {
+    /// The `suspense` boundary whose children are being built
+    static SUSPENSE_BOUNDARY: RefCell<Option<Rc<SuspenseBoundary>>> = const { RefCell::new(None) };
+}
+
+/// One build of a `suspense` boundary's children
+struct SuspenseBoundary {
+    /// The boundary's own id, which its children's ids are numbered under
+    id: String,
+    /// Resources read during the build that were still loading
+    pending: Cell<usize>,
+    /// Builds the children again; taken by the first resource to settle, as
+    /// that build replaces this one
+    on_settle: RefCell<Option<Box<dyn FnOnce()>>>,
+}
+
+impl SuspenseBoundary {
+    fn new(id: &str, on_settle: impl FnOnce() + 'static) -> Rc<Self> {
+        Rc::new(SuspenseBoundary {
+            id: id.to_string(),
+            pending: Cell::new(0),
+            on_settle: RefCell::new(Some(Box::new(on_settle))),
+        })
+    }
+
+    fn settle(&self) {
+        let on_settle = self.on_settle.borrow_mut().take();
+        if let Some(on_settle) = on_settle {
+            on_settle();
+        }
+    }
+
+    /// Call `children` with this boundary collecting the resources it reads.
+    /// Signals it reads aren't tracked: only settling resources rebuild it.
+    fn build(self: &Rc<Self>, children: &js_sys::Function) -> Result<JsValue, JsValue> {
+        let previous = SUSPENSE_BOUNDARY.with(|current| current.replace(Some(self.clone())));
+        let result = with_id_scope(&self.id, || untracked(|| children.call0(&JsValue::NULL)));
+        SUSPENSE_BOUNDARY.with(|current| *current.borrow_mut() = previous);
+        result
+    }
+}
+
+fn current_suspense_boundary() -> Option<Rc<SuspenseBoundary>> {
+    SUSPENSE_BOUNDARY.with(|current| current.borrow().clone())
+}
+
+/// Show `fallback` while resources read by `children` are loading, then
+/// `children`.
+///
+/// `children` is called right away; every `createResource` it reads that
+/// is still loading holds the boundary, including resources read by the
+/// components it renders (but not by reactive children or effects, which
+/// run later). Once they have all settled, `children` is called again and
+/// its result, built with the cached data, replaces the fallback. If that
+/// build reads resources that are still loading in turn, the fallback
+/// stays until they settle too. Refetching or invalidating a resource
+/// brings the fallback back until the new data arrives. `fallback` may be
+/// a value or a function returning one.
+///
+/// `onMount` callbacks registered by `children` run once its content is
+/// revealed, never for a build replaced by the fallback.
+///
+/// During SSR, `renderToString` renders the fallback, and `renderToStream`
+/// flushes it with the page, then streams the content once it resolves.
+#[wasm_bindgen(js_name = suspense)]
+pub fn suspense(fallback: JsValue, children: &js_sys::Function) -> Result<JsValue, JsValue> {
+    if is_ssr() {
+        return ssr_suspense(fallback, children);
+    }
+
+    // Bumped when a resource the current build waits on settles
+    let settled = Rc::new(Signal::new(JsValue::from(0)));
+    let id = create_unique_id();
+    let children = children.clone();
+    let view = Closure::wrap(Box::new(move || -> Result<JsValue, JsValue> {
+        let builds = settled.get()?;
+
+        let next = settled.clone();
+        let boundary = SuspenseBoundary::new(&id, move || {
+            let builds = builds.as_f64().unwrap_or_default() + 1.0;
+            if let Err(error) = next.set(JsValue::from(builds)) {
+                report_error(None, "Suspense error:", &error);
+            }
+        });
+        MOUNT_QUEUE.with(|queue| queue.borrow_mut().push(Vec::new()));
+        let content = boundary.build(&children);
+        let callbacks = MOUNT_QUEUE.with(|queue| queue.borrow_mut().pop().unwrap_or_default());
+        let content = content?;
+
+        if boundary.pending.get() > 0 {
+            return render_fallback(&fallback);
+        }
+        for (callback, error_scope) in callbacks {
+            queue_mount_callback(callback, error_scope);
+        }
+        Ok(content)
+    }) as Box<dyn Fn() -> Result<JsValue, JsValue>>);
+
+    let func = view.as_ref().clone();
+    view.forget();
+    Ok(func)
+}
+
+fn render_fallback(fallback: &JsValue) -> Result<JsValue, JsValue> {
+    match fallback.dyn_ref::<js_sys::Function>() {
+        Some(fallback) => untracked(|| fallback.call0(&JsValue::NULL)),
+        None => Ok(fallback.clone()),
+    }
+}
+
+// ============================================================================
+// SSR Support (Phase 6)
+// ============================================================================
+
+/// The page around the server-rendered root, split where the root's
+/// content and streamed boundaries go
+const SSR_DOCUMENT_START: &str = "<!DOCTYPE html>\
+     <html>\
+     <head><title>Velocity SSR</title></head>\
+     <body>\
+     <div id=\"root\" data-server-rendered=\"true\">";
+const SSR_ROOT_END: &str = "</div>";
+const SSR_DOCUMENT_END: &str = "<script type=\"module\" src=\"/velocity-runtime.js\"></script>\
+     </body>\
+     </html>";
+
+/// Property of the objects holding markup built during SSR, which sets
+/// them apart from strings, whose text is escaped when they're children
+const SSR_HTML: &str = "__velocityHtml";
+
+/// Markup built during SSR, as a value components can pass around
+fn ssr_node(html: String) -> JsValue {
+    let node = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&node, &JsValue::from_str(SSR_HTML), &JsValue::from_str(&html));
+    node.into()
+}
+
+/// The markup in `value`, if it was built during SSR
+fn ssr_node_html(value: &JsValue) -> Option<String> {
+    if !value.is_object() {
+        return None;
+    }
+    js_sys::Reflect::get(value, &JsValue::from_str(SSR_HTML)).ok()?.as_string()
+}
+
+/// `createElement` during SSR: the element's markup
+fn ssr_element(tag: &str, props: &JsValue, children: &js_sys::Array) -> Result<JsValue, JsValue> {
+    let mut attributes = Vec::new();
+    if props.is_object() {
+        let props = props.unchecked_ref::<js_sys::Object>();
+        for entry in js_sys::Object::entries(props).iter() {
+            let entry = entry.unchecked_into::<js_sys::Array>();
+            let (Some(key), Some(value)) = (entry.get(0).as_string(), entry.get(1).as_string()) else {
+                continue;
+            };
+            match key.as_str() {
+                "className" => attributes.push(("class".to_string(), value)),
+                key if key.starts_with("on") => {}
+                _ => attributes.push((key, value)),
+            }
+        }
+    }
+
+    let children = ssr_children(children)?;
+    Ok(ssr_node(ssr_html::element(tag, &attributes, &children)))
+}
+
+/// The markup of rendered children during SSR, mirroring `build_fragment`:
+/// strings and numbers are escaped text, functions are called once,
+/// arrays are flattened, and anything else renders nothing
+fn ssr_children(value: &JsValue) -> Result<String, JsValue> {
+    if let Some(html) = ssr_node_html(value) {
+        Ok(html)
+    } else if let Some(text) = value.as_string() {
+        Ok(ssr_html::escape_text(&text))
+    } else if value.as_f64().is_some() {
+        Ok(String::from(value.unchecked_ref::<js_sys::Number>().to_string_with_radix(10)?))
+    } else if let Some(func) = value.dyn_ref::<js_sys::Function>() {
+        ssr_children(&untracked(|| func.call0(&JsValue::NULL))?)
+    } else if js_sys::Array::is_array(value) {
+        value.unchecked_ref::<js_sys::Array>().iter().map(|item| ssr_children(&item)).collect()
+    } else {
+        Ok(String::new())
+    }
+}
+
+/// The HTML of what a component rendered during SSR. A string is taken to
+/// be HTML already, as components rendering without `createElement` return.
+fn ssr_markup(value: &JsValue) -> Result<String, JsValue> {
+    match value.as_string() {
+        Some(html) => Ok(html),
+        None => ssr_children(value),
+    }
+}
+
+thread_local! {
+    /// The `renderToStream` page being rendered, for `suspense` boundaries
+    /// to stream their content into
+    static SSR_STREAM: RefCell<Option<Rc<SsrStream>>> = const { RefCell::new(None) };
+}
+
+/// A page `renderToStream` is writing
+struct SsrStream {
+    controller: web_sys::ReadableStreamDefaultController,
+    next_boundary: Cell<usize>,
+    /// The shell and the boundaries whose content hasn't been written yet
+    unresolved: Cell<usize>,
+}
+
+impl SsrStream {
+    fn write(&self, chunk: &str) {
+        // Only fails once the reader has cancelled the stream
+        let _ = self.controller.enqueue_with_chunk(&JsValue::from_str(chunk));
+    }
+
+    /// Count the shell or a boundary as written, ending the page after the
+    /// last one
+    fn resolve(&self) {
+        self.unresolved.set(self.unresolved.get() - 1);
+        if self.unresolved.get() == 0 {
+            self.write(SSR_DOCUMENT_END);
+            let _ = self.controller.close();
+        }
+    }
+}
+
+/// Run `f` with `stream` as the page being streamed
+fn with_ssr_stream<T>(stream: Option<Rc<SsrStream>>, f: impl FnOnce() -> T) -> T {
+    let previous = SSR_STREAM.with(|current| current.replace(stream));
+    let result = f();
+    SSR_STREAM.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// The context scope a server render starts with: only `request`
+fn request_scope(request: JsValue) -> Option<Rc<ContextScope>> {
+    Some(Rc::new(ContextScope {
+        context: REQUEST_CONTEXT,
+        value: request,
+        parent: None,
+    }))
+}
+
+/// Render component to HTML string for SSR.
+///
+/// `request` is the data of the request being served (URL, headers,
+/// cookies, in whatever shape the server has them); components read it
+/// with `useContext(RequestContext)`. The render starts with no context
+/// values provided, so nothing provided by an earlier render or around
+/// the call leaks into it. Rendering is synchronous, so renders for
+/// different requests can't interleave. Each render restarts
+/// `createUniqueId`. `suspense` boundaries still
+/// waiting on resources render their fallback.
+#[wasm_bindgen(js_name = renderToString)]
+pub fn render_to_string(component: &js_sys::Function, request: JsValue) -> Result<String, JsValue> {
+    // Create a virtual DOM context for SSR
+    let result = with_id_scope(RENDER_ID_PREFIX, || {
+        with_context_scope(request_scope(request), || component.call0(&JsValue::NULL))
+    })?;
+
+    // Convert the result to HTML string
+    // In a full implementation, this would traverse the component tree
+    // and generate HTML with hydration markers
+
+    Ok(format!(
+        "{}{}{}{}",
+        SSR_DOCUMENT_START,
+        ssr_markup(&result)?,
+        SSR_ROOT_END,
+        SSR_DOCUMENT_END
     ))
 }
 
-/// Render component to readable stream for streaming SSR
+/// Render component to a `ReadableStream` of HTML strings for streaming
+/// SSR. `request` is provided as in [`render_to_string`].
+///
+/// The page is written right away, with the fallback of each `suspense`
+/// boundary still waiting on resources in a `<div
+/// data-velocity-suspense>`. As each boundary's resources settle, its
+/// content is written after the root in a `<template>`, with an inline
+/// script moving it into place, so the browser shows it without waiting
+/// for the rest. The stream closes once every boundary has been written.
 #[wasm_bindgen(js_name = renderToStream)]
-pub fn render_to_stream(component: &js_sys::Function) -> Result<JsValue, JsValue> {
-    // This would return a ReadableStream in a full implementation
-    // For now, return the HTML as a promise
-    let html = render_to_string(component)?;
-    let promise = js_sys::Promise::resolve(&JsValue::from_str(&html));
-    Ok(promise.into())
+pub fn render_to_stream(component: &js_sys::Function, request: JsValue) -> Result<web_sys::ReadableStream, JsValue> {
+    // `start` runs inside the constructor, handing over the controller
+    let controller = Rc::new(RefCell::new(None));
+    let started = controller.clone();
+    let start = Closure::once(move |controller: web_sys::ReadableStreamDefaultController| {
+        *started.borrow_mut() = Some(controller);
+    });
+    let source = js_sys::Object::new();
+    js_sys::Reflect::set(&source, &JsValue::from_str("start"), start.as_ref())?;
+    let readable = web_sys::ReadableStream::new_with_underlying_source(&source)?;
+    let controller = controller.borrow_mut().take().ok_or("ReadableStream didn't start")?;
+
+    let stream = Rc::new(SsrStream {
+        controller,
+        next_boundary: Cell::new(0),
+        unresolved: Cell::new(1),
+    });
+    let result = with_ssr_stream(Some(stream.clone()), || {
+        with_id_scope(RENDER_ID_PREFIX, || {
+            with_context_scope(request_scope(request), || component.call0(&JsValue::NULL))
+        })
+    })?;
+
+    stream.write(&format!(
+        "{}{}{}",
+        SSR_DOCUMENT_START,
+        ssr_markup(&result)?,
+        SSR_ROOT_END
+    ));
+    stream.resolve();
+    Ok(readable)
+}
+
+/// A Promise, and the callback resolving it
+fn settle_promise() -> (js_sys::Promise, impl FnOnce()) {
+    let mut resolve = None;
+    let promise = js_sys::Promise::new(&mut |resolve_fn, _reject| resolve = Some(resolve_fn));
+    let settle = move || {
+        if let Some(resolve) = resolve {
+            let _ = resolve.call0(&JsValue::NULL);
+        }
+    };
+    (promise, settle)
+}
+
+/// `suspense` on the server: the children if their resources have all
+/// settled, else the fallback, streamed over once they do when rendering
+/// with `renderToStream`
+fn ssr_suspense(fallback: JsValue, children: &js_sys::Function) -> Result<JsValue, JsValue> {
+    let id = create_unique_id();
+    let (settled, on_settle) = settle_promise();
+    let boundary = SuspenseBoundary::new(&id, on_settle);
+    let content = boundary.build(children)?;
+    if boundary.pending.get() == 0 {
+        return Ok(content);
+    }
+
+    let fallback = render_fallback(&fallback)?;
+    let Some(stream) = SSR_STREAM.with(|current| current.borrow().clone()) else {
+        return Ok(fallback);
+    };
+    let index = stream.next_boundary.replace(stream.next_boundary.get() + 1);
+    stream.unresolved.set(stream.unresolved.get() + 1);
+    wasm_bindgen_futures::spawn_local(stream_boundary(
+        stream,
+        index,
+        id,
+        children.clone(),
+        current_context_scope(),
+        current_error_scope(),
+        settled,
+    ));
+
+    Ok(ssr_node(format!(
+        "<div data-velocity-suspense=\"{}\">{}</div>",
+        index,
+        ssr_markup(&fallback)?
+    )))
+}
+
+/// Rebuild a streamed boundary's children each time a resource it waits on
+/// settles, and write them once nothing is loading. If they throw, the
+/// fallback stays.
+async fn stream_boundary(
+    stream: Rc<SsrStream>,
+    index: usize,
+    id: String,
+    children: js_sys::Function,
+    context: Option<Rc<ContextScope>>,
+    error_scope: Option<Rc<ErrorScope>>,
+    mut settled: js_sys::Promise,
+) {
+    loop {
+        let _ = wasm_bindgen_futures::JsFuture::from(settled).await;
+
+        let (next, on_settle) = settle_promise();
+        let boundary = SuspenseBoundary::new(&id, on_settle);
+        let content = with_ssr_stream(Some(stream.clone()), || {
+            with_context_scope(context.clone(), || with_error_scope(error_scope.clone(), || boundary.build(&children)))
+        });
+        match content.and_then(|content| ssr_markup(&content)) {
+            Ok(_) if boundary.pending.get() > 0 => settled = next,
+            Ok(content) => {
+                stream.write(&format!(
+                    "<template data-velocity-resolved=\"{index}\">{}</template>\
+                     <script>(function(){{\
+                     var t=document.querySelector('template[data-velocity-resolved=\"{index}\"]'),\
+                     b=document.querySelector('[data-velocity-suspense=\"{index}\"]');\
+                     if(t&&b){{b.replaceChildren(t.content);t.remove()}}\
+                     }})()</script>",
+                    content,
+                ));
+                break;
+            }
+            Err(error) => {
+                report_error(error_scope.clone(), "Suspense render error:", &error);
+                break;
+            }
+        }
+    }
+    stream.resolve();
 }
 
 /// Hydrate server-rendered content on the client
 #[wasm_bindgen(js_name = hydrateRoot)]
 pub fn hydrate_root(container_id: &str) -> Result<(), JsValue> {
-    let window = web_sys::window().ok_or("No window")?;
-    let document = window.document().ok_or("No document")?;
+    let document = document("hydrateRoot")?;
 
     let container = document
         .get_element_by_id(container_id)
@@ -695,8 +2312,12 @@ pub fn is_ssr() -> bool {
 }
 
 /// Serialize app state for hydration
+///
+/// Returns a string in the structured format from [`serialize`], so signal
+/// and resource values keep their `Date`s, `Map`s, `Set`s and circular
+/// references across the server/client boundary.
 #[wasm_bindgen(js_name = serializeState)]
-pub fn serialize_state() -> JsValue {
+pub fn serialize_state() -> String {
     let state = js_sys::Object::new();
 
     // Serialize all signals
@@ -736,16 +2357,26 @@ pub fn serialize_state() -> JsValue {
         js_sys::Reflect::set(&state, &JsValue::from_str("resources"), &resources_obj).ok();
     });
 
-    state.into()
+    serialize::encode(&js_to_graph(&state))
 }
 
 /// Deserialize and restore app state during hydration
+///
+/// Accepts the string produced by `serializeState`, or an already parsed
+/// state object.
 #[wasm_bindgen(js_name = deserializeState)]
 pub fn deserialize_state(state: &JsValue) -> Result<(), JsValue> {
-    let state_obj = js_sys::Object::from(state.clone());
+    let state = match state.as_string() {
+        Some(text) => deserialize_value(&text)?,
+        None => state.clone(),
+    };
+    let state_obj = js_sys::Object::from(state);
 
     // Restore signals
     if let Ok(signals) = js_sys::Reflect::get(&state_obj, &JsValue::from_str("signals")) {
+        if !signals.is_object() {
+            return Ok(());
+        }
         let signals_obj = js_sys::Object::from(signals);
         let keys = js_sys::Object::keys(&signals_obj);
 
@@ -765,54 +2396,311 @@ pub fn deserialize_state(state: &JsValue) -> Result<(), JsValue> {
     Ok(())
 }
 
-// ============================================================================
-// Production Polish (Phase 7)
-// ============================================================================
+/// Serialize any value in the hydration format
+#[wasm_bindgen(js_name = serializeValue)]
+pub fn serialize_value(value: &JsValue) -> String {
+    serialize::encode(&js_to_graph(value))
+}
 
-thread_local! {
-    static ERROR_BOUNDARY_HANDLERS: RefCell<Vec<js_sys::Function>> = RefCell::new(Vec::new());
-    static DEVTOOLS_ENABLED: RefCell<bool> = RefCell::new(false);
+/// Parse a string produced by `serializeValue` or `serializeState`
+#[wasm_bindgen(js_name = deserializeValue)]
+pub fn deserialize_value(text: &str) -> Result<JsValue, JsValue> {
+    let graph = serialize::decode(text).map_err(|e| JsValue::from_str(&e))?;
+    Ok(graph_to_js(&graph))
 }
 
-/// Create an error boundary to catch component errors
-#[wasm_bindgen(js_name = createErrorBoundary)]
-pub fn create_error_boundary(
-    component: &js_sys::Function,
-    fallback: &js_sys::Function,
-) -> js_sys::Function {
-    let component_clone = component.clone();
-    let fallback_clone = fallback.clone();
+/// Flatten a JS value into a graph, visiting each object once
+fn js_to_graph(value: &JsValue) -> Graph {
+    let mut nodes = Vec::new();
+    let seen = js_sys::Map::new();
+    let root = js_to_ref(value, &mut nodes, &seen);
+    Graph { root, nodes }
+}
 
-    let boundary = Closure::wrap(Box::new(move || -> JsValue {
-        match component_clone.call0(&JsValue::NULL) {
-            Ok(result) => result,
-            Err(error) => {
-                console::error_2(&"Error boundary caught:".into(), &error);
+fn js_to_ref(value: &JsValue, nodes: &mut Vec<GraphNode>, seen: &js_sys::Map) -> Ref {
+    if let Some(number) = value.as_f64() {
+        return Ref::number(number, nodes);
+    }
+    if value.is_undefined() || value.is_function() || value.is_symbol() {
+        return Ref::Undefined;
+    }
+    if !value.is_object() {
+        let node = if value.is_null() {
+            GraphNode::Null
+        } else if let Some(flag) = value.as_bool() {
+            GraphNode::Bool(flag)
+        } else if let Some(text) = value.as_string() {
+            GraphNode::String(text)
+        } else if value.is_bigint() {
+            let digits = value
+                .unchecked_ref::<js_sys::BigInt>()
+                .to_string(10)
+                .map(String::from)
+                .unwrap_or_default();
+            GraphNode::BigInt(digits)
+        } else {
+            return Ref::Undefined;
+        };
+        nodes.push(node);
+        return Ref::Node(nodes.len() - 1);
+    }
 
-                // Trigger error handlers
-                ERROR_BOUNDARY_HANDLERS.with(|handlers| {
-                    for handler in handlers.borrow().iter() {
-                        let _ = handler.call1(&JsValue::NULL, &error);
-                    }
-                });
+    if let Some(index) = seen.get(value).as_f64() {
+        return Ref::Node(index as usize);
+    }
 
-                // Return fallback UI
-                fallback_clone.call0(&JsValue::NULL).unwrap_or(JsValue::NULL)
-            }
+    // Reserve the slot first so cycles back to this object resolve to it
+    let index = nodes.len();
+    nodes.push(GraphNode::Null);
+    seen.set(value, &JsValue::from_f64(index as f64));
+
+    let node = if let Some(date) = value.dyn_ref::<js_sys::Date>() {
+        let time = date.get_time();
+        GraphNode::Date((!time.is_nan()).then_some(time))
+    } else if let Some(regexp) = value.dyn_ref::<js_sys::RegExp>() {
+        GraphNode::RegExp {
+            source: regexp.source().into(),
+            flags: regexp.flags().into(),
         }
-    }) as Box<dyn Fn() -> JsValue>);
-
-    let func = boundary.as_ref().clone();
-    boundary.forget();
-    func.into()
-}
+    } else if let Some(map) = value.dyn_ref::<js_sys::Map>() {
+        let entries = js_sys::Array::from(&map.entries());
+        let mut pairs = Vec::with_capacity(entries.length() as usize);
+        for entry in entries.iter() {
+            let entry: js_sys::Array = entry.unchecked_into();
+            let key = js_to_ref(&entry.get(0), nodes, seen);
+            let value = js_to_ref(&entry.get(1), nodes, seen);
+            pairs.push((key, value));
+        }
+        GraphNode::Map(pairs)
+    } else if let Some(set) = value.dyn_ref::<js_sys::Set>() {
+        let values = js_sys::Array::from(set);
+        GraphNode::Set(values.iter().map(|item| js_to_ref(&item, nodes, seen)).collect())
+    } else if js_sys::Array::is_array(value) {
+        let array: &js_sys::Array = value.unchecked_ref();
+        let object: &js_sys::Object = value.unchecked_ref();
+        let items = (0..array.length())
+            .map(|i| {
+                if js_sys::Object::has_own(object, &JsValue::from_f64(i as f64)) {
+                    js_to_ref(&array.get(i), nodes, seen)
+                } else {
+                    Ref::Hole
+                }
+            })
+            .collect();
+        GraphNode::Array(items)
+    } else {
+        let object: &js_sys::Object = value.unchecked_ref();
+        let keys = js_sys::Object::keys(object);
+        let mut entries = Vec::with_capacity(keys.length() as usize);
+        for key in keys.iter() {
+            let Some(name) = key.as_string() else { continue };
+            let property = js_sys::Reflect::get(object, &key).unwrap_or(JsValue::UNDEFINED);
+            entries.push((name, js_to_ref(&property, nodes, seen)));
+        }
+        GraphNode::Object(entries)
+    };
 
-/// Register a global error handler
-#[wasm_bindgen(js_name = onError)]
-pub fn on_error(handler: &js_sys::Function) {
-    ERROR_BOUNDARY_HANDLERS.with(|handlers| {
-        handlers.borrow_mut().push(handler.clone());
-    });
+    nodes[index] = node;
+    Ref::Node(index)
+}
+
+/// Rebuild a JS value from a graph. Containers are created before they are
+/// filled so references back into them (cycles) point at the same object.
+fn graph_to_js(graph: &Graph) -> JsValue {
+    let values: Vec<JsValue> = graph
+        .nodes
+        .iter()
+        .map(|node| match node {
+            GraphNode::Null => JsValue::NULL,
+            GraphNode::Bool(flag) => JsValue::from_bool(*flag),
+            GraphNode::Number(number) => JsValue::from_f64(*number),
+            GraphNode::String(text) => JsValue::from_str(text),
+            GraphNode::BigInt(digits) => js_sys::BigInt::new(&JsValue::from_str(digits))
+                .map(JsValue::from)
+                .unwrap_or(JsValue::UNDEFINED),
+            GraphNode::Array(items) => js_sys::Array::new_with_length(items.len() as u32).into(),
+            GraphNode::Object(_) => js_sys::Object::new().into(),
+            GraphNode::Date(time) => js_sys::Date::new(&JsValue::from_f64(time.unwrap_or(f64::NAN))).into(),
+            GraphNode::Map(_) => js_sys::Map::new().into(),
+            GraphNode::Set(_) => js_sys::Set::new(&JsValue::UNDEFINED).into(),
+            GraphNode::RegExp { source, flags } => js_sys::RegExp::new(source, flags).into(),
+        })
+        .collect();
+
+    let resolve = |reference: Ref| match reference {
+        Ref::Node(index) => values[index].clone(),
+        Ref::Undefined | Ref::Hole => JsValue::UNDEFINED,
+        Ref::NaN => JsValue::from_f64(f64::NAN),
+        Ref::Infinity => JsValue::from_f64(f64::INFINITY),
+        Ref::NegInfinity => JsValue::from_f64(f64::NEG_INFINITY),
+        Ref::NegZero => JsValue::from_f64(-0.0),
+    };
+
+    for (node, value) in graph.nodes.iter().zip(&values) {
+        match node {
+            GraphNode::Array(items) => {
+                let array: &js_sys::Array = value.unchecked_ref();
+                for (i, item) in items.iter().enumerate() {
+                    // Holes stay holes: never assigning the index keeps the array sparse
+                    if *item != Ref::Hole {
+                        array.set(i as u32, resolve(*item));
+                    }
+                }
+            }
+            GraphNode::Object(entries) => {
+                let object: &js_sys::Object = value.unchecked_ref();
+                for (key, item) in entries {
+                    let key = JsValue::from_str(key);
+                    if key == "__proto__" {
+                        // A plain assignment would replace the prototype instead
+                        let descriptor = js_sys::Object::new();
+                        js_sys::Reflect::set(&descriptor, &JsValue::from_str("value"), &resolve(*item)).ok();
+                        js_sys::Reflect::set(&descriptor, &JsValue::from_str("enumerable"), &JsValue::TRUE).ok();
+                        js_sys::Reflect::set(&descriptor, &JsValue::from_str("writable"), &JsValue::TRUE).ok();
+                        js_sys::Reflect::set(&descriptor, &JsValue::from_str("configurable"), &JsValue::TRUE).ok();
+                        js_sys::Object::define_property(object, &key, &descriptor);
+                    } else {
+                        js_sys::Reflect::set(object, &key, &resolve(*item)).ok();
+                    }
+                }
+            }
+            GraphNode::Map(pairs) => {
+                let map: &js_sys::Map = value.unchecked_ref();
+                for (key, item) in pairs {
+                    map.set(&resolve(*key), &resolve(*item));
+                }
+            }
+            GraphNode::Set(items) => {
+                let set: &js_sys::Set = value.unchecked_ref();
+                for item in items {
+                    set.add(&resolve(*item));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    resolve(graph.root)
+}
+
+// ============================================================================
+// Production Polish (Phase 7)
+// ============================================================================
+
+thread_local! {
+    static ERROR_BOUNDARY_HANDLERS: RefCell<Vec<js_sys::Function>> = RefCell::new(Vec::new());
+    static DEVTOOLS_ENABLED: RefCell<bool> = RefCell::new(false);
+    static ERROR_SCOPE: RefCell<Option<Rc<ErrorScope>>> = const { RefCell::new(None) };
+}
+
+/// A `catchError` handler and the scope it was installed in
+struct ErrorScope {
+    handler: js_sys::Function,
+    parent: Option<Rc<ErrorScope>>,
+}
+
+fn current_error_scope() -> Option<Rc<ErrorScope>> {
+    ERROR_SCOPE.with(|scope| scope.borrow().clone())
+}
+
+/// Run `f` with `scope` as the current error scope
+fn with_error_scope<T>(scope: Option<Rc<ErrorScope>>, f: impl FnOnce() -> T) -> T {
+    let previous = ERROR_SCOPE.with(|current| current.replace(scope));
+    let result = f();
+    ERROR_SCOPE.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// Send an error to the nearest `catchError` handler. A handler that throws
+/// passes its error on to the next one out; with no handler left the error is
+/// logged and the `onError` handlers are notified.
+fn report_error(scope: Option<Rc<ErrorScope>>, context: &str, error: &JsValue) {
+    let mut scope = scope;
+    let mut error = error.clone();
+
+    while let Some(current) = scope {
+        let handled = with_error_scope(current.parent.clone(), || {
+            current.handler.call1(&JsValue::NULL, &error)
+        });
+        match handled {
+            Ok(_) => return,
+            Err(rethrown) => {
+                error = rethrown;
+                scope = current.parent.clone();
+            }
+        }
+    }
+
+    console::error_2(&context.into(), &error);
+    notify_error_handlers(&error);
+}
+
+/// Run `func`, sending errors it throws to `handler` — including errors
+/// thrown later by effects, memos and `onMount` callbacks created inside it,
+/// and rejected `createResource` fetches started inside it.
+///
+/// The nearest `catchError` wins; if `handler` throws, the error moves on to
+/// the enclosing one. Returns the result of `func`, or `undefined` if it threw.
+#[wasm_bindgen(js_name = catchError)]
+pub fn catch_error(func: &js_sys::Function, handler: &js_sys::Function) -> JsValue {
+    let scope = Rc::new(ErrorScope {
+        handler: handler.clone(),
+        parent: current_error_scope(),
+    });
+
+    match with_error_scope(Some(scope.clone()), || func.call0(&JsValue::NULL)) {
+        Ok(value) => value,
+        Err(error) => {
+            report_error(Some(scope), "Uncaught error:", &error);
+            JsValue::UNDEFINED
+        }
+    }
+}
+
+/// Create an error boundary to catch component errors
+#[wasm_bindgen(js_name = createErrorBoundary)]
+pub fn create_error_boundary(
+    component: &js_sys::Function,
+    fallback: &js_sys::Function,
+) -> js_sys::Function {
+    let component_clone = component.clone();
+    let fallback_clone = fallback.clone();
+
+    let boundary = Closure::wrap(Box::new(move || -> JsValue {
+        match component_clone.call0(&JsValue::NULL) {
+            Ok(result) => result,
+            Err(error) => {
+                console::error_2(&"Error boundary caught:".into(), &error);
+
+                // Trigger error handlers
+                notify_error_handlers(&error);
+
+                // Return fallback UI
+                fallback_clone.call0(&JsValue::NULL).unwrap_or(JsValue::NULL)
+            }
+        }
+    }) as Box<dyn Fn() -> JsValue>);
+
+    let func = boundary.as_ref().clone();
+    boundary.forget();
+    func.into()
+}
+
+/// Register a global error handler
+#[wasm_bindgen(js_name = onError)]
+pub fn on_error(handler: &js_sys::Function) {
+    ERROR_BOUNDARY_HANDLERS.with(|handlers| {
+        handlers.borrow_mut().push(handler.clone());
+    });
+}
+
+/// Call every registered `onError` handler with the error
+fn notify_error_handlers(error: &JsValue) {
+    ERROR_BOUNDARY_HANDLERS.with(|handlers| {
+        for handler in handlers.borrow().iter() {
+            let _ = handler.call1(&JsValue::NULL, error);
+        }
+    });
 }
 
 /// Enable DevTools integration
@@ -856,6 +2744,38 @@ pub fn enable_dev_tools() {
     js_sys::Reflect::set(&devtools, &JsValue::from_str("getSignals"), signals_fn.as_ref()).ok();
     signals_fn.forget();
 
+    // Expose effect inspection
+    let effects_fn = Closure::wrap(Box::new(|| -> JsValue {
+        RUNTIME.with(|runtime| {
+            let runtime = runtime.borrow();
+            let effects_obj = js_sys::Object::new();
+
+            for (id, effect) in runtime.effects.iter() {
+                let timing = match effect.timing {
+                    EffectTiming::Sync => "sync",
+                    EffectTiming::Microtask => "microtask",
+                    EffectTiming::Frame => "frame",
+                    EffectTiming::Eager => "eager",
+                };
+                let effect_info = js_sys::Object::new();
+                js_sys::Reflect::set(&effect_info, &JsValue::from_str("label"), &JsValue::from_str(&runtime.effect_name(*id))).ok();
+                js_sys::Reflect::set(&effect_info, &JsValue::from_str("timing"), &JsValue::from_str(timing)).ok();
+                js_sys::Reflect::set(&effect_info, &JsValue::from_str("dependencies"), &JsValue::from_f64(effect.dependencies.len() as f64)).ok();
+
+                js_sys::Reflect::set(
+                    &effects_obj,
+                    &JsValue::from_str(&format!("effect_{}", id)),
+                    &effect_info,
+                ).ok();
+            }
+
+            effects_obj.into()
+        })
+    }) as Box<dyn Fn() -> JsValue>);
+
+    js_sys::Reflect::set(&devtools, &JsValue::from_str("getEffects"), effects_fn.as_ref()).ok();
+    effects_fn.forget();
+
     // Expose resource inspection
     let resources_fn = Closure::wrap(Box::new(|| -> JsValue {
         RESOURCE_CACHE.with(|cache| {
@@ -925,8 +2845,11 @@ pub fn measure(name: &str, start_mark: &str, end_mark: &str) -> f64 {
             if performance.measure_with_start_mark_and_end_mark(name, start_mark, end_mark).is_ok() {
                 let entries = performance.get_entries_by_name(name);
                 if entries.length() > 0 {
+                    // The measure's `PerformanceEntry` side has its duration;
+                    // `PerformanceMeasure` itself is behind web-sys's
+                    // unstable APIs
                     let entry = entries.get(entries.length() - 1);
-                    if let Ok(measure) = entry.dyn_into::<web_sys::PerformanceMeasure>() {
+                    if let Ok(measure) = entry.dyn_into::<web_sys::PerformanceEntry>() {
                         return measure.duration();
                     }
                 }
@@ -936,11 +2859,788 @@ pub fn measure(name: &str, start_mark: &str, end_mark: &str) -> f64 {
     0.0
 }
 
+// ============================================================================
+// Profiling
+// ============================================================================
+
+/// Completed profile windows kept by `getProfile`; older ones are dropped
+const MAX_PROFILE_WINDOWS: usize = 1000;
+
+thread_local! {
+    /// Checked before any profiling work, so a disabled profiler costs one
+    /// flag read per effect run and signal write
+    static PROFILING: Cell<bool> = const { Cell::new(false) };
+    static PROFILE: RefCell<Profile> = RefCell::new(Profile::default());
+}
+
+#[derive(Default)]
+struct Profile {
+    /// Window of the macrotask currently running, closed by a timeout
+    current: Option<ProfileWindow>,
+    windows: std::collections::VecDeque<ProfileWindow>,
+}
+
+/// Reactive work done in one macrotask, including the microtasks it queued
+struct ProfileWindow {
+    start: f64,
+    effect_runs: u32,
+    signal_writes: u32,
+    /// Label and duration in milliseconds of the slowest effect run
+    longest_effect: Option<(String, f64)>,
+}
+
+/// High-resolution time in milliseconds (`Date.now()` where
+/// `performance` is missing)
+fn profile_now() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map(|performance| performance.now())
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+/// Run `f` on the current window, opening one if this is the macrotask's
+/// first reactive work
+fn with_profile_window(f: impl FnOnce(&mut ProfileWindow)) {
+    let opened = PROFILE.with(|profile| {
+        let mut profile = profile.borrow_mut();
+        let opened = profile.current.is_none();
+        let window = profile.current.get_or_insert_with(|| ProfileWindow {
+            start: profile_now(),
+            effect_runs: 0,
+            signal_writes: 0,
+            longest_effect: None,
+        });
+        f(window);
+        opened
+    });
+
+    // A zero-delay timeout runs after this task and all of its microtasks
+    if opened {
+        let close = Closure::once_into_js(close_profile_window);
+        let set_timeout = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+        match set_timeout {
+            Some(set_timeout) => {
+                let _ = set_timeout.call2(&JsValue::NULL, &close, &JsValue::from_f64(0.0));
+            }
+            None => close_profile_window(),
+        }
+    }
+}
+
+fn close_profile_window() {
+    PROFILE.with(|profile| {
+        let mut profile = profile.borrow_mut();
+        if let Some(window) = profile.current.take() {
+            if profile.windows.len() == MAX_PROFILE_WINDOWS {
+                profile.windows.pop_front();
+            }
+            profile.windows.push_back(window);
+        }
+    });
+}
+
+/// Count signal (or store property) writes in the current window
+fn profile_signal_writes(count: usize) {
+    if PROFILING.with(Cell::get) {
+        with_profile_window(|window| window.signal_writes += count as u32);
+    }
+}
+
+/// Count an effect run of `duration` milliseconds in the current window
+fn profile_effect_run(id: EffectId, duration: f64) {
+    with_profile_window(|window| {
+        window.effect_runs += 1;
+        if window.longest_effect.as_ref().is_none_or(|(_, longest)| duration > *longest) {
+            let name = RUNTIME.with(|runtime| runtime.borrow().effect_name(id));
+            window.longest_effect = Some((name, duration));
+        }
+    });
+}
+
+/// Start recording, per macrotask, how many effects ran, how many signals
+/// were written and which effect took longest. Clears earlier recordings.
+#[wasm_bindgen(js_name = enableProfiling)]
+pub fn enable_profiling() {
+    PROFILE.with(|profile| *profile.borrow_mut() = Profile::default());
+    PROFILING.with(|profiling| profiling.set(true));
+}
+
+/// Stop recording; what was recorded stays available to `getProfile`
+#[wasm_bindgen(js_name = disableProfiling)]
+pub fn disable_profiling() {
+    PROFILING.with(|profiling| profiling.set(false));
+    close_profile_window();
+}
+
+/// Recorded windows, oldest first:
+/// `[{ start, effectRuns, signalWrites, longestEffect: { label, duration } | null }]`.
+/// `start` and `duration` are in milliseconds from `performance.now()`.
+#[wasm_bindgen(js_name = getProfile)]
+pub fn get_profile() -> js_sys::Array {
+    PROFILE.with(|profile| {
+        let profile = profile.borrow();
+        profile
+            .windows
+            .iter()
+            .chain(profile.current.as_ref())
+            .map(|window| {
+                let entry = js_sys::Object::new();
+                js_sys::Reflect::set(&entry, &JsValue::from_str("start"), &JsValue::from_f64(window.start)).ok();
+                js_sys::Reflect::set(&entry, &JsValue::from_str("effectRuns"), &JsValue::from(window.effect_runs)).ok();
+                js_sys::Reflect::set(&entry, &JsValue::from_str("signalWrites"), &JsValue::from(window.signal_writes)).ok();
+                let longest = match &window.longest_effect {
+                    Some((label, duration)) => {
+                        let longest = js_sys::Object::new();
+                        js_sys::Reflect::set(&longest, &JsValue::from_str("label"), &JsValue::from_str(label)).ok();
+                        js_sys::Reflect::set(&longest, &JsValue::from_str("duration"), &JsValue::from_f64(*duration)).ok();
+                        longest.into()
+                    }
+                    None => JsValue::NULL,
+                };
+                js_sys::Reflect::set(&entry, &JsValue::from_str("longestEffect"), &longest).ok();
+                JsValue::from(entry)
+            })
+            .collect()
+    })
+}
+
+// ============================================================================
+// Code Splitting
+// ============================================================================
+
+enum LazyState {
+    Idle,
+    Loading,
+    Loaded(js_sys::Function),
+    Failed(JsValue),
+}
+
+/// Create a component that loads its implementation on first render.
+///
+/// `loader` returns a Promise of either a component function or a module
+/// whose `default` export is one. Until it resolves the optional `fallback`
+/// component is shown; the swap happens inside an effect so it is reactive.
+///
+/// Load errors are reported to the `onError` handlers. Rendering the lazy
+/// component again after a failed load throws the error, so the nearest
+/// `createErrorBoundary` shows its fallback.
+#[wasm_bindgen(js_name = lazy)]
+pub fn lazy(loader: &js_sys::Function, fallback: Option<js_sys::Function>) -> js_sys::Function {
+    let state = Rc::new(RefCell::new(LazyState::Idle));
+    // Bumped when the load settles so mounted instances re-render
+    let settled = Rc::new(Signal::new(JsValue::FALSE));
+    let loader = loader.clone();
+
+    let component = Closure::wrap(Box::new(move |props: JsValue| -> Result<JsValue, JsValue> {
+        match &*state.borrow() {
+            LazyState::Loaded(component) => return component.call1(&JsValue::NULL, &props),
+            LazyState::Failed(error) => return Err(error.clone()),
+            LazyState::Idle | LazyState::Loading => {}
+        }
+
+        if matches!(*state.borrow(), LazyState::Idle) {
+            *state.borrow_mut() = LazyState::Loading;
+            start_lazy_load(&loader, state.clone(), settled.clone());
+        }
+
+        // On the server there's nothing to swap later: render the fallback
+        if is_ssr() {
+            return match &fallback {
+                Some(fallback) => fallback.call0(&JsValue::NULL),
+                None => Ok(JsValue::NULL),
+            };
+        }
+
+        // Mount into a container that the effect below fills in
+        let document = document("lazy")?;
+        let container = document.create_element("div")?;
+        container.set_attribute("data-lazy", "")?;
+
+        let effect_container = container.clone();
+        let effect_state = state.clone();
+        let effect_settled = settled.clone();
+        let fallback = fallback.clone();
+        let effect_fn = Rc::new(move || {
+            // Read only to subscribe; a failed read has already been logged
+            let _ = effect_settled.get();
+
+            let content = match &*effect_state.borrow() {
+                LazyState::Loaded(component) => component.call1(&JsValue::NULL, &props),
+                LazyState::Failed(_) => Ok(JsValue::NULL),
+                LazyState::Idle | LazyState::Loading => match &fallback {
+                    Some(fallback) => fallback.call0(&JsValue::NULL),
+                    None => Ok(JsValue::NULL),
+                },
+            };
+
+            let result = content.and_then(|value| mount_value(&effect_container, &value));
+            if let Err(e) = result {
+                report_error(current_error_scope(), "Lazy render error:", &e);
+            }
+        });
+
+        let effect_id = RUNTIME.with(|runtime| runtime.borrow_mut().create_effect(effect_fn, EffectTiming::Sync, Some("lazy".to_string())));
+        Runtime::run_effect(effect_id);
+
+        Ok(container.into())
+    }) as Box<dyn Fn(JsValue) -> Result<JsValue, JsValue>>);
+
+    let func = component.as_ref().clone();
+    component.forget();
+    func.into()
+}
+
+/// Call the loader and record the resolved component (or error) in `state`
+fn start_lazy_load(loader: &js_sys::Function, state: Rc<RefCell<LazyState>>, settled: Rc<Signal>) {
+    let loader = loader.clone();
+    let error_scope = current_error_scope();
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let loaded = call_async_fetcher(&loader, &JsValue::UNDEFINED).await.and_then(|module| {
+            if module.is_function() {
+                return Ok(module.unchecked_into::<js_sys::Function>());
+            }
+            js_sys::Reflect::get(&module, &JsValue::from_str("default"))?
+                .dyn_into::<js_sys::Function>()
+                .map_err(|_| JsValue::from_str("Lazy module has no default component export"))
+        });
+
+        match loaded {
+            Ok(component) => *state.borrow_mut() = LazyState::Loaded(component),
+            Err(error) => {
+                report_error(error_scope, "Lazy load failed:", &error);
+                *state.borrow_mut() = LazyState::Failed(error);
+            }
+        }
+
+        if let Err(error) = settled.set(JsValue::TRUE) {
+            report_error(None, "Lazy load failed:", &error);
+        }
+    });
+}
+
+/// Replace the contents of `parent` with a rendered value (node, text or an
+/// array of them). The new content is built offscreen and inserted at once.
+fn mount_value(parent: &Element, value: &JsValue) -> Result<(), JsValue> {
+    let document = document("render")?;
+    let fragment = build_fragment(&document, value)?;
+
+    release_portals(parent);
+    parent.set_text_content(None);
+    parent.append_child(&fragment)?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Props Utilities
+// ============================================================================
+
+/// Merge props objects into one; later objects override earlier ones.
+///
+/// Every key becomes a getter that looks the value up on access, so
+/// getter-backed reactive props are not read eagerly and stay reactive.
+/// `undefined` values fall through to earlier objects, which makes
+/// `mergeProps({ size: "md" }, props)` work for defaults. Keys added to a
+/// source after merging are not picked up.
+///
+/// The compiler builds the props of components with spread attributes
+/// (`<Button {...base} variant="primary" />`) with this, in source order.
+///
+/// Exposed as `mergeProps(...sources)` by the JS wrapper.
+#[wasm_bindgen(js_name = mergeProps)]
+pub fn merge_props(sources: js_sys::Array) -> Result<js_sys::Object, JsValue> {
+    let sources: Rc<Vec<JsValue>> = Rc::new(sources.iter().filter(|s| s.is_object()).collect());
+    let merged = js_sys::Object::new();
+    let mut seen = std::collections::HashSet::new();
+
+    for source in sources.iter() {
+        for key in js_sys::Object::keys(source.unchecked_ref::<js_sys::Object>()).iter() {
+            let Some(name) = key.as_string() else { continue };
+            if !seen.insert(name) {
+                continue;
+            }
+
+            let sources = sources.clone();
+            let lookup_key = key.clone();
+            define_prop_getter(&merged, &key, move || {
+                sources
+                    .iter()
+                    .rev()
+                    .map(|source| js_sys::Reflect::get(source, &lookup_key).unwrap_or(JsValue::UNDEFINED))
+                    .find(|value| !value.is_undefined())
+                    .unwrap_or(JsValue::UNDEFINED)
+            })?;
+        }
+    }
+
+    Ok(merged)
+}
+
+/// Split `props` into `[picked, rest]`, where `picked` holds `keys`.
+///
+/// Both objects forward to `props` through getters, so reactive props are
+/// only read when accessed.
+#[wasm_bindgen(js_name = splitProps)]
+pub fn split_props(props: &JsValue, keys: js_sys::Array) -> Result<js_sys::Array, JsValue> {
+    let picked = js_sys::Object::new();
+    let rest = js_sys::Object::new();
+
+    if props.is_object() {
+        let wanted: std::collections::HashSet<String> = keys.iter().filter_map(|k| k.as_string()).collect();
+
+        for key in js_sys::Object::keys(props.unchecked_ref::<js_sys::Object>()).iter() {
+            let Some(name) = key.as_string() else { continue };
+            let target = if wanted.contains(&name) { &picked } else { &rest };
+
+            let props = props.clone();
+            let lookup_key = key.clone();
+            define_prop_getter(target, &key, move || {
+                js_sys::Reflect::get(&props, &lookup_key).unwrap_or(JsValue::UNDEFINED)
+            })?;
+        }
+    }
+
+    Ok(js_sys::Array::of2(&picked, &rest))
+}
+
+/// Define an enumerable getter property on `target`
+fn define_prop_getter(
+    target: &js_sys::Object,
+    key: &JsValue,
+    get: impl Fn() -> JsValue + 'static,
+) -> Result<(), JsValue> {
+    let getter = Closure::wrap(Box::new(get) as Box<dyn Fn() -> JsValue>).into_js_value();
+
+    let descriptor = js_sys::Object::new();
+    js_sys::Reflect::set(&descriptor, &"get".into(), &getter)?;
+    js_sys::Reflect::set(&descriptor, &"enumerable".into(), &JsValue::TRUE)?;
+    js_sys::Reflect::set(&descriptor, &"configurable".into(), &JsValue::TRUE)?;
+    js_sys::Object::define_property(target, key, &descriptor);
+
+    Ok(())
+}
+
+// ============================================================================
+// Stores
+// ============================================================================
+
+type StoreNodeId = usize;
+
+/// Signal key tracking an object's set of keys (`Object.keys`, `in`)
+const STORE_KEYS: &str = "\u{0}keys";
+
+thread_local! {
+    /// Raw store object → node id its property signals are keyed by
+    static STORE_NODES: js_sys::WeakMap = js_sys::WeakMap::new();
+    static NEXT_STORE_NODE: Cell<StoreNodeId> = const { Cell::new(0) };
+    /// Property signals, created the first time an effect reads the property
+    static STORE_SIGNALS: RefCell<HashMap<(StoreNodeId, String), SignalId>> = RefCell::new(HashMap::new());
+    /// Raw store object → its read-only proxy, so `store.a === store.a`
+    static STORE_PROXIES: js_sys::WeakMap = js_sys::WeakMap::new();
+    /// Traps shared by every read-only store proxy
+    static STORE_HANDLER: js_sys::Object = store_handler();
+    /// Store and draft proxies answer this key with their raw object
+    static STORE_RAW: js_sys::Symbol = js_sys::Symbol::for_("velocity.store.raw");
+}
+
+/// A `(raw object, property)` whose readers must be notified
+type StoreWrite = (js_sys::Object, String);
+
+/// Create a store: a deeply reactive object. Effects reading
+/// `store.todos[0].done` re-run only when that property changes, not on
+/// every update to the store.
+///
+/// Returns `[store, setStore]`. The store is read-only; update it with
+/// `setStore(...path, value)` (`value` may be a function of the current
+/// value, and a bare object is merged into the root) or with [`produce`].
+/// Plain objects and arrays are tracked; class instances such as `Date` are
+/// stored as values.
+///
+/// Exposed with a variadic `setStore` by the JS wrapper.
+#[wasm_bindgen(js_name = createStore)]
+pub fn create_store(initial: JsValue) -> Result<js_sys::Array, JsValue> {
+    if !is_store_object(&initial) {
+        return Err(js_sys::Error::new("createStore expects a plain object or an array").into());
+    }
+    let root: js_sys::Object = initial.unchecked_into();
+
+    let setter_root = root.clone();
+    let set_store = Closure::wrap(Box::new(move |args: js_sys::Array| set_store(&setter_root, &args))
+        as Box<dyn Fn(js_sys::Array) -> Result<(), JsValue>>)
+    .into_js_value();
+
+    Ok(js_sys::Array::of2(&store_proxy(&root), &set_store))
+}
+
+/// Update a store by mutating a draft of it, Immer-style:
+///
+/// ```js
+/// produce(store, draft => {
+///   draft.todos.push({ title: 'Write docs', done: false });
+///   draft.todos[0].done = true;
+/// });
+/// ```
+///
+/// `store` may be the store or any object inside it. Only the properties the
+/// recipe changed notify their readers, and effects re-run once after the
+/// recipe returns. Array methods work on drafts: `push`, `splice`, `sort`
+/// and `length` writes notify the indices they touch and `length`, which is
+/// what list rendering tracks. The draft stops working when the recipe
+/// returns; its return value is ignored.
+#[wasm_bindgen]
+pub fn produce(store: &JsValue, recipe: &js_sys::Function) -> Result<(), JsValue> {
+    let target = store_raw(store)
+        .ok_or_else(|| js_sys::Error::new("produce expects a store created by createStore"))?;
+
+    let draft = Draft::new();
+    let result = recipe.call1(&JsValue::NULL, &draft.proxy(&target));
+    // Writes made before a throw have happened; their readers still update
+    let writes = draft.finish();
+    notify_store_writes(&writes);
+
+    result.map(|_| ())
+}
+
+/// `setStore(...path, value)`, with `args` as an array
+fn set_store(root: &js_sys::Object, args: &js_sys::Array) -> Result<(), JsValue> {
+    let args: Vec<JsValue> = args.iter().collect();
+    let Some((value, path)) = args.split_last() else {
+        return Err(js_sys::Error::new("setStore expects a value").into());
+    };
+
+    let mut writes = Vec::new();
+    let Some((key, parents)) = path.split_last() else {
+        // setStore({ ... }) merges into the root
+        let value = store_raw(value).map(JsValue::from).unwrap_or_else(|| value.clone());
+        if !is_store_object(&value) {
+            return Err(js_sys::Error::new("setStore without a path expects an object to merge").into());
+        }
+        for key in js_sys::Object::keys(value.unchecked_ref::<js_sys::Object>()).iter() {
+            let name = property_name(&key).unwrap_or_default();
+            write_store(root, &name, &js_sys::Reflect::get(&value, &key)?, &mut writes)?;
+        }
+        notify_store_writes(&writes);
+        return Ok(());
+    };
+
+    let mut target: JsValue = root.clone().into();
+    for parent in parents {
+        target = js_sys::Reflect::get(&target, parent)?;
+        if !target.is_object() {
+            return Err(js_sys::Error::new(&format!(
+                "setStore path doesn't exist: no object at `{}`",
+                parent.as_string().or_else(|| parent.as_f64().map(|n| n.to_string())).unwrap_or_default()
+            ))
+            .into());
+        }
+    }
+    let target: js_sys::Object = target.unchecked_into();
+    let name = property_name(key).ok_or_else(|| js_sys::Error::new("setStore path keys must be strings or numbers"))?;
+
+    // setStore("count", c => c + 1)
+    let value = match value.dyn_ref::<js_sys::Function>() {
+        Some(update) => {
+            let current = js_sys::Reflect::get(&target, key)?;
+            let current = if is_store_object(&current) { store_proxy(current.unchecked_ref()) } else { current };
+            update.call1(&JsValue::NULL, &current)?
+        }
+        None => value.clone(),
+    };
+
+    write_store(&target, &name, &value, &mut writes)?;
+    notify_store_writes(&writes);
+    Ok(())
+}
+
+/// Set `target[key]`, recording what changed: the property, the key set if
+/// the property is new, and the indices dropped by shrinking an array
+fn write_store(target: &js_sys::Object, key: &str, value: &JsValue, writes: &mut Vec<StoreWrite>) -> Result<(), JsValue> {
+    // Assigning a store or draft object stores the object itself
+    let value = store_raw(value).map(JsValue::from).unwrap_or_else(|| value.clone());
+    let js_key = JsValue::from_str(key);
+    let existed = js_sys::Object::has_own(target, &js_key);
+    let old = js_sys::Reflect::get(target, &js_key)?;
+
+    if key == "length" && js_sys::Array::is_array(target) {
+        if let (Some(old_length), Some(new_length)) = (old.as_f64(), value.as_f64()) {
+            for index in (new_length as usize)..(old_length as usize) {
+                writes.push((target.clone(), index.to_string()));
+            }
+        }
+    }
+
+    js_sys::Reflect::set(target, &js_key, &value)?;
+    if !js_sys::Object::is(&old, &value) {
+        writes.push((target.clone(), key.to_string()));
+    }
+    if !existed {
+        writes.push((target.clone(), STORE_KEYS.to_string()));
+    }
+    Ok(())
+}
+
+/// Delete `target[key]`, recording the property and the key set
+fn delete_store(target: &js_sys::Object, key: &str, writes: &mut Vec<StoreWrite>) -> Result<bool, JsValue> {
+    let js_key = JsValue::from_str(key);
+    if !js_sys::Object::has_own(target, &js_key) {
+        return Ok(true);
+    }
+    let deleted = js_sys::Reflect::delete_property(target, &js_key)?;
+    if deleted {
+        writes.push((target.clone(), key.to_string()));
+        writes.push((target.clone(), STORE_KEYS.to_string()));
+    }
+    Ok(deleted)
+}
+
+/// Notify the readers of each written property, running every affected
+/// effect once after all of them
+fn notify_store_writes(writes: &[StoreWrite]) {
+    profile_signal_writes(writes.len());
+    let mut effects = Vec::new();
+    for (target, key) in writes {
+        // Objects nothing has read yet have no signals to notify
+        let Some(node) = STORE_NODES.with(|nodes| nodes.get(target).as_f64()) else {
+            continue;
+        };
+        let signal = STORE_SIGNALS.with(|signals| signals.borrow().get(&(node as StoreNodeId, key.clone())).copied());
+        let Some(signal) = signal else {
+            continue;
+        };
+        if let Ok(Some(subscribers)) = with_runtime(|runtime| runtime.write_signal(signal, JsValue::UNDEFINED)) {
+            for id in subscribers {
+                if !effects.contains(&id) {
+                    effects.push(id);
+                }
+            }
+        }
+    }
+
+    for id in effects {
+        Scheduler::schedule(id);
+    }
+}
+
+/// Track a read of `target[key]` as a dependency of the running effect
+fn track_store_read(target: &js_sys::Object, key: &str) {
+    if CURRENT_EFFECT.with(|e| e.borrow().is_none()) {
+        return;
+    }
+
+    let node = STORE_NODES.with(|nodes| match nodes.get(target).as_f64() {
+        Some(node) => node as StoreNodeId,
+        None => {
+            let node = NEXT_STORE_NODE.with(|next| next.replace(next.get() + 1));
+            nodes.set(target, &JsValue::from(node as f64));
+            node
+        }
+    });
+
+    let signal = STORE_SIGNALS.with(|signals| {
+        let mut signals = signals.borrow_mut();
+        if let Some(&signal) = signals.get(&(node, key.to_string())) {
+            return Some(signal);
+        }
+        let signal = with_runtime(|runtime| runtime.create_signal(JsValue::UNDEFINED)).ok()?;
+        signals.insert((node, key.to_string()), signal);
+        Some(signal)
+    });
+
+    if let Some(signal) = signal {
+        let _ = with_runtime(|runtime| runtime.read_signal(signal));
+    }
+}
+
+/// The read-only proxy for a raw store object
+fn store_proxy(target: &js_sys::Object) -> JsValue {
+    let cached = STORE_PROXIES.with(|proxies| proxies.get(target));
+    if !cached.is_undefined() {
+        return cached;
+    }
+
+    let proxy: JsValue = STORE_HANDLER.with(|handler| js_sys::Proxy::new(target, handler)).into();
+    STORE_PROXIES.with(|proxies| proxies.set(target, &proxy));
+    proxy
+}
+
+/// Traps of read-only store proxies: reads are tracked, writes are refused
+fn store_handler() -> js_sys::Object {
+    let handler = js_sys::Object::new();
+
+    let get = Closure::wrap(Box::new(|target: js_sys::Object, key: JsValue| {
+        if is_store_raw_key(&key) {
+            return Ok(target.into());
+        }
+        let value = js_sys::Reflect::get(&target, &key)?;
+        let Some(name) = property_name(&key) else {
+            return Ok(value);
+        };
+        // Prototype methods (`items.map`) aren't state
+        if value.is_function() && !js_sys::Object::has_own(&target, &key) {
+            return Ok(value);
+        }
+
+        track_store_read(&target, &name);
+        Ok(if is_store_object(&value) { store_proxy(value.unchecked_ref()) } else { value })
+    }) as Box<dyn Fn(js_sys::Object, JsValue) -> Result<JsValue, JsValue>>);
+
+    let has = Closure::wrap(Box::new(|target: js_sys::Object, key: JsValue| {
+        if property_name(&key).is_some() {
+            track_store_read(&target, STORE_KEYS);
+        }
+        js_sys::Reflect::has(&target, &key)
+    }) as Box<dyn Fn(js_sys::Object, JsValue) -> Result<bool, JsValue>>);
+
+    let own_keys = Closure::wrap(Box::new(|target: js_sys::Object| {
+        track_store_read(&target, STORE_KEYS);
+        js_sys::Reflect::own_keys(&target)
+    }) as Box<dyn Fn(js_sys::Object) -> Result<js_sys::Array, JsValue>>);
+
+    // Returning true keeps strict-mode code from throwing; the write is dropped
+    let refuse = Closure::wrap(Box::new(|| {
+        console::warn_1(&"store is read-only: update it with setStore or produce".into());
+        true
+    }) as Box<dyn Fn() -> bool>)
+    .into_js_value();
+
+    for (name, trap) in [
+        ("get", get.into_js_value()),
+        ("has", has.into_js_value()),
+        ("ownKeys", own_keys.into_js_value()),
+        ("set", refuse.clone()),
+        ("deleteProperty", refuse),
+    ] {
+        js_sys::Reflect::set(&handler, &name.into(), &trap).ok();
+    }
+    handler
+}
+
+/// Proxies over a store's raw objects that `produce` hands to its recipe.
+/// Writes go straight to the raw objects and are recorded for notification.
+struct Draft {
+    handler: js_sys::Object,
+    /// Raw object → its draft proxy, so `draft.a === draft.a`
+    proxies: js_sys::WeakMap,
+    writes: Rc<RefCell<Vec<StoreWrite>>>,
+    #[allow(clippy::type_complexity)]
+    traps: (
+        Closure<dyn Fn(js_sys::Object, JsValue) -> Result<JsValue, JsValue>>,
+        Closure<dyn Fn(js_sys::Object, JsValue, JsValue) -> Result<bool, JsValue>>,
+        Closure<dyn Fn(js_sys::Object, JsValue) -> Result<bool, JsValue>>,
+    ),
+}
+
+impl Draft {
+    fn new() -> Self {
+        let handler = js_sys::Object::new();
+        let proxies = js_sys::WeakMap::new();
+        let writes: Rc<RefCell<Vec<StoreWrite>>> = Rc::new(RefCell::new(Vec::new()));
+
+        let (get_handler, get_proxies) = (handler.clone(), proxies.clone());
+        let get = Closure::wrap(Box::new(move |target: js_sys::Object, key: JsValue| {
+            if is_store_raw_key(&key) {
+                return Ok(target.into());
+            }
+            let value = js_sys::Reflect::get(&target, &key)?;
+            if !is_store_object(&value) {
+                return Ok(value);
+            }
+            Ok(draft_proxy(&get_handler, &get_proxies, value.unchecked_ref()))
+        }) as Box<dyn Fn(js_sys::Object, JsValue) -> Result<JsValue, JsValue>>);
+
+        let set_writes = writes.clone();
+        let set = Closure::wrap(Box::new(move |target: js_sys::Object, key: JsValue, value: JsValue| {
+            match property_name(&key) {
+                Some(name) => write_store(&target, &name, &value, &mut set_writes.borrow_mut())?,
+                None => {
+                    js_sys::Reflect::set(&target, &key, &value)?;
+                }
+            }
+            Ok(true)
+        }) as Box<dyn Fn(js_sys::Object, JsValue, JsValue) -> Result<bool, JsValue>>);
+
+        let delete_writes = writes.clone();
+        let delete = Closure::wrap(Box::new(move |target: js_sys::Object, key: JsValue| {
+            match property_name(&key) {
+                Some(name) => delete_store(&target, &name, &mut delete_writes.borrow_mut()),
+                None => js_sys::Reflect::delete_property(&target, &key),
+            }
+        }) as Box<dyn Fn(js_sys::Object, JsValue) -> Result<bool, JsValue>>);
+
+        js_sys::Reflect::set(&handler, &"get".into(), get.as_ref()).ok();
+        js_sys::Reflect::set(&handler, &"set".into(), set.as_ref()).ok();
+        js_sys::Reflect::set(&handler, &"deleteProperty".into(), delete.as_ref()).ok();
+
+        Self {
+            handler,
+            proxies,
+            writes,
+            traps: (get, set, delete),
+        }
+    }
+
+    fn proxy(&self, target: &js_sys::Object) -> JsValue {
+        draft_proxy(&self.handler, &self.proxies, target)
+    }
+
+    /// Drop the traps, disabling the draft, and return the recorded writes
+    fn finish(self) -> Vec<StoreWrite> {
+        drop(self.traps);
+        self.writes.take()
+    }
+}
+
+fn draft_proxy(handler: &js_sys::Object, proxies: &js_sys::WeakMap, target: &js_sys::Object) -> JsValue {
+    let cached = proxies.get(target);
+    if !cached.is_undefined() {
+        return cached;
+    }
+    let proxy: JsValue = js_sys::Proxy::new(target, handler).into();
+    proxies.set(target, &proxy);
+    proxy
+}
+
+/// The raw object behind a store or draft proxy
+fn store_raw(value: &JsValue) -> Option<js_sys::Object> {
+    if !value.is_object() {
+        return None;
+    }
+    let raw = STORE_RAW.with(|symbol| js_sys::Reflect::get(value, symbol)).ok()?;
+    raw.is_object().then(|| raw.unchecked_into())
+}
+
+fn is_store_raw_key(key: &JsValue) -> bool {
+    STORE_RAW.with(|symbol| js_sys::Object::is(key, symbol))
+}
+
+/// Plain objects and arrays are tracked through proxies. Class instances
+/// (`Date`, `Map`, DOM nodes) are values: their methods need the real object
+/// as `this`.
+fn is_store_object(value: &JsValue) -> bool {
+    if js_sys::Array::is_array(value) {
+        return true;
+    }
+    if !value.is_object() || value.is_function() {
+        return false;
+    }
+    let prototype = js_sys::Object::get_prototype_of(value);
+    prototype.is_null() || js_sys::Object::is(&prototype, &js_sys::Object::get_prototype_of(&js_sys::Object::new()))
+}
+
+/// A string or numeric property key as a string; `None` for symbols
+fn property_name(key: &JsValue) -> Option<String> {
+    key.as_string().or_else(|| key.as_f64().map(|n| n.to_string()))
+}
+
 // ============================================================================
 // Initialization
 // ============================================================================
 
-#[wasm_bindgen(start)]
+// The test harness exports its own `main`
+#[cfg_attr(not(test), wasm_bindgen(start))]
 pub fn main() {
     console::log_1(&"Velocity WASM Runtime initialized".into());
 }
@@ -949,3 +3649,289 @@ pub fn main() {
 pub fn greet(name: &str) {
     console::log_1(&format!("Hello from Velocity WASM, {}!", name).into());
 }
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn read(signal: &[JsValue]) -> JsValue {
+        signal[0].unchecked_ref::<js_sys::Function>().call0(&JsValue::NULL).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_create_signal_calls_initializer_once() {
+        let calls = Rc::new(Cell::new(0));
+        let counted = calls.clone();
+        let initializer = Closure::wrap(Box::new(move || {
+            counted.set(counted.get() + 1);
+            JsValue::from(42)
+        }) as Box<dyn Fn() -> JsValue>);
+
+        let signal = create_signal(initializer.as_ref().clone(), None).unwrap();
+        assert_eq!(read(&signal), 42);
+        assert_eq!(read(&signal), 42);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_create_signal_stores_function_returned_by_initializer() {
+        let initializer = js_sys::Function::new_no_args("return function onSave() { return 'saved'; };");
+        let signal = create_signal(initializer.into(), None).unwrap();
+
+        let stored = read(&signal);
+        let stored = stored.dyn_ref::<js_sys::Function>().expect("the signal holds the returned function");
+        assert_eq!(stored.name(), "onSave");
+        assert_eq!(stored.call0(&JsValue::NULL).unwrap(), "saved");
+    }
+
+    /// An effect running `on(deps, ...)` whose body reads `untracked_read`,
+    /// and the number of times the body ran
+    fn counted_on_effect(deps: JsValue, untracked_read: JsValue, options: JsValue) -> Rc<Cell<u32>> {
+        let runs = Rc::new(Cell::new(0));
+        let counted = runs.clone();
+        let body = Closure::wrap(Box::new(move |_input: JsValue| {
+            untracked_read.unchecked_ref::<js_sys::Function>().call0(&JsValue::NULL).unwrap();
+            counted.set(counted.get() + 1);
+        }) as Box<dyn Fn(JsValue)>);
+        let effect = on(deps, body.as_ref().unchecked_ref(), options).unwrap();
+        body.forget();
+        create_render_effect(&effect, None);
+        runs
+    }
+
+    fn write(signal: &[JsValue], value: i32) {
+        signal[1].unchecked_ref::<js_sys::Function>().call1(&JsValue::NULL, &value.into()).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_on_tracks_only_listed_deps() {
+        let a = create_signal(0.into(), None).unwrap();
+        let b = create_signal(0.into(), None).unwrap();
+        let deps: js_sys::Array = [a[0].clone()].into_iter().collect();
+        let runs = counted_on_effect(deps.into(), b[0].clone(), JsValue::UNDEFINED);
+        assert_eq!(runs.get(), 1);
+
+        write(&b, 1);
+        assert_eq!(runs.get(), 1, "a signal read in the body isn't a dependency");
+        write(&a, 1);
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_on_defer_skips_first_run() {
+        let a = create_signal(0.into(), None).unwrap();
+        let b = create_signal(0.into(), None).unwrap();
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(&options, &"defer".into(), &true.into()).unwrap();
+        let runs = counted_on_effect(a[0].clone(), b[0].clone(), options.into());
+        assert_eq!(runs.get(), 0);
+
+        write(&a, 1);
+        assert_eq!(runs.get(), 1);
+        write(&a, 2);
+        assert_eq!(runs.get(), 2);
+    }
+
+    /// A function reading `signal` that counts its calls, and the count
+    fn counted_read(signal: &[JsValue]) -> (js_sys::Function, Rc<Cell<u32>>) {
+        let runs = Rc::new(Cell::new(0));
+        let counted = runs.clone();
+        let getter = signal[0].clone();
+        let body = Closure::wrap(Box::new(move || {
+            getter.unchecked_ref::<js_sys::Function>().call0(&JsValue::NULL).unwrap();
+            counted.set(counted.get() + 1);
+        }) as Box<dyn Fn()>);
+        (body.into_js_value().unchecked_into(), runs)
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_create_effect_defers_and_render_effect_does_not() {
+        let signal = create_signal(0.into(), None).unwrap();
+        let (deferred, deferred_runs) = counted_read(&signal);
+        let (render, render_runs) = counted_read(&signal);
+        create_effect(&deferred, None, None).unwrap();
+        create_render_effect(&render, None);
+        assert_eq!((deferred_runs.get(), render_runs.get()), (1, 1), "both run once when created");
+
+        write(&signal, 1);
+        write(&signal, 2);
+        assert_eq!(render_runs.get(), 3);
+        assert_eq!(deferred_runs.get(), 1);
+
+        next_tick().await;
+        assert_eq!(deferred_runs.get(), 2, "both writes are handled in one run");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_create_root_disposes_effects_signals_and_cleanups() {
+        let signal = create_signal(0.into(), None).unwrap();
+        let (effect, runs) = counted_read(&signal);
+        let cleanups = Rc::new(Cell::new(0));
+        let (counted, owned) = (cleanups.clone(), Rc::new(RefCell::new(Vec::new())));
+        let inner = owned.clone();
+        let root = Closure::wrap(Box::new(move |dispose: JsValue| {
+            create_render_effect(&effect, None);
+            *inner.borrow_mut() = create_signal(1.into(), None).unwrap();
+            let counted = counted.clone();
+            let cleanup = Closure::wrap(Box::new(move || counted.set(counted.get() + 1)) as Box<dyn Fn()>);
+            on_cleanup(cleanup.as_ref().unchecked_ref());
+            cleanup.forget();
+            dispose
+        }) as Box<dyn Fn(JsValue) -> JsValue>);
+        let dispose: js_sys::Function = create_root(root.as_ref().unchecked_ref()).unwrap().unchecked_into();
+
+        write(&signal, 1);
+        assert_eq!(runs.get(), 2);
+        dispose.call0(&JsValue::NULL).unwrap();
+        assert_eq!(cleanups.get(), 1);
+        write(&signal, 2);
+        assert_eq!(runs.get(), 2, "a disposed effect doesn't re-run");
+        assert_eq!(read(&owned.borrow()), JsValue::UNDEFINED, "a disposed signal is released");
+
+        dispose.call0(&JsValue::NULL).unwrap();
+        assert_eq!(cleanups.get(), 1, "disposing again does nothing");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_keyed_signals_restore_saved_values_and_are_tracked() {
+        let saved = js_sys::JSON::parse(r#"{"src/App.tsx#App.count:0": [{"value": 5}, null, {"value": "five"}]}"#);
+        HMR_SIGNALS.with(|state| *state.borrow_mut() = Some(HmrSignals::new(saved.unwrap())));
+        let key = || Some("src/App.tsx#App.count:0".to_string());
+
+        let dispose = Rc::new(RefCell::new(JsValue::UNDEFINED));
+        let inner = dispose.clone();
+        let root = Closure::wrap(Box::new(move |dispose: JsValue| {
+            *inner.borrow_mut() = dispose;
+            let restored = create_signal(0.into(), key()).unwrap();
+            assert_eq!(read(&restored), 5);
+            let unsaved = create_signal(0.into(), key()).unwrap();
+            assert_eq!(read(&unsaved), 0, "an instance saved as null starts over");
+            let other_kind = create_signal(0.into(), key()).unwrap();
+            assert_eq!(read(&other_kind), 0, "a value of another kind isn't restored");
+        }) as Box<dyn Fn(JsValue)>);
+        create_root(root.as_ref().unchecked_ref()).unwrap();
+
+        let live: js_sys::Map = js_sys::Reflect::get(&js_sys::global(), &"__velocity_hmr_signals__".into())
+            .unwrap()
+            .unchecked_into();
+        let reads = || live.get(&key().unwrap().into()).unchecked_into::<js_sys::Set>();
+        assert_eq!(reads().size(), 3);
+        dispose.borrow().unchecked_ref::<js_sys::Function>().call0(&JsValue::NULL).unwrap();
+        assert_eq!(reads().size(), 0, "disposed signals aren't saved");
+    }
+
+    /// A fetcher whose Promise resolves when the returned function is called
+    fn deferred_fetcher() -> (js_sys::Function, js_sys::Function) {
+        let pair = js_sys::Function::new_no_args(
+            "let resolve; const data = new Promise(r => resolve = r); return [() => data, resolve];",
+        );
+        let pair: js_sys::Array = pair.call0(&JsValue::NULL).unwrap().unchecked_into();
+        (pair.get(0).unchecked_into(), pair.get(1).unchecked_into())
+    }
+
+    /// A page rendering the resource `key` as `<p>data</p>` inside `suspense`
+    fn suspense_page(key: &'static str, fetcher: js_sys::Function) -> js_sys::Function {
+        let children = Closure::wrap(Box::new(move || {
+            let data = create_resource(key, &fetcher).get(0);
+            JsValue::from_str(&format!("<p>{}</p>", data.as_string().unwrap_or_default()))
+        }) as Box<dyn Fn() -> JsValue>);
+        let children: js_sys::Function = children.into_js_value().unchecked_into();
+        let page = Closure::wrap(Box::new(move || suspense(JsValue::from_str("<p>Loading</p>"), &children))
+            as Box<dyn Fn() -> Result<JsValue, JsValue>>);
+        page.into_js_value().unchecked_into()
+    }
+
+    /// Wait until pending Promise callbacks have run
+    async fn next_tick() {
+        let tick = js_sys::Function::new_no_args("return new Promise(resolve => setTimeout(resolve, 0));");
+        let tick: js_sys::Promise = tick.call0(&JsValue::NULL).unwrap().unchecked_into();
+        wasm_bindgen_futures::JsFuture::from(tick).await.unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_suspense_shows_fallback_until_resources_settle() {
+        let (fetcher, resolve) = deferred_fetcher();
+        let page = suspense_page("suspense-string", fetcher);
+
+        let html = render_to_string(&page, JsValue::UNDEFINED).unwrap();
+        assert!(html.contains("<p>Loading</p>"), "{}", html);
+
+        resolve.call1(&JsValue::NULL, &"Ada".into()).unwrap();
+        next_tick().await;
+        let html = render_to_string(&page, JsValue::UNDEFINED).unwrap();
+        assert!(html.contains("<p>Ada</p>") && !html.contains("Loading"), "{}", html);
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_render_to_stream_flushes_fallback_then_content() {
+        let (fetcher, resolve) = deferred_fetcher();
+        let page = suspense_page("suspense-stream", fetcher);
+        let stream = render_to_stream(&page, JsValue::UNDEFINED).unwrap();
+
+        let read_all = js_sys::Function::new_with_args(
+            "stream",
+            "return (async () => { const reader = stream.getReader(); const chunks = []; \
+             for (;;) { const { done, value } = await reader.read(); if (done) return chunks; chunks.push(value); } })();",
+        );
+        let chunks: js_sys::Promise = read_all.call1(&JsValue::NULL, &stream).unwrap().unchecked_into();
+        resolve.call1(&JsValue::NULL, &"Ada".into()).unwrap();
+        let chunks: js_sys::Array = wasm_bindgen_futures::JsFuture::from(chunks).await.unwrap().unchecked_into();
+        let chunks: Vec<String> = chunks.iter().filter_map(|chunk| chunk.as_string()).collect();
+
+        assert_eq!(chunks.len(), 3, "{:?}", chunks);
+        assert!(chunks[0].contains("<div data-velocity-suspense=\"0\"><p>Loading</p></div></div>"), "{}", chunks[0]);
+        assert!(chunks[1].starts_with("<template data-velocity-resolved=\"0\"><p>Ada</p></template><script>"), "{}", chunks[1]);
+        assert!(chunks[2].ends_with("</html>"), "{}", chunks[2]);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_dom_helpers_degrade_during_ssr() {
+        let page = Closure::wrap(Box::new(|| {
+            let props = js_sys::Object::new();
+            js_sys::Reflect::set(&props, &"className".into(), &"note".into())?;
+            js_sys::Reflect::set(&props, &"onClick".into(), &js_sys::Function::new_no_args(""))?;
+            let children: js_sys::Array = [
+                JsValue::from_str("1 < 2"),
+                create_element("br", JsValue::NULL, js_sys::Array::new())?,
+                js_sys::Function::new_no_args("return 3;").into(),
+                create_text_node("<b>")?,
+            ]
+            .into_iter()
+            .collect();
+            create_element("p", props.into(), children)
+        }) as Box<dyn Fn() -> Result<JsValue, JsValue>>);
+        let page: js_sys::Function = page.into_js_value().unchecked_into();
+
+        let html = render_to_string(&page, JsValue::UNDEFINED).unwrap();
+        assert!(html.contains(r#"<p class="note">1 &lt; 2<br>3&lt;b&gt;</p>"#), "{}", html);
+
+        let error = hydrate_root("root").unwrap_err().as_string().unwrap();
+        assert_eq!(error, "hydrateRoot needs a browser DOM, which isn't available during server rendering");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_unique_ids_restart_per_render() {
+        let ids = Rc::new(RefCell::new(Vec::new()));
+        let seen = ids.clone();
+        let children = Closure::wrap(Box::new(move || {
+            seen.borrow_mut().push(create_unique_id());
+            JsValue::from_str("")
+        }) as Box<dyn Fn() -> JsValue>);
+        let children: js_sys::Function = children.into_js_value().unchecked_into();
+        let seen = ids.clone();
+        let page = Closure::wrap(Box::new(move || {
+            seen.borrow_mut().push(create_unique_id());
+            suspense(JsValue::NULL, &children)?;
+            seen.borrow_mut().push(create_unique_id());
+            Ok(JsValue::from_str(""))
+        }) as Box<dyn Fn() -> Result<JsValue, JsValue>>);
+        let page: js_sys::Function = page.into_js_value().unchecked_into();
+
+        render_to_string(&page, JsValue::UNDEFINED).unwrap();
+        assert_eq!(*ids.borrow(), ["v-0", "v-1-0", "v-2"]);
+        render_to_string(&page, JsValue::UNDEFINED).unwrap();
+        assert_eq!(ids.borrow()[3..], ["v-0", "v-1-0", "v-2"]);
+        assert!(create_unique_id().starts_with("vg-"));
+    }
+}
@@ -0,0 +1,574 @@
+//! Structured state serialization for SSR hydration
+//!
+//! Plain `JSON.stringify` loses `Date`, `Map`, `Set`, `undefined`, `NaN` and
+//! fails on circular references. State is instead encoded as a flat list of
+//! nodes that reference each other by index, so shared and circular
+//! references survive the round trip.
+//!
+//! ## Wire format
+//! The payload is JSON. It is either a bare negative integer (the root value
+//! is one of the specials below) or an array of nodes whose first entry is
+//! the root:
+//!
+//! ```text
+//! node := null | true | false | number | string
+//!       | [ref, ...]                      array
+//!       | {"key": ref, ...}               plain object
+//!       | ["Date", ms]                    ms since the epoch, null if invalid
+//!       | ["Map", keyRef, valueRef, ...]
+//!       | ["Set", ref, ...]
+//!       | ["RegExp", source, flags]
+//!       | ["BigInt", "digits"]
+//!
+//! ref  := index into the node list
+//!       | -1 undefined | -2 array hole | -3 NaN
+//!       | -4 Infinity  | -5 -Infinity  | -6 -0
+//! ```
+//!
+//! Plain arrays only ever contain integers, so a leading string always marks
+//! a tagged node. `<`, U+2028 and U+2029 are escaped so the payload can be
+//! inlined in a `<script>` tag.
+
+use std::fmt::Write;
+
+/// Reference to a value: a node, or a value JSON can't hold as a node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ref {
+    Node(usize),
+    Undefined,
+    /// Missing element of a sparse array
+    Hole,
+    NaN,
+    Infinity,
+    NegInfinity,
+    NegZero,
+}
+
+impl Ref {
+    /// Reference a number, using the special refs where JSON can't
+    pub fn number(value: f64, nodes: &mut Vec<Node>) -> Ref {
+        if value.is_nan() {
+            Ref::NaN
+        } else if value == f64::INFINITY {
+            Ref::Infinity
+        } else if value == f64::NEG_INFINITY {
+            Ref::NegInfinity
+        } else if value == 0.0 && value.is_sign_negative() {
+            Ref::NegZero
+        } else {
+            nodes.push(Node::Number(value));
+            Ref::Node(nodes.len() - 1)
+        }
+    }
+
+    fn code(self) -> i64 {
+        match self {
+            Ref::Node(index) => index as i64,
+            Ref::Undefined => -1,
+            Ref::Hole => -2,
+            Ref::NaN => -3,
+            Ref::Infinity => -4,
+            Ref::NegInfinity => -5,
+            Ref::NegZero => -6,
+        }
+    }
+
+    fn from_code(code: f64, node_count: usize) -> Result<Ref, String> {
+        match code {
+            -1.0 => Ok(Ref::Undefined),
+            -2.0 => Ok(Ref::Hole),
+            -3.0 => Ok(Ref::NaN),
+            -4.0 => Ok(Ref::Infinity),
+            -5.0 => Ok(Ref::NegInfinity),
+            -6.0 => Ok(Ref::NegZero),
+            index if index >= 0.0 && index.fract() == 0.0 && (index as usize) < node_count => {
+                Ok(Ref::Node(index as usize))
+            }
+            _ => Err(format!("Invalid reference {}", code)),
+        }
+    }
+}
+
+/// A value in the serialized graph
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Null,
+    Bool(bool),
+    /// A finite number other than -0
+    Number(f64),
+    String(String),
+    BigInt(String),
+    Array(Vec<Ref>),
+    Object(Vec<(String, Ref)>),
+    /// Milliseconds since the epoch; `None` for an invalid date
+    Date(Option<f64>),
+    Map(Vec<(Ref, Ref)>),
+    Set(Vec<Ref>),
+    RegExp { source: String, flags: String },
+}
+
+/// A value graph; `root` is `Ref::Node(0)` unless the value is a special
+#[derive(Debug, Clone, PartialEq)]
+pub struct Graph {
+    pub root: Ref,
+    pub nodes: Vec<Node>,
+}
+
+/// Encode a graph in the wire format
+pub fn encode(graph: &Graph) -> String {
+    if !matches!(graph.root, Ref::Node(_)) {
+        return graph.root.code().to_string();
+    }
+
+    let mut out = String::from("[");
+    for (i, node) in graph.nodes.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        encode_node(node, &mut out);
+    }
+    out.push(']');
+    out
+}
+
+fn encode_node(node: &Node, out: &mut String) {
+    let refs = |refs: &mut dyn Iterator<Item = Ref>, out: &mut String| {
+        for r in refs {
+            let _ = write!(out, ",{}", r.code());
+        }
+    };
+
+    match node {
+        Node::Null => out.push_str("null"),
+        Node::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+        Node::Number(value) => {
+            let _ = write!(out, "{}", value);
+        }
+        Node::String(value) => encode_string(value, out),
+        Node::BigInt(digits) => {
+            out.push_str("[\"BigInt\",");
+            encode_string(digits, out);
+            out.push(']');
+        }
+        Node::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                let _ = write!(out, "{}", item.code());
+            }
+            out.push(']');
+        }
+        Node::Object(entries) => {
+            out.push('{');
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                encode_string(key, out);
+                let _ = write!(out, ":{}", value.code());
+            }
+            out.push('}');
+        }
+        Node::Date(time) => match time {
+            Some(time) => {
+                let _ = write!(out, "[\"Date\",{}]", time);
+            }
+            None => out.push_str("[\"Date\",null]"),
+        },
+        Node::Map(entries) => {
+            out.push_str("[\"Map\"");
+            refs(&mut entries.iter().flat_map(|(k, v)| [*k, *v]), out);
+            out.push(']');
+        }
+        Node::Set(items) => {
+            out.push_str("[\"Set\"");
+            refs(&mut items.iter().copied(), out);
+            out.push(']');
+        }
+        Node::RegExp { source, flags } => {
+            out.push_str("[\"RegExp\",");
+            encode_string(source, out);
+            out.push(',');
+            encode_string(flags, out);
+            out.push(']');
+        }
+    }
+}
+
+/// JSON string literal that is also safe inside `<script>`
+fn encode_string(value: &str, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '<' | '\u{2028}' | '\u{2029}' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Decode a payload produced by [`encode`]
+pub fn decode(text: &str) -> Result<Graph, String> {
+    let json = JsonParser::new(text).parse_document()?;
+
+    let items = match json {
+        Json::Number(code) => {
+            return Ok(Graph {
+                root: Ref::from_code(code, 0)?,
+                nodes: Vec::new(),
+            })
+        }
+        Json::Array(items) if !items.is_empty() => items,
+        _ => return Err("Expected a node list or special value".to_string()),
+    };
+
+    let count = items.len();
+    let to_ref = |json: &Json| match json {
+        Json::Number(code) => Ref::from_code(*code, count),
+        _ => Err("Expected a reference".to_string()),
+    };
+
+    let nodes = items
+        .iter()
+        .map(|item| decode_node(item, &to_ref))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Graph {
+        root: Ref::Node(0),
+        nodes,
+    })
+}
+
+fn decode_node(json: &Json, to_ref: &dyn Fn(&Json) -> Result<Ref, String>) -> Result<Node, String> {
+    Ok(match json {
+        Json::Null => Node::Null,
+        Json::Bool(value) => Node::Bool(*value),
+        Json::Number(value) => Node::Number(*value),
+        Json::String(value) => Node::String(value.clone()),
+        Json::Object(entries) => Node::Object(
+            entries
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), to_ref(value)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        Json::Array(items) => match items.split_first() {
+            Some((Json::String(tag), rest)) => decode_tagged(tag, rest, to_ref)?,
+            _ => Node::Array(items.iter().map(to_ref).collect::<Result<_, _>>()?),
+        },
+    })
+}
+
+fn decode_tagged(tag: &str, args: &[Json], to_ref: &dyn Fn(&Json) -> Result<Ref, String>) -> Result<Node, String> {
+    let string = |json: Option<&Json>| match json {
+        Some(Json::String(value)) => Ok(value.clone()),
+        _ => Err(format!("Expected a string in {} node", tag)),
+    };
+
+    Ok(match tag {
+        "Date" => match args.first() {
+            Some(Json::Number(time)) => Node::Date(Some(*time)),
+            Some(Json::Null) => Node::Date(None),
+            _ => return Err("Expected a timestamp in Date node".to_string()),
+        },
+        "Map" => {
+            if !args.len().is_multiple_of(2) {
+                return Err("Map node has an odd number of refs".to_string());
+            }
+            Node::Map(
+                args.chunks(2)
+                    .map(|pair| Ok((to_ref(&pair[0])?, to_ref(&pair[1])?)))
+                    .collect::<Result<_, String>>()?,
+            )
+        }
+        "Set" => Node::Set(args.iter().map(to_ref).collect::<Result<_, _>>()?),
+        "RegExp" => Node::RegExp {
+            source: string(args.first())?,
+            flags: string(args.get(1))?,
+        },
+        "BigInt" => Node::BigInt(string(args.first())?),
+        _ => return Err(format!("Unknown node type {:?}", tag)),
+    })
+}
+
+/// Parsed JSON document
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// Minimal JSON parser for decoding payloads
+struct JsonParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, pos: 0 }
+    }
+
+    fn parse_document(mut self) -> Result<Json, String> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        if self.pos != self.text.len() {
+            return Err(format!("Unexpected trailing data at {}", self.pos));
+        }
+        Ok(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' at {}", byte as char, self.pos))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'n') => self.parse_literal("null", Json::Null),
+            Some(b't') => self.parse_literal("true", Json::Bool(true)),
+            Some(b'f') => self.parse_literal("false", Json::Bool(false)),
+            Some(b'"') => self.parse_string().map(Json::String),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.parse_list(b']', |parser| {
+                    items.push(parser.parse_value()?);
+                    Ok(())
+                })?;
+                Ok(Json::Array(items))
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                self.parse_list(b'}', |parser| {
+                    parser.skip_whitespace();
+                    let key = parser.parse_string()?;
+                    parser.expect(b':')?;
+                    entries.push((key, parser.parse_value()?));
+                    Ok(())
+                })?;
+                Ok(Json::Object(entries))
+            }
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            _ => Err(format!("Unexpected character at {}", self.pos)),
+        }
+    }
+
+    /// Comma-separated items up to `close`; the opening bracket is consumed
+    fn parse_list(&mut self, close: u8, mut item: impl FnMut(&mut Self) -> Result<(), String>) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            item(self)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(format!("Expected ',' or '{}' at {}", close as char, self.pos)),
+            }
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Json) -> Result<Json, String> {
+        if self.text[self.pos..].starts_with(literal) {
+            self.pos += literal.len();
+            Ok(value)
+        } else {
+            Err(format!("Unexpected character at {}", self.pos))
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Json, String> {
+        let rest = &self.text[self.pos..];
+        let len = rest
+            .find(|c: char| !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+            .unwrap_or(rest.len());
+        let value = rest[..len]
+            .parse::<f64>()
+            .map_err(|_| format!("Invalid number at {}", self.pos))?;
+        self.pos += len;
+        Ok(Json::Number(value))
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        if self.peek() != Some(b'"') {
+            return Err(format!("Expected a string at {}", self.pos));
+        }
+        self.pos += 1;
+
+        let mut value = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += offset + 1;
+                    return Ok(value);
+                }
+                '\\' => {
+                    let escape = chars.next().map(|(_, c)| c);
+                    match escape {
+                        Some('"') => value.push('"'),
+                        Some('\\') => value.push('\\'),
+                        Some('/') => value.push('/'),
+                        Some('b') => value.push('\u{8}'),
+                        Some('f') => value.push('\u{c}'),
+                        Some('n') => value.push('\n'),
+                        Some('r') => value.push('\r'),
+                        Some('t') => value.push('\t'),
+                        Some('u') => {
+                            let high = hex_unit(&mut chars)?;
+                            let code = if (0xd800..0xdc00).contains(&high) {
+                                // Surrogate pair: expect a `\uXXXX` low half next
+                                let (Some((_, '\\')), Some((_, 'u'))) = (chars.next(), chars.next()) else {
+                                    return Err("Unpaired surrogate".to_string());
+                                };
+                                let low = hex_unit(&mut chars)?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err("Unpaired surrogate".to_string());
+                                }
+                                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                            } else {
+                                high
+                            };
+                            value.push(char::from_u32(code).ok_or("Invalid \\u escape")?);
+                        }
+                        _ => return Err("Invalid escape".to_string()),
+                    }
+                }
+                c => value.push(c),
+            }
+        }
+        Err("Unterminated string".to_string())
+    }
+}
+
+/// The four hex digits of a `\uXXXX` escape
+fn hex_unit(chars: &mut std::str::CharIndices) -> Result<u32, String> {
+    let hex: String = chars.take(4).map(|(_, c)| c).collect();
+    if hex.len() != 4 {
+        return Err("Invalid \\u escape".to_string());
+    }
+    u32::from_str_radix(&hex, 16).map_err(|_| "Invalid \\u escape".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(graph: &Graph) -> Graph {
+        decode(&encode(graph)).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_structured_values() {
+        // { when: Date, tags: Set{"a"}, scores: Map{"x" => NaN}, missing: undefined, big: 10n, re: /a+/g }
+        let graph = Graph {
+            root: Ref::Node(0),
+            nodes: vec![
+                Node::Object(vec![
+                    ("when".into(), Ref::Node(1)),
+                    ("tags".into(), Ref::Node(2)),
+                    ("scores".into(), Ref::Node(4)),
+                    ("missing".into(), Ref::Undefined),
+                    ("big".into(), Ref::Node(6)),
+                    ("re".into(), Ref::Node(7)),
+                    ("zero".into(), Ref::NegZero),
+                ]),
+                Node::Date(Some(1_700_000_000_000.0)),
+                Node::Set(vec![Ref::Node(3)]),
+                Node::String("a".into()),
+                Node::Map(vec![(Ref::Node(5), Ref::NaN)]),
+                Node::String("x".into()),
+                Node::BigInt("10".into()),
+                Node::RegExp { source: "a+".into(), flags: "g".into() },
+            ],
+        };
+
+        assert_eq!(round_trip(&graph), graph);
+    }
+
+    #[test]
+    fn test_round_trip_circular_reference() {
+        // const a = { items: [] }; a.items.push(a, <hole>, Infinity)
+        let graph = Graph {
+            root: Ref::Node(0),
+            nodes: vec![
+                Node::Object(vec![("items".into(), Ref::Node(1))]),
+                Node::Array(vec![Ref::Node(0), Ref::Hole, Ref::Infinity]),
+            ],
+        };
+
+        assert_eq!(encode(&graph), r#"[{"items":1},[0,-2,-4]]"#);
+        assert_eq!(round_trip(&graph), graph);
+    }
+
+    #[test]
+    fn test_special_root_and_primitives() {
+        let undefined = Graph { root: Ref::Undefined, nodes: vec![] };
+        assert_eq!(encode(&undefined), "-1");
+        assert_eq!(round_trip(&undefined), undefined);
+
+        let mut nodes = Vec::new();
+        let root = Ref::number(1.5, &mut nodes);
+        let number = Graph { root, nodes };
+        assert_eq!(encode(&number), "[1.5]");
+        assert_eq!(round_trip(&number), number);
+    }
+
+    #[test]
+    fn test_strings_are_script_safe() {
+        let graph = Graph {
+            root: Ref::Node(0),
+            nodes: vec![Node::String("</script>\"\u{2028}😀".into())],
+        };
+
+        let encoded = encode(&graph);
+        assert!(!encoded.contains('<'));
+        assert_eq!(round_trip(&graph), graph);
+        // Escaped astral characters decode as surrogate pairs
+        assert_eq!(decode(r#"["\ud83d\ude00"]"#).unwrap().nodes, vec![Node::String("😀".into())]);
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_references() {
+        assert!(decode("[[5]]").is_err());
+        assert!(decode(r#"[["Nope"]]"#).is_err());
+        assert!(decode("[]").is_err());
+    }
+}
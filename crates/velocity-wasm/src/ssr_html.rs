@@ -0,0 +1,75 @@
+//! Markup for elements built during server rendering
+//!
+//! Without a window there are no DOM nodes to create, so `createElement`
+//! and `createTextNode` write HTML instead, and `renderToString` joins it
+//! into the page. These helpers only deal with text; telling rendered
+//! markup apart from plain strings is up to the caller.
+
+/// Elements that have no content and no closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// `text` escaped to appear as element content
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `value` escaped to appear inside a double-quoted attribute
+pub fn escape_attribute(value: &str) -> String {
+    escape_text(value).replace('"', "&quot;")
+}
+
+/// Whether `name` can be written as an attribute name as is. Anything else
+/// is dropped rather than escaped, since no escaping makes it one attribute.
+fn is_attribute_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| !c.is_whitespace() && !c.is_control() && !matches!(c, '"' | '\'' | '>' | '/' | '=' | '<'))
+}
+
+/// The markup of a `tag` element with `attributes` and already rendered
+/// `children`. Void elements (`<br>`, `<img>`) get no closing tag, and
+/// their children are dropped as the browser would.
+pub fn element(tag: &str, attributes: &[(String, String)], children: &str) -> String {
+    let mut html = format!("<{}", tag);
+    for (name, value) in attributes.iter().filter(|(name, _)| is_attribute_name(name)) {
+        html.push_str(&format!(" {}=\"{}\"", name, escape_attribute(value)));
+    }
+    html.push('>');
+
+    if !VOID_ELEMENTS.contains(&tag.to_ascii_lowercase().as_str()) {
+        html.push_str(children);
+        html.push_str(&format!("</{}>", tag));
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_element_escapes_and_closes() {
+        let attributes = [
+            ("class".to_string(), "a \"b\" & c".to_string()),
+            ("onclick=\"x\"".to_string(), "ignored".to_string()),
+        ];
+        assert_eq!(
+            element("p", &attributes, &escape_text("1 < 2 & <b>")),
+            "<p class=\"a &quot;b&quot; &amp; c\">1 &lt; 2 &amp; &lt;b&gt;</p>"
+        );
+        assert_eq!(element("IMG", &[("alt".to_string(), "x".to_string())], "ignored"), "<IMG alt=\"x\">");
+        assert_eq!(element("div", &[], ""), "<div></div>");
+    }
+}
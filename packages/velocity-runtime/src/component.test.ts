@@ -0,0 +1,66 @@
+import { describe, expect, it } from 'vitest';
+import { mergeProps } from './component';
+import { createEffect, createSignal, untrack } from './reactivity';
+
+describe('mergeProps', () => {
+  it('lets later sources override earlier ones, except with undefined', () => {
+    const merged = mergeProps({ size: 'md', variant: 'plain' }, null, { variant: 'primary', size: undefined });
+
+    expect(merged.size).toBe('md');
+    expect(merged.variant).toBe('primary');
+    expect(Object.keys(merged)).toEqual(['size', 'variant']);
+  });
+
+  it('keeps getter props reactive', () => {
+    const [count, setCount] = createSignal(1);
+    let reads = 0;
+    const props = {
+      get count() {
+        reads++;
+        return count();
+      },
+    };
+
+    const merged = mergeProps({ label: 'Clicks' }, props);
+    expect(reads).toBe(0);
+
+    const seen: unknown[] = [];
+    createEffect(() => {
+      seen.push(merged.count);
+    });
+    setCount(2);
+
+    expect(seen).toEqual([1, 2]);
+  });
+});
+
+describe('prop checks', () => {
+  // What dev builds insert at the top of `function Counter(props: { count: number })`
+  function Counter(props: { count: number }) {
+    untrack(() => {
+      if (props.count === undefined) console.warn('<Counter>: missing required prop `count`');
+      else if (!(typeof props.count === 'number')) console.warn('<Counter>: prop `count` should be number, got', props.count);
+    });
+    return null;
+  }
+
+  it('read getter props without subscribing the rendering effect', () => {
+    const [count, setCount] = createSignal(1);
+    let reads = 0;
+    let renders = 0;
+
+    createEffect(() => {
+      renders++;
+      Counter({
+        get count() {
+          reads++;
+          return count();
+        },
+      });
+    });
+    setCount(2);
+
+    expect(reads).toBe(2);
+    expect(renders).toBe(1);
+  });
+});
@@ -1,6 +1,6 @@
 // Component system with lifecycle hooks
 
-import { createEffect } from './reactivity';
+import { createEffect, onDispose } from './reactivity';
 
 export type ComponentFunction = (props?: any) => any;
 type CleanupFunction = () => void;
@@ -38,14 +38,16 @@ function cleanupContext(context: ComponentContext): void {
 }
 
 /**
- * Register a cleanup function to run when component unmounts
+ * Register a cleanup function to run when component unmounts: when the
+ * effect or root it was registered in (see `createRoot`, `render`) is
+ * disposed
  * @example
  * onCleanup(() => {
  *   console.log('Component unmounting');
  * });
  */
 export function onCleanup(fn: CleanupFunction): void {
-  if (currentContext) {
+  if (!onDispose(fn) && currentContext) {
     currentContext.cleanups.push(fn);
   }
 }
@@ -111,3 +113,42 @@ export function createContext<T>(defaultValue: T): {
     }
   };
 }
+
+/**
+ * Merge props objects into one; later sources override earlier ones.
+ *
+ * Every key becomes a getter that looks the value up on access, so getter
+ * props (`get count() { return count(); }`) aren't read while merging and
+ * stay reactive. `undefined` values fall through to earlier sources, which
+ * makes `mergeProps({ size: 'md' }, props)` work for defaults. Keys added to
+ * a source after merging aren't picked up.
+ *
+ * The compiler builds the props of components with spread attributes
+ * (`<Button {...base} variant="primary" />`) with this, in source order.
+ * @example
+ * const merged = mergeProps({ size: 'md' }, props);
+ */
+export function mergeProps(...sources: unknown[]): Record<string, unknown> {
+  const objects = sources.filter(
+    (source): source is Record<string, unknown> => typeof source === 'object' && source !== null
+  );
+  const merged: Record<string, unknown> = {};
+
+  for (const source of objects) {
+    for (const key of Object.keys(source)) {
+      if (Object.prototype.hasOwnProperty.call(merged, key)) continue;
+      Object.defineProperty(merged, key, {
+        enumerable: true,
+        get() {
+          for (let i = objects.length - 1; i >= 0; i--) {
+            const value = objects[i][key];
+            if (value !== undefined) return value;
+          }
+          return undefined;
+        },
+      });
+    }
+  }
+
+  return merged;
+}
@@ -1,10 +1,16 @@
 // Efficient DOM operations without Virtual DOM
 
-import { createEffect } from './reactivity';
+import { createEffect, createRoot, untrack } from './reactivity';
 
 type Child = Node | string | number | boolean | null | undefined | (() => Child);
 type Children = Child | Child[];
 
+/**
+ * A `use:name={value}` directive. The compiler calls it once with the element
+ * and an accessor for the value (`() => true` when no value is given).
+ */
+export type Directive<T = any> = (element: HTMLElement, value: () => T) => void;
+
 /**
  * Create an element with props and children
  */
@@ -127,26 +133,121 @@ export function insert(parent: Element, child: Child, marker?: Node): void {
 }
 
 /**
- * Render a component to the DOM
+ * Flatten a rendered child into the DOM nodes it produces
  */
-export function render(code: () => any, container: Element): () => void {
-  let dispose: (() => void) | null = null;
+function toNodes(child: Children): Node[] {
+  if (Array.isArray(child)) {
+    return child.flatMap(toNodes);
+  }
+  if (child == null || child === false || child === true) {
+    return [];
+  }
+  return [normalizeChild(typeof child === 'function' ? untrack(child) : child)];
+}
 
-  dispose = createEffect(() => {
-    // Clear container
-    container.innerHTML = '';
+/**
+ * Render a reactive list, reusing the nodes of items whose key is unchanged.
+ *
+ * The compiler lowers `{items().map(item => <li key={item.id} />)}` to
+ * `forEach(() => items(), item => ..., item => item.id)`. Without a key
+ * function items are matched by identity.
+ */
+export function forEach<T>(
+  list: () => readonly T[] | null | undefined,
+  renderItem: (item: T, index: number) => Children,
+  key: (item: T, index: number) => unknown = item => item
+): DocumentFragment {
+  const fragment = document.createDocumentFragment();
+  const start = document.createTextNode('');
+  const end = document.createTextNode('');
+  fragment.append(start, end);
 
-    // Render component
-    const result = code();
-    if (result) {
-      insert(container, result);
-    }
+  let rendered = new Map<unknown, Node[]>();
+
+  createEffect(() => {
+    const items = list() ?? [];
+    const parent = end.parentNode;
+    if (!parent) return;
+
+    untrack(() => {
+      const next = new Map<unknown, Node[]>();
+
+      items.forEach((item, index) => {
+        const itemKey = key(item, index);
+        // Duplicate keys render fresh nodes rather than stealing existing ones
+        const nodes = (!next.has(itemKey) && rendered.get(itemKey)) || toNodes(renderItem(item, index));
+        next.set(next.has(itemKey) ? {} : itemKey, nodes);
+      });
+
+      // Remove nodes for items that are gone
+      for (const [itemKey, nodes] of rendered) {
+        if (next.get(itemKey) !== nodes) {
+          nodes.forEach(node => node.parentNode?.removeChild(node));
+        }
+      }
+
+      // Insert/move nodes into order, skipping those already in place
+      let cursor: Node = start;
+      for (const nodes of next.values()) {
+        for (const node of nodes) {
+          if (cursor.nextSibling !== node) {
+            parent.insertBefore(node, cursor.nextSibling);
+          }
+          cursor = node;
+        }
+      }
+
+      rendered = next;
+    });
   });
 
-  return () => {
-    if (dispose) dispose();
-    container.innerHTML = '';
-  };
+  return fragment;
+}
+
+/**
+ * Remove the nodes between `start` and `end`
+ */
+function removeBetween(start: Node, end: Node): void {
+  while (start.nextSibling && start.nextSibling !== end) {
+    start.parentNode!.removeChild(start.nextSibling);
+  }
+}
+
+/**
+ * Render a component to the DOM, replacing the container's content.
+ *
+ * Returns a function that unmounts it: the effects and signals created while
+ * rendering are disposed, `onCleanup` callbacks run, and the nodes it
+ * inserted are removed. Nodes others added to the container since are left
+ * in place, so it can share the container with another app.
+ * @example
+ * const dispose = render(() => <App />, document.getElementById('root')!);
+ * dispose();
+ */
+export function render(code: () => any, container: Element): () => void {
+  container.innerHTML = '';
+  const start = document.createTextNode('');
+  const end = document.createTextNode('');
+  container.append(start, end);
+
+  return createRoot(dispose => {
+    createEffect(() => {
+      removeBetween(start, end);
+
+      // Render component
+      const result = code();
+      if (result) {
+        insert(container, result, end);
+      }
+    });
+
+    return () => {
+      dispose();
+      removeBetween(start, end);
+      start.parentNode?.removeChild(start);
+      end.parentNode?.removeChild(end);
+    };
+  });
 }
 
 /**
@@ -5,9 +5,11 @@
 export {
   createSignal,
   createEffect,
+  createRenderEffect,
   createMemo,
   batch,
   untrack,
+  createRoot,
 } from './reactivity';
 
 export {
@@ -16,12 +18,16 @@ export {
   createComponent,
   getContext,
   createContext,
+  mergeProps,
 } from './component';
 
+export { mark, measure } from './profiling';
+
 export {
   insert,
   render,
   createPortal,
+  forEach,
 } from './dom';
 
 // JSX runtime for automatic JSX transform
@@ -33,3 +39,4 @@ export const Fragment = (props: { children?: any }) => props.children;
 
 // Types
 export type { ComponentFunction } from './component';
+export type { Directive } from './dom';
@@ -0,0 +1,27 @@
+// Performance marks for `velocity build --profile`, which wraps every
+// component's body in them so renders show up by name in DevTools
+
+/**
+ * Add a performance mark. Does nothing where `performance` is missing.
+ */
+export function mark(name: string): void {
+  globalThis.performance?.mark(name);
+}
+
+/**
+ * Measure between two marks, returning the duration in milliseconds (0 if
+ * it can't be measured)
+ * @example
+ * mark('load:start');
+ * await load();
+ * mark('load:end');
+ * measure('load', 'load:start', 'load:end');
+ */
+export function measure(name: string, startMark: string, endMark: string): number {
+  try {
+    return globalThis.performance?.measure(name, startMark, endMark)?.duration ?? 0;
+  } catch {
+    // A mark that doesn't exist, e.g. cleared by the page
+    return 0;
+  }
+}
@@ -15,12 +15,46 @@ const context: Context = {
 
 const runningComputations = new Set<Effect>();
 
+/**
+ * Disposers of what was created under a root or an effect, run when it's
+ * disposed (and, for an effect, before it runs again)
+ */
+type Owner = Listener[];
+
+let currentOwner: Owner | null = null;
+
+function runOwned<T>(owner: Owner, fn: () => T): T {
+  const prevOwner = currentOwner;
+  currentOwner = owner;
+  try {
+    return fn();
+  } finally {
+    currentOwner = prevOwner;
+  }
+}
+
+function disposeOwned(owner: Owner): void {
+  // Last created first, so cleanups can still use what came before them
+  owner.splice(0).reverse().forEach(dispose => dispose());
+}
+
+/**
+ * Register `fn` to run when the current root or effect is disposed. Returns
+ * false outside of both.
+ */
+export function onDispose(fn: Listener): boolean {
+  if (!currentOwner) return false;
+  currentOwner.push(fn);
+  return true;
+}
+
 class Signal<T> {
   private value: T;
   private listeners = new Set<Listener>();
 
   constructor(initialValue: T) {
     this.value = initialValue;
+    onDispose(() => this.dispose());
   }
 
   read(): T {
@@ -51,19 +85,22 @@ class Signal<T> {
 
 class Effect {
   private sources = new Set<Signal<any>>();
+  private owned: Owner = [];
   private fn: Listener;
   private disposed = false;
 
   constructor(fn: Listener) {
     this.fn = fn;
+    onDispose(() => this.dispose());
     this.execute();
   }
 
   execute(): void {
     if (this.disposed) return;
 
-    // Cleanup old dependencies
+    // Cleanup old dependencies, and what the last run created
     this.cleanup();
+    disposeOwned(this.owned);
 
     const prevListener = context.listener;
     const prevSources = context.sources;
@@ -72,7 +109,7 @@ class Effect {
     context.sources = this.sources;
 
     try {
-      this.fn();
+      runOwned(this.owned, this.fn);
     } finally {
       context.listener = prevListener;
       context.sources = prevSources;
@@ -88,9 +125,76 @@ class Effect {
   }
 
   dispose(): void {
+    if (this.disposed) return;
     this.cleanup();
     this.disposed = true;
+    disposeOwned(this.owned);
+  }
+}
+
+// Hot update state: the compiler keys signals in dev builds, the HMR client
+// saves their values under `HMR_STATE_KEY` before reloading for an update,
+// and signals created with the same key afterwards start from them
+
+/** sessionStorage item the HMR client saves signal values in */
+const HMR_STATE_KEY = 'velocity-hmr-state';
+
+/** Saved value of each instance of a key, in creation order; null if it wasn't plain data */
+type SavedSignals = Record<string, ({ value: unknown } | null)[]>;
+
+interface HmrSignals {
+  /** Reads of the live signals with each key, which the HMR client saves */
+  live: Map<string, Set<() => unknown>>;
+  saved: SavedSignals;
+  /** Signals created with each key so far */
+  created: Map<string, number>;
+}
+
+let hmrSignals: HmrSignals | null = null;
+
+function hmrState(): HmrSignals {
+  if (!hmrSignals) {
+    let saved: SavedSignals = {};
+    try {
+      // Consumed once, so a manual reload starts fresh
+      saved = JSON.parse(sessionStorage.getItem(HMR_STATE_KEY) ?? '{}');
+      sessionStorage.removeItem(HMR_STATE_KEY);
+    } catch {
+      // No sessionStorage (workers, private modes) or a corrupt entry
+    }
+    hmrSignals = { live: new Map(), saved, created: new Map() };
+    (globalThis as any).__velocity_hmr_signals__ = hmrSignals.live;
   }
+  return hmrSignals;
+}
+
+/** null, undefined, or the JSON type of a value */
+function kindOf(value: unknown): string {
+  if (value == null) return 'empty';
+  return Array.isArray(value) ? 'array' : typeof value;
+}
+
+/**
+ * The value the key's next signal starts from: the one saved before the hot
+ * update, if it's of the same kind as `initialValue`
+ */
+function restoreSignal<T>(key: string, initialValue: T): T {
+  const state = hmrState();
+  const index = state.created.get(key) ?? 0;
+  state.created.set(key, index + 1);
+
+  const saved = state.saved[key]?.[index];
+  if (!saved) return initialValue;
+  const initialKind = kindOf(initialValue);
+  return initialKind === 'empty' || initialKind === kindOf(saved.value) ? saved.value as T : initialValue;
+}
+
+function trackSignal(key: string, read: () => unknown): void {
+  const live = hmrState().live;
+  const reads = live.get(key) ?? new Set();
+  live.set(key, reads);
+  reads.add(read);
+  onDispose(() => reads.delete(read));
 }
 
 /**
@@ -100,15 +204,41 @@ class Effect {
  * console.log(count()); // 0
  * setCount(1);
  * console.log(count()); // 1
+ *
+ * Dev builds pass an `hmrKey` naming the call, so its value survives hot
+ * updates; see the compiler's `hmr` module.
  */
-export function createSignal<T>(initialValue: T): [() => T, (value: T | ((prev: T) => T)) => void] {
-  const signal = new Signal(initialValue);
+export function createSignal<T>(
+  initialValue: T,
+  hmrKey?: string
+): [() => T, (value: T | ((prev: T) => T)) => void] {
+  const signal = new Signal(hmrKey === undefined ? initialValue : restoreSignal(hmrKey, initialValue));
+  if (hmrKey !== undefined) {
+    trackSignal(hmrKey, () => signal.read());
+  }
   return [
     () => signal.read(),
     (value) => signal.write(value)
   ];
 }
 
+/**
+ * Create a root scope - effects, signals and cleanups created while `fn`
+ * runs are disposed together by the function it's given. `fn` runs
+ * untracked, so a root created inside an effect isn't rerun by it.
+ * @example
+ * const count = createRoot(dispose => {
+ *   const [count, setCount] = createSignal(0);
+ *   createEffect(() => console.log('Count is:', count()));
+ *   setTimeout(dispose, 1000);
+ *   return count;
+ * });
+ */
+export function createRoot<T>(fn: (dispose: () => void) => T): T {
+  const owner: Owner = [];
+  return runOwned(owner, () => untrack(() => fn(() => disposeOwned(owner))));
+}
+
 /**
  * Create an effect - automatically runs when dependencies change
  * @example
@@ -122,6 +252,14 @@ export function createEffect(fn: Listener): () => void {
   return () => effect.dispose();
 }
 
+/**
+ * Create an effect that re-runs synchronously, before paint. Effects here
+ * always do, so it's `createEffect`; compiled DOM bindings call it by this name.
+ */
+export function createRenderEffect(fn: Listener): () => void {
+  return createEffect(fn);
+}
+
 /**
  * Create a memo - a cached computed value
  * @example
@@ -0,0 +1,25 @@
+// Worker-safe subset of the Velocity runtime: reactivity and components,
+// without the DOM helpers. Modules compiled for a Web Worker import from here.
+
+export {
+  createSignal,
+  createEffect,
+  createRenderEffect,
+  createMemo,
+  batch,
+  untrack,
+  createRoot,
+} from './reactivity';
+
+export {
+  onCleanup,
+  onMount,
+  createComponent,
+  getContext,
+  createContext,
+  mergeProps,
+} from './component';
+
+export { mark, measure } from './profiling';
+
+export type { ComponentFunction } from './component';